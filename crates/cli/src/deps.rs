@@ -0,0 +1,476 @@
+use std::fmt::Write;
+use std::hash::Hash;
+use std::io;
+use std::path::PathBuf;
+
+use bumpalo::Bump;
+use clap::ArgMatches;
+use roc_build::program::handle_loading_problem;
+use roc_collections::{MutMap, MutSet};
+use roc_error_macros::user_error;
+use roc_load::{
+    ExecutionMode, FunctionKind, LoadConfig, ModuleGraph, ModuleNode, OutputConfig, PackageRef,
+    PhaseTimes, Threading,
+};
+use roc_module::symbol::ModuleId;
+use roc_packaging::cache::{self, RocCacheDir};
+use roc_reporting::report::{RenderTarget, DEFAULT_PALETTE};
+use roc_target::Target;
+
+use crate::{
+    FLAG_BUILTINS, FLAG_GRAPH_FORMAT, FLAG_LINES, FLAG_MAX_THREADS, FLAG_PACKAGES, FLAG_TIME,
+    ROC_FILE,
+};
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum DepsFormat {
+    Text,
+    Dot,
+    Json,
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct DepsOptions {
+    pub format: DepsFormat,
+    /// Print the graph between packages rather than between modules
+    pub packages: bool,
+    pub include_builtins: bool,
+    pub show_timings: bool,
+    pub show_line_counts: bool,
+}
+
+pub fn deps(matches: &ArgMatches) -> io::Result<i32> {
+    let arena = Bump::new();
+    let roc_file_path = matches.get_one::<PathBuf>(ROC_FILE).unwrap();
+
+    let threading = match matches.get_one::<usize>(FLAG_MAX_THREADS) {
+        None => Threading::AllAvailable,
+        Some(0) => user_error!("cannot build with at most 0 threads"),
+        Some(1) => Threading::Single,
+        Some(n) => Threading::AtMost(*n),
+    };
+
+    let format = match matches
+        .get_one::<String>(FLAG_GRAPH_FORMAT)
+        .map(String::as_str)
+    {
+        Some("dot") => DepsFormat::Dot,
+        Some("json") => DepsFormat::Json,
+        _ => DepsFormat::Text,
+    };
+
+    let options = DepsOptions {
+        format,
+        packages: matches.get_flag(FLAG_PACKAGES),
+        include_builtins: matches.get_flag(FLAG_BUILTINS),
+        show_timings: matches.get_flag(FLAG_TIME),
+        show_line_counts: matches.get_flag(FLAG_LINES),
+    };
+
+    let load_config = LoadConfig {
+        // only used for generating errors, like in `roc check`
+        target: Target::LinuxX64,
+        function_kind: FunctionKind::LambdaSet,
+        render: RenderTarget::ColorTerminal,
+        palette: DEFAULT_PALETTE,
+        threading,
+        exec_mode: ExecutionMode::Check,
+        output: OutputConfig {
+            build_module_graph: true,
+            ..OutputConfig::default()
+        },
+    };
+
+    // Specialization timings are only available if we actually specialize.
+    let load_result = roc_load::load_module_graph(
+        &arena,
+        roc_file_path.to_owned(),
+        RocCacheDir::Persistent(cache::roc_cache_dir().as_path()),
+        load_config,
+        options.show_timings,
+    );
+
+    match load_result {
+        Ok(graph) => {
+            print!("{}", render_deps(&graph, options));
+
+            Ok(0)
+        }
+        Err(problem) => handle_loading_problem(problem),
+    }
+}
+
+pub fn render_deps(graph: &ModuleGraph, options: DepsOptions) -> String {
+    let mut buf = String::new();
+
+    match (options.format, options.packages) {
+        (DepsFormat::Text, false) => module_tree(&mut buf, graph, options),
+        (DepsFormat::Text, true) => package_tree(&mut buf, graph, options),
+        (DepsFormat::Dot, false) => module_dot(&mut buf, graph, options),
+        (DepsFormat::Dot, true) => package_dot(&mut buf, graph, options),
+        (DepsFormat::Json, _) => {
+            let json = graph_json(graph, options);
+
+            buf.push_str(&serde_json::to_string_pretty(&json).unwrap());
+            buf.push('\n');
+        }
+    }
+
+    buf
+}
+
+/// Maps every node that is part of a cycle to the index of its cycle, so we can tell
+/// whether an edge closes a cycle.
+struct CycleMembership<K>(MutMap<K, usize>);
+
+impl<K: Copy + Eq + Hash> CycleMembership<K> {
+    fn new(cycles: &[Vec<K>]) -> Self {
+        let mut membership = MutMap::default();
+
+        for (index, cycle) in cycles.iter().enumerate() {
+            for node in cycle {
+                membership.insert(*node, index);
+            }
+        }
+
+        Self(membership)
+    }
+
+    fn is_cyclic_edge(&self, from: K, to: K) -> bool {
+        match (self.0.get(&from), self.0.get(&to)) {
+            (Some(a), Some(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+fn module_label(node: &ModuleNode, options: DepsOptions) -> String {
+    let mut label = node.qualified_name();
+    let mut annotations = Vec::new();
+
+    if options.show_line_counts {
+        if let Some(line_count) = node.line_count {
+            annotations.push(format!("{line_count} lines"));
+        }
+    }
+
+    if options.show_timings {
+        if let Some(phase_times) = &node.phase_times {
+            annotations.push(format_phase_times(phase_times));
+        }
+    }
+
+    if !annotations.is_empty() {
+        let _ = write!(label, " [{}]", annotations.join(", "));
+    }
+
+    label
+}
+
+fn format_phase_times(phase_times: &PhaseTimes) -> String {
+    let ms = |duration: std::time::Duration| duration.as_secs_f64() * 1000.0;
+
+    format!(
+        "parse {:.3} ms, canonicalize {:.3} ms, solve {:.3} ms, specialize {:.3} ms, total {:.3} ms",
+        ms(phase_times.parse),
+        ms(phase_times.canonicalize),
+        ms(phase_times.solve),
+        ms(phase_times.specialize),
+        ms(phase_times.total),
+    )
+}
+
+/// Write an indented tree, starting at `root`. Every node is expanded only the first time
+/// it is reached; later occurrences are marked with `(*)`.
+fn write_tree<K: Copy + Eq + Hash>(
+    buf: &mut String,
+    root: K,
+    label: impl Fn(K) -> String,
+    children: impl Fn(K) -> Vec<K>,
+    cycles: &CycleMembership<K>,
+) {
+    fn help<K: Copy + Eq + Hash>(
+        buf: &mut String,
+        node: K,
+        prefix: &str,
+        label: &impl Fn(K) -> String,
+        children: &impl Fn(K) -> Vec<K>,
+        cycles: &CycleMembership<K>,
+        expanded: &mut MutSet<K>,
+    ) {
+        let deps = children(node);
+        let count = deps.len();
+
+        for (index, dep) in deps.into_iter().enumerate() {
+            let is_last = index + 1 == count;
+            let (branch, indent) = if is_last {
+                ("└── ", "    ")
+            } else {
+                ("├── ", "│   ")
+            };
+
+            let _ = write!(buf, "{prefix}{branch}{}", label(dep));
+
+            if cycles.is_cyclic_edge(node, dep) {
+                buf.push_str(" (cycle)");
+            }
+
+            if expanded.insert(dep) {
+                buf.push('\n');
+
+                let prefix = format!("{prefix}{indent}");
+                help(buf, dep, &prefix, label, children, cycles, expanded);
+            } else {
+                buf.push_str(" (*)\n");
+            }
+        }
+    }
+
+    let mut expanded = MutSet::default();
+    expanded.insert(root);
+
+    let _ = writeln!(buf, "{}", label(root));
+    help(buf, root, "", &label, &children, cycles, &mut expanded);
+}
+
+fn write_cycles<K>(
+    buf: &mut String,
+    heading: &str,
+    cycles: &[Vec<K>],
+    name: impl Fn(&K) -> String,
+) {
+    if cycles.is_empty() {
+        return;
+    }
+
+    let _ = writeln!(buf, "\n{heading}:");
+
+    for cycle in cycles {
+        let names: Vec<String> = cycle.iter().map(&name).collect();
+
+        let _ = writeln!(buf, "    {}", names.join(" <-> "));
+    }
+}
+
+fn module_tree(buf: &mut String, graph: &ModuleGraph, options: DepsOptions) {
+    let cycles = graph.module_cycles(options.include_builtins);
+    let membership = CycleMembership::new(&cycles);
+    let name = |module_id: &ModuleId| graph.get(*module_id).unwrap().qualified_name();
+
+    write_tree(
+        buf,
+        graph.root().module_id,
+        |module_id| module_label(graph.get(module_id).unwrap(), options),
+        |module_id| {
+            graph
+                .imports_of(module_id, options.include_builtins)
+                .iter()
+                .map(|node| node.module_id)
+                .collect()
+        },
+        &membership,
+    );
+
+    write_cycles(buf, "Import cycles", &cycles, name);
+}
+
+fn package_tree(buf: &mut String, graph: &ModuleGraph, options: DepsOptions) {
+    let package_imports = graph.package_imports(options.include_builtins);
+    let cycles = graph.package_cycles(options.include_builtins);
+    let membership = CycleMembership::new(&cycles);
+
+    write_tree(
+        buf,
+        graph.root().package_ref(),
+        |package| package.as_str().to_string(),
+        |package| {
+            let mut deps: Vec<PackageRef> = match package_imports.get(&package) {
+                Some(deps) => deps.iter().copied().collect(),
+                None => Vec::new(),
+            };
+            deps.sort();
+
+            deps
+        },
+        &membership,
+    );
+
+    write_cycles(buf, "Package cycles", &cycles, |package| {
+        package.as_str().to_string()
+    });
+}
+
+fn dot_escape(name: &str) -> String {
+    name.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn module_dot(buf: &mut String, graph: &ModuleGraph, options: DepsOptions) {
+    let cycles = graph.module_cycles(options.include_builtins);
+    let membership = CycleMembership::new(&cycles);
+    let modules = graph.modules(options.include_builtins);
+
+    buf.push_str("digraph modules {\n    node [shape=box];\n");
+
+    // Group the modules of each package into a cluster, leaving the root package ungrouped.
+    let mut clusters: Vec<(PackageRef, Vec<&ModuleNode>)> = Vec::new();
+
+    for node in modules.iter() {
+        match clusters.iter_mut().find(|(p, _)| *p == node.package_ref()) {
+            Some((_, nodes)) => nodes.push(node),
+            None => clusters.push((node.package_ref(), vec![node])),
+        }
+    }
+
+    for (package, nodes) in clusters {
+        let indent = match package {
+            PackageRef::Root => "    ",
+            _ => {
+                let _ = writeln!(
+                    buf,
+                    "    subgraph \"cluster_{0}\" {{\n        label=\"{0}\";",
+                    dot_escape(package.as_str())
+                );
+
+                "        "
+            }
+        };
+
+        for node in nodes {
+            let _ = writeln!(
+                buf,
+                "{indent}\"{}\" [label=\"{}\"];",
+                dot_escape(&node.qualified_name()),
+                dot_escape(&module_label(node, options)).replace(" [", "\\n[")
+            );
+        }
+
+        if !matches!(package, PackageRef::Root) {
+            buf.push_str("    }\n");
+        }
+    }
+
+    for node in modules.iter() {
+        for dep in graph.imports_of(node.module_id, options.include_builtins) {
+            let attributes = if membership.is_cyclic_edge(node.module_id, dep.module_id) {
+                " [color=red]"
+            } else {
+                ""
+            };
+
+            let _ = writeln!(
+                buf,
+                "    \"{}\" -> \"{}\"{attributes};",
+                dot_escape(&node.qualified_name()),
+                dot_escape(&dep.qualified_name()),
+            );
+        }
+    }
+
+    buf.push_str("}\n");
+}
+
+fn package_dot(buf: &mut String, graph: &ModuleGraph, options: DepsOptions) {
+    let package_imports = graph.package_imports(options.include_builtins);
+    let cycles = graph.package_cycles(options.include_builtins);
+    let membership = CycleMembership::new(&cycles);
+
+    buf.push_str("digraph packages {\n    node [shape=box];\n");
+
+    for package in package_imports.keys() {
+        let _ = writeln!(buf, "    \"{}\";", dot_escape(package.as_str()));
+    }
+
+    for (package, deps) in package_imports.iter() {
+        for dep in deps.iter() {
+            let attributes = if membership.is_cyclic_edge(*package, *dep) {
+                " [color=red]"
+            } else {
+                ""
+            };
+
+            let _ = writeln!(
+                buf,
+                "    \"{}\" -> \"{}\"{attributes};",
+                dot_escape(package.as_str()),
+                dot_escape(dep.as_str()),
+            );
+        }
+    }
+
+    buf.push_str("}\n");
+}
+
+fn graph_json(graph: &ModuleGraph, options: DepsOptions) -> serde_json::Value {
+    use serde_json::json;
+
+    let ms = |duration: std::time::Duration| duration.as_secs_f64() * 1000.0;
+
+    let modules: Vec<serde_json::Value> = graph
+        .modules(options.include_builtins)
+        .into_iter()
+        .map(|node| {
+            let imports: Vec<String> = graph
+                .imports_of(node.module_id, options.include_builtins)
+                .iter()
+                .map(|dep| dep.qualified_name())
+                .collect();
+
+            let timings_ms = match (&node.phase_times, options.show_timings) {
+                (Some(phase_times), true) => json!({
+                    "parse": ms(phase_times.parse),
+                    "canonicalize": ms(phase_times.canonicalize),
+                    "solve": ms(phase_times.solve),
+                    "specialize": ms(phase_times.specialize),
+                    "total": ms(phase_times.total),
+                }),
+                _ => serde_json::Value::Null,
+            };
+
+            json!({
+                "name": node.qualified_name(),
+                "package": node.package_ref().as_str(),
+                "path": node.path.as_ref().map(|path| path.display().to_string()),
+                "lines": node.line_count,
+                "timings_ms": timings_ms,
+                "imports": imports,
+            })
+        })
+        .collect();
+
+    let packages: Vec<serde_json::Value> = graph
+        .package_imports(options.include_builtins)
+        .iter()
+        .map(|(package, deps)| {
+            let imports: Vec<&str> = deps.iter().map(|dep| dep.as_str()).collect();
+
+            json!({ "name": package.as_str(), "imports": imports })
+        })
+        .collect();
+
+    let module_cycles: Vec<Vec<String>> = graph
+        .module_cycles(options.include_builtins)
+        .iter()
+        .map(|cycle| {
+            cycle
+                .iter()
+                .map(|module_id| graph.get(*module_id).unwrap().qualified_name())
+                .collect()
+        })
+        .collect();
+
+    let package_cycles: Vec<Vec<&str>> = graph
+        .package_cycles(options.include_builtins)
+        .iter()
+        .map(|cycle| cycle.iter().map(|package| package.as_str()).collect())
+        .collect();
+
+    json!({
+        "root": graph.root().qualified_name(),
+        "modules": modules,
+        "packages": packages,
+        "cycles": {
+            "modules": module_cycles,
+            "packages": package_cycles,
+        },
+    })
+}
@@ -15,10 +15,11 @@ use roc_build::program::{
 };
 use roc_collections::MutMap;
 use roc_error_macros::{internal_error, user_error};
-use roc_gen_dev::AssemblyBackendMode;
+use roc_gen_dev::{AssemblyBackendMode, RegisterAllocation};
 use roc_gen_llvm::llvm::build::LlvmBackendMode;
-use roc_load::{ExpectMetadata, Threading};
+use roc_load::{EmitIr, ExpectMetadata, IrPhase, Threading};
 use roc_module::symbol::ModuleId;
+use roc_mono::inline::InlineLevel;
 use roc_mono::ir::OptLevel;
 use roc_packaging::cache::RocCacheDir;
 use roc_packaging::tarball::Compression;
@@ -36,7 +37,9 @@ use strum::IntoEnumIterator;
 #[cfg(not(target_os = "linux"))]
 use tempfile::TempDir;
 
+mod deps;
 mod format;
+pub use deps::{deps, render_deps, DepsFormat, DepsOptions};
 pub use format::{format_files, format_src, FormatMode};
 
 pub const CMD_BUILD: &str = "build";
@@ -51,11 +54,18 @@ pub const CMD_TEST: &str = "test";
 pub const CMD_GLUE: &str = "glue";
 pub const CMD_GEN_STUB_LIB: &str = "gen-stub-lib";
 pub const CMD_PREPROCESS_HOST: &str = "preprocess-host";
+pub const CMD_DEPS: &str = "deps";
 
 pub const FLAG_EMIT_LLVM_IR: &str = "emit-llvm-ir";
+pub const FLAG_EMIT_ASM: &str = "emit-asm";
+pub const FLAG_EMIT_WAT: &str = "emit-wat";
+pub const FLAG_EMIT_IR: &str = "emit-ir";
+pub const FLAG_EMIT_DIR: &str = "emit-dir";
 pub const FLAG_PROFILING: &str = "profiling";
 pub const FLAG_BUNDLE: &str = "bundle";
 pub const FLAG_DEV: &str = "dev";
+pub const FLAG_LINEAR_SCAN: &str = "linear-scan";
+pub const FLAG_DEBUG: &str = "debug";
 pub const FLAG_OPTIMIZE: &str = "optimize";
 pub const FLAG_MAX_THREADS: &str = "max-threads";
 pub const FLAG_OPT_SIZE: &str = "opt-size";
@@ -63,6 +73,7 @@ pub const FLAG_LIB: &str = "lib";
 pub const FLAG_NO_LINK: &str = "no-link";
 pub const FLAG_TARGET: &str = "target";
 pub const FLAG_TIME: &str = "time";
+pub const FLAG_TIME_TRACE: &str = "time-trace";
 pub const FLAG_VERBOSE: &str = "verbose";
 pub const FLAG_LINKER: &str = "linker";
 pub const FLAG_PREBUILT: &str = "prebuilt-platform";
@@ -72,6 +83,13 @@ pub const FLAG_STDOUT: &str = "stdout";
 pub const FLAG_WASM_STACK_SIZE_KB: &str = "wasm-stack-size-kb";
 pub const FLAG_OUTPUT: &str = "output";
 pub const FLAG_FUZZ: &str = "fuzz";
+pub const FLAG_INSTRUMENT_MEMORY: &str = "instrument-memory";
+pub const FLAG_GRAPH_FORMAT: &str = "format";
+pub const FLAG_PACKAGES: &str = "packages";
+pub const FLAG_BUILTINS: &str = "builtins";
+pub const FLAG_LINES: &str = "lines";
+pub const FLAG_PERF_HINTS: &str = "perf-hints";
+pub const FLAG_SPEC_SIZES: &str = "spec-sizes";
 pub const ROC_FILE: &str = "ROC_FILE";
 pub const ROC_DIR: &str = "ROC_DIR";
 pub const GLUE_DIR: &str = "GLUE_DIR";
@@ -107,12 +125,36 @@ pub fn build_app() -> Command {
         .action(ArgAction::SetTrue)
         .required(false);
 
+    let flag_linear_scan = Arg::new(FLAG_LINEAR_SCAN)
+        .long(FLAG_LINEAR_SCAN)
+        .help("Use a linear-scan register allocator in the dev backend instead of the default greedy one (requires --dev)")
+        .action(ArgAction::SetTrue)
+        .required(false);
+
+    let flag_debug = Arg::new(FLAG_DEBUG)
+        .long(FLAG_DEBUG)
+        .help("Emit source-level debug info (DWARF), so gdb and lldb can map machine code to Roc lines and show variables, and write gdb and lldb pretty printers for Roc values next to the binary. For wasm32 with --dev, embed a source map instead")
+        .action(ArgAction::SetTrue)
+        .required(false);
+
     let flag_emit_llvm_ir = Arg::new(FLAG_EMIT_LLVM_IR)
         .long(FLAG_EMIT_LLVM_IR)
         .help("Emit a `.ll` file containing the LLVM IR of the program")
         .action(ArgAction::SetTrue)
         .required(false);
 
+    let flag_emit_asm = Arg::new(FLAG_EMIT_ASM)
+        .long(FLAG_EMIT_ASM)
+        .help("Emit a `.s` file containing the disassembled machine code of the program (requires --dev)")
+        .action(ArgAction::SetTrue)
+        .required(false);
+
+    let flag_emit_wat = Arg::new(FLAG_EMIT_WAT)
+        .long(FLAG_EMIT_WAT)
+        .help("Emit a `.wat` file containing the program in the WebAssembly text format (requires --dev and --target wasm32)")
+        .action(ArgAction::SetTrue)
+        .required(false);
+
     let flag_profiling = Arg::new(FLAG_PROFILING)
         .long(FLAG_PROFILING)
         .help("Keep debug info in the final generated program even in optimized builds")
@@ -125,6 +167,12 @@ pub fn build_app() -> Command {
         .action(ArgAction::SetTrue)
         .required(false);
 
+    let flag_time_trace = Arg::new(FLAG_TIME_TRACE)
+        .long(FLAG_TIME_TRACE)
+        .help("Write a profile of where compilation time went to the given file\n(The file uses the Chrome trace event format, so it can be opened in chrome://tracing or ui.perfetto.dev.)")
+        .value_parser(value_parser!(PathBuf))
+        .required(false);
+
     let flag_linker = Arg::new(FLAG_LINKER)
         .long(FLAG_LINKER)
         .help("Set which linker to use\n(The surgical linker is enabled by default only when building for wasm32 or x86_64 Linux, because those are the only targets it currently supports. Otherwise the legacy linker is used by default.)")
@@ -133,7 +181,7 @@ pub fn build_app() -> Command {
 
     let flag_prebuilt = Arg::new(FLAG_PREBUILT)
         .long(FLAG_PREBUILT)
-        .help("Assume the platform has been prebuilt and skip rebuilding the platform\n(This is enabled implicitly when using `roc build` with a --target other than `--target <current machine>`, unless the target is wasm.)")
+        .help("Assume the platform has been prebuilt and skip rebuilding the platform\n(This is enabled implicitly when using `roc build` with a --target other than `--target <current machine>`, unless the target is wasm. The host is looked up beside the platform's main.roc as `<target>.rh` for the surgical linker, or `<target>.a`/`<target>.o` for the legacy linker.)")
         .action(ArgAction::SetTrue)
         .required(false);
 
@@ -149,6 +197,12 @@ pub fn build_app() -> Command {
         .action(ArgAction::SetTrue)
         .required(false);
 
+    let flag_instrument_memory = Arg::new(FLAG_INSTRUMENT_MEMORY)
+        .long(FLAG_INSTRUMENT_MEMORY)
+        .help("Report every allocation and refcount change, and the Roc function it came from, to the host\n(The host must provide the roc_memory_on_* hooks, e.g. from the instrument-memory feature of roc_std.)")
+        .action(ArgAction::SetTrue)
+        .required(false);
+
     let roc_file_to_run = Arg::new(ROC_FILE)
         .help("The .roc file of an app to run")
         .value_parser(value_parser!(PathBuf))
@@ -180,12 +234,44 @@ pub fn build_app() -> Command {
             .arg(flag_max_threads.clone())
             .arg(flag_opt_size.clone())
             .arg(flag_dev.clone())
+            .arg(flag_linear_scan.clone())
+            .arg(flag_debug.clone())
             .arg(flag_emit_llvm_ir.clone())
+            .arg(flag_emit_asm.clone())
+            .arg(flag_emit_wat.clone())
+            .arg(
+                Arg::new(FLAG_EMIT_IR)
+                    .long(FLAG_EMIT_IR)
+                    .help("Write the mono IR after the given phases to --emit-dir, in one file per proc\n(The phases are specialization, trmc, inline, refcount, drop-specialization and reset-reuse, or all of them.)")
+                    .value_parser(PossibleValuesParser::new(
+                        IrPhase::ALL.iter().map(|phase| phase.name()).chain(["all"]),
+                    ))
+                    .value_delimiter(',')
+                    .action(ArgAction::Append)
+                    .requires(FLAG_EMIT_DIR)
+                    .required(false),
+            )
+            .arg(
+                Arg::new(FLAG_EMIT_DIR)
+                    .long(FLAG_EMIT_DIR)
+                    .help("The directory to write the files from --emit-ir to")
+                    .value_parser(value_parser!(PathBuf))
+                    .required(false),
+            )
+            .arg(
+                Arg::new(FLAG_SPEC_SIZES)
+                    .long(FLAG_SPEC_SIZES)
+                    .help("Print the size of every specialization of every function, biggest first\n(Sizes are counted in mono IR statements, after inlining, so they are the same for every backend.)")
+                    .action(ArgAction::SetTrue)
+                    .required(false),
+            )
             .arg(flag_profiling.clone())
             .arg(flag_time.clone())
+            .arg(flag_time_trace.clone())
             .arg(flag_linker.clone())
             .arg(flag_prebuilt.clone())
             .arg(flag_fuzz.clone())
+            .arg(flag_instrument_memory.clone())
             .arg(flag_wasm_stack_size_kb)
             .arg(
                 Arg::new(FLAG_TARGET)
@@ -198,7 +284,7 @@ pub fn build_app() -> Command {
             .arg(
                 Arg::new(FLAG_LIB)
                     .long(FLAG_LIB)
-                    .help("Build a C library instead of an executable")
+                    .help("Build a C library instead of an executable\n(If the platform ships a host that is itself a shared library, the app is linked into it.)")
                     .action(ArgAction::SetTrue)
                     .required(false),
             )
@@ -237,6 +323,7 @@ pub fn build_app() -> Command {
             .arg(flag_linker.clone())
             .arg(flag_prebuilt.clone())
             .arg(flag_fuzz.clone())
+            .arg(flag_instrument_memory.clone())
             .arg(
                 Arg::new(FLAG_VERBOSE)
                     .long(FLAG_VERBOSE)
@@ -262,12 +349,18 @@ pub fn build_app() -> Command {
             .arg(flag_max_threads.clone())
             .arg(flag_opt_size.clone())
             .arg(flag_dev.clone())
+            .arg(flag_linear_scan.clone())
+            .arg(flag_debug.clone())
             .arg(flag_emit_llvm_ir.clone())
+            .arg(flag_emit_asm.clone())
+            .arg(flag_emit_wat.clone())
             .arg(flag_profiling.clone())
             .arg(flag_time.clone())
+            .arg(flag_time_trace.clone())
             .arg(flag_linker.clone())
             .arg(flag_prebuilt.clone())
             .arg(flag_fuzz.clone())
+            .arg(flag_instrument_memory.clone())
             .arg(roc_file_to_run.clone())
             .arg(args_for_app.clone().last(true))
         )
@@ -277,12 +370,18 @@ pub fn build_app() -> Command {
             .arg(flag_max_threads.clone())
             .arg(flag_opt_size.clone())
             .arg(flag_dev.clone())
+            .arg(flag_linear_scan.clone())
+            .arg(flag_debug.clone())
             .arg(flag_emit_llvm_ir.clone())
+            .arg(flag_emit_asm.clone())
+            .arg(flag_emit_wat.clone())
             .arg(flag_profiling.clone())
             .arg(flag_time.clone())
+            .arg(flag_time_trace.clone())
             .arg(flag_linker.clone())
             .arg(flag_prebuilt.clone())
             .arg(flag_fuzz.clone())
+            .arg(flag_instrument_memory.clone())
             .arg(roc_file_to_run.clone())
             .arg(args_for_app.clone().last(true))
         )
@@ -322,7 +421,15 @@ pub fn build_app() -> Command {
         .subcommand(Command::new(CMD_CHECK)
             .about("Check the code for problems, but don’t build or run it")
             .arg(flag_time.clone())
+            .arg(flag_time_trace.clone())
             .arg(flag_max_threads.clone())
+            .arg(
+                Arg::new(FLAG_PERF_HINTS)
+                    .long(FLAG_PERF_HINTS)
+                    .help("Also show where values get copied because they are shared, and where allocations are (or are not) reused\n(This runs specialization, so the file must be an app.)")
+                    .action(ArgAction::SetTrue)
+                    .required(false),
+            )
             .arg(
                 Arg::new(ROC_FILE)
                     .help("The .roc file of an app to check")
@@ -331,6 +438,53 @@ pub fn build_app() -> Command {
                     .default_value(DEFAULT_ROC_FILENAME),
             )
             )
+        .subcommand(Command::new(CMD_DEPS)
+            .about("Print the graph of modules and packages imported by a .roc file")
+            .arg(flag_max_threads.clone())
+            .arg(
+                Arg::new(FLAG_GRAPH_FORMAT)
+                    .long(FLAG_GRAPH_FORMAT)
+                    .help("How to print the graph")
+                    .value_parser(["text", "dot", "json"])
+                    .default_value("text")
+                    .required(false),
+            )
+            .arg(
+                Arg::new(FLAG_PACKAGES)
+                    .long(FLAG_PACKAGES)
+                    .help("Print the imports between packages instead of between modules")
+                    .action(ArgAction::SetTrue)
+                    .required(false),
+            )
+            .arg(
+                Arg::new(FLAG_BUILTINS)
+                    .long(FLAG_BUILTINS)
+                    .help("Include the builtin modules, which every module imports implicitly")
+                    .action(ArgAction::SetTrue)
+                    .required(false),
+            )
+            .arg(
+                Arg::new(FLAG_LINES)
+                    .long(FLAG_LINES)
+                    .help("Annotate each module with its number of lines")
+                    .action(ArgAction::SetTrue)
+                    .required(false),
+            )
+            .arg(
+                Arg::new(FLAG_TIME)
+                    .long(FLAG_TIME)
+                    .help("Annotate each module with how long each compiler phase took\n(For apps, this also runs specialization.)")
+                    .action(ArgAction::SetTrue)
+                    .required(false),
+            )
+            .arg(
+                Arg::new(ROC_FILE)
+                    .help("The .roc file whose imports should be printed")
+                    .value_parser(value_parser!(PathBuf))
+                    .required(false)
+                    .default_value(DEFAULT_ROC_FILENAME),
+            )
+        )
         .subcommand(
             Command::new(CMD_DOCS)
                 .about("Generate documentation for a Roc package")
@@ -409,12 +563,18 @@ pub fn build_app() -> Command {
         .arg(flag_max_threads)
         .arg(flag_opt_size)
         .arg(flag_dev)
+        .arg(flag_linear_scan)
+        .arg(flag_debug)
         .arg(flag_emit_llvm_ir)
+        .arg(flag_emit_asm)
+        .arg(flag_emit_wat)
         .arg(flag_profiling)
         .arg(flag_time)
+        .arg(flag_time_trace)
         .arg(flag_linker)
         .arg(flag_prebuilt)
         .arg(flag_fuzz)
+        .arg(flag_instrument_memory)
         .arg(roc_file_to_run)
         .arg(args_for_app.trailing_var_arg(true))
 }
@@ -426,6 +586,25 @@ pub enum BuildConfig {
     BuildAndRunIfNoErrors,
 }
 
+fn emit_ir_from_flags(matches: &ArgMatches) -> Option<EmitIr> {
+    // Only `roc build` has these flags
+    let phases = matches.try_get_many::<String>(FLAG_EMIT_IR).ok()??;
+    let dir = matches.get_one::<PathBuf>(FLAG_EMIT_DIR)?;
+
+    let phases = phases
+        .flat_map(|name| match IrPhase::from_name(name) {
+            Some(phase) => vec![phase],
+            // "all"
+            None => IrPhase::ALL.to_vec(),
+        })
+        .collect();
+
+    Some(EmitIr {
+        phases,
+        dir: dir.clone(),
+    })
+}
+
 fn opt_level_from_flags(matches: &ArgMatches) -> OptLevel {
     match (
         matches.get_flag(FLAG_OPTIMIZE),
@@ -440,6 +619,14 @@ fn opt_level_from_flags(matches: &ArgMatches) -> OptLevel {
     }
 }
 
+/// The dev backends have no optimizer of their own, so `--dev` still inlines small procs.
+fn inline_level_from_flags(matches: &ArgMatches, opt_level: OptLevel) -> InlineLevel {
+    match opt_level {
+        OptLevel::Development if matches.get_flag(FLAG_DEV) => InlineLevel::Normal,
+        _ => InlineLevel::from(opt_level),
+    }
+}
+
 #[cfg(windows)]
 pub fn test(_matches: &ArgMatches, _target: Target) -> io::Result<i32> {
     todo!("running tests does not work on windows right now")
@@ -455,13 +642,18 @@ struct ModuleTestResults {
 #[cfg(not(windows))]
 pub fn test(matches: &ArgMatches, target: Target) -> io::Result<i32> {
     use roc_build::program::report_problems_monomorphized;
-    use roc_load::{ExecutionMode, FunctionKind, LoadConfig, LoadMonomorphizedError};
+    use roc_load::{ExecutionMode, FunctionKind, LoadConfig, LoadMonomorphizedError, OutputConfig};
     use roc_packaging::cache;
 
     let start_time = Instant::now();
     let arena = Bump::new();
     let opt_level = opt_level_from_flags(matches);
 
+    // The dev backend can only build dynamic libraries for these targets so far
+    if matches.get_flag(FLAG_DEV) && !matches!(target, Target::LinuxX64 | Target::LinuxArm64) {
+        user_error!("`roc test --dev` is only supported on x86_64 and aarch64 Linux");
+    }
+
     let threading = match matches.get_one::<usize>(FLAG_MAX_THREADS) {
         None => Threading::AllAvailable,
         Some(0) => user_error!("cannot build with at most 0 threads"),
@@ -505,6 +697,10 @@ pub fn test(matches: &ArgMatches, target: Target) -> io::Result<i32> {
         palette: roc_reporting::report::DEFAULT_PALETTE,
         threading,
         exec_mode: ExecutionMode::Test,
+        output: OutputConfig {
+            inline_level: inline_level_from_flags(matches, opt_level),
+            ..OutputConfig::default()
+        },
     };
     let load_result = roc_load::load_and_monomorphize(
         arena,
@@ -529,7 +725,9 @@ pub fn test(matches: &ArgMatches, target: Target) -> io::Result<i32> {
     let interns = loaded.interns.clone();
     let sources = loaded.sources.clone();
 
-    let (dyn_lib, expects_by_module, layout_interner) =
+    let (dyn_lib, expects_by_module, layout_interner) = if matches.get_flag(FLAG_DEV) {
+        roc_repl_expect::run::expect_mono_module_to_dylib_dev(arena, target, loaded).unwrap()
+    } else {
         roc_repl_expect::run::expect_mono_module_to_dylib(
             arena,
             target,
@@ -537,7 +735,8 @@ pub fn test(matches: &ArgMatches, target: Target) -> io::Result<i32> {
             opt_level,
             LlvmBackendMode::CliTest,
         )
-        .unwrap();
+        .unwrap()
+    };
 
     // Print warnings before running tests.
     {
@@ -783,7 +982,14 @@ pub fn build(
         if matches!(target.architecture(), Architecture::Wasm32) {
             CodeGenBackend::Wasm
         } else {
-            CodeGenBackend::Assembly(AssemblyBackendMode::Binary)
+            let backend_mode = match opt_level {
+                OptLevel::Development => AssemblyBackendMode::BinaryDev,
+                OptLevel::Normal | OptLevel::Size | OptLevel::Optimize => {
+                    AssemblyBackendMode::Binary
+                }
+            };
+
+            CodeGenBackend::Assembly(backend_mode)
         }
     } else {
         let backend_mode = match opt_level {
@@ -794,13 +1000,45 @@ pub fn build(
         CodeGenBackend::Llvm(backend_mode)
     };
 
+    let register_allocation = if matches.get_flag(FLAG_LINEAR_SCAN) {
+        if matches!(code_gen_backend, CodeGenBackend::Wasm) {
+            user_error!("Cannot use the linear-scan register allocator with the wasm dev backend, which has no register allocator. It only works for x86_64 and aarch64.");
+        }
+        if !matches!(code_gen_backend, CodeGenBackend::Assembly(_)) {
+            user_error!("Cannot use the linear-scan register allocator without the dev backend. Pass --dev as well.");
+        }
+
+        RegisterAllocation::LinearScan
+    } else {
+        RegisterAllocation::Greedy
+    };
+
     let emit_llvm_ir = matches.get_flag(FLAG_EMIT_LLVM_IR);
     if emit_llvm_ir && !matches!(code_gen_backend, CodeGenBackend::Llvm(_)) {
         user_error!("Cannot emit llvm ir while using a dev backend.");
     }
 
+    let emit_asm = matches.get_flag(FLAG_EMIT_ASM);
+    if emit_asm && !matches!(code_gen_backend, CodeGenBackend::Assembly(_)) {
+        user_error!("Cannot emit assembly without the dev backend for x86_64 or aarch64. Pass --dev as well.");
+    }
+    if emit_asm && !cfg!(feature = "disassemble") {
+        user_error!("This roc was built without the disassembler. Rebuild it with `--features disassemble` to use --emit-asm.");
+    }
+
+    let emit_wat = matches.get_flag(FLAG_EMIT_WAT);
+    if emit_wat && !matches!(code_gen_backend, CodeGenBackend::Wasm) {
+        user_error!("Cannot emit WebAssembly text without the wasm dev backend. Pass --dev and --target wasm32 as well.");
+    }
+
     let emit_debug_info = matches.get_flag(FLAG_PROFILING)
         || matches!(opt_level, OptLevel::Development | OptLevel::Normal);
+    let emit_source_debug_info = matches.get_flag(FLAG_DEBUG);
+    if emit_source_debug_info && matches!(code_gen_backend, CodeGenBackend::Assembly(_)) {
+        user_error!(
+            "Cannot emit source-level debug info with the dev backend for x86_64 or aarch64."
+        );
+    }
     let emit_timings = matches.get_flag(FLAG_TIME);
 
     let threading = match matches.get_one::<usize>(FLAG_MAX_THREADS) {
@@ -839,6 +1077,19 @@ pub fn build(
         user_error!("Cannot instrument binary for fuzzing while using a dev backend.");
     }
 
+    let instrument_memory = matches.get_flag(FLAG_INSTRUMENT_MEMORY);
+    if instrument_memory && !matches!(code_gen_backend, CodeGenBackend::Llvm(_)) {
+        user_error!("Cannot instrument memory while using a dev backend.");
+    }
+
+    // Only `roc build` has this flag
+    let report_spec_sizes = matches
+        .try_get_one::<bool>(FLAG_SPEC_SIZES)
+        .ok()
+        .flatten()
+        .copied()
+        .unwrap_or(false);
+
     let wasm_dev_stack_bytes: Option<u32> = matches
         .try_get_one::<u32>(FLAG_WASM_STACK_SIZE_KB)
         .ok()
@@ -854,11 +1105,28 @@ pub fn build(
         backend: code_gen_backend,
         opt_level,
         emit_debug_info,
+        emit_source_debug_info,
         emit_llvm_ir,
+        emit_asm,
+        emit_wat,
         fuzz,
+        report_spec_sizes,
+        instrument_memory,
+        register_allocation,
+    };
+
+    let mut load_config = standard_load_config(target, build_ordering, threading);
+    load_config.output = OutputConfig {
+        emit_ir: emit_ir_from_flags(matches),
+        inline_level: inline_level_from_flags(matches, opt_level),
+        mark_source_regions: emit_source_debug_info,
+        ..OutputConfig::default()
     };
 
-    let load_config = standard_load_config(target, build_ordering, threading);
+    let time_trace = matches.get_one::<PathBuf>(FLAG_TIME_TRACE);
+    if time_trace.is_some() {
+        roc_tracing::profile::enable();
+    }
 
     let res_binary_path = build_file(
         &arena,
@@ -875,6 +1143,10 @@ pub fn build(
         out_path,
     );
 
+    if let Some(trace_path) = time_trace {
+        roc_tracing::profile::write_trace(trace_path)?;
+    }
+
     match res_binary_path {
         Ok(BuiltFile {
             binary_path,
@@ -3,11 +3,11 @@ use bumpalo::Bump;
 use roc_build::link::LinkType;
 use roc_build::program::{check_file, CodeGenBackend};
 use roc_cli::{
-    build_app, format_files, format_src, test, BuildConfig, FormatMode, CMD_BUILD, CMD_CHECK,
-    CMD_DEV, CMD_DOCS, CMD_FORMAT, CMD_GEN_STUB_LIB, CMD_GLUE, CMD_PREPROCESS_HOST, CMD_REPL,
-    CMD_RUN, CMD_TEST, CMD_VERSION, DIRECTORY_OR_FILES, FLAG_CHECK, FLAG_DEV, FLAG_LIB,
-    FLAG_NO_LINK, FLAG_OUTPUT, FLAG_STDIN, FLAG_STDOUT, FLAG_TARGET, FLAG_TIME, GLUE_DIR,
-    GLUE_SPEC, ROC_FILE,
+    build_app, deps, format_files, format_src, test, BuildConfig, FormatMode, CMD_BUILD, CMD_CHECK,
+    CMD_DEPS, CMD_DEV, CMD_DOCS, CMD_FORMAT, CMD_GEN_STUB_LIB, CMD_GLUE, CMD_PREPROCESS_HOST,
+    CMD_REPL, CMD_RUN, CMD_TEST, CMD_VERSION, DIRECTORY_OR_FILES, FLAG_CHECK, FLAG_DEV, FLAG_LIB,
+    FLAG_NO_LINK, FLAG_OUTPUT, FLAG_PERF_HINTS, FLAG_STDIN, FLAG_STDOUT, FLAG_TARGET, FLAG_TIME,
+    FLAG_TIME_TRACE, GLUE_DIR, GLUE_SPEC, ROC_FILE,
 };
 use roc_docs::generate_docs_html;
 use roc_error_macros::user_error;
@@ -192,6 +192,7 @@ fn main() -> io::Result<()> {
             let arena = Bump::new();
 
             let emit_timings = matches.get_flag(FLAG_TIME);
+            let perf_hints = matches.get_flag(FLAG_PERF_HINTS);
             let roc_file_path = matches.get_one::<PathBuf>(ROC_FILE).unwrap();
             let threading = match matches.get_one::<usize>(roc_cli::FLAG_MAX_THREADS) {
                 None => Threading::AllAvailable,
@@ -200,13 +201,25 @@ fn main() -> io::Result<()> {
                 Some(n) => Threading::AtMost(*n),
             };
 
-            match check_file(
+            let time_trace = matches.get_one::<PathBuf>(FLAG_TIME_TRACE);
+            if time_trace.is_some() {
+                roc_tracing::profile::enable();
+            }
+
+            let check_result = check_file(
                 &arena,
                 roc_file_path.to_owned(),
                 emit_timings,
+                perf_hints,
                 RocCacheDir::Persistent(cache::roc_cache_dir().as_path()),
                 threading,
-            ) {
+            );
+
+            if let Some(trace_path) = time_trace {
+                roc_tracing::profile::write_trace(trace_path)?;
+            }
+
+            match check_result {
                 Ok((problems, total_time)) => {
                     problems.print_error_warning_count(total_time);
                     Ok(problems.exit_code())
@@ -222,6 +235,7 @@ fn main() -> io::Result<()> {
                 }
             }
         }
+        Some((CMD_DEPS, matches)) => deps(matches),
         Some((CMD_REPL, _)) => Ok(roc_repl_cli::main()),
         Some((CMD_DOCS, matches)) => {
             let root_path = matches.get_one::<PathBuf>(ROC_FILE).unwrap();
@@ -17,7 +17,7 @@ mod cli_run {
     use const_format::concatcp;
     use indoc::indoc;
     use regex::Regex;
-    use roc_cli::{CMD_BUILD, CMD_CHECK, CMD_DEV, CMD_FORMAT, CMD_RUN, CMD_TEST};
+    use roc_cli::{CMD_BUILD, CMD_CHECK, CMD_DEPS, CMD_DEV, CMD_FORMAT, CMD_RUN, CMD_TEST};
     use roc_reporting::report::strip_colors;
     use roc_test_utils::assert_multiline_str_eq;
     use serial_test::serial;
@@ -54,6 +54,18 @@ mod cli_run {
     const OPTIMIZE_FLAG: &str = concatcp!("--", roc_cli::FLAG_OPTIMIZE);
     const LINKER_FLAG: &str = concatcp!("--", roc_cli::FLAG_LINKER);
     const CHECK_FLAG: &str = concatcp!("--", roc_cli::FLAG_CHECK);
+    const DEBUG_FLAG: &str = concatcp!("--", roc_cli::FLAG_DEBUG);
+    const NO_LINK_FLAG: &str = concatcp!("--", roc_cli::FLAG_NO_LINK);
+    const EMIT_IR_FLAG: &str = concatcp!("--", roc_cli::FLAG_EMIT_IR);
+    const EMIT_DIR_FLAG: &str = concatcp!("--", roc_cli::FLAG_EMIT_DIR);
+    const EMIT_ASM_FLAG: &str = concatcp!("--", roc_cli::FLAG_EMIT_ASM);
+    #[allow(dead_code)]
+    const EMIT_WAT_FLAG: &str = concatcp!("--", roc_cli::FLAG_EMIT_WAT);
+    const PERF_HINTS_FLAG: &str = concatcp!("--", roc_cli::FLAG_PERF_HINTS);
+    const INSTRUMENT_MEMORY_FLAG: &str = concatcp!("--", roc_cli::FLAG_INSTRUMENT_MEMORY);
+    const SPEC_SIZES_FLAG: &str = concatcp!("--", roc_cli::FLAG_SPEC_SIZES);
+    const GRAPH_FORMAT_FLAG: &str = concatcp!("--", roc_cli::FLAG_GRAPH_FORMAT);
+    const LINES_FLAG: &str = concatcp!("--", roc_cli::FLAG_LINES);
     #[allow(dead_code)]
     const PREBUILT_PLATFORM: &str = concatcp!("--", roc_cli::FLAG_PREBUILT);
     #[allow(dead_code)]
@@ -555,39 +567,58 @@ mod cli_run {
         // these are in the same test function so we don't have to worry about race conditions
         // on the building of the platform
 
+        let dev_output = indoc!(
+            r#"
+            ── EXPECT FAILED in tests/expects/expects.roc ──────────────────────────────────
+
+            This expectation failed:
+
+            28│      expect words == []
+                            ^^^^^^^^^^^
+
+            When it failed, these variables had these values:
+
+            words : List Str
+            words = ["this", "will", "for", "sure", "be", "a", "large", "string", "so", "when", "we", "split", "it", "it", "will", "use", "seamless", "slices", "which", "affect", "printing"]
+
+            [<ignored for tests>:31] x = 42
+            [<ignored for tests>:33] "Fjoer en ferdjer frieten oan dyn geve lea" = "Fjoer en ferdjer frieten oan dyn geve lea"
+            [<ignored for tests>:35] "this is line 24" = "this is line 24"
+            [<ignored for tests>:21] x = "abc"
+            [<ignored for tests>:21] x = 10
+            [<ignored for tests>:21] x = (A (B C))
+            Program finished!
+            "#
+        );
+
         test_roc_app(
             "crates/cli/tests/expects",
             "expects.roc",
             &[],
             &[],
             &[],
-            indoc!(
-                r#"
-                ── EXPECT FAILED in tests/expects/expects.roc ──────────────────────────────────
-
-                This expectation failed:
-
-                28│      expect words == []
-                                ^^^^^^^^^^^
-
-                When it failed, these variables had these values:
-
-                words : List Str
-                words = ["this", "will", "for", "sure", "be", "a", "large", "string", "so", "when", "we", "split", "it", "it", "will", "use", "seamless", "slices", "which", "affect", "printing"]
-
-                [<ignored for tests>:31] x = 42
-                [<ignored for tests>:33] "Fjoer en ferdjer frieten oan dyn geve lea" = "Fjoer en ferdjer frieten oan dyn geve lea"
-                [<ignored for tests>:35] "this is line 24" = "this is line 24"
-                [<ignored for tests>:21] x = "abc"
-                [<ignored for tests>:21] x = 10
-                [<ignored for tests>:21] x = (A (B C))
-                Program finished!
-                "#
-            ),
+            dev_output,
             UseValgrind::Yes,
             TestCliCommands::Dev,
         );
 
+        // The dev backend reports the same dbgs and failed expects as LLVM
+        if cfg!(all(
+            target_os = "linux",
+            any(target_arch = "x86_64", target_arch = "aarch64")
+        )) {
+            check_output_with_stdin(
+                &file_path_from_root("crates/cli/tests/expects", "expects.roc"),
+                &[],
+                &["--dev"],
+                &[],
+                &[],
+                dev_output,
+                UseValgrind::Yes,
+                TestCliCommands::Dev,
+            );
+        }
+
         test_roc_app(
             "crates/cli/tests/expects",
             "expects.roc",
@@ -683,6 +714,66 @@ mod cli_run {
         );
     }
 
+    #[test]
+    #[cfg_attr(
+        not(all(
+            target_os = "linux",
+            any(target_arch = "x86_64", target_arch = "aarch64")
+        )),
+        ignore = "`roc test --dev` only supports x86_64 and aarch64 linux"
+    )]
+    fn transitive_expects_dev_backend() {
+        test_roc_expect(
+            "crates/cli/tests/expects_transitive",
+            "main.roc",
+            &["--dev", "--verbose"],
+            indoc!(
+                r#"
+                Compiled in <ignored for test> ms.
+
+                Direct.roc:
+                    0 failed and 2 passed in <ignored for test> ms.
+
+                Transitive.roc:
+                    0 failed and 1 passed in <ignored for test> ms.
+                "#
+            ),
+        );
+    }
+
+    #[test]
+    #[cfg_attr(
+        not(all(
+            target_os = "linux",
+            any(target_arch = "x86_64", target_arch = "aarch64")
+        )),
+        ignore = "`roc test --dev` only supports x86_64 and aarch64 linux"
+    )]
+    fn expects_dev_backend_matches_llvm() {
+        let path = file_path_from_root("crates/cli/tests/expects", "expects.roc");
+
+        let roc_test = |flags: &[&str]| {
+            let out = run_roc(
+                [CMD_TEST, path.to_str().unwrap()].iter().chain(flags),
+                &[],
+                &[],
+            );
+            assert!(!out.status.success(), "the expects should fail: {out:?}");
+
+            ignore_test_timings(&strip_colors(&out.stdout))
+        };
+
+        let llvm_output = roc_test(&[]);
+        let dev_output = roc_test(&["--dev"]);
+
+        // Both the top-level expect and the inline expects it runs into have failed
+        assert!(
+            llvm_output.contains("1 failed and 0 passed"),
+            "{llvm_output}"
+        );
+        assert_multiline_str_eq!(llvm_output.as_str(), dev_output.as_str());
+    }
+
     #[test]
     #[cfg_attr(
         windows,
@@ -1320,6 +1411,650 @@ mod cli_run {
         );
     }
 
+    #[test]
+    #[serial(multi_dep_str)]
+    #[cfg_attr(windows, ignore)]
+    fn run_multi_dep_str_with_debug_info() {
+        check_output_with_stdin(
+            &fixture_file("multi-dep-str", "DebugInfo.roc"),
+            &[],
+            &[DEBUG_FLAG],
+            &[],
+            &[],
+            "I am Dep2.str2!\n",
+            UseValgrind::Yes,
+            TestCliCommands::Run,
+        );
+
+        let roc_file = fixture_file("multi-dep-str", "DebugInfo.roc");
+        run_roc_on_failure_is_panic(
+            &roc_file,
+            [CMD_BUILD, DEBUG_FLAG, NO_LINK_FLAG],
+            &[],
+            &[],
+            &[],
+        );
+
+        let object_file = roc_file.with_extension("o");
+        let rows = dwarf_line_rows(&object_file);
+        let entries = dwarf_entries(&object_file);
+        std::fs::remove_file(&object_file).unwrap();
+
+        // The code of each definition in `main` is attributed to the line of that definition
+        for line in [8, 9] {
+            assert!(
+                rows.contains(&("DebugInfo.roc".to_string(), line)),
+                "no line table row for DebugInfo.roc:{line} in {rows:?}"
+            );
+        }
+        assert!(
+            rows.iter().any(|(file, _)| file == "Dep2.roc"),
+            "no line table row for Dep2.roc in {rows:?}"
+        );
+
+        // Procedures are functions named after their symbol
+        let functions: Vec<&str> = entries
+            .iter()
+            .filter(|entry| entry.tag == gimli::DW_TAG_subprogram)
+            .map(|entry| entry.name.as_str())
+            .collect();
+        assert!(
+            functions.iter().any(|name| name.ends_with(".main")),
+            "no function for main in {functions:?}"
+        );
+        assert!(
+            functions.contains(&"Dep2.str2"),
+            "no function for Dep2.str2 in {functions:?}"
+        );
+
+        // Each `let` in `main` is a local variable of it, with a type that describes its layout
+        for (name, line) in [("greeting", 8), ("excited", 9)] {
+            let variable = entries
+                .iter()
+                .find(|entry| entry.tag == gimli::DW_TAG_variable && entry.name == name)
+                .unwrap_or_else(|| panic!("no variable {name} in {entries:?}"));
+
+            assert!(
+                variable
+                    .function
+                    .as_deref()
+                    .unwrap_or("")
+                    .ends_with(".main"),
+                "{variable:?}"
+            );
+            assert_eq!(variable.line, Some(line), "{variable:?}");
+            assert_eq!(variable.type_name.as_deref(), Some("Str"), "{variable:?}");
+            assert_eq!(
+                variable.type_members,
+                ["bytes", "length", "capacity"],
+                "{variable:?}"
+            );
+        }
+    }
+
+    /// The DWARF debug info of an object file
+    fn load_dwarf(object_path: &Path) -> (gimli::Dwarf<Vec<u8>>, gimli::RunTimeEndian) {
+        use object::{Object, ObjectSection};
+
+        let bytes = std::fs::read(object_path)
+            .unwrap_or_else(|error| panic!("could not read {}: {error}", object_path.display()));
+        let object = object::File::parse(&*bytes).unwrap();
+        let endian = if object.is_little_endian() {
+            gimli::RunTimeEndian::Little
+        } else {
+            gimli::RunTimeEndian::Big
+        };
+
+        let load_section = |id: gimli::SectionId| -> Result<_, gimli::Error> {
+            Ok(object
+                .section_by_name(id.name())
+                .map(|section| relocated_data(&object, &section))
+                .unwrap_or_default())
+        };
+
+        (gimli::Dwarf::load(load_section).unwrap(), endian)
+    }
+
+    /// The data of an object file section, with its relocations applied like the linker would.
+    /// Until then, the references from one debug section into another (like the names in
+    /// `.debug_str`) are all zero in ELF object files.
+    fn relocated_data(object: &object::File, section: &object::Section) -> Vec<u8> {
+        use object::{Object, ObjectSection, ObjectSymbol, RelocationKind, RelocationTarget};
+
+        let mut data = section.uncompressed_data().unwrap().into_owned();
+
+        for (offset, relocation) in section.relocations() {
+            let target = match relocation.target() {
+                RelocationTarget::Symbol(index) => object.symbol_by_index(index).unwrap().address(),
+                RelocationTarget::Section(index) => {
+                    object.section_by_index(index).unwrap().address()
+                }
+                _ => continue,
+            };
+            let size = relocation.size() as usize / 8;
+            if relocation.kind() != RelocationKind::Absolute || !matches!(size, 4 | 8) {
+                continue;
+            }
+
+            let bytes = &mut data[offset as usize..][..size];
+            let read = |bytes: &[u8]| match (object.is_little_endian(), size) {
+                (true, 4) => u32::from_le_bytes(bytes.try_into().unwrap()) as u64,
+                (false, 4) => u32::from_be_bytes(bytes.try_into().unwrap()) as u64,
+                (true, _) => u64::from_le_bytes(bytes.try_into().unwrap()),
+                (false, _) => u64::from_be_bytes(bytes.try_into().unwrap()),
+            };
+            let implicit_addend = if relocation.has_implicit_addend() {
+                read(bytes)
+            } else {
+                0
+            };
+            let value = target
+                .wrapping_add(relocation.addend() as u64)
+                .wrapping_add(implicit_addend);
+
+            match (object.is_little_endian(), size) {
+                (true, 4) => bytes.copy_from_slice(&(value as u32).to_le_bytes()),
+                (false, 4) => bytes.copy_from_slice(&(value as u32).to_be_bytes()),
+                (true, _) => bytes.copy_from_slice(&value.to_le_bytes()),
+                (false, _) => bytes.copy_from_slice(&value.to_be_bytes()),
+            }
+        }
+
+        data
+    }
+
+    /// The file name and line of every row in the DWARF line tables of an object file
+    fn dwarf_line_rows(object_path: &Path) -> Vec<(String, u64)> {
+        let (sections, endian) = load_dwarf(object_path);
+        let dwarf = sections.borrow(|section| gimli::EndianSlice::new(section, endian));
+
+        let mut rows = Vec::new();
+        let mut units = dwarf.units();
+        while let Some(header) = units.next().unwrap() {
+            let unit = dwarf.unit(header).unwrap();
+            let program = match unit.line_program.clone() {
+                Some(program) => program,
+                None => continue,
+            };
+
+            let mut program_rows = program.rows();
+            while let Some((header, row)) = program_rows.next_row().unwrap() {
+                if let (Some(file), Some(line)) = (row.file(header), row.line()) {
+                    let name = dwarf.attr_string(&unit, file.path_name()).unwrap();
+
+                    rows.push((name.to_string_lossy().into_owned(), line.get()));
+                }
+            }
+        }
+
+        rows
+    }
+
+    /// A function or variable in DWARF debug info
+    #[derive(Debug)]
+    struct DwarfEntry {
+        tag: gimli::DwTag,
+        name: String,
+        line: Option<u64>,
+        /// The function a variable is local to
+        function: Option<String>,
+        type_name: Option<String>,
+        type_members: Vec<String>,
+    }
+
+    /// The named functions and variables in the DWARF debug info of an object file
+    fn dwarf_entries(object_path: &Path) -> Vec<DwarfEntry> {
+        type Unit<'d> = gimli::Unit<gimli::EndianSlice<'d, gimli::RunTimeEndian>>;
+        type Dwarf<'d> = gimli::Dwarf<gimli::EndianSlice<'d, gimli::RunTimeEndian>>;
+        type Entry<'a, 'u, 'd> =
+            gimli::DebuggingInformationEntry<'a, 'u, gimli::EndianSlice<'d, gimli::RunTimeEndian>>;
+
+        fn name(dwarf: &Dwarf, unit: &Unit, entry: &Entry) -> Option<String> {
+            let value = entry.attr_value(gimli::DW_AT_name).unwrap()?;
+            let name = dwarf.attr_string(unit, value).unwrap();
+
+            Some(name.to_string_lossy().into_owned())
+        }
+
+        let (sections, endian) = load_dwarf(object_path);
+        let dwarf = sections.borrow(|section| gimli::EndianSlice::new(section, endian));
+
+        let mut found = Vec::new();
+        let mut units = dwarf.units();
+        while let Some(header) = units.next().unwrap() {
+            let unit = dwarf.unit(header).unwrap();
+
+            // The functions that enclose the current entry, with their depth
+            let mut functions: Vec<(isize, String)> = Vec::new();
+            let mut depth = 0;
+            let mut entries = unit.entries();
+            while let Some((delta_depth, entry)) = entries.next_dfs().unwrap() {
+                depth += delta_depth;
+                while functions.last().map_or(false, |(at, _)| *at >= depth) {
+                    functions.pop();
+                }
+
+                let tag = entry.tag();
+                if tag != gimli::DW_TAG_subprogram && tag != gimli::DW_TAG_variable {
+                    continue;
+                }
+                let entry_name = match name(&dwarf, &unit, entry) {
+                    Some(entry_name) => entry_name,
+                    None => continue,
+                };
+
+                let line = entry
+                    .attr_value(gimli::DW_AT_decl_line)
+                    .unwrap()
+                    .and_then(|value| value.udata_value());
+
+                let mut type_name = None;
+                let mut type_members = Vec::new();
+                if let Some(gimli::AttributeValue::UnitRef(offset)) =
+                    entry.attr_value(gimli::DW_AT_type).unwrap()
+                {
+                    let mut tree = unit.entries_tree(Some(offset)).unwrap();
+                    let root = tree.root().unwrap();
+                    type_name = name(&dwarf, &unit, root.entry());
+
+                    let mut children = root.children();
+                    while let Some(child) = children.next().unwrap() {
+                        if child.entry().tag() == gimli::DW_TAG_member {
+                            type_members.extend(name(&dwarf, &unit, child.entry()));
+                        }
+                    }
+                }
+
+                found.push(DwarfEntry {
+                    tag,
+                    name: entry_name.clone(),
+                    line,
+                    function: functions.last().map(|(_, function)| function.clone()),
+                    type_name,
+                    type_members,
+                });
+
+                if tag == gimli::DW_TAG_subprogram {
+                    functions.push((depth, entry_name));
+                }
+            }
+        }
+
+        found
+    }
+
+    #[test]
+    #[serial(multi_dep_str)]
+    #[cfg_attr(windows, ignore)]
+    fn build_multi_dep_str_with_emit_ir() {
+        let emit_dir = tempfile::tempdir().unwrap();
+
+        run_roc_on_failure_is_panic(
+            &fixture_file("multi-dep-str", "Main.roc"),
+            [
+                CMD_BUILD,
+                EMIT_IR_FLAG,
+                "refcount,reset-reuse",
+                EMIT_DIR_FLAG,
+                emit_dir.path().to_str().unwrap(),
+            ],
+            &[],
+            &[],
+            &[],
+        );
+
+        for phase in ["refcount", "reset-reuse"] {
+            let path = emit_dir.path().join(phase).join("Dep2").join("str2.txt");
+            let ir = std::fs::read_to_string(&path)
+                .unwrap_or_else(|error| panic!("could not read {}: {error}", path.display()));
+
+            assert!(ir.contains("procedure Dep2.str2 ():"), "{ir}");
+            // symbols in string literals are left alone
+            assert!(ir.contains("\"I am Dep2.str2\""), "{ir}");
+        }
+
+        assert!(!emit_dir.path().join("specialization").exists());
+    }
+
+    #[test]
+    #[serial(multi_dep_str)]
+    #[cfg_attr(windows, ignore)]
+    fn build_multi_dep_str_with_emit_ir_is_deterministic() {
+        fn emit_ir(emit_dir: &Path) -> Out {
+            run_roc_on(
+                &fixture_file("multi-dep-str", "Main.roc"),
+                [
+                    CMD_BUILD,
+                    EMIT_IR_FLAG,
+                    "all",
+                    EMIT_DIR_FLAG,
+                    emit_dir.to_str().unwrap(),
+                ],
+                &[],
+                &[],
+                &[],
+            )
+        }
+
+        fn read_tree(dir: &Path) -> std::collections::BTreeMap<std::path::PathBuf, Vec<u8>> {
+            let mut files = std::collections::BTreeMap::new();
+            let mut pending = vec![dir.to_path_buf()];
+
+            while let Some(next) = pending.pop() {
+                for entry in std::fs::read_dir(&next).unwrap() {
+                    let path = entry.unwrap().path();
+                    if path.is_dir() {
+                        pending.push(path);
+                    } else {
+                        let contents = std::fs::read(&path).unwrap();
+                        files.insert(path.strip_prefix(dir).unwrap().to_path_buf(), contents);
+                    }
+                }
+            }
+
+            files
+        }
+
+        let first = tempfile::tempdir().unwrap();
+        let second = tempfile::tempdir().unwrap();
+
+        for emit_dir in [&first, &second] {
+            let out = emit_ir(emit_dir.path());
+            assert!(out.status.success(), "{}{}", out.stdout, out.stderr);
+        }
+
+        let first_files = read_tree(first.path());
+        assert!(!first_files.is_empty());
+        assert_eq!(first_files, read_tree(second.path()));
+
+        // Building again replaces our own output, and leaves other files alone
+        let user_file = first.path().join("notes.txt");
+        std::fs::write(&user_file, "mine").unwrap();
+
+        let out = emit_ir(first.path());
+        assert!(out.status.success(), "{}{}", out.stdout, out.stderr);
+
+        let mut rebuilt = read_tree(first.path());
+        assert_eq!(rebuilt.remove(Path::new("notes.txt")).unwrap(), b"mine");
+        assert_eq!(first_files, rebuilt);
+
+        // A phase directory that an earlier build did not write is never cleared
+        let foreign = tempfile::tempdir().unwrap();
+        let foreign_file = foreign.path().join("refcount").join("notes.txt");
+        std::fs::create_dir_all(foreign_file.parent().unwrap()).unwrap();
+        std::fs::write(&foreign_file, "mine").unwrap();
+
+        let out = emit_ir(foreign.path());
+        assert!(!out.status.success(), "{}{}", out.stdout, out.stderr);
+        assert!(out.stdout.contains("did not write"), "{}", out.stdout);
+        assert_eq!(std::fs::read_to_string(&foreign_file).unwrap(), "mine");
+    }
+
+    #[test]
+    #[serial(multi_dep_str)]
+    #[cfg_attr(
+        not(all(
+            target_os = "linux",
+            any(target_arch = "x86_64", target_arch = "aarch64")
+        )),
+        ignore = "the dev backend only supports x86_64 and aarch64 linux"
+    )]
+    #[cfg_attr(
+        not(feature = "disassemble"),
+        ignore = "needs the `disassemble` feature"
+    )]
+    fn build_multi_dep_str_with_emit_asm() {
+        let roc_file = fixture_file("multi-dep-str", "Main.roc");
+        let asm_file = roc_file.with_extension("s");
+
+        run_roc_on_failure_is_panic(
+            &roc_file,
+            [CMD_BUILD, "--dev", EMIT_ASM_FLAG],
+            &[],
+            &[],
+            &[],
+        );
+
+        let asm = std::fs::read_to_string(&asm_file)
+            .unwrap_or_else(|error| panic!("could not read {}: {error}", asm_file.display()));
+        std::fs::remove_file(&asm_file).unwrap();
+
+        assert!(asm.contains("\nroc__mainForHost_1_exposed:\n"), "{asm}");
+        // calls are annotated with the symbol the linker will patch in
+        assert!(asm.contains("  ; "), "{asm}");
+    }
+
+    #[test]
+    #[serial(multi_dep_str)]
+    fn build_multi_dep_str_dev_cross_targets() {
+        use object::{Object, ObjectSymbol};
+
+        let roc_file = fixture_file("multi-dep-str", "Main.roc");
+
+        for (target, extension, format, architecture, main_symbol) in [
+            (
+                "windows-x64",
+                "obj",
+                object::BinaryFormat::Coff,
+                object::Architecture::X86_64,
+                "roc__mainForHost_1_exposed",
+            ),
+            (
+                "macos-arm64",
+                "o",
+                object::BinaryFormat::MachO,
+                object::Architecture::Aarch64,
+                "_roc__mainForHost_1_exposed",
+            ),
+        ] {
+            let target_flag = format!("{TARGET_FLAG}={target}");
+            run_roc_on_failure_is_panic(
+                &roc_file,
+                [CMD_BUILD, "--dev", target_flag.as_str(), NO_LINK_FLAG],
+                &[],
+                &[],
+                &[],
+            );
+
+            let object_path = roc_file.with_extension(extension);
+            let bytes = std::fs::read(&object_path).unwrap_or_else(|error| {
+                panic!("could not read {}: {error}", object_path.display())
+            });
+            std::fs::remove_file(&object_path).unwrap();
+
+            let file = object::File::parse(bytes.as_slice()).unwrap();
+            assert_eq!(file.format(), format, "{target}");
+            assert_eq!(file.architecture(), architecture, "{target}");
+            assert!(
+                file.symbols()
+                    .any(|sym| sym.is_definition() && sym.name() == Ok(main_symbol)),
+                "{target} object does not define {main_symbol}"
+            );
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "wasm32-cli-run")]
+    fn build_benchmark_with_emit_wat() {
+        let roc_file = cli_utils::helpers::cli_testing_dir("benchmarks").join("cFold.roc");
+        let wat_file = roc_file.with_extension("wat");
+
+        run_roc_on_failure_is_panic(
+            &roc_file,
+            [
+                CMD_BUILD,
+                "--dev",
+                concatcp!(TARGET_FLAG, "=wasm32"),
+                EMIT_WAT_FLAG,
+            ],
+            &[],
+            &[],
+            &[],
+        );
+
+        let wat = std::fs::read_to_string(&wat_file)
+            .unwrap_or_else(|error| panic!("could not read {}: {error}", wat_file.display()));
+        std::fs::remove_file(&wat_file).unwrap();
+
+        assert!(wat.starts_with("(module\n"), "{wat}");
+        assert!(wat.contains("  (export \"_start\" (func "), "{wat}");
+        // Roc procs are named after their symbols
+        assert!(wat.contains("  (func $"), "{wat}");
+        assert!(wat.ends_with(")\n"), "{wat}");
+
+        // Emitting the text doesn't change the binary
+        let stdout = crate::run_wasm(&roc_file.with_extension("wasm"), &["3"]);
+        assert!(stdout.ends_with("11 & 11\n"), "{stdout}");
+    }
+
+    #[test]
+    #[serial(multi_dep_str)]
+    #[cfg_attr(windows, ignore)]
+    fn build_multi_dep_str_with_spec_sizes() {
+        let out = run_roc_on_failure_is_panic(
+            &fixture_file("multi-dep-str", "Main.roc"),
+            [CMD_BUILD, SPEC_SIZES_FLAG],
+            &[],
+            &[],
+            &[],
+        );
+        let stdout = strip_colors(&out.stdout);
+
+        assert!(stdout.contains(" specializations of "), "{stdout}");
+        assert!(
+            stdout.contains("  Main.main (1 specialization)\n"),
+            "{stdout}"
+        );
+        // `Dep1.str1` is inlined into `main`, after which nothing calls it
+        assert!(!stdout.contains("Dep1.str1"), "{stdout}");
+        assert!(
+            stdout.contains("  Dep2.str2 (1 specialization)\n"),
+            "{stdout}"
+        );
+    }
+
+    fn roc_deps(roc_file: &Path, flags: &[&str]) -> String {
+        let out = run_roc(
+            [CMD_DEPS, roc_file.to_str().unwrap()].iter().chain(flags),
+            &[],
+            &[],
+        );
+        let stdout = strip_colors(&out.stdout);
+
+        assert!(out.status.success(), "{stdout}{}", out.stderr);
+
+        stdout
+    }
+
+    #[test]
+    fn deps_text() {
+        let stdout = roc_deps(&fixture_file("multi-dep-str", "Main.roc"), &[LINES_FLAG]);
+
+        assert!(stdout.starts_with("Main [7 lines]\n"), "{stdout}");
+        assert!(stdout.contains("── Dep1 [4 lines]\n"), "{stdout}");
+        assert!(stdout.contains("└── Dep2 [4 lines]\n"), "{stdout}");
+        assert!(!stdout.contains("cycle"), "{stdout}");
+    }
+
+    #[test]
+    fn deps_dot() {
+        let stdout = roc_deps(
+            &fixture_file("multi-dep-str", "Main.roc"),
+            &[GRAPH_FORMAT_FLAG, "dot"],
+        );
+
+        assert!(stdout.starts_with("digraph modules {\n"), "{stdout}");
+        assert!(stdout.contains("    \"Main\" -> \"Dep1\";\n"), "{stdout}");
+        assert!(stdout.contains("    \"Dep1\" -> \"Dep2\";\n"), "{stdout}");
+        assert!(stdout.ends_with("}\n"), "{stdout}");
+    }
+
+    #[test]
+    fn deps_json() {
+        let stdout = roc_deps(
+            &fixture_file("multi-dep-str", "Main.roc"),
+            &[GRAPH_FORMAT_FLAG, "json"],
+        );
+        let json: serde_json::Value = serde_json::from_str(&stdout).unwrap();
+
+        assert_eq!(json["root"], "Main");
+
+        let module = |name: &str| {
+            json["modules"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .find(|module| module["name"] == name)
+                .unwrap_or_else(|| panic!("{name} is not in {stdout}"))
+                .clone()
+        };
+
+        assert_eq!(module("Dep1")["imports"], serde_json::json!(["Dep2"]));
+        assert_eq!(module("Dep2")["imports"], serde_json::json!([]));
+        assert_eq!(module("Dep2")["lines"], 4);
+        assert_eq!(json["cycles"]["modules"], serde_json::json!([]));
+    }
+
+    #[test]
+    fn deps_load_error() {
+        let out = run_roc([CMD_DEPS, "does-not-exist.roc"], &[], &[]);
+
+        // Reported, not a compiler crash
+        assert_eq!(out.status.code(), Some(1), "{}", out.stderr);
+    }
+
+    #[test]
+    fn check_perf_hints() {
+        let roc_file = fixture_file("perf-hints", "Main.roc");
+        let out = run_roc(
+            [CMD_CHECK, roc_file.to_str().unwrap(), PERF_HINTS_FLAG],
+            &[],
+            &[],
+        );
+        let stdout = strip_colors(&out.stdout);
+
+        assert!(out.status.success(), "{stdout}");
+        // `original` is used after `List.set`, so `List.set` has to copy it
+        assert!(stdout.contains("── COPIED FOR UPDATE in"), "{stdout}");
+        assert!(
+            stdout.contains("This passes original to List.set"),
+            "{stdout}"
+        );
+    }
+
+    #[test]
+    #[cfg_attr(windows, ignore)]
+    fn build_with_instrument_memory() {
+        let roc_file = fixture_file("instrument-memory", "Main.roc");
+
+        // The host is a Rust crate with the `instrument-memory` feature of roc_std
+        run_roc_on_failure_is_panic(
+            &roc_file,
+            [CMD_BUILD, INSTRUMENT_MEMORY_FLAG, LINKER_FLAG, "legacy"],
+            &[],
+            &[],
+            &[],
+        );
+
+        let out = run_cmd(roc_file.with_extension("").to_str().unwrap(), [], &[], []);
+        assert!(out.status.success(), "{}", out.stderr);
+        assert_eq!(
+            out.stdout,
+            "1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20"
+        );
+
+        assert!(out.stderr.contains("── MEMORY REPORT ──"), "{}", out.stderr);
+        // Sites in the app and platform are described by their source location
+        let fixture_dir = roc_file.parent().unwrap().to_str().unwrap();
+        assert!(out.stderr.contains(fixture_dir), "{}", out.stderr);
+        // The host dropped the string it got back, and that is reported too
+        assert!(
+            out.stderr.contains("All allocations were freed."),
+            "{}",
+            out.stderr
+        );
+    }
+
     #[test]
     #[serial(multi_dep_thunk)]
     #[cfg_attr(windows, ignore)]
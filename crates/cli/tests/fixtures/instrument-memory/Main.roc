@@ -0,0 +1,10 @@
+app "instrument-memory"
+    packages { pf: "platform/main.roc" }
+    imports []
+    provides [main] to pf
+
+main : Str
+main =
+    List.range { start: At 1, end: At 20 }
+    |> List.map Num.toStr
+    |> Str.joinWith ", "
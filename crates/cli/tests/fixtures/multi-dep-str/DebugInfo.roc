@@ -0,0 +1,11 @@
+app "debug-info"
+    packages { pf: "platform/main.roc" }
+    imports [Dep1]
+    provides [main] to pf
+
+main : Str
+main =
+    greeting = Dep1.str1
+    excited = Str.concat greeting "!"
+
+    excited
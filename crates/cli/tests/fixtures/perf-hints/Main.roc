@@ -0,0 +1,11 @@
+app "perf-hints"
+    packages { pf: "../multi-dep-str/platform/main.roc" }
+    imports []
+    provides [main] to pf
+
+main : Str
+main =
+    original = List.repeat 1u8 3
+    updated = List.set original 0 42
+
+    Num.toStr (List.len original + List.len updated)
@@ -12,6 +12,7 @@ use std::io;
 use std::path::{Path, PathBuf};
 use std::process::{self, Child, Command};
 use std::{env, fs};
+use strum::IntoEnumIterator;
 use wasi_libc_sys::{WASI_COMPILER_RT_PATH, WASI_LIBC_PATH};
 
 pub use roc_linker::LinkType;
@@ -44,20 +45,152 @@ pub fn link(
 
 /// Same format as the precompiled host filename, except with a file extension like ".o" or ".obj"
 pub fn legacy_host_file(target: Target, platform_main_roc: &Path) -> PathBuf {
-    let lib_ext = target.static_library_file_ext();
+    let files = PrebuiltHostFiles::new(target, platform_main_roc);
 
-    let file_name = roc_linker::preprocessed_host_filename(target)
-        .replace(roc_linker::PRECOMPILED_HOST_EXT, lib_ext);
+    match files.legacy_host() {
+        Some(path) => path.to_path_buf(),
+        None => files.object,
+    }
+}
 
-    let lib_path = platform_main_roc.with_file_name(file_name);
-    if lib_path.exists() {
-        lib_path
-    } else {
-        let obj_ext = target.object_file_ext();
-        lib_path.with_extension(obj_ext)
+/// The host files a platform can ship prebuilt for one target. They live beside the platform's
+/// main.roc, named after the target, e.g. `linux-x64.rh` or `macos-arm64.a`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PrebuiltHostFiles {
+    pub target: Target,
+    /// The preprocessed host used by the surgical linker, e.g. `linux-x64.rh`
+    pub preprocessed_host: PathBuf,
+    /// The metadata the surgical linker needs next to the preprocessed host, e.g. `metadata_linux-x64.rm`
+    pub metadata: PathBuf,
+    /// The host as used by the legacy linker, e.g. `linux-x64.a`
+    pub static_library: PathBuf,
+    /// The legacy linker also accepts a plain object file, e.g. `linux-x64.o`
+    pub object: PathBuf,
+}
+
+impl PrebuiltHostFiles {
+    pub fn new(target: Target, platform_main_roc: &Path) -> Self {
+        let file_name = |ext: &str| {
+            let name = roc_linker::preprocessed_host_filename(target)
+                .replace(roc_linker::PRECOMPILED_HOST_EXT, ext);
+
+            platform_main_roc.with_file_name(name)
+        };
+
+        Self {
+            target,
+            preprocessed_host: platform_main_roc
+                .with_file_name(roc_linker::preprocessed_host_filename(target)),
+            metadata: platform_main_roc.with_file_name(roc_linker::metadata_file_name(target)),
+            static_library: file_name(target.static_library_file_ext()),
+            object: file_name(target.object_file_ext()),
+        }
+    }
+
+    pub fn surgical_host(&self) -> Option<&Path> {
+        if self.preprocessed_host.exists() && self.metadata.exists() {
+            Some(&self.preprocessed_host)
+        } else {
+            None
+        }
+    }
+
+    /// The static library if there is one, and otherwise the object file.
+    pub fn legacy_host(&self) -> Option<&Path> {
+        [&self.static_library, &self.object]
+            .into_iter()
+            .find(|path| path.exists())
+            .map(PathBuf::as_path)
+    }
+
+    /// Whether the platform ships any kind of host for this target.
+    pub fn any_exist(&self) -> bool {
+        self.preprocessed_host.exists() || self.legacy_host().is_some()
     }
 }
 
+/// A prebuilt platform has no host that can be used for a target.
+#[derive(Debug, Clone)]
+pub struct MissingPrebuiltHost {
+    pub target: Target,
+    /// The strategy that was asked for, before any fallback
+    pub linking_strategy: LinkingStrategy,
+    /// Each of these would have been needed, and does not exist
+    pub missing: Vec<PathBuf>,
+    /// The targets that the platform does have a host for
+    pub available_targets: Vec<Target>,
+}
+
+/// Find the prebuilt host to link against, beside the platform's main.roc.
+///
+/// If the surgical linker was asked for but the platform only ships a host for the legacy
+/// linker, this falls back to the legacy linker.
+pub fn select_prebuilt_host(
+    target: Target,
+    platform_main_roc: &Path,
+    linking_strategy: LinkingStrategy,
+) -> Result<(LinkingStrategy, PathBuf), MissingPrebuiltHost> {
+    let files = PrebuiltHostFiles::new(target, platform_main_roc);
+
+    let missing = match linking_strategy {
+        LinkingStrategy::Additive => {
+            if files.preprocessed_host.exists() {
+                return Ok((linking_strategy, files.preprocessed_host));
+            }
+
+            vec![files.preprocessed_host.clone()]
+        }
+        LinkingStrategy::Surgical => {
+            if let Some(host) = files.surgical_host() {
+                return Ok((LinkingStrategy::Surgical, host.to_path_buf()));
+            }
+
+            if let Some(host) = files.legacy_host() {
+                return Ok((LinkingStrategy::Legacy, host.to_path_buf()));
+            }
+
+            vec![
+                files.preprocessed_host.clone(),
+                files.metadata.clone(),
+                files.static_library.clone(),
+                files.object.clone(),
+            ]
+            .into_iter()
+            .filter(|path| !path.exists())
+            .collect()
+        }
+        LinkingStrategy::Legacy if target == Target::Wasm32 => {
+            // when compiling a wasm application, we implicitly assume here that the host is in zig
+            // and has a file called "host.zig"
+            let host = platform_main_roc.with_file_name("host.zig");
+
+            if host.exists() {
+                return Ok((linking_strategy, host));
+            }
+
+            vec![host]
+        }
+        LinkingStrategy::Legacy => {
+            if let Some(host) = files.legacy_host() {
+                return Ok((linking_strategy, host.to_path_buf()));
+            }
+
+            vec![files.static_library.clone(), files.object.clone()]
+        }
+    };
+
+    let available_targets = Target::iter()
+        .filter(|other| PrebuiltHostFiles::new(*other, platform_main_roc).any_exist())
+        .collect();
+
+    Err(MissingPrebuiltHost {
+        target,
+        linking_strategy,
+        missing,
+        available_targets,
+    })
+}
+
 // Attempts to find a file that is stored relative to the roc executable.
 // Since roc is built in target/debug/roc, we may need to drop that path to find the file.
 // This is used to avoid depending on the current working directory.
@@ -1259,6 +1392,44 @@ fn link_windows(
     }
 }
 
+/// Link an object file produced by the dev backend, together with the builtins, into a dylib
+pub fn dev_object_to_dylib(object_bytes: &[u8], target: Target) -> Result<Library, Error> {
+    let dir = tempfile::tempdir().unwrap();
+    let app_o_file = dir.path().join("app.o");
+
+    fs::write(&app_o_file, object_bytes).expect("Writing .o file failed");
+
+    let builtins_host_tempfile =
+        roc_bitcode::host_tempfile().expect("failed to write host builtins object to tempfile");
+
+    // Link app.o and the builtins into a dylib - e.g. app.so or app.dylib
+    let (mut child, dylib_path) = link(
+        target,
+        app_o_file.clone(),
+        &[
+            app_o_file.to_str().unwrap(),
+            builtins_host_tempfile.path().to_str().unwrap(),
+        ],
+        LinkType::Dylib,
+    )
+    .unwrap();
+
+    let exit_status = child.wait().unwrap();
+
+    assert!(
+        exit_status.success(),
+        "\n___________\nLinking command failed with status {exit_status:?}:\n\n  {child:?}\n___________\n"
+    );
+
+    // Extend the lifetime of the tempfile so it doesn't get dropped
+    // (and thus deleted) before the linking process is done using it!
+    let _ = builtins_host_tempfile;
+
+    let path = dylib_path.as_path().to_str().unwrap();
+
+    unsafe { Library::new(path) }
+}
+
 pub fn llvm_module_to_dylib(
     module: &inkwell::module::Module,
     target: Target,
@@ -1,23 +1,27 @@
 use crate::link::{
-    legacy_host_file, link, preprocess_host_wasm32, rebuild_host, LinkType, LinkingStrategy,
+    legacy_host_file, link, preprocess_host_wasm32, rebuild_host, select_prebuilt_host, LinkType,
+    LinkingStrategy, MissingPrebuiltHost,
 };
 use bumpalo::Bump;
 use inkwell::memory_buffer::MemoryBuffer;
 use roc_error_macros::internal_error;
-use roc_gen_dev::AssemblyBackendMode;
+use roc_gen_dev::{AssemblyBackendMode, RegisterAllocation};
 use roc_gen_llvm::llvm::build::{module_from_builtins, LlvmBackendMode};
 use roc_gen_llvm::llvm::externs::add_default_roc_externs;
 use roc_load::{
     EntryPoint, ExecutionMode, ExpectMetadata, FunctionKind, LoadConfig, LoadMonomorphizedError,
-    LoadedModule, LoadingProblem, MonomorphizedModule, Threading,
+    LoadedModule, LoadingProblem, MonomorphizedModule, OutputConfig, Threading,
 };
 use roc_mono::ir::{OptLevel, SingleEntryPoint};
+use roc_mono::layout::{InLayout, STLayoutInterner};
 use roc_packaging::cache::RocCacheDir;
+use roc_region::all::LineInfo;
 use roc_reporting::{
     cli::{report_problems, Problems},
     report::{RenderTarget, DEFAULT_PALETTE},
 };
 use roc_target::{Architecture, Target};
+use roc_tracing::profile;
 use std::ffi::OsStr;
 use std::ops::Deref;
 use std::{
@@ -26,8 +30,7 @@ use std::{
     time::{Duration, Instant},
 };
 
-#[cfg(feature = "target-wasm32")]
-use roc_collections::all::MutSet;
+use roc_collections::all::{MutMap, MutSet};
 
 pub const DEFAULT_ROC_FILENAME: &str = "main.roc";
 
@@ -56,6 +59,199 @@ pub fn report_problems_typechecked(loaded: &mut LoadedModule) -> Problems {
     )
 }
 
+/// Print where the app's values get copied, or their memory could not be reused
+/// (`roc check --perf-hints`). Returns the number of hints.
+pub fn report_perf_hints(loaded: &MonomorphizedModule) -> usize {
+    use roc_module::symbol::Symbol;
+    use roc_mono::perf_hints::{find_perf_hints, Callee, PerfHintKind};
+    use roc_problem::Severity;
+    use roc_reporting::report::{Annotation, Report, RocDocAllocator};
+    use ven_pretty::DocAllocator;
+
+    let hints = find_perf_hints(
+        &loaded.layout_interner,
+        &loaded.procedures,
+        &loaded.definition_regions,
+    );
+    let palette = DEFAULT_PALETTE;
+
+    for (home, (module_path, src)) in loaded.sources.iter() {
+        let src_lines: Vec<&str> = src.split('\n').collect();
+        let lines = LineInfo::new(src);
+        let alloc = RocDocAllocator::new(&src_lines, *home, &loaded.interns);
+
+        // Symbols made up by the compiler are just numbers
+        let value_doc = |value: Symbol| {
+            if value
+                .as_str(&loaded.interns)
+                .starts_with(|c: char| c.is_ascii_digit())
+            {
+                alloc.reflow("a value")
+            } else {
+                alloc.symbol_unqualified(value)
+            }
+        };
+
+        for hint in hints.iter().filter(|hint| hint.module == *home) {
+            let region = alloc.region(lines.convert_region(hint.region));
+
+            let (title, doc) = match hint.kind {
+                PerfHintKind::CopiedForUpdate { value, callee } => {
+                    let callee = match callee {
+                        Callee::Proc(symbol) if symbol.is_builtin() => {
+                            alloc.symbol_qualified(symbol)
+                        }
+                        Callee::Proc(symbol) => alloc.symbol_foreign_qualified(symbol),
+                        Callee::LowLevel(name) => alloc.text(name).annotate(Annotation::Symbol),
+                    };
+
+                    let doc = alloc.stack([
+                        alloc.concat([
+                            alloc.reflow("This passes "),
+                            value_doc(value),
+                            alloc.reflow(" to "),
+                            callee,
+                            alloc.reflow(", which could update it in place:"),
+                        ]),
+                        region,
+                        alloc.reflow(
+                            "It is used again afterwards, so it is shared at this point and has to be copied instead.",
+                        ),
+                    ]);
+
+                    ("COPIED FOR UPDATE", doc)
+                }
+                PerfHintKind::Cloned { value } => {
+                    let doc = alloc.stack([
+                        alloc.concat([
+                            alloc.reflow("This clones "),
+                            value_doc(value),
+                            alloc.reflow(":"),
+                        ]),
+                        region,
+                    ]);
+
+                    ("CLONED", doc)
+                }
+                PerfHintKind::Reused => {
+                    let doc = alloc.stack([
+                        alloc.reflow("This builds a tag union value in the memory of one that was taken apart before:"),
+                        region,
+                        alloc.reflow("If that value is unique at runtime, no allocation is needed."),
+                    ]);
+
+                    ("REUSED ALLOCATION", doc)
+                }
+                PerfHintKind::NotReused => {
+                    let doc = alloc.stack([
+                        alloc.reflow("This allocates a tag union value:"),
+                        region,
+                        alloc.reflow("A value with the same layout was dropped before this, but its memory could not be reused here."),
+                    ]);
+
+                    ("MISSED REUSE", doc)
+                }
+            };
+
+            let report = Report {
+                title: title.to_string(),
+                filename: module_path.clone(),
+                doc,
+                severity: Severity::Warning,
+            };
+
+            let mut buf = String::new();
+            report.render_color_terminal(&mut buf, &alloc, &palette);
+            println!("\n{buf}\n");
+        }
+    }
+
+    hints.len()
+}
+
+/// Print how big every specialization of every proc is, biggest procs first
+/// (`roc build --spec-sizes`). Sizes are measured in mono IR statements, so they mean the same
+/// for every backend.
+pub fn report_specialization_sizes(loaded: &MonomorphizedModule) {
+    use roc_module::symbol::Symbol;
+    use roc_mono::inline::proc_size;
+    use roc_mono::ir::ProcLayout;
+    use roc_mono::layout::LayoutInterner;
+    use ven_pretty::BoxAllocator;
+
+    let interner = &loaded.layout_interner;
+    let layout_to_string = |layout: InLayout| {
+        let mut w = Vec::new();
+        interner
+            .to_doc_top::<_, ()>(layout, &BoxAllocator)
+            .1
+            .render(200, &mut w)
+            .unwrap();
+
+        String::from_utf8(w).unwrap()
+    };
+    let signature = |proc_layout: &ProcLayout| {
+        let arguments: Vec<_> = proc_layout
+            .arguments
+            .iter()
+            .map(|argument| layout_to_string(*argument))
+            .collect();
+
+        format!(
+            "{} -> {}",
+            arguments.join(", "),
+            layout_to_string(proc_layout.result)
+        )
+    };
+
+    let mut by_symbol: MutMap<Symbol, Vec<(usize, String)>> = MutMap::default();
+    for ((symbol, proc_layout), proc) in loaded.procedures.iter() {
+        by_symbol
+            .entry(*symbol)
+            .or_default()
+            .push((proc_size(proc), signature(proc_layout)));
+    }
+
+    let mut procs: Vec<(usize, String, Vec<(usize, String)>)> = by_symbol
+        .into_iter()
+        .map(|(symbol, mut specializations)| {
+            specializations.sort_by(|a, b| b.cmp(a));
+
+            let total = specializations.iter().map(|(size, _)| size).sum();
+            let name = format!(
+                "{}.{}",
+                loaded.interns.module_name(symbol.module_id()).as_str(),
+                symbol.as_str(&loaded.interns)
+            );
+
+            (total, name, specializations)
+        })
+        .collect();
+    procs.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.cmp(&b.1)));
+
+    let total: usize = procs.iter().map(|(size, _, _)| size).sum();
+    let count: usize = procs.iter().map(|(_, _, specs)| specs.len()).sum();
+
+    println!(
+        "{count} specializations of {} procs, {total} statements in total\n",
+        procs.len()
+    );
+
+    for (size, name, specializations) in procs {
+        println!(
+            "{size:>8}  {name} ({} specialization{})",
+            specializations.len(),
+            if specializations.len() == 1 { "" } else { "s" }
+        );
+
+        for (size, signature) in specializations {
+            println!("{size:>16}  {signature}");
+        }
+    }
+
+    println!();
+}
+
 pub enum CodeObject {
     MemoryBuffer(MemoryBuffer),
     Vector(Vec<u8>),
@@ -84,8 +280,21 @@ pub struct CodeGenOptions {
     pub backend: CodeGenBackend,
     pub opt_level: OptLevel,
     pub emit_debug_info: bool,
+    /// Describe Roc source lines, functions and variables in the debug info (`--debug`).
+    /// The Wasm dev backend emits a source map instead. The other dev backends don't support this.
+    pub emit_source_debug_info: bool,
     pub emit_llvm_ir: bool,
+    /// Write a disassembly of the dev backend's machine code to a `.s` file (`--emit-asm`)
+    pub emit_asm: bool,
+    /// Write the Wasm dev backend's output in the text format to a `.wat` file (`--emit-wat`)
+    pub emit_wat: bool,
     pub fuzz: bool,
+    /// Print the size of every specialization before generating code (`--spec-sizes`)
+    pub report_spec_sizes: bool,
+    /// Report allocations and refcount changes to the host's `roc_memory_on_*` hooks
+    /// (`--instrument-memory`). Only the LLVM backend supports this.
+    pub instrument_memory: bool,
+    pub register_allocation: RegisterAllocation,
 }
 
 type GenFromMono<'a> = (CodeObject, CodeGenTiming, ExpectMetadata<'a>);
@@ -98,6 +307,7 @@ pub fn gen_from_mono_module<'a>(
     target: Target,
     code_gen_options: CodeGenOptions,
     preprocessed_host_path: &Path,
+    output_exe_path: &Path,
     wasm_dev_stack_bytes: Option<u32>,
 ) -> GenFromMono<'a> {
     let path = roc_file_path;
@@ -110,29 +320,36 @@ pub fn gen_from_mono_module<'a>(
         CodeGenBackend::Wasm => gen_from_mono_module_dev(
             arena,
             loaded,
+            path,
             target,
             preprocessed_host_path,
             wasm_dev_stack_bytes,
             AssemblyBackendMode::Binary, // dummy value, unused in practice
+            code_gen_options,
         ),
         CodeGenBackend::Assembly(backend_mode) => gen_from_mono_module_dev(
             arena,
             loaded,
+            path,
             target,
             preprocessed_host_path,
             wasm_dev_stack_bytes,
             backend_mode,
+            code_gen_options,
         ),
         CodeGenBackend::Llvm(backend_mode) => gen_from_mono_module_llvm(
             arena,
             loaded,
             path,
+            output_exe_path,
             target,
             opt,
             backend_mode,
             debug,
+            code_gen_options.emit_source_debug_info,
             emit_llvm_ir,
             fuzz,
+            code_gen_options.instrument_memory,
         ),
     }
 }
@@ -145,12 +362,15 @@ fn gen_from_mono_module_llvm<'a>(
     arena: &'a bumpalo::Bump,
     loaded: MonomorphizedModule<'a>,
     roc_file_path: &Path,
+    output_exe_path: &Path,
     target: Target,
     opt_level: OptLevel,
     backend_mode: LlvmBackendMode,
     emit_debug_info: bool,
+    emit_source_debug_info: bool,
     emit_llvm_ir: bool,
     fuzz: bool,
+    instrument_memory: bool,
 ) -> GenFromMono<'a> {
     use crate::target::{self, convert_opt_level};
     use inkwell::attributes::{Attribute, AttributeLoc};
@@ -199,6 +419,37 @@ fn gen_from_mono_module_llvm<'a>(
     let (dibuilder, compile_unit) = roc_gen_llvm::llvm::build::Env::new_debug_info(module);
     let (mpm, _fpm) = roc_gen_llvm::llvm::build::construct_optimization_passes(module, opt_level);
 
+    let source_files = || -> MutMap<_, _> {
+        loaded
+            .sources
+            .iter()
+            .map(|(module_id, (path, src))| {
+                let path: &Path = arena.alloc(path.clone());
+                (*module_id, (path, LineInfo::new(src)))
+            })
+            .collect()
+    };
+
+    let instrument_memory = if instrument_memory {
+        Some(roc_gen_llvm::llvm::instrument::MemoryInstrumentation {
+            files: source_files(),
+            definition_regions: loaded.definition_regions.clone(),
+        })
+    } else {
+        None
+    };
+
+    let debug_sources = if emit_source_debug_info {
+        Some(roc_gen_llvm::llvm::debug_info::DebugSources {
+            files: source_files(),
+            definition_regions: loaded.definition_regions,
+            current_module: Default::default(),
+            named_layouts: Default::default(),
+        })
+    } else {
+        None
+    };
+
     // Compile and add all the Procs before adding main
     let env = roc_gen_llvm::llvm::build::Env {
         arena,
@@ -217,6 +468,8 @@ fn gen_from_mono_module_llvm<'a>(
             .keys()
             .copied()
             .collect(),
+        debug_sources,
+        instrument_memory,
     };
 
     // does not add any externs for this mode (we have a host) but cleans up some functions around
@@ -237,6 +490,8 @@ fn gen_from_mono_module_llvm<'a>(
         EntryPoint::Test => roc_mono::ir::EntryPoint::Expects { symbols: &[] },
     };
 
+    let generate_ir_span = profile::span("codegen", "llvm: generate IR");
+
     roc_gen_llvm::llvm::build::build_procedures(
         &env,
         &loaded.layout_interner,
@@ -248,13 +503,25 @@ fn gen_from_mono_module_llvm<'a>(
         &loaded.glue_layouts,
     );
 
+    roc_gen_llvm::llvm::instrument::wrap_allocators(&env);
+
     // We are now finished building the LLVM IR.
+    drop(generate_ir_span);
     let generate_final_ir = all_code_gen_start.elapsed();
     let code_gen_object_start = Instant::now();
+    let code_gen_object_span = profile::span("codegen", "llvm: optimize and emit object");
 
     env.dibuilder.finalize();
 
-    if !emit_debug_info {
+    if let Some(debug_sources) = &env.debug_sources {
+        write_pretty_printers(
+            &loaded.layout_interner,
+            &debug_sources.named_layouts.borrow(),
+            output_exe_path,
+        );
+    }
+
+    if !(emit_debug_info || emit_source_debug_info) {
         module.strip_debug_info();
     }
 
@@ -407,6 +674,7 @@ fn gen_from_mono_module_llvm<'a>(
         }
     };
 
+    drop(code_gen_object_span);
     let code_gen_object = code_gen_object_start.elapsed();
     let total = all_code_gen_start.elapsed();
 
@@ -425,42 +693,102 @@ fn gen_from_mono_module_llvm<'a>(
     )
 }
 
+/// Write gdb and lldb pretty printers for the Roc types in the debug info next to the binary,
+/// as `<binary>-gdb.py` (which gdb auto-loads) and `<binary>_lldb.py`.
+fn write_pretty_printers<'a>(
+    layout_interner: &STLayoutInterner<'a>,
+    named_layouts: &MutSet<InLayout<'a>>,
+    output_exe_path: &Path,
+) {
+    use roc_gen_llvm::llvm::pretty_printers;
+
+    let mut layouts: Vec<_> = named_layouts.iter().copied().collect();
+    layouts.sort_by_key(|layout| layout.index());
+
+    let binary = output_exe_path
+        .file_name()
+        .unwrap_or_default()
+        .to_string_lossy();
+    let scripts = [
+        (
+            format!("{binary}-gdb.py"),
+            pretty_printers::gdb_script(layout_interner, &layouts),
+        ),
+        (
+            pretty_printers::lldb_script_file_name(&binary),
+            pretty_printers::lldb_script(layout_interner, &layouts),
+        ),
+    ];
+
+    for (file_name, script) in scripts {
+        let path = output_exe_path.with_file_name(file_name);
+
+        if let Err(error) = std::fs::write(&path, script) {
+            eprintln!(
+                "Warning: could not write the pretty printers to {}: {error}",
+                path.display()
+            );
+        }
+    }
+}
+
 #[cfg(feature = "target-wasm32")]
+#[allow(clippy::too_many_arguments)]
 fn gen_from_mono_module_dev<'a>(
     arena: &'a bumpalo::Bump,
     loaded: MonomorphizedModule<'a>,
+    roc_file_path: &Path,
     target: Target,
     preprocessed_host_path: &Path,
     wasm_dev_stack_bytes: Option<u32>,
     backend_mode: AssemblyBackendMode,
+    code_gen_options: CodeGenOptions,
 ) -> GenFromMono<'a> {
     match target.architecture() {
         Architecture::Wasm32 => gen_from_mono_module_dev_wasm32(
             arena,
             loaded,
+            roc_file_path,
             preprocessed_host_path,
             wasm_dev_stack_bytes,
+            code_gen_options.emit_source_debug_info,
+            code_gen_options.emit_wat,
+        ),
+        Architecture::X86_64 | Architecture::Aarch64 => gen_from_mono_module_dev_assembly(
+            arena,
+            loaded,
+            roc_file_path,
+            target,
+            backend_mode,
+            code_gen_options.register_allocation,
+            code_gen_options.emit_asm,
         ),
-        Architecture::X86_64 | Architecture::Aarch64 => {
-            gen_from_mono_module_dev_assembly(arena, loaded, target, backend_mode)
-        }
         _ => todo!(),
     }
 }
 
 #[cfg(not(feature = "target-wasm32"))]
+#[allow(clippy::too_many_arguments)]
 pub fn gen_from_mono_module_dev<'a>(
     arena: &'a bumpalo::Bump,
     loaded: MonomorphizedModule<'a>,
+    roc_file_path: &Path,
     target: Target,
     _host_input_path: &Path,
     _wasm_dev_stack_bytes: Option<u32>,
     backend_mode: AssemblyBackendMode,
+    code_gen_options: CodeGenOptions,
 ) -> GenFromMono<'a> {
     match target.architecture() {
-        Architecture::X86_64 | Architecture::Aarch64 => {
-            gen_from_mono_module_dev_assembly(arena, loaded, target, backend_mode)
-        }
+        Architecture::X86_64 | Architecture::Aarch64 => gen_from_mono_module_dev_assembly(
+            arena,
+            loaded,
+            roc_file_path,
+            target,
+            backend_mode,
+            code_gen_options.register_allocation,
+            code_gen_options.emit_asm,
+        ),
         _ => todo!(),
     }
 }
@@ -469,8 +797,11 @@ pub fn gen_from_mono_module_dev<'a>(
 fn gen_from_mono_module_dev_wasm32<'a>(
     arena: &'a bumpalo::Bump,
     loaded: MonomorphizedModule<'a>,
+    roc_file_path: &Path,
     preprocessed_host_path: &Path,
     wasm_dev_stack_bytes: Option<u32>,
+    emit_source_map: bool,
+    emit_wat: bool,
 ) -> GenFromMono<'a> {
     let all_code_gen_start = Instant::now();
     let MonomorphizedModule {
@@ -478,6 +809,7 @@ fn gen_from_mono_module_dev_wasm32<'a>(
         procedures,
         mut interns,
         mut layout_interner,
+        definition_regions,
         ..
     } = loaded;
 
@@ -488,11 +820,30 @@ fn gen_from_mono_module_dev_wasm32<'a>(
         .copied()
         .collect::<MutSet<_>>();
 
+    let debug_sources = if emit_source_map {
+        let files = loaded
+            .sources
+            .iter()
+            .map(|(module_id, (path, src))| {
+                let path = arena.alloc_str(&path.to_string_lossy());
+                let src = arena.alloc_str(src);
+                (*module_id, (&*path, &*src))
+            })
+            .collect();
+        Some(roc_gen_wasm::DebugSources {
+            files,
+            definition_regions,
+        })
+    } else {
+        None
+    };
+
     let env = roc_gen_wasm::Env {
         arena,
         module_id,
         exposed_to_host,
         stack_bytes: wasm_dev_stack_bytes.unwrap_or(roc_gen_wasm::Env::DEFAULT_STACK_BYTES),
+        debug_sources,
     };
 
     let host_bytes = std::fs::read(preprocessed_host_path).unwrap_or_else(|_| {
@@ -511,6 +862,8 @@ fn gen_from_mono_module_dev_wasm32<'a>(
         )
     });
 
+    let build_span = profile::span("codegen", "wasm: build app binary");
+
     let final_binary_bytes = roc_gen_wasm::build_app_binary(
         &env,
         &mut layout_interner,
@@ -519,6 +872,22 @@ fn gen_from_mono_module_dev_wasm32<'a>(
         procedures,
     );
 
+    drop(build_span);
+
+    if emit_wat {
+        let app_wat_file = roc_file_path.with_extension("wat");
+        eprintln!("Emitting WebAssembly text to {}", app_wat_file.display());
+
+        let wat = roc_gen_wasm::binary_to_wat(arena, &final_binary_bytes).unwrap_or_else(|e| {
+            internal_error!(
+                "I could not print the Wasm binary as text, at offset 0x{:x}:\n{}",
+                e.offset,
+                e.message
+            )
+        });
+        std::fs::write(app_wat_file, wat).unwrap();
+    }
+
     let generate_final_ir = all_code_gen_start.elapsed();
     let code_gen_object_start = Instant::now();
     let code_gen_object = code_gen_object_start.elapsed();
@@ -542,8 +911,11 @@ fn gen_from_mono_module_dev_wasm32<'a>(
 fn gen_from_mono_module_dev_assembly<'a>(
     arena: &'a bumpalo::Bump,
     loaded: MonomorphizedModule<'a>,
+    roc_file_path: &Path,
     target: Target,
     backend_mode: AssemblyBackendMode,
+    register_allocation: RegisterAllocation,
+    emit_asm: bool,
 ) -> GenFromMono<'a> {
     let all_code_gen_start = Instant::now();
 
@@ -564,17 +936,26 @@ fn gen_from_mono_module_dev_assembly<'a>(
         exposed_to_host: exposed_to_host.top_level_values.keys().copied().collect(),
         lazy_literals,
         mode: backend_mode,
+        register_allocation,
     };
 
+    let build_span = profile::span("codegen", "dev: build module");
     let module_object =
         roc_gen_dev::build_module(&env, &mut interns, &mut layout_interner, target, procedures);
+    drop(build_span);
 
     let generate_final_ir = all_code_gen_start.elapsed();
     let code_gen_object_start = Instant::now();
 
+    let write_span = profile::span("codegen", "dev: write object");
     let module_out = module_object
         .write()
         .expect("failed to build output object");
+    drop(write_span);
+
+    if emit_asm {
+        write_app_asm(roc_file_path, &module_out);
+    }
 
     let code_gen_object = code_gen_object_start.elapsed();
     let total = all_code_gen_start.elapsed();
@@ -594,6 +975,22 @@ fn gen_from_mono_module_dev_assembly<'a>(
     )
 }
 
+/// Write a disassembly of the dev backend's object file next to the app (`--emit-asm`)
+#[cfg(feature = "disassemble")]
+fn write_app_asm(roc_file_path: &Path, module_out: &[u8]) {
+    let app_asm_file = roc_file_path.with_extension("s");
+    eprintln!("Emitting assembly to {}", app_asm_file.display());
+
+    let listing = roc_gen_dev::disassemble_object(module_out)
+        .unwrap_or_else(|e| internal_error!("I could not disassemble the app: {e}"));
+    std::fs::write(app_asm_file, listing).unwrap();
+}
+
+#[cfg(not(feature = "disassemble"))]
+fn write_app_asm(_roc_file_path: &Path, _module_out: &[u8]) {
+    internal_error!("roc_build was compiled without the `disassemble` feature");
+}
+
 fn report_timing(buf: &mut String, label: &str, duration: Duration) {
     use std::fmt::Write;
 
@@ -714,6 +1111,7 @@ pub fn standard_load_config(
         palette: DEFAULT_PALETTE,
         threading,
         exec_mode,
+        output: OutputConfig::default(),
     }
 }
 
@@ -735,9 +1133,11 @@ pub fn build_file<'a>(
     let compilation_start = Instant::now();
 
     // Step 1: compile the app and generate the .o file
+    let load_span = profile::span("load", "load and monomorphize");
     let loaded =
         roc_load::load_and_monomorphize(arena, app_module_path.clone(), roc_cache_dir, load_config)
             .map_err(|e| BuildFileError::from_mono_error(e, compilation_start))?;
+    drop(load_span);
 
     build_loaded_file(
         arena,
@@ -779,18 +1179,44 @@ fn build_loaded_file<'a>(
     // even if the --prebuilt-platform CLI flag wasn't set.
     let is_platform_prebuilt = prebuilt_requested || loaded.uses_prebuilt_platform;
 
-    if is_platform_prebuilt && linking_strategy == LinkingStrategy::Surgical {
-        // Fallback to legacy linking if the preprocessed host file does not exist, but a legacy host does exist.
-        let preprocessed_host_path =
-            platform_main_roc.with_file_name(roc_linker::preprocessed_host_filename(target));
-        let legacy_host_path = legacy_host_file(target, &platform_main_roc);
-        if !preprocessed_host_path.exists() && legacy_host_path.exists() {
-            linking_strategy = LinkingStrategy::Legacy;
-        }
+    // A library is only linked surgically into a platform whose host is itself a shared library.
+    // Such hosts can't be rebuilt from source, so they are always treated as prebuilt.
+    let surgical_lib = link_type == LinkType::Dylib
+        && linking_strategy == LinkingStrategy::Surgical
+        && roc_linker::is_shared_library_host(
+            target,
+            &platform_main_roc.with_file_name(roc_linker::preprocessed_host_filename(target)),
+        );
+
+    if link_type == LinkType::Dylib && !surgical_lib {
+        // Build a library of just the app.
+        linking_strategy = LinkingStrategy::Legacy;
     }
 
+    let is_platform_prebuilt = is_platform_prebuilt || surgical_lib;
+
+    let needs_host = match link_type {
+        LinkType::Executable => true,
+        LinkType::Dylib => surgical_lib,
+        LinkType::None => false,
+    };
+
     // the preprocessed host is stored beside the platform's main.roc
-    let preprocessed_host_path = if linking_strategy == LinkingStrategy::Legacy {
+    let preprocessed_host_path = if is_platform_prebuilt && needs_host {
+        // Falls back to legacy linking if the platform only ships a legacy host for this target.
+        match select_prebuilt_host(target, &platform_main_roc, linking_strategy) {
+            Ok((strategy, host_path)) => {
+                linking_strategy = strategy;
+
+                host_path
+            }
+            Err(missing) => {
+                invalid_prebuilt_platform(prebuilt_requested, &missing);
+
+                std::process::exit(1);
+            }
+        }
+    } else if linking_strategy == LinkingStrategy::Legacy {
         if target == Target::Wasm32 {
             // when compiling a wasm application, we implicitly assume here that the host is in zig
             // and has a file called "host.zig"
@@ -839,15 +1265,9 @@ fn build_loaded_file<'a>(
     };
 
     // We don't need to spawn a rebuild thread when using a prebuilt host.
-    let rebuild_thread = if matches!(link_type, LinkType::Dylib | LinkType::None) {
+    let rebuild_thread = if !needs_host {
         None
     } else if is_platform_prebuilt {
-        if !preprocessed_host_path.exists() {
-            invalid_prebuilt_platform(prebuilt_requested, preprocessed_host_path);
-
-            std::process::exit(1);
-        }
-
         if linking_strategy == LinkingStrategy::Surgical {
             // Copy preprocessed host to executable location.
             // The surgical linker will modify that copy in-place.
@@ -933,6 +1353,10 @@ fn build_loaded_file<'a>(
         None
     };
 
+    if code_gen_options.report_spec_sizes {
+        report_specialization_sizes(&loaded);
+    }
+
     let (roc_app_bytes, code_gen_timing, expect_metadata) = gen_from_mono_module(
         arena,
         loaded,
@@ -940,6 +1364,7 @@ fn build_loaded_file<'a>(
         target,
         code_gen_options,
         &preprocessed_host_path,
+        &output_exe_path,
         wasm_dev_stack_bytes,
     );
 
@@ -973,6 +1398,7 @@ fn build_loaded_file<'a>(
     }
 
     if let Some(HostRebuildTiming::ConcurrentWithApp(thread)) = opt_rebuild_timing {
+        let _span = profile::span("link", "wait for platform rebuild");
         let rebuild_duration = thread.join().expect("Failed to (re)build platform.");
 
         if emit_timings && !is_platform_prebuilt {
@@ -982,6 +1408,7 @@ fn build_loaded_file<'a>(
 
     // Step 2: link the prebuilt platform and compiled app
     let link_start = Instant::now();
+    let link_span = profile::span("link", "link").arg("strategy", format!("{linking_strategy:?}"));
 
     match (linking_strategy, link_type) {
         (LinkingStrategy::Surgical, _) => {
@@ -1048,6 +1475,7 @@ fn build_loaded_file<'a>(
         }
     }
 
+    drop(link_span);
     let linking_time = link_start.elapsed();
 
     if emit_timings {
@@ -1064,35 +1492,85 @@ fn build_loaded_file<'a>(
     })
 }
 
-fn invalid_prebuilt_platform(prebuilt_requested: bool, preprocessed_host_path: PathBuf) {
+fn invalid_prebuilt_platform(prebuilt_requested: bool, missing: &MissingPrebuiltHost) {
+    use std::fmt::Write;
+
     let prefix = if prebuilt_requested {
         "Because I was run with --prebuilt-platform, "
     } else {
         ""
     };
 
-    let preprocessed_host_path_str = preprocessed_host_path.to_string_lossy();
-    let extra_err_msg = if preprocessed_host_path_str.ends_with(".rh") {
-        "\n\n\tNote: If the platform does have an .rh1 file but no .rh file, it's because it's been built with an older version of roc. Contact the author to release a new build of the platform using a roc release newer than March 21 2023.\n"
+    let (wanted, not_there) = if missing.missing.len() == 1 {
+        ("this file to exist", "it was not there")
+    } else {
+        ("these files to exist", "they were not there")
+    };
+
+    let mut missing_files = String::new();
+
+    for path in &missing.missing {
+        writeln!(missing_files, "    {}", path.to_string_lossy()).unwrap();
+    }
+
+    let alternatives = match missing.linking_strategy {
+        LinkingStrategy::Surgical => format!(
+            "\nThe surgical linker needs both the .rh and the .rm file. Without them, I can also use the legacy linker with either the .{} or the .{} file.\n",
+            missing.target.static_library_file_ext(),
+            missing.target.object_file_ext(),
+        ),
+        LinkingStrategy::Legacy if missing.missing.len() > 1 => {
+            "\nThe legacy linker can use either one of them.\n".to_string()
+        }
+        LinkingStrategy::Legacy | LinkingStrategy::Additive => String::new(),
+    };
+
+    let extra_err_msg = if missing
+        .missing
+        .iter()
+        .any(|path| path.extension() == Some(OsStr::new(roc_linker::PRECOMPILED_HOST_EXT)))
+    {
+        "\n\tNote: If the platform does have an .rh1 file but no .rh file, it's because it's been built with an older version of roc. Contact the author to release a new build of the platform using a roc release newer than March 21 2023.\n"
     } else {
         ""
     };
 
+    let available = if missing.available_targets.is_empty() {
+        "This platform does not have a prebuilt host for any target.".to_string()
+    } else {
+        let targets: Vec<String> = missing
+            .available_targets
+            .iter()
+            .map(|target| target.to_string())
+            .collect();
+
+        format!(
+            "This platform has prebuilt host files for: {}",
+            targets.join(", ")
+        )
+    };
+
     eprintln!(
         indoc::indoc!(
             r#"
-            {}I was expecting this file to exist:
+            {}I was expecting {} for the {} target:
 
-                {}
+            {}{}{}
+            However, {}!
 
-            However, it was not there!{}
+            {}
 
             If you have the platform's source code locally, you may be able to generate it by re-running this command omitting --prebuilt-platform
             "#
         ),
         prefix,
-        preprocessed_host_path.to_string_lossy(),
-        extra_err_msg
+        wanted,
+        missing.target,
+        missing_files,
+        alternatives,
+        extra_err_msg,
+        not_there,
+        available,
     );
 }
 
@@ -1113,6 +1591,7 @@ fn spawn_rebuild_thread(
         eprintln!("🔨 Rebuilding platform...");
 
         let rebuild_host_start = Instant::now();
+        let rebuild_span = profile::span("link", "rebuild platform");
 
         match linking_strategy {
             LinkingStrategy::Additive => {
@@ -1138,6 +1617,7 @@ fn spawn_rebuild_thread(
             }
         }
 
+        drop(rebuild_span);
         rebuild_host_start.elapsed().as_millis()
     })
 }
@@ -1188,6 +1668,7 @@ pub fn check_file<'a>(
     arena: &'a Bump,
     roc_file_path: PathBuf,
     emit_timings: bool,
+    perf_hints: bool,
     roc_cache_dir: RocCacheDir<'_>,
     threading: Threading,
 ) -> Result<(Problems, Duration), LoadingProblem<'a>> {
@@ -1199,7 +1680,7 @@ pub fn check_file<'a>(
 
     // Step 1: compile the app and generate the .o file
 
-    let load_config = LoadConfig {
+    let mut load_config = LoadConfig {
         target,
         // TODO: we may not want this for just checking.
         function_kind: FunctionKind::LambdaSet,
@@ -1208,15 +1689,49 @@ pub fn check_file<'a>(
         palette: DEFAULT_PALETTE,
         threading,
         exec_mode: ExecutionMode::Check,
+        output: OutputConfig::default(),
+    };
+
+    enum Checked<'a> {
+        Typechecked(LoadedModule),
+        /// Perf hints need the procs after the refcounting passes
+        Monomorphized(Box<MonomorphizedModule<'a>>),
+    }
+
+    let checked = if perf_hints {
+        load_config.exec_mode = ExecutionMode::ExecutableIfCheck;
+        // Perf hints are reported at the definition they're in
+        load_config.output.mark_source_regions = true;
+
+        let load_span = profile::span("load", "load and monomorphize");
+        let loaded =
+            roc_load::load_and_monomorphize(arena, roc_file_path, roc_cache_dir, load_config);
+        drop(load_span);
+
+        match loaded {
+            Ok(loaded) => Checked::Monomorphized(Box::new(loaded)),
+            Err(LoadMonomorphizedError::ErrorModule(module)) => Checked::Typechecked(module),
+            Err(LoadMonomorphizedError::LoadingProblem(problem)) => return Err(problem),
+        }
+    } else {
+        let load_span = profile::span("load", "load and typecheck");
+        let loaded =
+            roc_load::load_and_typecheck(arena, roc_file_path, roc_cache_dir, load_config)?;
+        drop(load_span);
+
+        Checked::Typechecked(loaded)
+    };
+
+    let (interns, timings) = match &checked {
+        Checked::Typechecked(loaded) => (&loaded.interns, &loaded.timings),
+        Checked::Monomorphized(loaded) => (&loaded.interns, &loaded.timings),
     };
-    let mut loaded =
-        roc_load::load_and_typecheck(arena, roc_file_path, roc_cache_dir, load_config)?;
 
     let buf = &mut String::with_capacity(1024);
 
-    let mut it = loaded.timings.iter().peekable();
+    let mut it = timings.iter().peekable();
     while let Some((module_id, module_timing)) = it.next() {
-        let module_name = loaded.interns.module_name(*module_id);
+        let module_name = interns.module_name(*module_id);
 
         buf.push_str("    ");
 
@@ -1254,7 +1769,21 @@ pub fn check_file<'a>(
         println!("Finished checking in {} ms\n", compilation_end.as_millis(),);
     }
 
-    Ok((report_problems_typechecked(&mut loaded), compilation_end))
+    let problems = match checked {
+        Checked::Typechecked(mut loaded) => report_problems_typechecked(&mut loaded),
+        Checked::Monomorphized(mut loaded) => {
+            let problems = report_problems_monomorphized(&mut loaded);
+            let hints = report_perf_hints(&loaded);
+            println!(
+                "{hints} perf {} found",
+                if hints == 1 { "hint" } else { "hints" }
+            );
+
+            problems
+        }
+    };
+
+    Ok((problems, compilation_end))
 }
 
 pub fn build_str_test<'a>(
@@ -1269,8 +1798,14 @@ pub fn build_str_test<'a>(
         backend: CodeGenBackend::Llvm(LlvmBackendMode::Binary),
         opt_level: OptLevel::Normal,
         emit_debug_info: false,
+        emit_source_debug_info: false,
         emit_llvm_ir: false,
+        emit_asm: false,
+        emit_wat: false,
         fuzz: false,
+        report_spec_sizes: false,
+        instrument_memory: false,
+        register_allocation: RegisterAllocation::default(),
     };
 
     let emit_timings = false;
@@ -1324,6 +1859,9 @@ fn with_output_extension(
             // Additive linking and no linking both output the object file type.
             path.with_extension(target.object_file_ext())
         }
+        (LinkingStrategy::Surgical, LinkType::Dylib) => {
+            path.with_extension(target.shared_library_file_ext())
+        }
         _ => path.with_extension(target.executable_file_ext().unwrap_or_default()),
     }
 }
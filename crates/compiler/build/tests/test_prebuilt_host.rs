@@ -0,0 +1,174 @@
+use roc_build::link::{select_prebuilt_host, LinkingStrategy, PrebuiltHostFiles};
+use roc_target::Target;
+use std::path::{Path, PathBuf};
+
+/// A platform directory containing main.roc and the given host files
+fn platform(files: &[&str]) -> (tempfile::TempDir, PathBuf) {
+    let dir = tempfile::tempdir().unwrap();
+
+    for file in files.iter().chain(&["main.roc"]) {
+        std::fs::write(dir.path().join(file), "").unwrap();
+    }
+
+    let main_roc = dir.path().join("main.roc");
+
+    (dir, main_roc)
+}
+
+fn file_name(path: &Path) -> &str {
+    path.file_name().unwrap().to_str().unwrap()
+}
+
+fn select(
+    target: Target,
+    files: &[&str],
+    linking_strategy: LinkingStrategy,
+) -> Result<(LinkingStrategy, String), (Vec<String>, Vec<Target>)> {
+    let (_dir, main_roc) = platform(files);
+
+    match select_prebuilt_host(target, &main_roc, linking_strategy) {
+        Ok((strategy, host)) => Ok((strategy, file_name(&host).to_string())),
+        Err(missing) => {
+            assert_eq!(missing.target, target);
+            assert_eq!(missing.linking_strategy, linking_strategy);
+
+            let missing_files = missing
+                .missing
+                .iter()
+                .map(|path| file_name(path).to_string())
+                .collect();
+
+            Err((missing_files, missing.available_targets))
+        }
+    }
+}
+
+#[test]
+fn prebuilt_host_file_names() {
+    let files = PrebuiltHostFiles::new(Target::LinuxX64, Path::new("platform/main.roc"));
+
+    assert_eq!(files.preprocessed_host, Path::new("platform/linux-x64.rh"));
+    assert_eq!(files.metadata, Path::new("platform/metadata_linux-x64.rm"));
+    assert_eq!(files.static_library, Path::new("platform/linux-x64.a"));
+    assert_eq!(files.object, Path::new("platform/linux-x64.o"));
+
+    let files = PrebuiltHostFiles::new(Target::WinX64, Path::new("platform/main.roc"));
+
+    assert_eq!(files.static_library, Path::new("platform/windows-x64.lib"));
+    assert_eq!(files.object, Path::new("platform/windows-x64.obj"));
+}
+
+#[test]
+fn surgical_host() {
+    assert_eq!(
+        select(
+            Target::LinuxX64,
+            &["linux-x64.rh", "metadata_linux-x64.rm", "linux-x64.a"],
+            LinkingStrategy::Surgical
+        ),
+        Ok((LinkingStrategy::Surgical, "linux-x64.rh".to_string()))
+    );
+}
+
+#[test]
+fn surgical_falls_back_to_legacy() {
+    // The preprocessed host is useless without its metadata
+    assert_eq!(
+        select(
+            Target::LinuxX64,
+            &["linux-x64.rh", "linux-x64.a"],
+            LinkingStrategy::Surgical
+        ),
+        Ok((LinkingStrategy::Legacy, "linux-x64.a".to_string()))
+    );
+
+    assert_eq!(
+        select(
+            Target::LinuxX64,
+            &["linux-x64.o"],
+            LinkingStrategy::Surgical
+        ),
+        Ok((LinkingStrategy::Legacy, "linux-x64.o".to_string()))
+    );
+}
+
+#[test]
+fn surgical_host_missing() {
+    assert_eq!(
+        select(
+            Target::LinuxX64,
+            &["linux-x64.rh"],
+            LinkingStrategy::Surgical
+        ),
+        Err((
+            vec![
+                "metadata_linux-x64.rm".to_string(),
+                "linux-x64.a".to_string(),
+                "linux-x64.o".to_string(),
+            ],
+            vec![Target::LinuxX64],
+        ))
+    );
+}
+
+#[test]
+fn legacy_host_prefers_static_library() {
+    assert_eq!(
+        select(
+            Target::MacArm64,
+            &["macos-arm64.o", "macos-arm64.a"],
+            LinkingStrategy::Legacy
+        ),
+        Ok((LinkingStrategy::Legacy, "macos-arm64.a".to_string()))
+    );
+
+    assert_eq!(
+        select(
+            Target::MacArm64,
+            &["macos-arm64.o"],
+            LinkingStrategy::Legacy
+        ),
+        Ok((LinkingStrategy::Legacy, "macos-arm64.o".to_string()))
+    );
+}
+
+#[test]
+fn legacy_host_missing_lists_other_targets() {
+    assert_eq!(
+        select(
+            Target::MacArm64,
+            &["linux-x64.rh", "windows-x64.obj"],
+            LinkingStrategy::Legacy
+        ),
+        Err((
+            vec!["macos-arm64.a".to_string(), "macos-arm64.o".to_string()],
+            vec![Target::LinuxX64, Target::WinX64],
+        ))
+    );
+}
+
+#[test]
+fn additive_host() {
+    assert_eq!(
+        select(Target::Wasm32, &["wasm32.rh"], LinkingStrategy::Additive),
+        Ok((LinkingStrategy::Additive, "wasm32.rh".to_string()))
+    );
+
+    assert_eq!(
+        select(Target::Wasm32, &[], LinkingStrategy::Additive),
+        Err((vec!["wasm32.rh".to_string()], vec![]))
+    );
+}
+
+#[test]
+fn wasm_legacy_host() {
+    assert_eq!(
+        select(Target::Wasm32, &["host.zig"], LinkingStrategy::Legacy),
+        Ok((LinkingStrategy::Legacy, "host.zig".to_string()))
+    );
+
+    assert_eq!(
+        select(Target::Wasm32, &[], LinkingStrategy::Legacy),
+        Err((vec!["host.zig".to_string()], vec![]))
+    );
+}
@@ -159,5 +159,8 @@ fn makeWasm32Target() CrossTarget {
     target.os_tag = std.Target.Os.Tag.freestanding;
     target.abi = std.Target.Abi.none;
 
+    // SIMD speeds up string comparison, and bulk memory turns @memcpy/@memset into single instructions
+    target.cpu_features_add = std.Target.wasm.featureSet(&[_]std.Target.wasm.Feature{ .bulk_memory, .simd128 });
+
     return target;
 }
@@ -1010,6 +1010,36 @@ pub fn listCapacity(
     return list.getCapacity();
 }
 
+pub fn listCloneTo(
+    list: RocList,
+    ptr: [*]u8,
+    offset: usize,
+    extra_offset: usize,
+    element_width: usize,
+) callconv(.C) usize {
+    const WIDTH: usize = @sizeOf(RocList);
+    const length = list.len();
+
+    // the elements are written just after the list struct, and we skip any extra capacity
+    var relative = RocList{
+        .bytes = @as(?[*]u8, @ptrFromInt(extra_offset)),
+        .length = length,
+        .capacity_or_alloc_ptr = length,
+    };
+
+    // write the list struct
+    const array = @as([*]u8, @ptrCast(&relative));
+    @memcpy(ptr[offset..(offset + WIDTH)], array[0..WIDTH]);
+
+    // write the elements; they must not contain any pointers themselves
+    const num_bytes = length * element_width;
+    if (list.bytes) |source_ptr| {
+        @memcpy(ptr[extra_offset..(extra_offset + num_bytes)], source_ptr[0..num_bytes]);
+    }
+
+    return extra_offset + num_bytes;
+}
+
 pub fn listAllocationPtr(
     list: RocList,
 ) callconv(.C) ?[*]u8 {
@@ -193,11 +193,18 @@ pub const RocStr = extern struct {
         // Now we have to look at the string contents
         const self_bytes = self.asU8ptr();
         const other_bytes = other.asU8ptr();
-        // TODO: we can make an optimization like memcmp does in glibc.
-        // We can check the min shared alignment 1, 2, 4, or 8.
-        // Then do a copy at that alignment before falling back on one byte at a time.
-        // Currently we have to be unaligned because slices can be at any alignment.
+        // Compare 16 bytes at a time using unaligned vector loads (SIMD on targets that have it),
+        // then fall back to one byte at a time for the remainder.
+        // We have to be unaligned because slices can be at any alignment.
+        const Chunk = @Vector(16, u8);
         var b: usize = 0;
+        while (b + 16 <= self_len) : (b += 16) {
+            const self_chunk: Chunk = self_bytes[b..][0..16].*;
+            const other_chunk: Chunk = other_bytes[b..][0..16].*;
+            if (@reduce(.Or, self_chunk != other_chunk)) {
+                return false;
+            }
+        }
         while (b < self_len) : (b += 1) {
             if (self_bytes[b] != other_bytes[b]) {
                 return false;
@@ -219,6 +219,10 @@ impl DerivedModule {
         }
     }
 
+    pub fn ident_ids(&self) -> &IdentIds {
+        &self.derived_ident_ids
+    }
+
     pub fn decompose(self) -> (Subs, IdentIds) {
         (self.subs, self.derived_ident_ids)
     }
@@ -0,0 +1,139 @@
+//! A listing of the machine code in an object file from the dev backend, for `roc build --emit-asm`.
+//! Uses the same disassembler as the instruction tests in `generic64`.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use capstone::prelude::*;
+use object::{
+    Architecture, Object, ObjectSection, ObjectSymbol, RelocationTarget, SectionKind, SymbolKind,
+};
+
+/// Disassemble the code sections of an object file built by [`build_module`](crate::build_module).
+///
+/// Each proc starts with its symbol name. Procs don't record their size, so a proc ends where
+/// the next one starts. The bytes that the linker patches are still placeholders, so instructions
+/// containing a relocation are annotated with the symbol it refers to.
+pub fn disassemble_object(object_bytes: &[u8]) -> Result<String, String> {
+    let file = object::File::parse(object_bytes)
+        .map_err(|e| format!("Could not parse the object file: {e}"))?;
+
+    let (capstone, instruction_alignment) = match file.architecture() {
+        Architecture::X86_64 => {
+            let capstone = Capstone::new()
+                .x86()
+                .mode(arch::x86::ArchMode::Mode64)
+                .syntax(arch::x86::ArchSyntax::Intel)
+                .build();
+            (capstone, 1)
+        }
+        Architecture::Aarch64 => {
+            let capstone = Capstone::new()
+                .arm64()
+                .mode(arch::arm64::ArchMode::Arm)
+                .build();
+            (capstone, 4)
+        }
+        other => return Err(format!("I can't disassemble {other:?} machine code")),
+    };
+    let capstone = capstone.map_err(|e| format!("Could not set up the disassembler: {e}"))?;
+
+    let mut listing = String::new();
+
+    for section in file.sections() {
+        if section.kind() != SectionKind::Text {
+            continue;
+        }
+
+        let code = section.data().map_err(|e| e.to_string())?;
+        if code.is_empty() {
+            continue;
+        }
+        let section_name = section.name().unwrap_or("<unnamed section>");
+
+        // Several symbols can point at the same proc
+        let mut procs: BTreeMap<u64, Vec<&str>> = BTreeMap::new();
+        for symbol in file.symbols() {
+            if symbol.kind() == SymbolKind::Text && symbol.section_index() == Some(section.index())
+            {
+                let offset = symbol.address() - section.address();
+                let name = symbol.name().unwrap_or("<unnamed symbol>");
+                procs.entry(offset).or_default().push(name);
+            }
+        }
+        // Any code before the first proc is labelled with the section name
+        procs.entry(0).or_insert_with(|| vec![section_name]);
+
+        let mut relocations: BTreeMap<u64, String> = BTreeMap::new();
+        for (offset, relocation) in section.relocations() {
+            let target = match relocation.target() {
+                RelocationTarget::Symbol(index) => file
+                    .symbol_by_index(index)
+                    .and_then(|symbol| symbol.name().map(str::to_string)),
+                RelocationTarget::Section(index) => file
+                    .section_by_index(index)
+                    .and_then(|section| section.name().map(str::to_string)),
+                _ => Ok("<absolute>".to_string()),
+            };
+            relocations.insert(offset, target.map_err(|e| e.to_string())?);
+        }
+
+        writeln!(listing, "section {section_name}").unwrap();
+
+        let starts: Vec<u64> = procs.keys().copied().collect();
+        for (i, (start, names)) in procs.iter().enumerate() {
+            let end = starts.get(i + 1).copied().unwrap_or(code.len() as u64);
+
+            listing.push('\n');
+            for name in names {
+                writeln!(listing, "{name}:").unwrap();
+            }
+
+            let mut offset = *start;
+            while offset < end {
+                let bytes = &code[offset as usize..end as usize];
+                let instructions = capstone
+                    .disasm_all(bytes, offset)
+                    .map_err(|e| format!("Could not disassemble {section_name}: {e}"))?;
+
+                for instruction in instructions.iter() {
+                    let address = instruction.address();
+                    let next = address + instruction.bytes().len() as u64;
+                    let text = format!(
+                        "{} {}",
+                        instruction.mnemonic().unwrap_or(""),
+                        instruction.op_str().unwrap_or("")
+                    );
+                    write_line(&mut listing, address, instruction.bytes(), text.trim_end());
+
+                    for target in relocations.range(address..next).map(|(_, target)| target) {
+                        write!(listing, "  ; {target}").unwrap();
+                    }
+                    listing.push('\n');
+
+                    offset = next;
+                }
+
+                // The disassembler stops at bytes it doesn't recognize. Show them as data.
+                if offset < end {
+                    let data_end = (offset + instruction_alignment).min(end);
+                    let data = &code[offset as usize..data_end as usize];
+                    let values: Vec<String> = data.iter().map(|b| format!("0x{b:02x}")).collect();
+                    let text = format!(".byte {}", values.join(", "));
+                    write_line(&mut listing, offset, data, &text);
+                    listing.push('\n');
+                    offset = data_end;
+                }
+            }
+        }
+
+        listing.push('\n');
+    }
+
+    Ok(listing)
+}
+
+fn write_line(listing: &mut String, address: u64, bytes: &[u8], text: &str) {
+    let hex: Vec<String> = bytes.iter().map(|b| format!("{b:02x}")).collect();
+    write!(listing, "{address:8x}:  {:<32}{text}", hex.join(" ")).unwrap();
+}
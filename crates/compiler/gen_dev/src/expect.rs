@@ -0,0 +1,1075 @@
+//! Reports failed expectations to the parent process through shared memory.
+//! The frames written here must have the same shape as the ones written by the LLVM backend,
+//! because the parent reads both of them back in the same way.
+//!
+//! Lists of values that contain pointers and tag unions that aren't safe to memcpy need loops,
+//! branches or recursion to clone. For those, we generate IR procedures that are built along with
+//! the higher-order caller procs.
+use crate::{AssemblyBackendMode, Backend};
+use bumpalo::collections::Vec;
+use bumpalo::Bump;
+use roc_builtins::bitcode;
+use roc_error_macros::{internal_error, todo_lambda_erasure};
+use roc_module::ident::ForeignSymbol;
+use roc_module::low_level::LowLevel;
+use roc_module::symbol::{IdentIds, ModuleId, Symbol};
+use roc_mono::code_gen_help::CallerProc;
+use roc_mono::ir::{
+    BranchInfo, Call, CallSpecId, CallType, Expr, JoinPointId, Literal, LookupType, Param, Proc,
+    ProcLayout, SelfRecursive, Stmt, UpdateModeId,
+};
+use roc_mono::layout::{
+    Builtin, InLayout, LambdaName, Layout, LayoutInterner, LayoutRepr, Niche, STLayoutInterner,
+    UnionLayout,
+};
+use roc_region::all::Region;
+use roc_target::Target;
+
+#[derive(Debug, Clone, Copy)]
+struct Cursors {
+    offset: Symbol,
+    extra_offset: Symbol,
+}
+
+fn shared_memory_pointer<'a, B: Backend<'a>>(backend: &mut B) -> Symbol {
+    let start_function = if let AssemblyBackendMode::BinaryDev = backend.env().mode {
+        bitcode::UTILS_EXPECT_FAILED_START_SHARED_FILE
+    } else {
+        bitcode::UTILS_EXPECT_FAILED_START_SHARED_BUFFER
+    };
+
+    let usize_layout = Layout::usize(backend.target());
+    let shared_memory = backend.debug_symbol("shared_memory");
+    backend.build_fn_call(
+        &shared_memory,
+        start_function.to_string(),
+        &[],
+        &[],
+        &usize_layout,
+    );
+
+    shared_memory
+}
+
+fn offset_add<'a, B: Backend<'a>>(backend: &mut B, current: Symbol, extra: u32) -> Symbol {
+    let usize_layout = Layout::usize(backend.target());
+    let extra_symbol = backend.debug_symbol("extra");
+    backend.load_literal_i64(&extra_symbol, extra as i64);
+
+    let new_offset = backend.debug_symbol("offset_add");
+    backend.build_num_add_wrap(&new_offset, &current, &extra_symbol, &usize_layout);
+    backend.free_symbol(&extra_symbol);
+
+    new_offset
+}
+
+/// Writes `value` into the buffer at `offset`, and returns the offset just after it
+fn build_copy<'a, B: Backend<'a>>(
+    backend: &mut B,
+    ptr: Symbol,
+    offset: Symbol,
+    value: Symbol,
+    layout: InLayout<'a>,
+) -> Symbol {
+    let usize_layout = Layout::usize(backend.target());
+    let at_current_offset = backend.debug_symbol("at_current_offset");
+    backend.build_num_add_wrap(&at_current_offset, &ptr, &offset, &usize_layout);
+
+    let ignored = backend.debug_symbol("ignored");
+    backend.build_ptr_store(ignored, at_current_offset, value, layout);
+    backend.free_symbol(&ignored);
+    backend.free_symbol(&at_current_offset);
+
+    let width = backend.interner().stack_size(layout);
+    offset_add(backend, offset, width)
+}
+
+/// Read the first two pointer-sized values from the shared memory,
+/// representing the total number of expect frames and the next free position
+fn read_state<'a, B: Backend<'a>>(backend: &mut B, ptr: Symbol) -> (Symbol, Symbol) {
+    let usize_layout = Layout::usize(backend.target());
+    let ptr_size = backend.target().ptr_size() as u32;
+
+    let count = backend.debug_symbol("load_count");
+    backend.build_ptr_load(count, ptr, usize_layout);
+
+    let offset_ptr = offset_add(backend, ptr, ptr_size);
+    let offset = backend.debug_symbol("load_offset");
+    backend.build_ptr_load(offset, offset_ptr, usize_layout);
+    backend.free_symbol(&offset_ptr);
+
+    (count, offset)
+}
+
+fn write_state<'a, B: Backend<'a>>(backend: &mut B, ptr: Symbol, count: Symbol, offset: Symbol) {
+    let usize_layout = Layout::usize(backend.target());
+    let ptr_size = backend.target().ptr_size() as u32;
+
+    let ignored = backend.debug_symbol("ignored");
+    backend.build_ptr_store(ignored, ptr, count, usize_layout);
+    backend.free_symbol(&ignored);
+
+    let offset_ptr = offset_add(backend, ptr, ptr_size);
+    let ignored = backend.debug_symbol("ignored");
+    backend.build_ptr_store(ignored, offset_ptr, offset, usize_layout);
+    backend.free_symbol(&ignored);
+    backend.free_symbol(&offset_ptr);
+}
+
+/// Writes the module and region into the buffer
+fn write_header<'a, B: Backend<'a>>(
+    backend: &mut B,
+    ptr: Symbol,
+    mut offset: Symbol,
+    condition: Symbol,
+    region: Region,
+) -> Symbol {
+    let module_id: u32 = unsafe { std::mem::transmute(condition.module_id()) };
+
+    for value in [region.start().offset, region.end().offset, module_id] {
+        let header_value = backend.debug_symbol("header_value");
+        backend.load_literal_i32(&header_value, value as i32);
+
+        offset = build_copy(backend, ptr, offset, header_value, Layout::U32);
+        backend.free_symbol(&header_value);
+    }
+
+    offset
+}
+
+pub(crate) fn notify_parent_expect<'a, B: Backend<'a>>(backend: &mut B, shared_memory: Symbol) {
+    let usize_layout = Layout::usize(backend.target());
+    let ignored = backend.debug_symbol("ignored");
+
+    backend.build_fn_call(
+        &ignored,
+        bitcode::NOTIFY_PARENT_EXPECT.to_string(),
+        &[shared_memory],
+        &[usize_layout],
+        &Layout::UNIT,
+    );
+
+    backend.free_symbol(&ignored);
+}
+
+// Shape of expect frame:
+//
+//     ===
+//     Fixed-size header
+//     ===
+// /-- ptr_lookup_1  (ptr_size)
+// |   var_lookup_1  (u32)
+// |   ..
+// |   ptr_lookup_n  (ptr_size)
+// |   var_lookup_n  (u32)
+// \-> lookup_val_1  (varsize)
+//     ..
+//     lookup_val_n  (varsize)
+//
+/// Returns the symbol holding the pointer to the shared memory.
+pub(crate) fn clone_to_shared_memory<'a, B: Backend<'a>>(
+    backend: &mut B,
+    condition: Symbol,
+    region: Region,
+    lookups: &'a [Symbol],
+    lookup_variables: &'a [LookupType],
+) -> Symbol {
+    let usize_layout = Layout::usize(backend.target());
+    let ptr_size = backend.target().ptr_size() as u32;
+
+    let shared_memory = shared_memory_pointer(backend);
+
+    let (count, mut offset) = read_state(backend, shared_memory);
+
+    offset = write_header(backend, shared_memory, offset, condition, region);
+
+    let after_header = offset;
+
+    let space_for_offsets = lookups.len() as u32 * (ptr_size + std::mem::size_of::<u32>() as u32);
+
+    let mut lookup_starts = Vec::with_capacity_in(lookups.len(), backend.env().arena);
+
+    offset = offset_add(backend, offset, space_for_offsets);
+
+    backend.load_literal_symbols(lookups);
+
+    for lookup in lookups.iter() {
+        lookup_starts.push(offset);
+
+        let layout = *backend.layout_map().get(lookup).unwrap();
+        let stack_size = backend.interner().stack_size(layout);
+
+        let extra_offset = offset_add(backend, offset, stack_size);
+
+        let cursors = Cursors {
+            offset,
+            extra_offset,
+        };
+
+        offset = build_clone(backend, shared_memory, cursors, *lookup, layout);
+    }
+
+    {
+        let mut offset = after_header;
+
+        for (lookup_start, lookup_var) in lookup_starts.into_iter().zip(lookup_variables) {
+            // Store the pointer to the value
+            offset = build_copy(backend, shared_memory, offset, lookup_start, usize_layout);
+
+            // Store the specialized variable of the value
+            let var_value = backend.debug_symbol("var_value");
+            backend.load_literal_i32(&var_value, lookup_var.index() as i32);
+
+            offset = build_copy(backend, shared_memory, offset, var_value, Layout::U32);
+            backend.free_symbol(&var_value);
+        }
+    }
+
+    let new_count = offset_add(backend, count, 1);
+    write_state(backend, shared_memory, new_count, offset);
+
+    shared_memory
+}
+
+/// Clones `value` into the buffer. Its stack representation is written at `cursors.offset`,
+/// anything it points to is written at `cursors.extra_offset`.
+/// Returns the offset just after the last byte that was written.
+fn build_clone<'a, B: Backend<'a>>(
+    backend: &mut B,
+    ptr: Symbol,
+    cursors: Cursors,
+    value: Symbol,
+    layout: InLayout<'a>,
+) -> Symbol {
+    let repr = backend.interner().get_repr(layout);
+
+    match repr {
+        LayoutRepr::Builtin(builtin) => {
+            build_clone_builtin(backend, ptr, cursors, value, layout, builtin)
+        }
+
+        LayoutRepr::Struct(field_layouts) => {
+            if repr.safe_to_memcpy(backend.interner()) {
+                build_copy(backend, ptr, cursors.offset, value, layout);
+
+                cursors.extra_offset
+            } else {
+                build_clone_struct(backend, ptr, cursors, value, field_layouts)
+            }
+        }
+
+        // Since we will never actually display functions (and hence lambda sets)
+        // we just write nothing to the buffer
+        LayoutRepr::LambdaSet(_) => cursors.extra_offset,
+
+        LayoutRepr::Union(_) if repr.safe_to_memcpy(backend.interner()) => {
+            build_copy(backend, ptr, cursors.offset, value, layout);
+
+            cursors.extra_offset
+        }
+
+        LayoutRepr::Union(_) => call_clone_proc(backend, ptr, cursors, value, layout),
+
+        LayoutRepr::RecursivePointer(_) => {
+            unreachable!("recursive pointers are cloned by the procedure of their union")
+        }
+
+        LayoutRepr::Ptr(_) => {
+            unreachable!("for internal use only")
+        }
+        LayoutRepr::FunctionPointer(_) => todo_lambda_erasure!(),
+        LayoutRepr::Erased(_) => todo_lambda_erasure!(),
+    }
+}
+
+fn build_clone_struct<'a, B: Backend<'a>>(
+    backend: &mut B,
+    ptr: Symbol,
+    cursors: Cursors,
+    value: Symbol,
+    field_layouts: &'a [InLayout<'a>],
+) -> Symbol {
+    let mut cursors = cursors;
+
+    for (i, field_layout) in field_layouts.iter().enumerate() {
+        let field = backend.debug_symbol("field");
+        backend.load_struct_at_index(&field, &value, i as u64, field_layouts);
+
+        let new_extra = build_clone(backend, ptr, cursors, field, *field_layout);
+
+        let field_width = backend.interner().stack_size(*field_layout);
+
+        cursors.extra_offset = new_extra;
+        cursors.offset = offset_add(backend, cursors.offset, field_width);
+    }
+
+    cursors.extra_offset
+}
+
+fn build_clone_builtin<'a, B: Backend<'a>>(
+    backend: &mut B,
+    ptr: Symbol,
+    cursors: Cursors,
+    value: Symbol,
+    layout: InLayout<'a>,
+    builtin: Builtin<'a>,
+) -> Symbol {
+    let usize_layout = Layout::usize(backend.target());
+
+    match builtin {
+        Builtin::Int(_) | Builtin::Float(_) | Builtin::Bool | Builtin::Decimal => {
+            build_copy(backend, ptr, cursors.offset, value, layout);
+
+            cursors.extra_offset
+        }
+
+        Builtin::Str => {
+            let new_offset = backend.debug_symbol("new_offset");
+
+            backend.build_fn_call(
+                &new_offset,
+                bitcode::STR_CLONE_TO.to_string(),
+                &[value, ptr, cursors.offset, cursors.extra_offset],
+                &[Layout::STR, usize_layout, usize_layout, usize_layout],
+                &usize_layout,
+            );
+
+            new_offset
+        }
+
+        Builtin::List(elem) if backend.interner().safe_to_memcpy(elem) => {
+            let element_width = backend.debug_symbol("element_width");
+            let element_width_int = backend.interner().stack_size(elem);
+            backend.load_literal_i64(&element_width, element_width_int as i64);
+
+            // we only copy the elements we actually have (and skip extra capacity)
+            let new_offset = backend.debug_symbol("new_offset");
+            backend.build_fn_call(
+                &new_offset,
+                bitcode::LIST_CLONE_TO.to_string(),
+                &[
+                    value,
+                    ptr,
+                    cursors.offset,
+                    cursors.extra_offset,
+                    element_width,
+                ],
+                &[
+                    layout,
+                    usize_layout,
+                    usize_layout,
+                    usize_layout,
+                    usize_layout,
+                ],
+                &usize_layout,
+            );
+
+            backend.free_symbol(&element_width);
+
+            new_offset
+        }
+
+        Builtin::List(_) => call_clone_proc(backend, ptr, cursors, value, layout),
+    }
+}
+
+/// Calls the generated procedure that clones values of this layout, creating it if needed
+fn call_clone_proc<'a, B: Backend<'a>>(
+    backend: &mut B,
+    ptr: Symbol,
+    cursors: Cursors,
+    value: Symbol,
+    layout: InLayout<'a>,
+) -> Symbol {
+    let arena = backend.env().arena;
+    let target = backend.target();
+    let usize_layout = Layout::usize(target);
+
+    let clone_symbol = {
+        let (home, interner, interns, _, caller_procs) = backend.module_interns_helpers_mut();
+        let ident_ids = interns.all_ident_ids.get_mut(&home).unwrap();
+        let clone_symbol = Symbol::new(home, ident_ids.get_or_insert("#expect_clone"));
+
+        let mut procs = CloneProcs {
+            arena,
+            home,
+            target,
+            ident_ids,
+            interner,
+            caller_procs,
+            clone_symbol,
+            in_progress: std::vec::Vec::new(),
+        };
+        procs.generate(layout);
+
+        clone_symbol
+    };
+
+    let arg_layouts = [usize_layout, usize_layout, usize_layout, layout];
+    let fn_name = backend.lambda_name_to_string(
+        LambdaName::no_niche(clone_symbol),
+        arg_layouts.iter().copied(),
+        None,
+        usize_layout,
+    );
+
+    let new_offset = backend.debug_symbol("new_offset");
+    backend.build_fn_call(
+        &new_offset,
+        fn_name,
+        &[ptr, cursors.offset, cursors.extra_offset, value],
+        &arg_layouts,
+        &usize_layout,
+    );
+
+    new_offset
+}
+
+/// The arguments of a clone procedure, in IR
+#[derive(Clone, Copy)]
+struct CloneCursors {
+    ptr: Symbol,
+    offset: Symbol,
+    extra_offset: Symbol,
+}
+
+/// A procedure body under construction: a sequence of `Let`s, followed by control flow
+type Lets<'a> = std::vec::Vec<(Symbol, Expr<'a>, InLayout<'a>)>;
+
+/// Generates the IR of the clone procedures.
+/// Each one takes the buffer pointer, the offset and extra offset, and the value,
+/// and returns the offset just after the last byte that was written, like `build_clone`.
+struct CloneProcs<'a, 'r> {
+    arena: &'a Bump,
+    home: ModuleId,
+    target: Target,
+    ident_ids: &'r mut IdentIds,
+    interner: &'r mut STLayoutInterner<'a>,
+    caller_procs: &'r mut Vec<'a, CallerProc<'a>>,
+    clone_symbol: Symbol,
+    /// Layouts whose procedure is being generated, so recursive unions don't recurse forever
+    in_progress: std::vec::Vec<InLayout<'a>>,
+}
+
+impl<'a, 'r> CloneProcs<'a, 'r> {
+    fn usize(&self) -> InLayout<'a> {
+        Layout::usize(self.target)
+    }
+
+    fn symbol(&mut self, debug_name: &str) -> Symbol {
+        Symbol::new(self.home, self.ident_ids.add_str(debug_name))
+    }
+
+    fn proc_layout(&self, layout: InLayout<'a>) -> ProcLayout<'a> {
+        let usize_layout = self.usize();
+        ProcLayout {
+            arguments: self
+                .arena
+                .alloc([usize_layout, usize_layout, usize_layout, layout]),
+            result: usize_layout,
+            niche: Niche::NONE,
+        }
+    }
+
+    /// Generate the clone procedure for this layout, unless it already exists
+    fn generate(&mut self, layout: InLayout<'a>) {
+        let proc_layout = self.proc_layout(layout);
+        let exists = self.caller_procs.iter().any(|caller_proc| {
+            caller_proc.proc_symbol == self.clone_symbol && caller_proc.proc_layout == proc_layout
+        });
+        if exists || self.in_progress.contains(&layout) {
+            return;
+        }
+        self.in_progress.push(layout);
+
+        let cursors = CloneCursors {
+            ptr: self.symbol("ptr"),
+            offset: self.symbol("offset"),
+            extra_offset: self.symbol("extra_offset"),
+        };
+        let value = self.symbol("value");
+
+        let body = match self.interner.get_repr(layout) {
+            LayoutRepr::Builtin(Builtin::List(elem)) => self.clone_list(cursors, value, elem),
+            LayoutRepr::Union(union_layout) => self.clone_union(cursors, value, union_layout),
+            other => internal_error!("no clone procedure is needed for {other:?}"),
+        };
+
+        let usize_layout = self.usize();
+        let proc = Proc {
+            name: LambdaName::no_niche(self.clone_symbol),
+            args: self.arena.alloc([
+                (usize_layout, cursors.ptr),
+                (usize_layout, cursors.offset),
+                (usize_layout, cursors.extra_offset),
+                (layout, value),
+            ]),
+            body,
+            closure_data_layout: None,
+            ret_layout: usize_layout,
+            is_self_recursive: SelfRecursive::NotSelfRecursive,
+            is_erased: false,
+        };
+
+        self.caller_procs.push(CallerProc {
+            proc_symbol: self.clone_symbol,
+            proc_layout,
+            proc,
+        });
+    }
+
+    fn finish(&self, lets: Lets<'a>, end: Stmt<'a>) -> Stmt<'a> {
+        lets.into_iter()
+            .rev()
+            .fold(end, |next, (symbol, expr, layout)| {
+                Stmt::Let(symbol, expr, layout, self.arena.alloc(next))
+            })
+    }
+
+    fn let_expr(
+        &mut self,
+        lets: &mut Lets<'a>,
+        name: &str,
+        expr: Expr<'a>,
+        layout: InLayout<'a>,
+    ) -> Symbol {
+        let symbol = self.symbol(name);
+        lets.push((symbol, expr, layout));
+        symbol
+    }
+
+    fn literal(&mut self, lets: &mut Lets<'a>, value: u64, layout: InLayout<'a>) -> Symbol {
+        let expr = Expr::Literal(Literal::Int((value as i128).to_ne_bytes()));
+        self.let_expr(lets, "literal", expr, layout)
+    }
+
+    fn lowlevel(
+        &mut self,
+        lets: &mut Lets<'a>,
+        op: LowLevel,
+        arguments: &[Symbol],
+        layout: InLayout<'a>,
+    ) -> Symbol {
+        let expr = Expr::Call(Call {
+            call_type: CallType::LowLevel {
+                op,
+                update_mode: UpdateModeId::BACKEND_DUMMY,
+            },
+            arguments: self.arena.alloc_slice_copy(arguments),
+        });
+        self.let_expr(lets, "lowlevel", expr, layout)
+    }
+
+    fn foreign(
+        &mut self,
+        lets: &mut Lets<'a>,
+        function: &str,
+        arguments: &[Symbol],
+        layout: InLayout<'a>,
+    ) -> Symbol {
+        let expr = Expr::Call(Call {
+            call_type: CallType::Foreign {
+                foreign_symbol: ForeignSymbol::from(function),
+                ret_layout: layout,
+            },
+            arguments: self.arena.alloc_slice_copy(arguments),
+        });
+        self.let_expr(lets, "foreign", expr, layout)
+    }
+
+    fn offset_add(&mut self, lets: &mut Lets<'a>, offset: Symbol, extra: u32) -> Symbol {
+        if extra == 0 {
+            return offset;
+        }
+        let usize_layout = self.usize();
+        let extra = self.literal(lets, extra as u64, usize_layout);
+        self.lowlevel(lets, LowLevel::NumAdd, &[offset, extra], usize_layout)
+    }
+
+    /// Writes `value` into the buffer at `offset`
+    fn store(
+        &mut self,
+        lets: &mut Lets<'a>,
+        ptr: Symbol,
+        offset: Symbol,
+        value: Symbol,
+        layout: InLayout<'a>,
+    ) {
+        let usize_layout = self.usize();
+        let ptr_layout = self
+            .interner
+            .insert_direct_no_semantic(LayoutRepr::Ptr(layout));
+
+        let address = self.lowlevel(lets, LowLevel::NumAdd, &[ptr, offset], usize_layout);
+        let typed_ptr = self.lowlevel(lets, LowLevel::PtrCast, &[address], ptr_layout);
+        self.lowlevel(lets, LowLevel::PtrStore, &[typed_ptr, value], ptr_layout);
+    }
+
+    /// Clones `value`, calling another clone procedure if it needs one.
+    /// Returns the offset just after the last byte that was written.
+    fn clone_value(
+        &mut self,
+        lets: &mut Lets<'a>,
+        cursors: CloneCursors,
+        value: Symbol,
+        layout: InLayout<'a>,
+    ) -> Symbol {
+        let usize_layout = self.usize();
+        let repr = self.interner.get_repr(layout);
+
+        if repr.safe_to_memcpy(self.interner) {
+            self.store(lets, cursors.ptr, cursors.offset, value, layout);
+            return cursors.extra_offset;
+        }
+
+        let args = [cursors.ptr, cursors.offset, cursors.extra_offset, value];
+        match repr {
+            LayoutRepr::Builtin(Builtin::Str) => {
+                let args = [value, cursors.ptr, cursors.offset, cursors.extra_offset];
+                self.foreign(lets, bitcode::STR_CLONE_TO, &args, usize_layout)
+            }
+
+            LayoutRepr::Builtin(Builtin::List(elem)) if self.interner.safe_to_memcpy(elem) => {
+                let element_width = self.interner.stack_size(elem);
+                let element_width = self.literal(lets, element_width as u64, usize_layout);
+                let args = [
+                    value,
+                    cursors.ptr,
+                    cursors.offset,
+                    cursors.extra_offset,
+                    element_width,
+                ];
+                self.foreign(lets, bitcode::LIST_CLONE_TO, &args, usize_layout)
+            }
+
+            LayoutRepr::Struct(field_layouts) => {
+                self.clone_fields(lets, cursors, field_layouts, |index| Expr::StructAtIndex {
+                    index,
+                    field_layouts,
+                    structure: value,
+                })
+            }
+
+            // Since we will never actually display functions (and hence lambda sets)
+            // we just write nothing to the buffer
+            LayoutRepr::LambdaSet(_) => cursors.extra_offset,
+
+            LayoutRepr::Builtin(Builtin::List(_)) | LayoutRepr::Union(_) => {
+                self.call(lets, args, layout)
+            }
+
+            LayoutRepr::RecursivePointer(union_layout) => self.call(lets, args, union_layout),
+
+            LayoutRepr::Builtin(_) | LayoutRepr::Ptr(_) => {
+                internal_error!("{repr:?} is always safe to memcpy")
+            }
+            LayoutRepr::FunctionPointer(_) => todo_lambda_erasure!(),
+            LayoutRepr::Erased(_) => todo_lambda_erasure!(),
+        }
+    }
+
+    fn call(&mut self, lets: &mut Lets<'a>, args: [Symbol; 4], layout: InLayout<'a>) -> Symbol {
+        self.generate(layout);
+
+        let proc_layout = self.proc_layout(layout);
+        let expr = Expr::Call(Call {
+            call_type: CallType::ByName {
+                name: LambdaName::no_niche(self.clone_symbol),
+                ret_layout: proc_layout.result,
+                arg_layouts: proc_layout.arguments,
+                specialization_id: CallSpecId::BACKEND_DUMMY,
+            },
+            arguments: self.arena.alloc(args),
+        });
+        self.let_expr(lets, "new_offset", expr, proc_layout.result)
+    }
+
+    /// Clones the fields of a struct or tag payload, one after the other from `cursors.offset`
+    fn clone_fields<F>(
+        &mut self,
+        lets: &mut Lets<'a>,
+        cursors: CloneCursors,
+        field_layouts: &'a [InLayout<'a>],
+        load_field: F,
+    ) -> Symbol
+    where
+        F: Fn(u64) -> Expr<'a>,
+    {
+        let mut field_offset = 0;
+        let mut extra_offset = cursors.extra_offset;
+
+        for (index, field_layout) in field_layouts.iter().enumerate() {
+            let field = self.let_expr(lets, "field", load_field(index as u64), *field_layout);
+            let offset = self.offset_add(lets, cursors.offset, field_offset);
+            let field_cursors = CloneCursors {
+                offset,
+                extra_offset,
+                ..cursors
+            };
+            extra_offset = self.clone_value(lets, field_cursors, field, *field_layout);
+
+            field_offset += self.interner.stack_size(*field_layout);
+        }
+
+        extra_offset
+    }
+
+    fn clone_list(
+        &mut self,
+        cursors: CloneCursors,
+        list: Symbol,
+        elem_layout: InLayout<'a>,
+    ) -> Stmt<'a> {
+        let arena = self.arena;
+        let usize_layout = self.usize();
+        let ptr_size = self.target.ptr_size() as u32;
+        let mut lets = Lets::new();
+
+        // we only copy the elements we actually have (and skip extra capacity)
+        let len = self.lowlevel(&mut lets, LowLevel::ListLenUsize, &[list], usize_layout);
+        self.store(
+            &mut lets,
+            cursors.ptr,
+            cursors.offset,
+            cursors.extra_offset,
+            usize_layout,
+        );
+        for i in 1..=2 {
+            let offset = self.offset_add(&mut lets, cursors.offset, i * ptr_size);
+            self.store(&mut lets, cursors.ptr, offset, len, usize_layout);
+        }
+
+        // The elements go at the extra offset, and anything they point to goes after them
+        let element_width = self.interner.stack_size(elem_layout);
+        let element_width = self.literal(&mut lets, element_width as u64, usize_layout);
+        let elements_width = self.lowlevel(
+            &mut lets,
+            LowLevel::NumMul,
+            &[len, element_width],
+            usize_layout,
+        );
+        let rest_start = self.lowlevel(
+            &mut lets,
+            LowLevel::NumAdd,
+            &[cursors.extra_offset, elements_width],
+            usize_layout,
+        );
+        let zero = self.literal(&mut lets, 0, usize_layout);
+
+        // Loop over the elements
+        let elems_loop = JoinPointId(self.symbol("elems_loop"));
+        let index = self.symbol("index");
+        let rest_offset = self.symbol("rest_offset");
+
+        let mut loop_lets = Lets::new();
+        let is_end = self.lowlevel(
+            &mut loop_lets,
+            LowLevel::NumGte,
+            &[index, len],
+            Layout::BOOL,
+        );
+
+        let mut elem_lets = Lets::new();
+        let elem = self.lowlevel(
+            &mut elem_lets,
+            LowLevel::ListGetUnsafe,
+            &[list, index],
+            elem_layout,
+        );
+        let elem_offset = self.lowlevel(
+            &mut elem_lets,
+            LowLevel::NumMul,
+            &[index, element_width],
+            usize_layout,
+        );
+        let elem_offset = self.lowlevel(
+            &mut elem_lets,
+            LowLevel::NumAdd,
+            &[cursors.extra_offset, elem_offset],
+            usize_layout,
+        );
+        let elem_cursors = CloneCursors {
+            offset: elem_offset,
+            extra_offset: rest_offset,
+            ..cursors
+        };
+        let new_rest_offset = self.clone_value(&mut elem_lets, elem_cursors, elem, elem_layout);
+        let one = self.literal(&mut elem_lets, 1, usize_layout);
+        let next_index = self.lowlevel(
+            &mut elem_lets,
+            LowLevel::NumAdd,
+            &[index, one],
+            usize_layout,
+        );
+        let next_iteration = self.finish(
+            elem_lets,
+            Stmt::Jump(elems_loop, arena.alloc([next_index, new_rest_offset])),
+        );
+
+        let loop_body = self.finish(
+            loop_lets,
+            Stmt::if_then_else(
+                arena,
+                is_end,
+                usize_layout,
+                Stmt::Ret(rest_offset),
+                arena.alloc(next_iteration),
+            ),
+        );
+
+        let join = Stmt::Join {
+            id: elems_loop,
+            parameters: arena.alloc([
+                Param {
+                    symbol: index,
+                    layout: usize_layout,
+                },
+                Param {
+                    symbol: rest_offset,
+                    layout: usize_layout,
+                },
+            ]),
+            body: arena.alloc(loop_body),
+            remainder: arena.alloc(Stmt::Jump(elems_loop, arena.alloc([zero, rest_start]))),
+        };
+
+        self.finish(lets, join)
+    }
+
+    fn clone_union(
+        &mut self,
+        cursors: CloneCursors,
+        value: Symbol,
+        union_layout: UnionLayout<'a>,
+    ) -> Stmt<'a> {
+        let usize_layout = self.usize();
+        let mut lets = Lets::new();
+
+        match union_layout {
+            UnionLayout::NonRecursive(tags) => {
+                let tag_id_offset = union_layout.tag_id_offset(self.interner).unwrap();
+                let writes_tag_id = tags.len() > 1;
+                let branches = self.tag_branches(tags.len(), |procs, tag_id| {
+                    let mut lets = Lets::new();
+                    let new_offset =
+                        procs.clone_tag_fields(&mut lets, cursors, value, union_layout, tag_id);
+                    if writes_tag_id {
+                        let offset = procs.offset_add(&mut lets, cursors.offset, tag_id_offset);
+                        procs.store_tag_id(&mut lets, cursors.ptr, offset, union_layout, tag_id);
+                    }
+                    procs.finish(lets, Stmt::Ret(new_offset))
+                });
+                self.switch_on_tag_id(lets, value, union_layout, branches)
+            }
+
+            UnionLayout::Recursive(tags) => {
+                let branches = self.tag_branches(tags.len(), |procs, tag_id| {
+                    procs.clone_tag_on_heap(cursors, value, union_layout, tag_id)
+                });
+                self.switch_on_tag_id(lets, value, union_layout, branches)
+            }
+
+            UnionLayout::NonNullableUnwrapped(_) => {
+                self.clone_tag_on_heap(cursors, value, union_layout, 0)
+            }
+
+            UnionLayout::NullableWrapped {
+                nullable_id,
+                other_tags,
+            } => {
+                let branches = self.tag_branches(other_tags.len() + 1, |procs, tag_id| {
+                    if tag_id == nullable_id {
+                        procs.clone_null(cursors)
+                    } else {
+                        procs.clone_tag_on_heap(cursors, value, union_layout, tag_id)
+                    }
+                });
+                self.switch_on_tag_id(lets, value, union_layout, branches)
+            }
+
+            UnionLayout::NullableUnwrapped { nullable_id, .. } => {
+                let other_id = !nullable_id as u16;
+                let null_branch = self.clone_null(cursors);
+                let other_branch = self.clone_tag_on_heap(cursors, value, union_layout, other_id);
+                let tag_id = self.let_expr(
+                    &mut lets,
+                    "tag_id",
+                    Expr::GetTagId {
+                        structure: value,
+                        union_layout,
+                    },
+                    union_layout.tag_id_layout(),
+                );
+                let (then_branch, else_branch) = if nullable_id {
+                    (null_branch, other_branch)
+                } else {
+                    (other_branch, null_branch)
+                };
+                let switch = Stmt::if_then_else(
+                    self.arena,
+                    tag_id,
+                    usize_layout,
+                    then_branch,
+                    self.arena.alloc(else_branch),
+                );
+                self.finish(lets, switch)
+            }
+        }
+    }
+
+    /// The statement for each tag id
+    fn tag_branches<F>(&mut self, tag_count: usize, mut branch: F) -> std::vec::Vec<(u64, Stmt<'a>)>
+    where
+        F: FnMut(&mut Self, u16) -> Stmt<'a>,
+    {
+        (0..tag_count)
+            .map(|tag_id| (tag_id as u64, branch(self, tag_id as u16)))
+            .collect()
+    }
+
+    fn switch_on_tag_id(
+        &mut self,
+        mut lets: Lets<'a>,
+        value: Symbol,
+        union_layout: UnionLayout<'a>,
+        mut branches: std::vec::Vec<(u64, Stmt<'a>)>,
+    ) -> Stmt<'a> {
+        let tag_id_layout = union_layout.tag_id_layout();
+        let tag_id = self.let_expr(
+            &mut lets,
+            "tag_id",
+            Expr::GetTagId {
+                structure: value,
+                union_layout,
+            },
+            tag_id_layout,
+        );
+
+        let (_, default_branch) = branches
+            .pop()
+            .unwrap_or_else(|| internal_error!("cannot clone an empty tag union"));
+        let branches = self.arena.alloc_slice_fill_iter(
+            branches
+                .into_iter()
+                .map(|(id, stmt)| (id, BranchInfo::None, stmt)),
+        );
+
+        let switch = Stmt::Switch {
+            cond_symbol: tag_id,
+            cond_layout: tag_id_layout,
+            branches,
+            default_branch: (BranchInfo::None, self.arena.alloc(default_branch)),
+            ret_layout: self.usize(),
+        };
+        self.finish(lets, switch)
+    }
+
+    /// Clones the fields of a tag, one after the other from `cursors.offset`
+    fn clone_tag_fields(
+        &mut self,
+        lets: &mut Lets<'a>,
+        cursors: CloneCursors,
+        value: Symbol,
+        union_layout: UnionLayout<'a>,
+        tag_id: u16,
+    ) -> Symbol {
+        let field_layouts = match union_layout {
+            UnionLayout::NonRecursive(tags) | UnionLayout::Recursive(tags) => tags[tag_id as usize],
+            UnionLayout::NonNullableUnwrapped(fields) => fields,
+            UnionLayout::NullableWrapped {
+                nullable_id,
+                other_tags,
+            } => {
+                if tag_id > nullable_id {
+                    other_tags[tag_id as usize - 1]
+                } else {
+                    other_tags[tag_id as usize]
+                }
+            }
+            UnionLayout::NullableUnwrapped { other_fields, .. } => other_fields,
+        };
+
+        self.clone_fields(lets, cursors, field_layouts, |index| Expr::UnionAtIndex {
+            structure: value,
+            tag_id,
+            union_layout,
+            index,
+        })
+    }
+
+    /// Writes the "pointer" to the tag's data at `cursors.offset`,
+    /// and then the data itself at `cursors.extra_offset`
+    fn clone_tag_on_heap(
+        &mut self,
+        cursors: CloneCursors,
+        value: Symbol,
+        union_layout: UnionLayout<'a>,
+        tag_id: u16,
+    ) -> Stmt<'a> {
+        let usize_layout = self.usize();
+        let mut lets = Lets::new();
+
+        if union_layout.stores_tag_id_in_pointer(self.target) {
+            let tag_id = self.literal(&mut lets, tag_id as u64, Layout::U32);
+            self.store(&mut lets, cursors.ptr, cursors.offset, tag_id, Layout::U32);
+
+            let offset = self.offset_add(&mut lets, cursors.offset, 4);
+            let data_offset = self.lowlevel(
+                &mut lets,
+                LowLevel::NumIntCast,
+                &[cursors.extra_offset],
+                Layout::U32,
+            );
+            self.store(&mut lets, cursors.ptr, offset, data_offset, Layout::U32);
+        } else {
+            self.store(
+                &mut lets,
+                cursors.ptr,
+                cursors.offset,
+                cursors.extra_offset,
+                usize_layout,
+            );
+        }
+
+        let (data_width, _) = union_layout.data_size_and_alignment(self.interner);
+        let data_cursors = CloneCursors {
+            offset: cursors.extra_offset,
+            extra_offset: self.offset_add(&mut lets, cursors.extra_offset, data_width),
+            ..cursors
+        };
+        let new_offset =
+            self.clone_tag_fields(&mut lets, data_cursors, value, union_layout, tag_id);
+
+        if union_layout.stores_tag_id_as_data(self.target) {
+            let tag_id_offset = union_layout.tag_id_offset(self.interner).unwrap();
+            let offset = self.offset_add(&mut lets, data_cursors.offset, tag_id_offset);
+            self.store_tag_id(&mut lets, cursors.ptr, offset, union_layout, tag_id);
+        }
+
+        self.finish(lets, Stmt::Ret(new_offset))
+    }
+
+    /// Writes a null pointer at `cursors.offset`
+    fn clone_null(&mut self, cursors: CloneCursors) -> Stmt<'a> {
+        let usize_layout = self.usize();
+        let mut lets = Lets::new();
+
+        let null = self.literal(&mut lets, 0, usize_layout);
+        self.store(&mut lets, cursors.ptr, cursors.offset, null, usize_layout);
+
+        self.finish(lets, Stmt::Ret(cursors.extra_offset))
+    }
+
+    fn store_tag_id(
+        &mut self,
+        lets: &mut Lets<'a>,
+        ptr: Symbol,
+        offset: Symbol,
+        union_layout: UnionLayout<'a>,
+        tag_id: u16,
+    ) {
+        let tag_id_layout = union_layout.tag_id_layout();
+        let tag_id = self.literal(lets, tag_id as u64, tag_id_layout);
+        self.store(lets, ptr, offset, tag_id, tag_id_layout);
+    }
+}
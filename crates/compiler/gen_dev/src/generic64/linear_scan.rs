@@ -0,0 +1,503 @@
+//! A liveness-based linear-scan register allocator.
+//!
+//! By default the storage manager hands out registers greedily as values are defined, and spills the
+//! oldest value once it runs out. This pass looks at the whole procedure up front instead: it numbers
+//! the statements in the order `build_stmt` visits them, turns the last seen map into a live interval
+//! for every symbol that fits in a register, and runs linear scan over those intervals.
+//! Intervals that span a call are only given callee-saved registers, so they survive the call
+//! instead of being pushed to the stack by `push_used_caller_saved_regs_to_stack`.
+//!
+//! The result is a set of register hints. The storage manager stays in charge of the actual
+//! register state and falls back to its greedy choice whenever a hinted register is taken.
+
+use crate::{
+    pointer_layouts, single_register_floats, single_register_int_builtins, single_register_integers,
+};
+use bumpalo::collections::Vec;
+use roc_collections::all::{MutMap, MutSet};
+use roc_module::low_level::LowLevel;
+use roc_module::symbol::Symbol;
+use roc_mono::ir::{Call, CallType, Expr, Stmt};
+use roc_mono::layout::{InLayout, LayoutInterner, LayoutRepr, STLayoutInterner, UnionLayout};
+use std::cmp::Reverse;
+
+use super::storage::RegStorage;
+use super::{Assembler, CallConv, RegTrait};
+
+/// The number of caller-saved registers that are kept out of the allocation, so temporaries and
+/// values without a hint can be placed without displacing an allocated value.
+const RESERVED_GENERAL_REGS: usize = 3;
+const RESERVED_FLOAT_REGS: usize = 2;
+
+/// The number of most recently claimed registers that are never picked as spill victims.
+/// The instruction that is currently being built may still use them.
+const RECENTLY_CLAIMED_REGS: usize = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RegClass {
+    General,
+    Float,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct LiveInterval {
+    symbol: Symbol,
+    class: RegClass,
+    start: u32,
+    end: u32,
+}
+
+/// The registers picked by the linear-scan allocator for a single procedure.
+#[derive(Debug, Clone)]
+pub(crate) struct RegisterHints<GeneralReg: RegTrait, FloatReg: RegTrait> {
+    hints: MutMap<Symbol, RegStorage<GeneralReg, FloatReg>>,
+    interval_ends: MutMap<Symbol, u32>,
+    general_pool: MutSet<GeneralReg>,
+    float_pool: MutSet<FloatReg>,
+}
+
+impl<GeneralReg: RegTrait, FloatReg: RegTrait> RegisterHints<GeneralReg, FloatReg> {
+    pub fn general_hint(&self, sym: &Symbol) -> Option<GeneralReg> {
+        match self.hints.get(sym) {
+            Some(RegStorage::General(reg)) => Some(*reg),
+            _ => None,
+        }
+    }
+
+    pub fn float_hint(&self, sym: &Symbol) -> Option<FloatReg> {
+        match self.hints.get(sym) {
+            Some(RegStorage::Float(reg)) => Some(*reg),
+            _ => None,
+        }
+    }
+
+    /// Returns true if the allocator may hand out this register to a symbol.
+    pub fn in_general_pool(&self, reg: &GeneralReg) -> bool {
+        self.general_pool.contains(reg)
+    }
+
+    /// Returns true if the allocator may hand out this register to a symbol.
+    pub fn in_float_pool(&self, reg: &FloatReg) -> bool {
+        self.float_pool.contains(reg)
+    }
+
+    /// Picks the value to spill when the storage manager runs out of registers:
+    /// the one whose live interval ends furthest away, favoring older values on ties.
+    /// Values without an interval (temporaries) are spilled last.
+    pub fn spill_index<Reg>(&self, used_regs: &[(Reg, Symbol)]) -> usize {
+        let candidates = used_regs.len().saturating_sub(RECENTLY_CLAIMED_REGS).max(1);
+
+        (0..candidates)
+            .max_by_key(|i| {
+                let end = self.interval_ends.get(&used_regs[*i].1).copied();
+                (end.unwrap_or(0), Reverse(*i))
+            })
+            .unwrap_or(0)
+    }
+}
+
+/// Computes register hints for a procedure with the given arguments and body.
+/// `last_seen` must be the map produced by `scan_ast` for the same body.
+pub(crate) fn allocate_registers<'a, GeneralReg, FloatReg, ASM, CC>(
+    arena: &'a bumpalo::Bump,
+    layout_interner: &STLayoutInterner<'a>,
+    args: &'a [(InLayout<'a>, Symbol)],
+    body: &'a Stmt<'a>,
+    last_seen: &MutMap<Symbol, *const Stmt<'a>>,
+) -> RegisterHints<GeneralReg, FloatReg>
+where
+    GeneralReg: RegTrait,
+    FloatReg: RegTrait,
+    ASM: Assembler<GeneralReg, FloatReg>,
+    CC: CallConv<GeneralReg, FloatReg, ASM>,
+{
+    let mut numbering = StmtNumbering {
+        layout_interner,
+        next_position: 0,
+        positions: MutMap::default(),
+        definitions: Vec::new_in(arena),
+        register_symbols: MutSet::default(),
+        calls: Vec::new_in(arena),
+    };
+
+    let entry = numbering.next_position();
+    for (layout, symbol) in args {
+        numbering.define(*symbol, *layout, entry);
+    }
+    numbering.visit(body);
+
+    // Definitions are pushed in increasing position, so the intervals are already sorted by start.
+    let mut intervals = Vec::with_capacity_in(numbering.definitions.len(), arena);
+    let mut interval_ends = MutMap::default();
+    for (symbol, class, start) in numbering.definitions.iter().copied() {
+        let end = last_seen
+            .get(&symbol)
+            .and_then(|stmt| numbering.positions.get(stmt))
+            .copied()
+            .unwrap_or(start)
+            .max(start);
+
+        interval_ends.insert(symbol, end);
+        intervals.push(LiveInterval {
+            symbol,
+            class,
+            start,
+            end,
+        });
+    }
+
+    let general_pool = allocation_pool(
+        CC::GENERAL_DEFAULT_FREE_REGS,
+        CC::general_caller_saved,
+        RESERVED_GENERAL_REGS,
+        arena,
+    );
+    let float_pool = allocation_pool(
+        CC::FLOAT_DEFAULT_FREE_REGS,
+        CC::float_caller_saved,
+        RESERVED_FLOAT_REGS,
+        arena,
+    );
+
+    let mut hints = MutMap::default();
+
+    for (symbol, reg) in linear_scan(
+        arena,
+        &intervals,
+        RegClass::General,
+        &general_pool,
+        CC::general_callee_saved,
+        &numbering.calls,
+    ) {
+        hints.insert(symbol, RegStorage::General(reg));
+    }
+
+    for (symbol, reg) in linear_scan(
+        arena,
+        &intervals,
+        RegClass::Float,
+        &float_pool,
+        CC::float_callee_saved,
+        &numbering.calls,
+    ) {
+        hints.insert(symbol, RegStorage::Float(reg));
+    }
+
+    RegisterHints {
+        hints,
+        interval_ends,
+        general_pool: general_pool.iter().copied().collect(),
+        float_pool: float_pool.iter().copied().collect(),
+    }
+}
+
+/// Returns the registers the allocator may hand out, most preferred first.
+/// The default free regs list the most preferred register last, and the first few caller-saved
+/// registers in that order are held back for temporaries.
+fn allocation_pool<'a, Reg: RegTrait>(
+    default_free_regs: &[Reg],
+    caller_saved: fn(&Reg) -> bool,
+    reserved: usize,
+    arena: &'a bumpalo::Bump,
+) -> Vec<'a, Reg> {
+    let mut pool = Vec::with_capacity_in(default_free_regs.len(), arena);
+    let mut still_reserved = reserved;
+
+    for reg in default_free_regs.iter().rev() {
+        if still_reserved > 0 && caller_saved(reg) {
+            still_reserved -= 1;
+        } else {
+            pool.push(*reg);
+        }
+    }
+
+    pool
+}
+
+/// Classic linear scan over the intervals of one register class.
+/// When no register is free, the interval that ends last is the one left without a register.
+fn linear_scan<'a, Reg: RegTrait>(
+    arena: &'a bumpalo::Bump,
+    intervals: &[LiveInterval],
+    class: RegClass,
+    pool: &[Reg],
+    callee_saved: fn(&Reg) -> bool,
+    calls: &[u32],
+) -> Vec<'a, (Symbol, Reg)> {
+    let mut assigned: Vec<'a, (Symbol, Reg)> = Vec::new_in(arena);
+    let mut active: Vec<'a, (u32, Reg, Symbol)> = Vec::new_in(arena);
+    let mut free: Vec<'a, Reg> = Vec::from_iter_in(pool.iter().copied(), arena);
+
+    for interval in intervals.iter().filter(|interval| interval.class == class) {
+        // A value that dies at the statement defining this one is still in its register while
+        // that statement is built, so only strictly earlier intervals are expired.
+        active.retain(|(end, reg, _)| {
+            let expired = *end < interval.start;
+            if expired {
+                free.push(*reg);
+            }
+            !expired
+        });
+
+        let crosses_call = calls
+            .iter()
+            .any(|call| interval.start < *call && *call < interval.end);
+        let allowed = |reg: &Reg| !crosses_call || callee_saved(reg);
+
+        if let Some(reg) = pool
+            .iter()
+            .copied()
+            .find(|reg| allowed(reg) && free.contains(reg))
+        {
+            free.retain(|free_reg| *free_reg != reg);
+            active.push((interval.end, reg, interval.symbol));
+            assigned.push((interval.symbol, reg));
+            continue;
+        }
+
+        let victim = active
+            .iter()
+            .enumerate()
+            .filter(|(_, (_, reg, _))| allowed(reg))
+            .max_by_key(|(_, (end, _, _))| *end)
+            .map(|(index, _)| index);
+
+        if let Some(index) = victim {
+            let (end, reg, symbol) = active[index];
+            if end > interval.end {
+                assigned.retain(|(assigned_symbol, _)| *assigned_symbol != symbol);
+                active[index] = (interval.end, reg, interval.symbol);
+                assigned.push((interval.symbol, reg));
+            }
+        }
+    }
+
+    assigned
+}
+
+/// Numbers statements in the order that `build_stmt` generates code for them.
+struct StmtNumbering<'a, 'i> {
+    layout_interner: &'i STLayoutInterner<'a>,
+    next_position: u32,
+    positions: MutMap<*const Stmt<'a>, u32>,
+    definitions: Vec<'a, (Symbol, RegClass, u32)>,
+    register_symbols: MutSet<Symbol>,
+    /// Positions of statements that call out to another function.
+    calls: Vec<'a, u32>,
+}
+
+impl<'a, 'i> StmtNumbering<'a, 'i> {
+    fn next_position(&mut self) -> u32 {
+        let position = self.next_position;
+        self.next_position += 1;
+        position
+    }
+
+    fn enter(&mut self, stmt: &'a Stmt<'a>) -> u32 {
+        let position = self.next_position();
+        self.positions.insert(stmt as *const Stmt<'a>, position);
+        position
+    }
+
+    fn reg_class(&self, layout: InLayout<'a>) -> Option<RegClass> {
+        match self.layout_interner.get_repr(layout) {
+            single_register_floats!() => Some(RegClass::Float),
+            single_register_integers!() | pointer_layouts!() => Some(RegClass::General),
+            LayoutRepr::LambdaSet(lambda_set) => {
+                self.reg_class(lambda_set.runtime_representation())
+            }
+            _ => None,
+        }
+    }
+
+    fn define(&mut self, symbol: Symbol, layout: InLayout<'a>, position: u32) {
+        if let Some(class) = self.reg_class(layout) {
+            self.definitions.push((symbol, class, position));
+            self.register_symbols.insert(symbol);
+        }
+    }
+
+    /// Low levels on register-sized values are generated inline; everything else may call a builtin.
+    fn is_call(&self, call: &Call<'a>, ret_layout: InLayout<'a>) -> bool {
+        match call.call_type {
+            CallType::LowLevel { op, .. } => {
+                let inline_op = matches!(
+                    op,
+                    LowLevel::NumAdd
+                        | LowLevel::NumAddWrap
+                        | LowLevel::NumSub
+                        | LowLevel::NumSubWrap
+                        | LowLevel::NumMul
+                        | LowLevel::NumMulWrap
+                        | LowLevel::NumGt
+                        | LowLevel::NumGte
+                        | LowLevel::NumLt
+                        | LowLevel::NumLte
+                        | LowLevel::NumBitwiseAnd
+                        | LowLevel::NumBitwiseXor
+                        | LowLevel::NumBitwiseOr
+                        | LowLevel::NumShiftLeftBy
+                        | LowLevel::NumShiftRightBy
+                        | LowLevel::NumShiftRightZfBy
+                        | LowLevel::NumIntCast
+                        | LowLevel::Eq
+                        | LowLevel::NotEq
+                        | LowLevel::And
+                        | LowLevel::Or
+                        | LowLevel::Not
+                        | LowLevel::PtrLoad
+                );
+
+                let register_sized = self.reg_class(ret_layout).is_some()
+                    && call
+                        .arguments
+                        .iter()
+                        .all(|argument| self.register_symbols.contains(argument));
+
+                !(inline_op && register_sized)
+            }
+            CallType::ByName { .. }
+            | CallType::ByPointer { .. }
+            | CallType::Foreign { .. }
+            | CallType::HigherOrder(_) => true,
+        }
+    }
+
+    fn visit(&mut self, stmt: &'a Stmt<'a>) {
+        match stmt {
+            Stmt::Let(symbol, expr, layout, following) => {
+                let position = self.enter(stmt);
+                if let Expr::Call(call) = expr {
+                    if self.is_call(call, *layout) {
+                        self.calls.push(position);
+                    }
+                }
+                self.define(*symbol, *layout, position);
+                self.visit(following);
+            }
+            Stmt::Switch {
+                branches,
+                default_branch,
+                ..
+            } => {
+                for (_, _, branch) in branches.iter() {
+                    self.visit(branch);
+                }
+                self.visit(default_branch.1);
+
+                // The condition stays in its register until every branch has been built.
+                self.enter(stmt);
+            }
+            Stmt::Ret(_) | Stmt::Jump(_, _) => {
+                self.enter(stmt);
+            }
+            Stmt::SourceRegion { remainder, .. } => {
+                // Generates no code
+                self.visit(remainder);
+            }
+            Stmt::Crash(_, _) => {
+                let position = self.enter(stmt);
+                self.calls.push(position);
+            }
+            Stmt::Refcounting(_, remainder)
+            | Stmt::Dbg { remainder, .. }
+            | Stmt::Expect { remainder, .. }
+            | Stmt::ExpectFx { remainder, .. } => {
+                let position = self.enter(stmt);
+                self.calls.push(position);
+                self.visit(remainder);
+            }
+            Stmt::Join {
+                parameters,
+                body,
+                remainder,
+                ..
+            } => {
+                let start = self.next_position();
+                for param in parameters.iter() {
+                    self.define(param.symbol, param.layout, start);
+                }
+
+                // Same order as `build_join`: the remainder first, then the body.
+                self.visit(remainder);
+                self.visit(body);
+
+                // Symbols used in the body are last seen at the join itself,
+                // which keeps them alive for every jump back into the body.
+                self.enter(stmt);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generic64::x86_64::{
+        X86_64Assembler, X86_64FloatReg, X86_64GeneralReg, X86_64SystemV,
+    };
+
+    fn general_callee_saved(reg: &X86_64GeneralReg) -> bool {
+        <X86_64SystemV as CallConv<X86_64GeneralReg, X86_64FloatReg, X86_64Assembler>>::general_callee_saved(reg)
+    }
+
+    fn interval(symbol: Symbol, start: u32, end: u32) -> LiveInterval {
+        LiveInterval {
+            symbol,
+            class: RegClass::General,
+            start,
+            end,
+        }
+    }
+
+    #[test]
+    fn values_live_across_calls_get_callee_saved_regs() {
+        let arena = bumpalo::Bump::new();
+        let pool = [X86_64GeneralReg::RAX, X86_64GeneralReg::RBX];
+        let intervals = [
+            interval(Symbol::DEV_TMP, 0, 4),
+            interval(Symbol::DEV_TMP2, 1, 2),
+        ];
+
+        let assigned = linear_scan(
+            &arena,
+            &intervals,
+            RegClass::General,
+            &pool,
+            general_callee_saved,
+            &[3],
+        );
+
+        assert_eq!(
+            assigned.as_slice(),
+            &[
+                (Symbol::DEV_TMP, X86_64GeneralReg::RBX),
+                (Symbol::DEV_TMP2, X86_64GeneralReg::RAX),
+            ]
+        );
+    }
+
+    #[test]
+    fn longest_interval_loses_its_register() {
+        let arena = bumpalo::Bump::new();
+        let pool = [X86_64GeneralReg::RAX];
+        let intervals = [
+            interval(Symbol::DEV_TMP, 0, 10),
+            interval(Symbol::DEV_TMP2, 1, 2),
+        ];
+
+        let assigned = linear_scan(
+            &arena,
+            &intervals,
+            RegClass::General,
+            &pool,
+            general_callee_saved,
+            &[],
+        );
+
+        assert_eq!(
+            assigned.as_slice(),
+            &[(Symbol::DEV_TMP2, X86_64GeneralReg::RAX)]
+        );
+    }
+}
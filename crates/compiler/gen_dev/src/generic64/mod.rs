@@ -1,6 +1,6 @@
 use crate::{
-    pointer_layouts, single_register_floats, single_register_int_builtins,
-    single_register_integers, Backend, Env, Relocation,
+    expect, pointer_layouts, single_register_floats, single_register_int_builtins,
+    single_register_integers, Backend, Env, RegisterAllocation, Relocation,
 };
 use bumpalo::collections::{CollectIn, Vec};
 use roc_builtins::bitcode::{self, FloatWidth, IntWidth};
@@ -9,20 +9,22 @@ use roc_error_macros::{internal_error, todo_lambda_erasure};
 use roc_module::symbol::{Interns, ModuleId, Symbol};
 use roc_mono::code_gen_help::{CallerProc, CodeGenHelp, HelperOp};
 use roc_mono::ir::{
-    BranchInfo, HigherOrderLowLevel, JoinPointId, ListLiteralElement, Literal, Param, ProcLayout,
-    SelfRecursive, Stmt,
+    BranchInfo, HigherOrderLowLevel, JoinPointId, ListLiteralElement, Literal, LookupType, Param,
+    ProcLayout, SelfRecursive, Stmt,
 };
 use roc_mono::layout::{
     Builtin, InLayout, LambdaName, Layout, LayoutIds, LayoutInterner, LayoutRepr, STLayoutInterner,
     TagIdIntType, UnionLayout,
 };
 use roc_mono::low_level::HigherOrder;
+use roc_region::all::Region;
 use roc_target::Target;
 use std::marker::PhantomData;
 
 pub(crate) mod aarch64;
 #[cfg(test)]
 mod disassembler_test_macro;
+pub(crate) mod linear_scan;
 pub(crate) mod storage;
 pub(crate) mod x86_64;
 
@@ -557,6 +559,18 @@ pub trait Assembler<GeneralReg: RegTrait, FloatReg: RegTrait>: Sized + Copy {
     fn sqrt_freg32_freg32(buf: &mut Vec<'_, u8>, dst: FloatReg, src: FloatReg);
 
     fn neg_reg64_reg64(buf: &mut Vec<'_, u8>, dst: GeneralReg, src: GeneralReg);
+    fn neg_freg64_freg64(
+        buf: &mut Vec<'_, u8>,
+        relocs: &mut Vec<'_, Relocation>,
+        dst: FloatReg,
+        src: FloatReg,
+    );
+    fn neg_freg32_freg32(
+        buf: &mut Vec<'_, u8>,
+        relocs: &mut Vec<'_, Relocation>,
+        dst: FloatReg,
+        src: FloatReg,
+    );
     fn mul_freg32_freg32_freg32(
         buf: &mut Vec<'_, u8>,
         dst: FloatReg,
@@ -897,6 +911,20 @@ impl<
         self.storage_manager.reset();
     }
 
+    fn allocate_registers(&mut self, args: &'a [(InLayout<'a>, Symbol)], body: &'a Stmt<'a>) {
+        if let RegisterAllocation::LinearScan = self.env.register_allocation {
+            let hints = linear_scan::allocate_registers::<GeneralReg, FloatReg, ASM, CC>(
+                self.env.arena,
+                self.layout_interner,
+                args,
+                body,
+                &self.last_seen_map,
+            );
+
+            self.storage_manager.set_register_hints(hints);
+        }
+    }
+
     fn literal_map(&mut self) -> &mut MutMap<Symbol, (*const Literal<'a>, *const InLayout<'a>)> {
         &mut self.literal_map
     }
@@ -1311,13 +1339,69 @@ impl<
         }
     }
 
+    fn build_expect(
+        &mut self,
+        condition: Symbol,
+        region: Region,
+        lookups: &'a [Symbol],
+        variables: &'a [LookupType],
+        notify_parent: bool,
+    ) {
+        // The failure branch calls functions that may clobber registers, but the code after the
+        // expect runs whether or not that branch was taken. Free everything to the stack so both
+        // paths agree on where each symbol lives.
+        self.storage_manager.free_all_to_stack(&mut self.buf);
+
+        // this state is updated destructively in the failure branch. We don't want that branch
+        // to influence the code after the expect, so we must clone here.
+        let mut base_storage = self.storage_manager.clone();
+        let base_literal_map = self.literal_map.clone();
+
+        let cond_reg = self
+            .storage_manager
+            .load_to_general_reg(&mut self.buf, &condition);
+
+        // Create jump past the failure branch if the condition is true.
+        // Since we don't know the offset yet, set it to 0 and overwrite later.
+        let jne_location = self.buf.len();
+        let start_offset =
+            ASM::jne_reg64_imm64_imm32(&mut self.buf, &mut self.storage_manager, cond_reg, 0, 0);
+
+        let shared_memory =
+            expect::clone_to_shared_memory(self, condition, region, lookups, variables);
+
+        if notify_parent {
+            expect::notify_parent_expect(self, shared_memory);
+        }
+
+        // Overwrite the original jne with the correct offset.
+        let end_offset = self.buf.len();
+        let jne_offset = end_offset - start_offset;
+        let mut tmp = bumpalo::vec![in self.env.arena];
+        ASM::jne_reg64_imm64_imm32(
+            &mut tmp,
+            &mut self.storage_manager,
+            cond_reg,
+            0,
+            jne_offset as i32,
+        );
+        for (i, byte) in tmp.iter().enumerate() {
+            self.buf[jne_location + i] = *byte;
+        }
+
+        // Update important storage information to avoid overwrites.
+        base_storage.update_stack_size(self.storage_manager.stack_size());
+        base_storage.update_fn_call_stack_size(self.storage_manager.fn_call_stack_size());
+        base_storage
+            .used_callee_saved_regs
+            .extend(&self.storage_manager.used_callee_saved_regs);
+
+        self.storage_manager = base_storage;
+        self.literal_map = base_literal_map;
+    }
+
     fn build_num_abs(&mut self, dst: &Symbol, src: &Symbol, layout: &InLayout<'a>) {
         match self.interner().get_repr(*layout) {
-            LayoutRepr::Builtin(Builtin::Int(IntWidth::I64 | IntWidth::U64)) => {
-                let dst_reg = self.storage_manager.claim_general_reg(&mut self.buf, dst);
-                let src_reg = self.storage_manager.load_to_general_reg(&mut self.buf, src);
-                ASM::abs_reg64_reg64(&mut self.buf, dst_reg, src_reg);
-            }
             LayoutRepr::Builtin(Builtin::Float(FloatWidth::F64)) => {
                 let dst_reg = self.storage_manager.claim_float_reg(&mut self.buf, dst);
                 let src_reg = self.storage_manager.load_to_float_reg(&mut self.buf, src);
@@ -1328,7 +1412,21 @@ impl<
                 let src_reg = self.storage_manager.load_to_float_reg(&mut self.buf, src);
                 ASM::abs_freg32_freg32(&mut self.buf, &mut self.relocs, dst_reg, src_reg);
             }
-            x => todo!("NumAbs: layout, {:?}", x),
+            LayoutRepr::Builtin(Builtin::Int(int_width)) => self.build_fn_call(
+                dst,
+                bitcode::NUM_ABS_OR_PANIC_INT[int_width].to_string(),
+                &[*src],
+                &[*layout],
+                layout,
+            ),
+            LayoutRepr::Builtin(Builtin::Decimal) => self.build_fn_call(
+                dst,
+                bitcode::DEC_ABS.to_string(),
+                &[*src],
+                &[*layout],
+                layout,
+            ),
+            x => internal_error!("NumAbs is not defined for {:?}", x),
         }
     }
 
@@ -1407,6 +1505,16 @@ impl<
                 &Layout::DEC,
             ),
 
+            LayoutRepr::Builtin(Builtin::Int(int_width @ (IntWidth::I128 | IntWidth::U128))) => {
+                self.build_fn_call(
+                    dst,
+                    bitcode::NUM_ADD_WRAP_INT[int_width].to_string(),
+                    &[*src1, *src2],
+                    &[*layout, *layout],
+                    layout,
+                );
+            }
+
             other => unreachable!("NumAddWrap for layout {other:?}"),
         }
     }
@@ -1419,7 +1527,7 @@ impl<
         layout: InLayout<'a>,
     ) {
         match self.layout_interner.get_repr(layout) {
-            LayoutRepr::Builtin(Builtin::Int(width @ quadword_and_smaller!())) => {
+            LayoutRepr::Builtin(Builtin::Int(width)) => {
                 let intrinsic = bitcode::NUM_ADD_SATURATED_INT[width].to_string();
                 self.build_fn_call(&dst, intrinsic, &[src1, src2], &[layout, layout], &layout);
             }
@@ -1439,7 +1547,7 @@ impl<
                 let intrinsic = bitcode::DEC_ADD_SATURATED.to_string();
                 self.build_fn_call(&dst, intrinsic, &[src1, src2], &[layout, layout], &layout);
             }
-            x => todo!("NumAddSaturated: layout, {:?}", x),
+            x => internal_error!("NumAddSaturated is not defined for {:?}", x),
         }
     }
 
@@ -1567,13 +1675,7 @@ impl<
                     src2_reg,
                 );
             }
-            LayoutRepr::Builtin(Builtin::Int(IntWidth::I128 | IntWidth::U128)) => {
-                let int_width = match *layout {
-                    Layout::I128 => IntWidth::I128,
-                    Layout::U128 => IntWidth::U128,
-                    _ => unreachable!(),
-                };
-
+            LayoutRepr::Builtin(Builtin::Int(int_width @ (IntWidth::I128 | IntWidth::U128))) => {
                 self.build_fn_call(
                     dst,
                     bitcode::NUM_MUL_WRAP_INT[int_width].to_string(),
@@ -1594,7 +1696,7 @@ impl<
                 let src2_reg = self.storage_manager.load_to_float_reg(&mut self.buf, src2);
                 ASM::mul_freg32_freg32_freg32(&mut self.buf, dst_reg, src1_reg, src2_reg);
             }
-            x => todo!("NumMulWrap: layout, {:?}", x),
+            x => internal_error!("NumMulWrap is not defined for {:?}", x),
         }
     }
 
@@ -1606,7 +1708,7 @@ impl<
         layout: InLayout<'a>,
     ) {
         match self.layout_interner.get_repr(layout) {
-            LayoutRepr::Builtin(Builtin::Int(width @ quadword_and_smaller!())) => {
+            LayoutRepr::Builtin(Builtin::Int(width)) => {
                 let intrinsic = bitcode::NUM_MUL_SATURATED_INT[width].to_string();
                 self.build_fn_call(&dst, intrinsic, &[src1, src2], &[layout, layout], &layout);
             }
@@ -1626,7 +1728,7 @@ impl<
                 let intrinsic = bitcode::DEC_MUL_SATURATED.to_string();
                 self.build_fn_call(&dst, intrinsic, &[src1, src2], &[layout, layout], &layout);
             }
-            x => todo!("NumMulSaturated: layout, {:?}", x),
+            x => internal_error!("NumMulSaturated is not defined for {:?}", x),
         }
     }
 
@@ -1706,6 +1808,15 @@ impl<
                 let src2_reg = self.storage_manager.load_to_float_reg(&mut self.buf, src2);
                 ASM::div_freg32_freg32_freg32(&mut self.buf, dst_reg, src1_reg, src2_reg);
             }
+            LayoutRepr::Builtin(Builtin::Int(int_width @ (IntWidth::I128 | IntWidth::U128))) => {
+                self.build_fn_call(
+                    dst,
+                    bitcode::NUM_DIV_TRUNC[int_width].to_string(),
+                    &[*src1, *src2],
+                    &[*layout, *layout],
+                    layout,
+                );
+            }
             LayoutRepr::Builtin(Builtin::Decimal) => {
                 self.build_fn_call(
                     dst,
@@ -1715,7 +1826,7 @@ impl<
                     layout,
                 );
             }
-            x => todo!("NumDiv: layout, {:?}", x),
+            x => internal_error!("NumDiv is not defined for {:?}", x),
         }
     }
 
@@ -1734,7 +1845,7 @@ impl<
                 &[*layout, *layout],
                 layout,
             ),
-            x => todo!("NumDivCeilUnchecked: layout, {:?}", x),
+            x => internal_error!("NumDivCeilUnchecked is not defined for {:?}", x),
         }
     }
 
@@ -1778,7 +1889,16 @@ impl<
                     src2_reg,
                 );
             }
-            x => todo!("NumDiv: layout, {:?}", x),
+            LayoutRepr::Builtin(Builtin::Int(int_width @ (IntWidth::I128 | IntWidth::U128))) => {
+                self.build_fn_call(
+                    dst,
+                    bitcode::NUM_REM[int_width].to_string(),
+                    &[*src1, *src2],
+                    &[*layout, *layout],
+                    layout,
+                );
+            }
+            x => internal_error!("NumRem is not defined for {:?}", x),
         }
     }
 
@@ -1789,7 +1909,31 @@ impl<
                 let src_reg = self.storage_manager.load_to_general_reg(&mut self.buf, src);
                 ASM::neg_reg64_reg64(&mut self.buf, dst_reg, src_reg);
             }
-            x => todo!("NumNeg: layout, {:?}", x),
+            LayoutRepr::Builtin(Builtin::Int(int_width)) => self.build_fn_call(
+                dst,
+                bitcode::NUM_NEG_OR_PANIC_INT[int_width].to_string(),
+                &[*src],
+                &[*layout],
+                layout,
+            ),
+            LayoutRepr::Builtin(Builtin::Float(FloatWidth::F64)) => {
+                let dst_reg = self.storage_manager.claim_float_reg(&mut self.buf, dst);
+                let src_reg = self.storage_manager.load_to_float_reg(&mut self.buf, src);
+                ASM::neg_freg64_freg64(&mut self.buf, &mut self.relocs, dst_reg, src_reg);
+            }
+            LayoutRepr::Builtin(Builtin::Float(FloatWidth::F32)) => {
+                let dst_reg = self.storage_manager.claim_float_reg(&mut self.buf, dst);
+                let src_reg = self.storage_manager.load_to_float_reg(&mut self.buf, src);
+                ASM::neg_freg32_freg32(&mut self.buf, &mut self.relocs, dst_reg, src_reg);
+            }
+            LayoutRepr::Builtin(Builtin::Decimal) => self.build_fn_call(
+                dst,
+                bitcode::DEC_NEGATE.to_string(),
+                &[*src],
+                &[*layout],
+                layout,
+            ),
+            x => internal_error!("NumNeg is not defined for {:?}", x),
         }
     }
 
@@ -1846,7 +1990,28 @@ impl<
                     .load_to_general_reg(&mut self.buf, src2);
                 ASM::sub_reg64_reg64_reg64(&mut self.buf, dst_reg, src1_reg, src2_reg);
             }
-            x => todo!("NumSubWrap: layout, {:?}", x),
+            LayoutRepr::Builtin(Builtin::Int(int_width @ (IntWidth::I128 | IntWidth::U128))) => {
+                self.build_fn_call(
+                    dst,
+                    bitcode::NUM_SUB_WRAP_INT[int_width].to_string(),
+                    &[*src1, *src2],
+                    &[*layout, *layout],
+                    layout,
+                );
+            }
+            LayoutRepr::Builtin(Builtin::Float(FloatWidth::F64)) => {
+                let dst_reg = self.storage_manager.claim_float_reg(&mut self.buf, dst);
+                let src1_reg = self.storage_manager.load_to_float_reg(&mut self.buf, src1);
+                let src2_reg = self.storage_manager.load_to_float_reg(&mut self.buf, src2);
+                ASM::sub_freg64_freg64_freg64(&mut self.buf, dst_reg, src1_reg, src2_reg);
+            }
+            LayoutRepr::Builtin(Builtin::Float(FloatWidth::F32)) => {
+                let dst_reg = self.storage_manager.claim_float_reg(&mut self.buf, dst);
+                let src1_reg = self.storage_manager.load_to_float_reg(&mut self.buf, src1);
+                let src2_reg = self.storage_manager.load_to_float_reg(&mut self.buf, src2);
+                ASM::sub_freg32_freg32_freg32(&mut self.buf, dst_reg, src1_reg, src2_reg);
+            }
+            x => internal_error!("NumSubWrap is not defined for {:?}", x),
         }
     }
 
@@ -4648,7 +4813,9 @@ impl<
         let buf = &mut self.buf;
 
         match int_width {
-            IntWidth::U128 | IntWidth::I128 => todo!(),
+            IntWidth::U128 | IntWidth::I128 => {
+                self.build_int_bitwise_128(dst, src1, src2, ASM::and_reg64_reg64_reg64)
+            }
             _ => {
                 let dst_reg = self.storage_manager.claim_general_reg(buf, dst);
                 let src1_reg = self.storage_manager.load_to_general_reg(buf, src1);
@@ -4668,7 +4835,9 @@ impl<
         let buf = &mut self.buf;
 
         match int_width {
-            IntWidth::U128 | IntWidth::I128 => todo!(),
+            IntWidth::U128 | IntWidth::I128 => {
+                self.build_int_bitwise_128(dst, src1, src2, ASM::or_reg64_reg64_reg64)
+            }
             _ => {
                 let dst_reg = self.storage_manager.claim_general_reg(buf, dst);
                 let src1_reg = self.storage_manager.load_to_general_reg(buf, src1);
@@ -4688,7 +4857,9 @@ impl<
         let buf = &mut self.buf;
 
         match int_width {
-            IntWidth::U128 | IntWidth::I128 => todo!(),
+            IntWidth::U128 | IntWidth::I128 => {
+                self.build_int_bitwise_128(dst, src1, src2, ASM::xor_reg64_reg64_reg64)
+            }
             _ => {
                 let dst_reg = self.storage_manager.claim_general_reg(buf, dst);
                 let src1_reg = self.storage_manager.load_to_general_reg(buf, src1);
@@ -4708,7 +4879,17 @@ impl<
         let buf = &mut self.buf;
 
         match int_width {
-            IntWidth::U128 | IntWidth::I128 => todo!(),
+            IntWidth::U128 | IntWidth::I128 => {
+                let layout = Layout::from_int_width(int_width);
+
+                self.build_fn_call(
+                    dst,
+                    bitcode::NUM_SHIFT_LEFT_BY[int_width].to_string(),
+                    &[*src1, *src2],
+                    &[layout, Layout::U8],
+                    &layout,
+                );
+            }
             _ => {
                 let dst_reg = self.storage_manager.claim_general_reg(buf, dst);
                 let src1_reg = self.storage_manager.load_to_general_reg(buf, src1);
@@ -4735,7 +4916,17 @@ impl<
         let buf = &mut self.buf;
 
         match int_width {
-            IntWidth::U128 | IntWidth::I128 => todo!(),
+            IntWidth::U128 | IntWidth::I128 => {
+                let layout = Layout::from_int_width(int_width);
+
+                self.build_fn_call(
+                    dst,
+                    bitcode::NUM_SHIFT_RIGHT_BY[int_width].to_string(),
+                    &[*src1, *src2],
+                    &[layout, Layout::U8],
+                    &layout,
+                );
+            }
             _ => {
                 let dst_reg = self.storage_manager.claim_general_reg(buf, dst);
                 let src1_reg = self.storage_manager.load_to_general_reg(buf, src1);
@@ -4857,48 +5048,91 @@ impl<
         let buf = &mut self.buf;
 
         match (source, target) {
-            (U128, U64) => {
+            (U128 | I128, U128 | I128) => {
+                let to_offset = self.storage_manager.claim_stack_area_layout(
+                    self.layout_interner,
+                    *dst,
+                    Layout::from_int_width(target),
+                );
+
+                let (from_offset, size) = self.storage_manager.stack_offset_and_size(src);
+
+                self.storage_manager
+                    .copy_to_stack_offset(buf, size, from_offset, to_offset);
+
+                return;
+            }
+            (U128 | I128, _) => {
                 let dst_reg = self.storage_manager.claim_general_reg(buf, dst);
 
                 let (offset, _size) = self.storage_manager.stack_offset_and_size(src);
 
-                ASM::mov_reg64_base32(buf, dst_reg, offset + 8);
+                // the lower 8 bytes hold the bits that we keep
+                ASM::mov_reg64_base32(buf, dst_reg, offset);
+
+                match target.stack_size() {
+                    4 => ASM::mov_reg_reg(buf, RegisterWidth::W32, dst_reg, dst_reg),
+                    2 => ASM::mov_reg_reg(buf, RegisterWidth::W16, dst_reg, dst_reg),
+                    1 => ASM::mov_reg_reg(buf, RegisterWidth::W8, dst_reg, dst_reg),
+                    _ => {}
+                }
 
                 return;
             }
-            (U64, U128) => {
+            (_, U128 | I128) => {
                 let src_reg = self.storage_manager.load_to_general_reg(buf, src);
 
                 let base_offset = self.storage_manager.claim_stack_area_layout(
                     self.layout_interner,
                     *dst,
-                    Layout::U128,
+                    Layout::from_int_width(target),
                 );
 
                 let tmp = Symbol::DEV_TMP;
                 let tmp_reg = self.storage_manager.claim_general_reg(buf, &tmp);
 
-                // move a zero into the lower 8 bytes
-                ASM::mov_reg64_imm64(buf, tmp_reg, 0x0);
-                ASM::mov_base32_reg64(buf, base_offset + 8, tmp_reg);
-
-                ASM::mov_base32_reg64(buf, base_offset, src_reg);
+                let register_width = match source.stack_size() {
+                    8 => RegisterWidth::W64,
+                    4 => RegisterWidth::W32,
+                    2 => RegisterWidth::W16,
+                    _ => RegisterWidth::W8,
+                };
 
-                self.free_symbol(&tmp);
+                if source.is_signed() {
+                    // sign-extend the lower 8 bytes
+                    match register_width {
+                        RegisterWidth::W64 => ASM::mov_reg64_reg64(buf, tmp_reg, src_reg),
+                        width => ASM::movsx_reg_reg(buf, width, tmp_reg, src_reg),
+                    }
+                    ASM::mov_base32_reg64(buf, base_offset, tmp_reg);
 
-                return;
-            }
-            (U128, I128) | (I128, U128) => {
-                let to_offset = self.storage_manager.claim_stack_area_layout(
-                    self.layout_interner,
-                    *dst,
-                    Layout::from_int_width(target),
-                );
+                    // the upper 8 bytes are all copies of the sign bit
+                    let shift = Symbol::DEV_TMP2;
+                    let shift_reg = self.storage_manager.claim_general_reg(buf, &shift);
+                    ASM::mov_reg64_imm64(buf, shift_reg, 63);
+                    ASM::sar_reg64_reg64_reg64(
+                        buf,
+                        &mut self.storage_manager,
+                        tmp_reg,
+                        tmp_reg,
+                        shift_reg,
+                    );
+                    ASM::mov_base32_reg64(buf, base_offset + 8, tmp_reg);
+                    self.storage_manager.free_symbol(&shift);
+                } else {
+                    // zero-extend the lower 8 bytes
+                    match register_width {
+                        RegisterWidth::W64 => ASM::mov_reg64_reg64(buf, tmp_reg, src_reg),
+                        width => ASM::movzx_reg_reg(buf, width, tmp_reg, src_reg),
+                    }
+                    ASM::mov_base32_reg64(buf, base_offset, tmp_reg);
 
-                let (from_offset, size) = self.storage_manager.stack_offset_and_size(src);
+                    // move a zero into the upper 8 bytes
+                    ASM::mov_reg64_imm64(buf, tmp_reg, 0x0);
+                    ASM::mov_base32_reg64(buf, base_offset + 8, tmp_reg);
+                }
 
-                self.storage_manager
-                    .copy_to_stack_offset(buf, size, from_offset, to_offset);
+                self.storage_manager.free_symbol(&tmp);
 
                 return;
             }
@@ -5045,6 +5279,25 @@ impl<
                 let int_width = arg_layout.to_int_width();
                 self.build_int_to_float_cast(dst, src, int_width, FloatWidth::F32);
             }
+            LayoutRepr::Builtin(Builtin::Decimal) => {
+                // There is no Dec to F32 builtin, so convert to F64 and demote that
+                let as_f64 = self.debug_symbol("dec_as_f64");
+                self.build_fn_call(
+                    &as_f64,
+                    bitcode::DEC_TO_F64.to_string(),
+                    &[*src],
+                    &[*arg_layout],
+                    &Layout::F64,
+                );
+
+                let dst_reg = self.storage_manager.claim_float_reg(&mut self.buf, dst);
+                let src_reg = self
+                    .storage_manager
+                    .load_to_float_reg(&mut self.buf, &as_f64);
+                ASM::to_float_freg32_freg64(&mut self.buf, dst_reg, src_reg);
+
+                self.free_symbol(&as_f64);
+            }
             arg => todo!("NumToFrac: layout, arg {arg:?}, ret {:?}", Layout::F32),
         }
     }
@@ -5070,6 +5323,15 @@ impl<
                 let int_width = arg_layout.to_int_width();
                 self.build_int_to_float_cast(dst, src, int_width, FloatWidth::F64);
             }
+            LayoutRepr::Builtin(Builtin::Decimal) => {
+                self.build_fn_call(
+                    dst,
+                    bitcode::DEC_TO_F64.to_string(),
+                    &[*src],
+                    &[*arg_layout],
+                    &Layout::F64,
+                );
+            }
             arg => todo!("NumToFrac: layout, arg {arg:?}, ret {:?}", Layout::F64),
         }
     }
@@ -5085,11 +5347,71 @@ impl<
                     &Layout::DEC,
                 );
             }
+            LayoutRepr::Builtin(Builtin::Float(float_width)) => {
+                self.build_fn_call(
+                    dst,
+                    bitcode::DEC_FROM_FLOAT[float_width].to_string(),
+                    &[*src],
+                    &[*arg_layout],
+                    &Layout::DEC,
+                );
+            }
+            LayoutRepr::Builtin(Builtin::Decimal) => {
+                let (from_offset, size) = self.storage_manager.stack_offset_and_size(src);
+                let to_offset = self.storage_manager.claim_stack_area_layout(
+                    self.layout_interner,
+                    *dst,
+                    Layout::DEC,
+                );
+
+                self.storage_manager.copy_to_stack_offset(
+                    &mut self.buf,
+                    size,
+                    from_offset,
+                    to_offset,
+                );
+            }
 
             arg => todo!("NumToFrac: layout, arg {arg:?}, ret {:?}", Layout::DEC),
         }
     }
 
+    /// Applies a 64-bit bitwise operation to both halves of a 128-bit integer
+    fn build_int_bitwise_128(
+        &mut self,
+        dst: &Symbol,
+        src1: &Symbol,
+        src2: &Symbol,
+        op: fn(&mut Vec<'a, u8>, GeneralReg, GeneralReg, GeneralReg),
+    ) {
+        let (src1_offset, _) = self.storage_manager.stack_offset_and_size(src1);
+        let (src2_offset, _) = self.storage_manager.stack_offset_and_size(src2);
+
+        let dst_offset =
+            self.storage_manager
+                .claim_stack_area_layout(self.layout_interner, *dst, Layout::U128);
+
+        let tmp1_symbol = self.debug_symbol("bitwise_tmp1");
+        let tmp2_symbol = self.debug_symbol("bitwise_tmp2");
+
+        let buf = &mut self.buf;
+
+        let tmp1 = self.storage_manager.claim_general_reg(buf, &tmp1_symbol);
+        let tmp2 = self.storage_manager.claim_general_reg(buf, &tmp2_symbol);
+
+        for half_offset in [0, 8] {
+            ASM::mov_reg64_base32(buf, tmp1, src1_offset + half_offset);
+            ASM::mov_reg64_base32(buf, tmp2, src2_offset + half_offset);
+
+            op(buf, tmp1, tmp1, tmp2);
+
+            ASM::mov_base32_reg64(buf, dst_offset + half_offset, tmp1);
+        }
+
+        self.storage_manager.free_symbol(&tmp1_symbol);
+        self.storage_manager.free_symbol(&tmp2_symbol);
+    }
+
     fn compare_128bit(
         &mut self,
         op: CompareOperation,
@@ -23,6 +23,7 @@ use RegStorage::*;
 use StackStorage::*;
 use Storage::*;
 
+use super::linear_scan::RegisterHints;
 use super::RegisterWidth;
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
@@ -120,6 +121,9 @@ pub struct StorageManager<
 
     pub(crate) used_callee_saved_regs: UsedCalleeRegisters<GeneralReg, FloatReg>,
 
+    // Register choices made by the linear-scan allocator for the current proc, if it is enabled.
+    register_hints: Option<Rc<RegisterHints<GeneralReg, FloatReg>>>,
+
     free_stack_chunks: Vec<'a, (i32, u32)>,
     stack_size: u32,
 
@@ -153,6 +157,7 @@ pub fn new_storage_manager<
         used_callee_saved_regs: UsedCalleeRegisters::default(),
         float_free_regs: bumpalo::vec![in env.arena],
         float_used_regs: bumpalo::vec![in env.arena],
+        register_hints: None,
         free_stack_chunks: bumpalo::vec![in env.arena],
         stack_size: 0,
         fn_call_stack_size: 0,
@@ -228,11 +233,18 @@ impl<
         self.float_free_regs
             .extend_from_slice(CC::FLOAT_DEFAULT_FREE_REGS);
         self.used_callee_saved_regs.clear();
+        self.register_hints = None;
         self.free_stack_chunks.clear();
         self.stack_size = 0;
         self.fn_call_stack_size = 0;
     }
 
+    /// Sets the register choices of the linear-scan allocator for the current proc.
+    /// They are cleared again by `reset`.
+    pub(crate) fn set_register_hints(&mut self, hints: RegisterHints<GeneralReg, FloatReg>) {
+        self.register_hints = Some(Rc::new(hints));
+    }
+
     pub fn stack_size(&self) -> u32 {
         self.stack_size
     }
@@ -252,13 +264,17 @@ impl<
     /// Get a general register from the free list.
     /// Will free data to the stack if necessary to get the register.
     fn get_general_reg(&mut self, buf: &mut Vec<'a, u8>) -> GeneralReg {
-        if let Some(reg) = self.general_free_regs.pop() {
+        if let Some(reg) = self.pop_free_general_reg() {
             if CC::general_callee_saved(&reg) {
                 self.used_callee_saved_regs.insert_general(reg);
             }
             reg
         } else if !self.general_used_regs.is_empty() {
-            let (reg, sym) = self.general_used_regs.remove(0);
+            let index = match &self.register_hints {
+                Some(hints) => hints.spill_index(&self.general_used_regs),
+                None => 0,
+            };
+            let (reg, sym) = self.general_used_regs.remove(index);
             self.free_to_stack(buf, &sym, General(reg));
             reg
         } else {
@@ -269,13 +285,17 @@ impl<
     /// Get a float register from the free list.
     /// Will free data to the stack if necessary to get the register.
     fn get_float_reg(&mut self, buf: &mut Vec<'a, u8>) -> FloatReg {
-        if let Some(reg) = self.float_free_regs.pop() {
+        if let Some(reg) = self.pop_free_float_reg() {
             if CC::float_callee_saved(&reg) {
                 self.used_callee_saved_regs.insert_float(reg);
             }
             reg
         } else if !self.float_used_regs.is_empty() {
-            let (reg, sym) = self.float_used_regs.remove(0);
+            let index = match &self.register_hints {
+                Some(hints) => hints.spill_index(&self.float_used_regs),
+                None => 0,
+            };
+            let (reg, sym) = self.float_used_regs.remove(index);
             self.free_to_stack(buf, &sym, Float(reg));
             reg
         } else {
@@ -283,6 +303,80 @@ impl<
         }
     }
 
+    /// Pops a register from the general free list.
+    /// With register hints, registers outside of the allocation pool are handed out first,
+    /// so that values without a hint don't take the register of an allocated value.
+    fn pop_free_general_reg(&mut self) -> Option<GeneralReg> {
+        if let Some(hints) = &self.register_hints {
+            if let Some(index) = self
+                .general_free_regs
+                .iter()
+                .rposition(|reg| !hints.in_general_pool(reg))
+            {
+                return Some(self.general_free_regs.remove(index));
+            }
+        }
+        self.general_free_regs.pop()
+    }
+
+    /// Pops a register from the float free list.
+    /// With register hints, registers outside of the allocation pool are handed out first,
+    /// so that values without a hint don't take the register of an allocated value.
+    fn pop_free_float_reg(&mut self) -> Option<FloatReg> {
+        if let Some(hints) = &self.register_hints {
+            if let Some(index) = self
+                .float_free_regs
+                .iter()
+                .rposition(|reg| !hints.in_float_pool(reg))
+            {
+                return Some(self.float_free_regs.remove(index));
+            }
+        }
+        self.float_free_regs.pop()
+    }
+
+    /// Get a general register to hold `sym`.
+    /// Uses the register picked by the register allocator if it is free.
+    fn get_general_reg_for(&mut self, buf: &mut Vec<'a, u8>, sym: &Symbol) -> GeneralReg {
+        let hint = self
+            .register_hints
+            .as_ref()
+            .and_then(|hints| hints.general_hint(sym));
+
+        if let Some(reg) = hint {
+            if let Some(index) = self.general_free_regs.iter().position(|free| *free == reg) {
+                self.general_free_regs.remove(index);
+                if CC::general_callee_saved(&reg) {
+                    self.used_callee_saved_regs.insert_general(reg);
+                }
+                return reg;
+            }
+        }
+
+        self.get_general_reg(buf)
+    }
+
+    /// Get a float register to hold `sym`.
+    /// Uses the register picked by the register allocator if it is free.
+    fn get_float_reg_for(&mut self, buf: &mut Vec<'a, u8>, sym: &Symbol) -> FloatReg {
+        let hint = self
+            .register_hints
+            .as_ref()
+            .and_then(|hints| hints.float_hint(sym));
+
+        if let Some(reg) = hint {
+            if let Some(index) = self.float_free_regs.iter().position(|free| *free == reg) {
+                self.float_free_regs.remove(index);
+                if CC::float_callee_saved(&reg) {
+                    self.used_callee_saved_regs.insert_float(reg);
+                }
+                return reg;
+            }
+        }
+
+        self.get_float_reg(buf)
+    }
+
     /// Claims a general reg for a specific symbol.
     /// They symbol should not already have storage.
     pub fn claim_general_reg(&mut self, buf: &mut Vec<'a, u8>, sym: &Symbol) -> GeneralReg {
@@ -291,7 +385,7 @@ impl<
             None,
             "Symbol {sym:?} is already in the storage map!"
         );
-        let reg = self.get_general_reg(buf);
+        let reg = self.get_general_reg_for(buf, sym);
         self.general_used_regs.push((reg, *sym));
         self.symbol_storage_map.insert(*sym, Reg(General(reg)));
         reg
@@ -301,7 +395,7 @@ impl<
     /// They symbol should not already have storage.
     pub fn claim_float_reg(&mut self, buf: &mut Vec<'a, u8>, sym: &Symbol) -> FloatReg {
         debug_assert_eq!(self.symbol_storage_map.get(sym), None);
-        let reg = self.get_float_reg(buf);
+        let reg = self.get_float_reg_for(buf, sym);
         self.float_used_regs.push((reg, *sym));
         self.symbol_storage_map.insert(*sym, Reg(Float(reg)));
         reg
@@ -359,7 +453,7 @@ impl<
                 base_offset,
             }) => {
                 debug_assert_eq!(base_offset % 8, 0);
-                let reg = self.get_general_reg(buf);
+                let reg = self.get_general_reg_for(buf, sym);
                 ASM::mov_reg64_base32(buf, reg, base_offset);
                 self.general_used_regs.push((reg, *sym));
                 self.symbol_storage_map.insert(
@@ -376,7 +470,7 @@ impl<
                 size,
                 sign_extend,
             }) => {
-                let reg = self.get_general_reg(buf);
+                let reg = self.get_general_reg_for(buf, sym);
 
                 let register_width = match size {
                     8 => RegisterWidth::W64,
@@ -434,7 +528,7 @@ impl<
                 base_offset,
             }) => {
                 debug_assert_eq!(base_offset % 8, 0);
-                let reg = self.get_float_reg(buf);
+                let reg = self.get_float_reg_for(buf, sym);
                 ASM::mov_freg64_base32(buf, reg, base_offset);
                 self.float_used_regs.push((reg, *sym));
                 self.symbol_storage_map.insert(
@@ -451,7 +545,7 @@ impl<
             }) => {
                 if base_offset % 8 == 0 && size == 8 {
                     // The primitive is aligned and the data is exactly 8 bytes, treat it like regular stack.
-                    let reg = self.get_float_reg(buf);
+                    let reg = self.get_float_reg_for(buf, sym);
                     ASM::mov_freg64_base32(buf, reg, base_offset);
                     self.float_used_regs.push((reg, *sym));
                     self.symbol_storage_map.insert(*sym, Reg(Float(reg)));
@@ -459,7 +553,7 @@ impl<
                     reg
                 } else if base_offset % 4 == 0 && size == 4 {
                     // The primitive is aligned and the data is exactly 8 bytes, treat it like regular stack.
-                    let reg = self.get_float_reg(buf);
+                    let reg = self.get_float_reg_for(buf, sym);
                     ASM::mov_freg32_base32(buf, reg, base_offset);
                     self.float_used_regs.push((reg, *sym));
                     self.symbol_storage_map.insert(*sym, Reg(Float(reg)));
@@ -17,17 +17,23 @@ use roc_module::symbol::{Interns, ModuleId, Symbol};
 use roc_mono::code_gen_help::{CallerProc, CodeGenHelp};
 use roc_mono::ir::{
     BranchInfo, CallType, CrashTag, Expr, HigherOrderLowLevel, JoinPointId, ListLiteralElement,
-    Literal, ModifyRc, Param, Proc, ProcLayout, SelfRecursive, Stmt,
+    Literal, LookupType, ModifyRc, Param, Proc, ProcLayout, SelfRecursive, Stmt,
 };
 use roc_mono::layout::{
     Builtin, InLayout, LambdaName, Layout, LayoutIds, LayoutInterner, LayoutRepr, STLayoutInterner,
     TagIdIntType, UnionLayout,
 };
 use roc_mono::list_element_layout;
+use roc_region::all::Region;
 
+#[cfg(feature = "disassemble")]
+mod disassemble;
+mod expect;
 mod generic64;
 mod object_builder;
-pub use object_builder::build_module;
+#[cfg(feature = "disassemble")]
+pub use disassemble::disassemble_object;
+pub use object_builder::{build_module, expect_wrapper_name};
 use roc_target::Target;
 mod run_roc;
 
@@ -35,36 +41,67 @@ mod run_roc;
 pub enum AssemblyBackendMode {
     /// Assumes primitives (roc_alloc, roc_panic, etc) are provided by the host
     Binary,
+    /// Like `Binary`, but failed expectations are reported to the parent `roc dev` process
+    BinaryDev,
     /// Provides a testing implementation of primitives (roc_alloc, roc_panic, etc)
     Test,
     /// Provides a testing implementation of primitives (roc_alloc, roc_panic, etc)
     Repl,
+    /// Provides a testing implementation of primitives, and exposes the toplevel expects
+    /// to `roc test`, which reads failed expectations back from a shared buffer
+    CliTest,
 }
 
 impl AssemblyBackendMode {
     fn generate_allocators(self) -> bool {
         match self {
             AssemblyBackendMode::Binary => false,
+            AssemblyBackendMode::BinaryDev => false,
             AssemblyBackendMode::Test => true,
             AssemblyBackendMode::Repl => true,
+            AssemblyBackendMode::CliTest => true,
         }
     }
 
     fn generate_roc_panic(self) -> bool {
         match self {
             AssemblyBackendMode::Binary => false,
+            AssemblyBackendMode::BinaryDev => false,
             AssemblyBackendMode::Test => true,
             AssemblyBackendMode::Repl => true,
+            AssemblyBackendMode::CliTest => true,
         }
     }
 
     fn generate_roc_dbg(self) -> bool {
         match self {
             AssemblyBackendMode::Binary => false,
+            AssemblyBackendMode::BinaryDev => false,
             AssemblyBackendMode::Test => true,
             AssemblyBackendMode::Repl => true,
+            AssemblyBackendMode::CliTest => false,
         }
     }
+
+    fn runs_expects(self) -> bool {
+        match self {
+            AssemblyBackendMode::Binary => false,
+            AssemblyBackendMode::BinaryDev => true,
+            AssemblyBackendMode::Test => false,
+            AssemblyBackendMode::Repl => false,
+            AssemblyBackendMode::CliTest => true,
+        }
+    }
+}
+
+/// How the dev backend decides which values live in registers
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RegisterAllocation {
+    /// Hand out registers as values are defined, and spill the oldest value when they run out
+    #[default]
+    Greedy,
+    /// Run a liveness-based linear-scan allocator over each proc before generating code for it
+    LinearScan,
 }
 
 pub struct Env<'a> {
@@ -73,6 +110,7 @@ pub struct Env<'a> {
     pub exposed_to_host: MutSet<Symbol>,
     pub lazy_literals: bool,
     pub mode: AssemblyBackendMode,
+    pub register_allocation: RegisterAllocation,
 }
 
 // These relocations likely will need a length.
@@ -276,9 +314,33 @@ impl<'a> LastSeenMap<'a> {
                 }
             }
 
-            Stmt::Dbg { .. } => todo!("dbg not implemented in the dev backend"),
-            Stmt::Expect { .. } => todo!("expect is not implemented in the dev backend"),
-            Stmt::ExpectFx { .. } => todo!("expect-fx is not implemented in the dev backend"),
+            Stmt::Dbg {
+                symbol, remainder, ..
+            } => {
+                self.set_last_seen(*symbol, stmt);
+                self.scan_ast_help(remainder);
+            }
+            Stmt::SourceRegion { remainder, .. } => {
+                self.scan_ast_help(remainder);
+            }
+            Stmt::Expect {
+                condition,
+                lookups,
+                remainder,
+                ..
+            }
+            | Stmt::ExpectFx {
+                condition,
+                lookups,
+                remainder,
+                ..
+            } => {
+                self.set_last_seen(*condition, stmt);
+                for sym in *lookups {
+                    self.set_last_seen(*sym, stmt);
+                }
+                self.scan_ast_help(remainder);
+            }
 
             Stmt::Crash(msg, _crash_tag) => {
                 self.set_last_seen(*msg, stmt);
@@ -476,6 +538,10 @@ trait Backend<'a> {
     // The backend should track these args so it can use them as needed.
     fn load_args(&mut self, args: &'a [(InLayout<'a>, Symbol)], ret_layout: &InLayout<'a>);
 
+    /// allocate_registers runs the register allocator selected in the env over a procedure.
+    /// It uses the last seen map, so it must run after scan_ast.
+    fn allocate_registers(&mut self, args: &'a [(InLayout<'a>, Symbol)], body: &'a Stmt<'a>);
+
     /// Used for generating wrappers for malloc/realloc/free
     fn build_wrapped_jmp(&mut self) -> (&'a [u8], u64);
 
@@ -507,6 +573,7 @@ trait Backend<'a> {
         }
         self.scan_ast(body);
         self.create_free_map();
+        self.allocate_registers(proc.args, body);
         self.build_stmt(layout_ids, body, &proc.ret_layout);
 
         let mut helper_proc_names = bumpalo::vec![in self.env().arena];
@@ -649,7 +716,53 @@ trait Backend<'a> {
                 self.free_symbols(stmt);
             }
             Stmt::Crash(msg, crash_tag) => self.roc_panic(*msg, *crash_tag),
-            x => todo!("the statement, {:?}", x),
+            Stmt::SourceRegion { remainder, .. } => {
+                self.build_stmt(layout_ids, remainder, ret_layout);
+            }
+            Stmt::Dbg {
+                source_location,
+                source,
+                symbol,
+                remainder,
+                ..
+            } => {
+                if self.env().mode.runs_expects() {
+                    self.load_literal_symbols(&[*symbol]);
+                    self.roc_dbg(*source_location, *source, *symbol);
+                }
+                self.free_symbols(stmt);
+                self.build_stmt(layout_ids, remainder, ret_layout);
+            }
+            Stmt::Expect {
+                condition,
+                region,
+                lookups,
+                variables,
+                remainder,
+            } => {
+                if self.env().mode.runs_expects() {
+                    let notify_parent = matches!(self.env().mode, AssemblyBackendMode::BinaryDev);
+
+                    self.load_literal_symbols(&[*condition]);
+                    self.build_expect(*condition, *region, *lookups, *variables, notify_parent);
+                }
+                self.free_symbols(stmt);
+                self.build_stmt(layout_ids, remainder, ret_layout);
+            }
+            Stmt::ExpectFx {
+                condition,
+                region,
+                lookups,
+                variables,
+                remainder,
+            } => {
+                if self.env().mode.runs_expects() {
+                    self.load_literal_symbols(&[*condition]);
+                    self.build_expect(*condition, *region, *lookups, *variables, false);
+                }
+                self.free_symbols(stmt);
+                self.build_stmt(layout_ids, remainder, ret_layout);
+            }
         }
     }
 
@@ -685,6 +798,48 @@ trait Backend<'a> {
         self.free_symbol(&Symbol::DEV_TMP2);
     }
 
+    fn roc_dbg(&mut self, source_location: &'a str, source: &'a str, message: Symbol) {
+        let location = self.debug_symbol("dbg_location");
+        let source_str = self.debug_symbol("dbg_source");
+        self.load_literal(&location, &Layout::STR, &Literal::Str(source_location));
+        self.load_literal(&source_str, &Layout::STR, &Literal::Str(source));
+
+        // roc_dbg defined by a platform expects each of its arguments as a `*RocStr`.
+        let location_ptr = self.debug_symbol("dbg_location_ptr");
+        let message_ptr = self.debug_symbol("dbg_message_ptr");
+        let source_ptr = self.debug_symbol("dbg_source_ptr");
+        self.build_alloca(location_ptr, Some(location), Layout::STR);
+        self.build_alloca(message_ptr, Some(message), Layout::STR);
+        self.build_alloca(source_ptr, Some(source_str), Layout::STR);
+
+        // TODO: at some point it will be a breaking change, but flip order to (loc, src, msg)
+        self.build_fn_call(
+            &Symbol::DEV_TMP2,
+            String::from("roc_dbg"),
+            &[location_ptr, message_ptr, source_ptr],
+            &[Layout::U64, Layout::U64, Layout::U64],
+            &Layout::UNIT,
+        );
+
+        self.free_symbol(&location);
+        self.free_symbol(&source_str);
+        self.free_symbol(&location_ptr);
+        self.free_symbol(&message_ptr);
+        self.free_symbol(&source_ptr);
+        self.free_symbol(&Symbol::DEV_TMP2);
+    }
+
+    /// build_expect writes the lookups of an expect into the shared memory buffer if its condition is false.
+    /// With `notify_parent`, the program then waits until the parent process has rendered the failure.
+    fn build_expect(
+        &mut self,
+        condition: Symbol,
+        region: Region,
+        lookups: &'a [Symbol],
+        variables: &'a [LookupType],
+        notify_parent: bool,
+    );
+
     // build_switch generates a instructions for a switch statement.
     fn build_switch(
         &mut self,
@@ -7,6 +7,7 @@ use object::{
     Architecture, BinaryFormat, Endianness, RelocationEncoding, RelocationKind, SectionKind,
     SymbolFlags, SymbolKind, SymbolScope,
 };
+use roc_builtins::bitcode;
 use roc_collections::all::MutMap;
 use roc_error_macros::internal_error;
 use roc_module::symbol;
@@ -14,7 +15,7 @@ use roc_module::symbol::Interns;
 use roc_mono::ir::{Call, CallSpecId, Expr, UpdateModeId};
 use roc_mono::ir::{Proc, ProcLayout, Stmt};
 use roc_mono::layout::{LambdaName, Layout, LayoutIds, LayoutInterner, STLayoutInterner};
-use roc_target::Target;
+use roc_target::{OperatingSystem, Target};
 
 // This is used by some code below which is currently commented out.
 // See that code for more details!
@@ -237,73 +238,10 @@ fn generate_roc_panic<'a, B: Backend<'a>>(backend: &mut B, output: &mut Object)
     let proc_offset = output.add_symbol_data(proc_id, text_section, proc_data, 16);
 
     for r in relocs {
-        let relocation = match r {
+        let relocations = match r {
             Relocation::LinkedData { offset, name } => {
                 if let Some(sym_id) = output.symbol_id(name.as_bytes()) {
-                    if cfg!(all(target_arch = "aarch64", target_os = "linux")) {
-                        //     700: 90000001        adrp    x1, 0x0 <std.builtin.default_panic>
-                        //      0000000000000700:  R_AARCH64_ADR_PREL_PG_HI21   .rodata+0x650
-                        let relocation = write::Relocation {
-                            offset: offset + proc_offset,
-                            size: 21,
-                            kind: RelocationKind::Elf(object::elf::R_AARCH64_ADR_PREL_PG_HI21),
-                            encoding: RelocationEncoding::Generic,
-                            symbol: sym_id,
-                            addend: 0,
-                        };
-
-                        output.add_relocation(text_section, relocation).unwrap();
-
-                        //     704: 91000021        add x1, x1, #0x0
-                        //      0000000000000704:  R_AARCH64_ADD_ABS_LO12_NC    .rodata+0x650
-                        write::Relocation {
-                            offset: offset + proc_offset + 4,
-                            size: 12,
-                            kind: RelocationKind::Elf(object::elf::R_AARCH64_ADD_ABS_LO12_NC),
-                            encoding: RelocationEncoding::Generic,
-                            symbol: sym_id,
-                            addend: 0,
-                        }
-                    } else if cfg!(all(target_arch = "aarch64", target_os = "macos")) {
-                        //     4dc: 90000001        adrp    x1, 0x0 <ltmp0>
-                        //      00000000000004dc:  ARM64_RELOC_PAGE21   ___unnamed_6
-                        let relocation = write::Relocation {
-                            offset: offset + proc_offset,
-                            size: 32,
-                            kind: RelocationKind::MachO {
-                                value: object::macho::ARM64_RELOC_PAGE21,
-                                relative: true,
-                            },
-                            encoding: RelocationEncoding::Generic,
-                            symbol: sym_id,
-                            addend: 0,
-                        };
-
-                        output.add_relocation(text_section, relocation).unwrap();
-
-                        //     4e0: 91000021        add x1, x1, #0x0
-                        //      00000000000004e0:  ARM64_RELOC_PAGEOFF12    ___unnamed_6
-                        write::Relocation {
-                            offset: offset + proc_offset + 4,
-                            size: 32,
-                            kind: RelocationKind::MachO {
-                                value: object::macho::ARM64_RELOC_PAGEOFF12,
-                                relative: false,
-                            },
-                            encoding: RelocationEncoding::Generic,
-                            symbol: sym_id,
-                            addend: 0,
-                        }
-                    } else {
-                        write::Relocation {
-                            offset: offset + proc_offset,
-                            size: 32,
-                            kind: RelocationKind::GotRelative,
-                            encoding: RelocationEncoding::Generic,
-                            symbol: sym_id,
-                            addend: -4,
-                        }
-                    }
+                    create_data_relocations(backend.target(), sym_id, offset + proc_offset)
                 } else {
                     internal_error!("failed to find data symbol for {:?}", name);
                 }
@@ -315,7 +253,9 @@ fn generate_roc_panic<'a, B: Backend<'a>>(backend: &mut B, output: &mut Object)
             }
         };
 
-        output.add_relocation(text_section, relocation).unwrap();
+        for relocation in relocations {
+            output.add_relocation(text_section, relocation).unwrap();
+        }
     }
 }
 
@@ -386,13 +326,13 @@ fn create_relocation(target: Target, symbol: SymbolId, offset: u64) -> write::Re
     let (encoding, size, addend, kind) = match target.architecture() {
         roc_target::Architecture::Aarch32 => todo!(),
         roc_target::Architecture::Aarch64 => {
-            if cfg!(target_os = "macos") {
+            if target.operating_system() == OperatingSystem::Mac {
                 (
                     RelocationEncoding::Generic,
                     26,
                     0,
                     RelocationKind::MachO {
-                        value: 2,
+                        value: object::macho::ARM64_RELOC_BRANCH26,
                         relative: true,
                     },
                 )
@@ -425,6 +365,85 @@ fn create_relocation(target: Target, symbol: SymbolId, offset: u64) -> write::Re
     }
 }
 
+/// The relocations for a `Relocation::LinkedData`, which points at the instruction(s) that
+/// `data_pointer` emitted to load the address of `symbol`.
+///
+/// The choice depends on the target rather than the host, so that e.g. a Mach-O object for
+/// aarch64 can be produced on a Linux machine.
+fn create_data_relocations(
+    target: Target,
+    symbol: SymbolId,
+    offset: u64,
+) -> std::vec::Vec<write::Relocation> {
+    match target.arch_os() {
+        (roc_target::Architecture::Aarch64, OperatingSystem::Mac) => vec![
+            //     4dc: 90000001        adrp    x1, 0x0 <ltmp0>
+            //      00000000000004dc:  ARM64_RELOC_PAGE21   ___unnamed_6
+            write::Relocation {
+                offset,
+                size: 32,
+                kind: RelocationKind::MachO {
+                    value: object::macho::ARM64_RELOC_PAGE21,
+                    relative: true,
+                },
+                encoding: RelocationEncoding::Generic,
+                symbol,
+                addend: 0,
+            },
+            //     4e0: 91000021        add x1, x1, #0x0
+            //      00000000000004e0:  ARM64_RELOC_PAGEOFF12    ___unnamed_6
+            write::Relocation {
+                offset: offset + 4,
+                size: 32,
+                kind: RelocationKind::MachO {
+                    value: object::macho::ARM64_RELOC_PAGEOFF12,
+                    relative: false,
+                },
+                encoding: RelocationEncoding::Generic,
+                symbol,
+                addend: 0,
+            },
+        ],
+        // Both addends are 0: ADRP computes `Page(S + A) - Page(P)` and ADD the low 12 bits of
+        // `S + A`, so unlike the x86_64 relocation below, nothing is relative to the end of the
+        // instruction. (With an addend of -4, data in the first 4 bytes of a page was looked up
+        // in the page before it.)
+        (roc_target::Architecture::Aarch64, _) => vec![
+            //     700: 90000001        adrp    x1, 0x0 <std.builtin.default_panic>
+            //      0000000000000700:  R_AARCH64_ADR_PREL_PG_HI21   .rodata+0x650
+            write::Relocation {
+                offset,
+                size: 21,
+                kind: RelocationKind::Elf(object::elf::R_AARCH64_ADR_PREL_PG_HI21),
+                encoding: RelocationEncoding::Generic,
+                symbol,
+                addend: 0,
+            },
+            //     704: 91000021        add x1, x1, #0x0
+            //      0000000000000704:  R_AARCH64_ADD_ABS_LO12_NC    .rodata+0x650
+            write::Relocation {
+                offset: offset + 4,
+                size: 12,
+                kind: RelocationKind::Elf(object::elf::R_AARCH64_ADD_ABS_LO12_NC),
+                encoding: RelocationEncoding::Generic,
+                symbol,
+                addend: 0,
+            },
+        ],
+        // `data_pointer` loads the address of the data from a GOT entry. COFF has no GOT;
+        // there `object` points the relocation at a `.refptr` stub holding the address instead.
+        (roc_target::Architecture::X86_64, _) => vec![write::Relocation {
+            offset,
+            size: 32,
+            kind: RelocationKind::GotRelative,
+            encoding: RelocationEncoding::Generic,
+            symbol,
+            addend: -4,
+        }],
+        (arch, _) => internal_error!("data relocations are not supported for {:?}", arch),
+    }
+}
+
 fn build_object<'a, B: Backend<'a>>(
     procedures: MutMap<(symbol::Symbol, ProcLayout<'a>), Proc<'a>>,
     mut backend: B,
@@ -457,6 +476,16 @@ fn build_object<'a, B: Backend<'a>>(
         generate_roc_dbg(&mut backend, &mut output);
     }
 
+    if let AssemblyBackendMode::CliTest = backend.env().mode {
+        // `roc test` prints the output of `dbg`, so forward to the builtins implementation
+        generate_wrapper(
+            &mut backend,
+            &mut output,
+            "roc_dbg".into(),
+            bitcode::UTILS_DBG_IMPL.into(),
+        );
+    }
+
     if backend.env().mode.generate_allocators() {
         generate_wrapper(
             &mut backend,
@@ -586,7 +615,36 @@ fn build_object<'a, B: Backend<'a>>(
                         Exposed::TestMain,
                     );
                 }
-                AssemblyBackendMode::Binary => { /* do nothing */ }
+                AssemblyBackendMode::CliTest => {
+                    let expect_helper = roc_mono::code_gen_help::test_helper(
+                        code_gen_help,
+                        ident_ids,
+                        layout_interner,
+                        &proc,
+                    );
+
+                    #[cfg(debug_assertions)]
+                    {
+                        let module_id = exposed_generic_proc.name.name().module_id();
+                        let ident_ids = backend
+                            .interns_mut()
+                            .all_ident_ids
+                            .get_mut(&module_id)
+                            .unwrap();
+                        module_id.register_debug_idents(ident_ids);
+                    }
+
+                    build_proc_symbol(
+                        &mut output,
+                        &mut layout_ids,
+                        &mut procs,
+                        &mut backend,
+                        layout,
+                        expect_helper,
+                        Exposed::Expect(sym),
+                    );
+                }
+                AssemblyBackendMode::Binary | AssemblyBackendMode::BinaryDev => { /* do nothing */ }
             }
 
             build_proc_symbol(
@@ -867,6 +925,16 @@ enum Exposed {
     Exposed,
     NotExposed,
     TestMain,
+    Expect(symbol::Symbol),
+}
+
+/// The name of the symbol that `roc test` calls to run the toplevel expect `symbol`.
+/// Like `test_main`, it returns a `RocCallResult` by value.
+pub fn expect_wrapper_name(symbol: symbol::Symbol, interns: &Interns) -> String {
+    let ident_string = symbol.as_str(interns);
+    let module_string = interns.module_ids.get_name(symbol.module_id()).unwrap();
+
+    format!("Expect_{module_string}_{ident_string}")
 }
 
 fn build_proc_symbol<'a, B: Backend<'a>>(
@@ -900,6 +968,7 @@ fn build_proc_symbol<'a, B: Backend<'a>>(
             layout.result,
         ),
         Exposed::TestMain => String::from("test_main"),
+        Exposed::Expect(expect_symbol) => expect_wrapper_name(expect_symbol, backend.interns()),
     };
 
     let proc_symbol = Symbol {
@@ -910,7 +979,9 @@ fn build_proc_symbol<'a, B: Backend<'a>>(
         // TODO: Depending on whether we are building a static or dynamic lib, this should change.
         // We should use Dynamic -> anyone, Linkage -> static link, Compilation -> this module only.
         scope: match exposed {
-            Exposed::ExposedGeneric | Exposed::Exposed | Exposed::TestMain => SymbolScope::Dynamic,
+            Exposed::ExposedGeneric | Exposed::Exposed | Exposed::TestMain | Exposed::Expect(_) => {
+                SymbolScope::Dynamic
+            }
             Exposed::NotExposed => SymbolScope::Linkage,
         },
         weak: false,
@@ -968,70 +1039,13 @@ fn build_proc<'a, B: Backend<'a>>(
                 add_undefined_rc_proc(output, name, &rc_proc_names);
 
                 if let Some(sym_id) = output.symbol_id(name.as_bytes()) {
-                    if cfg!(all(target_arch = "aarch64", target_os = "linux")) {
-                        //     700: 90000001        adrp    x1, 0x0 <std.builtin.default_panic>
-                        //      0000000000000700:  R_AARCH64_ADR_PREL_PG_HI21   .rodata+0x650
-                        let r = write::Relocation {
-                            offset: proc_offset + offset,
-                            size: 21,
-                            kind: RelocationKind::Elf(object::elf::R_AARCH64_ADR_PREL_PG_HI21),
-                            encoding: RelocationEncoding::Generic,
-                            symbol: sym_id,
-                            addend: -4,
-                        };
-
-                        relocations.push((section_id, r));
-
-                        //     704: 91000021        add x1, x1, #0x0
-                        //      0000000000000704:  R_AARCH64_ADD_ABS_LO12_NC    .rodata+0x650
-                        write::Relocation {
-                            offset: proc_offset + offset + 4,
-                            size: 12,
-                            kind: RelocationKind::Elf(object::elf::R_AARCH64_ADD_ABS_LO12_NC),
-                            encoding: RelocationEncoding::Generic,
-                            symbol: sym_id,
-                            addend: 0,
-                        }
-                    } else if cfg!(all(target_arch = "aarch64", target_os = "macos")) {
-                        //    4ed0: 90000000        adrp    x0, 0x4000 <_std.unicode.utf8Decode4+0x16c>
-                        //      0000000000004ed0:  ARM64_RELOC_PAGE21   ___unnamed_11
-                        let r = write::Relocation {
-                            offset: proc_offset + offset,
-                            size: 21,
-                            kind: RelocationKind::MachO {
-                                value: object::macho::ARM64_RELOC_PAGE21,
-                                relative: true,
-                            },
-                            encoding: RelocationEncoding::Generic,
-                            symbol: sym_id,
-                            addend: 0,
-                        };
-
-                        relocations.push((section_id, r));
-
-                        //  4ed4: 91000000      add x0, x0, #0x0
-                        //      0000000000004ed4:  ARM64_RELOC_PAGEOFF12    ___unnamed_11
-                        write::Relocation {
-                            offset: proc_offset + offset + 4,
-                            size: 12,
-                            kind: RelocationKind::MachO {
-                                value: object::macho::ARM64_RELOC_PAGEOFF12,
-                                relative: false,
-                            },
-                            encoding: RelocationEncoding::Generic,
-                            symbol: sym_id,
-                            addend: 0,
-                        }
-                    } else {
-                        write::Relocation {
-                            offset: offset + proc_offset,
-                            size: 32,
-                            kind: RelocationKind::GotRelative,
-                            encoding: RelocationEncoding::Generic,
-                            symbol: sym_id,
-                            addend: -4,
-                        }
-                    }
+                    let mut data_relocations =
+                        create_data_relocations(target, sym_id, offset + proc_offset);
+                    let last = data_relocations.pop().unwrap();
+
+                    relocations.extend(data_relocations.into_iter().map(|r| (section_id, r)));
+
+                    last
                 } else {
                     internal_error!("failed to find data symbol for {:?}", name);
                 }
@@ -1103,3 +1117,380 @@ fn format_symbol_name(sym: roc_module::symbol::Symbol) -> std::vec::Vec<u8> {
 
     name.as_bytes()[..length].to_vec()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RegisterAllocation;
+    use capstone::prelude::*;
+    use object::read::{File, Object as _, ObjectSection, ObjectSymbol, RelocationTarget};
+    use roc_module::symbol::ModuleId;
+    use roc_mono::ir::{CrashTag, SelfRecursive};
+    use roc_mono::layout::{InLayout, Niche};
+
+    /// Builds an object without any user procedures. In test mode it still contains `roc_panic`,
+    /// which loads the address of the `setlongjmp_buffer`, and the allocator wrappers, which jump
+    /// to their libc counterparts.
+    fn build_empty_test_module(target: Target) -> std::vec::Vec<u8> {
+        build_test_module(target, false)
+    }
+
+    /// Like `build_empty_test_module`, but with three procedures:
+    ///
+    /// - `crashy : Str -> I64` crashes, so it stores its argument in `panic_msg`
+    /// - `second : I64, I64 -> I64` returns its second argument
+    /// - `fifth : I64, I64, I64, I64, I64 -> I64` returns its fifth argument
+    fn build_test_module_with_procs(target: Target) -> std::vec::Vec<u8> {
+        build_test_module(target, true)
+    }
+
+    fn build_test_module(target: Target, with_procs: bool) -> std::vec::Vec<u8> {
+        let arena = bumpalo::Bump::new();
+        let module_id = ModuleId::NUM;
+        let env = Env {
+            arena: &arena,
+            module_id,
+            exposed_to_host: Default::default(),
+            lazy_literals: false,
+            mode: AssemblyBackendMode::Test,
+            register_allocation: RegisterAllocation::default(),
+        };
+
+        let mut interns = Interns::default();
+        let ident_ids = interns.all_ident_ids.get_or_insert(module_id);
+        let mut layout_interner = STLayoutInterner::with_capacity(4, target);
+
+        let mut procedures = MutMap::default();
+        if with_procs {
+            let mut new_symbol =
+                |name: &str| symbol::Symbol::new(module_id, ident_ids.add_str(name));
+
+            let msg = new_symbol("msg");
+            let crashy = test_proc(
+                &arena,
+                new_symbol("crashy"),
+                &[(Layout::STR, msg)],
+                Stmt::Crash(msg, CrashTag::User),
+            );
+
+            let args: std::vec::Vec<_> = ["a", "b"]
+                .into_iter()
+                .map(|name| (Layout::I64, new_symbol(name)))
+                .collect();
+            let second = test_proc(&arena, new_symbol("second"), &args, Stmt::Ret(args[1].1));
+
+            let args: std::vec::Vec<_> = ["c", "d", "e", "f", "g"]
+                .into_iter()
+                .map(|name| (Layout::I64, new_symbol(name)))
+                .collect();
+            let fifth = test_proc(&arena, new_symbol("fifth"), &args, Stmt::Ret(args[4].1));
+
+            procedures.extend([crashy, second, fifth]);
+        }
+
+        build_module(&env, &mut interns, &mut layout_interner, target, procedures)
+            .write()
+            .expect("failed to write object")
+    }
+
+    /// A procedure that returns an I64
+    fn test_proc<'a>(
+        arena: &'a bumpalo::Bump,
+        name: symbol::Symbol,
+        args: &[(InLayout<'a>, symbol::Symbol)],
+        body: Stmt<'a>,
+    ) -> ((symbol::Symbol, ProcLayout<'a>), Proc<'a>) {
+        let proc_layout = ProcLayout {
+            arguments: arena.alloc_slice_fill_iter(args.iter().map(|(layout, _)| *layout)),
+            result: Layout::I64,
+            niche: Niche::NONE,
+        };
+        let proc = Proc {
+            name: LambdaName::no_niche(name),
+            args: arena.alloc_slice_copy(args),
+            body,
+            closure_data_layout: None,
+            ret_layout: Layout::I64,
+            is_self_recursive: SelfRecursive::NotSelfRecursive,
+            is_erased: false,
+        };
+
+        ((name, proc_layout), proc)
+    }
+
+    /// The instructions of the function whose symbol name contains `name`, up to its first `ret`
+    fn disassemble_function(cs: &Capstone, file: &File<'_>, name: &str) -> std::vec::Vec<String> {
+        let symbol = file
+            .symbols()
+            .find(|symbol| symbol.is_definition() && symbol.name().unwrap().contains(name))
+            .unwrap_or_else(|| panic!("no function named like {name:?}"));
+        let section = file
+            .section_by_index(symbol.section_index().unwrap())
+            .unwrap();
+        let start = symbol.address() - section.address();
+        let data = &section.data().unwrap()[start as usize..];
+
+        let instructions = cs.disasm_all(data, start).expect("failed to disassemble");
+        let mut lines = std::vec::Vec::new();
+        for instruction in instructions.iter() {
+            let mnemonic = instruction.mnemonic().unwrap();
+            lines.push(format!("{} {}", mnemonic, instruction.op_str().unwrap()));
+            if mnemonic == "ret" {
+                break;
+            }
+        }
+
+        lines
+    }
+
+    /// All relocations that refer to the symbol `name`, with the data of the section they patch.
+    fn relocations_to<'d>(
+        file: &File<'d>,
+        name: &str,
+    ) -> std::vec::Vec<(&'d [u8], u64, object::Relocation)> {
+        let mut found = std::vec::Vec::new();
+
+        for section in file.sections() {
+            for (offset, relocation) in section.relocations() {
+                if let RelocationTarget::Symbol(index) = relocation.target() {
+                    let symbol = file.symbol_by_index(index).unwrap();
+
+                    if symbol.name() == Ok(name) {
+                        found.push((section.data().unwrap(), offset, relocation));
+                    }
+                }
+            }
+        }
+
+        found
+    }
+
+    fn disassemble_one(cs: &Capstone, data: &[u8], offset: u64) -> String {
+        let instructions = cs
+            .disasm_count(&data[offset as usize..], offset, 1)
+            .expect("failed to disassemble");
+        let instruction = instructions.iter().next().unwrap();
+
+        format!(
+            "{} {}",
+            instruction.mnemonic().unwrap(),
+            instruction.op_str().unwrap()
+        )
+    }
+
+    #[test]
+    #[cfg(feature = "target-x86_64")]
+    fn windows_x64_coff() {
+        let bytes = build_empty_test_module(Target::WinX64);
+        let file = File::parse(bytes.as_slice()).unwrap();
+
+        assert_eq!(file.format(), BinaryFormat::Coff);
+        assert_eq!(file.architecture(), Architecture::X86_64);
+
+        let cs = Capstone::new()
+            .x86()
+            .mode(arch::x86::ArchMode::Mode64)
+            .syntax(arch::x86::ArchSyntax::Intel)
+            .build()
+            .expect("Failed to create Capstone object");
+
+        // COFF has no GOT, so `roc_panic` goes through a `.refptr` stub instead
+        let found = relocations_to(&file, ".refptr.setlongjmp_buffer");
+        assert_eq!(found.len(), 1);
+
+        let (data, offset, relocation) = &found[0];
+        assert_eq!(relocation.kind(), RelocationKind::Relative);
+        assert_eq!(relocation.size(), 32);
+        assert_eq!(relocation.addend(), -4);
+        assert_eq!(disassemble_one(&cs, data, offset - 3), "lea r8, [rip]");
+        assert!(disassemble_one(&cs, data, offset + 4).starts_with("mov r8, qword ptr [r8"));
+
+        // the stub holds the absolute address of the buffer
+        let found = relocations_to(&file, "setlongjmp_buffer");
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].2.kind(), RelocationKind::Absolute);
+        assert_eq!(found[0].2.size(), 64);
+
+        // `roc_alloc` (and `roc_getppid`) jump to `malloc`
+        let found = relocations_to(&file, "malloc");
+        assert_eq!(found.len(), 2);
+
+        for (data, offset, relocation) in found {
+            assert_eq!(relocation.kind(), RelocationKind::Relative);
+            assert_eq!(relocation.size(), 32);
+            assert_eq!(relocation.addend(), -4);
+            assert!(disassemble_one(&cs, data, offset - 1).starts_with("jmp"));
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "target-aarch64")]
+    fn macos_arm64_macho() {
+        let bytes = build_empty_test_module(Target::MacArm64);
+        let file = File::parse(bytes.as_slice()).unwrap();
+
+        assert_eq!(file.format(), BinaryFormat::MachO);
+        assert_eq!(file.architecture(), Architecture::Aarch64);
+
+        let cs = Capstone::new()
+            .arm64()
+            .mode(arch::arm64::ArchMode::Arm)
+            .build()
+            .expect("Failed to create Capstone object");
+
+        // `roc_panic` computes the address of the buffer with an adrp + add pair
+        let found = relocations_to(&file, "_setlongjmp_buffer");
+        assert_eq!(found.len(), 2);
+
+        let (data, page_offset, _) = found
+            .iter()
+            .find(|(_, _, r)| {
+                r.kind()
+                    == RelocationKind::MachO {
+                        value: object::macho::ARM64_RELOC_PAGE21,
+                        relative: true,
+                    }
+            })
+            .unwrap();
+        assert!(disassemble_one(&cs, data, *page_offset).starts_with("adrp x0"));
+
+        let (data, pageoff_offset, _) = found
+            .iter()
+            .find(|(_, _, r)| {
+                r.kind()
+                    == RelocationKind::MachO {
+                        value: object::macho::ARM64_RELOC_PAGEOFF12,
+                        relative: false,
+                    }
+            })
+            .unwrap();
+        assert_eq!(*pageoff_offset, page_offset + 4);
+        assert!(disassemble_one(&cs, data, *pageoff_offset).starts_with("add x0, x0"));
+
+        // `roc_alloc` jumps to `malloc`
+        let found = relocations_to(&file, "_malloc");
+        assert_eq!(found.len(), 1);
+
+        let (data, offset, relocation) = &found[0];
+        assert_eq!(
+            relocation.kind(),
+            RelocationKind::MachO {
+                value: object::macho::ARM64_RELOC_BRANCH26,
+                relative: true,
+            }
+        );
+        assert!(disassemble_one(&cs, data, *offset).starts_with("b "));
+    }
+
+    #[test]
+    #[cfg(feature = "target-aarch64")]
+    fn linux_arm64_elf() {
+        let bytes = build_empty_test_module(Target::LinuxArm64);
+        let file = File::parse(bytes.as_slice()).unwrap();
+
+        assert_eq!(file.format(), BinaryFormat::Elf);
+        assert_eq!(file.architecture(), Architecture::Aarch64);
+
+        // `roc_panic` computes the address of the buffer with an adrp + add pair.
+        // Both relocations point at the buffer itself, so neither has an addend.
+        let found = relocations_to(&file, "setlongjmp_buffer");
+        assert_eq!(found.len(), 2);
+
+        let kinds: std::vec::Vec<_> = found.iter().map(|(_, _, r)| r.kind()).collect();
+        assert!(kinds.contains(&RelocationKind::Elf(
+            object::elf::R_AARCH64_ADR_PREL_PG_HI21
+        )));
+        assert!(kinds.contains(&RelocationKind::Elf(object::elf::R_AARCH64_ADD_ABS_LO12_NC)));
+
+        for (_, _, relocation) in found {
+            assert_eq!(relocation.addend(), 0);
+        }
+    }
+
+    /// The relocations of `build_proc` (rather than the generated helpers) go through
+    /// `create_data_relocations` as well
+    #[test]
+    #[cfg(all(feature = "target-x86_64", feature = "target-aarch64"))]
+    fn proc_data_relocations() {
+        // COFF: a relative relocation to the `.refptr` stub
+        let bytes = build_test_module_with_procs(Target::WinX64);
+        let file = File::parse(bytes.as_slice()).unwrap();
+        let found = relocations_to(&file, ".refptr.panic_msg");
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].2.kind(), RelocationKind::Relative);
+        assert_eq!(found[0].2.addend(), -4);
+
+        // Mach-O: a page + page offset pair
+        let bytes = build_test_module_with_procs(Target::MacArm64);
+        let file = File::parse(bytes.as_slice()).unwrap();
+        let found = relocations_to(&file, "_panic_msg");
+        assert_eq!(found.len(), 2);
+        let kinds: std::vec::Vec<_> = found.iter().map(|(_, _, r)| r.kind()).collect();
+        assert!(kinds.contains(&RelocationKind::MachO {
+            value: object::macho::ARM64_RELOC_PAGE21,
+            relative: true,
+        }));
+        assert!(kinds.contains(&RelocationKind::MachO {
+            value: object::macho::ARM64_RELOC_PAGEOFF12,
+            relative: false,
+        }));
+
+        // ELF: the same pair, without addends
+        let bytes = build_test_module_with_procs(Target::LinuxArm64);
+        let file = File::parse(bytes.as_slice()).unwrap();
+        let found = relocations_to(&file, "panic_msg");
+        assert_eq!(found.len(), 2);
+        for (_, _, relocation) in found {
+            assert_eq!(relocation.addend(), 0);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "target-x86_64")]
+    fn windows_x64_calling_convention() {
+        let cs = Capstone::new()
+            .x86()
+            .mode(arch::x86::ArchMode::Mode64)
+            .syntax(arch::x86::ArchSyntax::Intel)
+            .build()
+            .expect("Failed to create Capstone object");
+
+        let bytes = build_test_module_with_procs(Target::WinX64);
+        let file = File::parse(bytes.as_slice()).unwrap();
+
+        // The second argument is passed in rdx (rather than rsi)
+        let second = disassemble_function(&cs, &file, "second");
+        assert!(second.iter().any(|i| i.contains("rdx")), "{second:#?}");
+
+        // The fifth argument is passed on the stack, after the return address, the saved
+        // base pointer and 32 bytes of shadow space for the four register arguments
+        let fifth = disassemble_function(&cs, &file, "fifth");
+        assert!(
+            fifth.iter().any(|i| i.contains("[rbp + 0x30]")),
+            "{fifth:#?}"
+        );
+
+        // For comparison, System V passes the second argument in rsi
+        let bytes = build_test_module_with_procs(Target::LinuxX64);
+        let file = File::parse(bytes.as_slice()).unwrap();
+        let second = disassemble_function(&cs, &file, "second");
+        assert!(second.iter().any(|i| i.contains("rsi")), "{second:#?}");
+    }
+
+    #[test]
+    #[cfg(feature = "target-aarch64")]
+    fn macos_arm64_calling_convention() {
+        let cs = Capstone::new()
+            .arm64()
+            .mode(arch::arm64::ArchMode::Arm)
+            .build()
+            .expect("Failed to create Capstone object");
+
+        let bytes = build_test_module_with_procs(Target::MacArm64);
+        let file = File::parse(bytes.as_slice()).unwrap();
+
+        // The second argument is passed in x1 and returned in x0
+        let second = disassemble_function(&cs, &file, "second");
+        assert!(second.iter().any(|i| i.contains("x0, x1")), "{second:#?}");
+    }
+}
@@ -3,7 +3,9 @@ use crate::llvm::build_list::{self, allocate_list, empty_polymorphic_list};
 use crate::llvm::convert::{
     argument_type_from_layout, basic_type_from_builtin, basic_type_from_layout, zig_str_type,
 };
+use crate::llvm::debug_info::{self, DebugSources};
 use crate::llvm::expect::{clone_to_shared_memory, SharedMemoryPointer};
+use crate::llvm::instrument::{self, MemoryInstrumentation};
 use crate::llvm::memcpy::build_memcpy;
 use crate::llvm::refcounting::{
     build_reset, decrement_refcount_layout, increment_refcount_layout, PointerToRefcount,
@@ -739,6 +741,12 @@ pub struct Env<'a, 'ctx, 'env> {
     pub target: Target,
     pub mode: LlvmBackendMode,
     pub exposed_to_host: MutSet<Symbol>,
+    /// Source code to describe in the debug info. If this is `None`, functions only get
+    /// placeholder locations.
+    pub debug_sources: Option<DebugSources<'a>>,
+    /// Report allocations and refcount changes to the host (see [instrument]). If this is
+    /// `None`, no instrumentation is emitted.
+    pub instrument_memory: Option<MemoryInstrumentation<'a>>,
 }
 
 impl<'a, 'ctx, 'env> Env<'a, 'ctx, 'env> {
@@ -3254,6 +3262,8 @@ pub(crate) fn build_exp_stmt<'a, 'ctx>(
                     expr,
                 );
 
+                debug_info::declare_let(env, layout_interner, parent, *symbol, *layout, val);
+
                 // Make a new scope which includes the binding we just encountered.
                 // This should be done *after* compiling the bound expr, since any
                 // recursive (in the LetRec sense) bindings should already have
@@ -3573,6 +3583,20 @@ pub(crate) fn build_exp_stmt<'a, 'ctx>(
             }
         }
 
+        SourceRegion { region, remainder } => {
+            debug_info::enter_region(env, parent, *region);
+
+            build_exp_stmt(
+                env,
+                layout_interner,
+                layout_ids,
+                func_spec_solutions,
+                scope,
+                parent,
+                remainder,
+            )
+        }
+
         Dbg {
             source_location,
             source,
@@ -5753,6 +5777,8 @@ fn build_procedures_help<'a>(
     let it1 = procedures.iter().map(|x| x.1);
     let it2 = host_exposed_lambda_sets.iter().map(|(_, _, hels)| hels);
 
+    let alias_analysis_span = roc_tracing::profile::span("codegen", "alias analysis");
+
     let solutions = match roc_alias_analysis::spec_program(
         env.arena,
         layout_interner,
@@ -5765,6 +5791,8 @@ fn build_procedures_help<'a>(
         Ok(solutions) => solutions,
     };
 
+    drop(alias_analysis_span);
+
     let solutions = env.arena.alloc(solutions);
 
     let mod_solutions = solutions
@@ -5937,7 +5965,7 @@ fn build_proc_header<'a, 'ctx>(
         Linkage::Internal,
     );
 
-    let subprogram = env.new_subprogram(&fn_name);
+    let subprogram = debug_info::proc_subprogram(env, layout_interner, proc, &fn_name);
     fn_val.set_subprogram(subprogram);
 
     debug_info_init!(env, fn_val);
@@ -6245,6 +6273,8 @@ fn build_proc<'a, 'ctx>(
     builder.position_at_end(entry);
 
     debug_info_init!(env, fn_val);
+    debug_info::init_proc(env, layout_interner, proc, fn_val);
+    instrument::enter_proc(env, proc, fn_val);
 
     // Add args to scope
     for (arg_val, (layout, arg_symbol)) in fn_val.get_param_iter().zip(args) {
@@ -6268,6 +6298,8 @@ fn build_proc<'a, 'ctx>(
             builder.new_build_return(Some(&body));
         }
     }
+
+    debug_info::exit_proc(env);
 }
 
 pub fn verify_fn(fn_val: FunctionValue<'_>) {
@@ -6406,6 +6438,7 @@ fn call_roc_function_help<'a, 'ctx>(
 
             // roc functions should have the fast calling convention
             call.set_call_convention(FAST_CALL_CONV);
+            instrument::restore_site(env);
 
             env.builder
                 .new_build_load(result_type, result_alloca, "load_result")
@@ -6424,6 +6457,7 @@ fn call_roc_function_help<'a, 'ctx>(
 
             // roc functions should have the fast calling convention
             call.set_call_convention(FAST_CALL_CONV);
+            instrument::restore_site(env);
 
             if result_layout.is_passed_by_reference(layout_interner) {
                 result_alloca.into()
@@ -6444,6 +6478,7 @@ fn call_roc_function_help<'a, 'ctx>(
 
             // roc functions should have the fast calling convention
             call.set_call_convention(FAST_CALL_CONV);
+            instrument::restore_site(env);
 
             call.try_as_basic_value()
                 .left()
@@ -0,0 +1,554 @@
+//! Source-level debug info (DWARF) for Roc procedures, emitted by `roc build --debug`.
+//!
+//! Without [DebugSources], every function gets a placeholder subprogram at line 0 (see
+//! `debug_info_init!`). With them, each specialization gets a subprogram at the line of its
+//! definition, named after its Roc symbol. The code of every definition in its body is
+//! attributed to the line of that definition (see [Stmt::SourceRegion]), and the values bound
+//! there are described as local variables.
+use crate::llvm::build::{create_entry_block_alloca, BuilderExt, Env};
+use crate::llvm::convert::basic_type_from_layout;
+use crate::llvm::pretty_printers;
+use bumpalo::collections::Vec;
+use inkwell::debug_info::{
+    AsDIScope, DIFile, DIFlags, DIFlagsConstants, DILocalVariable, DILocation, DISubprogram, DIType,
+};
+use inkwell::values::{BasicValueEnum, FunctionValue};
+use inkwell::AddressSpace;
+use roc_builtins::bitcode::{FloatWidth, IntWidth};
+use roc_collections::all::{MutMap, MutSet};
+use roc_module::symbol::{ModuleId, Symbol};
+use roc_mono::ir::{Proc, Stmt};
+use roc_mono::layout::{Builtin, InLayout, LayoutInterner, LayoutRepr, STLayoutInterner};
+use roc_region::all::{LineColumn, LineInfo, Region};
+use std::cell::{Cell, RefCell};
+use std::path::Path;
+
+// DWARF base type encodings (DW_ATE_*)
+const DW_ATE_ADDRESS: u32 = 0x01;
+const DW_ATE_BOOLEAN: u32 = 0x02;
+const DW_ATE_FLOAT: u32 = 0x04;
+const DW_ATE_SIGNED: u32 = 0x05;
+const DW_ATE_UNSIGNED: u32 = 0x08;
+
+/// Roc source code and the regions of its definitions, for generating debug info
+pub struct DebugSources<'a> {
+    /// Path and line offsets of each module's source file
+    pub files: MutMap<ModuleId, (&'a Path, LineInfo)>,
+    pub definition_regions: MutMap<Symbol, Region>,
+    /// The module of the procedure we're generating code for, if we know where it's defined.
+    /// The source regions in its body are in this module.
+    pub current_module: Cell<Option<ModuleId>>,
+    /// The layouts of the types we named for the pretty printers (see [pretty_printers]).
+    /// Filled in while generating the debug info.
+    pub named_layouts: RefCell<MutSet<InLayout<'a>>>,
+}
+
+impl DebugSources<'_> {
+    /// The file, line and column (both 1-based) where `region` of `module` starts
+    fn location(&self, module: ModuleId, region: Region) -> Option<(&Path, u32, u32)> {
+        let (path, line_info) = self.files.get(&module)?;
+        let LineColumn { line, column } = line_info.convert_pos(region.start());
+
+        Some((path, line + 1, column + 1))
+    }
+
+    /// Where `proc` is defined. Procedures that aren't top-level definitions, like lambdas,
+    /// start at their first definition.
+    fn proc_location(&self, proc: &Proc) -> Option<(&Path, u32, u32)> {
+        let symbol = proc.name.name();
+        let region = match self.definition_regions.get(&symbol) {
+            Some(region) => *region,
+            None => first_source_region(&proc.body)?,
+        };
+
+        self.location(symbol.module_id(), region)
+    }
+}
+
+fn first_source_region(mut stmt: &Stmt) -> Option<Region> {
+    loop {
+        match stmt {
+            Stmt::SourceRegion { region, .. } => return Some(*region),
+            Stmt::Let(_, _, _, continuation) | Stmt::Refcounting(_, continuation) => {
+                stmt = continuation;
+            }
+            _ => return None,
+        }
+    }
+}
+
+fn file_from_path<'ctx>(env: &Env<'_, 'ctx, '_>, path: &Path) -> DIFile<'ctx> {
+    let filename = path.file_name().unwrap_or_default().to_string_lossy();
+    let directory = path.parent().unwrap_or(Path::new("")).to_string_lossy();
+
+    env.dibuilder.create_file(&filename, &directory)
+}
+
+/// The subprogram for a Roc procedure. Falls back to a placeholder if we don't know where the
+/// procedure was defined, e.g. for procedures generated by the compiler.
+pub(crate) fn proc_subprogram<'a, 'ctx>(
+    env: &Env<'a, 'ctx, '_>,
+    layout_interner: &STLayoutInterner<'a>,
+    proc: &Proc<'a>,
+    fn_name: &str,
+) -> DISubprogram<'ctx> {
+    let symbol = proc.name.name();
+    let location = env
+        .debug_sources
+        .as_ref()
+        .and_then(|sources| sources.proc_location(proc));
+
+    let (path, line, _) = match location {
+        Some(location) => location,
+        None => return env.new_subprogram(fn_name),
+    };
+
+    let file = file_from_path(env, path);
+
+    let return_type = ditype_from_layout(env, layout_interner, proc.ret_layout);
+    let parameter_types = Vec::from_iter_in(
+        proc.args
+            .iter()
+            .map(|(layout, _)| ditype_from_layout(env, layout_interner, *layout)),
+        env.arena,
+    );
+    let subroutine_type = env.dibuilder.create_subroutine_type(
+        file,
+        Some(return_type),
+        &parameter_types,
+        DIFlags::PUBLIC,
+    );
+
+    let roc_name = format!(
+        "{}.{}",
+        env.interns.module_name(symbol.module_id()).as_str(),
+        symbol.as_str(&env.interns)
+    );
+
+    env.dibuilder.create_function(
+        /* scope */ file.as_debug_info_scope(),
+        /* func name */ &roc_name,
+        /* linkage_name */ Some(fn_name),
+        /* file */ file,
+        /* line_no */ line,
+        /* DIType */ subroutine_type,
+        /* is_local_to_unit */ true,
+        /* is_definition */ true,
+        /* scope_line */ line,
+        /* flags */ DIFlags::PUBLIC,
+        /* is_optimized */ false,
+    )
+}
+
+/// Attribute the start of `proc` to the line of its definition, and describe its arguments.
+/// Does nothing if we don't know where `proc` was defined.
+pub(crate) fn init_proc<'a, 'ctx>(
+    env: &Env<'a, 'ctx, '_>,
+    layout_interner: &STLayoutInterner<'a>,
+    proc: &Proc<'a>,
+    fn_val: FunctionValue<'ctx>,
+) {
+    let sources = match &env.debug_sources {
+        Some(sources) => sources,
+        None => return,
+    };
+
+    let (path, line, column) = match sources.proc_location(proc) {
+        Some(location) => location,
+        None => {
+            sources.current_module.set(None);
+            return;
+        }
+    };
+    sources
+        .current_module
+        .set(Some(proc.name.name().module_id()));
+
+    let file = file_from_path(env, path);
+    let scope = fn_val
+        .get_subprogram()
+        .expect("subprogram")
+        .as_debug_info_scope();
+    let debug_loc = env
+        .dibuilder
+        .create_debug_location(env.context, line, column, scope, None);
+    env.builder.set_current_debug_location(debug_loc);
+
+    for (index, (value, (layout, symbol))) in fn_val.get_param_iter().zip(proc.args).enumerate() {
+        let variable = env.dibuilder.create_parameter_variable(
+            scope,
+            symbol.as_str(&env.interns),
+            /* arg_no */ index as u32 + 1,
+            file,
+            line,
+            ditype_from_layout(env, layout_interner, *layout),
+            /* always_preserve */ true,
+            DIFlags::ZERO,
+        );
+
+        declare_variable(
+            env,
+            layout_interner,
+            fn_val,
+            variable,
+            *layout,
+            value,
+            debug_loc,
+        );
+    }
+}
+
+/// Forget the module of the procedure we generated code for, so the functions that aren't Roc
+/// procedures don't get locations in it
+pub(crate) fn exit_proc(env: &Env) {
+    if let Some(sources) = &env.debug_sources {
+        sources.current_module.set(None);
+    }
+}
+
+/// Attribute the code that follows to the line where `region` starts, if we know where the
+/// current procedure is.
+pub(crate) fn enter_region<'ctx>(
+    env: &Env<'_, 'ctx, '_>,
+    parent: FunctionValue<'ctx>,
+    region: Region,
+) {
+    let location = env.debug_sources.as_ref().and_then(|sources| {
+        let module = sources.current_module.get()?;
+
+        sources.location(module, region)
+    });
+
+    if let (Some((_, line, column)), Some(subprogram)) = (location, parent.get_subprogram()) {
+        let debug_loc = env.dibuilder.create_debug_location(
+            env.context,
+            line,
+            column,
+            subprogram.as_debug_info_scope(),
+            None,
+        );
+
+        env.builder.set_current_debug_location(debug_loc);
+    }
+}
+
+/// Describe the value bound by a `let` as a local variable at the current location, so
+/// debuggers can show it. Values made up by the compiler are left out.
+pub(crate) fn declare_let<'a, 'ctx>(
+    env: &Env<'a, 'ctx, '_>,
+    layout_interner: &STLayoutInterner<'a>,
+    parent: FunctionValue<'ctx>,
+    symbol: Symbol,
+    layout: InLayout<'a>,
+    value: BasicValueEnum<'ctx>,
+) {
+    let file = env.debug_sources.as_ref().and_then(|sources| {
+        let module = sources.current_module.get()?;
+
+        sources.files.get(&module)
+    });
+    let path = match file {
+        Some((path, _)) => path,
+        None => return,
+    };
+
+    // Symbols made up by the compiler are just numbers
+    let name = symbol.as_str(&env.interns);
+    if name.starts_with(|c: char| c.is_ascii_digit()) {
+        return;
+    }
+
+    let debug_loc = match env.builder.get_current_debug_location() {
+        Some(debug_loc) => debug_loc,
+        None => return,
+    };
+    let line = debug_loc.get_line();
+
+    let variable = env.dibuilder.create_auto_variable(
+        parent.get_subprogram().unwrap().as_debug_info_scope(),
+        name,
+        file_from_path(env, path),
+        line,
+        ditype_from_layout(env, layout_interner, layout),
+        /* always_preserve */ true,
+        DIFlags::ZERO,
+        /* align_in_bits */ 0,
+    );
+
+    declare_variable(
+        env,
+        layout_interner,
+        parent,
+        variable,
+        layout,
+        value,
+        debug_loc,
+    );
+}
+
+fn declare_variable<'a, 'ctx>(
+    env: &Env<'a, 'ctx, '_>,
+    layout_interner: &STLayoutInterner<'a>,
+    parent: FunctionValue<'ctx>,
+    variable: DILocalVariable<'ctx>,
+    layout: InLayout<'a>,
+    value: BasicValueEnum<'ctx>,
+    debug_loc: DILocation<'ctx>,
+) {
+    if layout_interner.stack_size(layout) == 0 {
+        return;
+    }
+
+    let block = match env.builder.get_insert_block() {
+        Some(block) if block.get_terminator().is_none() => block,
+        _ => return,
+    };
+
+    // Large values are held by reference; then the pointer is the storage the debugger should
+    // look at. Other values get a stack slot, which LLVM turns back into a plain value
+    // when optimizing.
+    let basic_type = basic_type_from_layout(env, layout_interner, layout_interner.get_repr(layout));
+    let storage = if value.is_pointer_value() && !basic_type.is_pointer_type() {
+        value.into_pointer_value()
+    } else {
+        let alloca = create_entry_block_alloca(env, parent, value.get_type(), "");
+        env.builder.new_build_store(alloca, value);
+        alloca
+    };
+
+    env.dibuilder
+        .insert_declare_at_end(storage, Some(variable), None, debug_loc, block);
+}
+
+fn basic_ditype<'ctx>(
+    env: &Env<'_, 'ctx, '_>,
+    name: &str,
+    bits: u64,
+    encoding: u32,
+) -> DIType<'ctx> {
+    env.dibuilder
+        .create_basic_type(name, bits, encoding, DIFlags::PUBLIC)
+        .unwrap()
+        .as_type()
+}
+
+/// A struct type with the given `(name, type, size, alignment)` fields (sizes in bytes), laid
+/// out in order with their natural alignment, like LLVM does for our struct types.
+fn struct_ditype<'ctx>(
+    env: &Env<'_, 'ctx, '_>,
+    name: &str,
+    size: u32,
+    alignment: u32,
+    fields: &[(&str, DIType<'ctx>, u32, u32)],
+) -> DIType<'ctx> {
+    let file = env.compile_unit.get_file();
+    let scope = env.compile_unit.as_debug_info_scope();
+
+    let mut offset = 0;
+    let mut members = Vec::with_capacity_in(fields.len(), env.arena);
+
+    for (field_name, field_type, field_size, field_alignment) in fields {
+        offset = round_up_to_alignment(offset, *field_alignment);
+
+        let member = env.dibuilder.create_member_type(
+            scope,
+            field_name,
+            file,
+            /* line_no */ 0,
+            *field_size as u64 * 8,
+            *field_alignment * 8,
+            offset as u64 * 8,
+            DIFlags::PUBLIC,
+            *field_type,
+        );
+        members.push(member.as_type());
+
+        offset += field_size;
+    }
+
+    env.dibuilder
+        .create_struct_type(
+            scope,
+            name,
+            file,
+            /* line_no */ 0,
+            size as u64 * 8,
+            alignment * 8,
+            DIFlags::PUBLIC,
+            /* derived_from */ None,
+            &members,
+            /* runtime_language */ 0,
+            /* vtable_holder */ None,
+            /* unique_id */ "",
+        )
+        .as_type()
+}
+
+fn round_up_to_alignment(offset: u32, alignment: u32) -> u32 {
+    match alignment {
+        0 | 1 => offset,
+        _ => (offset + alignment - 1) / alignment * alignment,
+    }
+}
+
+/// The debug info type of a layout. Unions, erased values and recursive pointers are opaque;
+/// the pretty printers decode them by their type names.
+fn ditype_from_layout<'a, 'ctx>(
+    env: &Env<'a, 'ctx, '_>,
+    layout_interner: &STLayoutInterner<'a>,
+    layout: InLayout<'a>,
+) -> DIType<'ctx> {
+    let (size, alignment) = layout_interner.stack_size_and_alignment(layout);
+    let ptr_bytes = env.target.ptr_width() as u32;
+
+    let printer_name = pretty_printers::type_name(layout_interner, layout);
+    if let (Some(_), Some(sources)) = (&printer_name, &env.debug_sources) {
+        let named_layout = layout_interner.chase_recursive_in(layout);
+        sources.named_layouts.borrow_mut().insert(named_layout);
+    }
+    let name = |default: &'static str| printer_name.as_deref().unwrap_or(default);
+
+    match layout_interner.get_repr(layout) {
+        LayoutRepr::Builtin(builtin) => match builtin {
+            Builtin::Int(int_width) => {
+                let name = match int_width {
+                    IntWidth::U8 => "U8",
+                    IntWidth::U16 => "U16",
+                    IntWidth::U32 => "U32",
+                    IntWidth::U64 => "U64",
+                    IntWidth::U128 => "U128",
+                    IntWidth::I8 => "I8",
+                    IntWidth::I16 => "I16",
+                    IntWidth::I32 => "I32",
+                    IntWidth::I64 => "I64",
+                    IntWidth::I128 => "I128",
+                };
+                let encoding = if int_width.is_signed() {
+                    DW_ATE_SIGNED
+                } else {
+                    DW_ATE_UNSIGNED
+                };
+
+                basic_ditype(
+                    env,
+                    printer_name.as_deref().unwrap_or(name),
+                    int_width.stack_size() as u64 * 8,
+                    encoding,
+                )
+            }
+            Builtin::Float(FloatWidth::F32) => basic_ditype(env, "F32", 32, DW_ATE_FLOAT),
+            Builtin::Float(FloatWidth::F64) => basic_ditype(env, "F64", 64, DW_ATE_FLOAT),
+            Builtin::Bool => basic_ditype(env, name("Bool"), 8, DW_ATE_BOOLEAN),
+            Builtin::Decimal => basic_ditype(env, "Dec", 128, DW_ATE_SIGNED),
+            Builtin::Str => {
+                let byte = basic_ditype(env, "U8", 8, DW_ATE_UNSIGNED);
+
+                list_like_ditype(env, "Str", "bytes", byte, size, alignment, ptr_bytes)
+            }
+            Builtin::List(element) => {
+                let element = ditype_from_layout(env, layout_interner, element);
+
+                list_like_ditype(
+                    env,
+                    name("List"),
+                    "elements",
+                    element,
+                    size,
+                    alignment,
+                    ptr_bytes,
+                )
+            }
+        },
+        LayoutRepr::Struct(field_layouts) => {
+            // Records and tuples keep their field names in the layout's order
+            let semantic = layout_interner.get_semantic(layout);
+            let names = match (semantic.record_fields(), semantic.tuple_indices()) {
+                (Some(fields), _) if fields.len() == field_layouts.len() => {
+                    Vec::from_iter_in(fields.iter().map(|field| field.to_string()), env.arena)
+                }
+                (_, Some(indices)) if indices.len() == field_layouts.len() => {
+                    Vec::from_iter_in(indices.iter().map(|index| index.to_string()), env.arena)
+                }
+                _ => Vec::from_iter_in(
+                    (0..field_layouts.len()).map(|index| index.to_string()),
+                    env.arena,
+                ),
+            };
+            let fields = Vec::from_iter_in(
+                field_layouts.iter().zip(names.iter()).map(|(field, name)| {
+                    let (field_size, field_alignment) =
+                        layout_interner.stack_size_and_alignment(*field);
+                    let field_type = ditype_from_layout(env, layout_interner, *field);
+
+                    (name.as_str(), field_type, field_size, field_alignment)
+                }),
+                env.arena,
+            );
+
+            struct_ditype(env, name("{}"), size, alignment, &fields)
+        }
+        LayoutRepr::Ptr(inner) => {
+            let pointee = ditype_from_layout(env, layout_interner, inner);
+
+            env.dibuilder
+                .create_pointer_type(
+                    "Ptr",
+                    pointee,
+                    ptr_bytes as u64 * 8,
+                    ptr_bytes * 8,
+                    AddressSpace::default(),
+                )
+                .as_type()
+        }
+        LayoutRepr::LambdaSet(lambda_set) => {
+            ditype_from_layout(env, layout_interner, lambda_set.runtime_representation())
+        }
+        LayoutRepr::RecursivePointer(_) => basic_ditype(
+            env,
+            name("RecursivePointer"),
+            ptr_bytes as u64 * 8,
+            DW_ATE_ADDRESS,
+        ),
+        LayoutRepr::FunctionPointer(_) => {
+            basic_ditype(env, "FunctionPointer", ptr_bytes as u64 * 8, DW_ATE_ADDRESS)
+        }
+        LayoutRepr::Union(_) => struct_ditype(env, name("Union"), size, alignment, &[]),
+        LayoutRepr::Erased(_) => struct_ditype(env, "Erased", size, alignment, &[]),
+    }
+}
+
+/// `Str` and `List` are a pointer to their elements, a length and a capacity
+fn list_like_ditype<'ctx>(
+    env: &Env<'_, 'ctx, '_>,
+    name: &str,
+    pointer_name: &str,
+    element: DIType<'ctx>,
+    size: u32,
+    alignment: u32,
+    ptr_bytes: u32,
+) -> DIType<'ctx> {
+    let pointer = env
+        .dibuilder
+        .create_pointer_type(
+            "",
+            element,
+            ptr_bytes as u64 * 8,
+            ptr_bytes * 8,
+            AddressSpace::default(),
+        )
+        .as_type();
+    let usize_name = if ptr_bytes == 4 { "U32" } else { "U64" };
+    let usize = basic_ditype(env, usize_name, ptr_bytes as u64 * 8, DW_ATE_UNSIGNED);
+
+    struct_ditype(
+        env,
+        name,
+        size,
+        alignment,
+        &[
+            (pointer_name, pointer, ptr_bytes, ptr_bytes),
+            ("length", usize, ptr_bytes, ptr_bytes),
+            ("capacity", usize, ptr_bytes, ptr_bytes),
+        ],
+    )
+}
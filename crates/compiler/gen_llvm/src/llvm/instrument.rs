@@ -0,0 +1,286 @@
+//! Allocation and refcount instrumentation, emitted by `roc build --instrument-memory`.
+//!
+//! Every allocation, reallocation and deallocation, and every refcount increment and decrement,
+//! is reported to the host through the `roc_memory_on_*` hooks (the `instrument-memory` feature
+//! of `roc_std` provides them). Each hook also gets the site: a NUL-terminated
+//! `"Module.name (file:line:col)"` string describing the Roc procedure that was running.
+//!
+//! The current site lives in the thread-local `roc_memory_site` global. It is set on entry to
+//! every procedure and again whenever a call to another Roc procedure returns, so allocations
+//! made by builtins are attributed to the procedure that called them.
+use crate::debug_info_init;
+use crate::llvm::build::{BuilderExt, Env, C_CALL_CONV};
+use bumpalo::collections::Vec;
+use inkwell::module::Linkage;
+use inkwell::types::{BasicMetadataTypeEnum, PointerType};
+use inkwell::values::{
+    BasicMetadataValueEnum, BasicValue, BasicValueEnum, FunctionValue, GlobalValue, IntValue,
+    PointerValue,
+};
+use inkwell::AddressSpace;
+use roc_collections::all::MutMap;
+use roc_module::symbol::{ModuleId, Symbol};
+use roc_mono::ir::Proc;
+use roc_region::all::{LineColumn, LineInfo, Region};
+use std::path::Path;
+
+const SITE_GLOBAL: &str = "roc_memory_site";
+
+const ON_ALLOC: &str = "roc_memory_on_alloc";
+const ON_REALLOC: &str = "roc_memory_on_realloc";
+const ON_DEALLOC: &str = "roc_memory_on_dealloc";
+const ON_INC: &str = "roc_memory_on_inc";
+const ON_DEC: &str = "roc_memory_on_dec";
+
+/// Roc source code and the regions of its definitions, for describing allocation sites
+pub struct MemoryInstrumentation<'a> {
+    /// Path and line offsets of each module's source file
+    pub files: MutMap<ModuleId, (&'a Path, LineInfo)>,
+    pub definition_regions: MutMap<Symbol, Region>,
+}
+
+impl MemoryInstrumentation<'_> {
+    /// The file, line and column (both 1-based) where `symbol` is defined
+    fn location(&self, symbol: Symbol) -> Option<(&Path, u32, u32)> {
+        let region = self.definition_regions.get(&symbol)?;
+        let (path, line_info) = self.files.get(&symbol.module_id())?;
+        let LineColumn { line, column } = line_info.convert_pos(region.start());
+
+        Some((path, line + 1, column + 1))
+    }
+}
+
+fn i8_ptr_type<'ctx>(env: &Env<'_, 'ctx, '_>) -> PointerType<'ctx> {
+    env.context.i8_type().ptr_type(AddressSpace::default())
+}
+
+fn site_global<'ctx>(env: &Env<'_, 'ctx, '_>) -> GlobalValue<'ctx> {
+    match env.module.get_global(SITE_GLOBAL) {
+        Some(global) => global,
+        None => {
+            let typ = i8_ptr_type(env);
+            let global = env.module.add_global(typ, None, SITE_GLOBAL);
+
+            global.set_initializer(&typ.const_null());
+            global.set_linkage(Linkage::Internal);
+            // Every thread of the host runs its own Roc code
+            global.set_thread_local(true);
+
+            global
+        }
+    }
+}
+
+fn site_name(fn_val: FunctionValue<'_>) -> String {
+    format!("{SITE_GLOBAL}.{}", fn_val.get_name().to_str().unwrap())
+}
+
+fn current_site<'ctx>(env: &Env<'_, 'ctx, '_>) -> BasicValueEnum<'ctx> {
+    env.builder.new_build_load(
+        i8_ptr_type(env),
+        site_global(env).as_pointer_value(),
+        "memory_site",
+    )
+}
+
+/// Call one of the `roc_memory_on_*` hooks, declaring it if this is the first call
+fn call_hook<'ctx>(env: &Env<'_, 'ctx, '_>, name: &str, args: &[BasicValueEnum<'ctx>]) {
+    let hook = match env.module.get_function(name) {
+        Some(hook) => hook,
+        None => {
+            let param_types = Vec::from_iter_in(
+                args.iter()
+                    .map(|arg| BasicMetadataTypeEnum::from(arg.get_type())),
+                env.arena,
+            );
+            let fn_type = env.context.void_type().fn_type(&param_types, false);
+
+            env.module
+                .add_function(name, fn_type, Some(Linkage::External))
+        }
+    };
+
+    let args = Vec::from_iter_in(
+        args.iter().map(|arg| BasicMetadataValueEnum::from(*arg)),
+        env.arena,
+    );
+    let call = env.builder.new_build_call(hook, &args, "");
+
+    call.set_call_convention(C_CALL_CONV);
+}
+
+/// Record `proc` as the current site. Called at the start of its entry block.
+pub(crate) fn enter_proc<'ctx>(
+    env: &Env<'_, 'ctx, '_>,
+    proc: &Proc<'_>,
+    fn_val: FunctionValue<'ctx>,
+) {
+    let instrumentation = match &env.instrument_memory {
+        Some(instrumentation) => instrumentation,
+        None => return,
+    };
+
+    let symbol = proc.name.name();
+    let roc_name = format!(
+        "{}.{}",
+        env.interns.module_name(symbol.module_id()).as_str(),
+        symbol.as_str(&env.interns)
+    );
+    let description = match instrumentation.location(symbol) {
+        Some((path, line, column)) => format!("{roc_name} ({}:{line}:{column})", path.display()),
+        None => roc_name,
+    };
+
+    let name = site_name(fn_val);
+    let site = match env.module.get_global(&name) {
+        Some(site) => site,
+        None => {
+            let text = env.context.const_string(description.as_bytes(), true);
+            let site = env.module.add_global(text.get_type(), None, &name);
+
+            site.set_initializer(&text);
+            site.set_constant(true);
+            site.set_unnamed_addr(true);
+            site.set_linkage(Linkage::Private);
+
+            site
+        }
+    };
+
+    set_site(env, site);
+}
+
+/// Restore the site of the function we're building, after a call to another Roc procedure
+/// has overwritten it.
+pub(crate) fn restore_site(env: &Env<'_, '_, '_>) {
+    if env.instrument_memory.is_none() {
+        return;
+    }
+
+    let parent = env
+        .builder
+        .get_insert_block()
+        .and_then(|block| block.get_parent())
+        .unwrap();
+
+    // Wrappers generated for the host don't have a site of their own
+    if let Some(site) = env.module.get_global(&site_name(parent)) {
+        set_site(env, site);
+    }
+}
+
+fn set_site<'ctx>(env: &Env<'_, 'ctx, '_>, site: GlobalValue<'ctx>) {
+    let site_ptr = env.builder.new_build_pointer_cast(
+        site.as_pointer_value(),
+        i8_ptr_type(env),
+        "memory_site_ptr",
+    );
+    env.builder
+        .new_build_store(site_global(env).as_pointer_value(), site_ptr);
+}
+
+/// Report an increment by `amount` of the refcount of the allocation `pointer` points into
+pub(crate) fn on_inc<'ctx>(
+    env: &Env<'_, 'ctx, '_>,
+    pointer: PointerValue<'ctx>,
+    amount: IntValue<'ctx>,
+) {
+    if env.instrument_memory.is_some() {
+        let ptr = env
+            .builder
+            .new_build_pointer_cast(pointer, i8_ptr_type(env), "to_i8_ptr");
+        call_hook(env, ON_INC, &[ptr.into(), amount.into(), current_site(env)]);
+    }
+}
+
+/// Report a decrement of the refcount of the allocation `pointer` points into. The pointer may
+/// be null.
+pub(crate) fn on_dec<'ctx>(env: &Env<'_, 'ctx, '_>, pointer: PointerValue<'ctx>) {
+    if env.instrument_memory.is_some() {
+        let ptr = env
+            .builder
+            .new_build_pointer_cast(pointer, i8_ptr_type(env), "to_i8_ptr");
+        call_hook(env, ON_DEC, &[ptr.into(), current_site(env)]);
+    }
+}
+
+/// Route every call to the host's `roc_alloc`, `roc_realloc` and `roc_dealloc` (including the
+/// ones from the builtins) through wrappers that report to the hooks. Run this after all
+/// procedures have been built. Allocators that we define ourselves are left alone.
+pub fn wrap_allocators(env: &Env<'_, '_, '_>) {
+    if env.instrument_memory.is_none() {
+        return;
+    }
+
+    // roc_alloc(size, alignment) -> ptr
+    wrap_allocator(env, "roc_alloc", |env, params, result| {
+        call_hook(
+            env,
+            ON_ALLOC,
+            &[result.unwrap(), params[0], params[1], current_site(env)],
+        );
+    });
+
+    // roc_realloc(ptr, new_size, old_size, alignment) -> ptr
+    wrap_allocator(env, "roc_realloc", |env, params, result| {
+        call_hook(
+            env,
+            ON_REALLOC,
+            &[params[0], result.unwrap(), params[1], current_site(env)],
+        );
+    });
+
+    // roc_dealloc(ptr, alignment)
+    wrap_allocator(env, "roc_dealloc", |env, params, _| {
+        call_hook(env, ON_DEALLOC, &[params[0], current_site(env)]);
+    });
+}
+
+fn wrap_allocator<'ctx>(
+    env: &Env<'_, 'ctx, '_>,
+    name: &str,
+    report: impl Fn(&Env<'_, 'ctx, '_>, &[BasicValueEnum<'ctx>], Option<BasicValueEnum<'ctx>>),
+) {
+    let original = match env.module.get_function(name) {
+        Some(original) if original.count_basic_blocks() == 0 => original,
+        _ => return,
+    };
+
+    let wrapper_name = name.replacen("roc_", "roc_instrumented_", 1);
+    let wrapper =
+        env.module
+            .add_function(&wrapper_name, original.get_type(), Some(Linkage::Internal));
+
+    // Must happen before we add the wrapper's own call to the original
+    original.replace_all_uses_with(wrapper);
+
+    let subprogram = env.new_subprogram(&wrapper_name);
+    wrapper.set_subprogram(subprogram);
+
+    let entry = env.context.append_basic_block(wrapper, "entry");
+    env.builder.position_at_end(entry);
+
+    debug_info_init!(env, wrapper);
+
+    let params = Vec::from_iter_in(wrapper.get_param_iter(), env.arena);
+    let args = Vec::from_iter_in(
+        params
+            .iter()
+            .map(|param| BasicMetadataValueEnum::from(*param)),
+        env.arena,
+    );
+
+    let call = env.builder.new_build_call(original, &args, name);
+    call.set_call_convention(C_CALL_CONV);
+
+    let result = call.try_as_basic_value().left();
+
+    report(env, &params, result);
+
+    env.builder
+        .new_build_return(result.as_ref().map(|result| result as &dyn BasicValue));
+
+    if cfg!(debug_assertions) {
+        crate::llvm::build::verify_fn(wrapper);
+    }
+}
@@ -4,10 +4,13 @@ pub mod build_list;
 pub mod build_str;
 pub mod compare;
 pub mod convert;
+pub mod debug_info;
 mod expect;
 pub mod externs;
+pub mod instrument;
 mod intrinsics;
 mod lowlevel;
+pub mod pretty_printers;
 pub mod refcounting;
 
 mod align;
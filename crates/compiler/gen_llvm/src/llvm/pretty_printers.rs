@@ -0,0 +1,1107 @@
+//! gdb and lldb pretty printers for Roc values, written next to the binary by `roc build --debug`.
+//!
+//! The debug info describes most values well enough on its own, but not the ones whose memory
+//! takes some decoding: small strings, lists behind a pointer, tag unions with their tag id in
+//! the data or in the pointer, `Dict`s, `Box`es and `Dec`s. Their types are named after their
+//! layout, like `List#12` or `Union#7`, and the printers look up how to decode them in a table
+//! of layouts that we generate here. Host types from `roc_std` get printers by their name.
+use roc_collections::all::MutSet;
+use roc_mono::layout::{
+    Builtin, InLayout, LayoutInterner, LayoutRepr, STLayoutInterner, UnionLayout,
+};
+use std::fmt::Write;
+
+const ROC_VALUES: &str = include_str!("pretty_printers/roc_values.py");
+const GDB: &str = include_str!("pretty_printers/gdb.py");
+const LLDB: &str = include_str!("pretty_printers/lldb.py");
+
+const DICT_FIELDS: [&str; 5] = [
+    "buckets",
+    "data",
+    "maxBucketCapacity",
+    "maxLoadFactor",
+    "shifts",
+];
+
+/// A gdb script with printers for the values of `layouts`. gdb loads it automatically from
+/// `<binary>-gdb.py`.
+pub fn gdb_script<'a>(interner: &STLayoutInterner<'a>, layouts: &[InLayout<'a>]) -> String {
+    script(interner, layouts, GDB)
+}
+
+/// An lldb script with formatters for the values of `layouts`, to load with
+/// `command script import`.
+pub fn lldb_script<'a>(interner: &STLayoutInterner<'a>, layouts: &[InLayout<'a>]) -> String {
+    script(interner, layouts, LLDB)
+}
+
+fn script<'a>(interner: &STLayoutInterner<'a>, layouts: &[InLayout<'a>], debugger: &str) -> String {
+    let mut script = String::from("# Generated by `roc build --debug`.\n\n");
+
+    script.push_str(&python_layouts(interner, layouts));
+    script.push('\n');
+    script.push_str(ROC_VALUES);
+    script.push('\n');
+    script.push_str(debugger);
+
+    script
+}
+
+/// The name of `layout`'s type in the debug info, if its values need a pretty printer
+pub fn type_name<'a>(interner: &STLayoutInterner<'a>, layout: InLayout<'a>) -> Option<String> {
+    let layout = interner.chase_recursive_in(layout);
+
+    let kind = match interner.get_repr(layout) {
+        LayoutRepr::Builtin(Builtin::Str) => return Some("Str".to_string()),
+        LayoutRepr::Builtin(Builtin::Decimal) => return Some("Dec".to_string()),
+        LayoutRepr::Builtin(Builtin::List(_)) => "List",
+        LayoutRepr::Builtin(_) | LayoutRepr::Struct(&[]) => {
+            enum_tag_names(interner, layout)?;
+
+            "Union"
+        }
+        LayoutRepr::Struct(_) => match dict_entries(interner, layout)? {
+            DictEntries { value: None, .. } => "Set",
+            DictEntries { value: Some(_), .. } => "Dict",
+        },
+        LayoutRepr::Union(union_layout) if is_box(interner, layout, union_layout) => "Box",
+        LayoutRepr::Union(_) => "Union",
+        LayoutRepr::Ptr(_)
+        | LayoutRepr::LambdaSet(_)
+        | LayoutRepr::RecursivePointer(_)
+        | LayoutRepr::FunctionPointer(_)
+        | LayoutRepr::Erased(_) => return None,
+    };
+
+    Some(format!("{kind}#{}", layout.index()))
+}
+
+/// The names of the tags of a union that is represented as just its tag id, like `[Red, Green]`
+fn enum_tag_names<'a>(
+    interner: &STLayoutInterner<'a>,
+    layout: InLayout<'a>,
+) -> Option<&'a [&'a str]> {
+    match interner.get_repr(layout) {
+        LayoutRepr::Builtin(Builtin::Bool | Builtin::Int(_)) | LayoutRepr::Struct(&[]) => {
+            interner.get_semantic(layout).tag_names()
+        }
+        _ => None,
+    }
+}
+
+/// Where the keys and values are in the entries of a `Dict` (or of a `Set`, which is a
+/// `Dict k {}`)
+struct DictEntries<'a> {
+    /// The offset of the list of entries in the dict, and the layout of that list
+    entries: (u32, InLayout<'a>),
+    key: (u32, InLayout<'a>),
+    /// `None` for sets
+    value: Option<(u32, InLayout<'a>)>,
+}
+
+/// Recognizes the record inside the `Dict` opaque type by its field names
+fn dict_entries<'a>(
+    interner: &STLayoutInterner<'a>,
+    layout: InLayout<'a>,
+) -> Option<DictEntries<'a>> {
+    let field_layouts = match interner.get_repr(layout) {
+        LayoutRepr::Struct(field_layouts) => field_layouts,
+        _ => return None,
+    };
+    let field_names = interner.get_semantic(layout).record_fields()?;
+
+    let mut sorted_names = field_names.to_vec();
+    sorted_names.sort_unstable();
+    if sorted_names != DICT_FIELDS {
+        return None;
+    }
+
+    let data_index = field_names.iter().position(|name| *name == "data")?;
+    let entries = field_layouts[data_index];
+    let entry = match interner.get_repr(entries) {
+        LayoutRepr::Builtin(Builtin::List(entry)) => entry,
+        _ => return None,
+    };
+
+    let entry_fields = match interner.get_repr(entry) {
+        LayoutRepr::Struct(entry_fields) => entry_fields,
+        _ => return None,
+    };
+    let indices = interner.get_semantic(entry).tuple_indices()?;
+    let offsets = field_offsets(interner, entry_fields);
+
+    let element = |index| {
+        let position = indices.iter().position(|i| *i == index)?;
+
+        Some((offsets[position], entry_fields[position]))
+    };
+
+    let key = element(0)?;
+    let value = element(1)?;
+    let value = if interner.stack_size(value.1) == 0 {
+        None
+    } else {
+        Some(value)
+    };
+
+    Some(DictEntries {
+        entries: (field_offsets(interner, field_layouts)[data_index], entries),
+        key,
+        value,
+    })
+}
+
+/// `Box a` is a union with one tag that holds an `a`. Unlike recursive unions with one tag,
+/// that `a` never points back to the union itself.
+fn is_box<'a>(
+    interner: &STLayoutInterner<'a>,
+    layout: InLayout<'a>,
+    union_layout: UnionLayout<'a>,
+) -> bool {
+    match union_layout {
+        UnionLayout::NonNullableUnwrapped([inner]) => {
+            !points_to(interner, *inner, layout, &mut Vec::new())
+        }
+        _ => false,
+    }
+}
+
+/// Whether `layout` contains a recursive pointer to `target`
+fn points_to<'a>(
+    interner: &STLayoutInterner<'a>,
+    layout: InLayout<'a>,
+    target: InLayout<'a>,
+    seen: &mut Vec<InLayout<'a>>,
+) -> bool {
+    if seen.contains(&layout) {
+        return false;
+    }
+    seen.push(layout);
+
+    let mut points_to_target = |layout| points_to(interner, layout, target, seen);
+
+    match interner.get_repr(layout) {
+        LayoutRepr::RecursivePointer(pointee) => pointee == target,
+        LayoutRepr::Builtin(Builtin::List(element)) => points_to_target(element),
+        LayoutRepr::Builtin(_) => false,
+        LayoutRepr::Struct(fields) => fields.iter().any(|field| points_to_target(*field)),
+        LayoutRepr::Ptr(pointee) => points_to_target(pointee),
+        LayoutRepr::Union(union_layout) => tags(union_layout)
+            .iter()
+            .flatten()
+            .any(|fields| fields.iter().any(|field| points_to_target(*field))),
+        LayoutRepr::LambdaSet(lambda_set) => points_to_target(lambda_set.runtime_representation()),
+        LayoutRepr::FunctionPointer(_) | LayoutRepr::Erased(_) => false,
+    }
+}
+
+/// The fields of each tag of a union by tag id, or `None` for the tag that is a null pointer
+fn tags<'a>(union_layout: UnionLayout<'a>) -> Vec<Option<&'a [InLayout<'a>]>> {
+    match union_layout {
+        UnionLayout::NonRecursive(tags) | UnionLayout::Recursive(tags) => {
+            tags.iter().map(|fields| Some(*fields)).collect()
+        }
+        UnionLayout::NonNullableUnwrapped(fields) => vec![Some(fields)],
+        UnionLayout::NullableWrapped {
+            nullable_id,
+            other_tags,
+        } => (0..=other_tags.len() as u16)
+            .map(|tag_id| match tag_id.cmp(&nullable_id) {
+                std::cmp::Ordering::Less => Some(other_tags[tag_id as usize]),
+                std::cmp::Ordering::Equal => None,
+                std::cmp::Ordering::Greater => Some(other_tags[tag_id as usize - 1]),
+            })
+            .collect(),
+        UnionLayout::NullableUnwrapped {
+            nullable_id,
+            other_fields,
+        } => {
+            let mut tags = vec![Some(other_fields), Some(other_fields)];
+            tags[nullable_id as usize] = None;
+
+            tags
+        }
+    }
+}
+
+/// The offset of each field of a struct. Like LLVM does for our struct types, each field is
+/// placed after the previous one at its natural alignment.
+fn field_offsets<'a>(interner: &STLayoutInterner<'a>, fields: &[InLayout<'a>]) -> Vec<u32> {
+    let mut offset = 0;
+
+    fields
+        .iter()
+        .map(|field| {
+            let (size, alignment) = interner.stack_size_and_alignment(*field);
+            let field_offset = round_up_to_alignment(offset, alignment);
+            offset = field_offset + size;
+
+            field_offset
+        })
+        .collect()
+}
+
+fn round_up_to_alignment(offset: u32, alignment: u32) -> u32 {
+    match alignment {
+        0 | 1 => offset,
+        _ => (offset + alignment - 1) / alignment * alignment,
+    }
+}
+
+/// The `PTR_BYTES` and `LAYOUTS` definitions that `roc_values.py` expects, for `roots` and all
+/// the layouts inside them
+fn python_layouts<'a>(interner: &STLayoutInterner<'a>, roots: &[InLayout<'a>]) -> String {
+    let mut python = String::new();
+    let mut stack: Vec<InLayout<'a>> = roots.iter().rev().copied().collect();
+    let mut seen = MutSet::default();
+
+    writeln!(
+        python,
+        "PTR_BYTES = {}",
+        interner.target().ptr_width() as u32
+    )
+    .unwrap();
+    python.push_str("LAYOUTS = {\n");
+
+    while let Some(layout) = stack.pop() {
+        let layout = interner.chase_recursive_in(layout);
+
+        if !seen.insert(layout) {
+            continue;
+        }
+
+        let description = describe(interner, layout, &mut stack);
+        writeln!(python, "    {}: {},", layout.index(), description).unwrap();
+    }
+
+    python.push_str("}\n");
+
+    python
+}
+
+/// A Python dict describing how `roc_values.py` should decode values of `layout`. Pushes the
+/// layouts it refers to onto `stack`.
+fn describe<'a>(
+    interner: &STLayoutInterner<'a>,
+    layout: InLayout<'a>,
+    stack: &mut Vec<InLayout<'a>>,
+) -> String {
+    let size = interner.stack_size(layout);
+    let mut id = |layout: InLayout<'a>| {
+        let layout = interner.chase_recursive_in(layout);
+        stack.push(layout);
+
+        layout.index()
+    };
+
+    if let Some(names) = enum_tag_names(interner, layout) {
+        return format!(
+            r#"{{"kind": "enum", "size": {size}, "names": {}}}"#,
+            python_strings(names)
+        );
+    }
+
+    match interner.get_repr(layout) {
+        LayoutRepr::Builtin(builtin) => match builtin {
+            Builtin::Int(int_width) => format!(
+                r#"{{"kind": "int", "size": {size}, "signed": {}}}"#,
+                if int_width.is_signed() {
+                    "True"
+                } else {
+                    "False"
+                }
+            ),
+            Builtin::Float(_) => format!(r#"{{"kind": "float", "size": {size}}}"#),
+            Builtin::Bool => format!(r#"{{"kind": "bool", "size": {size}}}"#),
+            Builtin::Decimal => format!(r#"{{"kind": "dec", "size": {size}}}"#),
+            Builtin::Str => format!(r#"{{"kind": "str", "size": {size}}}"#),
+            Builtin::List(element) => format!(
+                r#"{{"kind": "list", "size": {size}, "element": {}}}"#,
+                id(element)
+            ),
+        },
+        LayoutRepr::Struct(field_layouts) => {
+            if let Some(dict) = dict_entries(interner, layout) {
+                let value = match dict.value {
+                    Some((offset, value)) => format!("({offset}, {})", id(value)),
+                    None => "None".to_string(),
+                };
+
+                return format!(
+                    r#"{{"kind": "dict", "size": {size}, "entries": ({}, {}), "key": ({}, {}), "value": {value}}}"#,
+                    dict.entries.0,
+                    id(dict.entries.1),
+                    dict.key.0,
+                    id(dict.key.1),
+                );
+            }
+
+            let offsets = field_offsets(interner, field_layouts);
+            let semantic = interner.get_semantic(layout);
+
+            match semantic.record_fields() {
+                Some(names) if names.len() == field_layouts.len() => {
+                    let fields = names
+                        .iter()
+                        .zip(offsets)
+                        .zip(field_layouts.iter())
+                        .map(|((name, offset), field)| {
+                            format!("({name:?}, {offset}, {})", id(*field))
+                        })
+                        .collect::<Vec<_>>();
+
+                    format!(
+                        r#"{{"kind": "record", "size": {size}, "fields": [{}]}}"#,
+                        fields.join(", ")
+                    )
+                }
+                _ => {
+                    // Tuples, and structs the compiler made up. Those show their fields in order.
+                    let indices = match semantic.tuple_indices() {
+                        Some(indices) if indices.len() == field_layouts.len() => indices.to_vec(),
+                        _ => (0..field_layouts.len()).collect(),
+                    };
+                    let fields = indices
+                        .iter()
+                        .zip(offsets)
+                        .zip(field_layouts.iter())
+                        .map(|((index, offset), field)| {
+                            format!("({index}, {offset}, {})", id(*field))
+                        })
+                        .collect::<Vec<_>>();
+
+                    format!(
+                        r#"{{"kind": "tuple", "size": {size}, "fields": [{}]}}"#,
+                        fields.join(", ")
+                    )
+                }
+            }
+        }
+        LayoutRepr::Union(union_layout) => {
+            if is_box(interner, layout, union_layout) {
+                let inner = match union_layout {
+                    UnionLayout::NonNullableUnwrapped([inner]) => *inner,
+                    _ => unreachable!(),
+                };
+
+                return format!(
+                    r#"{{"kind": "box", "size": {size}, "inner": {}}}"#,
+                    id(inner)
+                );
+            }
+
+            describe_union(interner, layout, union_layout, &mut id)
+        }
+        LayoutRepr::Ptr(_) => format!(r#"{{"kind": "pointer", "size": {size}}}"#),
+        LayoutRepr::LambdaSet(_) | LayoutRepr::FunctionPointer(_) | LayoutRepr::Erased(_) => {
+            format!(r#"{{"kind": "function", "size": {size}}}"#)
+        }
+        LayoutRepr::RecursivePointer(_) => {
+            // `python_layouts` describes the union that this points to instead
+            unreachable!()
+        }
+    }
+}
+
+fn describe_union<'a>(
+    interner: &STLayoutInterner<'a>,
+    layout: InLayout<'a>,
+    union_layout: UnionLayout<'a>,
+    id: &mut impl FnMut(InLayout<'a>) -> usize,
+) -> String {
+    let target = interner.target();
+    let size = interner.stack_size(layout);
+    let tags = tags(union_layout);
+
+    let tags_python = tags
+        .iter()
+        .map(|fields| match fields {
+            Some(fields) => {
+                let fields = field_offsets(interner, fields)
+                    .into_iter()
+                    .zip(fields.iter())
+                    .map(|(offset, field)| format!("({offset}, {})", id(*field)))
+                    .collect::<Vec<_>>();
+
+                format!("[{}]", fields.join(", "))
+            }
+            None => "None".to_string(),
+        })
+        .collect::<Vec<_>>();
+
+    let tag_id = if union_layout.stores_tag_id_in_pointer(target) {
+        let (_, mask) = UnionLayout::tag_id_pointer_bits_and_mask(target);
+
+        format!(r#"{{"in": "pointer", "mask": {mask}}}"#)
+    } else {
+        let tag_id_size = union_layout.discriminant().stack_size();
+
+        match union_layout.tag_id_offset(interner) {
+            Some(offset) if tag_id_size > 0 => {
+                format!(r#"{{"in": "data", "offset": {offset}, "size": {tag_id_size}}}"#)
+            }
+            _ => "None".to_string(),
+        }
+    };
+
+    let null_tag = match union_layout {
+        UnionLayout::NullableWrapped { nullable_id, .. } => nullable_id.to_string(),
+        UnionLayout::NullableUnwrapped { nullable_id, .. } => (nullable_id as u16).to_string(),
+        _ => "None".to_string(),
+    };
+
+    let names = match interner.get_semantic(layout).tag_names() {
+        Some(names) if names.len() == tags.len() => python_strings(names),
+        _ => "None".to_string(),
+    };
+
+    format!(
+        r#"{{"kind": "union", "size": {size}, "recursive": {}, "data_size": {}, "tags": [{}], "tag_id": {tag_id}, "null_tag": {null_tag}, "names": {names}}}"#,
+        if union_layout.is_recursive() {
+            "True"
+        } else {
+            "False"
+        },
+        union_layout.data_size_and_alignment(interner).0,
+        tags_python.join(", "),
+    )
+}
+
+fn python_strings(strings: &[&str]) -> String {
+    let strings = strings
+        .iter()
+        .map(|string| format!("{string:?}"))
+        .collect::<Vec<_>>();
+
+    format!("[{}]", strings.join(", "))
+}
+
+/// The file name to write the lldb script for `binary` to. lldb imports it as a Python module,
+/// so it has to be a valid identifier.
+pub fn lldb_script_file_name(binary: &str) -> String {
+    let mut stem: String = binary
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+
+    if stem.starts_with(|c: char| c.is_ascii_digit()) {
+        stem.insert(0, '_');
+    }
+
+    format!("{stem}_lldb.py")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bumpalo::Bump;
+    use roc_mono::layout::Layout;
+    use roc_target::Target;
+    use std::io::Write as _;
+    use std::process::{Command, Stdio};
+
+    const MEMORY_HELPERS: &str = r#"
+def read_memory(address, size):
+    for start, data in MEMORY.items():
+        if start <= address and address + size <= start + len(data):
+            return data[address - start : address - start + size]
+    raise Exception("nothing at 0x{:x}".format(address))
+
+def word(n):
+    return n.to_bytes(PTR_BYTES, "little", signed=n < 0)
+
+def small_str(s):
+    return s.encode() + bytes(3 * PTR_BYTES - 1 - len(s)) + bytes([0x80 | len(s)])
+
+"#;
+
+    /// Runs `roc_values.py` with the descriptions of `layouts` on the Python code in `test`, and
+    /// returns what it printed. `test` can use `read_memory` to read from the memory image in
+    /// `MEMORY`, a dict from addresses to bytes.
+    fn run_python<'a>(
+        interner: &STLayoutInterner<'a>,
+        layouts: &[InLayout<'a>],
+        test: &str,
+    ) -> String {
+        let mut script = python_layouts(interner, layouts);
+        script.push_str(ROC_VALUES);
+        script.push_str(MEMORY_HELPERS);
+        script.push_str(test);
+
+        run_python_script(&script)
+    }
+
+    fn run_python_script(script: &str) -> String {
+        let mut child = Command::new("python3")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap_or_else(|error| {
+                panic!("The pretty printer tests need python3, which I could not run: {error}")
+            });
+
+        child
+            .stdin
+            .take()
+            .unwrap()
+            .write_all(script.as_bytes())
+            .unwrap();
+        let output = child.wait_with_output().unwrap();
+
+        assert!(
+            output.status.success(),
+            "{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+
+        String::from_utf8(output.stdout).unwrap()
+    }
+
+    /// Compiles an app whose `main` is a value, and returns its layout
+    fn main_layout<'a>(arena: &'a Bump, src: &str) -> (STLayoutInterner<'a>, InLayout<'a>) {
+        use roc_load::{
+            EntryPoint, ExecutionMode, FunctionKind, LoadConfig, OutputConfig, Threading,
+        };
+        use roc_packaging::cache::RocCacheDir;
+        use std::path::PathBuf;
+
+        let load_config = LoadConfig {
+            target: Target::LinuxX64,
+            function_kind: FunctionKind::LambdaSet,
+            threading: Threading::Single,
+            render: roc_reporting::report::RenderTarget::Generic,
+            palette: roc_reporting::report::DEFAULT_PALETTE,
+            exec_mode: ExecutionMode::Executable,
+            output: OutputConfig::default(),
+        };
+        let loaded = roc_load::load_and_monomorphize_from_str(
+            arena,
+            PathBuf::from("Test.roc"),
+            arena.alloc_str(src),
+            PathBuf::from("fake/test/path"),
+            RocCacheDir::Disallowed,
+            load_config,
+        )
+        .unwrap_or_else(|error| panic!("{error:?}"));
+
+        let main = match loaded.entry_point {
+            EntryPoint::Executable {
+                exposed_to_host: [(_, proc_layout)],
+                ..
+            } => proc_layout.result,
+            other => panic!("expected an app that provides only `main`, not {other:?}"),
+        };
+
+        (loaded.layout_interner, main)
+    }
+
+    #[test]
+    fn strings_and_lists() {
+        let mut interner = STLayoutInterner::with_capacity(8, Target::LinuxX64);
+        let list =
+            interner.insert_direct_no_semantic(LayoutRepr::Builtin(Builtin::List(Layout::STR)));
+
+        let test = format!(
+            r#"
+MEMORY = {{
+    0x1000: b"a string that is too long to be small",
+    0x2000: small_str("small") + word(0x1000) + word(37) + word(37),
+}}
+print(format_layout(read_memory, {str}, small_str("")))
+print(format_layout(read_memory, {str}, small_str("tab\t\"quote\"")))
+print(format_layout(read_memory, {str}, word(0x1000) + word(37) + word(40)))
+# a seamless slice of the big string
+print(format_layout(read_memory, {str}, word(0x1002) + word(6 | 1 << 63) + word(0x800)))
+print(format_layout(read_memory, {list}, word(0x2000) + word(2) + word(2)))
+print(format_layout(read_memory, {list}, word(0) + word(0) + word(0)))
+"#,
+            str = Layout::STR.index(),
+            list = list.index(),
+        );
+
+        assert_eq!(
+            run_python(&interner, &[Layout::STR, list], &test),
+            indoc::indoc!(
+                r#"
+                ""
+                "tab\t\"quote\""
+                "a string that is too long to be small"
+                "string"
+                ["small", "a string that is too long to be small"]
+                []
+                "#
+            )
+        );
+    }
+
+    #[test]
+    fn numbers() {
+        let interner = STLayoutInterner::with_capacity(8, Target::LinuxX64);
+
+        let test = format!(
+            r#"
+MEMORY = {{}}
+print(format_layout(read_memory, {dec}, (1_500_000_000_000_000_000).to_bytes(16, "little")))
+print(format_layout(read_memory, {dec}, (-25 * 10**16).to_bytes(16, "little", signed=True)))
+print(format_layout(read_memory, {dec}, (42 * 10**18).to_bytes(16, "little")))
+print(format_layout(read_memory, {i8}, b"\xff"))
+print(format_layout(read_memory, {u64}, word(-1)))
+print(format_layout(read_memory, {f32}, struct.pack("<f", 0.1)))
+print(format_layout(read_memory, {f64}, struct.pack("<d", 3.0)))
+print(format_layout(read_memory, {bool}, b"\x01"))
+"#,
+            dec = Layout::DEC.index(),
+            i8 = Layout::I8.index(),
+            u64 = Layout::U64.index(),
+            f32 = Layout::F32.index(),
+            f64 = Layout::F64.index(),
+            bool = Layout::BOOL.index(),
+        );
+        let layouts = [
+            Layout::DEC,
+            Layout::I8,
+            Layout::U64,
+            Layout::F32,
+            Layout::F64,
+            Layout::BOOL,
+        ];
+
+        assert_eq!(
+            run_python(&interner, &layouts, &test),
+            "1.5\n-0.25\n42\n-1\n18446744073709551615\n0.1\n3.0\nBool.true\n"
+        );
+    }
+
+    #[test]
+    fn tag_unions() {
+        let arena = Bump::new();
+        let mut interner = STLayoutInterner::with_capacity(8, Target::LinuxX64);
+        // [A Str, B I64 U8]
+        let union_layout = UnionLayout::NonRecursive(&[&[Layout::STR], &[Layout::I64, Layout::U8]]);
+        let union = interner.insert_direct_no_semantic(LayoutRepr::Union(union_layout));
+        let boxed = interner.insert_direct_no_semantic(LayoutRepr::Union(
+            UnionLayout::NonNullableUnwrapped(&[Layout::I64]),
+        ));
+        // (the union, Box I64)
+        let pair =
+            interner.insert_direct_no_semantic(LayoutRepr::struct_(arena.alloc([union, boxed])));
+
+        assert_eq!(union_layout.tag_id_offset(&interner), Some(24));
+        assert_eq!(
+            type_name(&interner, union),
+            Some(format!("Union#{}", union.index()))
+        );
+        assert_eq!(
+            type_name(&interner, boxed),
+            Some(format!("Box#{}", boxed.index()))
+        );
+        assert_eq!(type_name(&interner, pair), None);
+
+        let test = format!(
+            r#"
+MEMORY = {{0x1000: bytes(8) + word(-7)}}
+print(format_layout(read_memory, {union}, small_str("a") + b"\x00" + bytes(7)))
+print(format_layout(read_memory, {union}, word(5) + b"\x02" + bytes(15) + b"\x01" + bytes(7)))
+print(format_layout(read_memory, {union}, bytes(24) + b"\x07" + bytes(7)))
+print(format_layout(read_memory, {pair}, word(5) + b"\x02" + bytes(15) + b"\x01" + bytes(7) + word(0x1008)))
+"#,
+            union = union.index(),
+            pair = pair.index(),
+        );
+
+        assert_eq!(
+            run_python(&interner, &[pair], &test),
+            indoc::indoc!(
+                r#"
+                #0 "a"
+                #1 5 2
+                <invalid tag id 7>
+                (#1 5 2, Box.box -7)
+                "#
+            )
+        );
+    }
+
+    #[test]
+    fn dicts_and_sets() {
+        let arena = Bump::new();
+        let (interner, dict) = main_layout(
+            &arena,
+            indoc::indoc!(
+                r#"
+                app "test" provides [main] to "./platform"
+
+                main : Dict I64 Str
+                main = Dict.fromList [(1, "one"), (2, "two")]
+                "#
+            ),
+        );
+
+        assert_eq!(
+            type_name(&interner, dict),
+            Some(format!("Dict#{}", dict.index()))
+        );
+        // `data` comes after `buckets`, and the key of each `(I64, Str)` entry comes first
+        let entries = dict_entries(&interner, dict).unwrap();
+        assert_eq!(
+            (
+                entries.entries.0,
+                entries.key.0,
+                entries.value.map(|value| value.0)
+            ),
+            (24, 0, Some(8))
+        );
+
+        let test = format!(
+            r#"
+MEMORY = {{0x1000: word(1) + small_str("one") + word(2) + small_str("two")}}
+print(format_layout(read_memory, {dict}, bytes(24) + word(0x1000) + word(2) + word(2) + bytes(16)))
+print(format_layout(read_memory, {dict}, bytes(64)))
+"#,
+            dict = dict.index(),
+        );
+
+        assert_eq!(
+            run_python(&interner, &[dict], &test),
+            "Dict.fromList [(1, \"one\"), (2, \"two\")]\nDict.fromList []\n"
+        );
+
+        let arena = Bump::new();
+        let (interner, set) = main_layout(
+            &arena,
+            indoc::indoc!(
+                r#"
+                app "test" provides [main] to "./platform"
+
+                main : Set Str
+                main = Set.fromList ["a", "b"]
+                "#
+            ),
+        );
+
+        assert_eq!(
+            type_name(&interner, set),
+            Some(format!("Set#{}", set.index()))
+        );
+
+        let test = format!(
+            r#"
+MEMORY = {{0x1000: small_str("a") + small_str("b")}}
+print(format_layout(read_memory, {set}, bytes(24) + word(0x1000) + word(2) + word(2) + bytes(16)))
+"#,
+            set = set.index(),
+        );
+
+        assert_eq!(
+            run_python(&interner, &[set], &test),
+            "Set.fromList [\"a\", \"b\"]\n"
+        );
+    }
+
+    #[test]
+    fn recursive_unions() {
+        let arena = Bump::new();
+        let (interner, cons_list) = main_layout(
+            &arena,
+            indoc::indoc!(
+                r#"
+                app "test" provides [main] to "./platform"
+
+                ConsList : [Nil, Cons I64 ConsList]
+
+                main : ConsList
+                main = Cons 1 (Cons 2 Nil)
+                "#
+            ),
+        );
+
+        assert!(matches!(
+            interner.get_repr(cons_list),
+            LayoutRepr::Union(UnionLayout::NullableUnwrapped { .. })
+        ));
+        // Recursive unions have no semantic representation, so their tags are shown by id:
+        // `Cons` is 0 and `Nil` is 1
+
+        let test = format!(
+            r#"
+MEMORY = {{
+    0x1000: word(1) + word(0x2000),
+    0x2000: word(2) + word(0),
+}}
+print(format_layout(read_memory, {cons_list}, word(0x1000)))
+print(format_layout(read_memory, {cons_list}, word(0)))
+"#,
+            cons_list = cons_list.index(),
+        );
+
+        assert_eq!(
+            run_python(&interner, &[cons_list], &test),
+            "#0 1 (#0 2 #1)\n#1\n"
+        );
+
+        let arena = Bump::new();
+        let (interner, expr) = main_layout(
+            &arena,
+            indoc::indoc!(
+                r#"
+                app "test" provides [main] to "./platform"
+
+                Expr : [Empty, Num I64, Add Expr Expr]
+
+                main : Expr
+                main = Add (Num 5) Empty
+                "#
+            ),
+        );
+
+        assert!(matches!(
+            interner.get_repr(expr),
+            LayoutRepr::Union(UnionLayout::NullableWrapped { .. })
+        ));
+
+        // `Add` is 0, `Empty` is 1 and `Num` is 2, and the tag id is in the low bits of the
+        // pointer
+        let test = format!(
+            r#"
+MEMORY = {{
+    0x1000: word(0x2000 | 2) + word(0),
+    0x2000: word(5) + word(0),
+}}
+print(format_layout(read_memory, {expr}, word(0x1000)))
+print(format_layout(read_memory, {expr}, word(0x2000 | 2)))
+print(format_layout(read_memory, {expr}, word(0)))
+"#,
+            expr = expr.index(),
+        );
+
+        assert_eq!(
+            run_python(&interner, &[expr], &test),
+            "#0 (#2 5) #1\n#2 5\n#1\n"
+        );
+    }
+
+    /// `[A Str, B I64 U8]`, as in `tag_unions`
+    fn str_or_pair<'a>(interner: &mut STLayoutInterner<'a>) -> InLayout<'a> {
+        let union_layout = UnionLayout::NonRecursive(&[&[Layout::STR], &[Layout::I64, Layout::U8]]);
+
+        interner.insert_direct_no_semantic(LayoutRepr::Union(union_layout))
+    }
+
+    #[test]
+    fn gdb_printers() {
+        let mut interner = STLayoutInterner::with_capacity(8, Target::LinuxX64);
+        let union = str_or_pair(&mut interner);
+
+        // Just enough of gdb's Python API for the printers
+        let mut script = String::from(indoc::indoc!(
+            r#"
+            import sys
+            import types
+
+            gdb = types.ModuleType("gdb")
+            gdb.error = type("error", (Exception,), {})
+            gdb.pretty_printers = []
+
+            class Inferior:
+                def read_memory(self, address, size):
+                    return read_memory(address, size)
+
+            class Objfile:
+                pretty_printers = []
+
+            OBJFILE = Objfile()
+            gdb.selected_inferior = Inferior
+            gdb.current_objfile = lambda: OBJFILE
+
+            class Type:
+                def __init__(self, name, size):
+                    self.name = name
+                    self.tag = None
+                    self.sizeof = size
+
+                def strip_typedefs(self):
+                    return self
+
+            class Value:
+                def __init__(self, type_name, size, address, data=None):
+                    self.type = Type(type_name, size)
+                    self.address = address
+                    self.bytes = data
+
+            sys.modules["gdb"] = gdb
+            "#
+        ));
+        script.push_str(MEMORY_HELPERS);
+        script.push_str(&gdb_script(&interner, &[union]));
+        write!(
+            script,
+            r#"
+MEMORY = {{
+    0x1000: small_str("in memory"),
+    0x2000: word(5) + b"\x02" + bytes(15) + b"\x01" + bytes(7),
+}}
+lookup = OBJFILE.pretty_printers[0]
+print(lookup(Value("Str", 24, 0x1000)).to_string())
+print(lookup(Value("roc_std::RocStr", 24, None, small_str("in registers"))).to_string())
+print(lookup(Value("Union#{union}", 32, 0x2000)).to_string())
+print(lookup(Value("Union#{unknown}", 32, 0x2000)))
+print(lookup(Value("u64", 8, 0x2000)))
+"#,
+            union = union.index(),
+            unknown = union.index() + 1000,
+        )
+        .unwrap();
+
+        assert_eq!(
+            run_python_script(&script),
+            indoc::indoc!(
+                r#"
+                "in memory"
+                "in registers"
+                #1 5 2
+                None
+                None
+                "#
+            )
+        );
+    }
+
+    #[test]
+    fn lldb_formatters() {
+        let mut interner = STLayoutInterner::with_capacity(8, Target::LinuxX64);
+        let union = str_or_pair(&mut interner);
+
+        // Just enough of lldb's Python API for the formatters
+        let mut script = String::from(indoc::indoc!(
+            r#"
+            import re
+            import shlex
+            import sys
+            import types
+
+            lldb = types.ModuleType("lldb")
+
+            class SBError:
+                def __init__(self):
+                    self.message = None
+
+                def Fail(self):
+                    return self.message is not None
+
+                def GetCString(self):
+                    return self.message
+
+            lldb.SBError = SBError
+
+            class Process:
+                def ReadMemory(self, address, size, error):
+                    try:
+                        return read_memory(address, size)
+                    except Exception as exception:
+                        error.message = str(exception)
+
+            class Data:
+                def __init__(self, data):
+                    self.data = data
+
+                def GetByteSize(self):
+                    return len(self.data)
+
+                def ReadRawData(self, error, offset, size):
+                    return self.data[offset : offset + size]
+
+            class SBType:
+                def __init__(self, name):
+                    self.name = name
+
+                def GetCanonicalType(self):
+                    return self
+
+                def GetName(self):
+                    return self.name
+
+            class ValObj:
+                def __init__(self, type_name, data):
+                    self.type_name = type_name
+                    self.data = data
+
+                def GetProcess(self):
+                    return Process()
+
+                def GetData(self):
+                    return Data(self.data)
+
+                def GetType(self):
+                    return SBType(self.type_name)
+
+            class Debugger:
+                def __init__(self):
+                    self.commands = []
+
+                def HandleCommand(self, command):
+                    self.commands.append(command)
+
+            sys.modules["lldb"] = lldb
+            "#
+        ));
+        script.push_str(MEMORY_HELPERS);
+        script.push_str(&lldb_script(&interner, &[union]));
+        write!(
+            script,
+            r#"
+debugger = Debugger()
+__lldb_init_module(debugger, {{}})
+print(debugger.commands[-1])
+
+# The type name patterns and the formatter each one is registered with
+formatters = []
+for command in debugger.commands[:-1]:
+    words = shlex.split(command)
+    formatters.append((words[6], words[8].rsplit(".", 1)[1]))
+
+for name in ["Str", "roc_std::RocStr", "Dec", "Union#{union}", "roc_std::RocList<u8>", "roc_std::RocSet<i64>", "u64"]:
+    print(name, [formatter for regex, formatter in formatters if re.search(regex, name)])
+
+MEMORY = {{0x1000: b"a string that is too long to be small"}}
+print(str_summary(ValObj("Str", small_str("hi")), {{}}))
+print(str_summary(ValObj("Str", word(0x1000) + word(37) + word(37)), {{}}))
+print(str_summary(ValObj("Str", word(0x9000) + word(37) + word(37)), {{}}))
+print(dec_summary(ValObj("Dec", (15 * 10**17).to_bytes(16, "little")), {{}}))
+print(layout_summary(ValObj("Union#{union}", word(5) + b"\x02" + bytes(15) + b"\x01" + bytes(7)), {{}}))
+"#,
+            union = union.index(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            run_python_script(&script),
+            format!(
+                indoc::indoc!(
+                    r#"
+                    type category enable roc
+                    Str ['str_summary']
+                    roc_std::RocStr ['str_summary']
+                    Dec ['dec_summary']
+                    Union#{} ['layout_summary']
+                    roc_std::RocList<u8> ['HostListProvider']
+                    roc_std::RocSet<i64> ['HostSetProvider']
+                    u64 []
+                    "hi"
+                    "a string that is too long to be small"
+                    <nothing at 0x9000>
+                    1.5
+                    #1 5 2
+                    "#
+                ),
+                union.index()
+            )
+        );
+    }
+
+    #[test]
+    fn lldb_script_names_are_identifiers() {
+        assert_eq!(lldb_script_file_name("my-app"), "my_app_lldb.py");
+        assert_eq!(lldb_script_file_name("2048.exe"), "_2048_exe_lldb.py");
+    }
+}
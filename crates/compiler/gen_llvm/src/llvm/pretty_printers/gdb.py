@@ -0,0 +1,166 @@
+# gdb pretty printers for Roc values.
+#
+# gdb loads this file automatically when it sits next to the binary as
+# `<binary>-gdb.py` and that directory is in gdb's auto-load safe-path.
+# Otherwise, load it with `source <binary>-gdb.py`.
+
+import re
+
+import gdb
+
+_ROC_TYPE = re.compile(r"^(?:List|Union|Box|Dict|Set)#(\d+)$")
+
+# Types from `roc_std` and the hosts' own definitions of them
+_HOST_STR = re.compile(r"(?:^|::|\.)RocStr$")
+_HOST_DEC = re.compile(r"(?:^|::|\.)RocDec$")
+_HOST_LIST = re.compile(r"(?:^|::|\.)RocList<.+>$")
+_HOST_BOX = re.compile(r"(?:^|::|\.)RocBox<.+>$")
+_HOST_DICT = re.compile(r"(?:^|::|\.)RocDict<.+>$")
+_HOST_SET = re.compile(r"(?:^|::|\.)RocSet<.+>$")
+
+
+def _read_memory(address, size):
+    return bytes(gdb.selected_inferior().read_memory(address, size))
+
+
+def _value_bytes(value):
+    if value.address is not None:
+        return _read_memory(int(value.address), value.type.strip_typedefs().sizeof)
+
+    # The value only lives in registers
+    return bytes(value.bytes)
+
+
+class _SummaryPrinter:
+    def __init__(self, value, summarize):
+        self.value = value
+        self.summarize = summarize
+
+    def to_string(self):
+        try:
+            return self.summarize(_value_bytes(self.value))
+        except gdb.error as error:
+            return "<error: {}>".format(error)
+
+
+class _HostListPrinter:
+    """`RocList<T>`, whose elements gdb prints as `T`s"""
+
+    def __init__(self, value):
+        self.value = value
+        self.element_type = value.type.strip_typedefs().template_argument(0)
+        self.address, self.length = list_elements_and_length(_value_bytes(value))
+
+    def to_string(self):
+        return "RocList of length {}".format(self.length)
+
+    def children(self):
+        pointer = gdb.Value(self.address).cast(self.element_type.pointer())
+
+        for index in range(min(self.length, MAX_ELEMENTS)):
+            yield "[{}]".format(index), (pointer + index).dereference()
+
+    def display_hint(self):
+        return "array"
+
+
+class _HostBoxPrinter:
+    def __init__(self, value):
+        self.value = value
+
+    def to_string(self):
+        inner_type = self.value.type.strip_typedefs().template_argument(0)
+        address = read_uint(_value_bytes(self.value), 0, PTR_BYTES)
+
+        return gdb.Value(address).cast(inner_type.pointer()).dereference()
+
+
+class _HostDictPrinter:
+    """`RocDict<K, V>` and `RocSet<T>`, which are lists of key-value pairs"""
+
+    def __init__(self, value, is_set):
+        type_ = value.type.strip_typedefs()
+        if is_set:
+            # RocSet<T>(RocDict<T, ()>)
+            value = value[type_.fields()[0]]
+            type_ = value.type.strip_typedefs()
+
+        self.is_set = is_set
+        self.key_type = type_.template_argument(0)
+        self.value_type = type_.template_argument(1)
+        self.key_offset, self.value_offset, self.item_size = dict_item_layout(
+            self.key_type.sizeof,
+            self.key_type.alignof,
+            self.value_type.sizeof,
+            self.value_type.alignof,
+        )
+        self.address, self.length = list_elements_and_length(_value_bytes(value))
+
+    def to_string(self):
+        name = "RocSet" if self.is_set else "RocDict"
+
+        return "{} of length {}".format(name, self.length)
+
+    def children(self):
+        for index in range(min(self.length, MAX_ELEMENTS)):
+            item = self.address + index * self.item_size
+            key = self.at(item + self.key_offset, self.key_type)
+
+            if self.is_set:
+                yield "[{}]".format(index), key
+            else:
+                yield "key{}".format(index), key
+                value = self.at(item + self.value_offset, self.value_type)
+                yield "value{}".format(index), value
+
+    def display_hint(self):
+        return "array" if self.is_set else "map"
+
+    @staticmethod
+    def at(address, type_):
+        return gdb.Value(address).cast(type_.pointer()).dereference()
+
+
+def _lookup(value):
+    type_ = value.type.strip_typedefs()
+    name = type_.tag or type_.name
+    if name is None:
+        return None
+
+    if name == "Str" or _HOST_STR.search(name):
+        return _SummaryPrinter(value, lambda data: format_str(_read_memory, data))
+
+    if name == "Dec" or _HOST_DEC.search(name):
+        return _SummaryPrinter(value, format_dec)
+
+    match = _ROC_TYPE.match(name)
+    if match and int(match.group(1)) in LAYOUTS:
+        layout_id = int(match.group(1))
+
+        return _SummaryPrinter(
+            value, lambda data: format_layout(_read_memory, layout_id, data)
+        )
+
+    if _HOST_LIST.search(name):
+        return _HostListPrinter(value)
+
+    if _HOST_BOX.search(name):
+        return _HostBoxPrinter(value)
+
+    if _HOST_DICT.search(name):
+        return _HostDictPrinter(value, is_set=False)
+
+    if _HOST_SET.search(name):
+        return _HostDictPrinter(value, is_set=True)
+
+    return None
+
+
+def _register(objfile):
+    if objfile is None:
+        gdb.pretty_printers.append(_lookup)
+    else:
+        objfile.pretty_printers.append(_lookup)
+
+
+_register(gdb.current_objfile())
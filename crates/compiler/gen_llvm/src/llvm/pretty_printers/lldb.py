@@ -0,0 +1,234 @@
+# lldb formatters for Roc values.
+#
+# Load them with `command script import <binary>_lldb.py`, e.g. in `.lldbinit`.
+
+import lldb
+
+# Types from `roc_std` and the hosts' own definitions of them
+_HOST_PREFIX = r"(^|::|\.)"
+
+
+def _read_memory_of(valobj):
+    process = valobj.GetProcess()
+
+    def read_memory(address, size):
+        error = lldb.SBError()
+        data = process.ReadMemory(address, size, error)
+        if error.Fail():
+            raise RocValueError(error.GetCString())
+
+        return data
+
+    return read_memory
+
+
+def _value_bytes(valobj):
+    data = valobj.GetData()
+    error = lldb.SBError()
+    raw = data.ReadRawData(error, 0, data.GetByteSize())
+    if error.Fail():
+        raise RocValueError(error.GetCString())
+
+    return raw
+
+
+def _alignment(type_):
+    if hasattr(type_, "GetByteAlign"):
+        return type_.GetByteAlign()
+
+    # Older versions of lldb can't tell us; assume natural alignment.
+    size = max(type_.GetByteSize(), 1)
+    alignment = 1
+    while alignment < min(size, 16) and size % (alignment * 2) == 0:
+        alignment *= 2
+
+    return alignment
+
+
+def _summarize(valobj, summarize):
+    try:
+        return summarize(_value_bytes(valobj))
+    except RocValueError as error:
+        return "<{}>".format(error)
+
+
+def str_summary(valobj, internal_dict):
+    read_memory = _read_memory_of(valobj)
+
+    return _summarize(valobj, lambda data: format_str(read_memory, data))
+
+
+def dec_summary(valobj, internal_dict):
+    return _summarize(valobj, format_dec)
+
+
+def layout_summary(valobj, internal_dict):
+    name = valobj.GetType().GetCanonicalType().GetName()
+    layout_id = int(name.rsplit("#", 1)[1])
+    if layout_id not in LAYOUTS:
+        return None
+
+    read_memory = _read_memory_of(valobj)
+
+    return _summarize(
+        valobj, lambda data: format_layout(read_memory, layout_id, data)
+    )
+
+
+class HostListProvider:
+    """`RocList<T>`, whose elements lldb shows as `T`s"""
+
+    def __init__(self, valobj, internal_dict):
+        self.valobj = valobj
+        self.update()
+
+    def update(self):
+        type_ = self.valobj.GetType().GetCanonicalType()
+        self.element_type = type_.GetTemplateArgumentType(0)
+        self.address, self.length = list_elements_and_length(
+            _value_bytes(self.valobj)
+        )
+
+        return False
+
+    def num_children(self):
+        return min(self.length, MAX_ELEMENTS)
+
+    def get_child_index(self, name):
+        try:
+            return int(name.strip("[]"))
+        except ValueError:
+            return -1
+
+    def get_child_at_index(self, index):
+        offset = index * self.element_type.GetByteSize()
+
+        return self.valobj.CreateValueFromAddress(
+            "[{}]".format(index), self.address + offset, self.element_type
+        )
+
+
+class HostBoxProvider:
+    def __init__(self, valobj, internal_dict):
+        self.valobj = valobj
+        self.update()
+
+    def update(self):
+        type_ = self.valobj.GetType().GetCanonicalType()
+        self.inner_type = type_.GetTemplateArgumentType(0)
+        self.address = read_uint(_value_bytes(self.valobj), 0, PTR_BYTES)
+
+        return False
+
+    def num_children(self):
+        return 1
+
+    def get_child_index(self, name):
+        return 0 if name == "contents" else -1
+
+    def get_child_at_index(self, index):
+        return self.valobj.CreateValueFromAddress(
+            "contents", self.address, self.inner_type
+        )
+
+
+class HostDictProvider:
+    """`RocDict<K, V>`, which is a list of key-value pairs"""
+
+    def __init__(self, valobj, internal_dict):
+        self.valobj = valobj
+        self.update()
+
+    def dict_value(self):
+        return self.valobj
+
+    def update(self):
+        dict_value = self.dict_value()
+        type_ = dict_value.GetType().GetCanonicalType()
+        self.key_type = type_.GetTemplateArgumentType(0)
+        self.value_type = type_.GetTemplateArgumentType(1)
+        self.key_offset, self.value_offset, self.item_size = dict_item_layout(
+            self.key_type.GetByteSize(),
+            _alignment(self.key_type),
+            self.value_type.GetByteSize(),
+            _alignment(self.value_type),
+        )
+        self.address, self.length = list_elements_and_length(
+            _value_bytes(dict_value)
+        )
+
+        return False
+
+    def num_children(self):
+        return 2 * min(self.length, MAX_ELEMENTS)
+
+    def get_child_index(self, name):
+        for prefix, parity in (("key", 0), ("value", 1)):
+            if name.startswith(prefix) and name[len(prefix) :].isdigit():
+                return 2 * int(name[len(prefix) :]) + parity
+
+        return -1
+
+    def get_child_at_index(self, index):
+        entry, parity = divmod(index, 2)
+        item = self.address + entry * self.item_size
+
+        if parity == 0:
+            return self.valobj.CreateValueFromAddress(
+                "key{}".format(entry), item + self.key_offset, self.key_type
+            )
+
+        return self.valobj.CreateValueFromAddress(
+            "value{}".format(entry), item + self.value_offset, self.value_type
+        )
+
+
+class HostSetProvider(HostDictProvider):
+    """`RocSet<T>`, which wraps a `RocDict<T, ()>`"""
+
+    def dict_value(self):
+        return self.valobj.GetChildAtIndex(0)
+
+    def num_children(self):
+        return min(self.length, MAX_ELEMENTS)
+
+    def get_child_index(self, name):
+        try:
+            return int(name.strip("[]"))
+        except ValueError:
+            return -1
+
+    def get_child_at_index(self, index):
+        item = self.address + index * self.item_size
+
+        return self.valobj.CreateValueFromAddress(
+            "[{}]".format(index), item + self.key_offset, self.key_type
+        )
+
+
+def __lldb_init_module(debugger, internal_dict):
+    summaries = [
+        ("^Str$", "str_summary"),
+        (_HOST_PREFIX + "RocStr$", "str_summary"),
+        ("^Dec$", "dec_summary"),
+        (_HOST_PREFIX + "RocDec$", "dec_summary"),
+        ("^(List|Union|Box|Dict|Set)#[0-9]+$", "layout_summary"),
+    ]
+    providers = [
+        (_HOST_PREFIX + "RocList<.+>$", "HostListProvider"),
+        (_HOST_PREFIX + "RocBox<.+>$", "HostBoxProvider"),
+        (_HOST_PREFIX + "RocDict<.+>$", "HostDictProvider"),
+        (_HOST_PREFIX + "RocSet<.+>$", "HostSetProvider"),
+    ]
+
+    for regex, function in summaries:
+        debugger.HandleCommand(
+            'type summary add -w roc -x "{}" -F {}.{}'.format(regex, __name__, function)
+        )
+
+    for regex, provider in providers:
+        debugger.HandleCommand(
+            'type synthetic add -w roc -x "{}" -l {}.{}'.format(regex, __name__, provider)
+        )
+
+    debugger.HandleCommand("type category enable roc")
@@ -0,0 +1,381 @@
+# Decodes Roc values from raw memory, for the gdb and lldb pretty printers that
+# `roc build --debug` writes next to the binary.
+#
+# Nothing in here depends on a debugger. A value is decoded from its own bytes,
+# and any memory it points to is read with `read_memory(address, size)`, which
+# returns `bytes` or raises an exception.
+#
+# Generated code defines `PTR_BYTES`, the pointer width of the target, and
+# `LAYOUTS`, which describes the layout of each Roc type by its index in the
+# layout interner. The debug info names those types `<kind>#<index>`.
+
+import math
+import struct
+
+MAX_ELEMENTS = 100
+MAX_DEPTH = 16
+
+# Stop formatting once the output gets this long, e.g. for a huge value or for
+# corrupt memory that looks like one.
+MAX_OUTPUT_LENGTH = 4096
+
+
+class RocValueError(Exception):
+    pass
+
+
+def read_uint(data, offset, size):
+    return int.from_bytes(bytes(data[offset : offset + size]), "little")
+
+
+def read_int(data, offset, size):
+    return int.from_bytes(bytes(data[offset : offset + size]), "little", signed=True)
+
+
+def _seamless_slice_bit():
+    return 1 << (PTR_BYTES * 8 - 1)
+
+
+def _read(read_memory, address, size):
+    if size == 0:
+        return b""
+
+    if address == 0:
+        raise RocValueError("null pointer")
+
+    return bytes(read_memory(address, size))
+
+
+# Str and List
+
+
+def str_bytes(read_memory, data):
+    """The UTF-8 bytes of a `Str`.
+
+    Strings shorter than a `Str` itself are stored inline. Then the highest bit
+    of the last byte is set, and the rest of that byte is the length.
+    """
+    size = 3 * PTR_BYTES
+    last = data[size - 1]
+
+    if last & 0x80:
+        return bytes(data[: last & 0x7F])
+
+    elements = read_uint(data, 0, PTR_BYTES)
+    length = read_uint(data, PTR_BYTES, PTR_BYTES) & ~_seamless_slice_bit()
+
+    return _read(read_memory, elements, length)
+
+
+def list_elements_and_length(data):
+    """The element pointer and length of a `List`.
+
+    Seamless slices set the highest bit of their length or capacity; the length
+    is the same either way.
+    """
+    elements = read_uint(data, 0, PTR_BYTES)
+    length = read_uint(data, PTR_BYTES, PTR_BYTES) & ~_seamless_slice_bit()
+
+    return elements, length
+
+
+def dict_item_layout(key_size, key_alignment, value_size, value_alignment):
+    """The key offset, value offset and size of a `roc_std::RocDict` item.
+
+    Items store whichever of the key and value has the larger alignment first.
+    """
+
+    def round_up(offset, alignment):
+        return (offset + alignment - 1) // alignment * alignment
+
+    alignment = max(key_alignment, value_alignment, 1)
+
+    if key_alignment >= value_alignment:
+        key_offset = 0
+        value_offset = round_up(key_size, max(value_alignment, 1))
+        end = value_offset + value_size
+    else:
+        value_offset = 0
+        key_offset = round_up(value_size, max(key_alignment, 1))
+        end = key_offset + key_size
+
+    return key_offset, value_offset, round_up(end, alignment)
+
+
+def format_str(read_memory, data):
+    string = str_bytes(read_memory, data).decode("utf-8", errors="replace")
+    escaped = []
+
+    for char in string:
+        if char == '"':
+            escaped.append('\\"')
+        elif char == "\\":
+            escaped.append("\\\\")
+        elif char == "\n":
+            escaped.append("\\n")
+        elif char == "\r":
+            escaped.append("\\r")
+        elif char == "\t":
+            escaped.append("\\t")
+        elif ord(char) < 0x20 or ord(char) == 0x7F:
+            escaped.append("\\u({:x})".format(ord(char)))
+        else:
+            escaped.append(char)
+
+    return '"' + "".join(escaped) + '"'
+
+
+# Numbers
+
+
+def format_dec(data):
+    """A `Dec` is an I128 holding the value times 10^18."""
+    value = read_int(data, 0, 16)
+    sign = "-" if value < 0 else ""
+    whole, fraction = divmod(abs(value), 10**18)
+    fraction = str(fraction).rjust(18, "0").rstrip("0")
+
+    if fraction:
+        return "{}{}.{}".format(sign, whole, fraction)
+
+    return "{}{}".format(sign, whole)
+
+
+def format_float(data, size):
+    code = "<f" if size == 4 else "<d"
+    raw = bytes(data[:size])
+    (value,) = struct.unpack(code, raw)
+
+    if math.isnan(value) or math.isinf(value):
+        return str(value)
+
+    # The shortest representation that reads back as the same float
+    for precision in range(1, 18):
+        text = "{:.{}g}".format(value, precision)
+        if struct.pack(code, float(text)) == raw:
+            break
+
+    if "e" not in text and "." not in text:
+        text += ".0"
+
+    return text
+
+
+# Layouts
+
+
+def format_layout(read_memory, layout_id, data):
+    """Format the value with layout `layout_id` whose bytes are `data`, the way
+    `Inspect.toStr` would.
+    """
+    return _Formatter(read_memory).format(layout_id, data, 0)
+
+
+class _Formatter:
+    def __init__(self, read_memory):
+        self.read_memory = read_memory
+        self.length = 0
+
+    def format(self, layout_id, data, depth):
+        if depth > MAX_DEPTH or self.length > MAX_OUTPUT_LENGTH:
+            return "…"
+
+        layout = LAYOUTS.get(layout_id)
+        if layout is None:
+            return "<unknown layout {}>".format(layout_id)
+
+        try:
+            text = self.format_help(layout, data, depth)
+        except RocValueError as error:
+            text = "<{}>".format(error)
+        except Exception as error:  # e.g. unreadable memory
+            text = "<error: {}>".format(error)
+
+        self.length += len(text)
+
+        return text
+
+    def format_help(self, layout, data, depth):
+        kind = layout["kind"]
+
+        if kind == "int":
+            read = read_int if layout["signed"] else read_uint
+            return str(read(data, 0, layout["size"]))
+        elif kind == "float":
+            return format_float(data, layout["size"])
+        elif kind == "bool":
+            return "Bool.true" if data[0] else "Bool.false"
+        elif kind == "dec":
+            return format_dec(data)
+        elif kind == "str":
+            return format_str(self.read_memory, data)
+        elif kind == "list":
+            return self.format_list(layout, data, depth)
+        elif kind == "record":
+            return self.format_record(layout, data, depth)
+        elif kind == "tuple":
+            return self.format_tuple(layout, data, depth)
+        elif kind == "box":
+            address = read_uint(data, 0, PTR_BYTES)
+            inner = self.pointee(layout["inner"], address, depth)
+            return "Box.box " + self.argument(layout["inner"], inner)
+        elif kind == "enum":
+            tag_id = read_uint(data, 0, layout["size"])
+            return self.tag_name(layout, tag_id)
+        elif kind == "union":
+            return self.format_union(layout, data, depth)
+        elif kind == "dict":
+            return self.format_dict(layout, data, depth)
+        elif kind == "pointer":
+            return "0x{:x}".format(read_uint(data, 0, PTR_BYTES))
+        elif kind == "function":
+            return "<function>"
+        else:
+            return "<{}>".format(kind)
+
+    def size(self, layout_id):
+        return LAYOUTS[layout_id]["size"]
+
+    def pointee(self, layout_id, address, depth):
+        data = _read(self.read_memory, address, self.size(layout_id))
+        return self.format(layout_id, data, depth + 1)
+
+    def field(self, layout_id, data, offset, depth):
+        size = self.size(layout_id)
+        return self.format(layout_id, data[offset : offset + size], depth + 1)
+
+    def argument(self, layout_id, text):
+        """Wrap tag applications in parentheses, so they read as one argument"""
+        kind = LAYOUTS[layout_id]["kind"]
+
+        if kind in ("union", "box") and " " in text and text[0] not in "[{(\"<":
+            return "(" + text + ")"
+
+        return text
+
+    def elements(self, layout, data, depth):
+        address, length = list_elements_and_length(data)
+        element = layout["element"]
+        element_size = self.size(element)
+
+        shown = min(length, MAX_ELEMENTS)
+        memory = _read(self.read_memory, address, shown * element_size)
+
+        for index in range(shown):
+            offset = index * element_size
+            yield memory[offset : offset + element_size]
+
+        if length > shown:
+            yield None
+
+    def format_list(self, layout, data, depth):
+        items = []
+
+        for element in self.elements(layout, data, depth):
+            if element is None:
+                items.append("…")
+            else:
+                items.append(self.format(layout["element"], element, depth + 1))
+
+        return "[" + ", ".join(items) + "]"
+
+    def format_record(self, layout, data, depth):
+        fields = [
+            "{}: {}".format(name, self.field(field, data, offset, depth))
+            for name, offset, field in layout["fields"]
+        ]
+
+        if not fields:
+            return "{}"
+
+        return "{ " + ", ".join(fields) + " }"
+
+    def format_tuple(self, layout, data, depth):
+        elements = sorted(layout["fields"])
+
+        return (
+            "("
+            + ", ".join(
+                self.field(field, data, offset, depth) for _, offset, field in elements
+            )
+            + ")"
+        )
+
+    def tag_name(self, layout, tag_id):
+        names = layout.get("names")
+
+        if names is not None and tag_id < len(names):
+            return names[tag_id]
+
+        return "#{}".format(tag_id)
+
+    def format_union(self, layout, data, depth):
+        tags = layout["tags"]
+        tag_id_placement = layout["tag_id"]
+
+        if layout["recursive"]:
+            pointer = read_uint(data, 0, PTR_BYTES)
+            null_tag = layout["null_tag"]
+
+            if pointer == 0 and null_tag is not None:
+                return self.tag_name(layout, null_tag)
+
+            if tag_id_placement is None:
+                # the single non-null tag
+                tag_id = next(i for i, fields in enumerate(tags) if fields is not None)
+            elif tag_id_placement["in"] == "pointer":
+                tag_id = pointer & tag_id_placement["mask"]
+                pointer &= ~tag_id_placement["mask"]
+            else:
+                tag_id = None
+
+            payload = _read(self.read_memory, pointer, layout["data_size"])
+
+            if tag_id is None:
+                tag_id = read_uint(
+                    payload, tag_id_placement["offset"], tag_id_placement["size"]
+                )
+        else:
+            payload = data
+
+            if tag_id_placement is None:
+                tag_id = 0
+            else:
+                tag_id = read_uint(
+                    data, tag_id_placement["offset"], tag_id_placement["size"]
+                )
+
+        if tag_id >= len(tags) or tags[tag_id] is None:
+            raise RocValueError("invalid tag id {}".format(tag_id))
+
+        words = [self.tag_name(layout, tag_id)]
+
+        for offset, field in tags[tag_id]:
+            words.append(self.argument(field, self.field(field, payload, offset, depth)))
+
+        return " ".join(words)
+
+    def format_dict(self, layout, data, depth):
+        entries_offset, entries = layout["entries"]
+        entries_layout = LAYOUTS[entries]
+        entries_data = data[entries_offset : entries_offset + self.size(entries)]
+        key_offset, key = layout["key"]
+        items = []
+
+        for entry in self.elements(entries_layout, entries_data, depth):
+            if entry is None:
+                items.append("…")
+                continue
+
+            key_text = self.field(key, entry, key_offset, depth)
+
+            if layout["value"] is None:
+                items.append(key_text)
+            else:
+                value_offset, value = layout["value"]
+                value_text = self.field(value, entry, value_offset, depth)
+                items.append("({}, {})".format(key_text, value_text))
+
+        name = "Dict" if layout["value"] is not None else "Set"
+
+        return "{}.fromList [{}]".format(name, ", ".join(items))
@@ -9,6 +9,7 @@ use crate::llvm::build_list::{
 };
 use crate::llvm::build_str::str_allocation_ptr;
 use crate::llvm::convert::{basic_type_from_layout, zig_str_type, RocUnion};
+use crate::llvm::instrument;
 use crate::llvm::struct_::RocStruct;
 use bumpalo::collections::Vec;
 use inkwell::basic_block::BasicBlock;
@@ -212,6 +213,8 @@ fn incref_pointer<'ctx>(
     pointer: PointerValue<'ctx>,
     amount: IntValue<'ctx>,
 ) {
+    instrument::on_inc(env, pointer, amount);
+
     call_void_bitcode_fn(
         env,
         &[
@@ -251,6 +254,8 @@ fn free_pointer<'ctx>(
 }
 
 fn decref_pointer<'ctx>(env: &Env<'_, 'ctx, '_>, pointer: PointerValue<'ctx>, alignment: u32) {
+    instrument::on_dec(env, pointer);
+
     let alignment = env.context.i32_type().const_int(alignment as _, false);
     call_void_bitcode_fn(
         env,
@@ -274,6 +279,8 @@ pub fn decref_pointer_check_null<'ctx>(
     pointer: PointerValue<'ctx>,
     alignment: u32,
 ) {
+    instrument::on_dec(env, pointer);
+
     let alignment = env.context.i32_type().const_int(alignment as _, false);
     call_void_bitcode_fn(
         env,
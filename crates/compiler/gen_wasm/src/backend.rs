@@ -5,7 +5,7 @@ use roc_builtins::bitcode::{self, FloatWidth, IntWidth};
 use roc_collections::all::MutMap;
 use roc_error_macros::{internal_error, todo_lambda_erasure};
 use roc_module::low_level::{LowLevel, LowLevelWrapperType};
-use roc_module::symbol::{Interns, Symbol};
+use roc_module::symbol::{Interns, ModuleId, Symbol};
 use roc_mono::code_gen_help::{CodeGenHelp, HelperOp, REFCOUNT_MAX};
 use roc_mono::ir::{
     BranchInfo, CallType, CrashTag, Expr, JoinPointId, ListLiteralElement, Literal, ModifyRc,
@@ -15,6 +15,7 @@ use roc_mono::layout::{
     Builtin, InLayout, Layout, LayoutIds, LayoutInterner, LayoutRepr, STLayoutInterner,
     TagIdIntType, UnionLayout,
 };
+use roc_region::all::{LineColumn, LineInfo, Region};
 use roc_std::RocDec;
 
 use roc_wasm_module::linking::{DataSymbol, WasmObjectSymbol};
@@ -22,6 +23,7 @@ use roc_wasm_module::sections::{
     ConstExpr, DataMode, DataSegment, Export, Global, GlobalType, Import, ImportDesc, Limits,
     MemorySection, NameSection,
 };
+use roc_wasm_module::source_map::SourceLocation;
 use roc_wasm_module::{
     round_up_to_alignment, Align, ExportType, LocalId, Signature, SymInfo, ValueType, WasmModule,
 };
@@ -65,6 +67,8 @@ pub struct WasmBackend<'a, 'r> {
     host_lookup: Vec<'a, (&'a str, u32)>,
     helper_proc_gen: CodeGenHelp<'a>,
     can_relocate_heap: bool,
+    /// Source map file index and line info for each module, created when first needed
+    source_files: MutMap<ModuleId, (u32, LineInfo)>,
 
     // Function-level data
     pub code_builder: CodeBuilder<'a>,
@@ -73,6 +77,8 @@ pub struct WasmBackend<'a, 'r> {
     /// how many blocks deep are we (used for jumps)
     block_depth: u32,
     joinpoint_label_map: MutMap<JoinPointId, (u32, Vec<'a, StoredValue>)>,
+    /// The module that the current procedure's source regions refer to
+    proc_module: ModuleId,
 }
 
 impl<'a, 'r> WasmBackend<'a, 'r> {
@@ -135,10 +141,12 @@ impl<'a, 'r> WasmBackend<'a, 'r> {
             host_lookup,
             helper_proc_gen,
             can_relocate_heap: has_heap_base && has_heap_end,
+            source_files: MutMap::default(),
 
             // Function-level data
             block_depth: 0,
             joinpoint_label_map: MutMap::default(),
+            proc_module: env.module_id,
             code_builder: CodeBuilder::new(env.arena),
             storage: Storage::new(env.arena),
         }
@@ -371,6 +379,50 @@ impl<'a, 'r> WasmBackend<'a, 'r> {
     #[cfg(not(debug_assertions))]
     pub fn register_symbol_debug_names(&self) {}
 
+    /// Point each Roc procedure at the definition it came from, for the source map.
+    /// Procedures without a top-level definition (like lifted closures) are left out.
+    pub fn register_source_locations(&mut self) {
+        let env = self.env;
+        let debug_sources = match &env.debug_sources {
+            Some(debug_sources) => debug_sources,
+            None => return,
+        };
+
+        for i in 0..self.proc_lookup.len() {
+            let ProcLookupData { name, source, .. } = self.proc_lookup[i];
+            if !matches!(source, ProcSource::Roc) {
+                continue;
+            }
+            let region = match debug_sources.definition_regions.get(&name) {
+                Some(region) => *region,
+                None => continue,
+            };
+            if let Some(location) = self.source_location(name.module_id(), region) {
+                self.module
+                    .source_map
+                    .add_location(self.fn_index_offset + i as u32, 0, location);
+            }
+        }
+    }
+
+    /// Convert a region of a module's source to a source map location
+    fn source_location(&mut self, module_id: ModuleId, region: Region) -> Option<SourceLocation> {
+        let debug_sources = self.env.debug_sources.as_ref()?;
+        let (path, content) = *debug_sources.files.get(&module_id)?;
+        let source_map = &mut self.module.source_map;
+        let (file_index, line_info) = self.source_files.entry(module_id).or_insert_with(|| {
+            let file_index = source_map.file_index(path, content);
+            (file_index, LineInfo::new(content))
+        });
+
+        let LineColumn { line, column } = line_info.convert_pos(region.start());
+        Some(SourceLocation {
+            file_index: *file_index,
+            line,
+            column,
+        })
+    }
+
     pub fn get_fn_ptr(&mut self, fn_index: u32) -> i32 {
         self.module.element.get_or_insert_fn(fn_index)
     }
@@ -408,6 +460,7 @@ impl<'a, 'r> WasmBackend<'a, 'r> {
         }
 
         self.append_proc_debug_name(proc.name.name());
+        self.proc_module = proc.name.name().module_id();
 
         self.start_proc(proc);
 
@@ -726,6 +779,13 @@ impl<'a, 'r> WasmBackend<'a, 'r> {
                 _ => self.stmt_refcounting(modify, following),
             },
 
+            Stmt::SourceRegion { region, remainder } => {
+                if let Some(location) = self.source_location(self.proc_module, *region) {
+                    self.code_builder.source_location(location);
+                }
+                self.stmt(remainder)
+            }
+
             Stmt::Dbg { .. } => todo!("dbg is not implemented in the wasm backend"),
             Stmt::Expect { .. } => todo!("expect is not implemented in the wasm backend"),
             Stmt::ExpectFx { .. } => todo!("expect-fx is not implemented in the wasm backend"),
@@ -880,6 +940,7 @@ impl<'a, 'r> WasmBackend<'a, 'r> {
                         self.code_builder.f64_const(f64::from_bits(*value));
                         self.code_builder.f64_eq();
                     }
+                    ValueType::V128 => internal_error!("Cannot switch on a v128 value"),
                 }
             }
 
@@ -5,8 +5,9 @@ use core::panic;
 use roc_wasm_module::linking::IndexRelocType;
 
 use roc_error_macros::internal_error;
-use roc_wasm_module::opcodes::{OpCode, OpCode::*};
+use roc_wasm_module::opcodes::{MemoryInstruction, OpCode, OpCode::*, SimdInstruction};
 use roc_wasm_module::serialize::SerialBuffer;
+use roc_wasm_module::source_map::SourceLocation;
 use roc_wasm_module::{
     round_up_to_alignment, Align, LocalId, RelocationEntry, ValueType, WasmModule,
     FRAME_ALIGNMENT_BYTES, STACK_POINTER_GLOBAL_ID,
@@ -76,6 +77,9 @@ pub struct CodeBuilder<'a> {
 
     /// Keep track of which local variables have been set
     set_locals: BitVec<u32>,
+
+    /// Code positions where the code for a Roc source location starts, for the source map
+    source_locations: Vec<'a, (usize, SourceLocation)>,
 }
 
 #[allow(clippy::new_without_default)]
@@ -90,6 +94,7 @@ impl<'a> CodeBuilder<'a> {
             inner_length: Vec::with_capacity_in(5, arena),
             import_relocations: Vec::with_capacity_in(0, arena),
             set_locals: BitVec::with_capacity(64),
+            source_locations: Vec::with_capacity_in(0, arena),
         }
     }
 
@@ -101,6 +106,7 @@ impl<'a> CodeBuilder<'a> {
         self.inner_length.clear();
         self.import_relocations.clear();
         self.set_locals.clear();
+        self.source_locations.clear();
     }
 
     /**********************************************************
@@ -213,6 +219,9 @@ impl<'a> CodeBuilder<'a> {
     /// Serialize all byte vectors in the right order
     /// Insert relocations for imported functions
     pub fn insert_into_module(&self, module: &mut WasmModule<'a>) {
+        let fn_index = module.import.function_count() as u32
+            + module.code.dead_import_dummy_count
+            + module.code.function_count;
         let fn_offset = module.code.bytes.len();
         module.code.function_count += 1;
         module.code.function_offsets.push(fn_offset as u32);
@@ -257,6 +266,25 @@ impl<'a> CodeBuilder<'a> {
                 symbol_index,
             });
         }
+
+        // Source locations are relative to the start of the function, since it may move during DCE
+        for (code_pos, location) in self.source_locations.iter() {
+            let insertion_bytes: usize = self
+                .insertions
+                .iter()
+                .take_while(|insertion| insertion.at <= *code_pos)
+                .map(|insertion| insertion.end - insertion.start)
+                .sum();
+            let offset = code_offset - fn_offset + code_pos + insertion_bytes;
+            module
+                .source_map
+                .add_location(fn_index, offset as u32, *location);
+        }
+    }
+
+    /// Map the code generated from here on to a location in the Roc source
+    pub fn source_location(&mut self, location: SourceLocation) {
+        self.source_locations.push((self.code.len(), location));
     }
 
     /**********************************************************
@@ -301,6 +329,28 @@ impl<'a> CodeBuilder<'a> {
         log_instruction!("{:10} {:?} {}", format!("{opcode:?}"), align, offset);
     }
 
+    /// Bulk memory instruction (0xFC prefix)
+    fn inst_bulk_mem(&mut self, instruction: MemoryInstruction) {
+        self.inst_base(MEMORY);
+        self.code.push(instruction as u8);
+        log_instruction!("{instruction:?}");
+    }
+
+    /// SIMD instruction (0xFD prefix) without any immediates
+    fn inst_simd(&mut self, instruction: SimdInstruction) {
+        self.inst_base(SIMD);
+        self.code.encode_u32(instruction as u32);
+        log_instruction!("{instruction:?}");
+    }
+
+    fn inst_simd_mem(&mut self, instruction: SimdInstruction, align: Align, offset: u32) {
+        self.inst_base(SIMD);
+        self.code.encode_u32(instruction as u32);
+        self.code.push(align as u8);
+        self.code.encode_u32(offset);
+        log_instruction!("{:10} {:?} {}", format!("{instruction:?}"), align, offset);
+    }
+
     /**********************************************************
 
         INSTRUCTION METHODS
@@ -424,6 +474,15 @@ impl<'a> CodeBuilder<'a> {
         self.inst(GROWMEMORY);
         self.code.push(0);
     }
+    pub fn memory_copy(&mut self) {
+        self.inst_bulk_mem(MemoryInstruction::MemoryCopy);
+        self.code.push(0); // destination memory index
+        self.code.push(0); // source memory index
+    }
+    pub fn memory_fill(&mut self) {
+        self.inst_bulk_mem(MemoryInstruction::MemoryFill);
+        self.code.push(0);
+    }
 
     fn log_const<T>(&self, opcode: OpCode, x: T)
     where
@@ -577,4 +636,31 @@ impl<'a> CodeBuilder<'a> {
     instruction_no_args!(i64_reinterpret_f64, I64REINTERPRETF64);
     instruction_no_args!(f32_reinterpret_i32, F32REINTERPRETI32);
     instruction_no_args!(f64_reinterpret_i64, F64REINTERPRETI64);
+
+    /**********************************************************
+
+        SIMD INSTRUCTIONS
+
+        Only the few we use. See the `SimdInstruction` enum for the rest.
+
+    ***********************************************************/
+
+    pub fn v128_load(&mut self, align: Align, offset: u32) {
+        self.inst_simd_mem(SimdInstruction::V128Load, align, offset);
+    }
+    pub fn v128_store(&mut self, align: Align, offset: u32) {
+        self.inst_simd_mem(SimdInstruction::V128Store, align, offset);
+    }
+    pub fn v128_const(&mut self, bytes: [u8; 16]) {
+        self.inst_base(SIMD);
+        self.code.encode_u32(SimdInstruction::V128Const as u32);
+        self.code.extend_from_slice(&bytes);
+        log_instruction!("{:10}\t{:?}", "V128Const", bytes);
+    }
+    pub fn i8x16_eq(&mut self) {
+        self.inst_simd(SimdInstruction::I8x16Eq);
+    }
+    pub fn i8x16_all_true(&mut self) {
+        self.inst_simd(SimdInstruction::I8x16AllTrue);
+    }
 }
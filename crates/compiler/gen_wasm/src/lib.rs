@@ -18,6 +18,7 @@ use roc_module::symbol::{Interns, ModuleId, Symbol};
 use roc_mono::code_gen_help::CodeGenHelp;
 use roc_mono::ir::{Proc, ProcLayout};
 use roc_mono::layout::{LayoutIds, STLayoutInterner};
+use roc_region::all::Region;
 use roc_target::Target;
 use roc_wasm_module::parse::ParseError;
 use roc_wasm_module::{Align, LocalId, ValueType, WasmModule};
@@ -46,6 +47,15 @@ pub struct Env<'a> {
     pub module_id: ModuleId,
     pub exposed_to_host: MutSet<Symbol>,
     pub stack_bytes: u32,
+    /// Source code to reference in a source map. If this is `None`, we don't emit one.
+    pub debug_sources: Option<DebugSources<'a>>,
+}
+
+/// Roc source code and the regions of its top-level definitions, for generating a source map
+pub struct DebugSources<'a> {
+    /// Path and content of each module's source file
+    pub files: MutMap<ModuleId, (&'a str, &'a str)>,
+    pub definition_regions: MutMap<Symbol, Region>,
 }
 
 impl Env<'_> {
@@ -59,6 +69,12 @@ pub fn parse_host<'a>(arena: &'a Bump, host_bytes: &[u8]) -> Result<WasmModule<'
     WasmModule::preload(arena, host_bytes, require_relocatable)
 }
 
+/// Print a binary from `build_app_binary` in the WebAssembly text format, for `roc build --emit-wat`
+pub fn binary_to_wat(arena: &Bump, binary: &[u8]) -> Result<String, ParseError> {
+    let require_relocatable = false;
+    WasmModule::preload(arena, binary, require_relocatable)?.to_wat()
+}
+
 /// Generate a Wasm module in binary form, ready to write to a file. Entry point from roc_build.
 ///   env            environment data from previous compiler stages
 ///   interns        names of functions and variables (as memory-efficient interned strings)
@@ -155,6 +171,7 @@ pub fn build_app_module<'a, 'r>(
     let helper_procs = backend.get_helpers();
 
     backend.register_symbol_debug_names();
+    backend.register_source_locations();
 
     if DEBUG_SETTINGS.helper_procs_ir {
         println!("## helper_procs");
@@ -199,6 +216,9 @@ pub struct CopyMemoryConfig {
     alignment_bytes: u32,
 }
 
+/// Copies larger than this use `memory.copy` rather than a sequence of loads and stores
+const MAX_UNROLLED_COPY_SIZE: u32 = 64;
+
 pub fn copy_memory(code_builder: &mut CodeBuilder, config: CopyMemoryConfig) {
     if config.from_ptr == config.to_ptr && config.from_offset == config.to_offset {
         return;
@@ -207,9 +227,33 @@ pub fn copy_memory(code_builder: &mut CodeBuilder, config: CopyMemoryConfig) {
         return;
     }
 
+    // Beyond a few vectors, a single bulk memory instruction is smaller and just as fast
+    if config.size > MAX_UNROLLED_COPY_SIZE {
+        code_builder.get_local(config.to_ptr);
+        if config.to_offset != 0 {
+            code_builder.i32_const(config.to_offset as i32);
+            code_builder.i32_add();
+        }
+        code_builder.get_local(config.from_ptr);
+        if config.from_offset != 0 {
+            code_builder.i32_const(config.from_offset as i32);
+            code_builder.i32_add();
+        }
+        code_builder.i32_const(config.size as i32);
+        code_builder.memory_copy();
+        return;
+    }
+
     let alignment = Align::from(config.alignment_bytes);
     let mut i = 0;
-    while config.size - i >= 8 {
+    while config.size - i >= 16 {
+        code_builder.get_local(config.to_ptr);
+        code_builder.get_local(config.from_ptr);
+        code_builder.v128_load(alignment, i + config.from_offset);
+        code_builder.v128_store(alignment, i + config.to_offset);
+        i += 16;
+    }
+    if config.size - i >= 8 {
         code_builder.get_local(config.to_ptr);
         code_builder.get_local(config.from_ptr);
         code_builder.i64_load(alignment, i + config.from_offset);
@@ -6,10 +6,13 @@ use roc_module::low_level::LowLevel;
 use roc_module::symbol::Symbol;
 use roc_mono::code_gen_help::HelperOp;
 use roc_mono::ir::{HigherOrderLowLevel, PassedFunction, ProcLayout};
-use roc_mono::layout::{Builtin, InLayout, Layout, LayoutInterner, LayoutRepr, UnionLayout};
+use roc_mono::layout::{
+    Builtin, InLayout, Layout, LayoutInterner, LayoutRepr, STLayoutInterner, UnionLayout,
+};
 use roc_mono::low_level::HigherOrder;
 
 use crate::backend::{ProcLookupData, ProcSource, WasmBackend};
+use crate::code_builder::CodeBuilder;
 use crate::layout::{StackMemoryFormat, WasmLayout};
 use crate::storage::{AddressValue, StackMemoryLocation, StoredValue};
 use crate::PTR_TYPE;
@@ -74,6 +77,9 @@ impl From<ValueType> for CodeGenNumType {
             ValueType::I64 => CodeGenNumType::I64,
             ValueType::F32 => CodeGenNumType::F32,
             ValueType::F64 => CodeGenNumType::F64,
+            ValueType::V128 => {
+                internal_error!("Tried to perform a Num low-level operation on a v128 local")
+            }
         }
     }
 }
@@ -169,6 +175,198 @@ impl<'a> LowLevelCall<'a> {
         }
     }
 
+    /// The width of an integer return value, for choosing a Zig builtin
+    fn ret_int_width(&self) -> IntWidth {
+        match self.ret_layout_raw {
+            LayoutRepr::Builtin(Builtin::Int(width)) => width,
+            _ => internal_error!(
+                "Invalid return layout for {:?}: {:?}",
+                self.lowlevel,
+                self.ret_layout
+            ),
+        }
+    }
+
+    /// The location of a return value that is stored in stack memory
+    fn ret_local_and_offset(&self, backend: &WasmBackend<'a, '_>) -> (LocalId, u32) {
+        match &self.ret_storage {
+            StoredValue::StackMemory { location, .. } => {
+                location.local_and_offset(backend.storage.stack_frame_pointer)
+            }
+            _ => internal_error!("{:?} should return a value in stack memory", self.lowlevel),
+        }
+    }
+
+    /// Copy the two 64-bit halves of a 128-bit number into locals, least significant first.
+    /// Wasm has no 128-bit instructions, so some simple operations are done on the halves.
+    fn load_i128_halves(
+        &self,
+        backend: &mut WasmBackend<'a, '_>,
+        symbol: Symbol,
+    ) -> (LocalId, LocalId) {
+        let (ptr, offset) = match backend.storage.get(&symbol) {
+            StoredValue::StackMemory { location, .. } => {
+                location.local_and_offset(backend.storage.stack_frame_pointer)
+            }
+            _ => internal_error!("I128 should be in stack memory"),
+        };
+        let low = backend.storage.create_anonymous_local(ValueType::I64);
+        let high = backend.storage.create_anonymous_local(ValueType::I64);
+
+        backend.code_builder.get_local(ptr);
+        backend.code_builder.i64_load(Align::Bytes8, offset);
+        backend.code_builder.set_local(low);
+        backend.code_builder.get_local(ptr);
+        backend.code_builder.i64_load(Align::Bytes8, offset + 8);
+        backend.code_builder.set_local(high);
+
+        (low, high)
+    }
+
+    fn i128_bitwise(&self, backend: &mut WasmBackend<'a, '_>, op: fn(&mut CodeBuilder<'a>)) {
+        let (a_low, a_high) = self.load_i128_halves(backend, self.arguments[0]);
+        let (b_low, b_high) = self.load_i128_halves(backend, self.arguments[1]);
+        let (ret_ptr, ret_offset) = self.ret_local_and_offset(backend);
+
+        backend.code_builder.get_local(ret_ptr);
+        backend.code_builder.get_local(a_low);
+        backend.code_builder.get_local(b_low);
+        op(&mut backend.code_builder);
+        backend.code_builder.i64_store(Align::Bytes8, ret_offset);
+
+        backend.code_builder.get_local(ret_ptr);
+        backend.code_builder.get_local(a_high);
+        backend.code_builder.get_local(b_high);
+        op(&mut backend.code_builder);
+        backend
+            .code_builder
+            .i64_store(Align::Bytes8, ret_offset + 8);
+    }
+
+    /// Negate a 128-bit integer, or take its absolute value
+    fn i128_neg_or_abs(&self, backend: &mut WasmBackend<'a, '_>, panic_msg: &'a str) {
+        let is_signed = layout_is_signed_int(self.ret_layout);
+        let is_abs = self.lowlevel == LowLevel::NumAbs;
+        let (low, high) = self.load_i128_halves(backend, self.arguments[0]);
+        let (ret_ptr, ret_offset) = self.ret_local_and_offset(backend);
+
+        if is_abs && !is_signed {
+            backend.code_builder.get_local(ret_ptr);
+            backend.code_builder.get_local(low);
+            backend.code_builder.i64_store(Align::Bytes8, ret_offset);
+            backend.code_builder.get_local(ret_ptr);
+            backend.code_builder.get_local(high);
+            backend
+                .code_builder
+                .i64_store(Align::Bytes8, ret_offset + 8);
+            return;
+        }
+
+        if is_signed {
+            // The minimum value has no positive counterpart
+            backend.code_builder.get_local(high);
+            backend.code_builder.i64_const(i64::MIN);
+            backend.code_builder.i64_eq();
+            backend.code_builder.get_local(low);
+            backend.code_builder.i64_eqz();
+            backend.code_builder.i32_and();
+            backend.code_builder.if_();
+            backend.stmt_internal_error(panic_msg);
+            backend.code_builder.end();
+        }
+
+        // low half of -x
+        backend.code_builder.get_local(ret_ptr);
+        backend.code_builder.i64_const(0);
+        backend.code_builder.get_local(low);
+        backend.code_builder.i64_sub();
+        if is_abs {
+            // (x < 0) ? -x : x
+            backend.code_builder.get_local(low);
+            backend.code_builder.get_local(high);
+            backend.code_builder.i64_const(0);
+            backend.code_builder.i64_lt_s();
+            backend.code_builder.select();
+        }
+        backend.code_builder.i64_store(Align::Bytes8, ret_offset);
+
+        // high half of -x, borrowing 1 from it if the low half is non-zero
+        backend.code_builder.get_local(ret_ptr);
+        backend.code_builder.i64_const(0);
+        backend.code_builder.get_local(high);
+        backend.code_builder.i64_sub();
+        backend.code_builder.get_local(low);
+        backend.code_builder.i64_const(0);
+        backend.code_builder.i64_ne();
+        backend.code_builder.i64_extend_u_i32();
+        backend.code_builder.i64_sub();
+        if is_abs {
+            backend.code_builder.get_local(high);
+            backend.code_builder.get_local(high);
+            backend.code_builder.i64_const(0);
+            backend.code_builder.i64_lt_s();
+            backend.code_builder.select();
+        }
+        backend
+            .code_builder
+            .i64_store(Align::Bytes8, ret_offset + 8);
+    }
+
+    /// Load the argument and convert it to a float, for NumToFloatCast and NumToFloatChecked
+    fn load_arg_as_float(&self, backend: &mut WasmBackend<'a, '_>, float_width: FloatWidth) {
+        use CodeGenNumType::*;
+
+        let arg = self.arguments[0];
+        let arg_layout = backend.storage.symbol_layouts[&arg];
+        let float_layout = match float_width {
+            FloatWidth::F32 => WasmLayout::Primitive(ValueType::F32, 4),
+            FloatWidth::F64 => WasmLayout::Primitive(ValueType::F64, 8),
+        };
+
+        // Load only the argument, without any return pointer
+        backend.storage.load_symbols_for_call(
+            &mut backend.code_builder,
+            &[arg],
+            self.ret_symbol,
+            &float_layout,
+        );
+
+        match (float_width, backend.layout_interner.get_repr(arg_layout)) {
+            (FloatWidth::F32, LayoutRepr::Builtin(Builtin::Float(FloatWidth::F32))) => {}
+            (FloatWidth::F32, LayoutRepr::Builtin(Builtin::Float(FloatWidth::F64))) => {
+                backend.code_builder.f32_demote_f64()
+            }
+            (FloatWidth::F64, LayoutRepr::Builtin(Builtin::Float(FloatWidth::F32))) => {
+                backend.code_builder.f64_promote_f32()
+            }
+            (FloatWidth::F64, LayoutRepr::Builtin(Builtin::Float(FloatWidth::F64))) => {}
+            (_, LayoutRepr::Builtin(Builtin::Int(width))) => {
+                let is_signed = width.is_signed();
+                match (float_width, CodeGenNumType::from(arg_layout)) {
+                    (FloatWidth::F32, I32) if is_signed => backend.code_builder.f32_convert_s_i32(),
+                    (FloatWidth::F32, I32) => backend.code_builder.f32_convert_u_i32(),
+                    (FloatWidth::F32, I64) if is_signed => backend.code_builder.f32_convert_s_i64(),
+                    (FloatWidth::F32, I64) => backend.code_builder.f32_convert_u_i64(),
+                    (FloatWidth::F64, I32) if is_signed => backend.code_builder.f64_convert_s_i32(),
+                    (FloatWidth::F64, I32) => backend.code_builder.f64_convert_u_i32(),
+                    (FloatWidth::F64, I64) if is_signed => backend.code_builder.f64_convert_s_i64(),
+                    (FloatWidth::F64, I64) => backend.code_builder.f64_convert_u_i64(),
+                    (FloatWidth::F32, _) => backend
+                        .call_host_fn_after_loading_args(&bitcode::INT_TO_FLOAT_CAST_F32[width]),
+                    (FloatWidth::F64, _) => backend
+                        .call_host_fn_after_loading_args(&bitcode::INT_TO_FLOAT_CAST_F64[width]),
+                }
+            }
+            (_, LayoutRepr::Builtin(Builtin::Decimal)) => {
+                backend.call_host_fn_after_loading_args(bitcode::DEC_TO_F64);
+                if float_width == FloatWidth::F32 {
+                    backend.code_builder.f32_demote_f64();
+                }
+            }
+            (_, x) => internal_error!("{:?} is not defined for {:?}", self.lowlevel, x),
+        }
+    }
+
     ///  Main entrypoint from WasmBackend
     pub fn generate(&self, backend: &mut WasmBackend<'a, '_>) {
         use CodeGenNumType::*;
@@ -1057,12 +1255,11 @@ impl<'a> LowLevelCall<'a> {
                             &bitcode::NUM_GREATER_THAN[IntWidth::U128]
                         };
 
-                        self.load_args_and_call_zig(backend, intrinsic);
+                        backend.call_host_fn_after_loading_args(intrinsic);
                     }
                     Decimal => {
                         // same as i128
-                        self.load_args_and_call_zig(
-                            backend,
+                        backend.call_host_fn_after_loading_args(
                             &bitcode::NUM_GREATER_THAN[IntWidth::I128],
                         );
                     }
@@ -1094,12 +1291,11 @@ impl<'a> LowLevelCall<'a> {
                             &bitcode::NUM_GREATER_THAN_OR_EQUAL[IntWidth::U128]
                         };
 
-                        self.load_args_and_call_zig(backend, intrinsic);
+                        backend.call_host_fn_after_loading_args(intrinsic);
                     }
                     Decimal => {
                         // same as i128
-                        self.load_args_and_call_zig(
-                            backend,
+                        backend.call_host_fn_after_loading_args(
                             &bitcode::NUM_GREATER_THAN_OR_EQUAL[IntWidth::I128],
                         );
                     }
@@ -1131,12 +1327,11 @@ impl<'a> LowLevelCall<'a> {
                             &bitcode::NUM_LESS_THAN[IntWidth::U128]
                         };
 
-                        self.load_args_and_call_zig(backend, intrinsic);
+                        backend.call_host_fn_after_loading_args(intrinsic);
                     }
                     Decimal => {
                         // same as i128
-                        self.load_args_and_call_zig(
-                            backend,
+                        backend.call_host_fn_after_loading_args(
                             &bitcode::NUM_LESS_THAN[IntWidth::I128],
                         );
                     }
@@ -1169,12 +1364,11 @@ impl<'a> LowLevelCall<'a> {
                             &bitcode::NUM_LESS_THAN_OR_EQUAL[IntWidth::U128]
                         };
 
-                        self.load_args_and_call_zig(backend, intrinsic);
+                        backend.call_host_fn_after_loading_args(intrinsic);
                     }
                     Decimal => {
                         // same as i128
-                        self.load_args_and_call_zig(
-                            backend,
+                        backend.call_host_fn_after_loading_args(
                             &bitcode::NUM_LESS_THAN_OR_EQUAL[IntWidth::I128],
                         );
                     }
@@ -1227,8 +1421,17 @@ impl<'a> LowLevelCall<'a> {
                         backend.code_builder.f64_lt();
                         backend.code_builder.i32_add();
                     }
-                    I128 | Decimal => {
-                        self.load_args(backend);
+                    I128 => {
+                        let intrinsic = if is_signed {
+                            &bitcode::NUM_COMPARE[IntWidth::I128]
+                        } else {
+                            &bitcode::NUM_COMPARE[IntWidth::U128]
+                        };
+
+                        self.load_args_and_call_zig(backend, intrinsic);
+                    }
+                    Decimal => {
+                        // same as i128
                         self.load_args_and_call_zig(backend, &bitcode::NUM_COMPARE[IntWidth::I128]);
                     }
                 }
@@ -1238,8 +1441,8 @@ impl<'a> LowLevelCall<'a> {
                 match CodeGenNumType::for_symbol(backend, self.arguments[0]) {
                     F32 => backend.code_builder.f32_div(),
                     F64 => backend.code_builder.f64_div(),
-                    Decimal => self.load_args_and_call_zig(backend, bitcode::DEC_DIV),
-                    x => todo!("{:?} for {:?}", self.lowlevel, x),
+                    Decimal => backend.call_host_fn_after_loading_args(bitcode::DEC_DIV),
+                    x => internal_error!("NumDivFrac is not defined for {:?}", x),
                 }
             }
             NumDivTruncUnchecked => {
@@ -1260,7 +1463,10 @@ impl<'a> LowLevelCall<'a> {
                             backend.code_builder.i64_div_u()
                         }
                     }
-                    x => todo!("{:?} for {:?}", self.lowlevel, x),
+                    I128 => backend.call_host_fn_after_loading_args(
+                        &bitcode::NUM_DIV_TRUNC[self.ret_int_width()],
+                    ),
+                    x => internal_error!("NumDivTruncUnchecked is not defined for {:?}", x),
                 }
             }
             NumDivCeilUnchecked => match self.ret_layout_raw {
@@ -1272,10 +1478,25 @@ impl<'a> LowLevelCall<'a> {
 
             NumRemUnchecked => {
                 self.load_args(backend);
+                let is_signed = symbol_is_signed_int(backend, self.arguments[0]);
                 match CodeGenNumType::for_symbol(backend, self.arguments[0]) {
-                    I32 => backend.code_builder.i32_rem_s(),
-                    I64 => backend.code_builder.i64_rem_s(),
-                    _ => todo!("{:?} for {:?}", self.lowlevel, self.ret_layout),
+                    I32 => {
+                        if is_signed {
+                            backend.code_builder.i32_rem_s()
+                        } else {
+                            backend.code_builder.i32_rem_u()
+                        }
+                    }
+                    I64 => {
+                        if is_signed {
+                            backend.code_builder.i64_rem_s()
+                        } else {
+                            backend.code_builder.i64_rem_u()
+                        }
+                    }
+                    I128 => backend
+                        .call_host_fn_after_loading_args(&bitcode::NUM_REM[self.ret_int_width()]),
+                    _ => panic_ret_type(),
                 }
             }
             NumIsMultipleOf => {
@@ -1381,10 +1602,9 @@ impl<'a> LowLevelCall<'a> {
                 const PANIC_MSG: &str =
                     "Integer absolute overflowed because its argument is the minimum value";
 
-                self.load_args(backend);
-
                 match CodeGenNumType::from(self.ret_layout) {
                     I32 => {
+                        self.load_args(backend);
                         if !layout_is_signed_int(self.ret_layout) {
                             return;
                         }
@@ -1411,6 +1631,7 @@ impl<'a> LowLevelCall<'a> {
                         backend.code_builder.select();
                     }
                     I64 => {
+                        self.load_args(backend);
                         if !layout_is_signed_int(self.ret_layout) {
                             return;
                         }
@@ -1436,18 +1657,25 @@ impl<'a> LowLevelCall<'a> {
                         // (x >= 0) ? x : -x
                         backend.code_builder.select();
                     }
-                    F32 => backend.code_builder.f32_abs(),
-                    F64 => backend.code_builder.f64_abs(),
-                    _ => todo!("{:?} for {:?}", self.lowlevel, self.ret_layout),
+                    F32 => {
+                        self.load_args(backend);
+                        backend.code_builder.f32_abs()
+                    }
+                    F64 => {
+                        self.load_args(backend);
+                        backend.code_builder.f64_abs()
+                    }
+                    I128 => self.i128_neg_or_abs(backend, PANIC_MSG),
+                    Decimal => self.load_args_and_call_zig(backend, bitcode::DEC_ABS),
                 }
             }
             NumNeg => {
                 const PANIC_MSG: &str =
                     "Integer negation overflowed because its argument is the minimum value";
 
-                self.load_args(backend);
                 match CodeGenNumType::from(self.ret_layout) {
                     I32 => {
+                        self.load_args(backend);
                         backend.code_builder.i32_const(i32::MIN);
                         backend.code_builder.i32_eq();
                         backend.code_builder.if_();
@@ -1459,6 +1687,7 @@ impl<'a> LowLevelCall<'a> {
                         backend.code_builder.i32_sub();
                     }
                     I64 => {
+                        self.load_args(backend);
                         backend.code_builder.i64_const(i64::MIN);
                         backend.code_builder.i64_eq();
                         backend.code_builder.if_();
@@ -1469,9 +1698,16 @@ impl<'a> LowLevelCall<'a> {
                         self.load_args(backend);
                         backend.code_builder.i64_sub();
                     }
-                    F32 => backend.code_builder.f32_neg(),
-                    F64 => backend.code_builder.f64_neg(),
-                    _ => todo!("{:?} for {:?}", self.lowlevel, self.ret_layout),
+                    F32 => {
+                        self.load_args(backend);
+                        backend.code_builder.f32_neg()
+                    }
+                    F64 => {
+                        self.load_args(backend);
+                        backend.code_builder.f64_neg()
+                    }
+                    I128 => self.i128_neg_or_abs(backend, PANIC_MSG),
+                    Decimal => self.load_args_and_call_zig(backend, bitcode::DEC_NEGATE),
                 }
             }
             NumSin => match self.ret_layout_raw {
@@ -1519,55 +1755,33 @@ impl<'a> LowLevelCall<'a> {
                 }
                 _ => panic_ret_type(),
             },
-            NumToFrac => {
-                self.load_args(backend);
-                let ret_type = CodeGenNumType::from(self.ret_layout);
-                let arg_type = CodeGenNumType::for_symbol(backend, self.arguments[0]);
-                let arg_is_signed = symbol_is_signed_int(backend, self.arguments[0]);
-                match (ret_type, arg_type) {
-                    (F32, I32) => backend.code_builder.f32_convert_s_i32(),
-                    (F32, I64) => backend.code_builder.f32_convert_s_i64(),
-                    (F32, F32) => {}
-                    (F32, F64) => backend.code_builder.f32_demote_f64(),
-
-                    (F64, I32) => backend.code_builder.f64_convert_s_i32(),
-                    (F64, I64) => backend.code_builder.f64_convert_s_i64(),
-                    (F64, F32) => backend.code_builder.f64_promote_f32(),
-                    (F64, F64) => {}
-
-                    (Decimal, I32) => {
-                        let int_width = match arg_is_signed {
-                            true => IntWidth::I32,
-                            false => IntWidth::U32,
-                        };
-
-                        self.load_args_and_call_zig(backend, &bitcode::DEC_FROM_INT[int_width]);
-                    }
-                    (Decimal, I64) => {
-                        let int_width = match arg_is_signed {
-                            true => IntWidth::I64,
-                            false => IntWidth::U64,
-                        };
-
-                        self.load_args_and_call_zig(backend, &bitcode::DEC_FROM_INT[int_width]);
-                    }
-                    (Decimal, F32) => {
-                        self.load_args_and_call_zig(
-                            backend,
-                            &bitcode::DEC_FROM_FLOAT[FloatWidth::F32],
-                        );
-                    }
-                    (Decimal, F64) => {
-                        self.load_args_and_call_zig(
-                            backend,
-                            &bitcode::DEC_FROM_FLOAT[FloatWidth::F64],
-                        );
+            NumToFrac => match self.ret_layout_raw {
+                LayoutRepr::Builtin(Builtin::Float(width)) => {
+                    self.load_arg_as_float(backend, width)
+                }
+                LayoutRepr::Builtin(Builtin::Decimal) => {
+                    let arg_layout = backend.storage.symbol_layouts[&self.arguments[0]];
+                    match backend.layout_interner.get_repr(arg_layout) {
+                        LayoutRepr::Builtin(Builtin::Int(width)) => {
+                            self.load_args_and_call_zig(backend, &bitcode::DEC_FROM_INT[width])
+                        }
+                        LayoutRepr::Builtin(Builtin::Float(width)) => {
+                            self.load_args_and_call_zig(backend, &bitcode::DEC_FROM_FLOAT[width])
+                        }
+                        LayoutRepr::Builtin(Builtin::Decimal) => {
+                            let (ret_ptr, ret_offset) = self.ret_local_and_offset(backend);
+                            backend.storage.copy_value_to_memory(
+                                &mut backend.code_builder,
+                                ret_ptr,
+                                ret_offset,
+                                self.arguments[0],
+                            );
+                        }
+                        x => internal_error!("NumToFrac is not defined for {:?}", x),
                     }
-                    (Decimal, Decimal) => {}
-
-                    _ => todo!("{:?}: {:?} -> {:?}", self.lowlevel, arg_type, ret_type),
                 }
-            }
+                _ => panic_ret_type(),
+            },
             NumPow => match self.ret_layout_raw {
                 LayoutRepr::Builtin(Builtin::Float(width)) => {
                     self.load_args_and_call_zig(backend, &bitcode::NUM_POW[width]);
@@ -1620,14 +1834,14 @@ impl<'a> LowLevelCall<'a> {
                 let width = match ret_type {
                     CodeGenNumType::I32 => IntWidth::I32,
                     CodeGenNumType::I64 => IntWidth::I64,
-                    CodeGenNumType::I128 => todo!("{:?} for I128", self.lowlevel),
+                    CodeGenNumType::I128 => self.ret_int_width(),
                     _ => internal_error!("Invalid return type for round: {:?}", ret_type),
                 };
 
                 match arg_type {
-                    F32 => self.load_args_and_call_zig(backend, &bitcode::NUM_ROUND_F32[width]),
-                    F64 => self.load_args_and_call_zig(backend, &bitcode::NUM_ROUND_F64[width]),
-                    Decimal => self.load_args_and_call_zig(backend, &bitcode::DEC_ROUND[width]),
+                    F32 => backend.call_host_fn_after_loading_args(&bitcode::NUM_ROUND_F32[width]),
+                    F64 => backend.call_host_fn_after_loading_args(&bitcode::NUM_ROUND_F64[width]),
+                    Decimal => backend.call_host_fn_after_loading_args(&bitcode::DEC_ROUND[width]),
                     _ => internal_error!("Invalid argument type for round: {:?}", arg_type),
                 }
             }
@@ -1639,28 +1853,46 @@ impl<'a> LowLevelCall<'a> {
                 let width = match ret_type {
                     CodeGenNumType::I32 => IntWidth::I32,
                     CodeGenNumType::I64 => IntWidth::I64,
-                    CodeGenNumType::I128 => todo!("{:?} for I128", self.lowlevel),
+                    CodeGenNumType::I128 => self.ret_int_width(),
                     _ => internal_error!("Invalid return type for round: {:?}", ret_type),
                 };
 
-                match (arg_type, self.lowlevel) {
-                    (F32, NumCeiling) => {
+                match (arg_type, ret_type, self.lowlevel) {
+                    (F32, I128, NumCeiling) => {
+                        // Wasm has no instructions for 128-bit integers
+                        return backend
+                            .call_host_fn_after_loading_args(&bitcode::NUM_CEILING_F32[width]);
+                    }
+                    (F64, I128, NumCeiling) => {
+                        return backend
+                            .call_host_fn_after_loading_args(&bitcode::NUM_CEILING_F64[width]);
+                    }
+                    (F32, I128, NumFloor) => {
+                        return backend
+                            .call_host_fn_after_loading_args(&bitcode::NUM_FLOOR_F32[width]);
+                    }
+                    (F64, I128, NumFloor) => {
+                        return backend
+                            .call_host_fn_after_loading_args(&bitcode::NUM_FLOOR_F64[width]);
+                    }
+                    (F32, _, NumCeiling) => {
                         backend.code_builder.f32_ceil();
                     }
-                    (F64, NumCeiling) => {
+                    (F64, _, NumCeiling) => {
                         backend.code_builder.f64_ceil();
                     }
-                    (Decimal, NumCeiling) => {
-                        return self.load_args_and_call_zig(backend, &bitcode::DEC_CEILING[width]);
+                    (Decimal, _, NumCeiling) => {
+                        return backend
+                            .call_host_fn_after_loading_args(&bitcode::DEC_CEILING[width]);
                     }
-                    (F32, NumFloor) => {
+                    (F32, _, NumFloor) => {
                         backend.code_builder.f32_floor();
                     }
-                    (F64, NumFloor) => {
+                    (F64, _, NumFloor) => {
                         backend.code_builder.f64_floor();
                     }
-                    (Decimal, NumFloor) => {
-                        return self.load_args_and_call_zig(backend, &bitcode::DEC_FLOOR[width]);
+                    (Decimal, _, NumFloor) => {
+                        return backend.call_host_fn_after_loading_args(&bitcode::DEC_FLOOR[width]);
                     }
                     _ => internal_error!("Invalid argument type for ceiling: {:?}", arg_type),
                 }
@@ -1671,7 +1903,6 @@ impl<'a> LowLevelCall<'a> {
                     (I32, F64) => backend.code_builder.i32_trunc_s_f64(),
                     (I64, F32) => backend.code_builder.i64_trunc_s_f32(),
                     (I64, F64) => backend.code_builder.i64_trunc_s_f64(),
-                    (I128, _) => todo!("{:?} for I128", self.lowlevel),
                     _ => panic_ret_type(),
                 }
             }
@@ -1687,11 +1918,11 @@ impl<'a> LowLevelCall<'a> {
                 let width = match ret_type {
                     CodeGenNumType::I32 => IntWidth::I32,
                     CodeGenNumType::I64 => IntWidth::I64,
-                    CodeGenNumType::I128 => todo!("{:?} for I128", self.lowlevel),
+                    CodeGenNumType::I128 => self.ret_int_width(),
                     _ => internal_error!("Invalid return type for pow: {:?}", ret_type),
                 };
 
-                self.load_args_and_call_zig(backend, &bitcode::NUM_POW_INT[width])
+                backend.call_host_fn_after_loading_args(&bitcode::NUM_POW_INT[width])
             }
 
             NumIsNan => num_is_nan(backend, self.arguments[0]),
@@ -1725,46 +1956,63 @@ impl<'a> LowLevelCall<'a> {
                 }
                 _ => panic_ret_type(),
             },
-            NumBitwiseAnd => {
-                self.load_args(backend);
-                match CodeGenNumType::from(self.ret_layout) {
-                    I32 => backend.code_builder.i32_and(),
-                    I64 => backend.code_builder.i64_and(),
-                    I128 => todo!("{:?} for I128", self.lowlevel),
-                    _ => panic_ret_type(),
+            NumBitwiseAnd => match CodeGenNumType::from(self.ret_layout) {
+                I32 => {
+                    self.load_args(backend);
+                    backend.code_builder.i32_and()
                 }
-            }
-            NumBitwiseXor => {
-                self.load_args(backend);
-                match CodeGenNumType::from(self.ret_layout) {
-                    I32 => backend.code_builder.i32_xor(),
-                    I64 => backend.code_builder.i64_xor(),
-                    I128 => todo!("{:?} for I128", self.lowlevel),
-                    _ => panic_ret_type(),
+                I64 => {
+                    self.load_args(backend);
+                    backend.code_builder.i64_and()
                 }
-            }
-            NumBitwiseOr => {
-                self.load_args(backend);
-                match CodeGenNumType::from(self.ret_layout) {
-                    I32 => backend.code_builder.i32_or(),
-                    I64 => backend.code_builder.i64_or(),
-                    I128 => todo!("{:?} for I128", self.lowlevel),
-                    _ => panic_ret_type(),
+                I128 => self.i128_bitwise(backend, CodeBuilder::i64_and),
+                _ => panic_ret_type(),
+            },
+            NumBitwiseXor => match CodeGenNumType::from(self.ret_layout) {
+                I32 => {
+                    self.load_args(backend);
+                    backend.code_builder.i32_xor()
                 }
-            }
+                I64 => {
+                    self.load_args(backend);
+                    backend.code_builder.i64_xor()
+                }
+                I128 => self.i128_bitwise(backend, CodeBuilder::i64_xor),
+                _ => panic_ret_type(),
+            },
+            NumBitwiseOr => match CodeGenNumType::from(self.ret_layout) {
+                I32 => {
+                    self.load_args(backend);
+                    backend.code_builder.i32_or()
+                }
+                I64 => {
+                    self.load_args(backend);
+                    backend.code_builder.i64_or()
+                }
+                I128 => self.i128_bitwise(backend, CodeBuilder::i64_or),
+                _ => panic_ret_type(),
+            },
             NumShiftLeftBy => {
                 let num = self.arguments[0];
                 let bits = self.arguments[1];
-                backend
-                    .storage
-                    .load_symbols(&mut backend.code_builder, &[num, bits]);
                 match CodeGenNumType::from(self.ret_layout) {
-                    I32 => backend.code_builder.i32_shl(),
+                    I32 => {
+                        backend
+                            .storage
+                            .load_symbols(&mut backend.code_builder, &[num, bits]);
+                        backend.code_builder.i32_shl();
+                    }
                     I64 => {
+                        backend
+                            .storage
+                            .load_symbols(&mut backend.code_builder, &[num, bits]);
                         backend.code_builder.i64_extend_u_i32();
                         backend.code_builder.i64_shl();
                     }
-                    I128 => todo!("{:?} for I128", self.lowlevel),
+                    I128 => self.load_args_and_call_zig(
+                        backend,
+                        &bitcode::NUM_SHIFT_LEFT_BY[self.ret_int_width()],
+                    ),
                     _ => panic_ret_type(),
                 }
             }
@@ -1811,7 +2059,10 @@ impl<'a> LowLevelCall<'a> {
                         backend.code_builder.i64_extend_u_i32();
                         backend.code_builder.i64_shr_s();
                     }
-                    I128 => todo!("{:?} for I128", self.lowlevel),
+                    I128 => self.load_args_and_call_zig(
+                        backend,
+                        &bitcode::NUM_SHIFT_RIGHT_BY[self.ret_int_width()],
+                    ),
                     _ => panic_ret_type(),
                 }
             }
@@ -1852,7 +2103,10 @@ impl<'a> LowLevelCall<'a> {
                         backend.code_builder.i64_extend_u_i32();
                         backend.code_builder.i64_shr_u();
                     }
-                    I128 => self.load_args_and_call_zig(backend, "__lshrti3"), // from compiler_rt
+                    I128 => self.load_args_and_call_zig(
+                        backend,
+                        &bitcode::NUM_SHIFT_RIGHT_ZERO_FILL[self.ret_int_width()],
+                    ),
                     _ => panic_ret_type(),
                 }
             }
@@ -1882,8 +2136,15 @@ impl<'a> LowLevelCall<'a> {
                         self.wrap_small_int(backend, ret_width);
                     }
                     (I32, I128) => {
-                        self.load_args(backend);
-                        backend.code_builder.i32_load(Align::Bytes4, 0);
+                        let (frame_ptr, offset) = match backend.storage.get(&self.arguments[0]) {
+                            StoredValue::StackMemory { location, .. } => {
+                                location.local_and_offset(backend.storage.stack_frame_pointer)
+                            }
+                            _ => internal_error!("I128 should be in stack memory"),
+                        };
+                        backend.code_builder.get_local(frame_ptr);
+                        backend.code_builder.i32_load(Align::Bytes4, offset);
+                        self.wrap_small_int(backend, ret_width);
                     }
                     (I64, I32) => {
                         self.load_args(backend);
@@ -1906,73 +2167,60 @@ impl<'a> LowLevelCall<'a> {
                         backend.code_builder.get_local(frame_ptr);
                         backend.code_builder.i64_load(Align::Bytes8, offset);
                     }
-                    (I128, I64) => {
-                        // Symbols are loaded as if for a call, so the i128 "return address" and i64 value are on the value stack
-                        self.load_args(backend);
-                        backend.code_builder.i64_store(Align::Bytes8, 0);
-
-                        // Zero the most significant 64 bits
-                        let (frame_ptr, offset) = match &self.ret_storage {
-                            StoredValue::StackMemory { location, .. } => {
-                                location.local_and_offset(backend.storage.stack_frame_pointer)
+                    (I128, I32 | I64) => {
+                        let (ret_ptr, ret_offset) = self.ret_local_and_offset(backend);
+                        let low = backend.storage.create_anonymous_local(ValueType::I64);
+                        backend
+                            .storage
+                            .load_symbols(&mut backend.code_builder, self.arguments);
+                        if arg_type == I32 {
+                            if arg_width.is_signed() {
+                                backend.code_builder.i64_extend_s_i32();
+                            } else {
+                                backend.code_builder.i64_extend_u_i32();
                             }
-                            _ => internal_error!("I128 should be in stack memory"),
-                        };
-                        backend.code_builder.get_local(frame_ptr);
-                        backend.code_builder.i64_const(0);
-                        backend.code_builder.i64_store(Align::Bytes8, offset + 8);
-                    }
+                        }
+                        backend.code_builder.set_local(low);
 
-                    _ => todo!("{:?}: {:?} -> {:?}", self.lowlevel, arg_type, ret_type),
-                }
-            }
-            NumToFloatCast => {
-                self.load_args(backend);
-                let arg_layout = backend.storage.symbol_layouts[&self.arguments[0]];
-                let arg_signed = match backend.layout_interner.get_repr(arg_layout) {
-                    LayoutRepr::Builtin(Builtin::Int(w)) => w.is_signed(),
-                    LayoutRepr::Builtin(Builtin::Float(_)) => true, // unused
-                    LayoutRepr::Builtin(Builtin::Decimal) => true,
-                    x => internal_error!("Num.intCast is not defined for {:?}", x),
-                };
-                let ret_type = CodeGenNumType::from(self.ret_layout);
-                let arg_type = CodeGenNumType::from(arg_layout);
+                        backend.code_builder.get_local(ret_ptr);
+                        backend.code_builder.get_local(low);
+                        backend.code_builder.i64_store(Align::Bytes8, ret_offset);
 
-                match (ret_type, arg_type) {
-                    (F32, F32) => {}
-                    (F32, F64) => backend.code_builder.f32_demote_f64(),
-                    (F32, I32) => {
-                        if arg_signed {
-                            backend.code_builder.f32_convert_s_i32()
-                        } else {
-                            backend.code_builder.f32_convert_u_i32()
-                        }
-                    }
-                    (F32, I64) => {
-                        if arg_signed {
-                            backend.code_builder.f32_convert_s_i64()
-                        } else {
-                            backend.code_builder.f32_convert_u_i64()
-                        }
-                    }
-                    (F64, F64) => {}
-                    (F64, I32) => {
-                        if arg_signed {
-                            backend.code_builder.f64_convert_s_i32()
-                        } else {
-                            backend.code_builder.f64_convert_u_i32()
-                        }
-                    }
-                    (F64, I64) => {
-                        if arg_signed {
-                            backend.code_builder.f64_convert_s_i64()
+                        // Sign-extend or zero-extend into the most significant 64 bits
+                        backend.code_builder.get_local(ret_ptr);
+                        if arg_width.is_signed() {
+                            backend.code_builder.get_local(low);
+                            backend.code_builder.i64_const(63);
+                            backend.code_builder.i64_shr_s();
                         } else {
-                            backend.code_builder.f64_convert_u_i64()
+                            backend.code_builder.i64_const(0);
                         }
+                        backend
+                            .code_builder
+                            .i64_store(Align::Bytes8, ret_offset + 8);
+                    }
+                    (I128, I128) => {
+                        let (ret_ptr, ret_offset) = self.ret_local_and_offset(backend);
+                        backend.storage.copy_value_to_memory(
+                            &mut backend.code_builder,
+                            ret_ptr,
+                            ret_offset,
+                            self.arguments[0],
+                        );
                     }
-                    _ => todo!("{:?}: {:?} -> {:?}", self.lowlevel, arg_type, ret_type),
+                    _ => internal_error!(
+                        "Num.intCast is not defined for {:?} -> {:?}",
+                        arg_type,
+                        ret_type
+                    ),
                 }
             }
+            NumToFloatCast => match self.ret_layout_raw {
+                LayoutRepr::Builtin(Builtin::Float(width)) => {
+                    self.load_arg_as_float(backend, width)
+                }
+                _ => panic_ret_type(),
+            },
             NumToIntChecked => {
                 let arg_layout = backend.storage.symbol_layouts[&self.arguments[0]];
 
@@ -2015,7 +2263,73 @@ impl<'a> LowLevelCall<'a> {
                 }
             }
             NumToFloatChecked => {
-                todo!("implement toF32Checked and toF64Checked");
+                // The return value is a struct like { converted_val: F64, out_of_bounds: Bool }
+                let value_layout = match self.ret_layout_raw {
+                    LayoutRepr::Struct(&[value, ..]) => backend.layout_interner.get_repr(value),
+                    x => internal_error!("NumToFloatChecked is not defined for {:?}", x),
+                };
+                let float_width = match value_layout {
+                    LayoutRepr::Builtin(Builtin::Float(width)) => width,
+                    x => internal_error!("NumToFloatChecked is not defined for {:?}", x),
+                };
+                let (value_type, value_size) = match float_width {
+                    FloatWidth::F32 => (ValueType::F32, 4),
+                    FloatWidth::F64 => (ValueType::F64, 8),
+                };
+                let arg = self.arguments[0];
+                let arg_layout = backend.storage.symbol_layouts[&arg];
+                let (ret_ptr, ret_offset) = self.ret_local_and_offset(backend);
+
+                let value = backend.storage.create_anonymous_local(value_type);
+                self.load_arg_as_float(backend, float_width);
+                backend.code_builder.set_local(value);
+
+                backend.code_builder.get_local(ret_ptr);
+                backend.code_builder.get_local(value);
+                match float_width {
+                    FloatWidth::F32 => backend.code_builder.f32_store(Align::Bytes4, ret_offset),
+                    FloatWidth::F64 => backend.code_builder.f64_store(Align::Bytes8, ret_offset),
+                }
+
+                // A number is out of bounds if it was finite, but the conversion made it infinite
+                backend.code_builder.get_local(ret_ptr);
+                backend.code_builder.get_local(value);
+                match float_width {
+                    FloatWidth::F32 => {
+                        backend.code_builder.f32_abs();
+                        backend.code_builder.f32_const(f32::INFINITY);
+                        backend.code_builder.f32_eq();
+                    }
+                    FloatWidth::F64 => {
+                        backend.code_builder.f64_abs();
+                        backend.code_builder.f64_const(f64::INFINITY);
+                        backend.code_builder.f64_eq();
+                    }
+                }
+                match backend.layout_interner.get_repr(arg_layout) {
+                    LayoutRepr::Builtin(Builtin::Float(FloatWidth::F32)) => {
+                        backend
+                            .storage
+                            .load_symbols(&mut backend.code_builder, &[arg]);
+                        backend.code_builder.f32_abs();
+                        backend.code_builder.f32_const(f32::INFINITY);
+                        backend.code_builder.f32_ne();
+                        backend.code_builder.i32_and();
+                    }
+                    LayoutRepr::Builtin(Builtin::Float(FloatWidth::F64)) => {
+                        backend
+                            .storage
+                            .load_symbols(&mut backend.code_builder, &[arg]);
+                        backend.code_builder.f64_abs();
+                        backend.code_builder.f64_const(f64::INFINITY);
+                        backend.code_builder.f64_ne();
+                        backend.code_builder.i32_and();
+                    }
+                    _ => {}
+                }
+                backend
+                    .code_builder
+                    .i32_store8(Align::Bytes1, ret_offset + value_size);
             }
             NumWithoutDecimalPoint => self.load_args_and_call_zig(backend, bitcode::DEC_TO_I128),
             NumWithDecimalPoint => self.load_args_and_call_zig(backend, bitcode::DEC_FROM_I128),
@@ -2083,7 +2397,7 @@ impl<'a> LowLevelCall<'a> {
                 backend.code_builder.i32_and();
             }
 
-            Hash => todo!("{:?}", self.lowlevel),
+            Hash => internal_error!("{:?} is not used by any builtin", self.lowlevel),
 
             Eq | NotEq => self.eq_or_neq(backend),
 
@@ -2097,6 +2411,7 @@ impl<'a> LowLevelCall<'a> {
                     ValueType::I64 => backend.code_builder.i64_const(0),
                     ValueType::F32 => backend.code_builder.f32_const(0.0),
                     ValueType::F64 => backend.code_builder.f64_const(0.0),
+                    ValueType::V128 => backend.code_builder.v128_const([0; 16]),
                 },
                 StoredValue::StackMemory { .. } => { /* do nothing */ }
             },
@@ -2171,6 +2486,18 @@ impl<'a> LowLevelCall<'a> {
                 backend.code_builder.i32_const(!invert_result as i32);
             }
 
+            // Records of integers with no padding can be compared as bytes, 16 at a time.
+            // Smaller records are better handled by the specialized helper procedure.
+            LayoutRepr::Struct(_)
+                if backend.layout_interner.stack_size(arg_layout) >= 16
+                    && is_bytewise_comparable(backend.layout_interner, arg_layout) =>
+            {
+                self.eq_bytes(backend);
+                if invert_result {
+                    backend.code_builder.i32_eqz();
+                }
+            }
+
             LayoutRepr::Builtin(Builtin::List(_))
             | LayoutRepr::Struct { .. }
             | LayoutRepr::Union(_)
@@ -2217,12 +2544,14 @@ impl<'a> LowLevelCall<'a> {
                         ValueType::I64 => backend.code_builder.i64_eq(),
                         ValueType::F32 => backend.code_builder.f32_eq(),
                         ValueType::F64 => backend.code_builder.f64_eq(),
+                        ValueType::V128 => internal_error!("Roc numbers are never v128"),
                     },
                     LowLevel::NotEq => match value_type {
                         ValueType::I32 => backend.code_builder.i32_ne(),
                         ValueType::I64 => backend.code_builder.i64_ne(),
                         ValueType::F32 => backend.code_builder.f32_ne(),
                         ValueType::F64 => backend.code_builder.f64_ne(),
+                        ValueType::V128 => internal_error!("Roc numbers are never v128"),
                     },
                     _ => internal_error!("{:?} ended up in Equality code", self.lowlevel),
                 }
@@ -2290,6 +2619,84 @@ impl<'a> LowLevelCall<'a> {
         backend.code_builder.i32_and();
     }
 
+    /// Check that two values in stack memory contain the same bytes, using SIMD for 16-byte chunks
+    /// (Don't call "load arguments" or "load symbols" helpers before this, it'll just waste instructions)
+    fn eq_bytes(&self, backend: &mut WasmBackend<'a, '_>) {
+        let (location0, location1, size, alignment_bytes) = match (
+            backend.storage.get(&self.arguments[0]).to_owned(),
+            backend.storage.get(&self.arguments[1]).to_owned(),
+        ) {
+            (
+                StoredValue::StackMemory {
+                    location: location0,
+                    size,
+                    alignment_bytes,
+                    ..
+                },
+                StoredValue::StackMemory {
+                    location: location1,
+                    ..
+                },
+            ) => (location0, location1, size, alignment_bytes),
+            _ => internal_error!("Expected records in stack memory {:?}", self.arguments),
+        };
+        let (local0, offset0) = location0.local_and_offset(backend.storage.stack_frame_pointer);
+        let (local1, offset1) = location1.local_and_offset(backend.storage.stack_frame_pointer);
+        let align = Align::from(alignment_bytes);
+        let cb = &mut backend.code_builder;
+
+        let mut i = 0;
+        while size - i >= 16 {
+            cb.get_local(local0);
+            cb.v128_load(align, offset0 + i);
+            cb.get_local(local1);
+            cb.v128_load(align, offset1 + i);
+            cb.i8x16_eq();
+            cb.i8x16_all_true();
+            if i > 0 {
+                cb.i32_and();
+            }
+            i += 16;
+        }
+
+        // No padding means the size is a multiple of the alignment, so we can't have misaligned tails
+        if size - i >= 8 {
+            cb.get_local(local0);
+            cb.i64_load(align, offset0 + i);
+            cb.get_local(local1);
+            cb.i64_load(align, offset1 + i);
+            cb.i64_eq();
+            cb.i32_and();
+            i += 8;
+        }
+        if size - i >= 4 {
+            cb.get_local(local0);
+            cb.i32_load(align, offset0 + i);
+            cb.get_local(local1);
+            cb.i32_load(align, offset1 + i);
+            cb.i32_eq();
+            cb.i32_and();
+            i += 4;
+        }
+        if size - i >= 2 {
+            cb.get_local(local0);
+            cb.i32_load16_u(align, offset0 + i);
+            cb.get_local(local1);
+            cb.i32_load16_u(align, offset1 + i);
+            cb.i32_eq();
+            cb.i32_and();
+            i += 2;
+        }
+        if size - i >= 1 {
+            cb.get_local(local0);
+            cb.i32_load8_u(align, offset0 + i);
+            cb.get_local(local1);
+            cb.i32_load8_u(align, offset1 + i);
+            cb.i32_eq();
+            cb.i32_and();
+        }
+    }
+
     fn num_to_str(&self, backend: &mut WasmBackend<'a, '_>) {
         let arg_layout = backend.storage.symbol_layouts[&self.arguments[0]];
         match backend.layout_interner.runtime_representation(arg_layout) {
@@ -2314,6 +2721,25 @@ impl<'a> LowLevelCall<'a> {
     }
 }
 
+/// Can values of this layout be compared for equality by comparing their bytes?
+/// Floats can't (NaN != NaN, but 0.0 == -0.0), and neither can anything containing pointers or padding.
+fn is_bytewise_comparable<'a>(interner: &STLayoutInterner<'a>, layout: InLayout<'a>) -> bool {
+    match interner.get_repr(layout) {
+        LayoutRepr::Builtin(Builtin::Int(_) | Builtin::Bool | Builtin::Decimal) => true,
+        LayoutRepr::Struct(field_layouts) => {
+            let fields_size: u32 = field_layouts
+                .iter()
+                .map(|field| interner.stack_size(*field))
+                .sum();
+            fields_size == interner.stack_size(layout)
+                && field_layouts
+                    .iter()
+                    .all(|field| is_bytewise_comparable(interner, *field))
+        }
+        _ => false,
+    }
+}
+
 /// Helper for NumIsNan op
 fn num_is_nan(backend: &mut WasmBackend<'_, '_>, argument: Symbol) {
     use StoredValue::*;
@@ -2326,6 +2752,7 @@ fn num_is_nan(backend: &mut WasmBackend<'_, '_>, argument: Symbol) {
             match value_type {
                 // Integers are never NaN. Just return False.
                 ValueType::I32 | ValueType::I64 => backend.code_builder.i32_const(0),
+                ValueType::V128 => internal_error!("Roc numbers are never v128"),
                 ValueType::F32 => {
                     backend.code_builder.i32_reinterpret_f32();
                     backend.code_builder.i32_const(0x7f80_0000);
@@ -2389,6 +2816,7 @@ fn num_is_infinite(backend: &mut WasmBackend<'_, '_>, argument: Symbol) {
             match value_type {
                 // Integers are never infinite. Just return False.
                 ValueType::I32 | ValueType::I64 => backend.code_builder.i32_const(0),
+                ValueType::V128 => internal_error!("Roc numbers are never v128"),
                 ValueType::F32 => {
                     backend.code_builder.i32_reinterpret_f32();
                     backend.code_builder.i32_const(0x7fff_ffff);
@@ -2432,6 +2860,7 @@ fn num_is_finite(backend: &mut WasmBackend<'_, '_>, argument: Symbol) {
             match value_type {
                 // Integers are always finite. Just return True.
                 ValueType::I32 | ValueType::I64 => backend.code_builder.i32_const(1),
+                ValueType::V128 => internal_error!("Roc numbers are never v128"),
                 ValueType::F32 => {
                     backend.code_builder.i32_reinterpret_f32();
                     backend.code_builder.i32_const(0x7f80_0000);
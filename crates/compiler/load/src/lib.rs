@@ -17,13 +17,15 @@ const SKIP_SUBS_CACHE: bool = {
 };
 
 pub use roc_load_internal::docs;
+pub use roc_load_internal::emit_ir::{EmitIr, IrPhase};
 pub use roc_load_internal::file::{
-    ExecutionMode, ExpectMetadata, LoadConfig, LoadResult, LoadStart, LoadingProblem, Phase,
-    Threading,
+    ExecutionMode, ExpectMetadata, LoadConfig, LoadResult, LoadStart, LoadingProblem, OutputConfig,
+    Phase, Threading,
 };
 pub use roc_load_internal::module::{
     CheckedModule, EntryPoint, Expectations, ExposedToHost, LoadedModule, MonomorphizedModule,
 };
+pub use roc_load_internal::module_graph::{ModuleGraph, ModuleNode, PackageRef, PhaseTimes};
 pub use roc_solve::FunctionKind;
 
 #[allow(clippy::too_many_arguments)]
@@ -71,6 +73,7 @@ pub fn load_single_threaded<'a>(
         render,
         palette,
         exec_mode,
+        OutputConfig::default(),
         roc_cache_dir,
     )
 }
@@ -166,6 +169,42 @@ pub fn load_and_typecheck<'a>(
     }
 }
 
+/// Load a module and everything it imports, returning the import graph the loader tracked.
+///
+/// With `specialize`, apps are loaded all the way through specialization so that the graph
+/// includes those timings too. Other kinds of modules always stop after type checking.
+pub fn load_module_graph<'a>(
+    arena: &'a Bump,
+    filename: PathBuf,
+    roc_cache_dir: RocCacheDir<'_>,
+    mut load_config: LoadConfig,
+    specialize: bool,
+) -> Result<ModuleGraph, LoadingProblem<'a>> {
+    use LoadResult::*;
+
+    let load_start = LoadStart::from_path(
+        arena,
+        filename,
+        load_config.render,
+        roc_cache_dir,
+        load_config.palette,
+    )?;
+
+    load_config.output.build_module_graph = true;
+    load_config.exec_mode = if specialize && load_start.root_is_app() {
+        ExecutionMode::Executable
+    } else {
+        ExecutionMode::Check
+    };
+
+    let exposed_types = ExposedByModule::default();
+
+    match load(arena, load_start, exposed_types, roc_cache_dir, load_config)? {
+        Monomorphized(module) => Ok(module.module_graph.unwrap()),
+        TypeChecked(module) => Ok(module.module_graph.unwrap()),
+    }
+}
+
 #[allow(clippy::too_many_arguments)]
 pub fn load_and_typecheck_str<'a>(
     arena: &'a Bump,
@@ -0,0 +1,451 @@
+//! Writes the mono IR to files after the phases that transform it (`roc build --emit-ir`).
+//!
+//! Every proc goes to `<dir>/<phase>/<module>/<proc>.txt`, which holds all its specializations.
+//! Symbols are printed by name where they have one. Generated symbols are numbered in the order
+//! they come up: procs within their module, and everything else within the proc using it. So the
+//! files only change when the IR does, and can be diffed between versions of the compiler.
+
+use crate::file::LoadingProblem;
+use roc_collections::all::MutMap;
+use roc_module::symbol::{IdentIdsByModule, ModuleId, ModuleIds, Symbol};
+use roc_mono::ir::{PrintSymbols, Proc, ProcLayout};
+use roc_mono::layout::{InLayout, LayoutInterner, STLayoutInterner};
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use ven_pretty::BoxAllocator;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IrPhase {
+    /// Right after specialization
+    Specialization,
+    /// After tail recursion modulo cons
+    Trmc,
+    /// After inlining small procs
+    Inline,
+    /// After inserting reference counting
+    Refcount,
+    /// After specializing drops
+    DropSpecialization,
+    /// After inserting reset/reuse
+    ResetReuse,
+}
+
+impl IrPhase {
+    /// In the order they run
+    pub const ALL: [IrPhase; 6] = [
+        IrPhase::Specialization,
+        IrPhase::Trmc,
+        IrPhase::Inline,
+        IrPhase::Refcount,
+        IrPhase::DropSpecialization,
+        IrPhase::ResetReuse,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            IrPhase::Specialization => "specialization",
+            IrPhase::Trmc => "trmc",
+            IrPhase::Inline => "inline",
+            IrPhase::Refcount => "refcount",
+            IrPhase::DropSpecialization => "drop-specialization",
+            IrPhase::ResetReuse => "reset-reuse",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|phase| phase.name() == name)
+    }
+}
+
+/// Which phases to write the mono IR after, and where
+#[derive(Debug, Clone)]
+pub struct EmitIr {
+    pub phases: Vec<IrPhase>,
+    pub dir: PathBuf,
+}
+
+struct Specialization<'p, 'a> {
+    /// The argument and result layouts, as [Proc::to_pretty] leaves them out
+    signature: String,
+    proc: &'p Proc<'a>,
+}
+
+/// Write the IR of `procedures` to `<dir>/<phase>`, replacing what an earlier build wrote there
+pub(crate) fn write_ir<'a>(
+    emit_ir: &EmitIr,
+    phase: IrPhase,
+    interner: &STLayoutInterner<'a>,
+    module_ids: &ModuleIds,
+    all_ident_ids: &IdentIdsByModule,
+    procedures: &MutMap<(Symbol, ProcLayout<'a>), Proc<'a>>,
+) -> Result<(), LoadingProblem<'a>> {
+    let names = Names {
+        module_ids,
+        all_ident_ids,
+    };
+
+    let mut by_symbol: MutMap<Symbol, Vec<Specialization>> = MutMap::default();
+    for ((symbol, proc_layout), proc) in procedures {
+        by_symbol.entry(*symbol).or_default().push(Specialization {
+            signature: signature(interner, proc_layout),
+            proc,
+        });
+    }
+
+    let print = |specialization: &Specialization, name: &dyn Fn(Symbol) -> String| {
+        specialization
+            .proc
+            .to_pretty(interner, 200, PrintSymbols::Named(name))
+    };
+
+    // Order the specializations of a proc by what they look like without generated names
+    let mask = |symbol: Symbol| names.named(symbol).unwrap_or_else(|| "#".to_string());
+    for specializations in by_symbol.values_mut() {
+        specializations.sort_by_cached_key(|specialization| {
+            (
+                specialization.signature.clone(),
+                print(specialization, &mask),
+            )
+        });
+    }
+
+    // Named procs go first, then the generated ones in the order they are referred to
+    let mut proc_names: MutMap<Symbol, String> = MutMap::default();
+    let mut named: Vec<(String, Symbol)> = by_symbol
+        .keys()
+        .filter_map(|symbol| Some((names.named(*symbol)?, *symbol)))
+        .collect();
+    named.sort();
+
+    let mut queue: VecDeque<Symbol> = VecDeque::with_capacity(by_symbol.len());
+    for (name, symbol) in named {
+        proc_names.insert(symbol, name);
+        queue.push_back(symbol);
+    }
+
+    let mut unreferenced: Vec<Symbol> = by_symbol
+        .keys()
+        .filter(|symbol| !proc_names.contains_key(symbol))
+        .copied()
+        .collect();
+    unreferenced.sort_by_key(|symbol| u64::from(*symbol));
+
+    let mut generated_per_module: MutMap<ModuleId, usize> = MutMap::default();
+    let mut name_generated = |symbol: Symbol, proc_names: &mut MutMap<Symbol, String>| {
+        let count = generated_per_module.entry(symbol.module_id()).or_default();
+        *count += 1;
+
+        let name = format!("{}.#{count}", names.module_name(symbol.module_id()));
+        proc_names.insert(symbol, name);
+    };
+
+    let mut order = Vec::with_capacity(by_symbol.len());
+    loop {
+        while let Some(symbol) = queue.pop_front() {
+            order.push(symbol);
+
+            for specialization in &by_symbol[&symbol] {
+                let used = RefCell::new(Vec::new());
+                print(specialization, &|symbol| {
+                    used.borrow_mut().push(symbol);
+                    String::new()
+                });
+
+                for used in used.into_inner() {
+                    if by_symbol.contains_key(&used) && !proc_names.contains_key(&used) {
+                        name_generated(used, &mut proc_names);
+                        queue.push_back(used);
+                    }
+                }
+            }
+        }
+
+        match unreferenced
+            .iter()
+            .find(|symbol| !proc_names.contains_key(symbol))
+        {
+            Some(symbol) => {
+                name_generated(*symbol, &mut proc_names);
+                queue.push_back(*symbol);
+            }
+            None => break,
+        }
+    }
+
+    // Procs whose names only differ in characters that file names can't have share a file
+    let mut files: MutMap<PathBuf, String> = MutMap::default();
+    let phase_dir = emit_ir.dir.join(phase.name());
+
+    for symbol in order {
+        let module_name = names.module_name(symbol.module_id());
+        let proc_name = &proc_names[&symbol][module_name.len() + 1..];
+        let path = phase_dir
+            .join(file_name(&module_name))
+            .join(format!("{}.txt", file_name(proc_name)));
+        let contents = files.entry(path).or_default();
+
+        for specialization in &by_symbol[&symbol] {
+            let locals: RefCell<MutMap<Symbol, usize>> = RefCell::default();
+            let rename = |used: Symbol| {
+                if let Some(name) = proc_names.get(&used) {
+                    return name.clone();
+                }
+
+                if let Some(name) = names.named(used) {
+                    return name;
+                }
+
+                let mut locals = locals.borrow_mut();
+                let next = locals.len() + 1;
+                let index = *locals.entry(used).or_insert(next);
+
+                format!("{}.{index}", names.module_name(used.module_id()))
+            };
+
+            if !contents.is_empty() {
+                contents.push('\n');
+            }
+
+            contents.push_str("# ");
+            contents.push_str(&specialization.signature);
+            contents.push('\n');
+            contents.push_str(&print(specialization, &rename));
+        }
+    }
+
+    // Start over, so procs that are gone don't leave their files behind
+    remove_earlier_output(&phase_dir)?;
+
+    let mut written: Vec<&Path> = Vec::with_capacity(files.len());
+    for (path, contents) in files.iter() {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).map_err(file_problem(dir))?;
+        }
+
+        std::fs::write(path, contents).map_err(file_problem(path))?;
+        written.push(path.strip_prefix(&phase_dir).unwrap());
+    }
+
+    written.sort();
+    let manifest: Vec<_> = written
+        .iter()
+        .map(|path| path.to_string_lossy().into_owned())
+        .collect();
+    let manifest_path = phase_dir.join(MANIFEST);
+    std::fs::create_dir_all(&phase_dir).map_err(file_problem(&phase_dir))?;
+    std::fs::write(&manifest_path, manifest.join("\n")).map_err(file_problem(&manifest_path))?;
+
+    Ok(())
+}
+
+/// Lists the files [write_ir] wrote to a phase directory, one path relative to it per line
+const MANIFEST: &str = ".roc-emit-ir";
+
+/// Remove the files that an earlier build listed in the manifest of `phase_dir`. A directory
+/// with other files and no manifest is not ours, so we don't touch it.
+fn remove_earlier_output<'a>(phase_dir: &Path) -> Result<(), LoadingProblem<'a>> {
+    use std::io::ErrorKind;
+    use std::path::Component;
+
+    let manifest_path = phase_dir.join(MANIFEST);
+    let manifest = match std::fs::read_to_string(&manifest_path) {
+        Ok(manifest) => manifest,
+        Err(error) if error.kind() == ErrorKind::NotFound => {
+            let is_empty = match std::fs::read_dir(phase_dir) {
+                Ok(mut entries) => entries.next().is_none(),
+                Err(error) if error.kind() == ErrorKind::NotFound => true,
+                Err(error) => return Err(file_problem(phase_dir)(error)),
+            };
+
+            if is_empty {
+                return Ok(());
+            }
+
+            return Err(LoadingProblem::FormattedReport(format!(
+                "\n{} already has files that `roc build --emit-ir` did not write, so I will not \
+                write the IR there. Pick an --emit-dir that doesn't have a {} directory yet.\n",
+                phase_dir.display(),
+                phase_dir.file_name().unwrap_or_default().to_string_lossy(),
+            )));
+        }
+        Err(error) => return Err(file_problem(&manifest_path)(error)),
+    };
+
+    for line in manifest.lines() {
+        let relative = Path::new(line);
+        // We only ever write below the phase directory
+        if !relative
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)))
+        {
+            continue;
+        }
+
+        let path = phase_dir.join(relative);
+        match std::fs::remove_file(&path) {
+            Ok(()) => {}
+            Err(error) if error.kind() == ErrorKind::NotFound => {}
+            Err(error) => return Err(file_problem(&path)(error)),
+        }
+
+        // The module directory goes too, unless something else is in it
+        if let Some(dir) = path.parent() {
+            let _ = std::fs::remove_dir(dir);
+        }
+    }
+
+    std::fs::remove_file(&manifest_path).map_err(file_problem(&manifest_path))
+}
+
+fn file_problem<'a>(path: &Path) -> impl FnOnce(std::io::Error) -> LoadingProblem<'a> + '_ {
+    move |error| LoadingProblem::FileProblem {
+        filename: path.to_path_buf(),
+        error: error.kind(),
+    }
+}
+
+fn file_name(name: &str) -> String {
+    name.chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            _ => c,
+        })
+        .collect()
+}
+
+fn signature<'a>(interner: &STLayoutInterner<'a>, proc_layout: &ProcLayout<'a>) -> String {
+    let arguments = if proc_layout.arguments.is_empty() {
+        "{}".to_string()
+    } else {
+        let arguments: Vec<_> = proc_layout
+            .arguments
+            .iter()
+            .map(|argument| layout_to_string(interner, *argument))
+            .collect();
+
+        arguments.join(", ")
+    };
+
+    format!(
+        "{arguments} -> {}",
+        layout_to_string(interner, proc_layout.result)
+    )
+}
+
+fn layout_to_string<'a>(interner: &STLayoutInterner<'a>, layout: InLayout<'a>) -> String {
+    let allocator = BoxAllocator;
+    let mut w = std::vec::Vec::new();
+    interner
+        .to_doc_top::<_, ()>(layout, &allocator)
+        .1
+        .render(200, &mut w)
+        .unwrap();
+
+    String::from_utf8(w).unwrap()
+}
+
+struct Names<'b> {
+    module_ids: &'b ModuleIds,
+    all_ident_ids: &'b IdentIdsByModule,
+}
+
+impl<'b> Names<'b> {
+    fn module_name(&self, module_id: ModuleId) -> String {
+        match self.module_ids.get_name(module_id) {
+            Some(name) => name.as_str().to_string(),
+            None => format!("{module_id:?}"),
+        }
+    }
+
+    /// `<module>.<name>`, unless the compiler generated the symbol and named it by its index
+    fn named(&self, symbol: Symbol) -> Option<String> {
+        let ident_ids = self.all_ident_ids.get(&symbol.module_id())?;
+        let name = ident_ids.get_name(symbol.ident_id())?;
+
+        if name.is_empty() || name.bytes().all(|byte| byte.is_ascii_digit()) {
+            return None;
+        }
+
+        Some(format!("{}.{name}", self.module_name(symbol.module_id())))
+    }
+}
+
+#[cfg(test)]
+mod test_names {
+    use super::Names;
+    use roc_module::symbol::{IdentIdsByModule, ModuleIds, Symbol};
+
+    #[test]
+    fn named() {
+        let mut module_ids = ModuleIds::default();
+        let dep = module_ids.get_or_insert(&"Sub.Dep".into());
+        let mut all_ident_ids = IdentIdsByModule::default();
+        let ident_ids = all_ident_ids.get_or_insert(dep);
+        let str2 = Symbol::new(dep, ident_ids.add_str("str2"));
+        // compiler-generated symbols are named by their index
+        let generated = Symbol::new(dep, ident_ids.add_str("12"));
+        let names = Names {
+            module_ids: &module_ids,
+            all_ident_ids: &all_ident_ids,
+        };
+
+        assert_eq!(names.module_name(dep), "Sub.Dep");
+        assert_eq!(names.named(str2).as_deref(), Some("Sub.Dep.str2"));
+        assert_eq!(names.named(generated), None);
+    }
+}
+
+#[cfg(test)]
+mod test_remove_earlier_output {
+    use super::{remove_earlier_output, MANIFEST};
+    use roc_test_utils_dir::TmpDir;
+    use std::path::Path;
+
+    fn write(path: &Path, contents: &str) {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, contents).unwrap();
+    }
+
+    #[test]
+    fn missing_or_empty_dir() {
+        let dir = TmpDir::new("tmp/emit_ir_missing_or_empty_dir");
+
+        assert!(remove_earlier_output(&dir.path().join("refcount")).is_ok());
+        assert!(remove_earlier_output(dir.path()).is_ok());
+    }
+
+    #[test]
+    fn removes_only_listed_files() {
+        let dir = TmpDir::new("tmp/emit_ir_removes_only_listed_files");
+        let phase_dir = dir.path().join("refcount");
+
+        write(&phase_dir.join("Dep").join("str.txt"), "ir");
+        write(&phase_dir.join("Main").join("main.txt"), "ir");
+        write(&phase_dir.join("Main").join("notes.txt"), "mine");
+        write(&dir.path().join("outside.txt"), "mine");
+        write(
+            &phase_dir.join(MANIFEST),
+            "Dep/str.txt\nMain/main.txt\n../outside.txt",
+        );
+
+        assert!(remove_earlier_output(&phase_dir).is_ok());
+
+        assert!(!phase_dir.join("Dep").exists());
+        assert!(!phase_dir.join("Main").join("main.txt").exists());
+        assert!(!phase_dir.join(MANIFEST).exists());
+        assert!(phase_dir.join("Main").join("notes.txt").exists());
+        assert!(dir.path().join("outside.txt").exists());
+    }
+
+    #[test]
+    fn refuses_dir_without_manifest() {
+        let dir = TmpDir::new("tmp/emit_ir_refuses_dir_without_manifest");
+        let phase_dir = dir.path().join("refcount");
+        let notes = phase_dir.join("notes.txt");
+        write(&notes, "mine");
+
+        assert!(remove_earlier_output(&phase_dir).is_err());
+        assert_eq!(std::fs::read_to_string(notes).unwrap(), "mine");
+    }
+}
@@ -1,12 +1,14 @@
 #![allow(clippy::too_many_arguments)]
 
 use crate::docs::ModuleDocumentation;
+use crate::emit_ir::{write_ir, EmitIr, IrPhase};
 use crate::module::{
     CheckedModule, ConstrainedModule, EntryPoint, Expectations, ExposedToHost,
     FoundSpecializationsModule, LateSpecializationsModule, LoadedModule, ModuleHeader,
     ModuleTiming, MonomorphizedModule, ParsedModule, ToplevelExpects, TypeCheckedModule,
 };
 use crate::module_cache::ModuleCache;
+use crate::module_graph::ModuleGraph;
 use bumpalo::{collections::CollectIn, Bump};
 use crossbeam::channel::{bounded, Sender};
 use crossbeam::deque::{Injector, Stealer, Worker};
@@ -37,6 +39,7 @@ use roc_module::symbol::{
     IdentIds, IdentIdsByModule, Interns, ModuleId, ModuleIds, PQModuleName, PackageModuleIds,
     PackageQualified, Symbol,
 };
+use roc_mono::inline::InlineLevel;
 use roc_mono::ir::{
     CapturedSymbols, ExternalSpecializations, GlueLayouts, HostExposedLambdaSets, PartialProc,
     Proc, ProcLayout, Procs, ProcsBase, UpdateModeIds, UsageTrackingMap,
@@ -63,6 +66,7 @@ use roc_solve::module::{extract_module_owned_implementations, SolveConfig, Solve
 use roc_solve::FunctionKind;
 use roc_solve_problem::TypeError;
 use roc_target::Target;
+use roc_tracing::profile;
 use roc_types::subs::{CopiedImport, ExposedTypesStorageSubs, Subs, VarStore, Variable};
 use roc_types::types::{Alias, Types};
 use std::collections::hash_map::Entry::{Occupied, Vacant};
@@ -108,6 +112,21 @@ pub struct LoadConfig {
     pub threading: Threading,
     pub exec_mode: ExecutionMode,
     pub function_kind: FunctionKind,
+    pub output: OutputConfig,
+}
+
+/// How the procs get specialized, and what gets built besides them
+#[derive(Debug, Clone, Default)]
+pub struct OutputConfig {
+    /// Write the mono IR to files after these phases (`roc build --emit-ir`)
+    pub emit_ir: Option<EmitIr>,
+    /// Decides which procs get inlined (see [roc_mono::inline])
+    pub inline_level: InlineLevel,
+    /// Assemble the [ModuleGraph] of the loaded modules (for `roc deps`)
+    pub build_module_graph: bool,
+    /// Mark which statements of the procs come from which definitions (see
+    /// [roc_mono::ir::Stmt::SourceRegion]), for debug info and perf hints
+    pub mark_source_regions: bool,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -415,6 +434,7 @@ fn start_phase<'a>(
                     derived_module,
                     expectations,
                     build_expects,
+                    mark_source_regions: state.output.mark_source_regions,
                 }
             }
             Phase::MakeSpecializations => {
@@ -534,6 +554,7 @@ fn start_phase<'a>(
                     exposed_by_module: state.exposed_types.clone(),
                     derived_module,
                     expectations,
+                    mark_source_regions: state.output.mark_source_regions,
                 }
             }
         }
@@ -596,6 +617,7 @@ enum Msg<'a> {
         solved_subs: Solved<Subs>,
         module_timing: ModuleTiming,
         toplevel_expects: ToplevelExpects,
+        definition_regions: VecMap<Symbol, Region>,
         expectations: Option<Expectations>,
     },
     MadeSpecializations {
@@ -703,6 +725,7 @@ struct State<'a> {
     pub procedures: MutMap<(Symbol, ProcLayout<'a>), Proc<'a>>,
     pub host_exposed_lambda_sets: HostExposedLambdaSets<'a>,
     pub toplevel_expects: MutMap<ModuleId, ToplevelExpects>,
+    pub definition_regions: MutMap<Symbol, Region>,
     pub exposed_to_host: ExposedToHost,
 
     /// This is the "final" list of IdentIds, after canonicalization and constraint gen
@@ -732,6 +755,7 @@ struct State<'a> {
     pub render: RenderTarget,
     pub palette: Palette,
     pub exec_mode: ExecutionMode,
+    pub output: OutputConfig,
 
     /// All abilities across all modules.
     pub world_abilities: WorldAbilities,
@@ -765,6 +789,7 @@ impl<'a> State<'a> {
         palette: Palette,
         number_of_workers: usize,
         exec_mode: ExecutionMode,
+        output: OutputConfig,
     ) -> Self {
         let arc_shorthands = Arc::new(Mutex::new(MutMap::default()));
         let cache_dir = roc_packaging::cache::roc_cache_dir();
@@ -785,6 +810,7 @@ impl<'a> State<'a> {
             procedures: MutMap::default(),
             host_exposed_lambda_sets: std::vec::Vec::new(),
             toplevel_expects: MutMap::default(),
+            definition_regions: MutMap::default(),
             exposed_to_host: ExposedToHost::default(),
             exposed_modules: &[],
             exposed_types,
@@ -801,6 +827,7 @@ impl<'a> State<'a> {
             render,
             palette,
             exec_mode,
+            output,
             make_specializations_pass: MakeSpecializationsPass::Pass(1),
             world_abilities: Default::default(),
             layout_interner: GlobalLayoutInterner::with_capacity(128, target),
@@ -911,6 +938,7 @@ enum BuildTask<'a> {
         derived_module: SharedDerivedModule,
         expectations: Option<Expectations>,
         build_expects: bool,
+        mark_source_regions: bool,
     },
     MakeSpecializations {
         module_id: ModuleId,
@@ -924,6 +952,7 @@ enum BuildTask<'a> {
         world_abilities: WorldAbilities,
         derived_module: SharedDerivedModule,
         expectations: Option<Expectations>,
+        mark_source_regions: bool,
     },
 }
 
@@ -1069,6 +1098,7 @@ pub fn load_and_typecheck_str<'a>(
         threading,
         exec_mode: ExecutionMode::Check,
         function_kind,
+        output: OutputConfig::default(),
     };
 
     match load(
@@ -1160,6 +1190,11 @@ impl<'a> LoadStart<'a> {
         })
     }
 
+    /// Whether the root module is an app that names its platform, so it can be specialized.
+    pub fn root_is_app(&self) -> bool {
+        self.opt_platform_shorthand.is_some()
+    }
+
     pub fn from_str(
         arena: &'a Bump,
         filename: PathBuf,
@@ -1338,6 +1373,7 @@ pub fn load<'a>(
             load_config.render,
             load_config.palette,
             load_config.exec_mode,
+            load_config.output,
             roc_cache_dir,
         ),
         Threads::Many(threads) => load_multi_threaded(
@@ -1351,6 +1387,7 @@ pub fn load<'a>(
             load_config.palette,
             threads,
             load_config.exec_mode,
+            load_config.output,
             roc_cache_dir,
         ),
     }
@@ -1367,6 +1404,7 @@ pub fn load_single_threaded<'a>(
     render: RenderTarget,
     palette: Palette,
     exec_mode: ExecutionMode,
+    output: OutputConfig,
     roc_cache_dir: RocCacheDir<'_>,
 ) -> Result<LoadResult<'a>, LoadingProblem<'a>> {
     let LoadStart {
@@ -1401,6 +1439,7 @@ pub fn load_single_threaded<'a>(
         palette,
         number_of_workers,
         exec_mode,
+        output,
     );
 
     // We'll add tasks to this, and then worker threads will take tasks from it.
@@ -1722,6 +1761,7 @@ fn load_multi_threaded<'a>(
     palette: Palette,
     available_threads: usize,
     exec_mode: ExecutionMode,
+    output: OutputConfig,
     roc_cache_dir: RocCacheDir<'_>,
 ) -> Result<LoadResult<'a>, LoadingProblem<'a>> {
     let LoadStart {
@@ -1771,6 +1811,7 @@ fn load_multi_threaded<'a>(
         palette,
         num_workers,
         exec_mode,
+        output,
     );
 
     // an arena for every worker, stored in an arena-allocated bumpalo vec to make the lifetimes work
@@ -1809,7 +1850,7 @@ fn load_multi_threaded<'a>(
             let mut worker_listeners =
                 bumpalo::collections::Vec::with_capacity_in(num_workers, arena);
 
-            for worker_arena in it {
+            for (worker_index, worker_arena) in it.enumerate() {
                 let msg_tx = msg_tx.clone();
                 let worker = worker_queues.pop().unwrap();
 
@@ -1825,6 +1866,7 @@ fn load_multi_threaded<'a>(
                 // Record this thread's handle so the main thread can join it later.
                 let res_join_handle = thread_scope
                     .builder()
+                    .name(format!("load worker {worker_index}"))
                     .stack_size(EXPANDED_STACK_SIZE)
                     .spawn(move |_| {
                         // will process messages until we run out
@@ -2159,6 +2201,28 @@ macro_rules! debug_print_ir {
     };
 }
 
+macro_rules! emit_ir {
+    ($state:expr, $interner:expr, $phase:expr) => {
+        if let Some(emit_ir) = &$state.output.emit_ir {
+            if emit_ir.phases.contains(&$phase) {
+                let module_ids = $state.arc_modules.lock().clone().into_module_ids();
+                let mut all_ident_ids = $state.constrained_ident_ids.clone();
+                let derived_ident_ids = $state.derived_module.lock().unwrap().ident_ids().clone();
+                all_ident_ids.insert(ModuleId::DERIVED_SYNTH, derived_ident_ids);
+
+                write_ir(
+                    emit_ir,
+                    $phase,
+                    $interner,
+                    &module_ids,
+                    &all_ident_ids,
+                    &$state.procedures,
+                )?;
+            }
+        }
+    };
+}
+
 macro_rules! debug_check_ir {
     ($state:expr, $arena:expr, $interner:expr, $flag:path) => {
         dbg_do!($flag, {
@@ -2732,6 +2796,7 @@ fn update<'a>(
             layout_cache,
             module_timing,
             toplevel_expects,
+            definition_regions,
             expectations,
         } => {
             log!("found specializations for {:?}", module_id);
@@ -2742,6 +2807,8 @@ fn update<'a>(
                 state.toplevel_expects.insert(module_id, toplevel_expects);
             }
 
+            state.definition_regions.extend(definition_regions);
+
             state
                 .module_cache
                 .top_level_thunks
@@ -2913,9 +2980,12 @@ fn update<'a>(
 
                     debug_print_ir!(state, &layout_interner, ROC_PRINT_IR_AFTER_SPECIALIZATION);
                     debug_check_ir!(state, arena, layout_interner, ROC_CHECK_MONO_IR);
+                    emit_ir!(state, &layout_interner, IrPhase::Specialization);
 
                     let ident_ids = state.constrained_ident_ids.get_mut(&module_id).unwrap();
 
+                    let trmc_span = profile::span("mono", "tail recursion modulo cons");
+
                     roc_mono::tail_recursion::apply_trmc(
                         arena,
                         &mut layout_interner,
@@ -2924,7 +2994,50 @@ fn update<'a>(
                         &mut state.procedures,
                     );
 
+                    drop(trmc_span);
+
                     debug_print_ir!(state, &layout_interner, ROC_PRINT_IR_AFTER_TRMC);
+                    emit_ir!(state, &layout_interner, IrPhase::Trmc);
+
+                    let inline_span = profile::span("mono", "inline small procs");
+
+                    // Procs the host or `roc test` may call; the rest only live if these reach them
+                    let exposed = &state.exposed_to_host;
+                    let roots: std::vec::Vec<Symbol> = exposed
+                        .top_level_values
+                        .keys()
+                        .chain(exposed.closure_types.iter())
+                        .chain(exposed.lambda_sets.iter().map(|(symbol, _)| symbol))
+                        .chain(exposed.getters.iter())
+                        .copied()
+                        .chain(
+                            state
+                                .host_exposed_lambda_sets
+                                .iter()
+                                .flat_map(|(name, symbol, _)| [name.name(), *symbol]),
+                        )
+                        .chain(state.toplevel_expects.values().flat_map(|expects| {
+                            expects.pure.keys().chain(expects.fx.keys()).copied()
+                        }))
+                        .collect();
+
+                    let ident_ids = state.constrained_ident_ids.get_mut(&module_id).unwrap();
+
+                    roc_mono::inline::inline_small_procs(
+                        arena,
+                        module_id,
+                        ident_ids,
+                        &mut update_mode_ids,
+                        &mut state.procedures,
+                        state.output.inline_level,
+                        roots,
+                    );
+
+                    drop(inline_span);
+
+                    emit_ir!(state, &layout_interner, IrPhase::Inline);
+
+                    let inc_dec_span = profile::span("mono", "insert refcounting");
 
                     inc_dec::insert_inc_dec_operations(
                         arena,
@@ -2932,7 +3045,14 @@ fn update<'a>(
                         &mut state.procedures,
                     );
 
+                    drop(inc_dec_span);
+
                     debug_print_ir!(state, &layout_interner, ROC_PRINT_IR_AFTER_REFCOUNT);
+                    emit_ir!(state, &layout_interner, IrPhase::Refcount);
+
+                    let drops_span = profile::span("mono", "specialize drops");
+
+                    let ident_ids = state.constrained_ident_ids.get_mut(&module_id).unwrap();
 
                     drop_specialization::specialize_drops(
                         arena,
@@ -2942,11 +3062,18 @@ fn update<'a>(
                         &mut state.procedures,
                     );
 
+                    drop(drops_span);
+
                     debug_print_ir!(
                         state,
                         &layout_interner,
                         ROC_PRINT_IR_AFTER_DROP_SPECIALIZATION
                     );
+                    emit_ir!(state, &layout_interner, IrPhase::DropSpecialization);
+
+                    let ident_ids = state.constrained_ident_ids.get_mut(&module_id).unwrap();
+
+                    let reset_reuse_span = profile::span("mono", "insert reset/reuse");
 
                     reset_reuse::insert_reset_reuse_operations(
                         arena,
@@ -2958,7 +3085,10 @@ fn update<'a>(
                         &mut state.procedures,
                     );
 
+                    drop(reset_reuse_span);
+
                     debug_print_ir!(state, &layout_interner, ROC_PRINT_IR_AFTER_RESET_REUSE);
+                    emit_ir!(state, &layout_interner, IrPhase::ResetReuse);
 
                     // This is not safe with the new non-recursive RC updates that we do for tag unions
                     //
@@ -3128,6 +3258,7 @@ fn finish_specialization<'a>(
             roc_types::types::get_type_clone_count()
         );
     }
+    let module_graph = module_graph_from_state(&state);
     let module_ids = Arc::try_unwrap(state.arc_modules)
         .unwrap_or_else(|_| panic!("There were still outstanding Arc references to module_ids"))
         .into_inner()
@@ -3217,6 +3348,7 @@ fn finish_specialization<'a>(
 
     let State {
         toplevel_expects,
+        definition_regions,
         procedures,
         host_exposed_lambda_sets,
         module_cache,
@@ -3259,11 +3391,30 @@ fn finish_specialization<'a>(
         sources,
         timings: state.timings,
         toplevel_expects,
+        definition_regions,
         glue_layouts: GlueLayouts { getters: vec![] },
         uses_prebuilt_platform,
+        module_graph,
     })
 }
 
+/// Only kept when asked for, because it copies all imports and counts the lines of every module.
+fn module_graph_from_state(state: &State) -> Option<ModuleGraph> {
+    label_profile_modules(&state.arc_modules.lock());
+
+    if !state.output.build_module_graph {
+        return None;
+    }
+
+    Some(ModuleGraph::new(
+        state.root_id,
+        &state.arc_modules.lock(),
+        &state.module_cache.imports,
+        &state.module_cache.sources,
+        &state.timings,
+    ))
+}
+
 fn proc_layout_for<'a>(
     mut proc_symbols: impl Iterator<Item = (Symbol, ProcLayout<'a>)>,
     symbol: Symbol,
@@ -3296,6 +3447,7 @@ fn finish(
     //
     #[cfg(debug_assertions)] checkmate: Option<roc_checkmate::Collector>,
 ) -> LoadedModule {
+    let module_graph = module_graph_from_state(&state);
     let module_ids = Arc::try_unwrap(state.arc_modules)
         .unwrap_or_else(|_| panic!("There were still outstanding Arc references to module_ids"))
         .into_inner()
@@ -3353,6 +3505,7 @@ fn finish(
         exposed_imports: state.module_cache.exposed_imports,
         imports: state.module_cache.imports,
         exposes: state.module_cache.exposes,
+        module_graph,
     }
 }
 
@@ -5409,6 +5562,7 @@ fn make_specializations<'a>(
     exposed_by_module: &ExposedByModule,
     derived_module: SharedDerivedModule,
     mut expectations: Option<Expectations>,
+    mark_source_regions: bool,
 ) -> Msg<'a> {
     let make_specializations_start = Instant::now();
     let mut update_mode_ids = UpdateModeIds::new();
@@ -5427,6 +5581,7 @@ fn make_specializations<'a>(
         exposed_by_module,
         derived_module: &derived_module,
         struct_indexing: UsageTrackingMap::default(),
+        mark_source_regions,
     };
 
     let mut procs = Procs::new_in(arena);
@@ -5497,11 +5652,13 @@ fn build_pending_specializations<'a>(
     derived_module: SharedDerivedModule,
     mut expectations: Option<Expectations>,
     build_expects: bool,
+    mark_source_regions: bool,
 ) -> Msg<'a> {
     let find_specializations_start = Instant::now();
 
     let mut module_thunks = bumpalo::collections::Vec::new_in(arena);
     let mut toplevel_expects = ToplevelExpects::default();
+    let mut definition_regions = VecMap::with_capacity(declarations.len());
 
     let mut procs_base = ProcsBase {
         partial_procs: BumpMap::default(),
@@ -5530,6 +5687,7 @@ fn build_pending_specializations<'a>(
         exposed_by_module,
         derived_module: &derived_module,
         struct_indexing: UsageTrackingMap::default(),
+        mark_source_regions,
     };
 
     let layout_cache_snapshot = layout_cache.snapshot();
@@ -5549,6 +5707,8 @@ fn build_pending_specializations<'a>(
         let symbol = declarations.symbols[index].value;
         let expr_var = declarations.variables[index];
 
+        definition_regions.insert(symbol, declarations.symbols[index].region);
+
         let is_host_exposed = exposed_to_host.top_level_values.contains_key(&symbol);
 
         let body = declarations.expressions[index].clone();
@@ -5927,6 +6087,7 @@ fn build_pending_specializations<'a>(
         procs_base,
         module_timing,
         toplevel_expects,
+        definition_regions,
         expectations,
     }
 }
@@ -6011,6 +6172,8 @@ fn load_derived_partial_procs<'a>(
             exposed_by_module,
             derived_module,
             struct_indexing: UsageTrackingMap::default(),
+            // Derived implementations have no source
+            mark_source_regions: false,
         };
 
         let partial_proc = match derived_expr {
@@ -6078,6 +6241,8 @@ fn run_task<'a>(
 ) -> Result<(), LoadingProblem<'a>> {
     use BuildTask::*;
 
+    let _span = task_span(&task);
+
     let msg = match task {
         LoadModule {
             module_name,
@@ -6174,6 +6339,7 @@ fn run_task<'a>(
             derived_module,
             expectations,
             build_expects,
+            mark_source_regions,
         } => Ok(build_pending_specializations(
             arena,
             solved_subs,
@@ -6190,6 +6356,7 @@ fn run_task<'a>(
             derived_module,
             expectations,
             build_expects,
+            mark_source_regions,
         )),
         MakeSpecializations {
             module_id,
@@ -6203,6 +6370,7 @@ fn run_task<'a>(
             exposed_by_module,
             derived_module,
             expectations,
+            mark_source_regions,
         } => Ok(make_specializations(
             arena,
             module_id,
@@ -6217,6 +6385,7 @@ fn run_task<'a>(
             &exposed_by_module,
             derived_module,
             expectations,
+            mark_source_regions,
         )),
     }?;
 
@@ -6227,6 +6396,53 @@ fn run_task<'a>(
     Ok(())
 }
 
+/// The span for a task in the `--time-trace` profile, labeled with the module it works on.
+fn task_span(task: &BuildTask) -> profile::Span {
+    if !profile::is_enabled() {
+        return profile::span("load", "");
+    }
+
+    let (name, module_id) = match task {
+        BuildTask::LoadModule { module_name, .. } => {
+            return profile::span("load", "load header").label(qualified_name(module_name));
+        }
+        BuildTask::Parse { header, .. } => ("parse", header.module_id),
+        BuildTask::CanonicalizeAndConstrain { parsed, .. } => ("canonicalize", parsed.module_id),
+        BuildTask::Solve { module, .. } => ("solve", module.module_id),
+        BuildTask::BuildPendingSpecializations { module_id, .. } => {
+            ("find specializations", *module_id)
+        }
+        BuildTask::MakeSpecializations { module_id, .. } => ("make specializations", *module_id),
+    };
+
+    // The workers can't look up module names, so these labels are replaced once loading is
+    // done; see `label_profile_modules`.
+    profile::span("load", name).label(profile_module_label(module_id))
+}
+
+fn profile_module_label(module_id: ModuleId) -> String {
+    format!("#{}", module_id.to_zero_indexed())
+}
+
+/// The name as it would be written in an import, e.g. `pf.Stdout`
+fn qualified_name(module_name: &PQModuleName) -> String {
+    match module_name {
+        PQModuleName::Unqualified(name) => name.as_str().to_string(),
+        PQModuleName::Qualified(shorthand, name) => format!("{shorthand}.{}", name.as_str()),
+    }
+}
+
+/// Give the spans of [task_span] readable module names in the `--time-trace` profile.
+fn label_profile_modules(module_ids: &PackageModuleIds) {
+    if profile::is_enabled() {
+        profile::set_labels(module_ids.available_modules().filter_map(|module_name| {
+            let module_id = module_ids.get_id(module_name)?;
+
+            Some((profile_module_label(module_id), qualified_name(module_name)))
+        }));
+    }
+}
+
 fn to_import_cycle_report(
     module_ids: ModuleIds,
     all_ident_ids: IdentIdsByModule,
@@ -5,9 +5,11 @@
 
 use roc_module::symbol::ModuleId;
 pub mod docs;
+pub mod emit_ir;
 pub mod file;
 pub mod module;
 mod module_cache;
+pub mod module_graph;
 mod work;
 
 #[cfg(target_family = "wasm")]
@@ -1,4 +1,5 @@
 use crate::docs::ModuleDocumentation;
+use crate::module_graph::ModuleGraph;
 use roc_can::constraint::{Constraint as ConstraintSoa, Constraints};
 use roc_can::expr::{DbgLookup, ExpectLookup};
 use roc_can::{
@@ -52,6 +53,8 @@ pub struct LoadedModule {
     pub imports: MutMap<ModuleId, MutSet<ModuleId>>,
     pub exposed_imports: MutMap<ModuleId, MutMap<Symbol, Region>>,
     pub exposes: MutMap<ModuleId, Vec<(Symbol, Variable)>>,
+    /// Only if [crate::file::LoadConfig::build_module_graph] was set
+    pub module_graph: Option<ModuleGraph>,
 }
 
 impl LoadedModule {
@@ -181,6 +184,8 @@ pub struct MonomorphizedModule<'a> {
     pub procedures: MutMap<(Symbol, ProcLayout<'a>), Proc<'a>>,
     pub host_exposed_lambda_sets: HostExposedLambdaSets<'a>,
     pub toplevel_expects: MutMap<ModuleId, ToplevelExpects>,
+    /// Where each top-level definition is in its module's source code
+    pub definition_regions: MutMap<Symbol, Region>,
     pub entry_point: EntryPoint<'a>,
     pub exposed_to_host: ExposedToHost,
     pub sources: MutMap<ModuleId, (PathBuf, Box<str>)>,
@@ -188,6 +193,8 @@ pub struct MonomorphizedModule<'a> {
     pub expectations: VecMap<ModuleId, Expectations>,
     pub uses_prebuilt_platform: bool,
     pub glue_layouts: GlueLayouts<'a>,
+    /// Only if [crate::file::LoadConfig::build_module_graph] was set
+    pub module_graph: Option<ModuleGraph>,
 }
 
 #[derive(Debug)]
@@ -0,0 +1,284 @@
+//! The module and package import graph, as tracked by the loader.
+//!
+//! This is what `roc deps` prints. It is assembled from the imports the loader records while
+//! parsing headers, so it reflects exactly the modules that took part in a build.
+use crate::module::ModuleTiming;
+use roc_collections::{MutMap, MutSet, ReferenceMatrix, VecMap, VecSet};
+use roc_module::ident::ModuleName;
+use roc_module::symbol::{ModuleId, PQModuleName, PackageModuleIds};
+use std::path::{Path, PathBuf};
+
+#[cfg(target_family = "wasm")]
+use crate::wasm_instant::Duration;
+#[cfg(not(target_family = "wasm"))]
+use std::time::Duration;
+
+/// Time spent on a single module, grouped by compiler phase.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PhaseTimes {
+    /// Reading the file, and parsing its header and body
+    pub parse: Duration,
+    /// Canonicalization and constraint generation
+    pub canonicalize: Duration,
+    pub solve: Duration,
+    /// Finding and making specializations, summed over all passes
+    pub specialize: Duration,
+    pub total: Duration,
+}
+
+impl From<&ModuleTiming> for PhaseTimes {
+    fn from(timing: &ModuleTiming) -> Self {
+        let make_specializations: Duration = timing.make_specializations.iter().sum();
+
+        PhaseTimes {
+            parse: timing.read_roc_file + timing.parse_header + timing.parse_body,
+            canonicalize: timing.canonicalize + timing.constrain,
+            solve: timing.solve,
+            specialize: timing.find_specializations + make_specializations,
+            total: timing.total(),
+        }
+    }
+}
+
+/// Which package a module belongs to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum PackageRef<'g> {
+    /// The package of the root module, i.e. the modules that are imported without a shorthand
+    Root,
+    Builtins,
+    /// A package imported through a shorthand, e.g. `pf` in `import pf.Stdout`
+    Shorthand(&'g str),
+}
+
+impl<'g> PackageRef<'g> {
+    pub fn as_str(&self) -> &'g str {
+        match self {
+            PackageRef::Root => "(root)",
+            PackageRef::Builtins => "(builtins)",
+            PackageRef::Shorthand(shorthand) => shorthand,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ModuleNode {
+    pub module_id: ModuleId,
+    /// The shorthand of the package this module was loaded from, if it is not in the root package
+    pub package: Option<String>,
+    pub name: String,
+    pub path: Option<PathBuf>,
+    pub line_count: Option<usize>,
+    /// Only available for modules that went through the loader (not for cached builtins)
+    pub phase_times: Option<PhaseTimes>,
+}
+
+impl ModuleNode {
+    pub fn is_builtin(&self) -> bool {
+        self.module_id.is_builtin()
+    }
+
+    pub fn package_ref(&self) -> PackageRef<'_> {
+        match &self.package {
+            Some(shorthand) => PackageRef::Shorthand(shorthand),
+            None if self.is_builtin() => PackageRef::Builtins,
+            None => PackageRef::Root,
+        }
+    }
+
+    /// The name as it would be written in an import, e.g. `pf.Stdout`
+    pub fn qualified_name(&self) -> String {
+        match &self.package {
+            Some(shorthand) => format!("{shorthand}.{}", self.name),
+            None => self.name.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ModuleGraph {
+    root: ModuleId,
+    nodes: MutMap<ModuleId, ModuleNode>,
+    imports: MutMap<ModuleId, MutSet<ModuleId>>,
+}
+
+impl ModuleGraph {
+    pub(crate) fn new(
+        root: ModuleId,
+        module_ids: &PackageModuleIds,
+        imports: &MutMap<ModuleId, MutSet<ModuleId>>,
+        sources: &MutMap<ModuleId, (PathBuf, &str)>,
+        timings: &MutMap<ModuleId, ModuleTiming>,
+    ) -> Self {
+        let mut nodes = MutMap::default();
+
+        let all_modules = imports
+            .iter()
+            .flat_map(|(module_id, deps)| std::iter::once(module_id).chain(deps.iter()))
+            .chain(std::iter::once(&root));
+
+        for &module_id in all_modules {
+            if nodes.contains_key(&module_id) {
+                continue;
+            }
+
+            let (package, name) = match module_ids.get_name(module_id) {
+                Some(PQModuleName::Unqualified(name)) => (None, name.as_str().to_string()),
+                Some(PQModuleName::Qualified(shorthand, name)) => {
+                    (Some(shorthand.to_string()), name.as_str().to_string())
+                }
+                None => (None, format!("{module_id:?}")),
+            };
+
+            let (path, line_count) = match sources.get(&module_id) {
+                Some((path, src)) => (Some(path.clone()), Some(src.lines().count())),
+                None => (None, None),
+            };
+
+            // App modules don't have a user-facing name, so fall back on their filename.
+            let is_app = name.is_empty() || name == ModuleName::APP;
+            let name = match (is_app, &path) {
+                (true, Some(path)) => file_stem(path).unwrap_or(name),
+                _ => name,
+            };
+
+            nodes.insert(
+                module_id,
+                ModuleNode {
+                    module_id,
+                    package,
+                    name,
+                    path,
+                    line_count,
+                    phase_times: timings.get(&module_id).map(PhaseTimes::from),
+                },
+            );
+        }
+
+        Self {
+            root,
+            nodes,
+            imports: imports.clone(),
+        }
+    }
+
+    pub fn root(&self) -> &ModuleNode {
+        &self.nodes[&self.root]
+    }
+
+    pub fn get(&self, module_id: ModuleId) -> Option<&ModuleNode> {
+        self.nodes.get(&module_id)
+    }
+
+    /// All modules in the graph, with the root module first and the others sorted by name.
+    pub fn modules(&self, include_builtins: bool) -> Vec<&ModuleNode> {
+        let mut modules: Vec<&ModuleNode> = self
+            .nodes
+            .values()
+            .filter(|node| include_builtins || !node.is_builtin())
+            .collect();
+
+        modules.sort_by_cached_key(|node| (node.module_id != self.root, node.qualified_name()));
+
+        modules
+    }
+
+    /// The direct imports of a module, sorted by name.
+    pub fn imports_of(&self, module_id: ModuleId, include_builtins: bool) -> Vec<&ModuleNode> {
+        let mut imports: Vec<&ModuleNode> = self
+            .imports
+            .get(&module_id)
+            .into_iter()
+            .flatten()
+            .filter_map(|dep| self.nodes.get(dep))
+            .filter(|node| include_builtins || !node.is_builtin())
+            .collect();
+
+        imports.sort_by_cached_key(|node| node.qualified_name());
+
+        imports
+    }
+
+    /// Every set of modules that import each other, directly or transitively.
+    ///
+    /// The loader refuses to continue when it finds an import cycle between modules, so this is
+    /// only non-empty for graphs that include the builtins (which are allowed to import each other).
+    pub fn module_cycles(&self, include_builtins: bool) -> Vec<Vec<ModuleId>> {
+        let modules: Vec<ModuleId> = self
+            .modules(include_builtins)
+            .iter()
+            .map(|node| node.module_id)
+            .collect();
+
+        cycles(&modules, |module_id| {
+            self.imports_of(module_id, include_builtins)
+                .into_iter()
+                .map(|node| node.module_id)
+                .collect()
+        })
+    }
+
+    /// The import graph between packages, derived from the imports of their modules.
+    /// Imports within a single package are not included.
+    pub fn package_imports(
+        &self,
+        include_builtins: bool,
+    ) -> VecMap<PackageRef<'_>, VecSet<PackageRef<'_>>> {
+        let mut packages: VecMap<PackageRef, VecSet<PackageRef>> = VecMap::default();
+
+        for node in self.modules(include_builtins) {
+            let package = node.package_ref();
+            let deps = packages.get_or_insert(package, VecSet::default);
+
+            for dep in self.imports_of(node.module_id, include_builtins) {
+                if dep.package_ref() != package {
+                    deps.insert(dep.package_ref());
+                }
+            }
+        }
+
+        packages
+    }
+
+    /// Every set of packages that import each other, directly or transitively.
+    pub fn package_cycles(&self, include_builtins: bool) -> Vec<Vec<PackageRef<'_>>> {
+        let package_imports = self.package_imports(include_builtins);
+        let packages: Vec<PackageRef> = package_imports.keys().copied().collect();
+
+        cycles(&packages, |package| match package_imports.get(&package) {
+            Some(deps) => deps.iter().copied().collect(),
+            None => Vec::new(),
+        })
+    }
+}
+
+fn file_stem(path: &Path) -> Option<String> {
+    path.file_stem()
+        .and_then(|stem| stem.to_str())
+        .map(|stem| stem.to_string())
+}
+
+/// Find the strongly-connected components of a graph that contain a cycle.
+fn cycles<T: Copy + PartialEq>(nodes: &[T], edges: impl Fn(T) -> Vec<T>) -> Vec<Vec<T>> {
+    let mut matrix = ReferenceMatrix::new(nodes.len());
+
+    for (row, node) in nodes.iter().enumerate() {
+        for dep in edges(*node) {
+            if let Some(col) = nodes.iter().position(|n| *n == dep) {
+                matrix.set_row_col(row, col, true);
+            }
+        }
+    }
+
+    matrix
+        .strongly_connected_components_all()
+        .groups()
+        .filter_map(|(group, _)| {
+            let members: Vec<usize> = group.iter_ones().collect();
+
+            match members.as_slice() {
+                [single] if !matrix.get_row_col(*single, *single) => None,
+                _ => Some(members.into_iter().map(|index| nodes[index]).collect()),
+            }
+        })
+        .collect()
+}
@@ -44,3 +44,23 @@ impl Default for Duration {
 impl std::ops::AddAssign for Duration {
     fn add_assign(&mut self, _: Duration) {}
 }
+
+impl std::ops::Add for Duration {
+    type Output = Duration;
+
+    fn add(self, _: Duration) -> Duration {
+        Duration
+    }
+}
+
+impl std::iter::Sum for Duration {
+    fn sum<I: Iterator<Item = Duration>>(_: I) -> Duration {
+        Duration
+    }
+}
+
+impl<'a> std::iter::Sum<&'a Duration> for Duration {
+    fn sum<I: Iterator<Item = &'a Duration>>(_: I) -> Duration {
+        Duration
+    }
+}
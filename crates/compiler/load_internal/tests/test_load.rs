@@ -19,7 +19,7 @@ use bumpalo::Bump;
 use roc_can::module::ExposedByModule;
 use roc_load_internal::docs::DocDef;
 use roc_load_internal::file::{
-    ExecutionMode, LoadConfig, LoadResult, LoadStart, LoadingProblem, Threading,
+    ExecutionMode, LoadConfig, LoadResult, LoadStart, LoadingProblem, OutputConfig, Threading,
 };
 use roc_load_internal::module::LoadedModule;
 use roc_module::ident::ModuleName;
@@ -61,6 +61,10 @@ fn load_and_typecheck(
         palette: DEFAULT_PALETTE,
         threading: Threading::Single,
         exec_mode: ExecutionMode::Check,
+        output: OutputConfig {
+            build_module_graph: true,
+            ..OutputConfig::default()
+        },
     };
 
     match roc_load_internal::file::load(
@@ -644,6 +648,36 @@ fn app_dep_types() {
     );
 }
 
+#[test]
+fn module_graph() {
+    let subs_by_module = Default::default();
+    let loaded_module = load_fixture("app_with_deps", "Primary", subs_by_module);
+    let graph = loaded_module.module_graph.as_ref().unwrap();
+
+    let imports_of = |name: &str| -> Vec<String> {
+        let node = graph
+            .modules(false)
+            .into_iter()
+            .find(|node| node.name == name)
+            .unwrap_or_else(|| panic!("{name} is not in the module graph"));
+
+        graph
+            .imports_of(node.module_id, false)
+            .iter()
+            .map(|dep| dep.qualified_name())
+            .collect()
+    };
+
+    assert_eq!(graph.root().name, "Primary");
+    assert_eq!(imports_of("Primary"), ["Dep1", "Dep2", "Dep3Blah", "Res"]);
+    assert_eq!(imports_of("Dep1"), ["Dep3Blah"]);
+    assert_eq!(imports_of("Dep3Blah"), ["Dep3Other"]);
+    assert!(imports_of("Dep3Other").is_empty());
+
+    assert!(graph.module_cycles(false).is_empty());
+    assert_eq!(graph.package_imports(false).len(), 1);
+}
+
 #[test]
 fn imported_dep_regression() {
     let subs_by_module = Default::default();
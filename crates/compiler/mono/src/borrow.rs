@@ -86,6 +86,9 @@ impl<'a> State<'a> {
                 // TODO do we rely on values being passed by-value here?
                 // it would be better to pass by-reference in general
             }
+            Stmt::SourceRegion { remainder, .. } => {
+                self.inspect_stmt(remainder);
+            }
             Stmt::Join {
                 body, remainder, ..
             } => {
@@ -57,7 +57,7 @@ where
     docs.sort_by_key(|(line, _)| *line);
 
     let src = proc
-        .to_doc(f, interner, true, Parens::NotNeeded)
+        .to_doc(f, interner, true.into(), Parens::NotNeeded)
         .1
         .pretty(80)
         .to_string();
@@ -649,6 +649,16 @@ fn specialize_drops_stmt<'a, 'i>(
                 remainder,
             ),
         }),
+        Stmt::SourceRegion { region, remainder } => arena.alloc(Stmt::SourceRegion {
+            region: *region,
+            remainder: specialize_drops_stmt(
+                arena,
+                layout_interner,
+                ident_ids,
+                environment,
+                remainder,
+            ),
+        }),
         Stmt::Join {
             id,
             parameters,
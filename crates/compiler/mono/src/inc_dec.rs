@@ -182,7 +182,8 @@ impl<'a, 'i> SymbolRcTypesEnv<'a, 'i> {
             ),
             Stmt::Expect { remainder, .. }
             | Stmt::ExpectFx { remainder, .. }
-            | Stmt::Dbg { remainder, .. } => {
+            | Stmt::Dbg { remainder, .. }
+            | Stmt::SourceRegion { remainder, .. } => {
                 self.insert_symbols_rc_type_stmt(remainder);
             }
             Stmt::Join {
@@ -712,6 +713,10 @@ fn insert_refcount_operations_stmt<'v, 'a>(
                 remainder: newer_remainder,
             })
         }
+        Stmt::SourceRegion { region, remainder } => arena.alloc(Stmt::SourceRegion {
+            region: *region,
+            remainder: insert_refcount_operations_stmt(arena, environment, remainder),
+        }),
         Stmt::Join {
             id: joinpoint_id,
             parameters,
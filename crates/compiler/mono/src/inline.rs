@@ -0,0 +1,800 @@
+//! Inlining of small procs, after specialization and before reference counting.
+//!
+//! Every call by name to a small enough proc is replaced by a copy of that proc's body, with
+//! fresh symbols. The body jumps to a join point holding the rest of the caller where it used to
+//! return. How small "small enough" is depends on the [InlineLevel]. This runs on the mono IR, so
+//! every backend benefits.
+//!
+//! Callees are copied as they were before this pass, so a proc is inlined at most one level deep,
+//! and mutually recursive procs can't make inlining go on forever. Afterwards, procs that neither
+//! the host nor an expect can reach any more are removed.
+use crate::ir::{
+    BranchInfo, Call, CallSpecId, CallType, Expr, HigherOrderLowLevel, JoinPointId,
+    ListLiteralElement, ModifyRc, OptLevel, Param, PassedFunction, Proc, ProcLayout, ReuseToken,
+    SelfRecursive, Stmt, UpdateModeIds,
+};
+use crate::layout::InLayout;
+use crate::low_level::HigherOrder;
+use bumpalo::collections::Vec;
+use bumpalo::Bump;
+use roc_collections::{MutMap, MutSet};
+use roc_error_macros::internal_error;
+use roc_module::symbol::{IdentIds, ModuleId, Symbol};
+
+/// How much [inline_small_procs] inlines. This is separate from the [OptLevel], because the dev
+/// backends, which build at [OptLevel::Development], benefit the most from inlining.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum InlineLevel {
+    /// Keep compiling fast, and the IR close to the source
+    #[default]
+    Off,
+    /// Only procs that take about as much code as calling them
+    Size,
+    Normal,
+    Aggressive,
+}
+
+impl InlineLevel {
+    /// The size of the largest proc we inline, as measured by [proc_size]
+    fn size_limit(self) -> Option<usize> {
+        match self {
+            InlineLevel::Off => None,
+            InlineLevel::Size => Some(3),
+            InlineLevel::Normal => Some(8),
+            InlineLevel::Aggressive => Some(24),
+        }
+    }
+}
+
+/// How much LLVM builds inline at each optimization level
+impl From<OptLevel> for InlineLevel {
+    fn from(opt_level: OptLevel) -> Self {
+        match opt_level {
+            OptLevel::Development => InlineLevel::Off,
+            OptLevel::Size => InlineLevel::Size,
+            OptLevel::Normal => InlineLevel::Normal,
+            OptLevel::Optimize => InlineLevel::Aggressive,
+        }
+    }
+}
+
+/// The number of statements in the body of `proc`, as a rough measure of how much code the
+/// backends will generate for it
+pub fn proc_size(proc: &Proc<'_>) -> usize {
+    let mut size = 0;
+    let mut stack = std::vec![&proc.body];
+
+    while let Some(stmt) = stack.pop() {
+        // Source regions generate no code, so they don't change what gets inlined
+        if !matches!(stmt, Stmt::SourceRegion { .. }) {
+            size += 1;
+        }
+
+        match stmt {
+            Stmt::Let(_, _, _, continuation) => stack.push(continuation),
+            Stmt::Switch {
+                branches,
+                default_branch,
+                ..
+            } => {
+                stack.extend(branches.iter().map(|(_, _, branch)| branch));
+                stack.push(default_branch.1);
+            }
+            Stmt::Refcounting(_, continuation) => stack.push(continuation),
+            Stmt::Expect { remainder, .. }
+            | Stmt::ExpectFx { remainder, .. }
+            | Stmt::Dbg { remainder, .. }
+            | Stmt::SourceRegion { remainder, .. } => stack.push(remainder),
+            Stmt::Join {
+                body, remainder, ..
+            } => {
+                stack.push(body);
+                stack.push(remainder);
+            }
+            Stmt::Ret(_) | Stmt::Jump(_, _) | Stmt::Crash(_, _) => {}
+        }
+    }
+
+    size
+}
+
+/// Inlines small procs into their callers, and then removes the procs that only `roots` used to
+/// reach through a call we inlined. The roots are the procs that the host, expects, and the
+/// backends refer to.
+pub fn inline_small_procs<'a>(
+    arena: &'a Bump,
+    home: ModuleId,
+    ident_ids: &mut IdentIds,
+    update_mode_ids: &mut UpdateModeIds,
+    procs: &mut MutMap<(Symbol, ProcLayout<'a>), Proc<'a>>,
+    inline_level: InlineLevel,
+    roots: impl IntoIterator<Item = Symbol>,
+) {
+    let limit = match inline_level.size_limit() {
+        Some(limit) => limit,
+        None => return,
+    };
+
+    let callees: MutMap<(Symbol, ProcLayout<'a>), Proc<'a>> = procs
+        .iter()
+        .filter(|(_, proc)| can_inline(proc, limit))
+        .map(|(key, proc)| (*key, proc.clone()))
+        .collect();
+
+    if callees.is_empty() {
+        return;
+    }
+
+    for ((symbol, _), proc) in procs.iter_mut() {
+        let mut env = Env {
+            arena,
+            home,
+            ident_ids,
+            update_mode_ids,
+            callees: &callees,
+            caller: *symbol,
+            next_call_spec_id: max_call_spec_id(&proc.body).next(),
+        };
+
+        proc.body = env.inline_stmt(&proc.body).clone();
+    }
+
+    remove_unreachable_procs(procs, roots);
+}
+
+fn remove_unreachable_procs(
+    procs: &mut MutMap<(Symbol, ProcLayout<'_>), Proc<'_>>,
+    roots: impl IntoIterator<Item = Symbol>,
+) {
+    // Every specialization of a reachable symbol stays, which keeps this simple and is rarely
+    // more than we need.
+    let mut by_symbol: MutMap<Symbol, std::vec::Vec<&Proc<'_>>> = MutMap::default();
+    for ((symbol, _), proc) in procs.iter() {
+        by_symbol.entry(*symbol).or_default().push(proc);
+    }
+
+    let mut reachable = MutSet::default();
+    let mut stack: std::vec::Vec<Symbol> = roots.into_iter().collect();
+
+    while let Some(symbol) = stack.pop() {
+        if !reachable.insert(symbol) {
+            continue;
+        }
+
+        for proc in by_symbol.get(&symbol).into_iter().flatten() {
+            push_referenced_procs(&proc.body, &mut stack);
+        }
+    }
+
+    procs.retain(|(symbol, _), _| reachable.contains(symbol));
+}
+
+/// Pushes the symbols of the procs that `stmt` calls or takes a pointer to
+fn push_referenced_procs(stmt: &Stmt<'_>, symbols: &mut std::vec::Vec<Symbol>) {
+    let mut stack = std::vec![stmt];
+
+    while let Some(stmt) = stack.pop() {
+        match stmt {
+            Stmt::Let(_, expr, _, continuation) => {
+                match expr {
+                    Expr::Call(Call {
+                        call_type: CallType::ByName { name, .. },
+                        ..
+                    })
+                    | Expr::FunctionPointer { lambda_name: name } => symbols.push(name.name()),
+                    Expr::Call(Call {
+                        call_type: CallType::HigherOrder(higher_order),
+                        ..
+                    }) => symbols.push(higher_order.passed_function.name.name()),
+                    _ => {}
+                }
+
+                stack.push(continuation);
+            }
+            Stmt::Switch {
+                branches,
+                default_branch,
+                ..
+            } => {
+                stack.extend(branches.iter().map(|(_, _, branch)| branch));
+                stack.push(default_branch.1);
+            }
+            Stmt::Refcounting(_, continuation) => stack.push(continuation),
+            Stmt::Expect { remainder, .. }
+            | Stmt::ExpectFx { remainder, .. }
+            | Stmt::Dbg { remainder, .. }
+            | Stmt::SourceRegion { remainder, .. } => stack.push(remainder),
+            Stmt::Join {
+                body, remainder, ..
+            } => {
+                stack.push(body);
+                stack.push(remainder);
+            }
+            Stmt::Ret(_) | Stmt::Jump(_, _) | Stmt::Crash(_, _) => {}
+        }
+    }
+}
+
+fn can_inline(proc: &Proc<'_>, limit: usize) -> bool {
+    let recursive = matches!(proc.is_self_recursive, SelfRecursive::SelfRecursive(_));
+
+    !recursive && !proc.is_erased && !has_expect_or_dbg(&proc.body) && proc_size(proc) <= limit
+}
+
+/// Expects and dbgs refer to type variables of the module they're in, so we don't move them
+/// into other procs.
+fn has_expect_or_dbg(stmt: &Stmt<'_>) -> bool {
+    match stmt {
+        Stmt::Expect { .. } | Stmt::ExpectFx { .. } | Stmt::Dbg { .. } => true,
+        Stmt::Let(_, _, _, continuation) | Stmt::Refcounting(_, continuation) => {
+            has_expect_or_dbg(continuation)
+        }
+        Stmt::SourceRegion { remainder, .. } => has_expect_or_dbg(remainder),
+        Stmt::Switch {
+            branches,
+            default_branch,
+            ..
+        } => {
+            branches
+                .iter()
+                .any(|(_, _, branch)| has_expect_or_dbg(branch))
+                || has_expect_or_dbg(default_branch.1)
+        }
+        Stmt::Join {
+            body, remainder, ..
+        } => has_expect_or_dbg(body) || has_expect_or_dbg(remainder),
+        Stmt::Ret(_) | Stmt::Jump(_, _) | Stmt::Crash(_, _) => false,
+    }
+}
+
+/// Alias analysis needs the calls in a proc to have distinct ids, so the calls we copy into a
+/// proc get ids above the ones it already uses.
+fn max_call_spec_id(stmt: &Stmt<'_>) -> CallSpecId {
+    let mut max = CallSpecId::BACKEND_DUMMY;
+    let mut stack = std::vec![stmt];
+
+    while let Some(stmt) = stack.pop() {
+        match stmt {
+            Stmt::Let(_, expr, _, continuation) => {
+                if let Expr::Call(call) = expr {
+                    match &call.call_type {
+                        CallType::ByName {
+                            specialization_id, ..
+                        } => max = max.max(*specialization_id),
+                        CallType::HigherOrder(higher_order) => {
+                            max = max.max(higher_order.passed_function.specialization_id)
+                        }
+                        CallType::ByPointer { .. }
+                        | CallType::Foreign { .. }
+                        | CallType::LowLevel { .. } => {}
+                    }
+                }
+
+                stack.push(continuation);
+            }
+            Stmt::Switch {
+                branches,
+                default_branch,
+                ..
+            } => {
+                stack.extend(branches.iter().map(|(_, _, branch)| branch));
+                stack.push(default_branch.1);
+            }
+            Stmt::Refcounting(_, continuation) => stack.push(continuation),
+            Stmt::Expect { remainder, .. }
+            | Stmt::ExpectFx { remainder, .. }
+            | Stmt::Dbg { remainder, .. }
+            | Stmt::SourceRegion { remainder, .. } => stack.push(remainder),
+            Stmt::Join {
+                body, remainder, ..
+            } => {
+                stack.push(body);
+                stack.push(remainder);
+            }
+            Stmt::Ret(_) | Stmt::Jump(_, _) | Stmt::Crash(_, _) => {}
+        }
+    }
+
+    max
+}
+
+struct Env<'a, 'r> {
+    arena: &'a Bump,
+    home: ModuleId,
+    ident_ids: &'r mut IdentIds,
+    update_mode_ids: &'r mut UpdateModeIds,
+    callees: &'r MutMap<(Symbol, ProcLayout<'a>), Proc<'a>>,
+    caller: Symbol,
+    next_call_spec_id: CallSpecId,
+}
+
+impl<'a, 'r> Env<'a, 'r> {
+    fn unique_symbol(&mut self) -> Symbol {
+        let ident_id = self.ident_ids.gen_unique();
+
+        Symbol::new(self.home, ident_id)
+    }
+
+    fn call_spec_id(&mut self) -> CallSpecId {
+        let id = self.next_call_spec_id;
+        self.next_call_spec_id = id.next();
+
+        id
+    }
+
+    fn callee(&self, call: &Call<'a>) -> Option<&'r Proc<'a>> {
+        match call.call_type {
+            CallType::ByName {
+                name,
+                ret_layout,
+                arg_layouts,
+                ..
+            } if name.name() != self.caller => {
+                let proc_layout = ProcLayout {
+                    arguments: arg_layouts,
+                    result: ret_layout,
+                    niche: name.niche(),
+                };
+
+                let callees: &'r MutMap<_, Proc<'a>> = self.callees;
+                let callee = callees.get(&(name.name(), proc_layout))?;
+
+                (callee.args.len() == call.arguments.len()).then_some(callee)
+            }
+            _ => None,
+        }
+    }
+
+    fn inline_stmt(&mut self, stmt: &Stmt<'a>) -> &'a Stmt<'a> {
+        let arena = self.arena;
+
+        let new = match stmt {
+            Stmt::Let(symbol, expr, layout, continuation) => {
+                let continuation = self.inline_stmt(continuation);
+
+                match expr {
+                    Expr::Call(call) => match self.callee(call) {
+                        Some(callee) => {
+                            return self.inline_call(*symbol, *layout, callee, call, continuation)
+                        }
+                        None => Stmt::Let(*symbol, expr.clone(), *layout, continuation),
+                    },
+                    _ => Stmt::Let(*symbol, expr.clone(), *layout, continuation),
+                }
+            }
+            Stmt::Switch {
+                cond_symbol,
+                cond_layout,
+                branches,
+                default_branch,
+                ret_layout,
+            } => {
+                let branches = Vec::from_iter_in(
+                    branches.iter().map(|(label, info, branch)| {
+                        (*label, info.clone(), self.inline_stmt(branch).clone())
+                    }),
+                    arena,
+                );
+                let default_branch = (default_branch.0.clone(), self.inline_stmt(default_branch.1));
+
+                Stmt::Switch {
+                    cond_symbol: *cond_symbol,
+                    cond_layout: *cond_layout,
+                    branches: branches.into_bump_slice(),
+                    default_branch,
+                    ret_layout: *ret_layout,
+                }
+            }
+            Stmt::Refcounting(modify, continuation) => {
+                Stmt::Refcounting(*modify, self.inline_stmt(continuation))
+            }
+            Stmt::Expect {
+                condition,
+                region,
+                lookups,
+                variables,
+                remainder,
+            } => Stmt::Expect {
+                condition: *condition,
+                region: *region,
+                lookups,
+                variables,
+                remainder: self.inline_stmt(remainder),
+            },
+            Stmt::ExpectFx {
+                condition,
+                region,
+                lookups,
+                variables,
+                remainder,
+            } => Stmt::ExpectFx {
+                condition: *condition,
+                region: *region,
+                lookups,
+                variables,
+                remainder: self.inline_stmt(remainder),
+            },
+            Stmt::Dbg {
+                source_location,
+                source,
+                symbol,
+                variable,
+                remainder,
+            } => Stmt::Dbg {
+                source_location,
+                source,
+                symbol: *symbol,
+                variable: *variable,
+                remainder: self.inline_stmt(remainder),
+            },
+            Stmt::SourceRegion { region, remainder } => Stmt::SourceRegion {
+                region: *region,
+                remainder: self.inline_stmt(remainder),
+            },
+            Stmt::Join {
+                id,
+                parameters,
+                body,
+                remainder,
+            } => Stmt::Join {
+                id: *id,
+                parameters,
+                body: self.inline_stmt(body),
+                remainder: self.inline_stmt(remainder),
+            },
+            Stmt::Ret(_) | Stmt::Jump(_, _) | Stmt::Crash(_, _) => stmt.clone(),
+        };
+
+        arena.alloc(new)
+    }
+
+    /// Turn `let symbol = callee arguments in continuation` into
+    ///
+    /// ```text
+    /// join returned(symbol) = continuation
+    /// in <body of callee, with `ret x` replaced by `jump returned x`>
+    /// ```
+    fn inline_call(
+        &mut self,
+        symbol: Symbol,
+        layout: InLayout<'a>,
+        callee: &Proc<'a>,
+        call: &Call<'a>,
+        continuation: &'a Stmt<'a>,
+    ) -> &'a Stmt<'a> {
+        let returned = JoinPointId(self.unique_symbol());
+
+        let mut copy = Copy {
+            env: self,
+            returned,
+            substitutions: callee
+                .args
+                .iter()
+                .map(|(_, parameter)| *parameter)
+                .zip(call.arguments.iter().copied())
+                .collect(),
+        };
+
+        let body = copy.stmt(&callee.body);
+        let parameters = self.arena.alloc([Param { symbol, layout }]);
+
+        self.arena.alloc(Stmt::Join {
+            id: returned,
+            parameters,
+            body: continuation,
+            remainder: body,
+        })
+    }
+}
+
+/// Copies the body of a callee into a caller, giving everything it binds a fresh symbol
+struct Copy<'e, 'a, 'r> {
+    env: &'e mut Env<'a, 'r>,
+    /// Where the copied body returns to
+    returned: JoinPointId,
+    substitutions: MutMap<Symbol, Symbol>,
+}
+
+impl<'e, 'a, 'r> Copy<'e, 'a, 'r> {
+    fn symbol(&self, symbol: Symbol) -> Symbol {
+        self.substitutions.get(&symbol).copied().unwrap_or(symbol)
+    }
+
+    fn symbols(&self, symbols: &[Symbol]) -> &'a [Symbol] {
+        let symbols = symbols.iter().map(|symbol| self.symbol(*symbol));
+
+        Vec::from_iter_in(symbols, self.env.arena).into_bump_slice()
+    }
+
+    fn bind(&mut self, symbol: Symbol) -> Symbol {
+        let fresh = self.env.unique_symbol();
+        self.substitutions.insert(symbol, fresh);
+
+        fresh
+    }
+
+    fn stmt(&mut self, stmt: &Stmt<'a>) -> &'a Stmt<'a> {
+        let arena = self.env.arena;
+
+        let new = match stmt {
+            Stmt::Let(symbol, expr, layout, continuation) => {
+                let expr = self.expr(expr);
+                let symbol = self.bind(*symbol);
+
+                Stmt::Let(symbol, expr, *layout, self.stmt(continuation))
+            }
+            Stmt::Switch {
+                cond_symbol,
+                cond_layout,
+                branches,
+                default_branch,
+                ret_layout,
+            } => {
+                let branches = Vec::from_iter_in(
+                    branches.iter().map(|(label, info, branch)| {
+                        (*label, self.branch_info(info), self.stmt(branch).clone())
+                    }),
+                    arena,
+                );
+                let default_branch = (
+                    self.branch_info(&default_branch.0),
+                    self.stmt(default_branch.1),
+                );
+
+                Stmt::Switch {
+                    cond_symbol: self.symbol(*cond_symbol),
+                    cond_layout: *cond_layout,
+                    branches: branches.into_bump_slice(),
+                    default_branch,
+                    ret_layout: *ret_layout,
+                }
+            }
+            Stmt::Ret(symbol) => Stmt::Jump(self.returned, arena.alloc([self.symbol(*symbol)])),
+            Stmt::Refcounting(modify, continuation) => {
+                let modify = match *modify {
+                    ModifyRc::Inc(symbol, n) => ModifyRc::Inc(self.symbol(symbol), n),
+                    ModifyRc::Dec(symbol) => ModifyRc::Dec(self.symbol(symbol)),
+                    ModifyRc::DecRef(symbol) => ModifyRc::DecRef(self.symbol(symbol)),
+                    ModifyRc::Free(symbol) => ModifyRc::Free(self.symbol(symbol)),
+                };
+
+                Stmt::Refcounting(modify, self.stmt(continuation))
+            }
+            Stmt::Expect { .. } | Stmt::ExpectFx { .. } | Stmt::Dbg { .. } => {
+                internal_error!("procs with expects or dbgs are never inlined")
+            }
+            Stmt::SourceRegion { remainder, .. } => {
+                // These regions are in the callee's module, not the caller's. The inlined code
+                // is attributed to the call instead.
+                return self.stmt(remainder);
+            }
+            Stmt::Join {
+                id,
+                parameters,
+                body,
+                remainder,
+            } => {
+                let id = JoinPointId(self.bind(id.0));
+                let parameters = Vec::from_iter_in(
+                    parameters.iter().map(|param| Param {
+                        symbol: self.bind(param.symbol),
+                        layout: param.layout,
+                    }),
+                    arena,
+                );
+
+                Stmt::Join {
+                    id,
+                    parameters: parameters.into_bump_slice(),
+                    body: self.stmt(body),
+                    remainder: self.stmt(remainder),
+                }
+            }
+            Stmt::Jump(id, arguments) => {
+                Stmt::Jump(JoinPointId(self.symbol(id.0)), self.symbols(arguments))
+            }
+            Stmt::Crash(symbol, tag) => Stmt::Crash(self.symbol(*symbol), *tag),
+        };
+
+        arena.alloc(new)
+    }
+
+    fn branch_info(&self, info: &BranchInfo<'a>) -> BranchInfo<'a> {
+        match *info {
+            BranchInfo::None => BranchInfo::None,
+            BranchInfo::Constructor {
+                scrutinee,
+                layout,
+                tag_id,
+            } => BranchInfo::Constructor {
+                scrutinee: self.symbol(scrutinee),
+                layout,
+                tag_id,
+            },
+            BranchInfo::List { scrutinee, len } => BranchInfo::List {
+                scrutinee: self.symbol(scrutinee),
+                len,
+            },
+            BranchInfo::Unique { scrutinee, unique } => BranchInfo::Unique {
+                scrutinee: self.symbol(scrutinee),
+                unique,
+            },
+        }
+    }
+
+    fn expr(&mut self, expr: &Expr<'a>) -> Expr<'a> {
+        let arena = self.env.arena;
+
+        match expr {
+            Expr::Literal(_)
+            | Expr::NullPointer
+            | Expr::EmptyArray
+            | Expr::FunctionPointer { .. }
+            | Expr::RuntimeErrorFunction(_) => expr.clone(),
+            Expr::Call(call) => Expr::Call(Call {
+                call_type: self.call_type(&call.call_type),
+                arguments: self.symbols(call.arguments),
+            }),
+            Expr::Tag {
+                tag_layout,
+                tag_id,
+                arguments,
+                reuse,
+            } => Expr::Tag {
+                tag_layout: *tag_layout,
+                tag_id: *tag_id,
+                arguments: self.symbols(arguments),
+                reuse: reuse.map(|token| ReuseToken {
+                    symbol: self.symbol(token.symbol),
+                    update_tag_id: token.update_tag_id,
+                    update_mode: self.env.update_mode_ids.next_id(),
+                }),
+            },
+            Expr::Struct(fields) => Expr::Struct(self.symbols(fields)),
+            Expr::StructAtIndex {
+                index,
+                field_layouts,
+                structure,
+            } => Expr::StructAtIndex {
+                index: *index,
+                field_layouts,
+                structure: self.symbol(*structure),
+            },
+            Expr::GetTagId {
+                structure,
+                union_layout,
+            } => Expr::GetTagId {
+                structure: self.symbol(*structure),
+                union_layout: *union_layout,
+            },
+            Expr::UnionAtIndex {
+                structure,
+                tag_id,
+                union_layout,
+                index,
+            } => Expr::UnionAtIndex {
+                structure: self.symbol(*structure),
+                tag_id: *tag_id,
+                union_layout: *union_layout,
+                index: *index,
+            },
+            Expr::GetElementPointer {
+                structure,
+                union_layout,
+                indices,
+            } => Expr::GetElementPointer {
+                structure: self.symbol(*structure),
+                union_layout: *union_layout,
+                indices,
+            },
+            Expr::Array { elem_layout, elems } => {
+                let elems = elems.iter().map(|elem| match elem {
+                    ListLiteralElement::Literal(literal) => ListLiteralElement::Literal(*literal),
+                    ListLiteralElement::Symbol(symbol) => {
+                        ListLiteralElement::Symbol(self.symbol(*symbol))
+                    }
+                });
+
+                Expr::Array {
+                    elem_layout: *elem_layout,
+                    elems: Vec::from_iter_in(elems, arena).into_bump_slice(),
+                }
+            }
+            Expr::ErasedMake { value, callee } => Expr::ErasedMake {
+                value: value.map(|value| self.symbol(value)),
+                callee: self.symbol(*callee),
+            },
+            Expr::ErasedLoad { symbol, field } => Expr::ErasedLoad {
+                symbol: self.symbol(*symbol),
+                field: *field,
+            },
+            Expr::Alloca {
+                element_layout,
+                initializer,
+            } => Expr::Alloca {
+                element_layout: *element_layout,
+                initializer: initializer.map(|initializer| self.symbol(initializer)),
+            },
+            Expr::Reset { symbol, .. } => Expr::Reset {
+                symbol: self.symbol(*symbol),
+                update_mode: self.env.update_mode_ids.next_id(),
+            },
+            Expr::ResetRef { symbol, .. } => Expr::ResetRef {
+                symbol: self.symbol(*symbol),
+                update_mode: self.env.update_mode_ids.next_id(),
+            },
+        }
+    }
+
+    fn higher_order_op(&self, op: &HigherOrder) -> HigherOrder {
+        match *op {
+            HigherOrder::ListMap { xs } => HigherOrder::ListMap {
+                xs: self.symbol(xs),
+            },
+            HigherOrder::ListMap2 { xs, ys } => HigherOrder::ListMap2 {
+                xs: self.symbol(xs),
+                ys: self.symbol(ys),
+            },
+            HigherOrder::ListMap3 { xs, ys, zs } => HigherOrder::ListMap3 {
+                xs: self.symbol(xs),
+                ys: self.symbol(ys),
+                zs: self.symbol(zs),
+            },
+            HigherOrder::ListMap4 { xs, ys, zs, ws } => HigherOrder::ListMap4 {
+                xs: self.symbol(xs),
+                ys: self.symbol(ys),
+                zs: self.symbol(zs),
+                ws: self.symbol(ws),
+            },
+            HigherOrder::ListSortWith { xs } => HigherOrder::ListSortWith {
+                xs: self.symbol(xs),
+            },
+        }
+    }
+
+    /// Calls get fresh specialization ids and update modes, which must be unique within a proc
+    fn call_type(&mut self, call_type: &CallType<'a>) -> CallType<'a> {
+        match call_type {
+            CallType::ByName {
+                name,
+                ret_layout,
+                arg_layouts,
+                ..
+            } => CallType::ByName {
+                name: *name,
+                ret_layout: *ret_layout,
+                arg_layouts,
+                specialization_id: self.env.call_spec_id(),
+            },
+            CallType::ByPointer {
+                pointer,
+                ret_layout,
+                arg_layouts,
+            } => CallType::ByPointer {
+                pointer: self.symbol(*pointer),
+                ret_layout: *ret_layout,
+                arg_layouts,
+            },
+            CallType::Foreign { .. } => call_type.clone(),
+            CallType::LowLevel { op, .. } => CallType::LowLevel {
+                op: *op,
+                update_mode: self.env.update_mode_ids.next_id(),
+            },
+            CallType::HigherOrder(higher_order) => {
+                let passed_function = PassedFunction {
+                    specialization_id: self.env.call_spec_id(),
+                    captured_environment: self
+                        .symbol(higher_order.passed_function.captured_environment),
+                    ..higher_order.passed_function
+                };
+
+                CallType::HigherOrder(self.env.arena.alloc(HigherOrderLowLevel {
+                    op: self.higher_order_op(&higher_order.op),
+                    closure_env_layout: higher_order.closure_env_layout,
+                    update_mode: self.env.update_mode_ids.next_id(),
+                    passed_function,
+                }))
+            }
+        }
+    }
+}
@@ -337,7 +337,7 @@ impl<'a> Proc<'a> {
         &'b self,
         alloc: &'b D,
         interner: &'b I,
-        pretty: bool,
+        pretty: PrintSymbols<'_>,
         _parens: Parens,
     ) -> DocBuilder<'b, D, A>
     where
@@ -383,13 +383,20 @@ impl<'a> Proc<'a> {
         }
     }
 
-    pub fn to_pretty<I>(&self, interner: &I, width: usize, pretty: bool) -> String
+    /// Print the proc, with its symbols shown as `pretty` says. A `bool` shows them by index,
+    /// or by name when it is `true`.
+    pub fn to_pretty<'n, I>(
+        &self,
+        interner: &I,
+        width: usize,
+        pretty: impl Into<PrintSymbols<'n>>,
+    ) -> String
     where
         I: LayoutInterner<'a>,
     {
         let allocator = BoxAllocator;
         let mut w = std::vec::Vec::new();
-        self.to_doc::<_, (), _>(&allocator, interner, pretty, Parens::NotNeeded)
+        self.to_doc::<_, (), _>(&allocator, interner, pretty.into(), Parens::NotNeeded)
             .1
             .render(width, &mut w)
             .unwrap();
@@ -1362,6 +1369,9 @@ pub struct Env<'a, 'i> {
     pub exposed_by_module: &'i ExposedByModule,
     pub derived_module: &'i SharedDerivedModule,
     pub struct_indexing: UsageTrackingMap<(Symbol, u64), Symbol>,
+    /// Wrap the statements of every definition in a [Stmt::SourceRegion], for debug info and
+    /// perf hints
+    pub mark_source_regions: bool,
 }
 
 impl<'a, 'i> Env<'a, 'i> {
@@ -1540,6 +1550,13 @@ pub enum Stmt<'a> {
         /// What happens after the dbg
         remainder: &'a Stmt<'a>,
     },
+    /// The statements in `remainder` were generated from `region` in the source of the
+    /// procedure's module. Only emitted when the backends need to map code back to the source
+    /// (see `Env::mark_source_regions`), and dropped again when a procedure is inlined.
+    SourceRegion {
+        region: Region,
+        remainder: &'a Stmt<'a>,
+    },
     /// a join point `join f <params> = <continuation> in remainder`
     Join {
         id: JoinPointId,
@@ -1596,7 +1613,11 @@ pub enum BranchInfo<'a> {
 }
 
 impl<'a> BranchInfo<'a> {
-    pub fn to_doc<'b, D, A>(&'b self, alloc: &'b D, _pretty: bool) -> DocBuilder<'b, D, A>
+    pub fn to_doc<'b, D, A>(
+        &'b self,
+        alloc: &'b D,
+        _pretty: PrintSymbols<'_>,
+    ) -> DocBuilder<'b, D, A>
     where
         D: DocAllocator<'b, A>,
         D::Doc: Clone,
@@ -1626,7 +1647,7 @@ pub enum ModifyRc {
 }
 
 impl ModifyRc {
-    pub fn to_doc<'a, D, A>(self, alloc: &'a D, pretty: bool) -> DocBuilder<'a, D, A>
+    pub fn to_doc<'a, D, A>(self, alloc: &'a D, pretty: PrintSymbols<'_>) -> DocBuilder<'a, D, A>
     where
         D: DocAllocator<'a, A>,
         D::Doc: Clone,
@@ -1678,7 +1699,11 @@ pub struct Call<'a> {
 }
 
 impl<'a> Call<'a> {
-    pub fn to_doc<'b, D, A>(&'b self, alloc: &'b D, pretty: bool) -> DocBuilder<'b, D, A>
+    pub fn to_doc<'b, D, A>(
+        &'b self,
+        alloc: &'b D,
+        pretty: PrintSymbols<'_>,
+    ) -> DocBuilder<'b, D, A>
     where
         D: DocAllocator<'b, A>,
         D::Doc: Clone,
@@ -1725,7 +1750,7 @@ impl<'a> Call<'a> {
     }
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
 pub struct CallSpecId {
     id: u32,
 }
@@ -1735,6 +1760,11 @@ impl CallSpecId {
         self.id.to_ne_bytes()
     }
 
+    /// The id after this one, for passes that copy calls after specialization
+    pub(crate) fn next(self) -> Self {
+        Self { id: self.id + 1 }
+    }
+
     /// Dummy value for generating refcount helper procs in the backends
     /// This happens *after* specialization so it's safe
     pub const BACKEND_DUMMY: Self = Self { id: 0 };
@@ -1969,6 +1999,21 @@ impl<'a> Literal<'a> {
     }
 }
 
+/// How the IR printer shows symbols
+#[derive(Clone, Copy)]
+pub enum PrintSymbols<'n> {
+    /// As `<module>.<ident index>`, or by name if `pretty` is set or we print IR for debugging
+    Ids { pretty: bool },
+    /// As the function names them. It is called for each symbol as the printer comes to it.
+    Named(&'n dyn Fn(Symbol) -> String),
+}
+
+impl From<bool> for PrintSymbols<'_> {
+    fn from(pretty: bool) -> Self {
+        PrintSymbols::Ids { pretty }
+    }
+}
+
 pub(crate) fn symbol_to_doc_string(symbol: Symbol, force_pretty: bool) -> String {
     use roc_module::ident::ModuleName;
 
@@ -1986,19 +2031,26 @@ pub(crate) fn symbol_to_doc_string(symbol: Symbol, force_pretty: bool) -> String
     }
 }
 
-fn symbol_to_doc<'b, D, A>(alloc: &'b D, symbol: Symbol, force_pretty: bool) -> DocBuilder<'b, D, A>
+fn symbol_to_doc<'b, D, A>(
+    alloc: &'b D,
+    symbol: Symbol,
+    print: PrintSymbols<'_>,
+) -> DocBuilder<'b, D, A>
 where
     D: DocAllocator<'b, A>,
     D::Doc: Clone,
     A: Clone,
 {
-    alloc.text(symbol_to_doc_string(symbol, force_pretty))
+    match print {
+        PrintSymbols::Ids { pretty } => alloc.text(symbol_to_doc_string(symbol, pretty)),
+        PrintSymbols::Named(name) => alloc.text(name(symbol)),
+    }
 }
 
 fn join_point_to_doc<'b, D, A>(
     alloc: &'b D,
     symbol: JoinPointId,
-    pretty: bool,
+    pretty: PrintSymbols<'_>,
 ) -> DocBuilder<'b, D, A>
 where
     D: DocAllocator<'b, A>,
@@ -2009,7 +2061,11 @@ where
 }
 
 impl<'a> Expr<'a> {
-    pub fn to_doc<'b, D, A>(&'b self, alloc: &'b D, pretty: bool) -> DocBuilder<'b, D, A>
+    pub fn to_doc<'b, D, A>(
+        &'b self,
+        alloc: &'b D,
+        pretty: PrintSymbols<'_>,
+    ) -> DocBuilder<'b, D, A>
     where
         D: DocAllocator<'b, A>,
         D::Doc: Clone,
@@ -2177,7 +2233,7 @@ impl<'a> Expr<'a> {
     pub fn to_pretty(&self, width: usize, pretty: bool) -> String {
         let allocator = BoxAllocator;
         let mut w = std::vec::Vec::new();
-        self.to_doc::<_, ()>(&allocator, pretty)
+        self.to_doc::<_, ()>(&allocator, pretty.into())
             .1
             .render(width, &mut w)
             .unwrap();
@@ -2221,7 +2277,7 @@ impl<'a> Stmt<'a> {
         &'b self,
         alloc: &'b D,
         interner: &I,
-        pretty: bool,
+        pretty: PrintSymbols<'_>,
     ) -> DocBuilder<'b, D, A>
     where
         D: DocAllocator<'b, A>,
@@ -2257,6 +2313,11 @@ impl<'a> Stmt<'a> {
                 .append(alloc.hardline())
                 .append(remainder.to_doc(alloc, interner, pretty)),
 
+            SourceRegion { region, remainder } => alloc
+                .text(format!("region {:?};", region))
+                .append(alloc.hardline())
+                .append(remainder.to_doc(alloc, interner, pretty)),
+
             Expect {
                 condition,
                 remainder,
@@ -2386,7 +2447,7 @@ impl<'a> Stmt<'a> {
     {
         let allocator = BoxAllocator;
         let mut w = std::vec::Vec::new();
-        self.to_doc::<_, (), _>(&allocator, interner, pretty)
+        self.to_doc::<_, (), _>(&allocator, interner, pretty.into())
             .1
             .render(width, &mut w)
             .unwrap();
@@ -2433,6 +2494,39 @@ fn from_can_let<'a>(
     cont: Box<Loc<roc_can::expr::Expr>>,
     variable: Variable,
     opt_assigned_and_hole: Option<(Symbol, &'a Stmt<'a>)>,
+) -> Stmt<'a> {
+    // Definitions made up by the compiler have no region
+    let region = Region::span_across(&def.loc_pattern.region, &def.loc_expr.region);
+    let mark_region = env.mark_source_regions && def.loc_pattern.region != Region::zero();
+
+    let stmt = from_can_let_help(
+        env,
+        procs,
+        layout_cache,
+        def,
+        cont,
+        variable,
+        opt_assigned_and_hole,
+    );
+
+    if mark_region {
+        Stmt::SourceRegion {
+            region,
+            remainder: env.arena.alloc(stmt),
+        }
+    } else {
+        stmt
+    }
+}
+
+fn from_can_let_help<'a>(
+    env: &mut Env<'a, '_>,
+    procs: &mut Procs<'a>,
+    layout_cache: &mut LayoutCache<'a>,
+    def: Box<roc_can::def::Def>,
+    cont: Box<Loc<roc_can::expr::Expr>>,
+    variable: Variable,
+    opt_assigned_and_hole: Option<(Symbol, &'a Stmt<'a>)>,
 ) -> Stmt<'a> {
     use roc_can::expr::Expr::*;
 
@@ -7646,6 +7740,16 @@ fn substitute_in_stmt_help<'a>(
             Some(arena.alloc(expect))
         }
 
+        SourceRegion { region, remainder } => {
+            match substitute_in_stmt_help(arena, remainder, subs) {
+                Some(remainder) => Some(arena.alloc(SourceRegion {
+                    region: *region,
+                    remainder,
+                })),
+                None => None,
+            }
+        }
+
         Expect {
             condition,
             region,
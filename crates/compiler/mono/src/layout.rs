@@ -3413,11 +3413,12 @@ fn layout_from_flat_type<'a>(
                 // unwrap it.
                 Ok(sortables.pop().unwrap().1)
             } else {
+                let indices = arena.alloc_slice_fill_iter(sortables.iter().map(|t| t.0));
                 let field_layouts =
                     Vec::from_iter_in(sortables.into_iter().map(|t| t.1), arena).into_bump_slice();
                 let struct_layout = Layout {
                     repr: LayoutRepr::Struct(field_layouts).direct(),
-                    semantic: SemanticRepr::tuple(field_layouts.len()),
+                    semantic: SemanticRepr::tuple(indices),
                 };
 
                 Ok(env.cache.put_in(struct_layout))
@@ -2033,3 +2033,55 @@ mod insert_recursive_layout {
         assert_eq!(in1, in2);
     }
 }
+
+#[cfg(test)]
+mod insert_tuple {
+    use roc_target::Target;
+
+    use crate::layout::{InLayout, Layout, LayoutRepr, SemanticRepr};
+
+    use super::{GlobalLayoutInterner, LayoutInterner};
+
+    const TARGET: Target = Target::LinuxX64;
+
+    // `(U8, I64)` and `(I64, U8)` both put the I64 first
+    const FIELDS: &[InLayout] = &[Layout::I64, Layout::U8];
+
+    fn tuple(indices: &'static [usize]) -> Layout<'static> {
+        Layout {
+            repr: LayoutRepr::Struct(FIELDS).direct(),
+            semantic: SemanticRepr::tuple(indices),
+        }
+    }
+
+    #[test]
+    fn same_element_order_reinterns() {
+        let global = GlobalLayoutInterner::with_capacity(2, TARGET);
+        let mut interner = global.fork();
+
+        let first = interner.insert(tuple(&[0, 1]));
+        let second = interner.insert(tuple(&[0, 1]));
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn different_element_order_interns_separately() {
+        let global = GlobalLayoutInterner::with_capacity(2, TARGET);
+        let mut interner = global.fork();
+
+        let i64_u8 = interner.insert(tuple(&[0, 1]));
+        let u8_i64 = interner.insert(tuple(&[1, 0]));
+        assert_ne!(i64_u8, u8_i64);
+
+        // only the semantic representation differs
+        assert_eq!(interner.get_repr(i64_u8), interner.get_repr(u8_i64));
+        assert_eq!(
+            interner.get_semantic(i64_u8).tuple_indices(),
+            Some(&[0, 1][..])
+        );
+        assert_eq!(
+            interner.get_semantic(u8_i64).tuple_indices(),
+            Some(&[1, 0][..])
+        );
+    }
+}
@@ -25,7 +25,7 @@ impl<'a> SemanticRepr<'a> {
 enum Inner<'a> {
     None,
     Record(SemaRecord<'a>),
-    Tuple(SemaTuple),
+    Tuple(SemaTuple<'a>),
     TagUnion(SemaTagUnion<'a>),
     Lambdas(SemaLambdas<'a>),
 }
@@ -51,8 +51,8 @@ impl<'a> SemanticRepr<'a> {
         Self(Inner::Record(SemaRecord { fields }))
     }
 
-    pub(super) fn tuple(size: usize) -> Self {
-        Self(Inner::Tuple(SemaTuple { size }))
+    pub(super) fn tuple(indices: &'a [usize]) -> Self {
+        Self(Inner::Tuple(SemaTuple { indices }))
     }
 
     pub(super) fn tag_union(tags: &'a [&'a str]) -> Self {
@@ -62,6 +62,30 @@ impl<'a> SemanticRepr<'a> {
     pub(super) fn lambdas(lambdas: &'a [Symbol]) -> Self {
         Self(Inner::Lambdas(SemaLambdas { lambdas }))
     }
+
+    /// The names of a record's fields, in the order of the fields in its layout
+    pub fn record_fields(&self) -> Option<&'a [&'a str]> {
+        match self.0 {
+            Inner::Record(SemaRecord { fields }) => Some(fields),
+            _ => None,
+        }
+    }
+
+    /// The index each element of a tuple's layout has in the tuple as it was written
+    pub fn tuple_indices(&self) -> Option<&'a [usize]> {
+        match self.0 {
+            Inner::Tuple(SemaTuple { indices }) => Some(indices),
+            _ => None,
+        }
+    }
+
+    /// The names of a tag union's tags, in the order of their tag ids
+    pub fn tag_names(&self) -> Option<&'a [&'a str]> {
+        match self.0 {
+            Inner::TagUnion(SemaTagUnion { tags }) => Some(tags),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
@@ -70,8 +94,8 @@ struct SemaRecord<'a> {
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
-struct SemaTuple {
-    size: usize,
+struct SemaTuple<'a> {
+    indices: &'a [usize],
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
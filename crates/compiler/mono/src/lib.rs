@@ -13,9 +13,11 @@ pub mod borrow;
 pub mod code_gen_help;
 pub mod drop_specialization;
 pub mod inc_dec;
+pub mod inline;
 pub mod ir;
 pub mod layout;
 pub mod low_level;
+pub mod perf_hints;
 pub mod reset_reuse;
 pub mod tail_recursion;
 
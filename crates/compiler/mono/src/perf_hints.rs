@@ -0,0 +1,733 @@
+//! Finds where the refcounting passes ([inc_dec](crate::inc_dec), [reset_reuse](crate::reset_reuse)
+//! and [drop_specialization](crate::drop_specialization)) could not avoid a copy or an allocation,
+//! for `roc check --perf-hints`.
+//!
+//! This looks at the procs after all of those passes have run, so it sees the same refcounting
+//! operations as the backends. It can't know how values are shared at runtime, so a hint means
+//! "this happens unless you got lucky", not "this always happens".
+
+use roc_collections::{MutMap, MutSet};
+use roc_module::low_level::LowLevel;
+use roc_module::symbol::{ModuleId, Symbol};
+use roc_region::all::Region;
+
+use crate::inc_dec::lowlevel_borrow_signature;
+use crate::ir::{
+    Call, CallType, ErasedField, Expr, JoinPointId, ListLiteralElement, ModifyRc, Proc, ProcLayout,
+    Stmt,
+};
+use crate::layout::{InLayout, LayoutInterner, LayoutRepr, STLayoutInterner, UnionLayout};
+use crate::low_level::HigherOrder;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PerfHint {
+    pub module: ModuleId,
+    pub region: Region,
+    pub kind: PerfHintKind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PerfHintKind {
+    /// `value` is passed to `callee`, which would update it in place, but its refcount was
+    /// incremented first because it's used again later. So `callee` gets a shared value and
+    /// has to copy it.
+    CopiedForUpdate { value: Symbol, callee: Callee },
+    /// `value` is copied by an explicit `List.clone`
+    Cloned { value: Symbol },
+    /// A tag union value is built in the memory of one that was just taken apart
+    Reused,
+    /// A tag union value is allocated, even though a value with the same layout was dropped
+    /// earlier on the same path. Its memory could have been reused if it was unique.
+    NotReused,
+}
+
+/// What a value is passed to when it's updated in place
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Callee {
+    Proc(Symbol),
+    /// The name of the builtin that the lowlevel implements
+    LowLevel(&'static str),
+}
+
+/// Find the perf hints in the procs of user code, sorted by region.
+///
+/// The regions come from the definition a hint is in (see [Stmt::SourceRegion]), and otherwise
+/// from the proc it is in. Hints in builtins and in procs without any region are left out.
+pub fn find_perf_hints<'a>(
+    interner: &STLayoutInterner<'a>,
+    procedures: &MutMap<(Symbol, ProcLayout<'a>), Proc<'a>>,
+    definition_regions: &MutMap<Symbol, Region>,
+) -> Vec<PerfHint> {
+    let in_place = in_place_params(procedures);
+    let mut hints = MutSet::default();
+
+    for proc in procedures.values() {
+        let proc_name = proc.name.name();
+        if proc_name.module_id().is_builtin() {
+            continue;
+        }
+
+        let mut finder = HintFinder {
+            interner,
+            in_place: &in_place,
+            layouts: proc
+                .args
+                .iter()
+                .map(|(layout, arg)| (*arg, *layout))
+                .collect(),
+            found: Vec::new(),
+        };
+        finder.find(&proc.body, None, MutMap::default(), Vec::new());
+
+        for (region, kind) in finder.found {
+            if let Some(region) = region.or_else(|| definition_regions.get(&proc_name).copied()) {
+                hints.insert(PerfHint {
+                    module: proc_name.module_id(),
+                    region,
+                    kind,
+                });
+            }
+        }
+    }
+
+    let mut hints: Vec<PerfHint> = hints.into_iter().collect();
+    hints.sort_by_key(|hint| hint.region);
+
+    hints
+}
+
+/// The builtins implemented by lowlevels that update their first argument in place when it's
+/// unique, and copy it otherwise
+fn in_place_lowlevel(op: LowLevel) -> Option<&'static str> {
+    use LowLevel::*;
+
+    let name = match op {
+        ListReplaceUnsafe => "List.replace",
+        ListConcat => "List.concat",
+        ListPrepend => "List.prepend",
+        ListAppendUnsafe => "List.append",
+        ListReserve => "List.reserve",
+        ListSublist => "List.sublist",
+        ListDropAt => "List.dropAt",
+        ListSwap => "List.swap",
+        ListReleaseExcessCapacity => "List.releaseExcessCapacity",
+        StrConcat => "Str.concat",
+        StrReserve => "Str.reserve",
+        StrTrim => "Str.trim",
+        StrTrimStart => "Str.trimStart",
+        StrTrimEnd => "Str.trimEnd",
+        StrSubstringUnsafe => "Str.substring",
+        StrReleaseExcessCapacity => "Str.releaseExcessCapacity",
+        StrFromUtf8 => "Str.fromUtf8",
+        StrToUtf8 => "Str.toUtf8",
+        _ => return None,
+    };
+
+    Some(name)
+}
+
+/// The arguments of `call` that it updates in place when they're unique
+fn updated_arguments<'a>(
+    call: &Call<'a>,
+    in_place: &MutMap<Symbol, MutSet<usize>>,
+) -> Vec<(Symbol, Callee)> {
+    match call.call_type.clone().replace_lowlevel_wrapper() {
+        CallType::LowLevel { op, .. } => match in_place_lowlevel(op) {
+            Some(name) => vec![(call.arguments[0], Callee::LowLevel(name))],
+            None => vec![],
+        },
+        CallType::ByName { name, .. } => match in_place.get(&name.name()) {
+            Some(indices) => indices
+                .iter()
+                .filter_map(|index| call.arguments.get(*index))
+                .map(|argument| (*argument, Callee::Proc(name.name())))
+                .collect(),
+            None => vec![],
+        },
+        CallType::HigherOrder(higher_order) => match higher_order.op {
+            HigherOrder::ListSortWith { xs } => vec![(xs, Callee::LowLevel("List.sortWith"))],
+            _ => vec![],
+        },
+        CallType::ByPointer { .. } | CallType::Foreign { .. } => vec![],
+    }
+}
+
+/// The arguments that `expr` takes ownership of, once for every time they're passed. Mirrors
+/// what [inc_dec](crate::inc_dec) increments the refcount for.
+fn owned_arguments(expr: &Expr<'_>) -> Vec<Symbol> {
+    match expr {
+        Expr::Tag { arguments, .. } | Expr::Struct(arguments) => arguments.to_vec(),
+        Expr::Array { elems, .. } => elems
+            .iter()
+            .filter_map(|element| match element {
+                ListLiteralElement::Literal(_) => None,
+                ListLiteralElement::Symbol(symbol) => Some(*symbol),
+            })
+            .collect(),
+        Expr::ErasedMake { value, .. } => value.iter().copied().collect(),
+        Expr::ErasedLoad {
+            symbol,
+            field: ErasedField::Value,
+        } => vec![*symbol],
+        Expr::Alloca { initializer, .. } => initializer.iter().copied().collect(),
+        Expr::Call(call) => match call.call_type.clone().replace_lowlevel_wrapper() {
+            CallType::ByName { .. } | CallType::ByPointer { .. } => call.arguments.to_vec(),
+            CallType::Foreign { .. } => vec![],
+            CallType::LowLevel { op, .. } => {
+                use LowLevel::*;
+
+                match op {
+                    // Inserted by the refcounting passes themselves
+                    PtrClearTagId | RefCountIncRcPtr | RefCountDecRcPtr | RefCountIncDataPtr
+                    | RefCountDecDataPtr | RefCountIsUnique => vec![],
+                    // Returns a reference into the list, which gets its own increment
+                    ListGetUnsafe => vec![],
+                    _ => call
+                        .arguments
+                        .iter()
+                        .zip(lowlevel_borrow_signature(op))
+                        .filter(|(_, ownership)| ownership.is_owned())
+                        .map(|(argument, _)| *argument)
+                        .collect(),
+                }
+            }
+            CallType::HigherOrder(higher_order) => {
+                use HigherOrder::*;
+
+                match higher_order.op {
+                    ListMap { xs } | ListSortWith { xs } => vec![xs],
+                    ListMap2 { xs, ys } => vec![xs, ys],
+                    ListMap3 { xs, ys, zs } => vec![xs, ys, zs],
+                    ListMap4 { xs, ys, zs, ws } => vec![xs, ys, zs, ws],
+                }
+            }
+        },
+        _ => vec![],
+    }
+}
+
+/// For every proc, the indices of the arguments that it (or something it calls) may update in
+/// place. All specializations of a proc are lumped together.
+fn in_place_params<'a>(
+    procedures: &MutMap<(Symbol, ProcLayout<'a>), Proc<'a>>,
+) -> MutMap<Symbol, MutSet<usize>> {
+    let mut in_place: MutMap<Symbol, MutSet<usize>> = MutMap::default();
+
+    let statements: Vec<_> = procedures
+        .values()
+        .map(|proc| (proc, all_statements(&proc.body)))
+        .collect();
+    let aliases: Vec<_> = statements
+        .iter()
+        .map(|(proc, stmts)| param_aliases(proc, stmts))
+        .collect();
+
+    // A proc updates an argument in place if it passes it on to something that does
+    let mut changed = true;
+    while changed {
+        changed = false;
+
+        for ((proc, stmts), aliases) in statements.iter().zip(aliases.iter()) {
+            let mut updated = MutSet::default();
+
+            for stmt in stmts {
+                if let Stmt::Let(_, Expr::Call(call), _, _) = stmt {
+                    for (argument, _) in updated_arguments(call, &in_place) {
+                        if let Some(indices) = aliases.get(&argument) {
+                            updated.extend(indices.iter().copied());
+                        }
+                    }
+                }
+            }
+
+            let entry = in_place.entry(proc.name.name()).or_default();
+            for index in updated {
+                changed |= entry.insert(index);
+            }
+        }
+    }
+
+    in_place
+}
+
+/// Which arguments of `proc` each symbol may be. Loops are join points, so their parameters can
+/// be arguments too.
+fn param_aliases<'a>(proc: &Proc<'a>, stmts: &[&Stmt<'a>]) -> MutMap<Symbol, MutSet<usize>> {
+    let mut aliases: MutMap<Symbol, MutSet<usize>> = MutMap::default();
+    for (index, (_, arg)) in proc.args.iter().enumerate() {
+        aliases.entry(*arg).or_default().insert(index);
+    }
+
+    let mut join_params: MutMap<JoinPointId, Vec<Symbol>> = MutMap::default();
+    for stmt in stmts {
+        if let Stmt::Join { id, parameters, .. } = stmt {
+            join_params.insert(*id, parameters.iter().map(|param| param.symbol).collect());
+        }
+    }
+
+    let mut changed = true;
+    while changed {
+        changed = false;
+
+        for stmt in stmts {
+            if let Stmt::Jump(id, arguments) = stmt {
+                let params = match join_params.get(id) {
+                    Some(params) => params,
+                    None => continue,
+                };
+
+                for (param, argument) in params.iter().zip(arguments.iter()) {
+                    let indices: Vec<usize> = match aliases.get(argument) {
+                        Some(indices) => indices.iter().copied().collect(),
+                        None => continue,
+                    };
+                    let entry = aliases.entry(*param).or_default();
+                    for index in indices {
+                        changed |= entry.insert(index);
+                    }
+                }
+            }
+        }
+    }
+
+    aliases
+}
+
+/// Every statement in `body`, including those in branches and join points
+fn all_statements<'a, 'b>(body: &'b Stmt<'a>) -> Vec<&'b Stmt<'a>> {
+    let mut statements = Vec::new();
+    let mut stack = vec![body];
+
+    while let Some(stmt) = stack.pop() {
+        statements.push(stmt);
+
+        match stmt {
+            Stmt::Let(_, _, _, continuation) | Stmt::Refcounting(_, continuation) => {
+                stack.push(continuation);
+            }
+            Stmt::Switch {
+                branches,
+                default_branch,
+                ..
+            } => {
+                stack.extend(branches.iter().map(|(_, _, branch)| branch));
+                stack.push(default_branch.1);
+            }
+            Stmt::Expect { remainder, .. }
+            | Stmt::ExpectFx { remainder, .. }
+            | Stmt::Dbg { remainder, .. }
+            | Stmt::SourceRegion { remainder, .. } => {
+                stack.push(remainder);
+            }
+            Stmt::Join {
+                body, remainder, ..
+            } => {
+                stack.push(body);
+                stack.push(remainder);
+            }
+            Stmt::Ret(_) | Stmt::Jump(_, _) | Stmt::Crash(_, _) => {}
+        }
+    }
+
+    statements
+}
+
+struct HintFinder<'r, 'a> {
+    interner: &'r STLayoutInterner<'a>,
+    in_place: &'r MutMap<Symbol, MutSet<usize>>,
+    layouts: MutMap<Symbol, InLayout<'a>>,
+    /// The region of the definition each hint is in, if we know it
+    found: Vec<(Option<Region>, PerfHintKind)>,
+}
+
+impl<'r, 'a> HintFinder<'r, 'a> {
+    /// `region` is the definition we're in, `shared` counts the references to a symbol that
+    /// were added on the way here and not used up yet, and `dropped` has the layouts of the
+    /// heap-allocated tag unions that were dropped.
+    fn find(
+        &mut self,
+        mut stmt: &Stmt<'a>,
+        mut region: Option<Region>,
+        mut shared: MutMap<Symbol, u64>,
+        mut dropped: Vec<UnionLayout<'a>>,
+    ) {
+        loop {
+            match stmt {
+                Stmt::Let(binding, expr, layout, continuation) => {
+                    self.layouts.insert(*binding, *layout);
+
+                    match expr {
+                        Expr::Call(call) => {
+                            if let CallType::LowLevel {
+                                op: LowLevel::ListClone,
+                                ..
+                            } = call.call_type
+                            {
+                                let value = call.arguments[0];
+                                self.found.push((region, PerfHintKind::Cloned { value }));
+                            }
+
+                            for (value, callee) in updated_arguments(call, self.in_place) {
+                                if shared.contains_key(&value) {
+                                    let kind = PerfHintKind::CopiedForUpdate { value, callee };
+                                    self.found.push((region, kind));
+                                }
+                            }
+                        }
+                        Expr::Tag {
+                            tag_layout,
+                            tag_id,
+                            reuse,
+                            ..
+                        } => {
+                            if tag_layout.tag_is_null(*tag_id) {
+                                // Nothing is allocated
+                            } else if reuse.is_some() {
+                                self.found.push((region, PerfHintKind::Reused));
+                            } else if let Some(index) =
+                                dropped.iter().position(|layout| layout == tag_layout)
+                            {
+                                // Only one hint for every value that was dropped
+                                dropped.swap_remove(index);
+                                self.found.push((region, PerfHintKind::NotReused));
+                            }
+                        }
+                        _ => {}
+                    }
+
+                    // Whatever takes ownership uses up one of the extra references, so after
+                    // `inc x; f x` the next update of `x` may well be in place
+                    for symbol in owned_arguments(expr) {
+                        use_reference(&mut shared, symbol);
+                    }
+
+                    stmt = *continuation;
+                }
+                Stmt::Refcounting(modify_rc, continuation) => {
+                    match modify_rc {
+                        ModifyRc::Inc(symbol, count) => {
+                            *shared.entry(*symbol).or_default() += count;
+                        }
+                        ModifyRc::Dec(symbol)
+                        | ModifyRc::DecRef(symbol)
+                        | ModifyRc::Free(symbol) => {
+                            use_reference(&mut shared, *symbol);
+
+                            if let Some(union_layout) = self.heap_union(*symbol) {
+                                dropped.push(union_layout);
+                            }
+                        }
+                    }
+
+                    stmt = *continuation;
+                }
+                Stmt::Switch {
+                    branches,
+                    default_branch,
+                    ..
+                } => {
+                    for (_, _, branch) in branches.iter() {
+                        self.find(branch, region, shared.clone(), dropped.clone());
+                    }
+
+                    stmt = default_branch.1;
+                }
+                Stmt::Expect { remainder, .. }
+                | Stmt::ExpectFx { remainder, .. }
+                | Stmt::Dbg { remainder, .. } => {
+                    stmt = *remainder;
+                }
+                Stmt::SourceRegion {
+                    region: new_region,
+                    remainder,
+                } => {
+                    region = Some(*new_region);
+                    stmt = *remainder;
+                }
+                Stmt::Join {
+                    parameters,
+                    body,
+                    remainder,
+                    ..
+                } => {
+                    for param in parameters.iter() {
+                        self.layouts.insert(param.symbol, param.layout);
+                    }
+
+                    // A join point can be jumped to from anywhere, so we know nothing about its
+                    // parameters
+                    self.find(body, region, MutMap::default(), Vec::new());
+
+                    stmt = *remainder;
+                }
+                Stmt::Ret(_) | Stmt::Jump(_, _) | Stmt::Crash(_, _) => return,
+            }
+        }
+    }
+
+    /// The layout of `symbol` if it's a tag union on the heap
+    fn heap_union(&self, symbol: Symbol) -> Option<UnionLayout<'a>> {
+        let layout = self.layouts.get(&symbol)?;
+
+        match self.interner.chase_recursive(*layout) {
+            LayoutRepr::Union(UnionLayout::NonRecursive(_)) => None,
+            LayoutRepr::Union(union_layout) => Some(union_layout),
+            _ => None,
+        }
+    }
+}
+
+/// One of the extra references to `symbol` in `shared` is gone
+fn use_reference(shared: &mut MutMap<Symbol, u64>, symbol: Symbol) {
+    if let Some(count) = shared.get_mut(&symbol) {
+        *count -= 1;
+        if *count == 0 {
+            shared.remove(&symbol);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_hints {
+    use bumpalo::Bump;
+    use roc_collections::MutMap;
+    use roc_module::low_level::LowLevel;
+    use roc_module::symbol::{IdentIds, ModuleIds, Symbol};
+    use roc_region::all::Region;
+    use roc_target::Target;
+
+    use super::{find_perf_hints, Callee, PerfHintKind};
+    use crate::ir::{
+        Call, CallSpecId, CallType, Expr, ModifyRc, Proc, ProcLayout, ReuseToken, SelfRecursive,
+        Stmt, UpdateModeId,
+    };
+    use crate::layout::{
+        InLayout, LambdaName, Layout, LayoutInterner, LayoutRepr, Niche, STLayoutInterner,
+        UnionLayout,
+    };
+
+    const TREE: UnionLayout<'static> = UnionLayout::NonNullableUnwrapped(&[Layout::I64]);
+
+    /// Builds the body of one proc of a user module, and finds its hints
+    struct Test<'a> {
+        arena: &'a Bump,
+        interner: STLayoutInterner<'a>,
+        symbols: [Symbol; 8],
+    }
+
+    impl<'a> Test<'a> {
+        fn new(arena: &'a Bump) -> Self {
+            let home = ModuleIds::default().get_or_insert(&"Test".into());
+            let mut ident_ids = IdentIds::default();
+            let symbols = [(); 8].map(|_| Symbol::new(home, ident_ids.gen_unique()));
+
+            Self {
+                arena,
+                interner: STLayoutInterner::with_capacity(4, Target::LinuxX64),
+                symbols,
+            }
+        }
+
+        fn tree(&mut self) -> InLayout<'a> {
+            self.interner
+                .insert_direct_no_semantic(LayoutRepr::Union(TREE))
+        }
+
+        fn hints(&self, args: &[(InLayout<'a>, Symbol)], body: Stmt<'a>) -> Vec<PerfHintKind> {
+            let name = self.symbols[0];
+            let proc = Proc {
+                name: LambdaName::no_niche(name),
+                args: self.arena.alloc_slice_copy(args),
+                body,
+                closure_data_layout: None,
+                ret_layout: Layout::I64,
+                is_self_recursive: SelfRecursive::NotSelfRecursive,
+                is_erased: false,
+            };
+            let proc_layout = ProcLayout {
+                arguments: self
+                    .arena
+                    .alloc_slice_fill_iter(args.iter().map(|(layout, _)| *layout)),
+                result: Layout::I64,
+                niche: Niche::NONE,
+            };
+
+            let mut procedures = MutMap::default();
+            procedures.insert((name, proc_layout), proc);
+            let mut definition_regions = MutMap::default();
+            definition_regions.insert(name, Region::zero());
+
+            find_perf_hints(&self.interner, &procedures, &definition_regions)
+                .into_iter()
+                .map(|hint| hint.kind)
+                .collect()
+        }
+
+        fn stmt(&self, stmt: Stmt<'a>) -> &'a Stmt<'a> {
+            self.arena.alloc(stmt)
+        }
+
+        fn call(&self, call_type: CallType<'a>, arguments: &[Symbol]) -> Expr<'a> {
+            Expr::Call(Call {
+                call_type,
+                arguments: self.arena.alloc_slice_copy(arguments),
+            })
+        }
+
+        fn lowlevel(&self, op: LowLevel, arguments: &[Symbol]) -> Expr<'a> {
+            let call_type = CallType::LowLevel {
+                op,
+                update_mode: UpdateModeId::BACKEND_DUMMY,
+            };
+            self.call(call_type, arguments)
+        }
+
+        fn by_name(&self, name: Symbol, arguments: &[Symbol]) -> Expr<'a> {
+            let call_type = CallType::ByName {
+                name: LambdaName::no_niche(name),
+                ret_layout: Layout::I64,
+                arg_layouts: self
+                    .arena
+                    .alloc_slice_fill_iter(arguments.iter().map(|_| Layout::LIST_U8)),
+                specialization_id: CallSpecId::BACKEND_DUMMY,
+            };
+            self.call(call_type, arguments)
+        }
+    }
+
+    #[test]
+    fn cloned() {
+        let arena = Bump::new();
+        let test = Test::new(&arena);
+        let [_, list, cloned, ..] = test.symbols;
+
+        // let cloned = List.clone list; ret cloned
+        let body = Stmt::Let(
+            cloned,
+            test.lowlevel(LowLevel::ListClone, &[list]),
+            Layout::LIST_U8,
+            test.stmt(Stmt::Ret(cloned)),
+        );
+
+        assert_eq!(
+            test.hints(&[(Layout::LIST_U8, list)], body),
+            [PerfHintKind::Cloned { value: list }]
+        );
+    }
+
+    #[test]
+    fn reused() {
+        let arena = Bump::new();
+        let mut test = Test::new(&arena);
+        let tree = test.tree();
+        let [_, old, token, value, new, ..] = test.symbols;
+
+        // let new = Reuse token TagId(0) value; ret new
+        let reuse = ReuseToken {
+            symbol: token,
+            update_tag_id: false,
+            update_mode: UpdateModeId::BACKEND_DUMMY,
+        };
+        let body = Stmt::Let(
+            new,
+            Expr::Tag {
+                tag_layout: TREE,
+                tag_id: 0,
+                arguments: arena.alloc([value]),
+                reuse: Some(reuse),
+            },
+            tree,
+            test.stmt(Stmt::Ret(new)),
+        );
+
+        assert_eq!(
+            test.hints(&[(tree, old), (Layout::I64, value)], body),
+            [PerfHintKind::Reused]
+        );
+    }
+
+    #[test]
+    fn not_reused() {
+        let arena = Bump::new();
+        let mut test = Test::new(&arena);
+        let tree = test.tree();
+        let [_, old, value, new, ..] = test.symbols;
+
+        // dec old; let new = TagId(0) value; ret new
+        let body = Stmt::Refcounting(
+            ModifyRc::Dec(old),
+            test.stmt(Stmt::Let(
+                new,
+                Expr::Tag {
+                    tag_layout: TREE,
+                    tag_id: 0,
+                    arguments: arena.alloc([value]),
+                    reuse: None,
+                },
+                tree,
+                test.stmt(Stmt::Ret(new)),
+            )),
+        );
+
+        assert_eq!(
+            test.hints(&[(tree, old), (Layout::I64, value)], body),
+            [PerfHintKind::NotReused]
+        );
+    }
+
+    /// `inc list; let updated = List.append list elem; let n = f list; ...`
+    fn update_then_call<'a>(test: &Test<'a>, update_first: bool) -> Vec<PerfHintKind> {
+        let [_, list, elem, updated, n, f, ..] = test.symbols;
+
+        let update = |continuation| {
+            Stmt::Let(
+                updated,
+                test.lowlevel(LowLevel::ListAppendUnsafe, &[list, elem]),
+                Layout::LIST_U8,
+                test.stmt(continuation),
+            )
+        };
+        let call = |continuation| {
+            Stmt::Let(
+                n,
+                test.by_name(f, &[list]),
+                Layout::I64,
+                test.stmt(continuation),
+            )
+        };
+
+        let ret = Stmt::Ret(n);
+        let uses = if update_first {
+            update(call(ret))
+        } else {
+            call(update(ret))
+        };
+        let body = Stmt::Refcounting(ModifyRc::Inc(list, 1), test.stmt(uses));
+
+        test.hints(&[(Layout::LIST_U8, list), (Layout::U8, elem)], body)
+    }
+
+    #[test]
+    fn copied_for_update_while_shared() {
+        let arena = Bump::new();
+        let test = Test::new(&arena);
+        let list = test.symbols[1];
+
+        assert_eq!(
+            update_then_call(&test, true),
+            [PerfHintKind::CopiedForUpdate {
+                value: list,
+                callee: Callee::LowLevel("List.append"),
+            }]
+        );
+    }
+
+    #[test]
+    fn not_copied_once_the_extra_reference_is_used_up() {
+        let arena = Bump::new();
+        let test = Test::new(&arena);
+
+        // `f` takes ownership of the reference that `inc` added, so the update may be in place
+        assert_eq!(update_then_call(&test, false), []);
+    }
+}
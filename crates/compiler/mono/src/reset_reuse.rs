@@ -675,6 +675,22 @@ fn insert_reset_reuse_operations_stmt<'a, 'i>(
                 remainder: new_remainder,
             })
         }
+        Stmt::SourceRegion { region, remainder } => {
+            let new_remainder = insert_reset_reuse_operations_stmt(
+                arena,
+                layout_interner,
+                home,
+                ident_ids,
+                update_mode_ids,
+                environment,
+                remainder,
+            );
+
+            arena.alloc(Stmt::SourceRegion {
+                region: *region,
+                remainder: new_remainder,
+            })
+        }
         Stmt::Join {
             id: joinpoint_id,
             parameters,
@@ -353,6 +353,21 @@ fn insert_jumps<'a>(
             None => None,
         },
 
+        SourceRegion { region, remainder } => match insert_jumps(
+            arena,
+            remainder,
+            goal_id,
+            needle,
+            needle_arguments,
+            needle_result,
+        ) {
+            Some(cont) => Some(arena.alloc(SourceRegion {
+                region: *region,
+                remainder: cont,
+            })),
+            None => None,
+        },
+
         Expect {
             condition,
             region,
@@ -553,7 +568,10 @@ fn trmc_candidates_help(
         Stmt::Refcounting(_, next) => trmc_candidates_help(function_name, next, candidates),
         Stmt::Expect { remainder, .. }
         | Stmt::ExpectFx { remainder, .. }
-        | Stmt::Dbg { remainder, .. } => trmc_candidates_help(function_name, remainder, candidates),
+        | Stmt::Dbg { remainder, .. }
+        | Stmt::SourceRegion { remainder, .. } => {
+            trmc_candidates_help(function_name, remainder, candidates)
+        }
         Stmt::Join {
             body, remainder, ..
         } => {
@@ -1036,6 +1054,10 @@ impl<'a> TrmcEnv<'a> {
                 variable: *variable,
                 remainder: arena.alloc(self.walk_stmt(env, remainder)),
             },
+            Stmt::SourceRegion { region, remainder } => Stmt::SourceRegion {
+                region: *region,
+                remainder: arena.alloc(self.walk_stmt(env, remainder)),
+            },
             Stmt::Join {
                 id,
                 parameters,
@@ -1133,7 +1155,7 @@ fn stmt_contains_symbol_nonrec(stmt: &Stmt, needle: Symbol) -> bool {
             condition, lookups, ..
         } => needle == *condition || lookups.contains(&needle),
         Stmt::Dbg { symbol, .. } => needle == *symbol,
-        Stmt::Join { .. } => false,
+        Stmt::SourceRegion { .. } | Stmt::Join { .. } => false,
         Stmt::Jump(_, arguments) => arguments.contains(&needle),
         Stmt::Crash(symbol, _) => needle == *symbol,
     }
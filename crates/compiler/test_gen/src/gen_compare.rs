@@ -190,6 +190,35 @@ fn record() {
     );
 }
 
+#[test]
+#[cfg(any(feature = "gen-llvm", feature = "gen-wasm"))]
+fn large_int_record() {
+    // No padding, so the Wasm backend compares these as bytes, 16 at a time
+    let record = "{ a: 1u64, b: 2u64, c: 3u32, d: 4u16, e: 5u8, f: 6u8 }";
+    assert_evals_to!(&format!("{record} == {record}"), true, bool);
+    assert_evals_to!(&format!("{record} != {record}"), false, bool);
+
+    for changed in [
+        "{ a: 1u64, b: 7u64, c: 3u32, d: 4u16, e: 5u8, f: 6u8 }",
+        "{ a: 1u64, b: 2u64, c: 7u32, d: 4u16, e: 5u8, f: 6u8 }",
+        "{ a: 1u64, b: 2u64, c: 3u32, d: 4u16, e: 5u8, f: 7u8 }",
+    ] {
+        assert_evals_to!(&format!("{record} == {changed}"), false, bool);
+        assert_evals_to!(&format!("{record} != {changed}"), true, bool);
+    }
+}
+
+#[test]
+#[cfg(any(feature = "gen-llvm", feature = "gen-wasm"))]
+fn large_record_with_floats() {
+    // Floats can't be compared as bytes
+    assert_evals_to!(
+        "{ a: 1u64, b: 0.0f64, c: 3i128 } == { a: 1u64, b: -0.0f64, c: 3i128 }",
+        true,
+        bool
+    );
+}
+
 #[test]
 #[cfg(any(feature = "gen-llvm", feature = "gen-wasm"))]
 fn unit() {
@@ -561,26 +561,22 @@ fn i64_abs() {
 }
 
 #[test]
-#[cfg(any(feature = "gen-llvm", feature = "gen-wasm"))]
+#[cfg(any(feature = "gen-llvm", feature = "gen-wasm", feature = "gen-dev"))]
 fn various_sized_abs() {
     assert_evals_to!("Num.abs -6i8", 6, i8);
     assert_evals_to!("Num.abs -6i16", 6, i16);
     assert_evals_to!("Num.abs -6i32", 6, i32);
     assert_evals_to!("Num.abs -6i64", 6, i64);
-    if !cfg!(feature = "gen-wasm") {
-        assert_evals_to!("Num.abs -6i128", 6, i128);
-    }
+    assert_evals_to!("Num.abs -6i128", 6, i128);
     assert_evals_to!("Num.abs 6u8", 6, u8);
     assert_evals_to!("Num.abs 6u16", 6, u16);
     assert_evals_to!("Num.abs 6u32", 6, u32);
     assert_evals_to!("Num.abs 6u64", 6, u64);
-    if !cfg!(feature = "gen-wasm") {
-        assert_evals_to!("Num.abs 6u128", 6, u128);
-    }
+    assert_evals_to!("Num.abs 6u128", 6, u128);
 }
 
 #[test]
-#[cfg(any(feature = "gen-llvm", feature = "gen-wasm"))]
+#[cfg(any(feature = "gen-llvm", feature = "gen-wasm", feature = "gen-dev"))]
 #[should_panic(
     expected = r#"Roc failed with message: "Integer absolute overflowed because its argument is the minimum value"#
 )]
@@ -596,6 +592,53 @@ fn abs_min_int_overflow() {
     );
 }
 
+#[test]
+#[cfg(any(feature = "gen-llvm", feature = "gen-wasm", feature = "gen-dev"))]
+fn neg_i128() {
+    assert_evals_to!("Num.neg 123i128", -123, i128);
+    assert_evals_to!("Num.neg Num.maxI128", -i128::MAX, i128);
+    assert_evals_to!(
+        "Num.neg -18446744073709551616i128",
+        18446744073709551616,
+        i128
+    );
+    assert_evals_to!(
+        "Num.neg 18446744073709551617i128",
+        -18446744073709551617,
+        i128
+    );
+}
+
+#[test]
+#[cfg(any(feature = "gen-llvm", feature = "gen-wasm", feature = "gen-dev"))]
+fn abs_i128() {
+    assert_evals_to!(
+        "Num.abs -18446744073709551617i128",
+        18446744073709551617,
+        i128
+    );
+    assert_evals_to!("Num.abs (Num.minI128 + 1)", i128::MAX, i128);
+    assert_evals_to!("Num.abs Num.maxU128", u128::MAX, u128);
+}
+
+#[test]
+#[cfg(any(feature = "gen-llvm", feature = "gen-wasm", feature = "gen-dev"))]
+#[should_panic(
+    expected = r#"Roc failed with message: "Integer negation overflowed because its argument is the minimum value"#
+)]
+fn neg_min_i128_overflow() {
+    assert_evals_to!("Num.neg Num.minI128", 0, i128);
+}
+
+#[test]
+#[cfg(any(feature = "gen-llvm", feature = "gen-wasm", feature = "gen-dev"))]
+#[should_panic(
+    expected = r#"Roc failed with message: "Integer absolute overflowed because its argument is the minimum value"#
+)]
+fn abs_min_i128_overflow() {
+    assert_evals_to!("Num.abs Num.minI128", 0, i128);
+}
+
 #[test]
 #[cfg(any(feature = "gen-llvm", feature = "gen-dev", feature = "gen-wasm"))]
 fn gen_if_fn() {
@@ -635,7 +678,7 @@ fn gen_float_eq() {
 }
 
 #[test]
-#[cfg(any(feature = "gen-llvm", feature = "gen-wasm"))]
+#[cfg(any(feature = "gen-llvm", feature = "gen-wasm", feature = "gen-dev"))]
 fn gen_add_dec() {
     assert_evals_to!(
         indoc!(
@@ -1624,6 +1667,18 @@ fn int_negate() {
     assert_evals_to!("Num.neg (Num.minI64 + 1)", i64::MAX, i64);
 }
 
+#[test]
+#[cfg(any(feature = "gen-llvm", feature = "gen-wasm", feature = "gen-dev"))]
+fn float_negate() {
+    assert_evals_to!("Num.neg 1.5f64", -1.5, f64);
+    assert_evals_to!("Num.neg -2.5f32", 2.5, f32);
+
+    // negation flips the sign bit, so zero becomes negative zero and back
+    assert_evals_to!("Num.neg 0.0f64", (-0.0f64).to_bits(), f64, f64::to_bits);
+    assert_evals_to!("Num.neg -0.0f64", 0.0f64.to_bits(), f64, f64::to_bits);
+    assert_evals_to!("Num.neg 0.0f32", (-0.0f32).to_bits(), f32, f32::to_bits);
+}
+
 #[test]
 #[cfg(any(feature = "gen-llvm", feature = "gen-wasm"))]
 #[should_panic(
@@ -1815,6 +1870,22 @@ fn int_compare() {
     assert_evals_to!("Num.compare 1 0", RocOrder::Gt, RocOrder);
 }
 
+#[test]
+#[cfg(any(feature = "gen-llvm", feature = "gen-wasm", feature = "gen-dev"))]
+fn i128_compare() {
+    assert_evals_to!("Num.compare -1i128 1i128", RocOrder::Lt, RocOrder);
+    assert_evals_to!(
+        "Num.compare Num.maxI128 Num.maxI128",
+        RocOrder::Eq,
+        RocOrder
+    );
+    assert_evals_to!("Num.compare Num.maxU128 1u128", RocOrder::Gt, RocOrder);
+    assert_evals_to!("-18446744073709551616i128 < 1i128", true, bool);
+    assert_evals_to!("Num.maxU128 > 1u128", true, bool);
+    assert_evals_to!("Num.maxU128 <= 1u128", false, bool);
+    assert_evals_to!("Num.minI128 >= Num.minI128", true, bool);
+}
+
 #[test]
 #[cfg(any(feature = "gen-llvm", feature = "gen-wasm", feature = "gen-dev"))]
 fn float_compare() {
@@ -2192,6 +2263,21 @@ fn shift_right_cast_i8() {
     );
 }
 
+#[test]
+#[cfg(any(feature = "gen-llvm", feature = "gen-wasm", feature = "gen-dev"))]
+fn shift_i128() {
+    assert_evals_to!("Num.shiftLeftBy 1i128 100", 1i128 << 100, i128);
+    assert_evals_to!("Num.shiftLeftBy 0xffffu128 120", 0xffffu128 << 120, u128);
+    assert_evals_to!("Num.shiftRightBy -18446744073709551616i128 64", -1, i128);
+    assert_evals_to!("Num.shiftRightBy Num.minI128 127", -1, i128);
+    assert_evals_to!(
+        "Num.shiftRightBy 0x8000_0000_0000_0000_0000_0000_0000_0000u128 64",
+        0xffff_ffff_ffff_ffff_8000_0000_0000_0000u128,
+        u128
+    );
+    assert_evals_to!("Num.shiftRightZfBy -1i128 64", u64::MAX as i128, i128);
+}
+
 #[test]
 #[cfg(any(feature = "gen-llvm", feature = "gen-wasm", feature = "gen-dev"))]
 fn min_i128() {
@@ -2355,6 +2441,7 @@ num_conversion_tests! {
         to_i32_sign_extend_i16, "-15i16", -15, ["gen-wasm", "gen-dev"]
         to_i32_truncate, "115i64", 115, ["gen-wasm", "gen-dev"]
         to_i32_truncate_wraps, "5000000000i64", 705032704, ["gen-wasm", "gen-dev"]
+        to_i32_truncate_i128, "-15i128", -15, ["gen-wasm", "gen-dev"]
     )
     "Num.toI64", i64, (
         to_i64_same_width, "15u64", 15, ["gen-wasm", "gen-dev"]
@@ -2362,17 +2449,20 @@ num_conversion_tests! {
         to_i64_sign_extend_i8, "-15i8", -15, ["gen-wasm", "gen-dev"]
         to_i64_sign_extend_i16, "-15i16", -15, ["gen-wasm", "gen-dev"]
         to_i64_sign_extend_i32, "-15i32", -15, ["gen-wasm", "gen-dev"]
-        to_i64_truncate, "115i128", 115
-        to_i64_truncate_wraps, "10_000_000_000_000_000_000i128", -8446744073709551616
+        to_i64_truncate, "115i128", 115, ["gen-wasm", "gen-dev"]
+        to_i64_truncate_wraps, "10_000_000_000_000_000_000i128", -8446744073709551616, ["gen-wasm", "gen-dev"]
     )
     "Num.toI128", i128, (
-        to_i128_same_width, "15u128", 15, ["gen-dev"]
-        to_i128_extend, "15i8", 15
+        to_i128_same_width, "15u128", 15, ["gen-wasm", "gen-dev"]
+        to_i128_extend, "15i8", 15, ["gen-wasm", "gen-dev"]
+        to_i128_sign_extend_i8, "-15i8", -15, ["gen-wasm", "gen-dev"]
+        to_i128_sign_extend_i64, "-15i64", -15, ["gen-wasm", "gen-dev"]
     )
     "Num.toU8", u8, (
         to_u8_same_width, "15i8", 15, ["gen-wasm", "gen-dev"]
         to_u8_truncate, "115i32", 115, ["gen-wasm", "gen-dev"]
         to_u8_truncate_wraps, "500i32", 244, ["gen-wasm", "gen-dev"]
+        to_u8_truncate_wraps_i128, "500i128", 244, ["gen-wasm", "gen-dev"]
     )
     "Num.toU16", u16, (
         to_u16_same_width, "15i16", 15, ["gen-wasm", "gen-dev"]
@@ -2389,25 +2479,25 @@ num_conversion_tests! {
     "Num.toU64", u64, (
         to_u64_same_width, "15i64", 15, ["gen-wasm", "gen-dev"]
         to_u64_extend, "15i8", 15, ["gen-wasm", "gen-dev"]
-        to_u64_truncate, "115i128", 115
-        to_u64_truncate_wraps, "10_000_000_000_000_000_000_000i128", 1864712049423024128
+        to_u64_truncate, "115i128", 115, ["gen-wasm", "gen-dev"]
+        to_u64_truncate_wraps, "10_000_000_000_000_000_000_000i128", 1864712049423024128, ["gen-wasm", "gen-dev"]
     )
     "Num.toU128", u128, (
-        to_u128_same_width, "15i128", 15, ["gen-dev"]
-        to_u128_extend, "15i8", 15
-        to_u128_big, "11562537357600483583u64", 11562537357600483583, ["gen-dev"]
+        to_u128_same_width, "15i128", 15, ["gen-wasm", "gen-dev"]
+        to_u128_extend, "15i8", 15, ["gen-wasm", "gen-dev"]
+        to_u128_big, "11562537357600483583u64", 11562537357600483583, ["gen-wasm", "gen-dev"]
     )
     "Num.toF32", f32, (
         to_f32_from_i8, "15i8", 15.0, ["gen-wasm", "gen-dev"]
         to_f32_from_i16, "15i16", 15.0, ["gen-wasm", "gen-dev"]
         to_f32_from_i32, "15i32", 15.0, ["gen-wasm", "gen-dev"]
         to_f32_from_i64, "15i64", 15.0, ["gen-wasm", "gen-dev"]
-        to_f32_from_i128, "15i128", 15.0, ["gen-dev"]
+        to_f32_from_i128, "15i128", 15.0, ["gen-wasm", "gen-dev"]
         to_f32_from_u8, "15u8", 15.0, ["gen-wasm", "gen-dev"]
         to_f32_from_u16, "15u16", 15.0, ["gen-wasm", "gen-dev"]
         to_f32_from_u32, "15u32", 15.0, ["gen-wasm", "gen-dev"]
         to_f32_from_u64, "15u64", 15.0, ["gen-wasm", "gen-dev"]
-        to_f32_from_u128, "15u128", 15.0, ["gen-dev"]
+        to_f32_from_u128, "15u128", 15.0, ["gen-wasm", "gen-dev"]
         to_f32_from_f32, "1.5f32", 1.5, ["gen-wasm", "gen-dev"]
         to_f32_from_f64, "1.5f64", 1.5, ["gen-wasm", "gen-dev"]
     )
@@ -2416,13 +2506,13 @@ num_conversion_tests! {
         to_f64_from_i16, "15i16", 15.0, ["gen-wasm", "gen-dev"]
         to_f64_from_i32, "15i32", 15.0, ["gen-wasm", "gen-dev"]
         to_f64_from_i64, "15i64", 15.0, ["gen-wasm", "gen-dev"]
-        to_f64_from_i128, "15i128", 15.0, ["gen-dev"]
+        to_f64_from_i128, "15i128", 15.0, ["gen-wasm", "gen-dev"]
         to_f64_from_u8, "15u8", 15.0, ["gen-wasm", "gen-dev"]
         to_f64_from_u16, "15u16", 15.0, ["gen-wasm", "gen-dev"]
         to_f64_from_u32, "15u32", 15.0, ["gen-wasm", "gen-dev"]
         to_f64_from_u64, "15u64", 15.0, ["gen-wasm", "gen-dev"]
-        to_f64_from_u128, "15u128", 15.0, ["gen-dev"]
-        to_f64_from_f32, "1.5f32", 1.5, ["gen-dev"]
+        to_f64_from_u128, "15u128", 15.0, ["gen-wasm", "gen-dev"]
+        to_f64_from_f32, "1.5f32", 1.5, ["gen-wasm", "gen-dev"]
         to_f64_from_f64, "1.5f64", 1.5, ["gen-wasm", "gen-dev"]
     )
 }
@@ -3330,6 +3420,112 @@ fn round_to_u32() {
     );
 }
 
+#[test]
+#[cfg(any(feature = "gen-llvm", feature = "gen-wasm", feature = "gen-dev"))]
+fn round_to_i128() {
+    assert_evals_to!(
+        indoc!(
+            r"
+            n : I128
+            n = Num.round -1.0e30f64
+            n
+            "
+        ),
+        -1.0e30f64 as i128,
+        i128
+    );
+}
+
+#[test]
+#[cfg(any(feature = "gen-llvm", feature = "gen-wasm", feature = "gen-dev"))]
+fn ceiling_and_floor_to_i128() {
+    assert_evals_to!(
+        indoc!(
+            r"
+            n : I128
+            n = Num.ceiling 1.5e20f64
+            n
+            "
+        ),
+        1.5e20f64.ceil() as i128,
+        i128
+    );
+    assert_evals_to!(
+        indoc!(
+            r"
+            n : I128
+            n = Num.floor -1.0e20f32
+            n
+            "
+        ),
+        (-1.0e20f32).floor() as i128,
+        i128
+    );
+}
+
+#[test]
+#[cfg(any(feature = "gen-llvm", feature = "gen-wasm", feature = "gen-dev"))]
+fn pow_int_i128() {
+    assert_evals_to!("Num.powInt 2i128 100", 1i128 << 100, i128);
+    assert_evals_to!("Num.powInt 3u128 80", 3u128.pow(80), u128);
+}
+
+#[test]
+#[cfg(any(feature = "gen-llvm", feature = "gen-wasm", feature = "gen-dev"))]
+fn div_trunc_and_rem_i128() {
+    assert_evals_to!(
+        "Num.divTrunc 100000000000000000000000i128 -7",
+        100000000000000000000000i128 / -7,
+        i128
+    );
+    assert_evals_to!("Num.divTrunc Num.maxU128 3", u128::MAX / 3, u128);
+    assert_evals_to!("Num.rem -100000000000000000000001i128 10", -1, i128);
+    assert_evals_to!("Num.rem Num.maxU128 10", u128::MAX % 10, u128);
+}
+
+#[test]
+#[cfg(any(feature = "gen-llvm", feature = "gen-wasm", feature = "gen-dev"))]
+fn bitwise_i128() {
+    assert_evals_to!(
+        "Num.bitwiseAnd Num.maxI128 -18446744073709551616i128",
+        i128::MAX & -(1i128 << 64),
+        i128
+    );
+    assert_evals_to!(
+        "Num.bitwiseOr 0xffff_0000_0000_0000_0000_0000_0000_0000u128 0xffffu128",
+        0xffff_0000_0000_0000_0000_0000_0000_ffffu128,
+        u128
+    );
+    assert_evals_to!("Num.bitwiseXor -1i128 Num.maxI128", i128::MIN, i128);
+}
+
+#[test]
+#[cfg(any(feature = "gen-wasm", feature = "gen-dev"))]
+fn to_float_from_dec() {
+    assert_evals_to!("Num.toF64 1.5dec", 1.5, f64);
+    assert_evals_to!("Num.toF32 -2.25dec", -2.25, f32);
+}
+
+#[test]
+#[cfg(feature = "gen-wasm")]
+fn to_float_checked() {
+    assert_evals_to!(
+        "Num.toF32Checked 1.5f64",
+        RocResult::ok(1.5),
+        RocResult<f32, ()>
+    );
+    assert_evals_to!(
+        "Num.toF32Checked Num.maxF64",
+        RocResult::err(()),
+        RocResult<f32, ()>
+    );
+    assert_evals_to!(
+        "Num.toF64Checked Num.maxI128",
+        RocResult::ok(i128::MAX as f64),
+        RocResult<f64, ()>
+    );
+}
+
 #[test]
 #[cfg(any(feature = "gen-llvm", feature = "gen-wasm", feature = "gen-dev"))]
 fn promote_u64_number_layout() {
@@ -3496,7 +3692,7 @@ fn num_abs_diff_int() {
 }
 
 #[test]
-#[cfg(feature = "gen-llvm")]
+#[cfg(any(feature = "gen-llvm", feature = "gen-wasm", feature = "gen-dev"))]
 fn num_abs_diff_large_bits() {
     assert_evals_to!(r"Num.absDiff 0u128 0u128", 0, u128);
     assert_evals_to!(r"Num.absDiff 1u128 2u128", 1, u128);
@@ -3529,7 +3725,7 @@ fn num_abs_int_min_overflow() {
 }
 
 #[test]
-#[cfg(feature = "gen-llvm")]
+#[cfg(any(feature = "gen-llvm", feature = "gen-wasm", feature = "gen-dev"))]
 #[should_panic(expected = r#"Roc failed with message: "Integer subtraction overflowed!"#)]
 fn num_abs_large_bits_min_overflow() {
     assert_evals_to!(r"Num.absDiff Num.minI128 0", 0, i128);
@@ -1,6 +1,7 @@
 use libloading::Library;
 use roc_build::link::{link, LinkType};
-use roc_load::{EntryPoint, ExecutionMode, LoadConfig, Threading};
+use roc_load::{EntryPoint, ExecutionMode, LoadConfig, OutputConfig, Threading};
+use roc_mono::inline::InlineLevel;
 use roc_mono::ir::CrashTag;
 use roc_mono::ir::SingleEntryPoint;
 use roc_packaging::cache::RocCacheDir;
@@ -30,6 +31,17 @@ fn promote_expr_to_module(src: &str) -> String {
     buffer
 }
 
+/// Set ROC_DEV_REGISTER_ALLOCATION=linear-scan to run the whole suite with that allocator
+fn register_allocation() -> roc_gen_dev::RegisterAllocation {
+    match std::env::var("ROC_DEV_REGISTER_ALLOCATION").as_deref() {
+        Err(_) | Ok("greedy") => roc_gen_dev::RegisterAllocation::Greedy,
+        Ok("linear-scan") => roc_gen_dev::RegisterAllocation::LinearScan,
+        Ok(other) => panic!(
+            "unknown ROC_DEV_REGISTER_ALLOCATION {other:?}, expected \"greedy\" or \"linear-scan\""
+        ),
+    }
+}
+
 #[allow(dead_code)]
 pub fn helper(
     arena: &bumpalo::Bump,
@@ -62,6 +74,10 @@ pub fn helper(
         threading: Threading::Single,
         exec_mode: ExecutionMode::Executable,
         function_kind: FunctionKind::LambdaSet,
+        output: OutputConfig {
+            inline_level: InlineLevel::Normal,
+            ..OutputConfig::default()
+        },
     };
     let loaded = roc_load::load_and_monomorphize_from_str(
         arena,
@@ -195,6 +211,7 @@ pub fn helper(
         exposed_to_host: exposed_to_host.top_level_values.keys().copied().collect(),
         lazy_literals,
         mode: roc_gen_dev::AssemblyBackendMode::Test,
+        register_allocation: register_allocation(),
     };
 
     let target = target_lexicon::Triple::host().into();
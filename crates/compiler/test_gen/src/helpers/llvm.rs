@@ -10,7 +10,8 @@ use roc_command_utils::zig;
 use roc_gen_llvm::llvm::externs::add_default_roc_externs;
 use roc_gen_llvm::{llvm::build::LlvmBackendMode, run_roc::RocCallResult};
 use roc_load::{
-    EntryPoint, ExecutionMode, FunctionKind, LoadConfig, LoadMonomorphizedError, Threading,
+    EntryPoint, ExecutionMode, FunctionKind, LoadConfig, LoadMonomorphizedError, OutputConfig,
+    Threading,
 };
 use roc_mono::ir::{CrashTag, OptLevel, SingleEntryPoint};
 use roc_packaging::cache::RocCacheDir;
@@ -76,6 +77,10 @@ fn create_llvm_module<'a>(
         palette: DEFAULT_PALETTE,
         threading: Threading::Single,
         exec_mode: ExecutionMode::Executable,
+        output: OutputConfig {
+            inline_level: config.opt_level.into(),
+            ..OutputConfig::default()
+        },
     };
     let loaded = roc_load::load_and_monomorphize_from_str(
         arena,
@@ -229,6 +234,8 @@ fn create_llvm_module<'a>(
         mode: config.mode,
         // important! we don't want any procedures to get the C calling convention
         exposed_to_host: MutSet::default(),
+        debug_sources: None,
+        instrument_memory: None,
     };
 
     // Add roc_alloc, roc_realloc, and roc_dealloc, since the repl has no
@@ -4,7 +4,8 @@ use bumpalo::Bump;
 use roc_collections::all::MutSet;
 use roc_gen_wasm::wasm32_result::Wasm32Result;
 use roc_gen_wasm::DEBUG_SETTINGS;
-use roc_load::{ExecutionMode, LoadConfig, Threading};
+use roc_load::{ExecutionMode, LoadConfig, OutputConfig, Threading};
+use roc_mono::inline::InlineLevel;
 use roc_packaging::cache::RocCacheDir;
 use roc_reporting::report::DEFAULT_PALETTE_HTML;
 use roc_solve::FunctionKind;
@@ -56,8 +57,14 @@ pub fn compile_to_wasm_bytes<'a, T: Wasm32Result>(
     let platform_bytes = include_bytes!(host_bytes_path!());
     println!("Loading test host {}", host_bytes_path!());
 
-    let compiled_bytes =
-        compile_roc_to_wasm_bytes(arena, platform_bytes, src, test_wrapper_type_info);
+    let emit_source_map = false;
+    let compiled_bytes = compile_roc_to_wasm_bytes(
+        arena,
+        platform_bytes,
+        src,
+        test_wrapper_type_info,
+        emit_source_map,
+    );
 
     if write_final_wasm() {
         let build_dir_hash = crate::helpers::src_hash(src);
@@ -67,11 +74,30 @@ pub fn compile_to_wasm_bytes<'a, T: Wasm32Result>(
     compiled_bytes
 }
 
+/// Compile a Roc app with source regions marked, and embed a source map in the module
+#[allow(dead_code)]
+pub fn compile_to_wasm_bytes_with_source_map<'a, T: Wasm32Result>(
+    arena: &'a bumpalo::Bump,
+    src: &str,
+    test_wrapper_type_info: PhantomData<T>,
+) -> Vec<u8> {
+    let platform_bytes = include_bytes!(host_bytes_path!());
+    let emit_source_map = true;
+    compile_roc_to_wasm_bytes(
+        arena,
+        platform_bytes,
+        src,
+        test_wrapper_type_info,
+        emit_source_map,
+    )
+}
+
 fn compile_roc_to_wasm_bytes<'a, T: Wasm32Result>(
     arena: &'a bumpalo::Bump,
     host_bytes: &[u8],
     src: &str,
     _test_wrapper_type_info: PhantomData<T>,
+    emit_source_map: bool,
 ) -> Vec<u8> {
     let filename = PathBuf::from("Test.roc");
     let src_dir = PathBuf::from("fake/test/path");
@@ -94,6 +120,11 @@ fn compile_roc_to_wasm_bytes<'a, T: Wasm32Result>(
         threading: Threading::Single,
         exec_mode: ExecutionMode::Executable,
         function_kind: FunctionKind::LambdaSet,
+        output: OutputConfig {
+            inline_level: InlineLevel::Normal,
+            mark_source_regions: emit_source_map,
+            ..OutputConfig::default()
+        },
     };
     let loaded = roc_load::load_and_monomorphize_from_str(
         arena,
@@ -113,6 +144,8 @@ fn compile_roc_to_wasm_bytes<'a, T: Wasm32Result>(
         mut interns,
         exposed_to_host,
         mut layout_interner,
+        sources,
+        definition_regions,
         ..
     } = loaded;
 
@@ -124,11 +157,29 @@ fn compile_roc_to_wasm_bytes<'a, T: Wasm32Result>(
         .copied()
         .collect::<MutSet<_>>();
 
+    let debug_sources = if emit_source_map {
+        let files = sources
+            .iter()
+            .map(|(module_id, (path, src))| {
+                let path = arena.alloc_str(&path.to_string_lossy());
+                let src = arena.alloc_str(src);
+                (*module_id, (&*path, &*src))
+            })
+            .collect();
+        Some(roc_gen_wasm::DebugSources {
+            files,
+            definition_regions,
+        })
+    } else {
+        None
+    };
+
     let env = roc_gen_wasm::Env {
         arena,
         module_id,
         exposed_to_host,
         stack_bytes: roc_gen_wasm::Env::DEFAULT_STACK_BYTES,
+        debug_sources,
     };
 
     let host_module = roc_gen_wasm::parse_host(env.arena, host_bytes).unwrap_or_else(|e| {
@@ -25,5 +25,7 @@ pub mod wasm_str;
 
 #[cfg(feature = "gen-wasm")]
 pub mod wasm_linking;
+#[cfg(feature = "gen-wasm")]
+pub mod wasm_source_map;
 
 pub use helpers::platform_functions::*;
@@ -0,0 +1,150 @@
+#![cfg(feature = "gen-wasm")]
+
+use crate::helpers::wasm::compile_to_wasm_bytes_with_source_map;
+use bumpalo::Bump;
+use indoc::indoc;
+use roc_std::RocStr;
+use roc_wasm_module::WasmModule;
+use std::marker::PhantomData;
+
+const URL_PREFIX: &str = "data:application/json;base64,";
+
+/// The generated offset, source file index, line and column of each mapping
+fn decode_mappings(mappings: &str) -> Vec<[i64; 4]> {
+    let mut decoded = Vec::new();
+    let mut fields = [0; 4];
+    for segment in mappings.split(',').filter(|s| !s.is_empty()) {
+        let deltas = decode_vlqs(segment);
+        assert_eq!(deltas.len(), 4, "unexpected segment {segment:?}");
+        for (field, delta) in fields.iter_mut().zip(deltas) {
+            *field += delta;
+        }
+        decoded.push(fields);
+    }
+    decoded
+}
+
+fn decode_vlqs(segment: &str) -> Vec<i64> {
+    let mut values = Vec::new();
+    let mut value = 0;
+    let mut shift = 0;
+    for c in segment.bytes() {
+        let digit = base64_digit(c) as i64;
+        value |= (digit & 0x1f) << shift;
+        shift += 5;
+        if digit & 0x20 == 0 {
+            // The sign is in the lowest bit
+            let magnitude = value >> 1;
+            values.push(if value & 1 == 1 {
+                -magnitude
+            } else {
+                magnitude
+            });
+            value = 0;
+            shift = 0;
+        }
+    }
+    values
+}
+
+fn base64_digit(c: u8) -> u8 {
+    match c {
+        b'A'..=b'Z' => c - b'A',
+        b'a'..=b'z' => c - b'a' + 26,
+        b'0'..=b'9' => c - b'0' + 52,
+        b'+' => 62,
+        b'/' => 63,
+        _ => panic!("invalid base64 character {:?}", c as char),
+    }
+}
+
+fn decode_base64(text: &str) -> Vec<u8> {
+    let digits: Vec<u8> = text
+        .bytes()
+        .filter(|c| *c != b'=')
+        .map(base64_digit)
+        .collect();
+    let mut bytes = Vec::with_capacity(3 * digits.len() / 4);
+    for chunk in digits.chunks(4) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, d)| n | (*d as u32) << (18 - 6 * i));
+        bytes.extend_from_slice(&n.to_be_bytes()[1..chunk.len()]);
+    }
+    bytes
+}
+
+/// Get a string-valued field from the JSON source map
+fn json_field<'a>(json: &'a str, name: &str) -> &'a str {
+    let key = format!("\"{name}\":");
+    let start = json.find(&key).unwrap() + key.len();
+    let end = match &json[start..start + 1] {
+        "\"" => start + 1 + json[start + 1..].find('"').unwrap() + 1,
+        "[" => start + json[start..].find(']').unwrap() + 1,
+        other => panic!("unexpected JSON value starting with {other}"),
+    };
+    &json[start..end]
+}
+
+#[test]
+fn source_map_has_definition_regions() {
+    let src = indoc!(
+        r#"
+        app "test" provides [main] to "./platform"
+
+        main =
+            name = Str.concat "Ro" "c"
+            greeting = Str.concat "Hello, " name
+            Str.concat greeting "!"
+        "#
+    );
+
+    let arena = Bump::new();
+    let bytes = compile_to_wasm_bytes_with_source_map(&arena, src, PhantomData::<RocStr>);
+
+    // The sourceMappingURL section is last, so the URL runs to the end of the module
+    let url_start = bytes
+        .windows(URL_PREFIX.len())
+        .position(|w| w == URL_PREFIX.as_bytes())
+        .expect("no source map URL in the module");
+    let url = std::str::from_utf8(&bytes[url_start..]).unwrap();
+    let json = String::from_utf8(decode_base64(&url[URL_PREFIX.len()..])).unwrap();
+
+    assert!(json.starts_with("{\"version\":3,"), "{json}");
+    let sources = json_field(&json, "sources");
+    assert!(sources.contains("Test.roc\""), "{sources}");
+
+    let mappings = json_field(&json, "mappings").trim_matches('"');
+    let decoded = decode_mappings(mappings);
+
+    // Every mapping points into the code section
+    let module = WasmModule::preload(&arena, &bytes, false).unwrap();
+    let code_start = module.code.section_offset as i64;
+    let code_end = code_start + module.code.bytes.len() as i64;
+    for [offset, ..] in decoded.iter() {
+        assert!(
+            (code_start..code_end).contains(offset),
+            "offset {offset} is outside the code section {code_start}..{code_end}"
+        );
+    }
+
+    // `main` itself, and each of the definitions inside it (zero-based lines and columns)
+    let lines_and_columns: Vec<(i64, i64)> = decoded
+        .iter()
+        .map(|[_, _, line, column]| (*line, *column))
+        .collect();
+    for expected in [(2, 0), (3, 4), (4, 4)] {
+        assert!(
+            lines_and_columns.contains(&expected),
+            "no mapping for {expected:?} in {lines_and_columns:?}"
+        );
+    }
+
+    // The definitions come after the start of `main`, in source order
+    let position = |line_col| lines_and_columns.iter().position(|lc| *lc == line_col);
+    let main_offset = decoded[position((2, 0)).unwrap()][0];
+    let name_offset = decoded[position((3, 4)).unwrap()][0];
+    let greeting_offset = decoded[position((4, 4)).unwrap()][0];
+    assert!(main_offset < name_offset && name_offset < greeting_offset);
+}
@@ -17,11 +17,12 @@ use bumpalo::Bump;
 use roc_collections::all::MutMap;
 use roc_load::ExecutionMode;
 use roc_load::FunctionKind;
-use roc_load::LoadConfig;
 use roc_load::LoadMonomorphizedError;
 use roc_load::Threading;
+use roc_load::{LoadConfig, OutputConfig};
 use roc_module::symbol::Interns;
 use roc_module::symbol::Symbol;
+use roc_mono::inline::InlineLevel;
 use roc_mono::ir::Proc;
 use roc_mono::ir::ProcLayout;
 use roc_mono::layout::STLayoutInterner;
@@ -77,7 +78,15 @@ fn promote_expr_to_module(src: &str) -> String {
     buffer
 }
 
-fn compiles_to_ir(test_name: &str, src: &str, mode: &str, allow_type_errors: bool, no_check: bool) {
+fn compiles_to_ir(
+    test_name: &str,
+    src: &str,
+    mode: &str,
+    inline: &str,
+    allow_type_errors: bool,
+    no_check: bool,
+    source_regions: bool,
+) {
     use roc_packaging::cache::RocCacheDir;
     use std::path::PathBuf;
 
@@ -87,6 +96,14 @@ fn compiles_to_ir(test_name: &str, src: &str, mode: &str, allow_type_errors: boo
         _ => panic!("Invalid test_mono exec mode {mode}"),
     };
 
+    let inline_level = match inline {
+        "off" => InlineLevel::Off,
+        "size" => InlineLevel::Size,
+        "normal" => InlineLevel::Normal,
+        "aggressive" => InlineLevel::Aggressive,
+        _ => panic!("Invalid test_mono inline level {inline}"),
+    };
+
     let arena = &Bump::new();
 
     let filename = PathBuf::from("Test.roc");
@@ -111,6 +128,11 @@ fn compiles_to_ir(test_name: &str, src: &str, mode: &str, allow_type_errors: boo
         render: roc_reporting::report::RenderTarget::Generic,
         palette: roc_reporting::report::DEFAULT_PALETTE,
         exec_mode,
+        output: OutputConfig {
+            inline_level,
+            mark_source_regions: source_regions,
+            ..OutputConfig::default()
+        },
     };
     let loaded = roc_load::load_and_monomorphize_from_str(
         arena,
@@ -2251,6 +2273,23 @@ fn tuple_pattern_match() {
     )
 }
 
+#[mono_test]
+fn tuple_elements_reordered_by_alignment() {
+    indoc!(
+        r#"
+        app "test" provides [main] to "./platform"
+
+        swap : (a, b) -> (b, a)
+        swap = \(x, y) -> (y, x)
+
+        main =
+            (n, m) = swap (1u8, 2i64)
+            (k, j) = swap (n, m)
+            n + j + Num.toI64 m + Num.toI64 k
+        "#
+    )
+}
+
 #[mono_test(mode = "test")]
 fn issue_4705() {
     indoc!(
@@ -3500,3 +3539,182 @@ fn issue_6174() {
         "
     )
 }
+
+#[mono_test(inline = "normal")]
+fn inline_small_proc() {
+    indoc!(
+        r"
+        addOne = \x -> x + 1
+
+        addOne 41
+        "
+    )
+}
+
+#[mono_test(inline = "aggressive")]
+fn inline_proc_with_join_point() {
+    indoc!(
+        r"
+        clamp = \x ->
+            y = if x > 10 then 10 else x
+
+            y * 2
+
+        clamp 5 + clamp 20
+        "
+    )
+}
+
+#[mono_test(inline = "normal")]
+fn inline_mutually_recursive_procs() {
+    indoc!(
+        r#"
+        app "test" provides [main] to "./platform"
+
+        isEven = \n ->
+            if n == 0 then Bool.true else isOdd (n - 1)
+
+        isOdd = \n ->
+            if n == 0 then Bool.false else isEven (n - 1)
+
+        main = isEven 4
+        "#
+    )
+}
+
+#[mono_test(inline = "normal", source_regions = "true")]
+fn source_regions_of_definitions() {
+    indoc!(
+        r#"
+        app "test" provides [main] to "./platform"
+
+        double = \n ->
+            twice = n * 2
+            twice
+
+        main =
+            x = double 3
+            { a, b } = { a: Num.toStr x, b: "!" }
+
+            Str.concat a b
+        "#
+    )
+}
+
+/// The kinds of the perf hints that `roc check --perf-hints` reports for `src`, in source order
+fn perf_hints(src: &str) -> Vec<roc_mono::perf_hints::PerfHintKind> {
+    use roc_packaging::cache::RocCacheDir;
+    use std::path::PathBuf;
+
+    let arena = &Bump::new();
+
+    let load_config = LoadConfig {
+        target: TARGET,
+        function_kind: FunctionKind::LambdaSet,
+        threading: Threading::Single,
+        render: roc_reporting::report::RenderTarget::Generic,
+        palette: roc_reporting::report::DEFAULT_PALETTE,
+        exec_mode: ExecutionMode::Executable,
+        output: OutputConfig {
+            mark_source_regions: true,
+            ..OutputConfig::default()
+        },
+    };
+    let loaded = roc_load::load_and_monomorphize_from_str(
+        arena,
+        PathBuf::from("Test.roc"),
+        src,
+        PathBuf::from("fake/test/path"),
+        RocCacheDir::Disallowed,
+        load_config,
+    );
+
+    let loaded = match loaded {
+        Ok(x) => x,
+        Err(LoadMonomorphizedError::LoadingProblem(roc_load::LoadingProblem::FormattedReport(
+            report,
+        ))) => {
+            println!("{report}");
+            panic!();
+        }
+        Err(e) => panic!("{e:?}"),
+    };
+
+    roc_mono::perf_hints::find_perf_hints(
+        &loaded.layout_interner,
+        &loaded.procedures,
+        &loaded.definition_regions,
+    )
+    .into_iter()
+    .map(|hint| hint.kind)
+    .collect()
+}
+
+#[test]
+fn perf_hints_copied_for_update() {
+    use roc_mono::perf_hints::PerfHintKind;
+
+    let hints = perf_hints(indoc!(
+        r#"
+        app "test" provides [main] to "./platform"
+
+        main =
+            original = List.repeat 1u8 3
+            updated = List.set original 0 42
+
+            List.len original + List.len updated
+        "#
+    ));
+
+    assert!(
+        matches!(hints[..], [PerfHintKind::CopiedForUpdate { .. }]),
+        "{hints:?}"
+    );
+}
+
+#[test]
+fn perf_hints_not_copied_after_call_takes_ownership() {
+    let hints = perf_hints(indoc!(
+        r#"
+        app "test" provides [main] to "./platform"
+
+        count : List U8 -> U64
+        count = \list -> List.len list
+
+        main =
+            list = List.repeat 1u8 3
+            n = count list
+            updated = List.set list 0 42
+
+            n + List.len updated
+        "#
+    ));
+
+    assert_eq!(hints, []);
+}
+
+#[test]
+fn perf_hints_reused() {
+    use roc_mono::perf_hints::PerfHintKind;
+
+    let hints = perf_hints(indoc!(
+        r#"
+        app "test" provides [main] to "./platform"
+
+        Tree : [Leaf, Node Tree I64 Tree]
+
+        bump : Tree -> Tree
+        bump = \tree ->
+            when tree is
+                Leaf -> Leaf
+                Node left value right -> Node (bump left) (value + 1) (bump right)
+
+        main =
+            when bump (Node Leaf 1 Leaf) is
+                Leaf -> 0
+                Node _ value _ -> value
+        "#
+    ));
+
+    assert_eq!(hints, [PerfHintKind::Reused]);
+}
@@ -0,0 +1,1282 @@
+app "wit-glue"
+    packages { pf: "../platform/main.roc" }
+    imports [
+        pf.Types.{ Types },
+        pf.Shape.{ RocNum },
+        pf.File.{ File },
+        pf.TypeId.{ TypeId, typeIDtoU64 },
+        "../static/canonical_abi.zig" as canonicalAbi : Str,
+        "../../compiler/builtins/bitcode/src/list.zig" as rocStdList : Str,
+        "../../compiler/builtins/bitcode/src/str.zig" as rocStdStr : Str,
+        "../../compiler/builtins/bitcode/src/utils.zig" as rocStdUtils : Str,
+    ]
+    provides [makeGlue] to pf
+
+## Generates a WIT world for the platform's entry points, so that a Roc app can be packaged
+## as a WebAssembly component, along with Zig code that converts the entry points' arguments
+## and return values between Roc's memory layout and the component model's canonical ABI.
+## https://github.com/WebAssembly/component-model/blob/main/design/mvp/CanonicalABI.md
+makeGlue : List Types -> Result (List File) Str
+makeGlue = \typesByArch ->
+    # The component model only exists for wasm32
+    wasmTypes = List.findFirst typesByArch \types -> (Types.target types).architecture == Wasm32
+
+    when wasmTypes is
+        Ok types ->
+            generate types
+            |> Result.map \files -> List.concat files staticFiles
+
+        Err NotFound ->
+            Err "The WIT glue spec needs type information for wasm32, but `roc glue` didn't provide any."
+
+## These are always included, and don't depend on the specifics of the app.
+staticFiles : List File
+staticFiles = [
+    { name: "canonical_abi.zig", content: canonicalAbi },
+    { name: "list.zig", content: rocStdList },
+    { name: "str.zig", content: rocStdStr },
+    { name: "utils.zig", content: rocStdUtils },
+]
+
+EntryPoint : { name : Str, args : List TypeId, ret : TypeId }
+
+## How a Roc type is represented in WIT, in Roc's memory layout, and in the canonical ABI
+Repr : [
+    Unit,
+    Prim { zig : Str, wit : Str, size : U32, flat : CoreType },
+    String,
+    Sequence TypeId,
+    Record
+        {
+            style : [NamedRecord Str, NamedTuple Str, Tuple],
+            fields : List { name : Str, id : TypeId },
+        },
+    Enum { name : Str, tags : List Str, size : U32 },
+    Variant
+        {
+            style : [Named Str, Result],
+            cases : List Case,
+            rocDiscriminantSize : U32,
+            rocDiscriminantOffset : U32,
+        },
+]
+
+## `rocTag` is the case's discriminant in Roc's memory layout.
+## In the canonical ABI, the discriminant is the case's index in the list.
+Case : { name : Str, rocTag : U64, payload : [Some TypeId, None] }
+
+## The Wasm value types that the canonical ABI flattens values into
+CoreType : [I32, I64, F32, F64]
+
+generate : Types -> Result (List File) Str
+generate = \types ->
+    entryPointsResult =
+        Types.entryPoints types
+        |> List.mapTry \T name id -> toEntryPoint types name id
+
+    when entryPointsResult is
+        Err problem -> Err problem
+        Ok entryPoints ->
+            when collectTypes types entryPoints is
+                Err problem -> Err problem
+                Ok ids ->
+                    Ok [
+                        { name: "roc_app.wit", content: generateWit types ids entryPoints },
+                        { name: "roc_app.zig", content: generateZig types ids entryPoints },
+                    ]
+
+toEntryPoint : Types, Str, TypeId -> Result EntryPoint Str
+toEntryPoint = \types, name, id ->
+    when Types.shape types id is
+        Function rocFn ->
+            when Types.shape types rocFn.ret is
+                Function _ ->
+                    Err "The entry point `$(name)` returns a function. The WIT glue spec can't represent that yet."
+
+                _ ->
+                    Ok { name, args: rocFn.args, ret: rocFn.ret }
+
+        _ ->
+            # A value, which the host gets by calling a function with no arguments
+            Ok { name, args: [], ret: id }
+
+## Every type used by the entry points, with each type after the types it contains
+collectTypes : Types, List EntryPoint -> Result (List TypeId) Str
+collectTypes = \types, entryPoints ->
+    List.walkTry entryPoints [] \ids, { args, ret } ->
+        List.walkTry (List.append args ret) ids \state, id -> collectType types state id
+
+collectType : Types, List TypeId, TypeId -> Result (List TypeId) Str
+collectType = \types, ids, id ->
+    if List.contains ids id then
+        Ok ids
+    else
+        when repr types id is
+            Err problem -> Err problem
+            Ok r ->
+                List.walkTry (children r) ids (\state, child -> collectType types state child)
+                |> Result.map \state -> List.append state id
+
+children : Repr -> List TypeId
+children = \r ->
+    when r is
+        Sequence elem -> [elem]
+        Record { fields } -> List.map fields .id
+        Variant { cases } -> casePayloads cases
+        Unit | Prim _ | String | Enum _ -> []
+
+casePayloads : List Case -> List TypeId
+casePayloads = \cases ->
+    List.keepOks cases \{ payload } ->
+        when payload is
+            Some id -> Ok id
+            None -> Err None
+
+repr : Types, TypeId -> Result Repr Str
+repr = \types, id ->
+    when Types.shape types id is
+        Unit -> Ok Unit
+        Bool -> Ok (Prim { zig: "bool", wit: "bool", size: 1, flat: I32 })
+        Num num -> numRepr num
+        RocStr -> Ok String
+        RocList elem ->
+            if isUnitType types elem then
+                Err (unsupported "lists of empty values")
+            else
+                Ok (Sequence elem)
+
+        RocResult ok err ->
+            Ok
+                (
+                    Variant {
+                        style: Result,
+                        cases: [
+                            { name: "ok", rocTag: 1, payload: payloadOf types ok },
+                            { name: "err", rocTag: 0, payload: payloadOf types err },
+                        ],
+                        rocDiscriminantSize: 1,
+                        rocDiscriminantOffset: Num.max (roundedSize types ok) (roundedSize types err),
+                    }
+                )
+
+        Struct { name, fields } ->
+            when fields is
+                HasNoClosure list -> recordRepr types (NamedRecord name) list
+                HasClosure _ -> Err (unsupported "closures")
+
+        TagUnionPayload { fields } ->
+            when fields is
+                HasNoClosure list ->
+                    # These fields are named by their position, like "0"
+                    renamed = List.map list \{ name, id: fieldId } -> { name: "f$(name)", id: fieldId }
+
+                    recordRepr types Tuple renamed
+
+                HasClosure _ -> Err (unsupported "closures")
+
+        TagUnion (SingleTagStruct { name, payload }) ->
+            when payload is
+                HasNoClosure list ->
+                    named = List.mapWithIndex list \{ id: fieldId }, index -> { name: "f$(Num.toStr index)", id: fieldId }
+
+                    recordRepr types (NamedTuple name) named
+
+                HasClosure _ -> Err (unsupported "closures")
+
+        TagUnion (Enumeration { name, tags, size }) ->
+            Ok (Enum { name, tags, size })
+
+        TagUnion (NonRecursive { name, tags, discriminantSize, discriminantOffset }) ->
+            cases = List.mapWithIndex tags \tag, index ->
+                payload =
+                    when tag.payload is
+                        Some payloadId -> payloadOf types payloadId
+                        None -> None
+
+                { name: tag.name, rocTag: index, payload }
+
+            Ok
+                (
+                    Variant {
+                        style: Named name,
+                        cases,
+                        rocDiscriminantSize: discriminantSize,
+                        rocDiscriminantOffset: discriminantOffset,
+                    }
+                )
+
+        TagUnion (Recursive _)
+        | TagUnion (NullableWrapped _)
+        | TagUnion (NonNullableUnwrapped _)
+        | TagUnion (NullableUnwrapped _)
+        | RecursivePointer _ ->
+            Err (unsupported "recursive types")
+
+        RocDict _ _ -> Err (unsupported "Dict")
+        RocSet _ -> Err (unsupported "Set")
+        RocBox _ -> Err (unsupported "Box")
+        Function _ -> Err (unsupported "functions as values")
+        EmptyTagUnion -> Err (unsupported "empty tag unions")
+        Unsized -> Err (unsupported "unsized types")
+
+numRepr : RocNum -> Result Repr Str
+numRepr = \num ->
+    when num is
+        I8 -> Ok (Prim { zig: "i8", wit: "s8", size: 1, flat: I32 })
+        U8 -> Ok (Prim { zig: "u8", wit: "u8", size: 1, flat: I32 })
+        I16 -> Ok (Prim { zig: "i16", wit: "s16", size: 2, flat: I32 })
+        U16 -> Ok (Prim { zig: "u16", wit: "u16", size: 2, flat: I32 })
+        I32 -> Ok (Prim { zig: "i32", wit: "s32", size: 4, flat: I32 })
+        U32 -> Ok (Prim { zig: "u32", wit: "u32", size: 4, flat: I32 })
+        I64 -> Ok (Prim { zig: "i64", wit: "s64", size: 8, flat: I64 })
+        U64 -> Ok (Prim { zig: "u64", wit: "u64", size: 8, flat: I64 })
+        F32 -> Ok (Prim { zig: "f32", wit: "f32", size: 4, flat: F32 })
+        F64 -> Ok (Prim { zig: "f64", wit: "f64", size: 8, flat: F64 })
+        I128 | U128 | Dec -> Err (unsupported "128-bit numbers")
+
+recordRepr : Types, [NamedRecord Str, NamedTuple Str, Tuple], List { name : Str, id : TypeId } -> Result Repr Str
+recordRepr = \types, style, fields ->
+    # Zero-sized fields aren't stored in Roc's memory layout, and can't be written in WIT
+    nonUnitFields = List.dropIf fields \{ id } -> isUnitType types id
+
+    if List.isEmpty nonUnitFields then
+        Ok Unit
+    else
+        Ok (Record { style, fields: nonUnitFields })
+
+roundedSize : Types, TypeId -> U32
+roundedSize = \types, id ->
+    alignTo (Types.size types id) (Num.max 1 (Types.alignment types id))
+
+payloadOf : Types, TypeId -> [Some TypeId, None]
+payloadOf = \types, id ->
+    if isUnitType types id then
+        None
+    else
+        Some id
+
+isUnitType : Types, TypeId -> Bool
+isUnitType = \types, id ->
+    when repr types id is
+        Ok Unit -> Bool.true
+        _ -> Bool.false
+
+unsupported : Str -> Str
+unsupported = \what ->
+    "The WIT glue spec doesn't support $(what) yet."
+
+## Only call this for types that `collectTypes` has already checked
+reprOf : Types, TypeId -> Repr
+reprOf = \types, id ->
+    when repr types id is
+        Ok r -> r
+        Err problem -> crash "$(problem) This should have been caught earlier, and means there was a bug in the WIT glue spec."
+
+# WIT
+
+generateWit : Types, List TypeId, List EntryPoint -> Str
+generateWit = \types, ids, entryPoints ->
+    definitions =
+        ids
+        |> List.keepOks (\id -> witDefinition types id)
+        |> Str.joinWith "\n"
+
+    exports =
+        entryPoints
+        |> List.map (\entryPoint -> witExport types entryPoint)
+        |> Str.joinWith ""
+
+    """
+    // ⚠️ GENERATED CODE ⚠️ - this entire file was generated by the `roc glue` CLI command
+    package roc:app;
+
+    world roc-app {
+    $(definitions)
+    $(exports)}
+
+    """
+
+witExport : Types, EntryPoint -> Str
+witExport = \types, { name, args, ret } ->
+    params =
+        nonUnitArgs types args
+        |> List.map (\{ index, id } -> "arg$(Num.toStr index): $(witType types id)")
+        |> Str.joinWith ", "
+
+    result =
+        if isUnitType types ret then
+            ""
+        else
+            " -> $(witType types ret)"
+
+    "$(indent)export $(witName name): func($(params))$(result);\n"
+
+witDefinition : Types, TypeId -> Result Str [NoDefinition]
+witDefinition = \types, id ->
+    when reprOf types id is
+        Record { style, fields } ->
+            when style is
+                NamedRecord name ->
+                    body =
+                        fields
+                        |> List.map (\field -> "$(indent)$(indent)$(witName field.name): $(witType types field.id),\n")
+                        |> Str.joinWith ""
+
+                    Ok "$(indent)record $(witName name) {\n$(body)$(indent)}\n"
+
+                NamedTuple name ->
+                    Ok "$(indent)type $(witName name) = $(witTuple types fields);\n"
+
+                Tuple ->
+                    Err NoDefinition
+
+        Enum { name, tags } ->
+            body =
+                tags
+                |> List.map (\tag -> "$(indent)$(indent)$(witName tag),\n")
+                |> Str.joinWith ""
+
+            Ok "$(indent)enum $(witName name) {\n$(body)$(indent)}\n"
+
+        Variant { style, cases } ->
+            when style is
+                Named name ->
+                    body =
+                        cases
+                        |> List.map \case ->
+                            when case.payload is
+                                Some payloadId -> "$(indent)$(indent)$(witName case.name)($(witType types payloadId)),\n"
+                                None -> "$(indent)$(indent)$(witName case.name),\n"
+                        |> Str.joinWith ""
+
+                    Ok "$(indent)variant $(witName name) {\n$(body)$(indent)}\n"
+
+                Result ->
+                    Err NoDefinition
+
+        Unit | Prim _ | String | Sequence _ ->
+            Err NoDefinition
+
+witType : Types, TypeId -> Str
+witType = \types, id ->
+    when reprOf types id is
+        Prim { wit } -> wit
+        String -> "string"
+        Sequence elem -> "list<$(witType types elem)>"
+        Record { style, fields } ->
+            when style is
+                NamedRecord name -> witName name
+                NamedTuple name -> witName name
+                Tuple -> witTuple types fields
+
+        Enum { name } -> witName name
+        Variant { style, cases } ->
+            when style is
+                Named name -> witName name
+                Result ->
+                    when (payloadAt cases 0, payloadAt cases 1) is
+                        (Some ok, Some err) -> "result<$(witType types ok), $(witType types err)>"
+                        (Some ok, None) -> "result<$(witType types ok)>"
+                        (None, Some err) -> "result<_, $(witType types err)>"
+                        (None, None) -> "result"
+
+        Unit ->
+            crash "Zero-sized types have no WIT type. This means there was a bug in the WIT glue spec."
+
+witTuple : Types, List { name : Str, id : TypeId } -> Str
+witTuple = \types, fields ->
+    elems =
+        fields
+        |> List.map (\field -> witType types field.id)
+        |> Str.joinWith ", "
+
+    "tuple<$(elems)>"
+
+payloadAt : List Case, U64 -> [Some TypeId, None]
+payloadAt = \cases, index ->
+    when List.get cases index is
+        Ok case -> case.payload
+        Err OutOfBounds -> None
+
+## WIT identifiers are kebab-case, so `mainForHost` becomes `main-for-host`
+witName : Str -> Str
+witName = \name ->
+    appendDash = \acc, dash ->
+        if dash && !(List.isEmpty acc) then
+            List.append acc '-'
+        else
+            acc
+
+    { bytes } =
+        Str.toUtf8 name
+        |> List.walk { bytes: [], prev: Start, pendingDash: Bool.false } \state, byte ->
+            if byte >= 'A' && byte <= 'Z' then
+                needsDash = state.pendingDash || state.prev == Lower || state.prev == Digit
+                lowercase = byte + ('a' - 'A')
+
+                { bytes: state.bytes |> appendDash needsDash |> List.append lowercase, prev: Upper, pendingDash: Bool.false }
+            else if byte >= 'a' && byte <= 'z' then
+                { bytes: state.bytes |> appendDash state.pendingDash |> List.append byte, prev: Lower, pendingDash: Bool.false }
+            else if byte >= '0' && byte <= '9' then
+                # A word can't start with a digit, so digits never start a new word
+                { bytes: List.append state.bytes byte, prev: Digit, pendingDash: Bool.false }
+            else
+                # Anything else (like the underscore in `MyUnion_MyTag`) separates words
+                { state & pendingDash: Bool.true }
+
+    kebab =
+        when Str.fromUtf8 bytes is
+            Ok str -> str
+            Err _ -> crash "The WIT name for `$(name)` was invalid UTF-8. This means there was a bug in the WIT glue spec."
+
+    if Set.contains witKeywords kebab then
+        # Keywords can be used as identifiers by prefixing them with %
+        "%$(kebab)"
+    else
+        kebab
+
+witKeywords = Set.fromList [
+    "as",
+    "bool",
+    "borrow",
+    "char",
+    "constructor",
+    "enum",
+    "export",
+    "f32",
+    "f64",
+    "flags",
+    "float32",
+    "float64",
+    "func",
+    "future",
+    "import",
+    "include",
+    "interface",
+    "list",
+    "option",
+    "own",
+    "package",
+    "record",
+    "resource",
+    "result",
+    "s16",
+    "s32",
+    "s64",
+    "s8",
+    "static",
+    "stream",
+    "string",
+    "tuple",
+    "type",
+    "u16",
+    "u32",
+    "u64",
+    "u8",
+    "use",
+    "variant",
+    "with",
+    "world",
+]
+
+# Canonical ABI
+
+## The size and alignment of a type in the canonical ABI's memory layout
+canonLayout : Types, TypeId -> { size : U32, align : U32 }
+canonLayout = \types, id ->
+    when reprOf types id is
+        Unit -> { size: 0, align: 1 }
+        Prim { size } -> { size, align: size }
+        String | Sequence _ -> { size: 8, align: 4 }
+        Record { fields } ->
+            { size, align } = recordLayout types fields
+
+            { size, align }
+
+        Enum { tags } ->
+            size = canonDiscriminantSize (List.len tags)
+
+            { size, align: size }
+
+        Variant { cases } ->
+            { size, align } = variantLayout types cases
+
+            { size, align }
+
+recordLayout : Types, List { name : Str, id : TypeId } -> { size : U32, align : U32, offsets : List U32 }
+recordLayout = \types, fields ->
+    { end, align, offsets } =
+        List.walk fields { end: 0, align: 1, offsets: [] } \state, field ->
+            fieldLayout = canonLayout types field.id
+            offset = alignTo state.end fieldLayout.align
+
+            {
+                end: offset + fieldLayout.size,
+                align: Num.max state.align fieldLayout.align,
+                offsets: List.append state.offsets offset,
+            }
+
+    { size: alignTo end align, align, offsets }
+
+variantLayout : Types, List Case -> { size : U32, align : U32, payloadOffset : U32 }
+variantLayout = \types, cases ->
+    discriminant = canonDiscriminantSize (List.len cases)
+    payloadLayouts = List.map (casePayloads cases) \id -> canonLayout types id
+    maxSize = List.walk payloadLayouts 0 \max, layout -> Num.max max layout.size
+    maxAlign = List.walk payloadLayouts 1 \max, layout -> Num.max max layout.align
+    payloadOffset = alignTo discriminant maxAlign
+    align = Num.max discriminant maxAlign
+
+    { size: alignTo (payloadOffset + maxSize) align, align, payloadOffset }
+
+canonDiscriminantSize : U64 -> U32
+canonDiscriminantSize = \caseCount ->
+    if caseCount <= 256 then
+        1
+    else if caseCount <= 65536 then
+        2
+    else
+        4
+
+alignTo : U32, U32 -> U32
+alignTo = \n, align ->
+    (n + align - 1) // align * align
+
+## The Wasm values that a type is passed as, when it's a function argument or return value
+flatten : Types, TypeId -> List CoreType
+flatten = \types, id ->
+    when reprOf types id is
+        Unit -> []
+        Prim { flat } -> [flat]
+        String | Sequence _ -> [I32, I32]
+        Record { fields } -> List.joinMap fields \field -> flatten types field.id
+        Enum _ -> [I32]
+        Variant { cases } ->
+            casePayloads cases
+            |> List.walk [] (\joined, payloadId -> joinFlat joined (flatten types payloadId))
+            |> List.prepend I32
+
+## Variant cases share the Wasm values after the discriminant,
+## using a type that can hold the bits of any of them
+joinFlat : List CoreType, List CoreType -> List CoreType
+joinFlat = \a, b ->
+    List.range { start: At 0, end: Before (Num.max (List.len a) (List.len b)) }
+    |> List.map \index ->
+        when (List.get a index, List.get b index) is
+            (Ok x, Ok y) -> joinCoreType x y
+            (Ok x, Err _) -> x
+            (Err _, Ok y) -> y
+            (Err _, Err _) -> crash "joinFlat went out of bounds. This means there was a bug in the WIT glue spec."
+
+joinCoreType : CoreType, CoreType -> CoreType
+joinCoreType = \a, b ->
+    if a == b then
+        a
+    else
+        when (a, b) is
+            (I32, F32) | (F32, I32) -> I32
+            _ -> I64
+
+coreTypeName : CoreType -> Str
+coreTypeName = \coreType ->
+    when coreType is
+        I32 -> "i32"
+        I64 -> "i64"
+        F32 -> "f32"
+        F64 -> "f64"
+
+## The canonical ABI passes at most this many Wasm values as arguments.
+## If there are more, the arguments are stored in memory and passed as a pointer.
+maxFlatParams = 16
+
+# Zig
+
+generateZig : Types, List TypeId, List EntryPoint -> Str
+generateZig = \types, ids, entryPoints ->
+    conversions =
+        ids
+        |> List.map (\id -> zigConversions types id)
+        |> List.dropIf Str.isEmpty
+        |> Str.joinWith "\n"
+
+    exports =
+        entryPoints
+        |> List.mapWithIndex (\entryPoint, index -> zigExport types entryPoint index)
+        |> Str.joinWith "\n"
+
+    """
+    // ⚠️ GENERATED CODE ⚠️ - this entire file was generated by the `roc glue` CLI command
+    //
+    // Canonical ABI exports for the `roc-app` world in roc_app.wit.
+    // To make a component, reference this file from a wasm32 host, for example with
+    // `comptime { _ = @import("roc_app.zig"); }`, build the app with `--target wasm32`,
+    // then run `wasm-tools component embed roc_app.wit` and `wasm-tools component new` on it.
+    //
+    // Roc entry points are called with numbers, Bools and enums passed by value.
+    // Everything else is passed by pointer, and returned by writing to a pointer
+    // passed as the first argument.
+    const abi = @import("canonical_abi.zig");
+
+    export fn cabi_realloc(old_ptr: u32, old_size: u32, alignment: u32, new_size: u32) u32 {
+        return abi.realloc(old_ptr, old_size, alignment, new_size);
+    }
+
+    $(exports)
+    $(conversions)
+    """
+
+## The Zig type for a value in Roc's memory layout
+zigType : Types, TypeId -> Str
+zigType = \types, id ->
+    when reprOf types id is
+        Prim { zig } -> zig
+        String -> "abi.RocStr"
+        Sequence _ -> "abi.RocList"
+        Unit | Record _ | Enum _ | Variant _ -> "Roc$(idStr id)"
+
+## Roc passes numbers, Bools and enums by value, and everything else by pointer
+isPassedByValue : Types, TypeId -> Bool
+isPassedByValue = \types, id ->
+    when reprOf types id is
+        Prim _ | Enum _ -> Bool.true
+        Unit | String | Sequence _ | Record _ | Variant _ -> Bool.false
+
+## Whether the type owns memory, in both Roc's and the canonical ABI's layout
+needsFree : Types, TypeId -> Bool
+needsFree = \types, id ->
+    when reprOf types id is
+        String | Sequence _ -> Bool.true
+        Record { fields } -> List.any fields \field -> needsFree types field.id
+        Variant { cases } -> List.any (casePayloads cases) \payloadId -> needsFree types payloadId
+        Unit | Prim _ | Enum _ -> Bool.false
+
+idStr : TypeId -> Str
+idStr = \id -> Num.toStr (typeIDtoU64 id)
+
+lines : List Str -> Str
+lines = \list ->
+    list
+    |> List.map (\line -> Str.concat line "\n")
+    |> Str.joinWith ""
+
+## Each type gets functions to lift it from the canonical ABI's memory layout (liftMem) or
+## flattened values (liftFlat) into Roc's memory layout, and to lower it back the other way.
+## Types that own memory also get functions to free a canonical ABI value (freeMem), and
+## to decrement the refcounts in a Roc value once it's been lowered (release).
+zigConversions : Types, TypeId -> Str
+zigConversions = \types, id ->
+    n = idStr id
+    t = zigType types id
+
+    when reprOf types id is
+        Unit -> ""
+        Prim _ -> primConversions n t
+        String -> stringConversions n
+        Sequence elem -> listConversions types n elem
+        Record { fields } -> recordConversions types n fields
+        Enum { tags, size } -> enumConversions n tags size
+        Variant variant -> variantConversions types id variant
+
+primConversions : Str, Str -> Str
+primConversions = \n, t ->
+    lines [
+        "fn liftMem$(n)(p: [*]const u8) $(t) {",
+        "$(indent)return abi.load($(t), p);",
+        "}",
+        "",
+        "fn liftFlat$(n)(flat: []const u64, i: *usize) $(t) {",
+        "$(indent)i.* += 1;",
+        "$(indent)return abi.fromSlot($(t), flat[i.* - 1]);",
+        "}",
+        "",
+        "fn lowerMem$(n)(v: $(t), p: [*]u8) void {",
+        "$(indent)abi.store($(t), p, v);",
+        "}",
+        "",
+        "fn lowerFlat$(n)(v: $(t), flat: []u64, i: *usize) void {",
+        "$(indent)flat[i.*] = abi.toSlot($(t), v);",
+        "$(indent)i.* += 1;",
+        "}",
+    ]
+
+stringConversions : Str -> Str
+stringConversions = \n ->
+    lines [
+        "fn liftMem$(n)(p: [*]const u8) abi.RocStr {",
+        "$(indent)return abi.liftStr(abi.load(u32, p), abi.load(u32, p + 4));",
+        "}",
+        "",
+        "fn liftFlat$(n)(flat: []const u64, i: *usize) abi.RocStr {",
+        "$(indent)i.* += 2;",
+        "$(indent)return abi.liftStr(abi.fromSlot(u32, flat[i.* - 2]), abi.fromSlot(u32, flat[i.* - 1]));",
+        "}",
+        "",
+        "fn lowerMem$(n)(v: abi.RocStr, p: [*]u8) void {",
+        "$(indent)const slice = abi.lowerStr(v);",
+        "$(indent)abi.store(u32, p, slice.ptr);",
+        "$(indent)abi.store(u32, p + 4, slice.len);",
+        "}",
+        "",
+        "fn lowerFlat$(n)(v: abi.RocStr, flat: []u64, i: *usize) void {",
+        "$(indent)const slice = abi.lowerStr(v);",
+        "$(indent)flat[i.*] = slice.ptr;",
+        "$(indent)flat[i.* + 1] = slice.len;",
+        "$(indent)i.* += 2;",
+        "}",
+        "",
+        "fn freeMem$(n)(p: [*]const u8) void {",
+        "$(indent)abi.free(abi.load(u32, p), abi.load(u32, p + 4), 1);",
+        "}",
+        "",
+        "fn release$(n)(v: abi.RocStr) void {",
+        "$(indent)v.decref();",
+        "}",
+    ]
+
+listConversions : Types, Str, TypeId -> Str
+listConversions = \types, n, elem ->
+    e = idStr elem
+    et = zigType types elem
+    { size, align } = canonLayout types elem
+    elemSize = Num.toStr size
+    elemAlign = Num.toStr align
+
+    freeElements =
+        if needsFree types elem then
+            [
+                "$(indent)const src = abi.toPtr(address);",
+                "$(indent)for (0..len) |k| {",
+                "$(indent)$(indent)freeMem$(e)(src + k * $(elemSize));",
+                "$(indent)}",
+            ]
+        else
+            []
+
+    releaseElements =
+        if needsFree types elem then
+            [
+                "$(indent)// Only a unique list owns its elements",
+                "$(indent)if (v.isUnique()) {",
+                "$(indent)$(indent)if (v.elements($(et))) |elements| {",
+                "$(indent)$(indent)$(indent)for (0..v.len()) |k| {",
+                "$(indent)$(indent)$(indent)$(indent)release$(e)(elements[k]);",
+                "$(indent)$(indent)$(indent)}",
+                "$(indent)$(indent)}",
+                "$(indent)}",
+            ]
+        else
+            []
+
+    List.join [
+        [
+            "fn liftList$(n)(address: u32, len: u32) abi.RocList {",
+            "$(indent)if (len == 0) {",
+            "$(indent)$(indent)return abi.RocList.empty();",
+            "$(indent)}",
+            "$(indent)const list = abi.RocList.allocateExact(@alignOf($(et)), len, @sizeOf($(et)));",
+            "$(indent)const elements = list.elements($(et)).?;",
+            "$(indent)const src = abi.toPtr(address);",
+            "$(indent)for (0..len) |k| {",
+            "$(indent)$(indent)elements[k] = liftMem$(e)(src + k * $(elemSize));",
+            "$(indent)}",
+            "$(indent)abi.free(address, len * $(elemSize), $(elemAlign));",
+            "$(indent)return list;",
+            "}",
+            "",
+            "fn lowerList$(n)(v: abi.RocList) abi.Slice {",
+            "$(indent)const len: u32 = @intCast(v.len());",
+            "$(indent)const address = abi.allocate(len * $(elemSize), $(elemAlign));",
+            "$(indent)if (v.elements($(et))) |elements| {",
+            "$(indent)$(indent)const dest = abi.toPtr(address);",
+            "$(indent)$(indent)for (0..len) |k| {",
+            "$(indent)$(indent)$(indent)lowerMem$(e)(elements[k], dest + k * $(elemSize));",
+            "$(indent)$(indent)}",
+            "$(indent)}",
+            "$(indent)return .{ .ptr = address, .len = len };",
+            "}",
+            "",
+            "fn liftMem$(n)(p: [*]const u8) abi.RocList {",
+            "$(indent)return liftList$(n)(abi.load(u32, p), abi.load(u32, p + 4));",
+            "}",
+            "",
+            "fn liftFlat$(n)(flat: []const u64, i: *usize) abi.RocList {",
+            "$(indent)i.* += 2;",
+            "$(indent)return liftList$(n)(abi.fromSlot(u32, flat[i.* - 2]), abi.fromSlot(u32, flat[i.* - 1]));",
+            "}",
+            "",
+            "fn lowerMem$(n)(v: abi.RocList, p: [*]u8) void {",
+            "$(indent)const slice = lowerList$(n)(v);",
+            "$(indent)abi.store(u32, p, slice.ptr);",
+            "$(indent)abi.store(u32, p + 4, slice.len);",
+            "}",
+            "",
+            "fn lowerFlat$(n)(v: abi.RocList, flat: []u64, i: *usize) void {",
+            "$(indent)const slice = lowerList$(n)(v);",
+            "$(indent)flat[i.*] = slice.ptr;",
+            "$(indent)flat[i.* + 1] = slice.len;",
+            "$(indent)i.* += 2;",
+            "}",
+            "",
+            "fn freeMem$(n)(p: [*]const u8) void {",
+            "$(indent)const address = abi.load(u32, p);",
+            "$(indent)const len = abi.load(u32, p + 4);",
+        ],
+        freeElements,
+        [
+            "$(indent)abi.free(address, len * $(elemSize), $(elemAlign));",
+            "}",
+            "",
+            "fn release$(n)(v: abi.RocList) void {",
+        ],
+        releaseElements,
+        [
+            "$(indent)v.decref(@alignOf($(et)));",
+            "}",
+        ],
+    ]
+    |> lines
+
+recordConversions : Types, Str, List { name : Str, id : TypeId } -> Str
+recordConversions = \types, n, fields ->
+    { offsets } = recordLayout types fields
+
+    fieldsWithOffsets =
+        List.map2 fields offsets \field, offset -> {
+            name: "@\"$(field.name)\"",
+            id: idStr field.id,
+            offset: Num.toStr offset,
+            needsFree: needsFree types field.id,
+        }
+
+    freeFields = List.keepIf fieldsWithOffsets .needsFree
+
+    List.join [
+        ["const Roc$(n) = extern struct {"],
+        List.map fields \field -> "$(indent)@\"$(field.name)\": $(zigType types field.id),",
+        [
+            "};",
+            "",
+            "fn liftMem$(n)(p: [*]const u8) Roc$(n) {",
+            "$(indent)return .{",
+        ],
+        List.map fieldsWithOffsets \field -> "$(indent)$(indent).$(field.name) = liftMem$(field.id)(p + $(field.offset)),",
+        [
+            "$(indent)};",
+            "}",
+            "",
+            "fn liftFlat$(n)(flat: []const u64, i: *usize) Roc$(n) {",
+            "$(indent)return .{",
+        ],
+        List.map fieldsWithOffsets \field -> "$(indent)$(indent).$(field.name) = liftFlat$(field.id)(flat, i),",
+        [
+            "$(indent)};",
+            "}",
+            "",
+            "fn lowerMem$(n)(v: Roc$(n), p: [*]u8) void {",
+        ],
+        List.map fieldsWithOffsets \field -> "$(indent)lowerMem$(field.id)(v.$(field.name), p + $(field.offset));",
+        [
+            "}",
+            "",
+            "fn lowerFlat$(n)(v: Roc$(n), flat: []u64, i: *usize) void {",
+        ],
+        List.map fieldsWithOffsets \field -> "$(indent)lowerFlat$(field.id)(v.$(field.name), flat, i);",
+        ["}"],
+        if List.isEmpty freeFields then
+            []
+        else
+            List.join [
+                ["", "fn freeMem$(n)(p: [*]const u8) void {"],
+                List.map freeFields \field -> "$(indent)freeMem$(field.id)(p + $(field.offset));",
+                ["}", "", "fn release$(n)(v: Roc$(n)) void {"],
+                List.map freeFields \field -> "$(indent)release$(field.id)(v.$(field.name));",
+                ["}"],
+            ],
+    ]
+    |> lines
+
+enumConversions : Str, List Str, U32 -> Str
+enumConversions = \n, tags, size ->
+    rocType = "u$(Num.toStr (size * 8))"
+    canonType = "u$(Num.toStr (canonDiscriminantSize (List.len tags) * 8))"
+
+    lines [
+        "const Roc$(n) = $(rocType);",
+        "",
+        "fn liftMem$(n)(p: [*]const u8) Roc$(n) {",
+        "$(indent)return @intCast(abi.load($(canonType), p));",
+        "}",
+        "",
+        "fn liftFlat$(n)(flat: []const u64, i: *usize) Roc$(n) {",
+        "$(indent)i.* += 1;",
+        "$(indent)return @intCast(abi.fromSlot(u32, flat[i.* - 1]));",
+        "}",
+        "",
+        "fn lowerMem$(n)(v: Roc$(n), p: [*]u8) void {",
+        "$(indent)abi.store($(canonType), p, @intCast(v));",
+        "}",
+        "",
+        "fn lowerFlat$(n)(v: Roc$(n), flat: []u64, i: *usize) void {",
+        "$(indent)flat[i.*] = v;",
+        "$(indent)i.* += 1;",
+        "}",
+    ]
+
+variantConversions : Types, TypeId, { cases : List Case, rocDiscriminantSize : U32, rocDiscriminantOffset : U32 }* -> Str
+variantConversions = \types, id, { cases, rocDiscriminantSize, rocDiscriminantOffset } ->
+    n = idStr id
+    { payloadOffset } = variantLayout types cases
+    rocAlign = Num.max 1 (Types.alignment types id)
+    rocSize = Num.toStr (alignTo (Types.size types id) rocAlign)
+    canonTag = "u$(Num.toStr (canonDiscriminantSize (List.len cases) * 8))"
+    rocTag = "u$(Num.toStr (rocDiscriminantSize * 8))"
+    rocTagOffset = Num.toStr rocDiscriminantOffset
+    flatPayloadCount = Num.toStr (List.len (flatten types id) - 1)
+    hasPayloads = !(List.isEmpty (casePayloads cases))
+
+    # Each case, with its payload's Zig type and conversion function suffix
+    indexedCases =
+        List.mapWithIndex cases \case, index ->
+            payload =
+                when case.payload is
+                    Some payloadId ->
+                        Some {
+                            n: idStr payloadId,
+                            t: zigType types payloadId,
+                            needsFree: needsFree types payloadId,
+                        }
+
+                    None -> None
+
+            { canonTag: Num.toStr index, rocTag: Num.toStr case.rocTag, payload }
+
+    storeRocTag = \case -> "abi.store($(rocTag), @as([*]u8, &v.bytes) + $(rocTagOffset), $(case.rocTag))"
+    loadRocTag = "abi.load($(rocTag), @as([*]const u8, &v.bytes) + $(rocTagOffset))"
+
+    liftMemArms =
+        List.joinMap indexedCases \case ->
+            when case.payload is
+                Some payload -> [
+                        "$(indent)$(indent)$(case.canonTag) => {",
+                        "$(indent)$(indent)$(indent)abi.store($(payload.t), &v.bytes, liftMem$(payload.n)(p + $(Num.toStr payloadOffset)));",
+                        "$(indent)$(indent)$(indent)$(storeRocTag case);",
+                        "$(indent)$(indent)},",
+                    ]
+
+                None -> ["$(indent)$(indent)$(case.canonTag) => $(storeRocTag case),"]
+
+    liftFlatArms =
+        List.joinMap indexedCases \case ->
+            when case.payload is
+                Some payload -> [
+                        "$(indent)$(indent)$(case.canonTag) => {",
+                        "$(indent)$(indent)$(indent)abi.store($(payload.t), &v.bytes, liftFlat$(payload.n)(flat, &j));",
+                        "$(indent)$(indent)$(indent)$(storeRocTag case);",
+                        "$(indent)$(indent)},",
+                    ]
+
+                None -> ["$(indent)$(indent)$(case.canonTag) => $(storeRocTag case),"]
+
+    lowerMemArms =
+        List.joinMap indexedCases \case ->
+            when case.payload is
+                Some payload -> [
+                        "$(indent)$(indent)$(case.rocTag) => {",
+                        "$(indent)$(indent)$(indent)abi.store($(canonTag), p, $(case.canonTag));",
+                        "$(indent)$(indent)$(indent)lowerMem$(payload.n)(abi.load($(payload.t), &v.bytes), p + $(Num.toStr payloadOffset));",
+                        "$(indent)$(indent)},",
+                    ]
+
+                None -> ["$(indent)$(indent)$(case.rocTag) => abi.store($(canonTag), p, $(case.canonTag)),"]
+
+    lowerFlatArms =
+        List.joinMap indexedCases \case ->
+            when case.payload is
+                Some payload -> [
+                        "$(indent)$(indent)$(case.rocTag) => {",
+                        "$(indent)$(indent)$(indent)flat[i.*] = $(case.canonTag);",
+                        "$(indent)$(indent)$(indent)lowerFlat$(payload.n)(abi.load($(payload.t), &v.bytes), flat, &j);",
+                        "$(indent)$(indent)},",
+                    ]
+
+                None -> ["$(indent)$(indent)$(case.rocTag) => flat[i.*] = $(case.canonTag),"]
+
+    freeCases =
+        List.keepIf indexedCases \case ->
+            when case.payload is
+                Some payload -> payload.needsFree
+                None -> Bool.false
+
+    freeMemArms =
+        List.joinMap freeCases \case ->
+            when case.payload is
+                Some payload -> ["$(indent)$(indent)$(case.canonTag) => freeMem$(payload.n)(p + $(Num.toStr payloadOffset)),"]
+                None -> []
+
+    releaseArms =
+        List.joinMap freeCases \case ->
+            when case.payload is
+                Some payload -> ["$(indent)$(indent)$(case.rocTag) => release$(payload.n)(abi.load($(payload.t), &v.bytes)),"]
+                None -> []
+
+    flatCursor =
+        if hasPayloads then
+            ["$(indent)var j = i.* + 1;"]
+        else
+            []
+
+    clearFlatPayload =
+        if hasPayloads then
+            ["$(indent)@memset(flat[i.* + 1 .. i.* + 1 + $(flatPayloadCount)], 0);"]
+        else
+            []
+
+    unreachableArm = ["$(indent)$(indent)else => unreachable,", "$(indent)}"]
+
+    List.join [
+        [
+            "const Roc$(n) = extern struct {",
+            "$(indent)bytes: [$(rocSize)]u8 align($(Num.toStr rocAlign)),",
+            "};",
+            "",
+            "fn liftMem$(n)(p: [*]const u8) Roc$(n) {",
+            "$(indent)var v: Roc$(n) = undefined;",
+            "$(indent)switch (abi.load($(canonTag), p)) {",
+        ],
+        liftMemArms,
+        unreachableArm,
+        [
+            "$(indent)return v;",
+            "}",
+            "",
+            "fn liftFlat$(n)(flat: []const u64, i: *usize) Roc$(n) {",
+            "$(indent)var v: Roc$(n) = undefined;",
+        ],
+        flatCursor,
+        ["$(indent)switch (abi.fromSlot(u32, flat[i.*])) {"],
+        liftFlatArms,
+        unreachableArm,
+        [
+            "$(indent)i.* += 1 + $(flatPayloadCount);",
+            "$(indent)return v;",
+            "}",
+            "",
+            "fn lowerMem$(n)(v: Roc$(n), p: [*]u8) void {",
+            "$(indent)switch ($(loadRocTag)) {",
+        ],
+        lowerMemArms,
+        unreachableArm,
+        [
+            "}",
+            "",
+            "fn lowerFlat$(n)(v: Roc$(n), flat: []u64, i: *usize) void {",
+        ],
+        flatCursor,
+        clearFlatPayload,
+        ["$(indent)switch ($(loadRocTag)) {"],
+        lowerFlatArms,
+        unreachableArm,
+        [
+            "$(indent)i.* += 1 + $(flatPayloadCount);",
+            "}",
+        ],
+        if List.isEmpty freeCases then
+            []
+        else
+            List.join [
+                [
+                    "",
+                    "fn freeMem$(n)(p: [*]const u8) void {",
+                    "$(indent)switch (abi.load($(canonTag), p)) {",
+                ],
+                freeMemArms,
+                [
+                    "$(indent)$(indent)else => {},",
+                    "$(indent)}",
+                    "}",
+                    "",
+                    "fn release$(n)(v: Roc$(n)) void {",
+                    "$(indent)switch ($(loadRocTag)) {",
+                ],
+                releaseArms,
+                [
+                    "$(indent)$(indent)else => {},",
+                    "$(indent)}",
+                    "}",
+                ],
+            ],
+    ]
+    |> lines
+
+nonUnitArgs : Types, List TypeId -> List { index : U64, id : TypeId }
+nonUnitArgs = \types, args ->
+    args
+    |> List.mapWithIndex (\id, index -> { index, id })
+    |> List.dropIf \{ id } -> isUnitType types id
+
+## A core Wasm export with the canonical ABI's signature for an entry point,
+## which converts its arguments for Roc, calls Roc, and converts the return value back.
+zigExport : Types, EntryPoint, U64 -> Str
+zigExport = \types, { name, args, ret }, index ->
+    exportName = witName name
+    rocArgs = nonUnitArgs types args
+    flatParams = List.joinMap rocArgs \{ id } -> flatten types id
+    flatResults = flatten types ret
+    retIsUnit = isUnitType types ret
+    retIsIndirect = List.len flatResults > 1
+    retArea = "ret_area_$(Num.toStr index)"
+
+    params =
+        if List.len flatParams > maxFlatParams then
+            "args: i32"
+        else
+            flatParams
+            |> List.mapWithIndex (\coreType, k -> "p$(Num.toStr k): $(coreTypeName coreType)")
+            |> Str.joinWith ", "
+
+    resultType =
+        when List.first flatResults is
+            Err ListWasEmpty -> "void"
+            Ok coreType -> if retIsIndirect then "i32" else coreTypeName coreType
+
+    argName = \{ index: argIndex } -> "arg$(Num.toStr argIndex)"
+
+    declareArg = \arg, value ->
+        if isPassedByValue types arg.id then
+            "$(indent)const $(argName arg) = $(value);"
+        else
+            "$(indent)var $(argName arg) = $(value);"
+
+    liftArgs =
+        if List.isEmpty rocArgs then
+            []
+        else if List.len flatParams > maxFlatParams then
+            argFields = List.map rocArgs \{ id } -> { name: "", id }
+            { size, align, offsets } = recordLayout types argFields
+
+            List.join [
+                ["$(indent)const p = abi.toPtr(@bitCast(args));"],
+                List.map2 rocArgs offsets \arg, offset ->
+                    declareArg arg "liftMem$(idStr arg.id)(p + $(Num.toStr offset))",
+                ["$(indent)abi.free(@bitCast(args), $(Num.toStr size), $(Num.toStr align));"],
+            ]
+        else
+            slots =
+                flatParams
+                |> List.mapWithIndex (\coreType, k -> "abi.toSlot($(coreTypeName coreType), p$(Num.toStr k))")
+                |> Str.joinWith ", "
+
+            List.join [
+                [
+                    "$(indent)const flat = [_]u64{ $(slots) };",
+                    "$(indent)var i: usize = 0;",
+                ],
+                List.map rocArgs \arg -> declareArg arg "liftFlat$(idStr arg.id)(&flat, &i)",
+            ]
+
+    externParams =
+        rocArgs
+        |> List.map \arg ->
+            if isPassedByValue types arg.id then
+                "$(argName arg): $(zigType types arg.id)"
+            else
+                "$(argName arg): *$(zigType types arg.id)"
+
+    callArgs =
+        rocArgs
+        |> List.map \arg ->
+            if isPassedByValue types arg.id then
+                argName arg
+            else
+                "&$(argName arg)"
+
+    rocName = "roc__$(name)_1_exposed"
+    retType = zigType types ret
+
+    (externDecl, call) =
+        if retIsUnit then
+            externParamsStr = Str.joinWith externParams ", "
+            callArgsStr = Str.joinWith callArgs ", "
+
+            (
+                "extern fn $(rocName)($(externParamsStr)) void;",
+                ["$(indent)$(rocName)($(callArgsStr));"],
+            )
+        else if isPassedByValue types ret then
+            externParamsStr = Str.joinWith externParams ", "
+            callArgsStr = Str.joinWith callArgs ", "
+
+            (
+                "extern fn $(rocName)($(externParamsStr)) $(retType);",
+                ["$(indent)const ret = $(rocName)($(callArgsStr));"],
+            )
+        else
+            # Roc writes the return value to a pointer passed as the first argument
+            externParamsStr = List.prepend externParams "ret: *$(retType)" |> Str.joinWith ", "
+            callArgsStr = List.prepend callArgs "&ret" |> Str.joinWith ", "
+
+            (
+                "extern fn $(rocName)($(externParamsStr)) void;",
+                [
+                    "$(indent)var ret: $(retType) = undefined;",
+                    "$(indent)$(rocName)($(callArgsStr));",
+                ],
+            )
+
+    retNeedsFree = !retIsUnit && needsFree types ret
+    releaseRet =
+        if retNeedsFree then
+            ["$(indent)release$(idStr ret)(ret);"]
+        else
+            []
+
+    returnRet =
+        if retIsUnit then
+            []
+        else if retIsIndirect then
+            List.concat
+                ["$(indent)lowerMem$(idStr ret)(ret, &$(retArea));"]
+                (List.append releaseRet "$(indent)return @bitCast(abi.toAddress(&$(retArea)));")
+        else
+            List.join [
+                [
+                    "$(indent)var out = [_]u64{0};",
+                    "$(indent)var j: usize = 0;",
+                    "$(indent)lowerFlat$(idStr ret)(ret, &out, &j);",
+                ],
+                releaseRet,
+                ["$(indent)return abi.fromSlot($(resultType), out[0]);"],
+            ]
+
+    retAreaDecl =
+        if retIsIndirect then
+            { size, align } = canonLayout types ret
+
+            ["var $(retArea): [$(Num.toStr size)]u8 align($(Num.toStr align)) = undefined;", ""]
+        else
+            []
+
+    postReturn =
+        if retIsIndirect && retNeedsFree then
+            [
+                "",
+                "export fn @\"cabi_post_$(exportName)\"(ret: i32) void {",
+                "$(indent)freeMem$(idStr ret)(abi.toPtr(@bitCast(ret)));",
+                "}",
+            ]
+        else
+            []
+
+    List.join [
+        [externDecl, ""],
+        retAreaDecl,
+        ["export fn @\"$(exportName)\"($(params)) $(resultType) {"],
+        liftArgs,
+        call,
+        returnRet,
+        ["}"],
+        postReturn,
+    ]
+    |> lines
+
+indent = "    "
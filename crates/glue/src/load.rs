@@ -12,8 +12,11 @@ use roc_build::{
 use roc_collections::MutMap;
 use roc_error_macros::todo_lambda_erasure;
 use roc_gen_llvm::run_roc::RocCallResult;
-use roc_load::{ExecutionMode, FunctionKind, LoadConfig, LoadedModule, LoadingProblem, Threading};
-use roc_mono::ir::{generate_glue_procs, CrashTag, GlueProc, OptLevel};
+use roc_load::{
+    ExecutionMode, FunctionKind, LoadConfig, LoadedModule, LoadingProblem, OutputConfig, Threading,
+};
+use roc_mono::inline::InlineLevel;
+use roc_mono::ir::{generate_glue_procs, CrashTag, GlueProc};
 use roc_mono::layout::{GlobalLayoutInterner, LayoutCache, LayoutInterner};
 use roc_packaging::cache::{self, RocCacheDir};
 use roc_reporting::report::{RenderTarget, DEFAULT_PALETTE};
@@ -55,10 +58,16 @@ pub fn generate(
 
             let code_gen_options = CodeGenOptions {
                 backend,
-                opt_level: OptLevel::Development,
+                inline_level: InlineLevel::Off,
                 emit_debug_info: false,
+                emit_source_debug_info: false,
                 emit_llvm_ir: false,
+                emit_asm: false,
+                emit_wat: false,
                 fuzz: false,
+                report_spec_sizes: false,
+                instrument_memory: false,
+                register_allocation: Default::default(),
             };
 
             let load_config = standard_load_config(
@@ -422,6 +431,7 @@ pub fn load_types(
             palette: DEFAULT_PALETTE,
             threading,
             exec_mode: ExecutionMode::Check,
+            output: OutputConfig::default(),
         },
     )
     .unwrap_or_else(|problem| match problem {
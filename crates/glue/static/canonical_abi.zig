@@ -0,0 +1,106 @@
+//! This was generated by `roc glue`, using the WIT glue spec.
+//!
+//! Helpers for roc_app.zig, which converts between Roc's in-memory representation of values
+//! and the WebAssembly component model's canonical ABI:
+//! https://github.com/WebAssembly/component-model/blob/main/design/mvp/CanonicalABI.md
+const std = @import("std");
+const utils = @import("utils.zig");
+
+pub const RocStr = @import("str.zig").RocStr;
+pub const RocList = @import("list.zig").RocList;
+
+/// A string or list in the canonical ABI
+pub const Slice = struct { ptr: u32, len: u32 };
+
+pub fn load(comptime T: type, ptr: [*]const u8) T {
+    if (T == bool) {
+        return ptr[0] != 0;
+    }
+    return @as(*align(1) const T, @ptrCast(ptr)).*;
+}
+
+pub fn store(comptime T: type, ptr: [*]u8, value: T) void {
+    if (T == bool) {
+        ptr[0] = @intFromBool(value);
+    } else {
+        @as(*align(1) T, @ptrCast(ptr)).* = value;
+    }
+}
+
+// Flattened values are passed around as raw bits, zero-extended to 64 bits.
+// That makes the "join" of variant cases free: every coercion it requires
+// (like f32 to i32, or i32 to i64) is a reinterpretation of the same bits.
+
+pub fn toSlot(comptime T: type, value: T) u64 {
+    return switch (@typeInfo(T)) {
+        .Bool => @intFromBool(value),
+        .Int => |int| if (int.bits <= 32)
+            @as(u32, @bitCast(@as(std.meta.Int(int.signedness, 32), value)))
+        else
+            @as(u64, @bitCast(value)),
+        .Float => |float| @as(std.meta.Int(.unsigned, float.bits), @bitCast(value)),
+        else => @compileError("Not a flat canonical ABI type: " ++ @typeName(T)),
+    };
+}
+
+pub fn fromSlot(comptime T: type, slot: u64) T {
+    return switch (@typeInfo(T)) {
+        .Bool => @as(u32, @truncate(slot)) != 0,
+        .Int => |int| @bitCast(@as(std.meta.Int(.unsigned, int.bits), @truncate(slot))),
+        .Float => |float| @bitCast(@as(std.meta.Int(.unsigned, float.bits), @truncate(slot))),
+        else => @compileError("Not a flat canonical ABI type: " ++ @typeName(T)),
+    };
+}
+
+pub fn toPtr(address: u32) [*]u8 {
+    return @ptrFromInt(address);
+}
+
+pub fn toAddress(ptr: anytype) u32 {
+    return @intCast(@intFromPtr(ptr));
+}
+
+/// Allocate memory for a canonical ABI value. Empty allocations get a dangling, aligned address.
+pub fn allocate(size: usize, alignment: u32) u32 {
+    if (size == 0) {
+        return alignment;
+    }
+    return toAddress(utils.alloc(size, alignment) orelse unreachable);
+}
+
+pub fn free(address: u32, size: usize, alignment: u32) void {
+    if (size != 0) {
+        utils.dealloc(toPtr(address), alignment);
+    }
+}
+
+pub fn realloc(old_address: u32, old_size: usize, alignment: u32, new_size: usize) u32 {
+    if (old_size == 0) {
+        return allocate(new_size, alignment);
+    }
+    if (new_size == 0) {
+        free(old_address, old_size, alignment);
+        return alignment;
+    }
+    return toAddress(utils.realloc(toPtr(old_address), new_size, old_size, alignment));
+}
+
+/// Copy a canonical ABI string into a new RocStr, taking ownership of its memory
+pub fn liftStr(address: u32, len: u32) RocStr {
+    if (len == 0) {
+        return RocStr.empty();
+    }
+    const string = RocStr.init(toPtr(address), len);
+    free(address, len, 1);
+    return string;
+}
+
+/// Copy a RocStr into a new canonical ABI string. It's freed after the call, in `cabi_post_*`.
+pub fn lowerStr(string: RocStr) Slice {
+    const len: u32 = @intCast(string.len());
+    const address = allocate(len, 1);
+    if (len != 0) {
+        @memcpy(toPtr(address)[0..len], string.asU8ptr()[0..len]);
+    }
+    return .{ .ptr = address, .len = len };
+}
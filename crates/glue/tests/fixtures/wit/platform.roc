@@ -0,0 +1,18 @@
+platform "test-platform"
+    requires {} { main : _ }
+    exposes []
+    packages {}
+    imports []
+    provides [mainForHost]
+
+# This covers each kind of WIT definition the WIT glue spec generates:
+# a record, an enum, and a variant whose cases have a record, a string,
+# or no payload.
+Point : { x : I32, y : I32 }
+
+Color : [Red, Green, Blue]
+
+Shape : [Circle Point, Dot, Label Str]
+
+mainForHost : Point, Color -> Shape
+mainForHost = \point, color -> main point color
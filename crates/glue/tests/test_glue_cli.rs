@@ -91,6 +91,8 @@ mod glue_cli_run {
                     all_fixtures.insert($fixture_dir.to_string());
                 )*
 
+                all_fixtures.insert(WIT_FIXTURE.to_string());
+
                 check_for_tests(&mut all_fixtures);
             }
         }
@@ -168,6 +170,102 @@ mod glue_cli_run {
         "#),
     }
 
+    /// This fixture has no app or Rust host. Instead, its test checks the WIT world
+    /// that WitGlue.roc generates for the platform, and compiles the generated Zig.
+    const WIT_FIXTURE: &str = "wit";
+
+    #[test]
+    fn wit_glue() {
+        let platform_dir = fixtures_dir(WIT_FIXTURE);
+        let glue_dir = platform_dir.join("test_glue");
+
+        if glue_dir.exists() {
+            fs::remove_dir_all(&glue_dir)
+                .expect("Unable to remove test_glue dir in order to regenerate it in the test");
+        }
+
+        let wit_glue_spec = platform_dir
+            .parent()
+            .unwrap()
+            .parent()
+            .unwrap()
+            .parent()
+            .unwrap()
+            .join("src")
+            .join("WitGlue.roc");
+
+        let glue_out = run_glue([
+            "glue",
+            wit_glue_spec.to_str().unwrap(),
+            glue_dir.to_str().unwrap(),
+            platform_dir.join("platform.roc").to_str().unwrap(),
+        ]);
+
+        if has_error(&glue_out.stderr) {
+            panic!(
+                "`roc glue` command had unexpected stderr: {}",
+                glue_out.stderr
+            );
+        }
+
+        assert!(glue_out.status.success(), "bad status {glue_out:?}");
+
+        let wit = fs::read_to_string(glue_dir.join("roc_app.wit")).unwrap();
+
+        assert_eq!(
+            wit.trim_end(),
+            indoc!(
+                r#"
+                // ⚠️ GENERATED CODE ⚠️ - this entire file was generated by the `roc glue` CLI command
+                package roc:app;
+
+                world roc-app {
+                    record point {
+                        x: s32,
+                        y: s32,
+                    }
+
+                    enum color {
+                        blue,
+                        green,
+                        red,
+                    }
+
+                    variant shape {
+                        circle(point),
+                        dot,
+                        label(string),
+                    }
+
+                    export main-for-host: func(arg0: point, arg1: color) -> shape;
+                }
+                "#
+            )
+            .trim_end()
+        );
+
+        // roc_app.zig imports canonical_abi.zig and Roc's builtins, which the glue spec
+        // writes next to it. Building an object doesn't need the app's entry points, so
+        // this checks that all of the generated conversions compile for wasm32.
+        let zig_out = roc_command_utils::zig()
+            .current_dir(&glue_dir)
+            .args([
+                "build-obj",
+                "roc_app.zig",
+                "-target",
+                "wasm32-freestanding-musl",
+                "-femit-bin=roc_app.o",
+            ])
+            .output()
+            .unwrap();
+
+        assert!(
+            zig_out.status.success(),
+            "zig could not compile the generated roc_app.zig:\n{}",
+            String::from_utf8_lossy(&zig_out.stderr)
+        );
+    }
+
     fn check_for_tests(all_fixtures: &mut roc_collections::VecSet<String>) {
         use roc_collections::VecSet;
 
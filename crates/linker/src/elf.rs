@@ -20,7 +20,8 @@ use std::{
 
 use crate::{
     align_by_constraint, align_to_offset_by_constraint, load_struct_inplace,
-    load_struct_inplace_mut, load_structs_inplace_mut, open_mmap, open_mmap_mut,
+    load_struct_inplace_mut, load_structs_inplace, load_structs_inplace_mut, open_mmap,
+    open_mmap_mut,
 };
 
 const MIN_SECTION_ALIGNMENT: usize = 0x40;
@@ -38,7 +39,8 @@ struct ElfDynamicDeps {
 
 #[derive(Serialize, Deserialize, PartialEq, Eq, Debug)]
 enum VirtualOffset {
-    Absolute,
+    /// The address itself, plus an addend (e.g. for a pointer into a table of functions)
+    Absolute(i64),
     Relative(u64),
 }
 
@@ -161,6 +163,36 @@ fn collect_roc_definitions<'a>(object: &object::File<'a, &'a [u8]>) -> MutMap<St
     vaddresses
 }
 
+/// Whether an ELF file is a shared library, e.g. the host of a plugin-style platform.
+///
+/// Position-independent executables have the same `ET_DYN` type as shared libraries, so this
+/// also checks that there is no program interpreter.
+pub(crate) fn is_shared_object(data: &[u8]) -> bool {
+    let is_elf64 = data.len() >= mem::size_of::<elf::FileHeader64<LE>>()
+        && data.starts_with(&elf::ELFMAG)
+        && data[elf::EI_CLASS] == elf::ELFCLASS64;
+
+    if !is_elf64 {
+        return false;
+    }
+
+    let header = load_struct_inplace::<elf::FileHeader64<LE>>(data, 0);
+
+    if header.e_type.get(LE) != elf::ET_DYN {
+        return false;
+    }
+
+    let program_headers = load_structs_inplace::<elf::ProgramHeader64<LE>>(
+        data,
+        header.e_phoff.get(LE) as usize,
+        header.e_phnum.get(LE) as usize,
+    );
+
+    !program_headers
+        .iter()
+        .any(|ph| ph.p_type.get(LE) == elf::PT_INTERP)
+}
+
 struct Surgeries<'a> {
     surgeries: MutMap<String, Vec<SurgeryEntry>>,
     app_func_addresses: MutMap<u64, &'a str>,
@@ -356,6 +388,10 @@ pub(crate) fn preprocess_elf_le(
     };
 
     if verbose {
+        if is_shared_object(exec_data) {
+            println!("The host is a shared library");
+        }
+
         println!(
             "Found {} roc symbol definitions:",
             md.roc_symbol_vaddresses.len()
@@ -555,6 +591,32 @@ pub(crate) fn preprocess_elf_le(
     }
 }
 
+/// Turn a relocation of the address of an app function into a RELATIVE relocation. Surgery sets
+/// its addend to the function's address, plus the addend returned here.
+fn redirect_to_app<'s>(
+    rel: &mut elf::Rela64<LE>,
+    got_app_syms: &'s [(String, usize)],
+) -> Option<(&'s str, i64)> {
+    let r_type = rel.r_type(LE, false);
+    if r_type != elf::R_X86_64_GLOB_DAT && r_type != elf::R_X86_64_64 {
+        return None;
+    }
+
+    let r_sym = rel.r_sym(LE, false);
+    let (name, _) = got_app_syms
+        .iter()
+        .find(|(_, index)| *index as u32 == r_sym)?;
+
+    // GLOB_DAT ignores its addend
+    let addend = match r_type {
+        elf::R_X86_64_64 => rel.r_addend.get(LE),
+        _ => 0,
+    };
+    rel.set_r_info(LE, false, 0, elf::R_X86_64_RELATIVE);
+
+    Some((name, addend))
+}
+
 #[allow(clippy::too_many_arguments)]
 fn gen_elf_le(
     exec_data: &[u8],
@@ -733,23 +795,16 @@ fn gen_elf_le(
                 }
             }
             // If the relocation goes to a roc function, we need to surgically link it and change it to relative.
-            let r_type = rel.r_type(LE, false);
-            if r_type == elf::R_X86_64_GLOB_DAT {
-                let r_sym = rel.r_sym(LE, false);
-                for (name, index) in got_app_syms.iter() {
-                    if *index as u32 == r_sym {
-                        rel.set_r_info(LE, false, 0, elf::R_X86_64_RELATIVE);
-                        let addend_addr = sec_offset as usize
-                            + i * mem::size_of::<elf::Rela64<LE>>()
-                            // This 16 skips the first 2 fields and gets to the addend field.
-                            + 16;
-                        md.surgeries.get_mut(name).unwrap().push(SurgeryEntry {
-                            file_offset: addend_addr as u64,
-                            virtual_offset: VirtualOffset::Absolute,
-                            size: 8,
-                        });
-                    }
-                }
+            if let Some((name, addend)) = redirect_to_app(rel, got_app_syms) {
+                let addend_addr = sec_offset as usize
+                    + i * mem::size_of::<elf::Rela64<LE>>()
+                    // This 16 skips the first 2 fields and gets to the addend field.
+                    + 16;
+                md.surgeries.get_mut(name).unwrap().push(SurgeryEntry {
+                    file_offset: addend_addr as u64,
+                    virtual_offset: VirtualOffset::Absolute(addend),
+                    size: 8,
+                });
             }
         }
         // To correctly remove the JUMP_SLOT relocations for Roc functions we:
@@ -1023,25 +1078,25 @@ fn scan_elf_dynamic_deps(
     }
     let shared_lib_index = shared_lib_index.unwrap();
 
-    let symtab_sec = match exec_obj.section_by_name(".symtab") {
-        Some(sec) => sec,
-        None => {
-            panic!("There must be a symtab section in the executable");
-        }
-    };
-    let symtab_offset = match symtab_sec.compressed_file_range() {
-        Ok(
-            range @ CompressedFileRange {
-                format: CompressionFormat::None,
-                ..
-            },
-        ) => range.offset as usize,
-        _ => {
-            panic!("Surgical linking does not work with compressed symtab section");
-        }
-    };
-    md.symbol_table_section_offset = symtab_offset as u64;
-    md.symbol_table_size = symtab_sec.size();
+    // Stripped hosts (shared libraries in particular) may not have a static symbol table.
+    // In that case there are no static symbols to update.
+    if let Some(symtab_sec) = exec_obj.section_by_name(".symtab") {
+        let symtab_offset = match symtab_sec.compressed_file_range() {
+            Ok(
+                range @ CompressedFileRange {
+                    format: CompressionFormat::None,
+                    ..
+                },
+            ) => range.offset as usize,
+            _ => {
+                panic!("Surgical linking does not work with compressed symtab section");
+            }
+        };
+        md.symbol_table_section_offset = symtab_offset as u64;
+        md.symbol_table_size = symtab_sec.size();
+    } else if verbose {
+        println!("The host has no symtab section");
+    }
 
     let dynsym_sec = match exec_obj.section_by_name(".dynsym") {
         Some(sec) => sec,
@@ -1088,7 +1143,12 @@ fn scan_elf_dynamic_deps(
         }
     })
     .filter_map(|(_, reloc)| {
-        if let RelocationKind::Elf(elf::R_X86_64_GLOB_DAT) = reloc.kind() {
+        // Shared libraries can also refer to app functions with plain 64-bit addresses,
+        // e.g. in a table of function pointers.
+        let is_address = matches!(reloc.kind(), RelocationKind::Elf(elf::R_X86_64_GLOB_DAT))
+            || (reloc.kind() == RelocationKind::Absolute && reloc.size() == 64);
+
+        if is_address {
             for symbol in app_syms.iter() {
                 if reloc.target() == RelocationTarget::Symbol(symbol.index()) {
                     return Some((symbol.name().unwrap().to_string(), symbol.index().0));
@@ -1637,7 +1697,7 @@ fn surgery_elf_help(
             }
             let surgery_virt_offset = match s.virtual_offset {
                 VirtualOffset::Relative(vs) => (vs + md.added_byte_count) as i64,
-                VirtualOffset::Absolute => 0,
+                VirtualOffset::Absolute(addend) => -addend,
             };
             match s.size {
                 4 => {
@@ -1781,6 +1841,44 @@ mod tests {
         )
     }
 
+    #[test]
+    fn redirect_address_with_addend() {
+        let got_app_syms = [("roc_magic1".to_string(), 3), ("roc_magic2".to_string(), 5)];
+        let rela = |sym: u32, r_type: u32, addend: i64| {
+            let mut rel = elf::Rela64::<LE> {
+                r_offset: endian::U64::new(LE, 0x1000),
+                r_info: endian::U64::new(LE, 0),
+                r_addend: endian::I64::new(LE, addend),
+            };
+            rel.set_r_info(LE, false, sym, r_type);
+            rel
+        };
+
+        // e.g. `&table[1]` where table is a roc function pointer table
+        let mut rel = rela(5, elf::R_X86_64_64, 8);
+        assert_eq!(
+            redirect_to_app(&mut rel, &got_app_syms),
+            Some(("roc_magic2", 8))
+        );
+        assert_eq!(rel.r_type(LE, false), elf::R_X86_64_RELATIVE);
+        assert_eq!(rel.r_sym(LE, false), 0);
+
+        let mut rel = rela(3, elf::R_X86_64_GLOB_DAT, 0);
+        assert_eq!(
+            redirect_to_app(&mut rel, &got_app_syms),
+            Some(("roc_magic1", 0))
+        );
+
+        // not an app function
+        let mut rel = rela(4, elf::R_X86_64_64, 8);
+        assert_eq!(redirect_to_app(&mut rel, &got_app_syms), None);
+        assert_eq!(rel.r_type(LE, false), elf::R_X86_64_64);
+
+        // calls go through the PLT instead
+        let mut rel = rela(3, elf::R_X86_64_JUMP_SLOT, 0);
+        assert_eq!(redirect_to_app(&mut rel, &got_app_syms), None);
+    }
+
     #[allow(dead_code)]
     fn zig_host_app_help(dir: &Path, target: Target) {
         let host_zig = indoc!(
@@ -1915,4 +2013,109 @@ mod tests {
 
         assert_eq!("Hello foo\n", output);
     }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn zig_host_lib() {
+        let dir = tempfile::tempdir().unwrap();
+        let dir = dir.path();
+
+        let host_zig = indoc!(
+            r#"
+            extern fn roc_magic1(usize) callconv(.C) [*]const u8;
+
+            export fn host_first_letter() u8 {
+                return roc_magic1(0)[0];
+            }
+            "#
+        );
+
+        let app_zig = indoc!(
+            r#"
+            const X = [_][]const u8 { "foo" };
+
+            export fn roc_magic1(index: usize) [*]const u8 {
+                return X[index].ptr;
+            }
+            "#
+        );
+
+        let zig = std::env::var("ROC_ZIG").unwrap_or_else(|_| "zig".into());
+
+        std::fs::write(dir.join("host.zig"), host_zig.as_bytes()).unwrap();
+        std::fs::write(dir.join("app.zig"), app_zig.as_bytes()).unwrap();
+
+        let run = |args: &[&str]| {
+            let output = std::process::Command::new(&zig)
+                .current_dir(dir)
+                .args(args)
+                .output()
+                .unwrap();
+
+            if !output.status.success() {
+                use std::io::Write;
+
+                std::io::stdout().write_all(&output.stdout).unwrap();
+                std::io::stderr().write_all(&output.stderr).unwrap();
+
+                panic!("zig {} failed", args[0]);
+            }
+        };
+
+        run(&["build-obj", "app.zig", "-fPIC", "-OReleaseFast"]);
+
+        let file = std::fs::File::open(dir.join("app.o")).unwrap();
+        let roc_app = unsafe { memmap2::Mmap::map(&file) }.unwrap();
+
+        let dylib_bytes =
+            crate::generate_dylib::create_dylib_elf64(&["roc_magic1".into()]).unwrap();
+        std::fs::write(dir.join("libapp.so"), dylib_bytes).unwrap();
+
+        // the host is a shared library itself, e.g. a plugin
+        run(&[
+            "build-lib",
+            "-dynamic",
+            "libapp.so",
+            "host.zig",
+            "-fPIC",
+            "-lc",
+            "-OReleaseFast",
+            "-femit-bin=dynhost",
+        ]);
+
+        assert!(is_shared_object(&open_mmap(&dir.join("dynhost"))));
+
+        let preprocessed_host_filename = dir.join(preprocessed_host_filename(Target::LinuxX64));
+
+        preprocess_elf_le(
+            &dir.join("dynhost"),
+            &dir.join("metadata"),
+            &preprocessed_host_filename,
+            &dir.join("libapp.so"),
+            false,
+            false,
+        );
+
+        let final_lib = dir.join("final.so");
+        std::fs::copy(&preprocessed_host_filename, &final_lib).unwrap();
+
+        surgery_elf(&roc_app, &dir.join("metadata"), &final_lib, false, false);
+
+        assert!(is_shared_object(&open_mmap(&final_lib)));
+
+        let first_letter = unsafe {
+            let path = std::ffi::CString::new(final_lib.to_str().unwrap()).unwrap();
+            let handle = libc::dlopen(path.as_ptr(), libc::RTLD_NOW);
+            assert!(!handle.is_null(), "dlopen failed");
+
+            let symbol = libc::dlsym(handle, b"host_first_letter\0".as_ptr().cast());
+            assert!(!symbol.is_null(), "host_first_letter is not exported");
+
+            let host_first_letter: extern "C" fn() -> u8 = std::mem::transmute(symbol);
+
+            host_first_letter()
+        };
+
+        assert_eq!(b'f', first_letter);
+    }
 }
@@ -6,7 +6,7 @@
 use memmap2::{Mmap, MmapMut};
 use object::Object;
 use roc_error_macros::internal_error;
-use roc_load::{EntryPoint, ExecutionMode, ExposedToHost, LoadConfig, Threading};
+use roc_load::{EntryPoint, ExecutionMode, ExposedToHost, LoadConfig, OutputConfig, Threading};
 use roc_module::symbol::Interns;
 use roc_packaging::cache::RocCacheDir;
 use roc_reporting::report::{RenderTarget, DEFAULT_PALETTE};
@@ -31,16 +31,32 @@ pub enum LinkType {
 }
 
 pub fn supported(link_type: LinkType, target: Target) -> bool {
-    if let LinkType::Executable = link_type {
-        match target {
+    match link_type {
+        LinkType::Executable => match target {
             Target::LinuxX64 => true,
             Target::WinX64 => true,
-            // macho support is incomplete
-            Target::MacX64 => false,
+            Target::MacX64 => true,
+            Target::MacArm64 => true,
             _ => false,
-        }
-    } else {
-        false
+        },
+        // Only for platforms whose host is itself a shared library; see `is_shared_library_host`
+        LinkType::Dylib => matches!(target, Target::LinuxX64),
+        LinkType::None => false,
+    }
+}
+
+/// Whether a preprocessed host is a shared library rather than an executable.
+///
+/// Plugin-style platforms ship such a host, and `roc build --lib` links the app into it.
+/// For other platforms, `--lib` builds a library of just the app.
+pub fn is_shared_library_host(target: Target, preprocessed_host_path: &Path) -> bool {
+    if !preprocessed_host_path.exists() {
+        return false;
+    }
+
+    match target.operating_system() {
+        OperatingSystem::Linux => crate::elf::is_shared_object(&open_mmap(preprocessed_host_path)),
+        _ => false,
     }
 }
 
@@ -50,7 +66,7 @@ pub fn preprocessed_host_filename(target: Target) -> String {
     format!("{target}.{PRECOMPILED_HOST_EXT}")
 }
 
-fn metadata_file_name(target: Target) -> String {
+pub fn metadata_file_name(target: Target) -> String {
     format!("metadata_{}.rm", target)
 }
 
@@ -87,6 +103,7 @@ pub fn generate_stub_lib(
             palette: DEFAULT_PALETTE,
             threading: Threading::AllAvailable,
             exec_mode: ExecutionMode::Executable,
+            output: OutputConfig::default(),
         },
     )
     .unwrap_or_else(|problem| todo!("{:?}", problem));
@@ -325,6 +342,7 @@ fn object_matches_target<'a>(target: Target, object: &object::File<'a, &'a [u8]>
             let target_format = match target.operating_system() {
                 OperatingSystem::Linux => object::BinaryFormat::Elf,
                 OperatingSystem::Windows => object::BinaryFormat::Pe,
+                OperatingSystem::Mac => object::BinaryFormat::MachO,
                 _ => todo!("surgical linker does not support target {:?}", target),
             };
 
@@ -475,6 +493,11 @@ fn surgery(
                 verbose,
                 time,
             );
+
+            // Apple silicon refuses to run unsigned code, and surgery removed the host's signature
+            if target == Target::MacArm64 {
+                crate::macho::sign_executable(executable_path);
+            }
         }
 
         (_, OperatingSystem::Windows) => {
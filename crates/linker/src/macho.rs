@@ -1,15 +1,18 @@
 use bincode::{deserialize_from, serialize_into};
 use iced_x86::{Decoder, DecoderOptions, Instruction, OpCodeOperandKind, OpKind};
-use memmap2::MmapMut;
-use object::macho;
+use object::macho::{
+    self, DyldInfoCommand, DylibCommand, DysymtabCommand, LinkeditDataCommand, MachHeader64,
+    Section64, SegmentCommand64, SymtabCommand,
+};
 use object::{
-    CompressedFileRange, CompressionFormat, LittleEndian as LE, Object, ObjectSection,
-    ObjectSymbol, RelocationKind, RelocationTarget, Section, SectionIndex, SectionKind, Symbol,
-    SymbolIndex, SymbolSection,
+    Architecture, CompressedFileRange, CompressionFormat, LittleEndian as LE, Object,
+    ObjectSection, ObjectSymbol, RelocationEncoding, RelocationKind, RelocationTarget, Section,
+    SectionIndex, SectionKind, Symbol, SymbolIndex, SymbolSection, U32, U64,
 };
 use roc_collections::all::MutMap;
 use roc_error_macros::internal_error;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use std::{
     ffi::{c_char, CStr},
     io::{BufReader, BufWriter},
@@ -19,23 +22,47 @@ use std::{
 };
 
 use crate::{
-    align_by_constraint, align_to_offset_by_constraint, load_struct_inplace,
-    load_struct_inplace_mut, load_structs_inplace, load_structs_inplace_mut, open_mmap,
-    open_mmap_mut,
+    align_by_constraint, load_struct_inplace, load_struct_inplace_mut, load_structs_inplace,
+    open_mmap, open_mmap_mut,
 };
 
 const MIN_SECTION_ALIGNMENT: usize = 0x40;
 
-// TODO: Analyze if this offset is always correct.
-const PLT_ADDRESS_OFFSET: u64 = 0x10;
-const STUB_ADDRESS_OFFSET: u64 = 0x06;
-
-// struct MachoDynamicDeps {
-//     got_app_syms: Vec<(String, usize)>,
-//     got_sections: Vec<(usize, usize)>,
-//     dynamic_lib_count: usize,
-//     shared_lib_index: usize,
-// }
+/// Room that surgery needs after the host's load commands: segment commands for the app's code
+/// (one section) and data (two sections), plus an `LC_CODE_SIGNATURE` for signing the result.
+const APP_LOAD_COMMANDS_SIZE: usize = 2 * mem::size_of::<SegmentCommand64<LE>>()
+    + 3 * mem::size_of::<Section64<LE>>()
+    + mem::size_of::<LinkeditDataCommand<LE>>();
+
+const ARM64_NOP: u32 = 0xd503_201f;
+
+// Chained fixups (`mach-o/fixup-chains.h`), which `object` does not describe yet.
+const DYLD_CHAINED_PTR_64: u16 = 2;
+const DYLD_CHAINED_PTR_64_OFFSET: u16 = 6;
+const DYLD_CHAINED_PTR_START_NONE: u16 = 0xffff;
+const DYLD_CHAINED_IMPORT: u32 = 1;
+const DYLD_CHAINED_IMPORT_ADDEND: u32 = 2;
+const DYLD_CHAINED_IMPORT_ADDEND64: u32 = 3;
+const CHAINED_BIND_BIT: u64 = 1 << 63;
+const CHAINED_NEXT_SHIFT: u32 = 51;
+const CHAINED_NEXT_MASK: u64 = 0xfff << CHAINED_NEXT_SHIFT;
+
+// Code signatures (`kern/cs_blobs.h`), all big-endian.
+const CSMAGIC_EMBEDDED_SIGNATURE: u32 = 0xfade_0cc0;
+const CSMAGIC_CODEDIRECTORY: u32 = 0xfade_0c02;
+const CSSLOT_CODEDIRECTORY: u32 = 0;
+const CS_SUPPORTSEXECSEG: u32 = 0x2_0400;
+const CS_ADHOC: u32 = 0x2;
+const CS_LINKER_SIGNED: u32 = 0x2_0000;
+const CS_HASHTYPE_SHA256: u8 = 2;
+const CS_HASH_SIZE: usize = 32;
+const CS_EXECSEG_MAIN_BINARY: u64 = 0x1;
+/// Code signatures hash 4KiB pages, whatever the page size of the segments.
+const CS_PAGE_SIZE: usize = 0x1000;
+/// The SuperBlob header and the index of its one blob
+const CS_SUPER_BLOB_SIZE: usize = 12 + 8;
+/// A CodeDirectory of version `CS_SUPPORTSEXECSEG`, without its identifier and hashes
+const CS_CODE_DIRECTORY_SIZE: usize = 88;
 
 #[derive(Serialize, Deserialize, PartialEq, Eq, Debug)]
 enum VirtualOffset {
@@ -50,29 +77,40 @@ struct SurgeryEntry {
     size: u8,
 }
 
-// TODO: Reanalyze each piece of data in this struct.
-// I think a number of them can be combined to reduce string duplication.
-// Also I think a few of them aren't need.
-// For example, I think preprocessing can deal with all shifting and remove the need for added_byte_count.
+/// How dyld slides a pointer in the host's data.
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone, Copy)]
+enum PointerFixup {
+    /// Preprocessing added a rebase for it to the classic dyld info.
+    Rebase,
+    /// It is part of a chain of fixups, where the pointer itself says how to slide it.
+    Chained,
+}
+
+/// A pointer in the host's data that dyld would have bound to an app symbol.
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug)]
+struct AppPointer {
+    file_offset: u64,
+    addend: i64,
+    fixup: PointerFixup,
+}
+
 // TODO: we probably should be storing numbers in an endian neutral way.
 #[derive(Default, Serialize, Deserialize, PartialEq, Eq, Debug)]
 struct Metadata {
+    cpu_type: u32,
+    page_size: u64,
+    /// The address of the `__TEXT` segment, which chained fixups count offsets from.
+    image_base: u64,
     app_functions: Vec<String>,
     // offset followed by address.
-    plt_addresses: MutMap<String, (u64, u64)>,
+    stubs: MutMap<String, (u64, u64)>,
+    stub_size: u64,
     surgeries: MutMap<String, Vec<SurgeryEntry>>,
-    dynamic_symbol_indices: MutMap<String, u64>,
-    _static_symbol_indices: MutMap<String, u64>,
+    app_pointers: MutMap<String, Vec<AppPointer>>,
     roc_symbol_vaddresses: MutMap<String, u64>,
-    exec_len: u64,
-    load_align_constraint: u64,
-    added_byte_count: u64,
-    last_vaddr: u64,
-    _dynamic_section_offset: u64,
-    _dynamic_symbol_table_section_offset: u64,
-    _symbol_table_section_offset: u64,
-    _symbol_table_size: u64,
-    macho_cmd_loc: u64,
+    /// Set when the host uses chained fixups rather than classic dyld info.
+    chained_pointer_format: Option<u16>,
+    load_commands_space: u64,
 }
 
 impl Metadata {
@@ -201,7 +239,10 @@ impl<'a> Surgeries<'a> {
         }
 
         for text_section in text_sections {
-            self.append_text_section(object_bytes, &text_section, verbose)
+            match object.architecture() {
+                Architecture::Aarch64 => self.append_arm64_text_section(&text_section, verbose),
+                _ => self.append_text_section(object_bytes, &text_section, verbose),
+            }
         }
     }
 
@@ -237,7 +278,7 @@ impl<'a> Surgeries<'a> {
             // Note: This gets really complex fast if we want to support more than basic calls/jumps.
             // A lot of them have to load addresses into registers/memory so we would have to discover that value.
             // Would probably require some static code analysis and would be impossible in some cases.
-            // As an alternative we can leave in the calls to the plt, but change the plt to jmp to the static function.
+            // As an alternative we can leave in the calls to the stubs, but change the stubs to jmp to the static function.
             // That way any indirect call will just have the overhead of an extra jump.
             match inst.try_op_kind(0) {
                 // Relative Offsets.
@@ -318,1287 +359,3104 @@ impl<'a> Surgeries<'a> {
             }
         }
     }
-}
-
-/// Constructs a `Metadata` from a host executable binary, and writes it to disk
-pub(crate) fn preprocess_macho_le(
-    host_exe_path: &Path,
-    metadata_path: &Path,
-    preprocessed_path: &Path,
-    shared_lib: &Path,
-    verbose: bool,
-    time: bool,
-) {
-    let total_start = Instant::now();
-    let exec_parsing_start = total_start;
-    let exec_data = &*open_mmap(host_exe_path);
-    let exec_obj = match object::File::parse(exec_data) {
-        Ok(obj) => obj,
-        Err(err) => {
-            internal_error!("Failed to parse executable file: {}", err);
-        }
-    };
 
-    let mut md = Metadata {
-        roc_symbol_vaddresses: collect_roc_definitions(&exec_obj),
-        ..Default::default()
-    };
+    /// arm64 instructions are all 4 bytes, so rather than disassembling, this looks at each word
+    /// for a `B` or `BL` to a stub. Only those can call the app directly.
+    fn append_arm64_text_section(&mut self, sec: &Section, verbose: bool) {
+        let file_offset = match sec.compressed_file_range() {
+            Ok(CompressedFileRange {
+                format: CompressionFormat::None,
+                offset,
+                ..
+            }) => offset,
+            _ => {
+                internal_error!(
+                    "Surgical linking does not work with compressed text sections: {:+x?}",
+                    sec
+                );
+            }
+        };
 
-    if verbose {
-        println!(
-            "Found roc symbol definitions: {:+x?}",
-            md.roc_symbol_vaddresses
-        );
-    }
+        let data = match sec.data() {
+            Ok(data) => data,
+            Err(err) => {
+                internal_error!("Failed to load text section, {:+x?}: {}", sec, err);
+            }
+        };
 
-    let exec_parsing_duration = exec_parsing_start.elapsed();
+        for (index, word) in data.chunks_exact(4).enumerate() {
+            let inst = u32::from_le_bytes(word.try_into().unwrap());
+            if inst & 0x7c00_0000 != 0x1400_0000 {
+                continue;
+            }
 
-    // PLT stands for Procedure Linkage Table which is, put simply, used to call external
-    // procedures/functions whose address isn't known in the time of linking, and is left
-    // to be resolved by the dynamic linker at run time.
-    let symbol_and_plt_processing_start = Instant::now();
-    let plt_section_name = "__stubs";
-
-    let (plt_address, plt_offset) = match exec_obj.section_by_name(plt_section_name) {
-        Some(section) => {
-            let file_offset = match section.compressed_file_range() {
-                Ok(
-                    range @ CompressedFileRange {
-                        format: CompressionFormat::None,
-                        ..
-                    },
-                ) => range.offset,
-                _ => {
-                    internal_error!("Surgical linking does not work with compressed plt section");
+            let address = sec.address() + 4 * index as u64;
+            // imm26, sign extended and scaled by the instruction size
+            let delta = (((inst & 0x03ff_ffff) << 6) as i32 >> 4) as i64;
+            let target = address.wrapping_add_signed(delta);
+
+            if let Some(func_name) = self.app_func_addresses.get(&target) {
+                let offset = file_offset + 4 * index as u64;
+                if verbose {
+                    println!("Found branch from {address:+x} to {target:+x}({func_name})");
+                    println!(
+                        "\tNeed to surgically replace the instruction at file offset {offset:+x}"
+                    );
                 }
-            };
-            (section.address(), file_offset)
-        }
-        None => {
-            internal_error!("Failed to find PLT section. Probably an malformed executable.");
+                self.surgeries
+                    .get_mut(*func_name)
+                    .unwrap()
+                    .push(SurgeryEntry {
+                        file_offset: offset,
+                        virtual_offset: VirtualOffset::Relative(address),
+                        size: 4,
+                    });
+            }
         }
-    };
-    if verbose {
-        println!("PLT Address: {plt_address:+x}");
-        println!("PLT File Offset: {plt_offset:+x}");
     }
+}
 
-    let app_syms: Vec<_> = exec_obj.symbols().filter(is_roc_undefined).collect();
-
-    let mut app_func_addresses: MutMap<u64, &str> = MutMap::default();
-    let mut macho_load_so_offset = None;
-
-    {
-        use macho::{DyldInfoCommand, DylibCommand, Section64, SegmentCommand64};
-
-        let exec_header = load_struct_inplace::<macho::MachHeader64<LE>>(exec_data, 0);
-        let num_load_cmds = exec_header.ncmds.get(LE);
+fn read_u16_at(data: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes(data[offset..offset + 2].try_into().unwrap())
+}
 
-        let mut offset = mem::size_of_val(exec_header);
+fn read_u32_at(data: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap())
+}
 
-        let mut stubs_symbol_index = None;
-        let mut stubs_symbol_count = None;
+fn read_u64_at(data: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(data[offset..offset + 8].try_into().unwrap())
+}
 
-        'cmds: for _ in 0..num_load_cmds {
-            let info = load_struct_inplace::<macho::LoadCommand<LE>>(exec_data, offset);
-            let cmd = info.cmd.get(LE);
-            let cmdsize = info.cmdsize.get(LE);
+fn write_u32_at(data: &mut [u8], offset: usize, value: u32) {
+    data[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+}
 
-            if cmd == macho::LC_SEGMENT_64 {
-                let info = load_struct_inplace::<SegmentCommand64<LE>>(exec_data, offset);
+fn write_u64_at(data: &mut [u8], offset: usize, value: u64) {
+    data[offset..offset + 8].copy_from_slice(&value.to_le_bytes());
+}
 
-                if &info.segname[0..6] == b"__TEXT" {
-                    let sections = info.nsects.get(LE);
+fn write_i32_at(data: &mut [u8], offset: usize, value: i64) {
+    let value = i32::try_from(value).unwrap_or_else(|_| {
+        internal_error!("{value:+x} does not fit in a 32-bit relative offset");
+    });
+    data[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+}
 
-                    let sections_info = load_structs_inplace::<Section64<LE>>(
-                        exec_data,
-                        offset + mem::size_of_val(info),
-                        sections as usize,
-                    );
+fn c_string_at(data: &[u8], offset: usize) -> String {
+    let bytes = &data[offset..];
+    let end = bytes.iter().position(|b| *b == 0).unwrap_or(bytes.len());
+    String::from_utf8_lossy(&bytes[..end]).into_owned()
+}
 
-                    for section_info in sections_info {
-                        if &section_info.sectname[0..7] == b"__stubs" {
-                            stubs_symbol_index = Some(section_info.reserved1.get(LE));
-                            stubs_symbol_count =
-                                Some(section_info.size.get(LE) / STUB_ADDRESS_OFFSET);
+fn read_uleb128(data: &[u8], offset: &mut usize) -> u64 {
+    let mut result = 0;
+    let mut shift = 0;
+    loop {
+        let byte = data[*offset];
+        *offset += 1;
+        if shift < 64 {
+            result |= ((byte & 0x7f) as u64) << shift;
+        }
+        shift += 7;
+        if byte & 0x80 == 0 {
+            return result;
+        }
+    }
+}
 
-                            break 'cmds;
-                        }
-                    }
-                }
+fn read_sleb128(data: &[u8], offset: &mut usize) -> i64 {
+    let mut result = 0;
+    let mut shift = 0;
+    loop {
+        let byte = data[*offset];
+        *offset += 1;
+        if shift < 64 {
+            result |= ((byte & 0x7f) as i64) << shift;
+        }
+        shift += 7;
+        if byte & 0x80 == 0 {
+            if shift < 64 && byte & 0x40 != 0 {
+                result |= -1 << shift;
             }
-
-            offset += cmdsize as usize;
+            return result;
         }
+    }
+}
 
-        let stubs_symbol_index = stubs_symbol_index.unwrap_or_else(|| {
-            panic!("Could not find stubs symbol index.");
-        });
-        let stubs_symbol_count = stubs_symbol_count.unwrap_or_else(|| {
-            panic!("Could not find stubs symbol count.");
-        });
+fn write_uleb128(out: &mut Vec<u8>, mut value: u64) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            out.push(byte);
+            return;
+        }
+        out.push(byte | 0x80);
+    }
+}
 
-        // Reset offset before looping through load commands again
-        offset = mem::size_of_val(exec_header);
+fn write_sleb128(out: &mut Vec<u8>, mut value: i64) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if (value == 0 && byte & 0x40 == 0) || (value == -1 && byte & 0x40 != 0) {
+            out.push(byte);
+            return;
+        }
+        out.push(byte | 0x80);
+    }
+}
 
-        let shared_lib_filename = shared_lib.file_name();
+/// The type, file offset and size of each load command.
+fn load_commands(data: &[u8]) -> Vec<(u32, usize, usize)> {
+    let header = load_struct_inplace::<MachHeader64<LE>>(data, 0);
+    let mut offset = mem::size_of_val(header);
+
+    (0..header.ncmds.get(LE))
+        .map(|_| {
+            let info = load_struct_inplace::<macho::LoadCommand<LE>>(data, offset);
+            let command = (info.cmd.get(LE), offset, info.cmdsize.get(LE) as usize);
+            offset += command.2;
+            command
+        })
+        .collect()
+}
 
-        for _ in 0..num_load_cmds {
-            let info = load_struct_inplace::<macho::LoadCommand<LE>>(exec_data, offset);
-            let cmd = info.cmd.get(LE);
-            let cmdsize = info.cmdsize.get(LE);
+fn find_load_command(data: &[u8], cmds: &[u32]) -> Option<usize> {
+    load_commands(data)
+        .into_iter()
+        .find(|(cmd, _, _)| cmds.contains(cmd))
+        .map(|(_, offset, _)| offset)
+}
 
-            if cmd == macho::LC_DYLD_INFO_ONLY {
-                let info = load_struct_inplace::<DyldInfoCommand<LE>>(exec_data, offset);
+fn load_commands_end(data: &[u8]) -> usize {
+    let header = load_struct_inplace::<MachHeader64<LE>>(data, 0);
+    mem::size_of_val(header) + header.sizeofcmds.get(LE) as usize
+}
 
-                let lazy_bind_offset = info.lazy_bind_off.get(LE) as usize;
+/// The padding between the load commands and the first section, where more commands can go.
+fn load_commands_space(data: &[u8]) -> usize {
+    let first_section_offset = segments(data)
+        .iter()
+        .flat_map(|segment| segment.sections(data))
+        .map(|section| section.offset.get(LE) as usize)
+        .filter(|offset| *offset != 0)
+        .min()
+        .unwrap_or_else(|| find_segment(data, b"__LINKEDIT").fileoff as usize);
 
-                let lazy_bind_symbols = mach_object::LazyBind::parse(
-                    &exec_data[lazy_bind_offset..],
-                    mem::size_of::<usize>(),
-                );
+    first_section_offset - load_commands_end(data)
+}
 
-                // Find all the lazily-bound roc symbols
-                // (e.g. "_roc__mainForHost_1_exposed")
-                // For Macho, we may need to deal with some GOT stuff here as well.
-                for (i, symbol) in lazy_bind_symbols
-                    .skip(stubs_symbol_index as usize)
-                    .take(stubs_symbol_count as usize)
-                    .enumerate()
-                {
-                    if let Some(sym) = app_syms
-                        .iter()
-                        .find(|app_sym| app_sym.name() == Ok(&symbol.name))
-                    {
-                        let func_address = (i as u64 + 1) * STUB_ADDRESS_OFFSET + plt_address;
-                        let func_offset = (i as u64 + 1) * STUB_ADDRESS_OFFSET + plt_offset;
-                        app_func_addresses.insert(func_address, sym.name().unwrap());
-                        md.plt_addresses
-                            .insert(sym.name().unwrap().to_string(), (func_offset, func_address));
-                    }
-                }
-            } else if cmd == macho::LC_LOAD_DYLIB {
-                let info = load_struct_inplace::<DylibCommand<LE>>(exec_data, offset);
-                let name_offset = info.dylib.name.offset.get(LE) as usize;
-                let str_start_index = offset + name_offset;
-                let str_end_index = offset + cmdsize as usize;
-                let str_bytes = &exec_data[str_start_index..str_end_index];
-                let path = {
-                    if str_bytes[str_bytes.len() - 1] == 0 {
-                        // If it's nul-terminated, it's a C String.
-                        // Use the unchecked version because these are
-                        // padded with 0s at the end, so since we don't
-                        // know the exact length, using the checked version
-                        // of this can fail due to the interior nul bytes.
-                        //
-                        // Also, we have to use from_ptr instead of
-                        // from_bytes_with_nul_unchecked because currently
-                        // std::ffi::CStr is actually not a char* under
-                        // the hood (!) but rather an array, so to strip
-                        // the trailing null bytes we have to use from_ptr.
-                        let c_str = unsafe { CStr::from_ptr(str_bytes.as_ptr() as *const c_char) };
-
-                        Path::new(c_str.to_str().unwrap())
-                    } else {
-                        // It wasn't nul-terminated, so treat all the bytes
-                        // as the string
-
-                        Path::new(std::str::from_utf8(str_bytes).unwrap())
-                    }
-                };
+/// The alignment of segments in executables for the given CPU type.
+fn segment_page_size(cpu_type: u32) -> u64 {
+    match cpu_type {
+        macho::CPU_TYPE_X86_64 => 0x1000,
+        macho::CPU_TYPE_ARM64 => 0x4000,
+        other => internal_error!("Surgical linking does not support Mach-O CPU type {other:#x}"),
+    }
+}
 
-                if path.file_name() == shared_lib_filename {
-                    macho_load_so_offset = Some(offset);
-                }
-            }
+#[derive(Debug, Clone)]
+struct SegmentInfo {
+    index: usize,
+    cmd_offset: usize,
+    name: [u8; 16],
+    vmaddr: u64,
+    fileoff: u64,
+    filesize: u64,
+    nsects: usize,
+}
 
-            offset += cmdsize as usize;
-        }
+impl SegmentInfo {
+    fn is_named(&self, name: &[u8]) -> bool {
+        self.name.split(|b| *b == 0).next() == Some(name)
     }
 
-    for sym in app_syms.iter() {
-        let name = sym.name().unwrap().to_string();
-        md.app_functions.push(name.clone());
-        md.dynamic_symbol_indices.insert(name, sym.index().0 as u64);
+    fn sections<'a>(&self, data: &'a [u8]) -> &'a [Section64<LE>] {
+        load_structs_inplace(
+            data,
+            self.cmd_offset + mem::size_of::<SegmentCommand64<LE>>(),
+            self.nsects,
+        )
     }
-    if verbose {
-        println!();
-        println!("PLT Symbols for App Functions");
-        for symbol in app_syms.iter() {
-            println!("{}: {:+x?}", symbol.index().0, symbol);
-        }
+}
 
-        println!();
-        println!("App Function Address Map: {app_func_addresses:+x?}");
-    }
-    let symbol_and_plt_processing_duration = symbol_and_plt_processing_start.elapsed();
+fn segments(data: &[u8]) -> Vec<SegmentInfo> {
+    load_commands(data)
+        .into_iter()
+        .filter(|(cmd, _, _)| *cmd == macho::LC_SEGMENT_64)
+        .enumerate()
+        .map(|(index, (_, cmd_offset, _))| {
+            let info = load_struct_inplace::<SegmentCommand64<LE>>(data, cmd_offset);
+            SegmentInfo {
+                index,
+                cmd_offset,
+                name: info.segname,
+                vmaddr: info.vmaddr.get(LE),
+                fileoff: info.fileoff.get(LE),
+                filesize: info.filesize.get(LE),
+                nsects: info.nsects.get(LE) as usize,
+            }
+        })
+        .collect()
+}
 
-    // look at the text (i.e. code) sections and see collect work needs to be done
-    let text_disassembly_start = Instant::now();
+fn find_segment(data: &[u8], name: &[u8]) -> SegmentInfo {
+    segments(data)
+        .into_iter()
+        .find(|segment| segment.is_named(name))
+        .unwrap_or_else(|| {
+            internal_error!(
+                "Failed to find the {} segment. Probably an malformed executable.",
+                String::from_utf8_lossy(name)
+            )
+        })
+}
 
-    let mut surgeries = Surgeries::new(&app_syms, app_func_addresses);
-    surgeries.append_text_sections(exec_data, &exec_obj, verbose);
-    md.surgeries = surgeries.surgeries;
+/// Sets the size of `__LINKEDIT`, which must be the last segment, to the rest of the file.
+fn set_linkedit_size(data: &mut [u8], page_size: u64) {
+    let linkedit = find_segment(data, b"__LINKEDIT");
+    let filesize = data.len() as u64 - linkedit.fileoff;
 
-    let text_disassembly_duration = text_disassembly_start.elapsed();
+    let cmd = load_struct_inplace_mut::<SegmentCommand64<LE>>(data, linkedit.cmd_offset);
+    cmd.filesize.set(LE, filesize);
+    cmd.vmsize.set(
+        LE,
+        align_by_constraint(filesize as usize, page_size as usize) as u64,
+    );
+}
+
+/// Appends `bytes` to `__LINKEDIT`, and returns their file offset.
+fn append_to_linkedit(out: &mut Vec<u8>, bytes: &[u8], page_size: u64) -> usize {
+    out.resize(align_by_constraint(out.len(), 8), 0);
+    let offset = out.len();
+    out.extend_from_slice(bytes);
+    set_linkedit_size(out, page_size);
 
-    let scanning_dynamic_deps_start = Instant::now();
+    offset
+}
 
-    // let ElfDynamicDeps {
-    //     got_app_syms,
-    //     got_sections,
-    //     dynamic_lib_count,
-    //     shared_lib_index,
-    // } = scan_elf_dynamic_deps(
-    //     &exec_obj, &mut md, &app_syms, shared_lib, exec_data, verbose,
-    // );
+fn remove_load_command(out: &mut [u8], cmd_offset: usize) {
+    let commands_end = load_commands_end(out);
+    let cmdsize = load_struct_inplace::<macho::LoadCommand<LE>>(out, cmd_offset)
+        .cmdsize
+        .get(LE);
 
-    let scanning_dynamic_deps_duration = scanning_dynamic_deps_start.elapsed();
+    out.copy_within(cmd_offset + cmdsize as usize..commands_end, cmd_offset);
+    out[commands_end - cmdsize as usize..commands_end].fill(0);
 
-    let platform_gen_start = Instant::now();
+    let header = load_struct_inplace_mut::<MachHeader64<LE>>(out, 0);
+    header.ncmds.set(LE, header.ncmds.get(LE) - 1);
+    header
+        .sizeofcmds
+        .set(LE, header.sizeofcmds.get(LE) - cmdsize);
+}
 
-    // TODO little endian
-    let macho_load_so_offset = match macho_load_so_offset {
-        Some(offset) => offset,
-        None => {
-            internal_error!("Host does not link library `{}`!", shared_lib.display());
-        }
+/// Surgery changes the host, so its code signature would no longer be valid.
+fn remove_code_signature(out: &mut Vec<u8>, page_size: u64, verbose: bool) {
+    let Some(cmd_offset) = find_load_command(out, &[macho::LC_CODE_SIGNATURE]) else {
+        return;
     };
 
-    // TODO this is correct on modern Macs (they align to the page size)
-    // but maybe someone can override the alignment somehow? Maybe in the
-    // future this could change? Is there some way to make this more future-proof?
-    md.load_align_constraint = 4096;
+    let info = load_struct_inplace::<LinkeditDataCommand<LE>>(out, cmd_offset);
+    let dataoff = info.dataoff.get(LE) as usize;
+    let datasize = info.datasize.get(LE) as usize;
 
-    let out_mmap = gen_macho_le(
-        exec_data,
-        &mut md,
-        preprocessed_path,
-        macho_load_so_offset,
-        verbose,
-    );
+    remove_load_command(out, cmd_offset);
 
-    let platform_gen_duration = platform_gen_start.elapsed();
+    // The signature comes last in __LINKEDIT
+    if dataoff + datasize == out.len() {
+        out.truncate(dataoff);
+        set_linkedit_size(out, page_size);
+    }
 
     if verbose {
-        println!();
-        println!("{md:+x?}");
+        println!("Removed the code signature of {datasize:+x} bytes at {dataoff:+x}");
     }
+}
 
-    let saving_metadata_start = Instant::now();
-    md.write_to_file(metadata_path);
-    let saving_metadata_duration = saving_metadata_start.elapsed();
-
-    let flushing_data_start = Instant::now();
-    out_mmap
-        .flush()
-        .unwrap_or_else(|e| internal_error!("{}", e));
-    // Also drop files to to ensure data is fully written here.
-    drop(out_mmap);
-    let flushing_data_duration = flushing_data_start.elapsed();
+/// The file offset of the load command for the shared library that stands in for the app, and
+/// the ordinal that binds use to refer to it.
+fn find_app_dylib(exec_data: &[u8], shared_lib: &Path) -> Option<(usize, u64)> {
+    let shared_lib_filename = shared_lib.file_name();
+    let mut ordinal = 0;
+
+    for (cmd, offset, cmdsize) in load_commands(exec_data) {
+        if !matches!(
+            cmd,
+            macho::LC_LOAD_DYLIB
+                | macho::LC_LOAD_WEAK_DYLIB
+                | macho::LC_REEXPORT_DYLIB
+                | macho::LC_LAZY_LOAD_DYLIB
+                | macho::LC_LOAD_UPWARD_DYLIB
+        ) {
+            continue;
+        }
 
-    let total_duration = total_start.elapsed();
+        ordinal += 1;
+
+        let info = load_struct_inplace::<DylibCommand<LE>>(exec_data, offset);
+        let name_offset = info.dylib.name.offset.get(LE) as usize;
+        let str_bytes = &exec_data[offset + name_offset..offset + cmdsize];
+        let path = {
+            if str_bytes[str_bytes.len() - 1] == 0 {
+                // If it's nul-terminated, it's a C String.
+                // Use the unchecked version because these are
+                // padded with 0s at the end, so since we don't
+                // know the exact length, using the checked version
+                // of this can fail due to the interior nul bytes.
+                //
+                // Also, we have to use from_ptr instead of
+                // from_bytes_with_nul_unchecked because currently
+                // std::ffi::CStr is actually not a char* under
+                // the hood (!) but rather an array, so to strip
+                // the trailing null bytes we have to use from_ptr.
+                let c_str = unsafe { CStr::from_ptr(str_bytes.as_ptr() as *const c_char) };
+
+                Path::new(c_str.to_str().unwrap())
+            } else {
+                // It wasn't nul-terminated, so treat all the bytes
+                // as the string
+
+                Path::new(std::str::from_utf8(str_bytes).unwrap())
+            }
+        };
 
-    if verbose || time {
-        println!();
-        println!("Timings");
-        report_timing("Executable Parsing", exec_parsing_duration);
-        report_timing(
-            "Symbol and PLT Processing",
-            symbol_and_plt_processing_duration,
-        );
-        report_timing("Text Disassembly", text_disassembly_duration);
-        report_timing("Scanning Dynamic Deps", scanning_dynamic_deps_duration);
-        report_timing("Generate Modified Platform", platform_gen_duration);
-        report_timing("Saving Metadata", saving_metadata_duration);
-        report_timing("Flushing Data to Disk", flushing_data_duration);
-        report_timing(
-            "Other",
-            total_duration
-                - exec_parsing_duration
-                - symbol_and_plt_processing_duration
-                - text_disassembly_duration
-                - scanning_dynamic_deps_duration
-                - platform_gen_duration
-                - saving_metadata_duration
-                - flushing_data_duration,
-        );
-        report_timing("Total", total_duration);
+        if path.file_name() == shared_lib_filename {
+            return Some((offset, ordinal));
+        }
     }
+
+    None
 }
 
-fn gen_macho_le(
-    exec_data: &[u8],
-    md: &mut Metadata,
-    out_filename: &Path,
-    macho_load_so_offset: usize,
-    _verbose: bool,
-) -> MmapMut {
-    // Just adding some extra context/useful info here.
-    // I was talking to Jakub from the Zig team about macho linking and here are some useful comments:
-    // 1) Macho WILL run fine with multiple text segments (and theoretically data segments).
-    // 2) Theoretically the headers just need to be in a loadable segment,
-    //    but otherwise don't need to relate to the starting text segment (releated to some added byte shifting information below).
-    // 2) Apple tooling dislikes whenever you do something non-standard,
-    //    and there is a chance it won't work right (e.g. codesigning might fail)
-    // 3) Jakub wants to make apple tooling absolute is working on zignature for code signing and zig-deploy for ios apps
-    // https://github.com/kubkon/zignature
-    // https://github.com/kubkon/zig-deploy
-
-    use macho::{Section64, SegmentCommand64};
-
-    let exec_header = load_struct_inplace::<macho::MachHeader64<LE>>(exec_data, 0);
-    let num_load_cmds = exec_header.ncmds.get(LE);
-    let size_of_cmds = exec_header.sizeofcmds.get(LE) as usize;
-
-    // Add a new text segment and data segment
-    let segment_cmd_size = mem::size_of::<SegmentCommand64<LE>>();
-    let section_size = mem::size_of::<Section64<LE>>();
-
-    // We need the full command size, including the dynamic-length string at the end.
-    // To get that, we need to load the command.
-    let info = load_struct_inplace::<macho::LoadCommand<LE>>(exec_data, macho_load_so_offset);
-    let total_cmd_size = info.cmdsize.get(LE) as usize;
-
-    // ======================== Important TODO ==========================
-    // TODO: we accidentally instroduced a big change here.
-    // We use a mix of added_bytes and md.added_byte_count.
-    // Theses should proabably be the same variable.
-    // Also, I just realized that I have been shifting a lot of virtual offsets.
-    // This should not be necessary. If we add a fully 4k of buffer to the file, all of the virtual offsets will still stay aligned.
-    // So a lot of the following work can probably be commented out if we fix that.
-    // Of coruse, it will cost about 4k bytes to a final executable.
-    // This is what the elf version currently does.
-    // Theoretically it is not needed (if we update all virtual addresses in the binary),
-    // but I definitely ran into problems with elf when not adding this extra buffering.
-
-    // Copy header and shift everything to enable more program sections.
-    let added_byte_count = ((2 * segment_cmd_size) + (2 * section_size) - total_cmd_size) as u64;
-    md.added_byte_count = added_byte_count
-        // add some alignment padding
-        + (MIN_SECTION_ALIGNMENT as u64 - added_byte_count % MIN_SECTION_ALIGNMENT as u64);
-
-    md.exec_len = exec_data.len() as u64 + md.added_byte_count;
-
-    let mut out_mmap = open_mmap_mut(out_filename, md.exec_len as usize);
-    let end_of_cmds = size_of_cmds + mem::size_of_val(exec_header);
-
-    // "Delete" the dylib load command - by copying all the bytes before it
-    // and all the bytes after it, while skipping over its bytes.
-    // It has a dynamic-length string at the end that we also need to delete,
-    // in addition to the header.
-    out_mmap[..macho_load_so_offset].copy_from_slice(&exec_data[..macho_load_so_offset]);
-
-    out_mmap[macho_load_so_offset..end_of_cmds - total_cmd_size]
-        .copy_from_slice(&exec_data[macho_load_so_offset + total_cmd_size..end_of_cmds]);
-
-    // Copy the rest of the file, leaving a gap for the surgical linking to add our 2 commands
-    // (which happens after preprocessing), and some zero padding at the end for alignemnt.
-    // (It seems to cause bugs if that padding isn't there!)
-    let rest_of_data = &exec_data[end_of_cmds..];
-    let start_index = end_of_cmds + md.added_byte_count as usize;
-
-    out_mmap[start_index..start_index + rest_of_data.len()].copy_from_slice(rest_of_data);
-
-    let out_header = load_struct_inplace_mut::<macho::MachHeader64<LE>>(&mut out_mmap, 0);
-
-    // TODO: this needs to change to adding the 2 new commands when we are ready.
-    // -1 because we're deleting 1 load command and then NOT adding 2 new ones.
-    {
-        let added_bytes = -(total_cmd_size as isize); // TODO: Change when add the new sections.
-        out_header.ncmds.set(LE, num_load_cmds - 1);
-        out_header
-            .sizeofcmds
-            .set(LE, (size_of_cmds as isize + added_bytes) as u32);
-    }
-
-    // Go through every command and shift it by added_bytes if it's absolute, unless it's inside the command header
-    let mut offset = mem::size_of_val(exec_header);
-
-    // TODO: Add this back in the future when needed.
-    // let cpu_type = match target.architecture {
-    //     target_lexicon::Architecture::X86_64 => macho::CPU_TYPE_X86_64,
-    //     target_lexicon::Architecture::Aarch64(_) => macho::CPU_TYPE_ARM64,
-    //     _ => {
-    //         // We should have verified this via supported() before calling this function
-    //         unreachable!()
-    //     }
-    // };
-
-    // minus one because we "deleted" a load command
-    for _ in 0..(num_load_cmds - 1) {
-        let info = load_struct_inplace::<macho::LoadCommand<LE>>(&out_mmap, offset);
-        let cmd_size = info.cmdsize.get(LE) as usize;
-
-        match info.cmd.get(LE) {
-            macho::LC_SEGMENT_64 => {
-                let cmd =
-                    load_struct_inplace_mut::<macho::SegmentCommand64<LE>>(&mut out_mmap, offset);
-
-                // Ignore page zero, it never moves.
-                if cmd.segname == "__PAGEZERO\0\0\0\0\0\0".as_bytes() || cmd.vmaddr.get(LE) == 0 {
-                    offset += cmd_size;
-                    continue;
-                }
+/// The name, file offset and address of each stub, and the size of a stub.
+fn collect_stubs(exec_data: &[u8], exec_obj: &object::File) -> (Vec<(String, u64, u64)>, u64) {
+    let dysymtab_offset =
+        find_load_command(exec_data, &[macho::LC_DYSYMTAB]).unwrap_or_else(|| {
+            internal_error!("Failed to find LC_DYSYMTAB. Probably an malformed executable.");
+        });
+    let dysymtab = load_struct_inplace::<DysymtabCommand<LE>>(exec_data, dysymtab_offset);
+    let indirect_symbols = load_structs_inplace::<U32<LE>>(
+        exec_data,
+        dysymtab.indirectsymoff.get(LE) as usize,
+        dysymtab.nindirectsyms.get(LE) as usize,
+    );
 
-                let old_file_offest = cmd.fileoff.get(LE);
-                // The segment with file offset zero also includes the header.
-                // As such, its file offset does not change.
-                // Instead, its file size should be increased.
-                if old_file_offest > 0 {
-                    cmd.fileoff.set(LE, old_file_offest + md.added_byte_count);
-                    cmd.vmaddr.set(LE, cmd.vmaddr.get(LE) + md.added_byte_count);
-                } else {
-                    cmd.filesize
-                        .set(LE, cmd.filesize.get(LE) + md.added_byte_count);
-                    cmd.vmsize.set(LE, cmd.vmsize.get(LE) + md.added_byte_count);
-                }
+    let mut stubs = Vec::new();
+    let mut stub_size = 0;
 
-                // let num_sections = cmd.nsects.get(LE);
-                // let sections = load_structs_inplace_mut::<macho::Section64<LE >>(
-                //     &mut out_mmap,
-                //     offset + mem::size_of::<macho::SegmentCommand64<LE >>(),
-                //     num_sections as usize,
-                // );
-                // struct Relocation {
-                //     offset: u32,
-                //     num_relocations: u32,
-                // }
-
-                // let mut relocation_offsets = Vec::with_capacity(sections.len());
-
-                // for section in sections {
-                //     section.addr.set(
-                //         LE ,
-                //         section.addr.get(LE) + md.added_byte_count as u64,
-                //     );
-
-                //     // If offset is zero, don't update it.
-                //     // Zero is used for things like BSS that don't exist in the file.
-                //     let old_offset = section.offset.get(LE);
-                //     if old_offset > 0 {
-                //         section
-                //             .offset
-                //             .set(LE , old_offset + md.added_byte_count as u32);
-                //     }
-
-                //     // dbg!(&section.reloff.get(LE));
-                //     // dbg!(section.reloff.get(LE) as i32);
-                //     // dbg!(&section);
-                //     // dbg!(&md.added_byte_count);
-                //     // dbg!(String::from_utf8_lossy(&section.sectname));
-                //     if section.nreloc.get(LE) > 0 {
-                //         section.reloff.set(
-                //             LE ,
-                //             section.reloff.get(LE) + md.added_byte_count as u32,
-                //         );
-                //     }
-
-                //     relocation_offsets.push(Relocation {
-                //         offset: section.reloff.get(LE),
-                //         num_relocations: section.nreloc.get(LE),
-                //     });
-                // }
-
-                // TODO FIXME this is necessary for ARM, but seems to be broken. Skipping for now since we're just targeting x86
-                // for Relocation {
-                //     offset,
-                //     num_relocations,
-                // } in relocation_offsets
-                // {
-                //     let relos = load_structs_inplace_mut::<macho::Relocation<LE >>(
-                //         &mut out_mmap,
-                //         offset as usize,
-                //         num_relocations as usize,
-                //     );
-
-                //     // TODO this has never been tested, because scattered relocations only come up on ARM!
-                //     for relo in relos.iter_mut() {
-                //         if relo.r_scattered(LE , cpu_type) {
-                //             let mut scattered_info = relo.scattered_info(LE);
-
-                //             if !scattered_info.r_pcrel {
-                //                 scattered_info.r_value += md.added_byte_count as u32;
-
-                //                 let new_info = scattered_info.relocation(LE );
-
-                //                 relo.r_word0 = new_info.r_word0;
-                //                 relo.r_word1 = new_info.r_word1;
-                //             }
-                //         }
-                //     }
-                // }
-
-                // TODO this seems to be wrong and unnecessary, and should probably be deleted.
-                // offset += num_sections as usize * mem::size_of::<macho::Section64<LE >>();
+    for segment in segments(exec_data) {
+        for section in segment.sections(exec_data) {
+            if section.flags.get(LE) & macho::SECTION_TYPE != macho::S_SYMBOL_STUBS {
+                continue;
             }
-            macho::LC_SYMTAB => {
-                let cmd =
-                    load_struct_inplace_mut::<macho::SymtabCommand<LE>>(&mut out_mmap, offset);
 
-                let sym_offset = cmd.symoff.get(LE);
-                let num_syms = cmd.nsyms.get(LE);
+            stub_size = section.reserved2.get(LE) as u64;
+            let first_symbol = section.reserved1.get(LE) as usize;
+            let count = section.size.get(LE) / stub_size;
 
-                if num_syms > 0 {
-                    cmd.symoff.set(LE, sym_offset + md.added_byte_count as u32);
+            for i in 0..count {
+                let symbol_index = indirect_symbols[first_symbol + i as usize].get(LE);
+                if symbol_index & (macho::INDIRECT_SYMBOL_LOCAL | macho::INDIRECT_SYMBOL_ABS) != 0 {
+                    continue;
                 }
 
-                if cmd.strsize.get(LE) > 0 {
-                    cmd.stroff
-                        .set(LE, cmd.stroff.get(LE) + md.added_byte_count as u32);
+                let name = exec_obj
+                    .symbol_by_index(SymbolIndex(symbol_index as usize))
+                    .and_then(|symbol| symbol.name().map(str::to_string));
+                if let Ok(name) = name {
+                    stubs.push((
+                        name,
+                        section.offset.get(LE) as u64 + i * stub_size,
+                        section.addr.get(LE) + i * stub_size,
+                    ));
                 }
+            }
+        }
+    }
 
-                let table = load_structs_inplace_mut::<macho::Nlist64<LE>>(
-                    &mut out_mmap,
-                    sym_offset as usize + md.added_byte_count as usize,
-                    num_syms as usize,
-                );
+    (stubs, stub_size)
+}
 
-                for entry in table {
-                    let entry_type = entry.n_type & macho::N_TYPE;
-                    if entry_type == macho::N_ABS || entry_type == macho::N_SECT {
-                        entry
-                            .n_value
-                            .set(LE, entry.n_value.get(LE) + md.added_byte_count);
-                    }
+/// One pointer that the classic dyld info binds to a symbol.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct BindRecord {
+    ordinal: i64,
+    symbol: String,
+    flags: u8,
+    bind_type: u8,
+    addend: i64,
+    segment: u8,
+    offset: u64,
+}
+
+fn parse_bind_opcodes(data: &[u8]) -> Vec<BindRecord> {
+    const POINTER_SIZE: u64 = 8;
+
+    let mut records = Vec::new();
+    let mut state = BindRecord {
+        ordinal: 0,
+        symbol: String::new(),
+        flags: 0,
+        bind_type: 0,
+        addend: 0,
+        segment: 0,
+        offset: 0,
+    };
+
+    let mut i = 0;
+    while i < data.len() {
+        let byte = data[i];
+        i += 1;
+        let immediate = byte & macho::BIND_IMMEDIATE_MASK;
+
+        match byte & macho::BIND_OPCODE_MASK {
+            macho::BIND_OPCODE_DONE => break,
+            macho::BIND_OPCODE_SET_DYLIB_ORDINAL_IMM => state.ordinal = immediate as i64,
+            macho::BIND_OPCODE_SET_DYLIB_ORDINAL_ULEB => {
+                state.ordinal = read_uleb128(data, &mut i) as i64
+            }
+            macho::BIND_OPCODE_SET_DYLIB_SPECIAL_IMM => {
+                state.ordinal = if immediate == 0 {
+                    0
+                } else {
+                    (macho::BIND_OPCODE_MASK | immediate) as i8 as i64
                 }
             }
-            macho::LC_DYSYMTAB => {
-                let cmd =
-                    load_struct_inplace_mut::<macho::DysymtabCommand<LE>>(&mut out_mmap, offset);
-
-                if cmd.ntoc.get(LE) > 0 {
-                    cmd.tocoff
-                        .set(LE, cmd.tocoff.get(LE) + md.added_byte_count as u32);
+            macho::BIND_OPCODE_SET_SYMBOL_TRAILING_FLAGS_IMM => {
+                state.symbol = c_string_at(data, i);
+                state.flags = immediate;
+                i += state.symbol.len() + 1;
+            }
+            macho::BIND_OPCODE_SET_TYPE_IMM => state.bind_type = immediate,
+            macho::BIND_OPCODE_SET_ADDEND_SLEB => state.addend = read_sleb128(data, &mut i),
+            macho::BIND_OPCODE_SET_SEGMENT_AND_OFFSET_ULEB => {
+                state.segment = immediate;
+                state.offset = read_uleb128(data, &mut i);
+            }
+            macho::BIND_OPCODE_ADD_ADDR_ULEB => {
+                state.offset = state.offset.wrapping_add(read_uleb128(data, &mut i))
+            }
+            macho::BIND_OPCODE_DO_BIND => {
+                records.push(state.clone());
+                state.offset = state.offset.wrapping_add(POINTER_SIZE);
+            }
+            macho::BIND_OPCODE_DO_BIND_ADD_ADDR_ULEB => {
+                records.push(state.clone());
+                let skip = read_uleb128(data, &mut i);
+                state.offset = state.offset.wrapping_add(POINTER_SIZE).wrapping_add(skip);
+            }
+            macho::BIND_OPCODE_DO_BIND_ADD_ADDR_IMM_SCALED => {
+                records.push(state.clone());
+                state.offset += POINTER_SIZE + immediate as u64 * POINTER_SIZE;
+            }
+            macho::BIND_OPCODE_DO_BIND_ULEB_TIMES_SKIPPING_ULEB => {
+                let count = read_uleb128(data, &mut i);
+                let skip = read_uleb128(data, &mut i);
+                for _ in 0..count {
+                    records.push(state.clone());
+                    state.offset = state.offset.wrapping_add(POINTER_SIZE + skip);
                 }
+            }
+            _ => {
+                internal_error!("Bind opcode not yet supported: {:#x}", byte);
+            }
+        }
+    }
 
-                if cmd.nmodtab.get(LE) > 0 {
-                    cmd.modtaboff
-                        .set(LE, cmd.modtaboff.get(LE) + md.added_byte_count as u32);
-                }
+    records
+}
 
-                if cmd.nextrefsyms.get(LE) > 0 {
-                    cmd.extrefsymoff
-                        .set(LE, cmd.extrefsymoff.get(LE) + md.added_byte_count as u32);
-                }
+fn bind_opcodes(records: &[BindRecord]) -> Vec<u8> {
+    let mut out = Vec::new();
+    let mut previous: Option<&BindRecord> = None;
 
-                if cmd.nindirectsyms.get(LE) > 0 {
-                    cmd.indirectsymoff
-                        .set(LE, cmd.indirectsymoff.get(LE) + md.added_byte_count as u32);
+    for record in records {
+        if previous.map(|p| p.ordinal) != Some(record.ordinal) {
+            match record.ordinal {
+                ordinal @ 1..=15 => {
+                    out.push(macho::BIND_OPCODE_SET_DYLIB_ORDINAL_IMM | ordinal as u8);
                 }
-
-                if cmd.nextrel.get(LE) > 0 {
-                    cmd.extreloff
-                        .set(LE, cmd.extreloff.get(LE) + md.added_byte_count as u32);
+                ordinal @ 16.. => {
+                    out.push(macho::BIND_OPCODE_SET_DYLIB_ORDINAL_ULEB);
+                    write_uleb128(&mut out, ordinal as u64);
                 }
-
-                if cmd.nlocrel.get(LE) > 0 {
-                    cmd.locreloff
-                        .set(LE, cmd.locreloff.get(LE) + md.added_byte_count as u32);
+                ordinal => {
+                    out.push(
+                        macho::BIND_OPCODE_SET_DYLIB_SPECIAL_IMM
+                            | (ordinal as u8 & macho::BIND_IMMEDIATE_MASK),
+                    );
                 }
-
-                // TODO maybe we need to update something else too - relocations maybe?
-                // I think this also has symbols that need to get moved around.
-                // Look at otool -I at least for the indirect symbols.
             }
-            macho::LC_TWOLEVEL_HINTS => {
-                let cmd = load_struct_inplace_mut::<macho::TwolevelHintsCommand<LE>>(
-                    &mut out_mmap,
-                    offset,
-                );
+        }
+        if previous.map(|p| (&p.symbol, p.flags)) != Some((&record.symbol, record.flags)) {
+            out.push(macho::BIND_OPCODE_SET_SYMBOL_TRAILING_FLAGS_IMM | record.flags);
+            out.extend_from_slice(record.symbol.as_bytes());
+            out.push(0);
+        }
+        if previous.map(|p| p.bind_type) != Some(record.bind_type) {
+            out.push(macho::BIND_OPCODE_SET_TYPE_IMM | record.bind_type);
+        }
+        if previous.map_or(0, |p| p.addend) != record.addend {
+            out.push(macho::BIND_OPCODE_SET_ADDEND_SLEB);
+            write_sleb128(&mut out, record.addend);
+        }
+        out.push(macho::BIND_OPCODE_SET_SEGMENT_AND_OFFSET_ULEB | record.segment);
+        write_uleb128(&mut out, record.offset);
+        out.push(macho::BIND_OPCODE_DO_BIND);
 
-                if cmd.nhints.get(LE) > 0 {
-                    cmd.offset
-                        .set(LE, cmd.offset.get(LE) + md.added_byte_count as u32);
-                }
-            }
-            macho::LC_FUNCTION_STARTS => {
-                let cmd = load_struct_inplace_mut::<macho::LinkeditDataCommand<LE>>(
-                    &mut out_mmap,
-                    offset,
-                );
+        previous = Some(record);
+    }
 
-                if cmd.datasize.get(LE) > 0 {
-                    cmd.dataoff
-                        .set(LE, cmd.dataoff.get(LE) + md.added_byte_count as u32);
-                    // TODO: This lists the start of every function. Which, of course, have moved.
-                    // That being said, to my understanding this section is optional and may just be debug information.
-                    // As such, updating it should not be required.
-                    // It will be more work to update due to being in "DWARF-style ULEB128" values.
-                }
-            }
-            macho::LC_DATA_IN_CODE => {
-                let (offset, size) = {
-                    let cmd = load_struct_inplace_mut::<macho::LinkeditDataCommand<LE>>(
-                        &mut out_mmap,
-                        offset,
-                    );
+    out.push(macho::BIND_OPCODE_DONE);
+    out
+}
 
-                    if cmd.datasize.get(LE) > 0 {
-                        cmd.dataoff
-                            .set(LE, cmd.dataoff.get(LE) + md.added_byte_count as u32);
-                    }
-                    (cmd.dataoff.get(LE), cmd.datasize.get(LE))
-                };
+/// Rebase opcodes for pointers at the given segment indices and offsets, followed by `existing`.
+fn prepend_rebases(existing: &[u8], locations: &[(u8, u64)]) -> Vec<u8> {
+    let mut out = vec![macho::REBASE_OPCODE_SET_TYPE_IMM | macho::REBASE_TYPE_POINTER];
 
-                // Update every data in code entry.
-                if size > 0 {
-                    let entry_size = mem::size_of::<macho::DataInCodeEntry<LE>>();
-                    let entries = load_structs_inplace_mut::<macho::DataInCodeEntry<LE>>(
-                        &mut out_mmap,
-                        offset as usize,
-                        size as usize / entry_size,
-                    );
-                    for entry in entries.iter_mut() {
-                        entry
-                            .offset
-                            .set(LE, entry.offset.get(LE) + md.added_byte_count as u32)
-                    }
-                }
-            }
-            macho::LC_CODE_SIGNATURE
-            | macho::LC_SEGMENT_SPLIT_INFO
-            | macho::LC_DYLIB_CODE_SIGN_DRS
-            | macho::LC_LINKER_OPTIMIZATION_HINT
-            | macho::LC_DYLD_EXPORTS_TRIE
-            | macho::LC_DYLD_CHAINED_FIXUPS => {
-                let cmd = load_struct_inplace_mut::<macho::LinkeditDataCommand<LE>>(
-                    &mut out_mmap,
-                    offset,
-                );
+    for (segment, offset) in locations {
+        out.push(macho::REBASE_OPCODE_SET_SEGMENT_AND_OFFSET_ULEB | segment);
+        write_uleb128(&mut out, *offset);
+        out.push(macho::REBASE_OPCODE_DO_REBASE_IMM_TIMES | 1);
+    }
 
-                if cmd.datasize.get(LE) > 0 {
-                    cmd.dataoff
-                        .set(LE, cmd.dataoff.get(LE) + md.added_byte_count as u32);
-                }
-            }
-            macho::LC_ENCRYPTION_INFO_64 => {
-                let cmd = load_struct_inplace_mut::<macho::EncryptionInfoCommand64<LE>>(
-                    &mut out_mmap,
-                    offset,
-                );
+    if existing.is_empty() {
+        out.push(macho::REBASE_OPCODE_DONE);
+    } else {
+        out.extend_from_slice(existing);
+    }
 
-                if cmd.cryptsize.get(LE) > 0 {
-                    cmd.cryptoff
-                        .set(LE, cmd.cryptoff.get(LE) + md.added_byte_count as u32);
-                }
-            }
-            macho::LC_DYLD_INFO | macho::LC_DYLD_INFO_ONLY => {
-                let cmd =
-                    load_struct_inplace_mut::<macho::DyldInfoCommand<LE>>(&mut out_mmap, offset);
+    out
+}
 
-                if cmd.rebase_size.get(LE) > 0 {
-                    cmd.rebase_off
-                        .set(LE, cmd.rebase_off.get(LE) + md.added_byte_count as u32);
-                }
+/// A symbol that chained fixups can bind pointers to.
+struct ChainedImport {
+    lib_ordinal: i64,
+    name: String,
+    addend: i64,
+    /// The file offset of the byte holding its weak-import flag, and the flag's bit.
+    weak_flag: (usize, u8),
+}
 
-                if cmd.bind_size.get(LE) > 0 {
-                    cmd.bind_off
-                        .set(LE, cmd.bind_off.get(LE) + md.added_byte_count as u32);
-                }
+/// A pointer that chained fixups bind to an import.
+struct ChainedBind {
+    file_offset: u64,
+    import: usize,
+    addend: i64,
+}
 
-                if cmd.weak_bind_size.get(LE) > 0 {
-                    cmd.weak_bind_off
-                        .set(LE, cmd.weak_bind_off.get(LE) + md.added_byte_count as u32);
-                }
+struct ChainedFixups {
+    pointer_format: Option<u16>,
+    imports: Vec<ChainedImport>,
+    binds: Vec<ChainedBind>,
+}
 
-                if cmd.lazy_bind_size.get(LE) > 0 {
-                    cmd.lazy_bind_off
-                        .set(LE, cmd.lazy_bind_off.get(LE) + md.added_byte_count as u32);
-                }
+impl ChainedFixups {
+    fn parse(data: &[u8], fixups_offset: usize, segments: &[SegmentInfo]) -> Self {
+        let header = |field: usize| read_u32_at(data, fixups_offset + 4 * field);
+        let starts = fixups_offset + header(1) as usize;
+        let imports_offset = fixups_offset + header(2) as usize;
+        let symbols_offset = fixups_offset + header(3) as usize;
+        let imports_count = header(4) as usize;
+        let imports_format = header(5);
+
+        if header(6) != 0 {
+            internal_error!("Compressed chained fixup symbols are not yet supported");
+        }
 
-                // TODO: Parse and update the related tables here.
-                // It is possible we may just need to delete things that point to stuff that will be in the roc app.
-                // We also may just be able to ignore it (lazy bindings should never run).
-                // This definitely has a list of virtual address that need to be updated.
-                // Some of them definitely will point to the roc app and should probably be removed.
-                // Also `xcrun dyldinfo` is useful for debugging this.
-            }
-            macho::LC_SYMSEG => {
-                let cmd =
-                    load_struct_inplace_mut::<macho::SymsegCommand<LE>>(&mut out_mmap, offset);
+        let imports = (0..imports_count)
+            .map(|index| {
+                let (lib_ordinal, name_offset, addend, weak_flag) = match imports_format {
+                    DYLD_CHAINED_IMPORT => {
+                        let entry = imports_offset + 4 * index;
+                        let raw = read_u32_at(data, entry);
+                        (raw as u8 as i8 as i64, raw >> 9, 0, (entry + 1, 1))
+                    }
+                    DYLD_CHAINED_IMPORT_ADDEND => {
+                        let entry = imports_offset + 8 * index;
+                        let raw = read_u32_at(data, entry);
+                        let addend = read_u32_at(data, entry + 4) as i32 as i64;
+                        (raw as u8 as i8 as i64, raw >> 9, addend, (entry + 1, 1))
+                    }
+                    DYLD_CHAINED_IMPORT_ADDEND64 => {
+                        let entry = imports_offset + 16 * index;
+                        let raw = read_u64_at(data, entry);
+                        let addend = read_u64_at(data, entry + 8) as i64;
+                        let name_offset = (raw >> 32) as u32;
+                        (
+                            raw as u16 as i16 as i64,
+                            name_offset,
+                            addend,
+                            (entry + 2, 1),
+                        )
+                    }
+                    other => {
+                        internal_error!("Chained fixups import format not yet supported: {other}");
+                    }
+                };
 
-                if cmd.size.get(LE) > 0 {
-                    cmd.offset
-                        .set(LE, cmd.offset.get(LE) + md.added_byte_count as u32);
+                ChainedImport {
+                    lib_ordinal,
+                    name: c_string_at(data, symbols_offset + name_offset as usize),
+                    addend,
+                    weak_flag,
                 }
+            })
+            .collect();
+
+        let mut pointer_format = None;
+        let mut binds = Vec::new();
+        let segment_count = read_u32_at(data, starts) as usize;
+
+        for (index, segment) in segments.iter().enumerate().take(segment_count) {
+            let info_offset = read_u32_at(data, starts + 4 + 4 * index) as usize;
+            if info_offset == 0 {
+                continue;
             }
-            macho::LC_MAIN => {
-                let cmd =
-                    load_struct_inplace_mut::<macho::EntryPointCommand<LE>>(&mut out_mmap, offset);
 
-                cmd.entryoff
-                    .set(LE, cmd.entryoff.get(LE) + md.added_byte_count);
+            let segment_starts = starts + info_offset;
+            let page_size = read_u16_at(data, segment_starts + 4) as u64;
+            let format = read_u16_at(data, segment_starts + 6);
+            let page_count = read_u16_at(data, segment_starts + 20) as usize;
+
+            if !matches!(format, DYLD_CHAINED_PTR_64 | DYLD_CHAINED_PTR_64_OFFSET) {
+                internal_error!("Chained fixups pointer format not yet supported: {format}");
             }
-            macho::LC_NOTE => {
-                let cmd = load_struct_inplace_mut::<macho::NoteCommand<LE>>(&mut out_mmap, offset);
+            pointer_format = Some(format);
 
-                if cmd.size.get(LE) > 0 {
-                    cmd.offset.set(LE, cmd.offset.get(LE) + md.added_byte_count);
+            for page in 0..page_count {
+                let start = read_u16_at(data, segment_starts + 22 + 2 * page);
+                if start == DYLD_CHAINED_PTR_START_NONE {
+                    continue;
+                }
+
+                let mut file_offset = segment.fileoff + page as u64 * page_size + start as u64;
+                loop {
+                    let raw = read_u64_at(data, file_offset as usize);
+                    if raw & CHAINED_BIND_BIT != 0 {
+                        binds.push(ChainedBind {
+                            file_offset,
+                            import: (raw & 0xff_ffff) as usize,
+                            addend: ((raw >> 24) & 0xff) as i64,
+                        });
+                    }
+
+                    let next = (raw & CHAINED_NEXT_MASK) >> CHAINED_NEXT_SHIFT;
+                    if next == 0 {
+                        break;
+                    }
+                    file_offset += 4 * next;
                 }
-            }
-            macho::LC_ID_DYLIB
-            | macho::LC_LOAD_WEAK_DYLIB
-            | macho::LC_LOAD_DYLIB
-            | macho::LC_REEXPORT_DYLIB
-            | macho::LC_SOURCE_VERSION
-            | macho::LC_IDENT
-            | macho::LC_LINKER_OPTION
-            | macho::LC_BUILD_VERSION
-            | macho::LC_VERSION_MIN_MACOSX
-            | macho::LC_VERSION_MIN_IPHONEOS
-            | macho::LC_VERSION_MIN_WATCHOS
-            | macho::LC_VERSION_MIN_TVOS
-            | macho::LC_UUID
-            | macho::LC_RPATH
-            | macho::LC_ID_DYLINKER
-            | macho::LC_LOAD_DYLINKER
-            | macho::LC_DYLD_ENVIRONMENT
-            | macho::LC_ROUTINES_64
-            | macho::LC_THREAD
-            | macho::LC_UNIXTHREAD
-            | macho::LC_PREBOUND_DYLIB
-            | macho::LC_SUB_FRAMEWORK
-            | macho::LC_SUB_CLIENT
-            | macho::LC_SUB_UMBRELLA
-            | macho::LC_SUB_LIBRARY => {
-                // These don't involve file offsets, so no change is needed for these.
-                // Just continue the loop!
-            }
-            macho::LC_PREBIND_CKSUM => {
-                // We don't *think* we need to change this, but
-                // maybe what we're doing will break checksums?
-            }
-            macho::LC_SEGMENT | macho::LC_ROUTINES | macho::LC_ENCRYPTION_INFO => {
-                // These are 32-bit and unsuppoted
-                unreachable!();
-            }
-            macho::LC_FVMFILE | macho::LC_IDFVMLIB | macho::LC_LOADFVMLIB => {
-                // These are obsolete and unsupported
-                unreachable!()
-            }
-            cmd => {
-                eprintln!(
-                    "- - - Unrecognized Mach-O command during linker preprocessing: 0x{cmd:x?}"
-                );
-                // panic!(
-                //     "Unrecognized Mach-O command during linker preprocessing: 0x{:x?}",
-                //     cmd
-                // );
             }
         }
 
-        offset += dbg!(cmd_size);
+        Self {
+            pointer_format,
+            imports,
+            binds,
+        }
     }
+}
 
-    // cmd_loc should be where the last offset ended
-    md.macho_cmd_loc = offset as u64;
+/// A chained fixup that makes dyld slide a pointer to `target`.
+fn chained_rebase(pointer_format: u16, image_base: u64, target: u64) -> u64 {
+    let target = match pointer_format {
+        DYLD_CHAINED_PTR_64 => target,
+        DYLD_CHAINED_PTR_64_OFFSET => target - image_base,
+        other => internal_error!("Chained fixups pointer format not yet supported: {other}"),
+    };
 
-    out_mmap
+    // The top byte of the pointer is stored separately from the rest
+    (target & 0xf_ffff_ffff) | ((target >> 56) << 36)
 }
 
-// fn scan_macho_dynamic_deps(
-//     _exec_obj: &object::File,
-//     _md: &mut Metadata,
-//     _app_syms: &[Symbol],
-//     _shared_lib: &str,
-//     _exec_data: &[u8],
-//     _verbose: bool,
-// ) {
-//     // TODO
-// }
+/// Rebuilds the host's chained fixups for the final executable. The app's segments come just
+/// before `__LINKEDIT`, and `app_starts` describes the chain of rebases in its data segment.
+fn rebuild_chained_fixups(
+    exec_data: &[u8],
+    cmd_offset: usize,
+    linkedit_index: usize,
+    app_starts: Option<&[u8]>,
+) -> Vec<u8> {
+    let info = load_struct_inplace::<LinkeditDataCommand<LE>>(exec_data, cmd_offset);
+    let dataoff = info.dataoff.get(LE) as usize;
+    let old = &exec_data[dataoff..dataoff + info.datasize.get(LE) as usize];
+
+    let starts_offset = read_u32_at(old, 4) as usize;
+    let imports_offset = read_u32_at(old, 8) as usize;
+    let symbols_offset = read_u32_at(old, 12) as usize;
+    let old_segment_count = read_u32_at(old, starts_offset) as usize;
+
+    let mut segment_starts: Vec<Option<&[u8]>> =
+        vec![None; old_segment_count.max(linkedit_index + 1) + 2];
+    for index in 0..old_segment_count {
+        let info_offset = read_u32_at(old, starts_offset + 4 + 4 * index) as usize;
+        if info_offset == 0 {
+            continue;
+        }
 
-pub(crate) fn surgery_macho(
-    roc_app_bytes: &[u8],
+        let start = starts_offset + info_offset;
+        let size = read_u32_at(old, start) as usize;
+        let new_index = if index < linkedit_index {
+            index
+        } else {
+            index + 2
+        };
+        segment_starts[new_index] = Some(&old[start..start + size]);
+    }
+    segment_starts[linkedit_index + 1] = app_starts;
+
+    let mut blob = vec![0; 32];
+    blob[..28].copy_from_slice(&old[..28]);
+
+    let starts = blob.len();
+    blob.extend_from_slice(&(segment_starts.len() as u32).to_le_bytes());
+    blob.resize(blob.len() + 4 * segment_starts.len(), 0);
+    for (index, segment) in segment_starts.iter().enumerate() {
+        if let Some(segment) = segment {
+            blob.resize(align_by_constraint(blob.len(), 8), 0);
+            let info_offset = (blob.len() - starts) as u32;
+            write_u32_at(&mut blob, starts + 4 + 4 * index, info_offset);
+            blob.extend_from_slice(segment);
+        }
+    }
+
+    blob.resize(align_by_constraint(blob.len(), 8), 0);
+    let imports = blob.len();
+    blob.extend_from_slice(&old[imports_offset..symbols_offset]);
+    let symbols = blob.len();
+    blob.extend_from_slice(&old[symbols_offset..]);
+
+    write_u32_at(&mut blob, 4, starts as u32);
+    write_u32_at(&mut blob, 8, imports as u32);
+    write_u32_at(&mut blob, 12, symbols as u32);
+
+    blob
+}
+
+/// Constructs a `Metadata` from a host executable binary, and writes it to disk
+pub(crate) fn preprocess_macho_le(
+    host_exe_path: &Path,
     metadata_path: &Path,
-    executable_path: &Path,
+    preprocessed_path: &Path,
+    shared_lib: &Path,
     verbose: bool,
     time: bool,
 ) {
-    let app_obj = match object::File::parse(roc_app_bytes) {
+    let total_start = Instant::now();
+    let exec_parsing_start = total_start;
+    let exec_data = &*open_mmap(host_exe_path);
+    let exec_obj = match object::File::parse(exec_data) {
         Ok(obj) => obj,
         Err(err) => {
-            internal_error!("Failed to parse application file: {}", err);
+            internal_error!("Failed to parse executable file: {}", err);
         }
     };
 
-    let total_start = Instant::now();
+    let exec_header = load_struct_inplace::<MachHeader64<LE>>(exec_data, 0);
+    if exec_header.filetype.get(LE) != macho::MH_EXECUTE {
+        internal_error!(
+            "The host must be an executable, but its Mach-O file type is {}",
+            exec_header.filetype.get(LE)
+        );
+    }
 
-    let loading_metadata_start = total_start;
-    let md = Metadata::read_from_file(metadata_path);
-    let loading_metadata_duration = loading_metadata_start.elapsed();
+    let cpu_type = exec_header.cputype.get(LE);
+    let mut md = Metadata {
+        cpu_type,
+        page_size: segment_page_size(cpu_type),
+        image_base: find_segment(exec_data, b"__TEXT").vmaddr,
+        roc_symbol_vaddresses: collect_roc_definitions(&exec_obj),
+        ..Default::default()
+    };
 
-    let load_and_mmap_start = Instant::now();
-    let max_out_len = md.exec_len + roc_app_bytes.len() as u64 + md.load_align_constraint;
-    let mut exec_mmap = open_mmap_mut(executable_path, max_out_len as usize);
-    let load_and_mmap_duration = load_and_mmap_start.elapsed();
+    if verbose {
+        println!(
+            "Found roc symbol definitions: {:+x?}",
+            md.roc_symbol_vaddresses
+        );
+    }
 
-    let out_gen_start = Instant::now();
-    let mut offset = 0;
+    let exec_parsing_duration = exec_parsing_start.elapsed();
 
-    surgery_macho_help(
-        metadata_path,
-        executable_path,
-        verbose,
-        time,
-        &md,
-        &mut exec_mmap,
-        &mut offset,
-        app_obj,
-    );
+    // Stubs are used to call functions in other libraries: each one jumps to an address that
+    // dyld fills in at load time. Calls into the app go through the stubs for its symbols.
+    let symbol_and_plt_processing_start = Instant::now();
 
-    let out_gen_duration = out_gen_start.elapsed();
-    let flushing_data_start = Instant::now();
+    let app_syms: Vec<_> = exec_obj.symbols().filter(is_roc_undefined).collect();
+    for sym in app_syms.iter() {
+        md.app_functions.push(sym.name().unwrap().to_string());
+    }
 
-    // TODO investigate using the async version of flush - might be faster due to not having to block on that
-    exec_mmap
-        .flush()
-        .unwrap_or_else(|e| internal_error!("{}", e));
-    // Also drop files to to ensure data is fully written here.
-    drop(exec_mmap);
+    let (all_stubs, stub_size) = collect_stubs(exec_data, &exec_obj);
+    let stubs: MutMap<String, (u64, u64)> = all_stubs
+        .into_iter()
+        .filter(|(name, _, _)| md.app_functions.contains(name))
+        .map(|(name, offset, address)| (name, (offset, address)))
+        .collect();
+    let app_func_addresses: MutMap<u64, &str> = stubs
+        .iter()
+        .map(|(name, (_, address))| (*address, name.as_str()))
+        .collect();
 
-    let flushing_data_duration = flushing_data_start.elapsed();
+    if verbose {
+        println!();
+        println!("Stubs for App Functions");
+        for symbol in app_syms.iter() {
+            println!("{}: {:+x?}", symbol.index().0, symbol);
+        }
 
-    // Make sure the final executable has permision to execute.
-    #[cfg(target_family = "unix")]
-    {
-        use std::fs;
-        use std::os::unix::fs::PermissionsExt;
+        println!();
+        println!("App Function Address Map: {app_func_addresses:+x?}");
+    }
+    let symbol_and_plt_processing_duration = symbol_and_plt_processing_start.elapsed();
 
-        let mut perms = fs::metadata(executable_path)
-            .unwrap_or_else(|e| internal_error!("{}", e))
-            .permissions();
-        perms.set_mode(perms.mode() | 0o111);
-        fs::set_permissions(executable_path, perms).unwrap_or_else(|e| internal_error!("{}", e));
+    // look at the text (i.e. code) sections and see collect work needs to be done
+    let text_disassembly_start = Instant::now();
+
+    let mut surgeries = Surgeries::new(&app_syms, app_func_addresses);
+    surgeries.append_text_sections(exec_data, &exec_obj, verbose);
+    md.surgeries = surgeries.surgeries;
+    md.stubs = stubs;
+    md.stub_size = stub_size;
+
+    let text_disassembly_duration = text_disassembly_start.elapsed();
+
+    let platform_gen_start = Instant::now();
+
+    let out = gen_macho_le(exec_data, &mut md, shared_lib, verbose);
+
+    let platform_gen_duration = platform_gen_start.elapsed();
+
+    if verbose {
+        println!();
+        println!("{md:+x?}");
     }
 
+    let saving_metadata_start = Instant::now();
+    md.write_to_file(metadata_path);
+    let saving_metadata_duration = saving_metadata_start.elapsed();
+
+    let flushing_data_start = Instant::now();
+    std::fs::write(preprocessed_path, out).unwrap_or_else(|e| internal_error!("{}", e));
+    let flushing_data_duration = flushing_data_start.elapsed();
+
     let total_duration = total_start.elapsed();
 
     if verbose || time {
-        println!("\nTimings");
-        report_timing("Loading Metadata", loading_metadata_duration);
-        report_timing("Loading and mmap-ing", load_and_mmap_duration);
-        report_timing("Output Generation", out_gen_duration);
+        println!();
+        println!("Timings");
+        report_timing("Executable Parsing", exec_parsing_duration);
+        report_timing(
+            "Symbol and PLT Processing",
+            symbol_and_plt_processing_duration,
+        );
+        report_timing("Text Disassembly", text_disassembly_duration);
+        report_timing("Generate Modified Platform", platform_gen_duration);
+        report_timing("Saving Metadata", saving_metadata_duration);
         report_timing("Flushing Data to Disk", flushing_data_duration);
+        report_timing(
+            "Other",
+            total_duration
+                - exec_parsing_duration
+                - symbol_and_plt_processing_duration
+                - text_disassembly_duration
+                - platform_gen_duration
+                - saving_metadata_duration
+                - flushing_data_duration,
+        );
+        report_timing("Total", total_duration);
+    }
+}
 
-        let sum = loading_metadata_duration
-            + load_and_mmap_duration
-            + out_gen_duration
-            + flushing_data_duration;
+/// Copies the host, changed so that it no longer needs the app's shared library at load time.
+///
+/// Surgery then adds the app in new segments between the host's segments and `__LINKEDIT`, so
+/// nothing in the host moves except `__LINKEDIT`.
+fn gen_macho_le(exec_data: &[u8], md: &mut Metadata, shared_lib: &Path, verbose: bool) -> Vec<u8> {
+    let mut out = exec_data.to_vec();
 
-        report_timing("Other", total_duration.saturating_sub(sum));
-        report_timing("Total", total_duration);
+    let (dylib_cmd_offset, app_ordinal) = find_app_dylib(&out, shared_lib).unwrap_or_else(|| {
+        internal_error!("Host does not link library `{}`!", shared_lib.display());
+    });
+
+    // A weak library may be missing at load time. Keeping the load command, rather than deleting
+    // it, keeps the ordinals of the libraries after it the same.
+    load_struct_inplace_mut::<DylibCommand<LE>>(&mut out, dylib_cmd_offset)
+        .cmd
+        .set(LE, macho::LC_LOAD_WEAK_DYLIB);
+
+    remove_code_signature(&mut out, md.page_size, verbose);
+
+    let linkedit = find_segment(&out, b"__LINKEDIT");
+    if linkedit.fileoff + linkedit.filesize != out.len() as u64 {
+        internal_error!("The __LINKEDIT segment must be at the end of the host executable");
+    }
+
+    let dyld_info = find_load_command(&out, &[macho::LC_DYLD_INFO, macho::LC_DYLD_INFO_ONLY]);
+    let chained_fixups = find_load_command(&out, &[macho::LC_DYLD_CHAINED_FIXUPS]);
+    match (dyld_info, chained_fixups) {
+        (Some(cmd_offset), _) => convert_app_binds(&mut out, md, cmd_offset, app_ordinal, verbose),
+        (None, Some(cmd_offset)) => record_chained_app_binds(&mut out, md, cmd_offset, app_ordinal),
+        (None, None) => {}
     }
+
+    // The app's load commands go in the padding between the load commands and the first section.
+    md.load_commands_space = load_commands_space(&out) as u64;
+
+    if md.load_commands_space < APP_LOAD_COMMANDS_SIZE as u64 {
+        internal_error!(
+            "The host only has room for {} more bytes of load commands, but the app needs {}. Try linking the host with `-headerpad 0x1000`.",
+            md.load_commands_space,
+            APP_LOAD_COMMANDS_SIZE
+        );
+    }
+
+    out
 }
 
-#[allow(clippy::too_many_arguments)]
-fn surgery_macho_help(
-    _metadata_filename: &Path,
-    _out_filename: &Path,
+/// With classic dyld info, the binds of app symbols are replaced by rebases. Surgery then
+/// writes the unslid address of the app symbol to each of those pointers.
+fn convert_app_binds(
+    out: &mut Vec<u8>,
+    md: &mut Metadata,
+    cmd_offset: usize,
+    app_ordinal: u64,
     verbose: bool,
-    _time: bool,
-    md: &Metadata,
-    exec_mmap: &mut MmapMut,
-    offset_ref: &mut usize, // TODO return this instead of taking a mutable reference to it
-    app_obj: object::File,
 ) {
-    let mut offset = align_by_constraint(md.exec_len as usize, MIN_SECTION_ALIGNMENT);
-    // let new_rodata_section_offset = offset;
-
-    // Align physical and virtual address of new segment.
-    let mut virt_offset = align_to_offset_by_constraint(
-        md.last_vaddr as usize,
-        offset,
-        md.load_align_constraint as usize,
-    );
-    let new_rodata_section_vaddr = virt_offset;
-    if verbose {
-        println!();
-        println!("New Virtual Rodata Section Address: {new_rodata_section_vaddr:+x?}");
+    let info = load_struct_inplace::<DyldInfoCommand<LE>>(out, cmd_offset);
+    let rebase_off = info.rebase_off.get(LE) as usize;
+    let rebase_size = info.rebase_size.get(LE) as usize;
+    let bind_off = info.bind_off.get(LE) as usize;
+    let bind_size = info.bind_size.get(LE) as usize;
+
+    let segments = segments(out);
+    let mut kept = Vec::new();
+    let mut locations = Vec::new();
+
+    for record in parse_bind_opcodes(&out[bind_off..bind_off + bind_size]) {
+        if record.ordinal != app_ordinal as i64 {
+            kept.push(record);
+            continue;
+        }
+
+        if verbose {
+            println!("Converting bind of {} to a rebase", record.symbol);
+        }
+
+        let segment = &segments[record.segment as usize];
+        locations.push((record.segment, record.offset));
+        md.app_pointers
+            .entry(record.symbol)
+            .or_default()
+            .push(AppPointer {
+                file_offset: segment.fileoff + record.offset,
+                addend: record.addend,
+                fixup: PointerFixup::Rebase,
+            });
     }
 
-    // First decide on sections locations and then recode every exact symbol locations.
+    if locations.is_empty() {
+        return;
+    }
 
-    // Copy sections and resolve their symbols/relocations.
-    let symbols = app_obj.symbols().collect::<Vec<Symbol>>();
-    let mut section_offset_map: MutMap<SectionIndex, (usize, usize)> = MutMap::default();
-    let mut symbol_vaddr_map: MutMap<SymbolIndex, usize> = MutMap::default();
-    let mut app_func_vaddr_map: MutMap<String, usize> = MutMap::default();
-    let mut app_func_size_map: MutMap<String, u64> = MutMap::default();
+    let rebases = prepend_rebases(&out[rebase_off..rebase_off + rebase_size], &locations);
+    let binds = bind_opcodes(&kept);
+    let rebase_off = append_to_linkedit(out, &rebases, md.page_size);
+    let bind_off = append_to_linkedit(out, &binds, md.page_size);
 
-    // TODO: In the future Roc may use a data section to store memoized toplevel thunks
-    // in development builds for caching the results of top-level constants
+    let info = load_struct_inplace_mut::<DyldInfoCommand<LE>>(out, cmd_offset);
+    info.rebase_off.set(LE, rebase_off as u32);
+    info.rebase_size.set(LE, rebases.len() as u32);
+    info.bind_off.set(LE, bind_off as u32);
+    info.bind_size.set(LE, binds.len() as u32);
+}
 
-    let rodata_sections: Vec<Section> = app_obj
-        .sections()
-        .filter(|sec| sec.kind() == SectionKind::ReadOnlyData)
-        .collect();
+/// With chained fixups, the binds of app symbols stay in their chains until surgery turns each
+/// of them into a rebase.
+fn record_chained_app_binds(
+    out: &mut [u8],
+    md: &mut Metadata,
+    cmd_offset: usize,
+    app_ordinal: u64,
+) {
+    let info = load_struct_inplace::<LinkeditDataCommand<LE>>(out, cmd_offset);
+    let fixups = ChainedFixups::parse(out, info.dataoff.get(LE) as usize, &segments(out));
 
-    // bss section is like rodata section, but it has zero file size and non-zero virtual size.
-    let bss_sections: Vec<Section> = app_obj
-        .sections()
-        .filter(|sec| sec.kind() == SectionKind::UninitializedData)
-        .collect();
+    md.chained_pointer_format = Some(fixups.pointer_format.unwrap_or(DYLD_CHAINED_PTR_64_OFFSET));
 
-    let text_sections: Vec<Section> = app_obj
-        .sections()
-        .filter(|sec| sec.kind() == SectionKind::Text)
-        .collect();
-    if text_sections.is_empty() {
-        internal_error!("No text sections found. This application has no code.");
+    // The app's library is weak now, so its imports must be too.
+    for import in fixups.imports.iter() {
+        if import.lib_ordinal == app_ordinal as i64 {
+            let (byte, bit) = import.weak_flag;
+            out[byte] |= bit;
+        }
     }
 
-    // Calculate addresses and load symbols.
-    // Note, it is important the bss sections come after the rodata sections.
-    for sec in rodata_sections
-        .iter()
-        .chain(bss_sections.iter())
-        .chain(text_sections.iter())
-    {
-        offset = align_by_constraint(offset, MIN_SECTION_ALIGNMENT);
-        virt_offset =
-            align_to_offset_by_constraint(virt_offset, offset, md.load_align_constraint as usize);
-        if verbose {
-            println!(
-                "Section, {}, is being put at offset: {:+x}(virt: {:+x})",
-                sec.name().unwrap(),
-                offset,
-                virt_offset
-            )
+    for bind in fixups.binds {
+        let import = &fixups.imports[bind.import];
+        if import.lib_ordinal == app_ordinal as i64 {
+            md.app_pointers
+                .entry(import.name.clone())
+                .or_default()
+                .push(AppPointer {
+                    file_offset: bind.file_offset,
+                    addend: import.addend + bind.addend,
+                    fixup: PointerFixup::Chained,
+                });
         }
-        section_offset_map.insert(sec.index(), (offset, virt_offset));
-        for sym in symbols.iter() {
-            if sym.section() == SymbolSection::Section(sec.index()) {
-                let name = sym.name().unwrap_or_default().to_string();
-                if !md.roc_symbol_vaddresses.contains_key(&name) {
-                    symbol_vaddr_map.insert(sym.index(), virt_offset + sym.address() as usize);
-                }
-                if md.app_functions.contains(&name) {
-                    app_func_vaddr_map.insert(name.clone(), virt_offset + sym.address() as usize);
-                    app_func_size_map.insert(name, sym.size());
-                }
-            }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Region {
+    Text,
+    Data,
+    Bss,
+}
+
+fn app_region(sec: &Section) -> Option<Region> {
+    // Unwind info is not carried over
+    if matches!(sec.name(), Ok("__eh_frame" | "__compact_unwind")) {
+        return None;
+    }
+
+    match sec.kind() {
+        SectionKind::Text => Some(Region::Text),
+        SectionKind::Data | SectionKind::ReadOnlyData | SectionKind::ReadOnlyString => {
+            Some(Region::Data)
         }
-        let section_size = match sec.file_range() {
-            Some((_, size)) => size,
-            None => 0,
-        };
-        if sec.name().unwrap_or_default().starts_with("__BSS") {
-            // bss sections only modify the virtual size.
-            virt_offset += sec.size() as usize;
-        } else if section_size != sec.size() {
-            internal_error!( "We do not deal with non bss sections that have different on disk and in memory sizes");
-        } else {
-            offset += section_size as usize;
-            virt_offset += sec.size() as usize;
+        SectionKind::UninitializedData | SectionKind::Common => Some(Region::Bss),
+        _ => None,
+    }
+}
+
+/// The Mach-O relocation type, which `object` only passes through for some relocations.
+fn macho_relocation_type(cpu_type: u32, rel: &object::Relocation) -> u8 {
+    match (cpu_type, rel.kind(), rel.encoding()) {
+        (_, RelocationKind::MachO { value, .. }, _) => value,
+        (macho::CPU_TYPE_X86_64, RelocationKind::Absolute, _) => macho::X86_64_RELOC_UNSIGNED,
+        (macho::CPU_TYPE_X86_64, RelocationKind::Relative, RelocationEncoding::X86Branch) => {
+            macho::X86_64_RELOC_BRANCH
+        }
+        (macho::CPU_TYPE_X86_64, RelocationKind::Relative, _) => macho::X86_64_RELOC_SIGNED,
+        (
+            macho::CPU_TYPE_X86_64,
+            RelocationKind::GotRelative,
+            RelocationEncoding::X86RipRelativeMovq,
+        ) => macho::X86_64_RELOC_GOT_LOAD,
+        (macho::CPU_TYPE_X86_64, RelocationKind::GotRelative, _) => macho::X86_64_RELOC_GOT,
+        (macho::CPU_TYPE_ARM64, RelocationKind::Absolute, _) => macho::ARM64_RELOC_UNSIGNED,
+        (macho::CPU_TYPE_ARM64, RelocationKind::Relative, RelocationEncoding::AArch64Call) => {
+            macho::ARM64_RELOC_BRANCH26
+        }
+        (_, kind, _) => {
+            internal_error!("Relocation Kind not yet support: {:?}", kind);
         }
     }
-    if verbose {
-        println!("Data Relocation Offsets: {symbol_vaddr_map:+x?}");
-        println!("Found App Function Symbols: {app_func_vaddr_map:+x?}");
+}
+
+/// Sets the target of an arm64 `B` or `BL` instruction.
+fn arm64_branch(inst: u32, delta: i64) -> u32 {
+    if delta % 4 != 0 || !(-(1 << 27)..1 << 27).contains(&delta) {
+        internal_error!("Branch offset {delta:+x} is out of range for arm64");
     }
 
-    // let (new_text_section_offset, new_text_section_vaddr) = text_sections
-    //     .iter()
-    //     .map(|sec| section_offset_map.get(&sec.index()).unwrap())
-    //     .min()
-    //     .unwrap();
-    // let (new_text_section_offset, new_text_section_vaddr) =
-    //     (*new_text_section_offset, *new_text_section_vaddr);
+    (inst & 0xfc00_0000) | ((delta >> 2) as u32 & 0x03ff_ffff)
+}
 
-    // Move data and deal with relocations.
-    for sec in rodata_sections
-        .iter()
-        .chain(bss_sections.iter())
-        .chain(text_sections.iter())
-    {
-        let data = match sec.data() {
-            Ok(data) => data,
-            Err(err) => {
-                internal_error!(
-                    "Failed to load data for section, {:+x?}: {}",
-                    sec.name().unwrap(),
-                    err
-                );
-            }
-        };
-        let (section_offset, section_virtual_offset) =
-            section_offset_map.get(&sec.index()).unwrap();
-        let (section_offset, section_virtual_offset) = (*section_offset, *section_virtual_offset);
-        exec_mmap[section_offset..section_offset + data.len()].copy_from_slice(data);
-        // Deal with definitions and relocations for this section.
+/// Sets the page that an arm64 `ADRP` instruction computes.
+fn arm64_adrp(inst: u32, page_delta: i64) -> u32 {
+    if !(-(1 << 20)..1 << 20).contains(&page_delta) {
+        internal_error!("Page offset {page_delta:+x} is out of range for arm64");
+    }
+
+    let imm = page_delta as u32;
+    (inst & 0x9f00_001f) | ((imm & 0b11) << 29) | (((imm >> 2) & 0x7_ffff) << 5)
+}
+
+/// Sets the offset within a page of an `ADD`, or of a load or store, which scale it by the
+/// access size.
+fn arm64_pageoff12(inst: u32, target: i64) -> u32 {
+    let offset = (target & 0xfff) as u32;
+    let scale = if inst & 0x3b00_0000 == 0x3900_0000 {
+        if inst & 0x0480_0000 == 0x0480_0000 {
+            // 128-bit vector register
+            4
+        } else {
+            inst >> 30
+        }
+    } else {
+        0
+    };
+
+    if offset & ((1 << scale) - 1) != 0 {
+        internal_error!("Page offset {offset:+x} is misaligned for {inst:#x}");
+    }
+
+    (inst & !(0xfff << 10)) | ((offset >> scale) << 10)
+}
+
+/// The app's code and data, laid out as they will be in the executable: code in a `__ROC_TEXT`
+/// segment where `__LINKEDIT` was, followed by a `__ROC_DATA` segment.
+struct AppImage {
+    fileoff: u64,
+    vmaddr: u64,
+    text: Vec<u8>,
+    text_align: usize,
+    data_fileoff: u64,
+    data_vmaddr: u64,
+    data: Vec<u8>,
+    data_align: usize,
+    bss_vmaddr: u64,
+    bss_size: u64,
+    bss_align: usize,
+    /// Addresses of the absolute pointers in `data`, which dyld has to slide.
+    rebases: Vec<u64>,
+    function_addresses: MutMap<String, u64>,
+}
+
+impl AppImage {
+    fn build(
+        app_obj: &object::File,
+        md: &Metadata,
+        fileoff: u64,
+        vmaddr: u64,
+        verbose: bool,
+    ) -> Self {
+        let sections: Vec<(Section, Region)> = app_obj
+            .sections()
+            .filter_map(|sec| app_region(&sec).map(|region| (sec, region)))
+            .collect();
+        if !sections.iter().any(|(_, region)| *region == Region::Text) {
+            internal_error!("No text sections found. This application has no code.");
+        }
         if verbose {
             println!();
-            println!(
-                "Processing Relocations for Section: 0x{sec:+x?} @ {section_offset:+x} (virt: {section_virtual_offset:+x})"
-            );
+            println!("App Sections");
+            for (sec, region) in sections.iter() {
+                println!("{region:?}: {sec:+x?}");
+            }
         }
-        for rel in sec.relocations() {
-            if verbose {
-                println!("\tFound Relocation: {rel:+x?}");
+
+        // Each region's sections go one after the other
+        let mut placements: MutMap<SectionIndex, (Region, usize)> = MutMap::default();
+        let mut lengths = [0; 3];
+        let mut alignments = [MIN_SECTION_ALIGNMENT; 3];
+        for (sec, region) in sections.iter() {
+            let r = *region as usize;
+            let align = (sec.align() as usize).max(MIN_SECTION_ALIGNMENT);
+            let offset = align_by_constraint(lengths[r], align);
+            placements.insert(sec.index(), (*region, offset));
+            lengths[r] = offset + sec.size() as usize;
+            alignments[r] = alignments[r].max(align);
+        }
+
+        let [text_len, data_len, bss_len] = lengths;
+        let data_fileoff =
+            align_by_constraint(fileoff as usize + text_len, md.page_size as usize) as u64;
+        let data_vmaddr = vmaddr + (data_fileoff - fileoff);
+        let bss_vmaddr = align_by_constraint(data_vmaddr as usize + data_len, alignments[2]) as u64;
+        let region_vmaddrs = [vmaddr, data_vmaddr, bss_vmaddr];
+
+        let section_vmaddr = |index: SectionIndex| {
+            placements
+                .get(&index)
+                .map(|(region, offset)| region_vmaddrs[*region as usize] + *offset as u64)
+        };
+        let symbol_vmaddr = |index: SymbolIndex| {
+            let sym = app_obj.symbol_by_index(index).ok()?;
+            match sym.section() {
+                SymbolSection::Section(section_index) => {
+                    let sec = app_obj.section_by_index(section_index).ok()?;
+                    Some(section_vmaddr(section_index)? + sym.address() - sec.address())
+                }
+                SymbolSection::Absolute => Some(sym.address()),
+                SymbolSection::Undefined => md
+                    .roc_symbol_vaddresses
+                    .get(sym.name().ok()?.trim_start_matches('_'))
+                    .copied(),
+                _ => None,
             }
-            match rel.1.target() {
-                RelocationTarget::Symbol(index) => {
-                    let target_offset = if let Some(target_offset) = symbol_vaddr_map.get(&index) {
-                        if verbose {
-                            println!("\t\tRelocation targets symbol in app at: {target_offset:+x}");
-                        }
-                        Some(*target_offset as i64)
-                    } else {
-                        app_obj
-                            .symbol_by_index(index)
-                            .and_then(|sym| sym.name())
-                            .ok()
-                            .and_then(|name| {
-                                md.roc_symbol_vaddresses.get(name).map(|address| {
-                                    let vaddr = (*address + md.added_byte_count) as i64;
-                                    if verbose {
-                                        println!(
-                                            "\t\tRelocation targets symbol in host: {name} @ {vaddr:+x}"
-                                        );
-                                    }
-                                    vaddr
-                                })
-                            })
+        };
+
+        let mut text = vec![0; text_len];
+        let mut data = vec![0; data_len];
+        let mut rebases = Vec::new();
+
+        for (sec, region) in sections.iter().filter(|(_, r)| *r != Region::Bss) {
+            let section_offset = placements[&sec.index()].1;
+            let base_vmaddr = region_vmaddrs[*region as usize] + section_offset as u64;
+            let bytes = if *region == Region::Text {
+                &mut text
+            } else {
+                &mut data
+            };
+
+            let section_data = match sec.data() {
+                Ok(data) => data,
+                Err(err) => {
+                    internal_error!("Failed to load section, {:+x?}: {}", sec, err);
+                }
+            };
+            bytes[section_offset..section_offset + section_data.len()]
+                .copy_from_slice(section_data);
+
+            let mut arm64_addend = 0;
+            let mut subtrahend = None;
+
+            for (offset, rel) in sec.relocations() {
+                if verbose {
+                    println!("\tFound Relocation: {rel:+x?}");
+                }
+
+                let location = section_offset + offset as usize;
+                let place = (base_vmaddr + offset) as i64;
+                let r_type = macho_relocation_type(md.cpu_type, &rel);
+
+                if md.cpu_type == macho::CPU_TYPE_ARM64 && r_type == macho::ARM64_RELOC_ADDEND {
+                    // The addend for the next relocation is stored in place of its target
+                    let raw = match rel.target() {
+                        RelocationTarget::Symbol(index) => index.0,
+                        RelocationTarget::Section(index) => index.0,
+                        _ => 0,
                     };
+                    arm64_addend = ((raw as i64) << 40) >> 40;
+                    continue;
+                }
+                let addend = mem::take(&mut arm64_addend);
+
+                let (target, is_section) = match rel.target() {
+                    RelocationTarget::Symbol(index) => match symbol_vmaddr(index) {
+                        Some(address) => (address as i64, false),
+                        None if matches!(app_obj.symbol_by_index(index), Ok(sym) if ["__divti3", "__udivti3", "___divti3", "___udivti3"].contains(&sym.name().unwrap_or_default())) =>
+                        {
+                            // Explicitly ignore some symbols that are currently always linked.
+                            continue;
+                        }
+                        None => {
+                            internal_error!(
+                                "Undefined Symbol in relocation, {:+x?}: {:+x?}",
+                                rel,
+                                app_obj.symbol_by_index(index)
+                            );
+                        }
+                    },
+                    // Relative to where the section was in the object file
+                    RelocationTarget::Section(index) => {
+                        match (section_vmaddr(index), app_obj.section_by_index(index)) {
+                            (Some(address), Ok(target_sec)) => {
+                                (address as i64 - target_sec.address() as i64, true)
+                            }
+                            _ => {
+                                internal_error!("Relocation target not yet support: {:+x?}", rel);
+                            }
+                        }
+                    }
+                    _ => {
+                        internal_error!("Relocation target not yet support: {:+x?}", rel);
+                    }
+                };
 
-                    if let Some(target_offset) = target_offset {
-                        let virt_base = section_virtual_offset + rel.0 as usize;
-                        let base = section_offset + rel.0 as usize;
-                        let target: i64 = match rel.1.kind() {
-                            RelocationKind::Relative | RelocationKind::PltRelative => {
-                                target_offset - virt_base as i64 + rel.1.addend()
+                if is_section
+                    && (subtrahend.is_some()
+                        || (md.cpu_type == macho::CPU_TYPE_ARM64
+                            && r_type != macho::ARM64_RELOC_UNSIGNED))
+                {
+                    internal_error!("Section relative relocation not yet support: {:+x?}", rel);
+                }
+
+                match (md.cpu_type, r_type) {
+                    (macho::CPU_TYPE_X86_64, macho::X86_64_RELOC_SUBTRACTOR)
+                    | (macho::CPU_TYPE_ARM64, macho::ARM64_RELOC_SUBTRACTOR) => {
+                        subtrahend = Some(target);
+                    }
+                    (macho::CPU_TYPE_X86_64, macho::X86_64_RELOC_UNSIGNED)
+                    | (macho::CPU_TYPE_ARM64, macho::ARM64_RELOC_UNSIGNED) => {
+                        match (rel.size(), subtrahend.take()) {
+                            (32, Some(subtrahend)) => {
+                                let implicit = read_u32_at(bytes, location) as i32 as i64;
+                                write_i32_at(bytes, location, target + implicit - subtrahend);
+                            }
+                            (64, Some(subtrahend)) => {
+                                let implicit = read_u64_at(bytes, location) as i64;
+                                let value = target + implicit - subtrahend;
+                                write_u64_at(bytes, location, value as u64);
+                            }
+                            (64, None) => {
+                                if *region == Region::Text {
+                                    internal_error!(
+                                        "Absolute pointers in code are not supported: {:+x?}",
+                                        rel
+                                    );
+                                }
+                                let implicit = read_u64_at(bytes, location) as i64;
+                                let value = target + implicit + addend;
+                                write_u64_at(bytes, location, value as u64);
+                                rebases.push(place as u64);
                             }
-                            x => {
-                                internal_error!("Relocation Kind not yet support: {:?}", x);
+                            (size, _) => {
+                                internal_error!("Relocation size not yet supported: {}", size);
                             }
+                        }
+                    }
+                    (
+                        macho::CPU_TYPE_X86_64,
+                        macho::X86_64_RELOC_SIGNED
+                        | macho::X86_64_RELOC_BRANCH
+                        | macho::X86_64_RELOC_GOT_LOAD
+                        | macho::X86_64_RELOC_GOT
+                        | macho::X86_64_RELOC_SIGNED_1
+                        | macho::X86_64_RELOC_SIGNED_2
+                        | macho::X86_64_RELOC_SIGNED_4,
+                    ) => {
+                        let k = match r_type {
+                            macho::X86_64_RELOC_SIGNED_1 => 1,
+                            macho::X86_64_RELOC_SIGNED_2 => 2,
+                            macho::X86_64_RELOC_SIGNED_4 => 4,
+                            _ => 0,
                         };
-                        if verbose {
-                            println!(
-                                "\t\tRelocation base location: {base:+x} (virt: {virt_base:+x})"
-                            );
-                            println!("\t\tFinal relocation target offset: {target:+x}");
+                        let mut addend = rel.addend() + read_u32_at(bytes, location) as i32 as i64;
+                        if is_section {
+                            // The implicit addend is the target's address in the object file,
+                            // relative to the end of the instruction there.
+                            addend += (sec.address() + offset) as i64 + 4 + k;
                         }
-                        match rel.1.size() {
-                            32 => {
-                                let data = (target as i32).to_le_bytes();
-                                exec_mmap[base..base + 4].copy_from_slice(&data);
+
+                        // All app symbols are local, so they don't need to go through the GOT.
+                        let opcode = location.checked_sub(2).map(|op| [bytes[op], bytes[op + 1]]);
+                        match (r_type, opcode) {
+                            (macho::X86_64_RELOC_GOT_LOAD, Some([0x8b, _])) => {
+                                // mov reg, [rip + sym@GOTPCREL] becomes lea reg, [rip + sym]
+                                bytes[location - 2] = 0x8d;
                             }
-                            64 => {
-                                let data = target.to_le_bytes();
-                                exec_mmap[base..base + 8].copy_from_slice(&data);
+                            (macho::X86_64_RELOC_GOT, Some([0xff, 0x15])) => {
+                                // call [rip + sym@GOTPCREL] becomes addr32 call sym
+                                bytes[location - 2..location].copy_from_slice(&[0x67, 0xe8]);
                             }
-                            x => {
-                                internal_error!("Relocation size not yet supported: {}", x);
+                            (macho::X86_64_RELOC_GOT_LOAD | macho::X86_64_RELOC_GOT, _) => {
+                                internal_error!(
+                                    "Unsupported instruction for GOT relocation: {:+x?}",
+                                    rel
+                                );
                             }
+                            _ => {}
                         }
-                    } else if matches!(app_obj.symbol_by_index(index), Ok(sym) if ["__divti3", "__udivti3", "___divti3", "___udivti3"].contains(&sym.name().unwrap_or_default()))
-                    {
-                        // Explicitly ignore some symbols that are currently always linked.
-                        continue;
-                    } else {
+
+                        // `object` includes the distance from the place to the end of the
+                        // instruction in the addend, like it is for ELF.
+                        write_i32_at(bytes, location, target + addend - place);
+                    }
+                    (macho::CPU_TYPE_ARM64, macho::ARM64_RELOC_BRANCH26) => {
+                        let inst = read_u32_at(bytes, location);
+                        let inst = arm64_branch(inst, target + addend - place);
+                        write_u32_at(bytes, location, inst);
+                    }
+                    (
+                        macho::CPU_TYPE_ARM64,
+                        macho::ARM64_RELOC_PAGE21 | macho::ARM64_RELOC_GOT_LOAD_PAGE21,
+                    ) => {
+                        let page_delta = ((target + addend) >> 12) - (place >> 12);
+                        let inst = arm64_adrp(read_u32_at(bytes, location), page_delta);
+                        write_u32_at(bytes, location, inst);
+                    }
+                    (macho::CPU_TYPE_ARM64, macho::ARM64_RELOC_PAGEOFF12) => {
+                        let inst = arm64_pageoff12(read_u32_at(bytes, location), target + addend);
+                        write_u32_at(bytes, location, inst);
+                    }
+                    (macho::CPU_TYPE_ARM64, macho::ARM64_RELOC_GOT_LOAD_PAGEOFF12) => {
+                        let inst = read_u32_at(bytes, location);
+                        if inst & 0xffc0_0000 != 0xf940_0000 {
+                            internal_error!(
+                                "Unsupported instruction for GOT relocation: {:+x?}",
+                                rel
+                            );
+                        }
+                        // ldr xd, [xn, sym@GOTPAGEOFF] becomes add xd, xn, sym@PAGEOFF
+                        let offset = ((target + addend) & 0xfff) as u32;
+                        let inst = 0x9100_0000 | (offset << 10) | (inst & 0x3ff);
+                        write_u32_at(bytes, location, inst);
+                    }
+                    (_, other) => {
+                        internal_error!("Relocation type {} not yet supported: {:+x?}", other, rel);
+                    }
+                }
+            }
+        }
+
+        let function_addresses = app_obj
+            .symbols()
+            .filter(|sym| sym.is_definition())
+            .filter_map(|sym| {
+                let name = sym.name().ok()?;
+                if !md.app_functions.iter().any(|func_name| func_name == name) {
+                    return None;
+                }
+                Some((name.to_string(), symbol_vmaddr(sym.index())?))
+            })
+            .collect();
+
+        Self {
+            fileoff,
+            vmaddr,
+            text,
+            text_align: alignments[0],
+            data_fileoff,
+            data_vmaddr,
+            data,
+            data_align: alignments[1],
+            bss_vmaddr,
+            bss_size: bss_len as u64,
+            bss_align: alignments[2],
+            rebases,
+            function_addresses,
+        }
+    }
+
+    fn has_data_segment(&self) -> bool {
+        !self.data.is_empty() || self.bss_size > 0
+    }
+
+    fn end_fileoff(&self, page_size: u64) -> u64 {
+        align_by_constraint(
+            self.data_fileoff as usize + self.data.len(),
+            page_size as usize,
+        ) as u64
+    }
+
+    fn end_vmaddr(&self, page_size: u64) -> u64 {
+        align_by_constraint(
+            (self.bss_vmaddr + self.bss_size) as usize,
+            page_size as usize,
+        ) as u64
+    }
+
+    /// Links the pointers in `data` into chains of rebases, and returns the
+    /// `dyld_chained_starts_in_segment` that leads dyld to them.
+    fn chain_rebases(
+        &mut self,
+        pointer_format: u16,
+        image_base: u64,
+        page_size: u64,
+    ) -> Option<Vec<u8>> {
+        if self.rebases.is_empty() {
+            return None;
+        }
+
+        let mut rebases = self.rebases.clone();
+        rebases.sort_unstable();
+
+        let page_count = (self.end_vmaddr(page_size) - self.data_vmaddr) / page_size;
+        let mut page_starts = vec![DYLD_CHAINED_PTR_START_NONE; page_count as usize];
+
+        for (index, address) in rebases.iter().enumerate() {
+            let offset = address - self.data_vmaddr;
+            let page = (offset / page_size) as usize;
+            if page_starts[page] == DYLD_CHAINED_PTR_START_NONE {
+                page_starts[page] = (offset % page_size) as u16;
+            }
+
+            let next = match rebases.get(index + 1) {
+                Some(next) if (next - self.data_vmaddr) / page_size == page as u64 => {
+                    if (next - address) % 4 != 0 {
                         internal_error!(
-                            "Undefined Symbol in relocation, {:+x?}: {:+x?}",
-                            rel,
-                            app_obj.symbol_by_index(index)
+                            "Pointers at {address:+x} and {next:+x} can not be chained"
                         );
                     }
+                    (next - address) / 4
                 }
+                _ => 0,
+            };
 
-                _ => {
-                    internal_error!("Relocation target not yet support: {:+x?}", rel);
-                }
+            let target = read_u64_at(&self.data, offset as usize);
+            let value =
+                chained_rebase(pointer_format, image_base, target) | (next << CHAINED_NEXT_SHIFT);
+            write_u64_at(&mut self.data, offset as usize, value);
+        }
+
+        let mut starts = Vec::new();
+        starts.extend_from_slice(&(22 + 2 * page_starts.len() as u32).to_le_bytes());
+        starts.extend_from_slice(&(page_size as u16).to_le_bytes());
+        starts.extend_from_slice(&pointer_format.to_le_bytes());
+        starts.extend_from_slice(&(self.data_vmaddr - image_base).to_le_bytes());
+        // max_valid_pointer, which only 32-bit formats use
+        starts.extend_from_slice(&0u32.to_le_bytes());
+        starts.extend_from_slice(&(page_starts.len() as u16).to_le_bytes());
+        for start in page_starts {
+            starts.extend_from_slice(&start.to_le_bytes());
+        }
+
+        Some(starts)
+    }
+
+    /// The `LC_SEGMENT_64` commands for the app's segments.
+    fn load_commands(&self, page_size: u64) -> Vec<u8> {
+        let mut commands = Vec::new();
+
+        push_segment_command(
+            &mut commands,
+            "__ROC_TEXT",
+            (self.vmaddr, self.data_vmaddr - self.vmaddr),
+            (self.fileoff, self.data_fileoff - self.fileoff),
+            macho::VM_PROT_READ | macho::VM_PROT_EXECUTE,
+            &[section_header(
+                "__text",
+                "__ROC_TEXT",
+                (self.vmaddr, self.text.len() as u64),
+                self.fileoff as u32,
+                self.text_align,
+                macho::S_REGULAR
+                    | macho::S_ATTR_PURE_INSTRUCTIONS
+                    | macho::S_ATTR_SOME_INSTRUCTIONS,
+            )],
+        );
+
+        if self.has_data_segment() {
+            let mut sections = vec![section_header(
+                "__data",
+                "__ROC_DATA",
+                (self.data_vmaddr, self.data.len() as u64),
+                self.data_fileoff as u32,
+                self.data_align,
+                macho::S_REGULAR,
+            )];
+            if self.bss_size > 0 {
+                sections.push(section_header(
+                    "__bss",
+                    "__ROC_DATA",
+                    (self.bss_vmaddr, self.bss_size),
+                    0,
+                    self.bss_align,
+                    macho::S_ZEROFILL,
+                ));
             }
+
+            push_segment_command(
+                &mut commands,
+                "__ROC_DATA",
+                (
+                    self.data_vmaddr,
+                    self.end_vmaddr(page_size) - self.data_vmaddr,
+                ),
+                (
+                    self.data_fileoff,
+                    self.end_fileoff(page_size) - self.data_fileoff,
+                ),
+                macho::VM_PROT_READ | macho::VM_PROT_WRITE,
+                &sections,
+            );
         }
+
+        commands
     }
+}
+
+fn name16(name: &str) -> [u8; 16] {
+    let mut bytes = [0; 16];
+    bytes[..name.len()].copy_from_slice(name.as_bytes());
+    bytes
+}
+
+fn section_header(
+    sectname: &str,
+    segname: &str,
+    (addr, size): (u64, u64),
+    offset: u32,
+    align: usize,
+    flags: u32,
+) -> Section64<LE> {
+    Section64 {
+        sectname: name16(sectname),
+        segname: name16(segname),
+        addr: U64::new(LE, addr),
+        size: U64::new(LE, size),
+        offset: U32::new(LE, offset),
+        align: U32::new(LE, align.trailing_zeros()),
+        reloff: U32::new(LE, 0),
+        nreloc: U32::new(LE, 0),
+        flags: U32::new(LE, flags),
+        reserved1: U32::new(LE, 0),
+        reserved2: U32::new(LE, 0),
+        reserved3: U32::new(LE, 0),
+    }
+}
+
+fn push_segment_command(
+    commands: &mut Vec<u8>,
+    segname: &str,
+    (vmaddr, vmsize): (u64, u64),
+    (fileoff, filesize): (u64, u64),
+    prot: u32,
+    sections: &[Section64<LE>],
+) {
+    let cmdsize = mem::size_of::<SegmentCommand64<LE>>() + mem::size_of_val(sections);
+
+    let command = SegmentCommand64 {
+        cmd: U32::new(LE, macho::LC_SEGMENT_64),
+        cmdsize: U32::new(LE, cmdsize as u32),
+        segname: name16(segname),
+        vmaddr: U64::new(LE, vmaddr),
+        vmsize: U64::new(LE, vmsize),
+        fileoff: U64::new(LE, fileoff),
+        filesize: U64::new(LE, filesize),
+        maxprot: U32::new(LE, prot),
+        initprot: U32::new(LE, prot),
+        nsects: U32::new(LE, sections.len() as u32),
+        flags: U32::new(LE, 0),
+    };
+
+    commands.extend_from_slice(object::pod::bytes_of(&command));
+    for section in sections {
+        commands.extend_from_slice(object::pod::bytes_of(section));
+    }
+}
+
+/// Inserts `commands` just before the load command at `cmd_offset`.
+fn insert_load_commands(exec_mmap: &mut [u8], cmd_offset: usize, commands: &[u8], count: u32) {
+    let commands_end = load_commands_end(exec_mmap);
+    exec_mmap.copy_within(cmd_offset..commands_end, cmd_offset + commands.len());
+    exec_mmap[cmd_offset..cmd_offset + commands.len()].copy_from_slice(commands);
+
+    let header = load_struct_inplace_mut::<MachHeader64<LE>>(exec_mmap, 0);
+    header.ncmds.set(LE, header.ncmds.get(LE) + count);
+    header
+        .sizeofcmds
+        .set(LE, header.sizeofcmds.get(LE) + commands.len() as u32);
+}
+
+fn shift_offset(field: &mut U32<LE>, from: u64, shift: u64) {
+    if field.get(LE) as u64 >= from {
+        field.set(LE, field.get(LE) + shift as u32);
+    }
+}
+
+/// Moves every file offset in the load commands that points into `__LINKEDIT`.
+fn shift_linkedit_offsets(exec_mmap: &mut [u8], linkedit_fileoff: u64, shift: u64) {
+    let from = linkedit_fileoff;
+
+    for (cmd, offset, _) in load_commands(exec_mmap) {
+        match cmd {
+            macho::LC_SYMTAB => {
+                let info = load_struct_inplace_mut::<SymtabCommand<LE>>(exec_mmap, offset);
+                shift_offset(&mut info.symoff, from, shift);
+                shift_offset(&mut info.stroff, from, shift);
+            }
+            macho::LC_DYSYMTAB => {
+                let info = load_struct_inplace_mut::<DysymtabCommand<LE>>(exec_mmap, offset);
+                shift_offset(&mut info.tocoff, from, shift);
+                shift_offset(&mut info.modtaboff, from, shift);
+                shift_offset(&mut info.extrefsymoff, from, shift);
+                shift_offset(&mut info.indirectsymoff, from, shift);
+                shift_offset(&mut info.extreloff, from, shift);
+                shift_offset(&mut info.locreloff, from, shift);
+            }
+            macho::LC_DYLD_INFO | macho::LC_DYLD_INFO_ONLY => {
+                let info = load_struct_inplace_mut::<DyldInfoCommand<LE>>(exec_mmap, offset);
+                shift_offset(&mut info.rebase_off, from, shift);
+                shift_offset(&mut info.bind_off, from, shift);
+                shift_offset(&mut info.weak_bind_off, from, shift);
+                shift_offset(&mut info.lazy_bind_off, from, shift);
+                shift_offset(&mut info.export_off, from, shift);
+            }
+            macho::LC_FUNCTION_STARTS
+            | macho::LC_DATA_IN_CODE
+            | macho::LC_CODE_SIGNATURE
+            | macho::LC_SEGMENT_SPLIT_INFO
+            | macho::LC_DYLIB_CODE_SIGN_DRS
+            | macho::LC_LINKER_OPTIMIZATION_HINT
+            | macho::LC_DYLD_EXPORTS_TRIE
+            | macho::LC_DYLD_CHAINED_FIXUPS => {
+                let info = load_struct_inplace_mut::<LinkeditDataCommand<LE>>(exec_mmap, offset);
+                shift_offset(&mut info.dataoff, from, shift);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Fixups for the final executable that are appended to `__LINKEDIT`.
+enum LinkeditAddition {
+    None,
+    /// Rebase opcodes that replace the host's
+    Rebases(Vec<u8>),
+    /// Chained fixups that replace the host's
+    ChainedFixups(Vec<u8>),
+}
+
+impl LinkeditAddition {
+    fn new(exec_data: &[u8], md: &Metadata, app: &mut AppImage, linkedit_index: usize) -> Self {
+        if let Some(pointer_format) = md.chained_pointer_format {
+            let cmd_offset = find_load_command(exec_data, &[macho::LC_DYLD_CHAINED_FIXUPS])
+                .unwrap_or_else(|| {
+                    internal_error!("Failed to find LC_DYLD_CHAINED_FIXUPS in the host");
+                });
+            let app_starts = app.chain_rebases(pointer_format, md.image_base, md.page_size);
+
+            return Self::ChainedFixups(rebuild_chained_fixups(
+                exec_data,
+                cmd_offset,
+                linkedit_index,
+                app_starts.as_deref(),
+            ));
+        }
+
+        if app.rebases.is_empty() {
+            return Self::None;
+        }
+
+        let cmd_offset =
+            find_load_command(exec_data, &[macho::LC_DYLD_INFO, macho::LC_DYLD_INFO_ONLY])
+                .unwrap_or_else(|| {
+                    internal_error!(
+                        "The app has pointers to rebase, but the host has no dyld info"
+                    );
+                });
+        let info = load_struct_inplace::<DyldInfoCommand<LE>>(exec_data, cmd_offset);
+        let rebase_off = info.rebase_off.get(LE) as usize;
+        let existing = &exec_data[rebase_off..rebase_off + info.rebase_size.get(LE) as usize];
+
+        // The app's data segment comes right after its text segment, which takes the index of
+        // __LINKEDIT.
+        let data_segment = linkedit_index as u8 + 1;
+        let locations: Vec<_> = app
+            .rebases
+            .iter()
+            .map(|address| (data_segment, address - app.data_vmaddr))
+            .collect();
+
+        Self::Rebases(prepend_rebases(existing, &locations))
+    }
+
+    fn bytes(&self) -> &[u8] {
+        match self {
+            Self::None => &[],
+            Self::Rebases(bytes) | Self::ChainedFixups(bytes) => bytes,
+        }
+    }
+}
+
+pub(crate) fn surgery_macho(
+    roc_app_bytes: &[u8],
+    metadata_path: &Path,
+    executable_path: &Path,
+    verbose: bool,
+    time: bool,
+) {
+    let app_obj = match object::File::parse(roc_app_bytes) {
+        Ok(obj) => obj,
+        Err(err) => {
+            internal_error!("Failed to parse application file: {}", err);
+        }
+    };
+
+    let total_start = Instant::now();
+
+    let loading_metadata_start = total_start;
+    let md = Metadata::read_from_file(metadata_path);
+    let loading_metadata_duration = loading_metadata_start.elapsed();
+
+    let load_and_mmap_start = Instant::now();
+    let exec_data = open_mmap(executable_path);
+    let load_and_mmap_duration = load_and_mmap_start.elapsed();
+
+    let out_gen_start = Instant::now();
 
-    // Flush app only data to speed up write to disk.
+    let linkedit = find_segment(&exec_data, b"__LINKEDIT");
+    let mut app = AppImage::build(&app_obj, &md, linkedit.fileoff, linkedit.vmaddr, verbose);
+    let addition = LinkeditAddition::new(&exec_data, &md, &mut app, linkedit.index);
+
+    let new_linkedit_end = (app.end_fileoff(md.page_size) + linkedit.filesize) as usize;
+    let addition_offset = align_by_constraint(new_linkedit_end, 8);
+    let out_len = match addition {
+        LinkeditAddition::None => new_linkedit_end,
+        _ => addition_offset + addition.bytes().len(),
+    };
+    drop(exec_data);
+
+    let mut exec_mmap = open_mmap_mut(executable_path, out_len);
+
+    surgery_macho_help(
+        verbose,
+        &md,
+        &mut exec_mmap,
+        &app,
+        &linkedit,
+        &addition,
+        addition_offset,
+    );
+
+    let out_gen_duration = out_gen_start.elapsed();
+    let flushing_data_start = Instant::now();
+
+    // TODO investigate using the async version of flush - might be faster due to not having to block on that
     exec_mmap
-        .flush_async_range(md.exec_len as usize, offset - md.exec_len as usize)
+        .flush()
         .unwrap_or_else(|e| internal_error!("{}", e));
+    // Also drop files to to ensure data is fully written here.
+    drop(exec_mmap);
+
+    let flushing_data_duration = flushing_data_start.elapsed();
+
+    // Make sure the final executable has permision to execute.
+    #[cfg(target_family = "unix")]
+    {
+        use std::fs;
+        use std::os::unix::fs::PermissionsExt;
+
+        let mut perms = fs::metadata(executable_path)
+            .unwrap_or_else(|e| internal_error!("{}", e))
+            .permissions();
+        perms.set_mode(perms.mode() | 0o111);
+        fs::set_permissions(executable_path, perms).unwrap_or_else(|e| internal_error!("{}", e));
+    }
+
+    let total_duration = total_start.elapsed();
 
-    // TODO: look into merging symbol tables, debug info, and eh frames to enable better debugger experience.
-
-    // let mut cmd_offset = md.macho_cmd_loc as usize;
-
-    // // Load this section (we made room for it earlier) and then mutate all its data to make it the desired command
-    // {
-    //     let cmd =
-    //         load_struct_inplace_mut::<macho::SegmentCommand64<LE >>(exec_mmap, cmd_offset);
-    //     let size_of_section = mem::size_of::<macho::Section64<LE >>() as u32;
-    //     let size_of_cmd = mem::size_of_val(cmd);
-
-    //     cmd_offset += size_of_cmd;
-
-    //     cmd.cmd.set(LE , macho::LC_SEGMENT_64);
-    //     cmd.cmdsize
-    //         .set(LE , size_of_section + size_of_cmd as u32);
-    //     cmd.segname = *b"__DATA_CONST\0\0\0\0";
-    //     cmd.vmaddr
-    //         .set(LE , new_rodata_section_vaddr as u64);
-    //     cmd.vmsize.set(
-    //         LE ,
-    //         (new_text_section_vaddr - new_rodata_section_vaddr) as u64,
-    //     );
-    //     cmd.fileoff
-    //         .set(LE , new_rodata_section_offset as u64);
-    //     cmd.filesize.set(
-    //         LE ,
-    //         (new_text_section_offset - new_rodata_section_offset) as u64,
-    //     );
-    //     cmd.nsects.set(LE , 1);
-    //     cmd.maxprot.set(LE , 0x00000003);
-    //     cmd.initprot.set(LE , 0x00000003);
-
-    //     // TODO set protection
-    // }
-
-    // {
-    //     let cmd = load_struct_inplace_mut::<macho::Section64<LE >>(exec_mmap, cmd_offset);
-    //     let size_of_cmd = mem::size_of_val(cmd);
-
-    //     cmd_offset += size_of_cmd;
-
-    //     cmd.sectname = *b"__const\0\0\0\0\0\0\0\0\0";
-    //     cmd.segname = *b"__DATA_CONST\0\0\0\0";
-    //     cmd.addr.set(LE , new_rodata_section_vaddr as u64);
-    //     cmd.size.set(
-    //         LE ,
-    //         (new_text_section_offset - new_rodata_section_offset) as u64,
-    //     );
-    //     cmd.offset.set(LE , 0); // TODO is this offset since the start of the file, or segment offset?
-    //     cmd.align.set(LE , 12); // TODO should this be 4096?
-    //     cmd.reloff.set(LE , 264); // TODO this should NOT be hardcoded! Should get it from somewhere.
-    // }
-
-    // {
-    //     let cmd =
-    //         load_struct_inplace_mut::<macho::SegmentCommand64<LE >>(exec_mmap, cmd_offset);
-    //     let size_of_section = mem::size_of::<macho::Section64<LE >>() as u32;
-    //     let size_of_cmd = mem::size_of_val(cmd);
-
-    //     cmd_offset += size_of_cmd;
-
-    //     cmd.cmd.set(LE , macho::LC_SEGMENT_64);
-    //     cmd.cmdsize
-    //         .set(LE , size_of_section + size_of_cmd as u32);
-    //     cmd.segname = *b"__TEXT\0\0\0\0\0\0\0\0\0\0";
-    //     cmd.vmaddr.set(LE , new_text_section_vaddr as u64);
-    //     cmd.vmsize
-    //         .set(LE , (offset - new_text_section_offset) as u64);
-    //     cmd.fileoff
-    //         .set(LE , new_text_section_offset as u64);
-    //     cmd.filesize
-    //         .set(LE , (offset - new_text_section_offset) as u64);
-    //     cmd.nsects.set(LE , 1);
-    //     cmd.maxprot.set(LE , 0x00000005); // this is what a zig-generated host had
-    //     cmd.initprot.set(LE , 0x00000005); // this is what a zig-generated host had
-    // }
-
-    // {
-    //     let cmd = load_struct_inplace_mut::<macho::Section64<LE >>(exec_mmap, cmd_offset);
-
-    //     cmd.segname = *b"__TEXT\0\0\0\0\0\0\0\0\0\0";
-    //     cmd.sectname = *b"__text\0\0\0\0\0\0\0\0\0\0";
-    //     cmd.addr.set(LE , new_text_section_vaddr as u64);
-    //     cmd.size
-    //         .set(LE , (offset - new_text_section_offset) as u64);
-    //     cmd.offset.set(LE , 0); // TODO is this offset since the start of the file, or segment offset?
-    //     cmd.align.set(LE , 12); // TODO this is 4096 (2^12) - which load_align_constraint does, above - but should it?
-    //     cmd.flags.set(LE , 0x80000400); // TODO this is what a zig-generated host had
-    //     cmd.reloff.set(LE , 264); // TODO this should NOT be hardcoded! Should get it from somewhere.
-    // }
-
-    // Update calls from platform and dynamic symbols.
-    // let dynsym_offset = md.dynamic_symbol_table_section_offset + md.added_byte_count;
+    if verbose || time {
+        println!("\nTimings");
+        report_timing("Loading Metadata", loading_metadata_duration);
+        report_timing("Loading and mmap-ing", load_and_mmap_duration);
+        report_timing("Output Generation", out_gen_duration);
+        report_timing("Flushing Data to Disk", flushing_data_duration);
+
+        let sum = loading_metadata_duration
+            + load_and_mmap_duration
+            + out_gen_duration
+            + flushing_data_duration;
+
+        report_timing("Other", total_duration.saturating_sub(sum));
+        report_timing("Total", total_duration);
+    }
+}
+
+fn surgery_macho_help(
+    verbose: bool,
+    md: &Metadata,
+    exec_mmap: &mut [u8],
+    app: &AppImage,
+    linkedit: &SegmentInfo,
+    addition: &LinkeditAddition,
+    addition_offset: usize,
+) {
+    let old_fileoff = linkedit.fileoff as usize;
+    let new_fileoff = app.end_fileoff(md.page_size) as usize;
+    let filesize = linkedit.filesize as usize;
+
+    if verbose {
+        println!();
+        println!("Moving __LINKEDIT from {old_fileoff:+x} to {new_fileoff:+x}");
+    }
+
+    // __LINKEDIT moves behind the app, which takes its place.
+    exec_mmap.copy_within(old_fileoff..old_fileoff + filesize, new_fileoff);
+    exec_mmap[old_fileoff..new_fileoff].fill(0);
+    exec_mmap[old_fileoff..old_fileoff + app.text.len()].copy_from_slice(&app.text);
+    let data_fileoff = app.data_fileoff as usize;
+    exec_mmap[data_fileoff..data_fileoff + app.data.len()].copy_from_slice(&app.data);
+
+    let addition_bytes = addition.bytes();
+    exec_mmap[addition_offset..addition_offset + addition_bytes.len()]
+        .copy_from_slice(addition_bytes);
 
     for func_name in md.app_functions.iter() {
-        let func_virt_offset = match app_func_vaddr_map.get(func_name) {
-            Some(offset) => *offset as u64,
+        let func_address = match app.function_addresses.get(func_name) {
+            Some(address) => *address,
             None => {
                 internal_error!("Function, {}, was not defined by the app", &func_name);
             }
         };
         if verbose {
-            println!(
-                "Updating calls to {} to the address: {:+x}",
-                &func_name, func_virt_offset
-            );
+            println!();
+            println!("Processing Function: {func_name} at {func_address:+x}");
         }
 
-        for s in md.surgeries.get(func_name).unwrap_or(&vec![]) {
+        for s in md.surgeries.get(func_name).into_iter().flatten() {
             if verbose {
                 println!("\tPerforming surgery: {s:+x?}");
             }
-            let surgery_virt_offset = match s.virtual_offset {
-                VirtualOffset::Relative(vs) => (vs + md.added_byte_count) as i64,
-                VirtualOffset::Absolute => 0,
-            };
-            match s.size {
-                4 => {
-                    let target = (func_virt_offset as i64 - surgery_virt_offset) as i32;
-                    if verbose {
-                        println!("\tTarget Jump: {target:+x}");
-                    }
-                    let data = target.to_le_bytes();
-                    exec_mmap[(s.file_offset + md.added_byte_count) as usize
-                        ..(s.file_offset + md.added_byte_count) as usize + 4]
-                        .copy_from_slice(&data);
+            let surgery_offset = s.file_offset as usize;
+            match (md.cpu_type, s.size, &s.virtual_offset) {
+                (macho::CPU_TYPE_X86_64, 4, VirtualOffset::Relative(next_ip)) => {
+                    let target = func_address as i64 - *next_ip as i64;
+                    write_i32_at(exec_mmap, surgery_offset, target);
+                }
+                (macho::CPU_TYPE_ARM64, 4, VirtualOffset::Relative(address)) => {
+                    let inst = read_u32_at(exec_mmap, surgery_offset);
+                    let inst = arm64_branch(inst, func_address as i64 - *address as i64);
+                    write_u32_at(exec_mmap, surgery_offset, inst);
+                }
+                _ => {
+                    internal_error!("Surgery not yet supported: {:+x?}", s);
                 }
-                8 => {
-                    let target = func_virt_offset as i64 - surgery_virt_offset;
-                    if verbose {
-                        println!("\tTarget Jump: {target:+x}");
+            }
+        }
+
+        // This is a backup in case we missed a call to the stub.
+        if let Some((stub_offset, stub_address)) = md.stubs.get(func_name) {
+            let stub_offset = *stub_offset as usize;
+            let stub_size = md.stub_size as usize;
+            let target = func_address as i64 - *stub_address as i64;
+            if verbose {
+                println!("\tStub: {stub_offset:+x}, {stub_address:+x}");
+            }
+
+            match md.cpu_type {
+                macho::CPU_TYPE_ARM64 => {
+                    write_u32_at(exec_mmap, stub_offset, arm64_branch(0x1400_0000, target));
+                    for nop_offset in (stub_offset + 4..stub_offset + stub_size).step_by(4) {
+                        write_u32_at(exec_mmap, nop_offset, ARM64_NOP);
                     }
-                    let data = target.to_le_bytes();
-                    exec_mmap[(s.file_offset + md.added_byte_count) as usize
-                        ..(s.file_offset + md.added_byte_count) as usize + 8]
-                        .copy_from_slice(&data);
                 }
-                x => {
-                    internal_error!("Surgery size not yet supported: {}", x);
+                _ => {
+                    let jmp_inst_len = 5;
+                    exec_mmap[stub_offset] = 0xE9;
+                    write_i32_at(exec_mmap, stub_offset + 1, target - jmp_inst_len as i64);
+                    exec_mmap[stub_offset + jmp_inst_len..stub_offset + stub_size].fill(0x90);
                 }
             }
         }
 
-        // Replace plt call code with just a jump.
-        // This is a backup incase we missed a call to the plt.
-        if let Some((plt_off, plt_vaddr)) = md.plt_addresses.get(func_name) {
-            let plt_off = (*plt_off + md.added_byte_count) as usize;
-            let plt_vaddr = *plt_vaddr + md.added_byte_count;
-            let jmp_inst_len = 5;
-            let target =
-                (func_virt_offset as i64 - (plt_vaddr as i64 + jmp_inst_len as i64)) as i32;
+        for pointer in md.app_pointers.get(func_name).into_iter().flatten() {
             if verbose {
-                println!("\tPLT: {plt_off:+x}, {plt_vaddr:+x}");
-                println!("\tTarget Jump: {target:+x}");
+                println!("\tPointer: {pointer:+x?}");
+            }
+            let pointer_offset = pointer.file_offset as usize;
+            let target = (func_address as i64 + pointer.addend) as u64;
+            let value = match (pointer.fixup, md.chained_pointer_format) {
+                (PointerFixup::Chained, Some(pointer_format)) => {
+                    let next = read_u64_at(exec_mmap, pointer_offset) & CHAINED_NEXT_MASK;
+                    chained_rebase(pointer_format, md.image_base, target) | next
+                }
+                _ => target,
+            };
+            write_u64_at(exec_mmap, pointer_offset, value);
+        }
+    }
+
+    // TODO: look into merging symbol tables, debug info, and unwind info to enable better debugger experience.
+
+    let commands = app.load_commands(md.page_size);
+    let command_count = if app.has_data_segment() { 2 } else { 1 };
+    if commands.len() as u64 > md.load_commands_space {
+        internal_error!("The host does not have enough room for the app's load commands");
+    }
+    insert_load_commands(exec_mmap, linkedit.cmd_offset, &commands, command_count);
+    shift_linkedit_offsets(
+        exec_mmap,
+        linkedit.fileoff,
+        (new_fileoff - old_fileoff) as u64,
+    );
+
+    let linkedit_cmd = load_struct_inplace_mut::<SegmentCommand64<LE>>(
+        exec_mmap,
+        linkedit.cmd_offset + commands.len(),
+    );
+    linkedit_cmd.fileoff.set(LE, new_fileoff as u64);
+    linkedit_cmd.vmaddr.set(LE, app.end_vmaddr(md.page_size));
+    set_linkedit_size(exec_mmap, md.page_size);
+
+    match addition {
+        LinkeditAddition::None => {}
+        LinkeditAddition::Rebases(bytes) => {
+            let cmd_offset =
+                find_load_command(exec_mmap, &[macho::LC_DYLD_INFO, macho::LC_DYLD_INFO_ONLY])
+                    .unwrap();
+            let info = load_struct_inplace_mut::<DyldInfoCommand<LE>>(exec_mmap, cmd_offset);
+            info.rebase_off.set(LE, addition_offset as u32);
+            info.rebase_size.set(LE, bytes.len() as u32);
+        }
+        LinkeditAddition::ChainedFixups(bytes) => {
+            let cmd_offset =
+                find_load_command(exec_mmap, &[macho::LC_DYLD_CHAINED_FIXUPS]).unwrap();
+            let info = load_struct_inplace_mut::<LinkeditDataCommand<LE>>(exec_mmap, cmd_offset);
+            info.dataoff.set(LE, addition_offset as u32);
+            info.datasize.set(LE, bytes.len() as u32);
+        }
+    }
+}
+
+/// Ad-hoc signs an executable, which macOS requires before running arm64 code.
+pub(crate) fn sign_executable(executable_path: &Path) {
+    let mut out = std::fs::read(executable_path).unwrap_or_else(|e| internal_error!("{}", e));
+    let identifier = executable_path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+
+    add_code_signature(&mut out, &identifier);
+
+    std::fs::write(executable_path, out).unwrap_or_else(|e| internal_error!("{}", e));
+}
+
+/// Appends an ad-hoc code signature to `__LINKEDIT`, the way `ld` signs the executables it links:
+/// a `CodeDirectory` with the SHA-256 hash of each page of the file before the signature, and
+/// nothing that needs a certificate.
+fn add_code_signature(out: &mut Vec<u8>, identifier: &str) {
+    let cpu_type = load_struct_inplace::<MachHeader64<LE>>(out, 0)
+        .cputype
+        .get(LE);
+    let page_size = segment_page_size(cpu_type);
+
+    remove_code_signature(out, page_size, false);
+
+    let commands_end = load_commands_end(out);
+    let command_size = mem::size_of::<LinkeditDataCommand<LE>>();
+    if load_commands_space(out) < command_size {
+        internal_error!("The executable has no room for the load command of its code signature");
+    }
+
+    let dataoff = align_by_constraint(out.len(), 16);
+    let code_slots = dataoff.div_ceil(CS_PAGE_SIZE);
+    let ident_offset = CS_CODE_DIRECTORY_SIZE;
+    let hash_offset = ident_offset + identifier.len() + 1;
+    let directory_size = hash_offset + code_slots * CS_HASH_SIZE;
+    let blob_size = CS_SUPER_BLOB_SIZE + directory_size;
+    let datasize = align_by_constraint(blob_size, 16);
+
+    let command = LinkeditDataCommand {
+        cmd: U32::new(LE, macho::LC_CODE_SIGNATURE),
+        cmdsize: U32::new(LE, command_size as u32),
+        dataoff: U32::new(LE, dataoff as u32),
+        datasize: U32::new(LE, datasize as u32),
+    };
+    insert_load_commands(out, commands_end, object::pod::bytes_of(&command), 1);
+
+    // The signature is the last thing in __LINKEDIT, and the hashes cover everything before it,
+    // including the load commands that point at it.
+    out.resize(dataoff + datasize, 0);
+    set_linkedit_size(out, page_size);
+
+    let text = find_segment(out, b"__TEXT");
+    let mut blob = Vec::with_capacity(datasize);
+
+    // The SuperBlob, with the CodeDirectory as its only blob
+    for word in [
+        CSMAGIC_EMBEDDED_SIGNATURE,
+        blob_size as u32,
+        1,
+        CSSLOT_CODEDIRECTORY,
+        CS_SUPER_BLOB_SIZE as u32,
+    ] {
+        blob.extend_from_slice(&word.to_be_bytes());
+    }
+
+    for word in [
+        CSMAGIC_CODEDIRECTORY,
+        directory_size as u32,
+        CS_SUPPORTSEXECSEG,
+        CS_ADHOC | CS_LINKER_SIGNED,
+        hash_offset as u32,
+        ident_offset as u32,
+        // nSpecialSlots: there are no other blobs to hash
+        0,
+        code_slots as u32,
+        dataoff as u32,
+    ] {
+        blob.extend_from_slice(&word.to_be_bytes());
+    }
+    blob.extend_from_slice(&[
+        CS_HASH_SIZE as u8,
+        CS_HASHTYPE_SHA256,
+        // platform
+        0,
+        CS_PAGE_SIZE.trailing_zeros() as u8,
+    ]);
+    // spare2, scatterOffset, teamOffset and spare3
+    blob.extend_from_slice(&[0; 16]);
+    // codeLimit64, which is only for files of 4GiB or more
+    blob.extend_from_slice(&0u64.to_be_bytes());
+    blob.extend_from_slice(&text.fileoff.to_be_bytes());
+    blob.extend_from_slice(&text.filesize.to_be_bytes());
+    blob.extend_from_slice(&CS_EXECSEG_MAIN_BINARY.to_be_bytes());
+    debug_assert_eq!(CS_SUPER_BLOB_SIZE + ident_offset, blob.len());
+
+    blob.extend_from_slice(identifier.as_bytes());
+    blob.push(0);
+
+    for page in out[..dataoff].chunks(CS_PAGE_SIZE) {
+        blob.extend_from_slice(&Sha256::digest(page));
+    }
+
+    out[dataoff..dataoff + blob.len()].copy_from_slice(&blob);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::preprocessed_host_filename;
+    use indoc::indoc;
+    use object::{U64Bytes, U16};
+    use roc_target::Target;
+
+    #[test]
+    fn leb128_round_trip() {
+        for value in [0, 1, 0x7f, 0x80, 0x3fff, 0x4000, u64::MAX] {
+            let mut bytes = Vec::new();
+            write_uleb128(&mut bytes, value);
+            assert_eq!(value, read_uleb128(&bytes, &mut 0));
+        }
+
+        for value in [0, 1, -1, 0x3f, 0x40, -0x40, -0x41, i64::MIN, i64::MAX] {
+            let mut bytes = Vec::new();
+            write_sleb128(&mut bytes, value);
+            assert_eq!(value, read_sleb128(&bytes, &mut 0));
+        }
+    }
+
+    #[test]
+    fn bind_opcodes_round_trip() {
+        let records = vec![
+            BindRecord {
+                ordinal: 2,
+                symbol: "_roc_magic1".to_string(),
+                flags: 0,
+                bind_type: macho::BIND_TYPE_POINTER,
+                addend: 0,
+                segment: 2,
+                offset: 0,
+            },
+            BindRecord {
+                ordinal: 1,
+                symbol: "_printf".to_string(),
+                flags: 0,
+                bind_type: macho::BIND_TYPE_POINTER,
+                addend: 8,
+                segment: 2,
+                offset: 0x10,
+            },
+        ];
+
+        assert_eq!(records, parse_bind_opcodes(&bind_opcodes(&records)));
+    }
+
+    #[test]
+    fn rebases_come_before_the_existing_ones() {
+        let existing = [0x11, 0x22, 0x10, 0x51, 0x00];
+
+        assert_eq!(
+            [0x11, 0x24, 0x00, 0x51, 0x11, 0x22, 0x10, 0x51, 0x00],
+            prepend_rebases(&existing, &[(4, 0)]).as_slice()
+        );
+    }
+
+    #[test]
+    fn arm64_instructions() {
+        // bl 0
+        assert_eq!(0x9400_0800, arm64_branch(0x9400_0000, 0x2000));
+        assert_eq!(0x97ff_fffe, arm64_branch(0x9400_0000, -8));
+
+        // adrp x0, 0
+        assert_eq!(0x9000_0020, arm64_adrp(0x9000_0000, 4));
+        assert_eq!(0xf0ff_ffe0, arm64_adrp(0x9000_0000, -1));
+
+        // add x0, x0, 0 and ldr x0, [x0]
+        assert_eq!(0x9100_2000, arm64_pageoff12(0x9100_0000, 0x4008));
+        assert_eq!(0xf940_0400, arm64_pageoff12(0xf940_0000, 0x4008));
+    }
+
+    #[test]
+    fn chained_rebases() {
+        let base = 0x1_0000_0000;
+
+        assert_eq!(
+            0xc008,
+            chained_rebase(DYLD_CHAINED_PTR_64_OFFSET, base, base + 0xc008)
+        );
+        assert_eq!(
+            0x1_0000_c008,
+            chained_rebase(DYLD_CHAINED_PTR_64, base, base + 0xc008)
+        );
+    }
+
+    const HOST_BASE: u64 = 0x1_0000_0000;
+    const HOST_PAGE: u64 = 0x4000;
+    const HOST_TEXT: u64 = 0x1000;
+    const HOST_STUBS: u64 = 0x1010;
+
+    fn push_command(commands: &mut Vec<u8>, cmd: u32, fields: &[u32], payload: &[u8]) {
+        let start = commands.len();
+        let cmdsize = align_by_constraint(8 + 4 * fields.len() + payload.len(), 8);
+
+        for word in [cmd, cmdsize as u32].iter().chain(fields) {
+            commands.extend_from_slice(&word.to_le_bytes());
+        }
+        commands.extend_from_slice(payload);
+        commands.resize(start + cmdsize, 0);
+    }
+
+    /// An executable as `ld` would lay it out, for a host whose `_main` calls `_roc_magic1`
+    /// in `libapp.so`. The call goes through a stub, which jumps to the address that dyld
+    /// binds in the GOT.
+    fn host(cpu_type: u32) -> Vec<u8> {
+        let words = |insts: &[u32]| -> Vec<u8> {
+            insts.iter().flat_map(|inst| inst.to_le_bytes()).collect()
+        };
+        let (main_code, stub_code) = match cpu_type {
+            macho::CPU_TYPE_ARM64 => (
+                // bl _roc_magic1 (the stub); ret
+                words(&[0x9400_0004, 0xd65f_03c0]),
+                // adrp x16, _roc_magic1@GOTPAGE; ldr x16, [x16, _roc_magic1@GOTPAGEOFF]; br x16
+                words(&[arm64_adrp(0x9000_0010, 3), 0xf940_0210, 0xd61f_0200]),
+            ),
+            _ => (
+                // call _roc_magic1 (the stub); ret
+                vec![0xe8, 0x0b, 0x00, 0x00, 0x00, 0xc3],
+                // jmp [rip + _roc_magic1@GOTPCREL]
+                vec![0xff, 0x25, 0xea, 0x2f, 0x00, 0x00],
+            ),
+        };
+        let stub_size = stub_code.len() as u32;
+
+        let got_fileoff = HOST_PAGE;
+        let linkedit_fileoff = 2 * HOST_PAGE;
+
+        let mut linkedit = bind_opcodes(&[BindRecord {
+            ordinal: 2,
+            symbol: "_roc_magic1".to_string(),
+            flags: 0,
+            bind_type: macho::BIND_TYPE_POINTER,
+            addend: 0,
+            segment: 2,
+            offset: 0,
+        }]);
+        let bind_size = linkedit.len();
+        linkedit.resize(align_by_constraint(linkedit.len(), 8), 0);
+
+        let symoff = linkedit.len();
+        let mut strings = vec![0];
+        for (name, n_type, n_sect, n_desc, n_value) in [
+            (
+                "_main",
+                macho::N_SECT | macho::N_EXT,
+                1,
+                0,
+                HOST_BASE + HOST_TEXT,
+            ),
+            // from the second library
+            ("_roc_magic1", macho::N_UNDF | macho::N_EXT, 0, 2 << 8, 0),
+        ] {
+            let nlist = macho::Nlist64 {
+                n_strx: U32::new(LE, strings.len() as u32),
+                n_type,
+                n_sect,
+                n_desc: U16::new(LE, n_desc),
+                n_value: U64Bytes::new(LE, n_value),
+            };
+            linkedit.extend_from_slice(object::pod::bytes_of(&nlist));
+            strings.extend_from_slice(name.as_bytes());
+            strings.push(0);
+        }
+
+        // The stub and the GOT entry are both for `_roc_magic1`
+        let indirectsymoff = linkedit.len();
+        linkedit.extend_from_slice(&[1, 0, 0, 0, 1, 0, 0, 0]);
+
+        let stroff = linkedit.len();
+        strings.resize(align_by_constraint(strings.len(), 8), 0);
+        linkedit.extend_from_slice(&strings);
+
+        let at = |offset: usize| (linkedit_fileoff as usize + offset) as u32;
+        let mut commands = Vec::new();
+
+        push_segment_command(&mut commands, "__PAGEZERO", (0, HOST_BASE), (0, 0), 0, &[]);
+
+        let text = section_header(
+            "__text",
+            "__TEXT",
+            (HOST_BASE + HOST_TEXT, 8),
+            HOST_TEXT as u32,
+            4,
+            macho::S_REGULAR | macho::S_ATTR_PURE_INSTRUCTIONS | macho::S_ATTR_SOME_INSTRUCTIONS,
+        );
+        let mut stubs = section_header(
+            "__stubs",
+            "__TEXT",
+            (HOST_BASE + HOST_STUBS, stub_size as u64),
+            HOST_STUBS as u32,
+            4,
+            macho::S_SYMBOL_STUBS
+                | macho::S_ATTR_PURE_INSTRUCTIONS
+                | macho::S_ATTR_SOME_INSTRUCTIONS,
+        );
+        stubs.reserved2 = U32::new(LE, stub_size);
+        push_segment_command(
+            &mut commands,
+            "__TEXT",
+            (HOST_BASE, HOST_PAGE),
+            (0, HOST_PAGE),
+            macho::VM_PROT_READ | macho::VM_PROT_EXECUTE,
+            &[text, stubs],
+        );
+
+        let mut got = section_header(
+            "__got",
+            "__DATA_CONST",
+            (HOST_BASE + got_fileoff, 8),
+            got_fileoff as u32,
+            8,
+            macho::S_NON_LAZY_SYMBOL_POINTERS,
+        );
+        got.reserved1 = U32::new(LE, 1);
+        push_segment_command(
+            &mut commands,
+            "__DATA_CONST",
+            (HOST_BASE + got_fileoff, HOST_PAGE),
+            (got_fileoff, HOST_PAGE),
+            macho::VM_PROT_READ | macho::VM_PROT_WRITE,
+            &[got],
+        );
+
+        push_segment_command(
+            &mut commands,
+            "__LINKEDIT",
+            (HOST_BASE + linkedit_fileoff, HOST_PAGE),
+            (linkedit_fileoff, linkedit.len() as u64),
+            macho::VM_PROT_READ,
+            &[],
+        );
+
+        #[rustfmt::skip]
+        push_command(&mut commands, macho::LC_DYLD_INFO_ONLY, &[
+            0, 0, at(0), bind_size as u32, 0, 0, 0, 0, 0, 0,
+        ], &[]);
+        push_command(
+            &mut commands,
+            macho::LC_SYMTAB,
+            &[at(symoff), 2, at(stroff), strings.len() as u32],
+            &[],
+        );
+        #[rustfmt::skip]
+        push_command(&mut commands, macho::LC_DYSYMTAB, &[
+            0, 0, 0, 1, 1, 1, 0, 0, 0, 0, 0, 0, at(indirectsymoff), 2, 0, 0, 0, 0,
+        ], &[]);
+        for name in ["/usr/lib/libSystem.B.dylib", "libapp.so"] {
+            push_command(
+                &mut commands,
+                macho::LC_LOAD_DYLIB,
+                &[24, 2, 0x1_0000, 0x1_0000],
+                name.as_bytes(),
+            );
+        }
+        push_command(
+            &mut commands,
+            macho::LC_MAIN,
+            &[HOST_TEXT as u32, 0, 0, 0],
+            &[],
+        );
+
+        let header = MachHeader64 {
+            magic: U32::new(object::BigEndian, macho::MH_CIGAM_64),
+            cputype: U32::new(LE, cpu_type),
+            cpusubtype: U32::new(LE, 0),
+            filetype: U32::new(LE, macho::MH_EXECUTE),
+            ncmds: U32::new(LE, 10),
+            sizeofcmds: U32::new(LE, commands.len() as u32),
+            flags: U32::new(LE, macho::MH_DYLDLINK | macho::MH_TWOLEVEL | macho::MH_PIE),
+            reserved: U32::new(LE, 0),
+        };
+
+        let mut host = object::pod::bytes_of(&header).to_vec();
+        host.extend_from_slice(&commands);
+        host.resize(HOST_TEXT as usize, 0);
+
+        host.extend_from_slice(&main_code);
+        host.resize(HOST_STUBS as usize, 0);
+        host.extend_from_slice(&stub_code);
+
+        host.resize(linkedit_fileoff as usize, 0);
+        host.extend_from_slice(&linkedit);
+
+        host
+    }
+
+    /// An app whose `roc_magic1` returns the address of a string that a pointer in its data
+    /// points to, so it has code relocations to data as well as a pointer to rebase.
+    fn arm64_app() -> Vec<u8> {
+        use object::write;
+
+        let mut app = write::Object::new(
+            object::BinaryFormat::MachO,
+            Architecture::Aarch64,
+            object::Endianness::Little,
+        );
+
+        let data = app.section_id(write::StandardSection::Data);
+        app.append_section_data(data, &[0; 8], 8);
+        app.append_section_data(data, b"foo\0\0\0\0\0", 8);
+        let x = app.add_symbol(write::Symbol {
+            name: b"X".to_vec(),
+            value: 8,
+            size: 8,
+            kind: object::SymbolKind::Data,
+            scope: object::SymbolScope::Compilation,
+            weak: false,
+            section: write::SymbolSection::Section(data),
+            flags: object::SymbolFlags::None,
+        });
+
+        let text = app.section_id(write::StandardSection::Text);
+        let mut code = Vec::new();
+        // adrp x0, _X@PAGE; add x0, x0, _X@PAGEOFF; ret
+        for inst in [0x9000_0000u32, 0x9100_0000, 0xd65f_03c0] {
+            code.extend_from_slice(&inst.to_le_bytes());
+        }
+        let roc_magic1 = app.add_symbol(write::Symbol {
+            name: b"roc_magic1".to_vec(),
+            value: 0,
+            size: 0,
+            kind: object::SymbolKind::Text,
+            scope: object::SymbolScope::Linkage,
+            weak: false,
+            section: write::SymbolSection::Undefined,
+            flags: object::SymbolFlags::None,
+        });
+        app.add_symbol_data(roc_magic1, text, &code, 4);
+
+        for (section, offset, kind) in [
+            (data, 0, RelocationKind::Absolute),
+            (
+                text,
+                0,
+                RelocationKind::MachO {
+                    value: macho::ARM64_RELOC_PAGE21,
+                    relative: true,
+                },
+            ),
+            (
+                text,
+                4,
+                RelocationKind::MachO {
+                    value: macho::ARM64_RELOC_PAGEOFF12,
+                    relative: false,
+                },
+            ),
+        ] {
+            let size = if section == data { 64 } else { 32 };
+            app.add_relocation(
+                section,
+                write::Relocation {
+                    offset,
+                    size,
+                    kind,
+                    encoding: RelocationEncoding::Generic,
+                    symbol: x,
+                    addend: 0,
+                },
+            )
+            .unwrap();
+        }
+
+        app.write().unwrap()
+    }
+
+    fn read_u32s(data: &[u8], offset: usize, count: usize) -> Vec<u32> {
+        (0..count)
+            .map(|i| read_u32_at(data, offset + 4 * i))
+            .collect()
+    }
+
+    /// An x86_64 app with a relocation of each kind that compilers use for local symbols.
+    /// Its `roc_magic1` returns the address of a string, and the data has a pointer to it.
+    fn x86_64_app() -> Vec<u8> {
+        use object::write;
+
+        let mut app = write::Object::new(
+            object::BinaryFormat::MachO,
+            Architecture::X86_64,
+            object::Endianness::Little,
+        );
+
+        let data = app.section_id(write::StandardSection::Data);
+        app.append_section_data(data, &[0; 8], 8);
+        app.append_section_data(data, b"foo\0\0\0\0\0", 8);
+        app.append_section_data(data, &[0; 8], 8);
+
+        let mut local_symbol = |name: &[u8], value, kind, section| {
+            app.add_symbol(write::Symbol {
+                name: name.to_vec(),
+                value,
+                size: 0,
+                kind,
+                scope: object::SymbolScope::Compilation,
+                weak: false,
+                section: write::SymbolSection::Section(section),
+                flags: object::SymbolFlags::None,
+            })
+        };
+        let x = local_symbol(b"X", 8, object::SymbolKind::Data, data);
+        let counter = local_symbol(b"COUNTER", 16, object::SymbolKind::Data, data);
+
+        let text = app.section_id(write::StandardSection::Text);
+        #[rustfmt::skip]
+        let code = [
+            // lea rax, [rip + X]
+            0x48, 0x8d, 0x05, 0, 0, 0, 0,
+            // mov dword [rip + COUNTER], 42
+            0xc7, 0x05, 0, 0, 0, 0, 0x2a, 0, 0, 0,
+            // mov rcx, [rip + X@GOTPCREL]
+            0x48, 0x8b, 0x0d, 0, 0, 0, 0,
+            // call [rip + helper@GOTPCREL]
+            0xff, 0x15, 0, 0, 0, 0,
+            // call helper
+            0xe8, 0, 0, 0, 0,
+            // ret
+            0xc3,
+            // helper: ret
+            0xc3,
+        ];
+        let roc_magic1 = app.add_symbol(write::Symbol {
+            name: b"roc_magic1".to_vec(),
+            value: 0,
+            size: 0,
+            kind: object::SymbolKind::Text,
+            scope: object::SymbolScope::Linkage,
+            weak: false,
+            section: write::SymbolSection::Undefined,
+            flags: object::SymbolFlags::None,
+        });
+        app.add_symbol_data(roc_magic1, text, &code, 16);
+        let helper = app.add_symbol(write::Symbol {
+            name: b"helper".to_vec(),
+            value: 0x24,
+            size: 0,
+            kind: object::SymbolKind::Text,
+            scope: object::SymbolScope::Compilation,
+            weak: false,
+            section: write::SymbolSection::Section(text),
+            flags: object::SymbolFlags::None,
+        });
+
+        let macho_relative = |value| RelocationKind::MachO {
+            value,
+            relative: true,
+        };
+        for (section, offset, size, kind, encoding, symbol, addend) in [
+            (
+                data,
+                0,
+                64,
+                RelocationKind::Absolute,
+                RelocationEncoding::Generic,
+                x,
+                0,
+            ),
+            (
+                text,
+                0x3,
+                32,
+                RelocationKind::Relative,
+                RelocationEncoding::Generic,
+                x,
+                -4,
+            ),
+            // The 4 bytes of the immediate come after the displacement
+            (
+                text,
+                0x9,
+                32,
+                macho_relative(macho::X86_64_RELOC_SIGNED_4),
+                RelocationEncoding::Generic,
+                counter,
+                -8,
+            ),
+            (
+                text,
+                0x14,
+                32,
+                macho_relative(macho::X86_64_RELOC_GOT_LOAD),
+                RelocationEncoding::Generic,
+                x,
+                -4,
+            ),
+            (
+                text,
+                0x1a,
+                32,
+                macho_relative(macho::X86_64_RELOC_GOT),
+                RelocationEncoding::Generic,
+                helper,
+                -4,
+            ),
+            (
+                text,
+                0x1f,
+                32,
+                RelocationKind::Relative,
+                RelocationEncoding::X86Branch,
+                helper,
+                -4,
+            ),
+        ] {
+            app.add_relocation(
+                section,
+                write::Relocation {
+                    offset,
+                    size,
+                    kind,
+                    encoding,
+                    symbol,
+                    addend,
+                },
+            )
+            .unwrap();
+        }
+
+        app.write().unwrap()
+    }
+
+    /// Preprocesses a [host] for `cpu_type` and links its app into it, returning the host and
+    /// the executable.
+    fn surgery(dir: &Path, cpu_type: u32) -> (Vec<u8>, Vec<u8>) {
+        let host = host(cpu_type);
+        std::fs::write(dir.join("host"), &host).unwrap();
+
+        let app = match cpu_type {
+            macho::CPU_TYPE_ARM64 => arm64_app(),
+            _ => x86_64_app(),
+        };
+
+        preprocess_macho_le(
+            &dir.join("host"),
+            &dir.join("metadata"),
+            &dir.join("final"),
+            Path::new("libapp.so"),
+            false,
+            false,
+        );
+        surgery_macho(
+            &app,
+            &dir.join("metadata"),
+            &dir.join("final"),
+            false,
+            false,
+        );
+
+        (host, std::fs::read(dir.join("final")).unwrap())
+    }
+
+    /// Checks the load commands after [surgery], and returns the name, address, file offset
+    /// and file size of the segments that come after the host's.
+    fn surgery_load_commands(cpu_type: u32) -> Vec<(String, u64, u64, u64)> {
+        let dir = tempfile::tempdir().unwrap();
+        let (_, out) = surgery(dir.path(), cpu_type);
+
+        let commands: Vec<_> = load_commands(&out)
+            .into_iter()
+            .map(|(cmd, _, cmdsize)| (cmd, cmdsize))
+            .collect();
+        assert_eq!(
+            commands,
+            [
+                (macho::LC_SEGMENT_64, 0x48),
+                (macho::LC_SEGMENT_64, 0xe8),
+                (macho::LC_SEGMENT_64, 0x98),
+                // the app's code and data
+                (macho::LC_SEGMENT_64, 0x98),
+                (macho::LC_SEGMENT_64, 0x98),
+                (macho::LC_SEGMENT_64, 0x48),
+                (macho::LC_DYLD_INFO_ONLY, 0x30),
+                (macho::LC_SYMTAB, 0x18),
+                (macho::LC_DYSYMTAB, 0x50),
+                (macho::LC_LOAD_DYLIB, 0x38),
+                (macho::LC_LOAD_WEAK_DYLIB, 0x28),
+                (macho::LC_MAIN, 0x18),
+            ]
+        );
+
+        segments(&out)
+            .into_iter()
+            .skip(3)
+            .map(|segment| {
+                let name = String::from_utf8_lossy(&segment.name);
+                let name = name.trim_end_matches('\0').to_string();
+                (name, segment.vmaddr, segment.fileoff, segment.filesize)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn arm64_surgery_load_commands() {
+        assert_eq!(
+            surgery_load_commands(macho::CPU_TYPE_ARM64),
+            [
+                ("__ROC_TEXT".to_string(), 0x1_0000_8000, 0x8000, 0x4000),
+                ("__ROC_DATA".to_string(), 0x1_0000_c000, 0xc000, 0x4000),
+                ("__LINKEDIT".to_string(), 0x1_0001_0000, 0x1_0000, 0x71),
+            ]
+        );
+    }
+
+    #[test]
+    fn x86_64_surgery_load_commands() {
+        // The app's segments are aligned to 4KiB pages rather than 16KiB ones
+        assert_eq!(
+            surgery_load_commands(macho::CPU_TYPE_X86_64),
+            [
+                ("__ROC_TEXT".to_string(), 0x1_0000_8000, 0x8000, 0x1000),
+                ("__ROC_DATA".to_string(), 0x1_0000_9000, 0x9000, 0x1000),
+                ("__LINKEDIT".to_string(), 0x1_0000_a000, 0xa000, 0x71),
+            ]
+        );
+    }
+
+    /// Checks that the symbol tables after [surgery] are the host's, moved along with
+    /// `__LINKEDIT` by `shift`.
+    fn surgery_symtab(cpu_type: u32, shift: usize) {
+        let dir = tempfile::tempdir().unwrap();
+        let (host, out) = surgery(dir.path(), cpu_type);
+
+        let symtab = |data: &[u8]| {
+            let offset = find_load_command(data, &[macho::LC_SYMTAB]).unwrap();
+            let symtab = load_struct_inplace::<SymtabCommand<LE>>(data, offset);
+            let symoff = symtab.symoff.get(LE) as usize;
+            let stroff = symtab.stroff.get(LE) as usize;
+            let strsize = symtab.strsize.get(LE) as usize;
+            (symoff, data[symoff..stroff + strsize].to_vec())
+        };
+        let (host_symoff, host_symbols) = symtab(&host);
+        let (symoff, symbols) = symtab(&out);
+        assert_eq!(symoff, host_symoff + shift);
+        assert_eq!(symbols, host_symbols);
+
+        let offset = find_load_command(&out, &[macho::LC_DYSYMTAB]).unwrap();
+        let dysymtab = load_struct_inplace::<DysymtabCommand<LE>>(&out, offset);
+        assert_eq!(
+            dysymtab.indirectsymoff.get(LE) as usize,
+            host_symoff + shift + 0x20
+        );
+
+        let object = object::File::parse(&*out).unwrap();
+        let symbols: Vec<_> = object
+            .symbols()
+            .map(|symbol| (symbol.name().unwrap().to_string(), symbol.address()))
+            .collect();
+        assert_eq!(
+            symbols,
+            [
+                ("_main".to_string(), 0x1_0000_1000),
+                ("_roc_magic1".to_string(), 0)
+            ]
+        );
+    }
+
+    #[test]
+    fn arm64_surgery_symtab() {
+        // __LINKEDIT moved by 0x8000, and everything in it with it
+        surgery_symtab(macho::CPU_TYPE_ARM64, 0x8000);
+    }
+
+    #[test]
+    fn x86_64_surgery_symtab() {
+        surgery_symtab(macho::CPU_TYPE_X86_64, 0x2000);
+    }
+
+    #[test]
+    fn arm64_surgery_relocations() {
+        let dir = tempfile::tempdir().unwrap();
+        let (_, out) = surgery(dir.path(), macho::CPU_TYPE_ARM64);
+
+        // bl _roc_magic1, now straight to the app; ret
+        assert_eq!(read_u32s(&out, 0x1000, 2), [0x9400_1c00, 0xd65f_03c0]);
+        // The stub jumps to the app too, in case something else calls it
+        assert_eq!(
+            read_u32s(&out, 0x1010, 3),
+            [0x1400_1bfc, ARM64_NOP, ARM64_NOP]
+        );
+        // The GOT entry that dyld used to bind
+        assert_eq!(read_u64_at(&out, 0x4000), 0x1_0000_8000);
+
+        // adrp x0, X@PAGE; add x0, x0, X@PAGEOFF; ret
+        assert_eq!(
+            read_u32s(&out, 0x8000, 3),
+            [0x9000_0020, 0x9100_2000, 0xd65f_03c0]
+        );
+        assert_eq!(read_u64_at(&out, 0xc000), 0x1_0000_c008);
+        assert_eq!(&out[0xc008..0xc00c], b"foo\0");
+
+        // Rebases for the app's pointer and then for the GOT entry
+        let offset = find_load_command(&out, &[macho::LC_DYLD_INFO_ONLY]).unwrap();
+        let info = load_struct_inplace::<DyldInfoCommand<LE>>(&out, offset);
+        let rebase_off = info.rebase_off.get(LE) as usize;
+        let rebase_size = info.rebase_size.get(LE) as usize;
+        assert_eq!(
+            &out[rebase_off..rebase_off + rebase_size],
+            [0x11, 0x24, 0x00, 0x51, 0x11, 0x22, 0x00, 0x51, 0x00]
+        );
+
+        // No more binds to the app
+        let bind_off = info.bind_off.get(LE) as usize;
+        let bind_size = info.bind_size.get(LE) as usize;
+        assert_eq!(
+            &out[bind_off..bind_off + bind_size],
+            [macho::BIND_OPCODE_DONE]
+        );
+    }
+
+    #[test]
+    fn x86_64_surgery_relocations() {
+        let dir = tempfile::tempdir().unwrap();
+        let (_, out) = surgery(dir.path(), macho::CPU_TYPE_X86_64);
+
+        // call _roc_magic1, now straight to the app; ret
+        assert_eq!(out[0x1000..0x1006], [0xe8, 0xfb, 0x6f, 0x00, 0x00, 0xc3]);
+        // The stub jumps to the app too, in case something else calls it
+        assert_eq!(out[0x1010..0x1016], [0xe9, 0xeb, 0x6f, 0x00, 0x00, 0x90]);
+        // The GOT entry that dyld used to bind
+        assert_eq!(read_u64_at(&out, 0x4000), 0x1_0000_8000);
+
+        #[rustfmt::skip]
+        let code = [
+            // lea rax, [rip + X]
+            0x48, 0x8d, 0x05, 0x01, 0x10, 0x00, 0x00,
+            // mov dword [rip + COUNTER], 42
+            0xc7, 0x05, 0xff, 0x0f, 0x00, 0x00, 0x2a, 0x00, 0x00, 0x00,
+            // lea rcx, [rip + X], as X is local
+            0x48, 0x8d, 0x0d, 0xf0, 0x0f, 0x00, 0x00,
+            // addr32 call helper
+            0x67, 0xe8, 0x06, 0x00, 0x00, 0x00,
+            // call helper
+            0xe8, 0x01, 0x00, 0x00, 0x00,
+            // ret
+            0xc3,
+            // helper: ret
+            0xc3,
+        ];
+        assert_eq!(out[0x8000..0x8025], code);
+        assert_eq!(read_u64_at(&out, 0x9000), 0x1_0000_9008);
+        assert_eq!(&out[0x9008..0x900c], b"foo\0");
+        assert_eq!(read_u64_at(&out, 0x9010), 0);
+
+        // Rebases for the app's pointer and then for the GOT entry
+        let offset = find_load_command(&out, &[macho::LC_DYLD_INFO_ONLY]).unwrap();
+        let info = load_struct_inplace::<DyldInfoCommand<LE>>(&out, offset);
+        let rebase_off = info.rebase_off.get(LE) as usize;
+        let rebase_size = info.rebase_size.get(LE) as usize;
+        assert_eq!(
+            &out[rebase_off..rebase_off + rebase_size],
+            [0x11, 0x24, 0x00, 0x51, 0x11, 0x22, 0x00, 0x51, 0x00]
+        );
+
+        // No more binds to the app
+        let bind_off = info.bind_off.get(LE) as usize;
+        let bind_size = info.bind_size.get(LE) as usize;
+        assert_eq!(
+            &out[bind_off..bind_off + bind_size],
+            [macho::BIND_OPCODE_DONE]
+        );
+    }
+
+    #[test]
+    fn arm64_code_signature() {
+        let dir = tempfile::tempdir().unwrap();
+        let dir = dir.path();
+        surgery(dir, macho::CPU_TYPE_ARM64);
+
+        sign_executable(&dir.join("final"));
+        let out = std::fs::read(dir.join("final")).unwrap();
+
+        let (cmd, offset, cmdsize) = *load_commands(&out).last().unwrap();
+        assert_eq!((cmd, cmdsize), (macho::LC_CODE_SIGNATURE, 0x10));
+        let info = load_struct_inplace::<LinkeditDataCommand<LE>>(&out, offset);
+        assert_eq!(info.dataoff.get(LE), 0x1_0080);
+        assert_eq!(info.datasize.get(LE), 0x2a0);
+        assert_eq!(out.len(), 0x1_0080 + 0x2a0);
+
+        let linkedit = find_segment(&out, b"__LINKEDIT");
+        assert_eq!(linkedit.fileoff + linkedit.filesize, out.len() as u64);
+
+        let mut header = Vec::new();
+        for word in [
+            // SuperBlob with one CodeDirectory
+            0xfade_0cc0u32,
+            0x292,
+            1,
+            0,
+            0x14,
+            // CodeDirectory
+            0xfade_0c02,
+            0x27e,
+            0x2_0400,
+            0x2_0002,
+            0x5e,
+            0x58,
+            0,
+            0x11,
+            0x1_0080,
+        ] {
+            header.extend_from_slice(&word.to_be_bytes());
+        }
+        header.extend_from_slice(&[0x20, 2, 0, 12]);
+        header.extend_from_slice(&[0; 16]);
+        // codeLimit64, and then the offset and size of __TEXT
+        header.extend_from_slice(&[0; 16]);
+        header.extend_from_slice(&0x4000u64.to_be_bytes());
+        header.extend_from_slice(&1u64.to_be_bytes());
+        header.extend_from_slice(b"final\0");
+
+        let signature = &out[0x1_0080..];
+        assert_eq!(&signature[..header.len()], header);
+
+        let hashes: Vec<_> = signature[header.len()..0x292]
+            .chunks(CS_HASH_SIZE)
+            .collect();
+        let pages: Vec<_> = out[..0x1_0080].chunks(CS_PAGE_SIZE).collect();
+        assert_eq!(hashes.len(), pages.len());
+        for (hash, page) in hashes.into_iter().zip(pages) {
+            assert_eq!(hash, Sha256::digest(page).as_slice());
+        }
+
+        // Signing again replaces the signature rather than adding another
+        sign_executable(&dir.join("final"));
+        assert_eq!(std::fs::read(dir.join("final")).unwrap(), out);
+    }
+
+    #[allow(dead_code)]
+    fn zig_host_app_help(dir: &Path, target: Target) {
+        let host_zig = indoc!(
+            r#"
+            const std = @import("std");
+
+            extern fn roc_magic1(usize) callconv(.C) [*]const u8;
+
+            pub fn main() !void {
+                const stdout = std.io.getStdOut().writer();
+                try stdout.print("Hello {s}\n", .{roc_magic1(0)[0..3]});
             }
-            let data = target.to_le_bytes();
-            exec_mmap[plt_off] = 0xE9;
-            exec_mmap[plt_off + 1..plt_off + jmp_inst_len].copy_from_slice(&data);
-            for i in jmp_inst_len..PLT_ADDRESS_OFFSET as usize {
-                exec_mmap[plt_off + i] = 0x90;
+            "#
+        );
+
+        let app_zig = indoc!(
+            r#"
+            const X = [_][]const u8 { "foo" };
+
+            export fn roc_magic1(index: usize) [*]const u8 {
+                return X[index].ptr;
             }
+            "#
+        );
+
+        let zig = std::env::var("ROC_ZIG").unwrap_or_else(|_| "zig".into());
+
+        std::fs::write(dir.join("host.zig"), host_zig.as_bytes()).unwrap();
+        std::fs::write(dir.join("app.zig"), app_zig.as_bytes()).unwrap();
+
+        // we need to compile the app first
+        let output = std::process::Command::new(&zig)
+            .current_dir(dir)
+            .args(["build-obj", "app.zig", "-fPIC", "-OReleaseFast"])
+            .output()
+            .unwrap();
+
+        if !output.status.success() {
+            use std::io::Write;
+
+            std::io::stdout().write_all(&output.stdout).unwrap();
+            std::io::stderr().write_all(&output.stderr).unwrap();
+
+            panic!("zig build-obj failed");
+        }
+
+        // open our app object; we'll copy sections from it later
+        let roc_app = std::fs::read(dir.join("app.o")).unwrap();
+
+        let names: Vec<String> = {
+            let object = object::File::parse(&*roc_app).unwrap();
+
+            object
+                .symbols()
+                .filter(|s| !s.is_local())
+                .map(|e| e.name().unwrap().trim_start_matches('_').to_string())
+                .collect()
+        };
+
+        let dylib_bytes = crate::generate_dylib::generate(target, &names).unwrap();
+        std::fs::write(dir.join("libapp.so"), dylib_bytes).unwrap();
+
+        // now we can compile the host (it uses libapp.so, hence the order here)
+        let output = std::process::Command::new(&zig)
+            .current_dir(dir)
+            .args([
+                "build-exe",
+                "libapp.so",
+                "host.zig",
+                "-fPIE",
+                "-lc",
+                "-OReleaseFast",
+                "-headerpad",
+                "0x1000",
+            ])
+            .output()
+            .unwrap();
+
+        if !output.status.success() {
+            use std::io::Write;
+
+            std::io::stdout().write_all(&output.stdout).unwrap();
+            std::io::stderr().write_all(&output.stderr).unwrap();
+
+            panic!("zig build-exe failed");
+        }
+
+        let preprocessed_host_filename = dir.join(preprocessed_host_filename(target));
+
+        preprocess_macho_le(
+            &dir.join("host"),
+            &dir.join("metadata"),
+            &preprocessed_host_filename,
+            &dir.join("libapp.so"),
+            false,
+            false,
+        );
+
+        std::fs::copy(&preprocessed_host_filename, dir.join("final")).unwrap();
+
+        surgery_macho(
+            &roc_app,
+            &dir.join("metadata"),
+            &dir.join("final"),
+            false,
+            false,
+        );
+
+        if target == Target::MacArm64 {
+            sign_executable(&dir.join("final"));
         }
+    }
+
+    #[cfg(target_os = "macos")]
+    #[test]
+    fn zig_host_app() {
+        let dir = tempfile::tempdir().unwrap();
+        let dir = dir.path();
 
-        // Commented out because it doesn't apply to mach-o
-        // if let Some(i) = md.dynamic_symbol_indices.get(func_name) {
-        //     let sym = load_struct_inplace_mut::<elf::Sym64<LE >>(
-        //         exec_mmap,
-        //         dynsym_offset as usize + *i as usize * mem::size_of::<elf::Sym64<LE >>(),
-        //     );
-        //     sym.st_value.set(LE , func_virt_offset as u64);
-        //     sym.st_size.set(
-        //         LE ,
-        //         match app_func_size_map.get(func_name) {
-        //             Some(size) => *size,
-        //             None => {
-        //                 internal_error!("Size missing for: {}", func_name);
-        //             }
-        //         },
-        //     );
-        // }
-    }
-
-    *offset_ref = offset;
+        let target = if cfg!(target_arch = "aarch64") {
+            Target::MacArm64
+        } else {
+            Target::MacX64
+        };
+        zig_host_app_help(dir, target);
+
+        let output = std::process::Command::new(dir.join("final"))
+            .current_dir(dir)
+            .output()
+            .unwrap();
+
+        if !output.status.success() {
+            use std::io::Write;
+
+            std::io::stdout().write_all(&output.stdout).unwrap();
+            std::io::stderr().write_all(&output.stderr).unwrap();
+
+            panic!("app.exe failed");
+        }
+
+        let output = String::from_utf8_lossy(&output.stdout);
+
+        assert_eq!("Hello foo\n", output);
+    }
 }
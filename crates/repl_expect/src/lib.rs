@@ -95,14 +95,25 @@ mod test {
     use pretty_assertions::assert_eq;
     use roc_error_macros::internal_error;
     use roc_gen_llvm::{llvm::build::LlvmBackendMode, run_roc::RocCallResult, run_roc_dylib};
-    use roc_load::{ExecutionMode, FunctionKind, LoadConfig, LoadMonomorphizedError, Threading};
+    use roc_load::{
+        ExecutionMode, FunctionKind, LoadConfig, LoadMonomorphizedError, OutputConfig, Threading,
+    };
     use roc_packaging::cache::RocCacheDir;
     use roc_reporting::report::{RenderTarget, DEFAULT_PALETTE};
     use target_lexicon::Triple;
 
-    use crate::run::expect_mono_module_to_dylib;
+    use crate::run::{expect_mono_module_to_dylib, expect_mono_module_to_dylib_dev};
 
     fn run_expect_test(source: &str, expected: &str) {
+        run_expect_test_help(source, expected, false)
+    }
+
+    /// Like `run_expect_test`, but compiles the expects with the dev backend
+    fn run_expect_test_dev(source: &str, expected: &str) {
+        run_expect_test_help(source, expected, true)
+    }
+
+    fn run_expect_test_help(source: &str, expected: &str, dev_backend: bool) {
         let arena = bumpalo::Bump::new();
         let arena = &arena;
 
@@ -124,6 +135,10 @@ mod test {
             palette: DEFAULT_PALETTE,
             threading: Threading::Single,
             exec_mode: ExecutionMode::Test,
+            output: OutputConfig {
+                inline_level: opt_level.into(),
+                ..OutputConfig::default()
+            },
         };
         let loaded = match roc_load::load_and_monomorphize_from_str(
             arena,
@@ -146,9 +161,12 @@ mod test {
 
         let interns = loaded.interns.clone();
 
-        let (dy_lib, expects_by_module, layout_interner) =
+        let (dy_lib, expects_by_module, layout_interner) = if dev_backend {
+            expect_mono_module_to_dylib_dev(arena, target, loaded)
+        } else {
             expect_mono_module_to_dylib(arena, target, loaded, opt_level, LlvmBackendMode::CliTest)
-                .unwrap();
+        }
+        .unwrap();
 
         let arena = &bumpalo::Bump::new();
         let interns = arena.alloc(interns);
@@ -1248,4 +1266,178 @@ mod test {
             ),
         );
     }
+
+    #[test]
+    fn dev_lookup_list_of_strings() {
+        run_expect_test_dev(
+            indoc!(
+                r#"
+                app "test" provides [main] to "./platform"
+
+                main = 0
+
+                expect
+                    a = ["foo"]
+                    b = ["a string so long that it cannot be short", "bar"]
+
+                    a == b
+                "#
+            ),
+            indoc!(
+                r#"
+                This expectation failed:
+
+                5│>  expect
+                6│>      a = ["foo"]
+                7│>      b = ["a string so long that it cannot be short", "bar"]
+                8│>
+                9│>      a == b
+
+                When it failed, these variables had these values:
+
+                a : List Str
+                a = ["foo"]
+
+                b : List Str
+                b = ["a string so long that it cannot be short", "bar"]
+                "#
+            ),
+        );
+    }
+
+    #[test]
+    fn dev_result_with_strings() {
+        run_expect_test_dev(
+            indoc!(
+                r#"
+                app "test" provides [main] to "./platform"
+
+                main = 0
+
+                expect
+                    a = Ok "Astra mortemque praestare gradatim"
+                    b = Err "Profundum et fundamentum"
+
+                    a == b
+                "#
+            ),
+            indoc!(
+                r#"
+                This expectation failed:
+
+                5│>  expect
+                6│>      a = Ok "Astra mortemque praestare gradatim"
+                7│>      b = Err "Profundum et fundamentum"
+                8│>
+                9│>      a == b
+
+                When it failed, these variables had these values:
+
+                a : [
+                    Err Str,
+                    Ok Str,
+                ]
+                a = Ok "Astra mortemque praestare gradatim"
+
+                b : [
+                    Err Str,
+                    Ok Str,
+                ]
+                b = Err "Profundum et fundamentum"
+                "#
+            ),
+        );
+    }
+
+    #[test]
+    fn dev_recursive_tree() {
+        run_expect_test_dev(
+            indoc!(
+                r#"
+                app "test" provides [main] to "./platform"
+
+                main = 0
+
+                Tree a : [ Leaf a, Node (Tree a) (Tree a) ]
+
+                expect
+                    a : Tree Str
+                    a = Leaf "Astra mortemque praestare gradatim"
+
+                    b : Tree Str
+                    b = Node (Leaf "a") (Leaf "b")
+
+                    a == b
+                "#
+            ),
+            indoc!(
+                r#"
+                This expectation failed:
+
+                 7│>  expect
+                 8│>      a : Tree Str
+                 9│>      a = Leaf "Astra mortemque praestare gradatim"
+                10│>
+                11│>      b : Tree Str
+                12│>      b = Node (Leaf "a") (Leaf "b")
+                13│>
+                14│>      a == b
+
+                When it failed, these variables had these values:
+
+                a : Tree Str
+                a = Leaf "Astra mortemque praestare gradatim"
+
+                b : Tree Str
+                b = Node (Leaf "a") (Leaf "b")
+                "#
+            ),
+        );
+    }
+
+    #[test]
+    fn dev_nullable_tree() {
+        run_expect_test_dev(
+            indoc!(
+                r#"
+                app "test" provides [main] to "./platform"
+
+                main = 0
+
+                Tree a : [ Empty, Leaf a, Node (Tree a) (Tree a) ]
+
+                expect
+                    a : Tree Str
+                    a = Leaf "Astra mortemque praestare gradatim"
+
+                    b : Tree Str
+                    b = Node Empty (Leaf "b")
+
+                    a == b
+                "#
+            ),
+            indoc!(
+                r#"
+                This expectation failed:
+
+                 7│>  expect
+                 8│>      a : Tree Str
+                 9│>      a = Leaf "Astra mortemque praestare gradatim"
+                10│>
+                11│>      b : Tree Str
+                12│>      b = Node Empty (Leaf "b")
+                13│>
+                14│>      a == b
+
+                When it failed, these variables had these values:
+
+                a : Tree Str
+                a = Leaf "Astra mortemque praestare gradatim"
+
+                b : Tree Str
+                b = Node Empty (Leaf "b")
+                "#
+            ),
+        );
+    }
 }
@@ -9,7 +9,7 @@ use std::{
 use bumpalo::collections::Vec as BumpVec;
 use bumpalo::Bump;
 use inkwell::context::Context;
-use roc_build::link::llvm_module_to_dylib;
+use roc_build::link::{dev_object_to_dylib, llvm_module_to_dylib};
 use roc_can::expr::ExpectLookup;
 use roc_collections::{MutMap, MutSet, VecMap};
 use roc_error_macros::internal_error;
@@ -21,7 +21,7 @@ use roc_gen_llvm::{
 use roc_load::{Expectations, MonomorphizedModule};
 use roc_module::symbol::{Interns, ModuleId, Symbol};
 use roc_mono::{
-    ir::OptLevel,
+    ir::{CrashTag, OptLevel},
     layout::{GlobalLayoutInterner, STLayoutInterner},
 };
 use roc_region::all::Region;
@@ -248,11 +248,9 @@ fn run_expect_pure<'a, W: std::io::Write>(
     shared_memory: &mut ExpectMemory,
     expect: ToplevelExpect<'_>,
 ) -> std::io::Result<bool> {
-    use roc_gen_llvm::try_run_jit_function;
-
     let sequence = ExpectSequence::new(shared_memory.ptr.cast());
 
-    let result: Result<(), (String, _)> = try_run_jit_function!(lib, expect.name, (), |v: ()| v);
+    let result = expect.run(lib);
 
     let shared_memory_ptr: *const u8 = shared_memory.ptr.cast();
 
@@ -314,18 +312,13 @@ fn run_expect_fx<'a, W: std::io::Write>(
         0 => unsafe {
             // we are the child
 
-            use roc_gen_llvm::try_run_jit_function;
-
             let mut child_memory = parent_memory.reuse_mmap().unwrap();
 
             let sequence = ExpectSequence::new(child_memory.ptr);
 
             child_memory.set_shared_buffer(lib);
 
-            let result: Result<(), (String, _)> =
-                try_run_jit_function!(lib, expect.name, (), |v: ()| v);
-
-            if let Err((msg, _)) = result {
+            if let Err((msg, _)) = expect.run(lib) {
                 internal_error!("roc panic {msg}");
             }
 
@@ -597,11 +590,40 @@ impl ExpectFrame {
     }
 }
 
+/// The backend that compiled the expects, which determines how their results are returned
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExpectBackend {
+    /// The caller passes a pointer that the `RocCallResult` is written into
+    Llvm,
+    /// The `RocCallResult` is returned by value
+    Dev,
+}
+
 #[derive(Debug, Clone, Copy)]
 pub struct ToplevelExpect<'a> {
     pub name: &'a str,
     pub symbol: Symbol,
     pub region: Region,
+    pub backend: ExpectBackend,
+}
+
+impl ToplevelExpect<'_> {
+    fn run(&self, lib: &libloading::Library) -> Result<(), (String, CrashTag)> {
+        match self.backend {
+            ExpectBackend::Llvm => {
+                use roc_gen_llvm::try_run_jit_function;
+
+                try_run_jit_function!(lib, self.name, (), |v: ()| v)
+            }
+            ExpectBackend::Dev => unsafe {
+                let main: libloading::Symbol<unsafe extern "C" fn() -> RocCallResult<()>> = lib
+                    .get(self.name.as_bytes())
+                    .unwrap_or_else(|_| internal_error!("expect `{}` is not defined", self.name));
+
+                main().into()
+            },
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -657,6 +679,8 @@ pub fn expect_mono_module_to_dylib<'a>(
         mode,
         // important! we don't want any procedures to get the C calling convention
         exposed_to_host: MutSet::default(),
+        debug_sources: None,
+        instrument_memory: None,
     };
 
     // Add roc_alloc, roc_realloc, and roc_dealloc, since the repl has no
@@ -702,6 +726,7 @@ pub fn expect_mono_module_to_dylib<'a>(
                     symbol,
                     region,
                     name,
+                    backend: ExpectBackend::Llvm,
                 }),
             env.arena,
         );
@@ -713,6 +738,7 @@ pub fn expect_mono_module_to_dylib<'a>(
                         symbol,
                         region,
                         name,
+                        backend: ExpectBackend::Llvm,
                     },
                 ),
                 env.arena,
@@ -753,3 +779,69 @@ pub fn expect_mono_module_to_dylib<'a>(
     llvm_module_to_dylib(env.module, target, opt_level)
         .map(|dy_lib| (dy_lib, modules_expects, layout_interner))
 }
+
+/// Like `expect_mono_module_to_dylib`, but compiles the expects with the dev backend.
+pub fn expect_mono_module_to_dylib_dev<'a>(
+    arena: &'a Bump,
+    target: Target,
+    loaded: MonomorphizedModule<'a>,
+) -> Result<
+    (
+        libloading::Library,
+        MutMap<ModuleId, ExpectFunctions<'a>>,
+        STLayoutInterner<'a>,
+    ),
+    libloading::Error,
+> {
+    let MonomorphizedModule {
+        module_id,
+        toplevel_expects,
+        procedures,
+        mut interns,
+        mut layout_interner,
+        ..
+    } = loaded;
+
+    let exposed_to_host = toplevel_expects
+        .values()
+        .flat_map(|expects| expects.pure.keys().chain(expects.fx.keys()))
+        .copied()
+        .collect();
+
+    let env = roc_gen_dev::Env {
+        arena,
+        module_id,
+        exposed_to_host,
+        lazy_literals: true,
+        mode: roc_gen_dev::AssemblyBackendMode::CliTest,
+        register_allocation: roc_gen_dev::RegisterAllocation::default(),
+    };
+
+    let module_object =
+        roc_gen_dev::build_module(&env, &mut interns, &mut layout_interner, target, procedures);
+
+    let module_out = module_object
+        .write()
+        .expect("failed to build output object");
+
+    let mut modules_expects: MutMap<ModuleId, ExpectFunctions> = MutMap::default();
+
+    for (module_id, expects) in toplevel_expects.into_iter() {
+        let to_toplevel_expect = |(symbol, region): (Symbol, Region)| ToplevelExpect {
+            name: arena.alloc_str(&roc_gen_dev::expect_wrapper_name(symbol, &interns)),
+            symbol,
+            region,
+            backend: ExpectBackend::Dev,
+        };
+
+        let expect_funs = ExpectFunctions {
+            pure: BumpVec::from_iter_in(expects.pure.into_iter().map(to_toplevel_expect), arena),
+            fx: BumpVec::from_iter_in(expects.fx.into_iter().map(to_toplevel_expect), arena),
+        };
+
+        modules_expects.insert(module_id, expect_funs);
+    }
+
+    dev_object_to_dylib(&module_out, target)
+        .map(|dy_lib| (dy_lib, modules_expects, layout_interner))
+}
@@ -259,6 +259,7 @@ async fn eval_wasm<'a>(
                 .keys()
                 .copied()
                 .collect::<MutSet<_>>(),
+            debug_sources: None,
         };
 
         let (mut module, mut called_fns, main_fn_index) = {
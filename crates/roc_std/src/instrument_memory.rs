@@ -0,0 +1,307 @@
+//! Host-side hooks for apps built with `roc build --instrument-memory`.
+//!
+//! An instrumented app reports every allocation, reallocation and deallocation, and every
+//! refcount increment and decrement, to the `roc_memory_on_*` functions defined here, along with
+//! the Roc function it happened in. Link this into the host (by enabling the `instrument-memory`
+//! feature) and call [print_report] before the host exits to see which Roc functions allocate
+//! the most, and which allocations were never freed.
+//!
+//! The host's own allocations and frees through roc_std's types (like dropping a [RocStr] the
+//! app returned) are reported too, with `<host>` as their site.
+//!
+//! [RocStr]: crate::RocStr
+use core::ffi::{c_char, c_void, CStr};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::Mutex;
+
+/// What happened at one site, i.e. in one Roc function
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SiteSummary {
+    pub site: String,
+    pub allocations: usize,
+    pub reallocations: usize,
+    pub deallocations: usize,
+    pub bytes_allocated: usize,
+    pub increments: usize,
+    pub decrements: usize,
+}
+
+/// An allocation that has not been freed
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Leak {
+    pub address: usize,
+    pub size: usize,
+    /// The site that allocated it
+    pub allocated_at: String,
+    /// The site that last changed its refcount, if any did
+    pub last_changed_at: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MemoryReport {
+    /// Sorted by the number of bytes allocated, most first
+    pub sites: Vec<SiteSummary>,
+    /// Sorted by address
+    pub leaks: Vec<Leak>,
+}
+
+/// Sites are pointers to NUL-terminated strings in the app's read-only data, so we keep them
+/// as addresses and only read them when making a report.
+type Site = usize;
+
+struct Allocation {
+    size: usize,
+    allocated_at: Site,
+    last_changed_at: Option<Site>,
+}
+
+#[derive(Default)]
+struct SiteStats {
+    allocations: usize,
+    reallocations: usize,
+    deallocations: usize,
+    bytes_allocated: usize,
+    increments: usize,
+    decrements: usize,
+}
+
+struct State {
+    /// Live allocations, by start address
+    live: BTreeMap<usize, Allocation>,
+    sites: BTreeMap<Site, SiteStats>,
+}
+
+static STATE: Mutex<State> = Mutex::new(State {
+    live: BTreeMap::new(),
+    sites: BTreeMap::new(),
+});
+
+impl State {
+    fn site(&mut self, site: *const c_char) -> &mut SiteStats {
+        self.sites.entry(site as Site).or_default()
+    }
+
+    /// The live allocation that `ptr` points into (or just past the end of, like the data
+    /// pointer of an empty list with capacity)
+    fn containing(&mut self, ptr: *const c_void) -> Option<&mut Allocation> {
+        let address = ptr as usize;
+        let (start, allocation) = self.live.range_mut(..=address).next_back()?;
+
+        (address <= start + allocation.size).then_some(allocation)
+    }
+}
+
+fn with_state<T>(f: impl FnOnce(&mut State) -> T) -> T {
+    // Keep counting even if a panic poisoned the lock; the counts are still consistent.
+    let mut state = STATE
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+
+    f(&mut state)
+}
+
+fn site_name(site: Site) -> String {
+    if site == 0 {
+        // roc_std reporting for the host, or the host calling into the app before any Roc ran
+        String::from("<host>")
+    } else {
+        unsafe { CStr::from_ptr(site as *const c_char) }
+            .to_string_lossy()
+            .into_owned()
+    }
+}
+
+/// # Safety
+/// Called by instrumented Roc apps; `site` must be null or a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn roc_memory_on_alloc(
+    ptr: *mut c_void,
+    size: usize,
+    _alignment: u32,
+    site: *const c_char,
+) {
+    with_state(|state| {
+        let stats = state.site(site);
+        stats.allocations += 1;
+        stats.bytes_allocated += size;
+
+        state.live.insert(
+            ptr as usize,
+            Allocation {
+                size,
+                allocated_at: site as Site,
+                last_changed_at: None,
+            },
+        );
+    })
+}
+
+/// # Safety
+/// Called by instrumented Roc apps; `site` must be null or a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn roc_memory_on_realloc(
+    old_ptr: *mut c_void,
+    new_ptr: *mut c_void,
+    new_size: usize,
+    site: *const c_char,
+) {
+    with_state(|state| {
+        let stats = state.site(site);
+        stats.reallocations += 1;
+        stats.bytes_allocated += new_size;
+
+        let allocation = match state.live.remove(&(old_ptr as usize)) {
+            Some(old) => Allocation {
+                size: new_size,
+                ..old
+            },
+            None => Allocation {
+                size: new_size,
+                allocated_at: site as Site,
+                last_changed_at: None,
+            },
+        };
+
+        state.live.insert(new_ptr as usize, allocation);
+    })
+}
+
+/// # Safety
+/// Called by instrumented Roc apps; `site` must be null or a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn roc_memory_on_dealloc(ptr: *mut c_void, site: *const c_char) {
+    with_state(|state| {
+        state.site(site).deallocations += 1;
+        state.live.remove(&(ptr as usize));
+    })
+}
+
+/// # Safety
+/// Called by instrumented Roc apps; `site` must be null or a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn roc_memory_on_inc(ptr: *mut c_void, _amount: isize, site: *const c_char) {
+    with_state(|state| {
+        state.site(site).increments += 1;
+
+        // Constants live in read-only memory rather than in an allocation
+        if let Some(allocation) = state.containing(ptr) {
+            allocation.last_changed_at = Some(site as Site);
+        }
+    })
+}
+
+/// # Safety
+/// Called by instrumented Roc apps; `site` must be null or a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn roc_memory_on_dec(ptr: *mut c_void, site: *const c_char) {
+    if ptr.is_null() {
+        return;
+    }
+
+    with_state(|state| {
+        state.site(site).decrements += 1;
+
+        if let Some(allocation) = state.containing(ptr) {
+            allocation.last_changed_at = Some(site as Site);
+        }
+    })
+}
+
+/// Summarize everything reported so far. Anything still allocated counts as a leak, so call this
+/// once the host is done with all the values it got from Roc.
+pub fn report() -> MemoryReport {
+    with_state(|state| {
+        let mut sites: Vec<_> = state
+            .sites
+            .iter()
+            .map(|(site, stats)| SiteSummary {
+                site: site_name(*site),
+                allocations: stats.allocations,
+                reallocations: stats.reallocations,
+                deallocations: stats.deallocations,
+                bytes_allocated: stats.bytes_allocated,
+                increments: stats.increments,
+                decrements: stats.decrements,
+            })
+            .collect();
+        sites.sort_by(|a, b| {
+            b.bytes_allocated
+                .cmp(&a.bytes_allocated)
+                .then_with(|| a.site.cmp(&b.site))
+        });
+
+        let leaks = state
+            .live
+            .iter()
+            .map(|(address, allocation)| Leak {
+                address: *address,
+                size: allocation.size,
+                allocated_at: site_name(allocation.allocated_at),
+                last_changed_at: allocation.last_changed_at.map(site_name),
+            })
+            .collect();
+
+        MemoryReport { sites, leaks }
+    })
+}
+
+/// Print [report] to stderr
+pub fn print_report() {
+    eprint!("{}", report());
+}
+
+impl fmt::Display for MemoryReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "── MEMORY REPORT ──")?;
+        writeln!(f)?;
+        writeln!(
+            f,
+            "{:>8} {:>8} {:>8} {:>12} {:>8} {:>8}  site",
+            "allocs", "reallocs", "frees", "bytes", "incs", "decs"
+        )?;
+
+        for site in &self.sites {
+            writeln!(
+                f,
+                "{:>8} {:>8} {:>8} {:>12} {:>8} {:>8}  {}",
+                site.allocations,
+                site.reallocations,
+                site.deallocations,
+                site.bytes_allocated,
+                site.increments,
+                site.decrements,
+                site.site
+            )?;
+        }
+
+        writeln!(f)?;
+
+        if self.leaks.is_empty() {
+            return writeln!(f, "All allocations were freed.");
+        }
+
+        let bytes: usize = self.leaks.iter().map(|leak| leak.size).sum();
+        writeln!(
+            f,
+            "{} allocation(s) totalling {} bytes were never freed:",
+            self.leaks.len(),
+            bytes
+        )?;
+
+        for leak in &self.leaks {
+            write!(
+                f,
+                "  {:#x} ({} bytes) allocated in {}",
+                leak.address, leak.size, leak.allocated_at
+            )?;
+
+            match &leak.last_changed_at {
+                Some(site) => writeln!(f, ", refcount last changed in {site}")?,
+                None => writeln!(f)?,
+            }
+        }
+
+        Ok(())
+    }
+}
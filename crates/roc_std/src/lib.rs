@@ -11,6 +11,8 @@ use core::mem::{ManuallyDrop, MaybeUninit};
 use core::ops::Drop;
 use core::str;
 
+#[cfg(feature = "instrument-memory")]
+pub mod instrument_memory;
 mod roc_box;
 mod roc_dict;
 mod roc_list;
@@ -40,6 +42,38 @@ extern "C" {
     pub fn roc_memset(dst: *mut c_void, c: i32, n: usize) -> *mut c_void;
 }
 
+// roc_std allocates and frees memory on behalf of the host through these, so that an app built
+// with `--instrument-memory` also sees what the host does with the values it gets from Roc.
+pub(crate) unsafe fn host_alloc(size: usize, alignment: u32) -> *mut c_void {
+    let ptr = roc_alloc(size, alignment);
+
+    #[cfg(feature = "instrument-memory")]
+    instrument_memory::roc_memory_on_alloc(ptr, size, alignment, core::ptr::null());
+
+    ptr
+}
+
+pub(crate) unsafe fn host_realloc(
+    ptr: *mut c_void,
+    new_size: usize,
+    old_size: usize,
+    alignment: u32,
+) -> *mut c_void {
+    let new_ptr = roc_realloc(ptr, new_size, old_size, alignment);
+
+    #[cfg(feature = "instrument-memory")]
+    instrument_memory::roc_memory_on_realloc(ptr, new_ptr, new_size, core::ptr::null());
+
+    new_ptr
+}
+
+pub(crate) unsafe fn host_dealloc(ptr: *mut c_void, alignment: u32) {
+    #[cfg(feature = "instrument-memory")]
+    instrument_memory::roc_memory_on_dealloc(ptr, core::ptr::null());
+
+    roc_dealloc(ptr, alignment)
+}
+
 pub fn roc_alloc_refcounted<T>() -> *mut T {
     let size = core::mem::size_of::<T>();
     let align = core::mem::align_of::<T>();
@@ -53,7 +87,7 @@ fn roc_alloc_refcounted_help(mut size: usize, mut align: usize) -> *mut u8 {
     align = align.max(core::mem::size_of::<crate::Storage>());
 
     unsafe {
-        let allocation_ptr = roc_alloc(size, align as _) as *mut u8;
+        let allocation_ptr = host_alloc(size, align as _) as *mut u8;
         let data_ptr = allocation_ptr.add(prefix);
         let storage_ptr = (data_ptr as *mut crate::Storage).sub(1);
 
@@ -1,6 +1,6 @@
 #![deny(unsafe_op_in_unsafe_fn)]
 
-use crate::{roc_alloc, roc_dealloc, storage::Storage};
+use crate::{host_alloc, host_dealloc, storage::Storage};
 use core::{
     cell::Cell,
     cmp::{self, Ordering},
@@ -20,7 +20,7 @@ impl<T> RocBox<T> {
         let alignment = Self::alloc_alignment();
         let bytes = mem::size_of::<T>() + alignment;
 
-        let ptr = unsafe { roc_alloc(bytes, alignment as u32) };
+        let ptr = unsafe { host_alloc(bytes, alignment as u32) };
 
         if ptr.is_null() {
             todo!("Call roc_panic with the info that an allocation failed.");
@@ -170,7 +170,7 @@ impl<T> Drop for RocBox<T> {
                 let alignment = Self::alloc_alignment();
 
                 // Release the memory.
-                roc_dealloc(
+                host_dealloc(
                     contents.as_ptr().cast::<u8>().sub(alignment).cast(),
                     alignment as u32,
                 );
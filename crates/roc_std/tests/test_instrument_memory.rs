@@ -0,0 +1,103 @@
+#![allow(clippy::missing_safety_doc)]
+
+extern crate roc_std;
+
+use core::ffi::{c_char, c_void};
+use roc_std::instrument_memory::*;
+use roc_std::RocStr;
+
+#[no_mangle]
+pub unsafe extern "C" fn roc_alloc(size: usize, _alignment: u32) -> *mut c_void {
+    libc::malloc(size)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn roc_realloc(
+    c_ptr: *mut c_void,
+    new_size: usize,
+    _old_size: usize,
+    _alignment: u32,
+) -> *mut c_void {
+    libc::realloc(c_ptr, new_size)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn roc_dealloc(c_ptr: *mut c_void, _alignment: u32) {
+    libc::free(c_ptr)
+}
+
+// The hooks record into global state, so everything is checked in one test.
+#[test]
+fn report_sites_and_leaks() {
+    let main = b"Main.main (Main.roc:3:1)\0".as_ptr() as *const c_char;
+    let helper = b"Main.helper (Main.roc:8:1)\0".as_ptr() as *const c_char;
+
+    let mut freed = [0u64; 4];
+    let mut leaked = [0u64; 8];
+    let freed_ptr = freed.as_mut_ptr() as *mut c_void;
+    let leaked_ptr = leaked.as_mut_ptr() as *mut c_void;
+
+    unsafe {
+        roc_memory_on_alloc(freed_ptr, 32, 8, main);
+        roc_memory_on_alloc(leaked_ptr, 64, 8, helper);
+
+        // refcounts are reported with pointers into the allocation
+        roc_memory_on_inc(leaked_ptr.add(8), 1, main);
+        roc_memory_on_dec(freed_ptr.add(8), helper);
+        roc_memory_on_dec(core::ptr::null_mut(), helper);
+
+        roc_memory_on_dealloc(freed_ptr, helper);
+    }
+
+    let report = report();
+
+    assert_eq!(
+        report.sites,
+        vec![
+            SiteSummary {
+                site: "Main.helper (Main.roc:8:1)".to_string(),
+                allocations: 1,
+                deallocations: 1,
+                bytes_allocated: 64,
+                decrements: 1,
+                ..Default::default()
+            },
+            SiteSummary {
+                site: "Main.main (Main.roc:3:1)".to_string(),
+                allocations: 1,
+                bytes_allocated: 32,
+                increments: 1,
+                ..Default::default()
+            },
+        ]
+    );
+
+    assert_eq!(
+        report.leaks,
+        vec![Leak {
+            address: leaked_ptr as usize,
+            size: 64,
+            allocated_at: "Main.helper (Main.roc:8:1)".to_string(),
+            last_changed_at: Some("Main.main (Main.roc:3:1)".to_string()),
+        }]
+    );
+
+    assert!(report
+        .to_string()
+        .contains("1 allocation(s) totalling 64 bytes were never freed"));
+
+    // Values the host allocates and frees through roc_std are reported too, so a value the app
+    // returned is not a leak once the host drops it
+    drop(RocStr::from(
+        "too long to be stored inside the RocStr itself",
+    ));
+
+    let after = roc_std::instrument_memory::report();
+    let host = after
+        .sites
+        .iter()
+        .find(|site| site.site == "<host>")
+        .unwrap();
+    assert_eq!((host.allocations, host.deallocations), (1, 1));
+    assert_eq!(after.leaks, report.leaks);
+}
@@ -13,8 +13,12 @@
 //! Tracing is only turned on in debug builds. Use the provided [setup_tracing] macro to turn on
 //! tracing at an executable's entry point.
 //!
+//! For measuring where build time goes (e.g. `roc build --time-trace`), see [profile].
+//!
 //! [directive-syntax]: https://docs.rs/tracing-subscriber/latest/tracing_subscriber/filter/struct.EnvFilter.html#directives
 
+pub mod profile;
+
 /// Sets up tracing of a Roc executable. The value of this macro must be bound to a variable that
 /// is not dropped until tracing has completed.
 ///
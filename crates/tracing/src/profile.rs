@@ -0,0 +1,357 @@
+//! A profile of where the compiler spends its time, written in the Chrome trace event format.
+//!
+//! Unlike the rest of this crate, profiling also works in release builds, because that is where
+//! build times matter. It is off until [enable] is called, and every [span] is a no-op until then.
+//!
+//! The resulting file can be opened in `chrome://tracing`, [Perfetto](https://ui.perfetto.dev)
+//! or [Speedscope](https://www.speedscope.app). See the [format documentation] for details.
+//!
+//! [format documentation]: https://docs.google.com/document/d/1CvAClvFfyA5R-PhYUmn5OOQtYMH4h6I0nSsKchNAySU
+
+use serde_json::{json, Map, Value};
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::io;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::Instant;
+
+static PROFILE: OnceLock<Profile> = OnceLock::new();
+
+static NEXT_THREAD_ID: AtomicU64 = AtomicU64::new(0);
+
+thread_local! {
+    static THREAD_ID: Cell<Option<u64>> = Cell::new(None);
+}
+
+struct Profile {
+    start: Instant,
+    events: Mutex<Vec<Event>>,
+    /// Replacements for span labels, e.g. from a module's id to its name
+    labels: Mutex<HashMap<String, String>>,
+}
+
+enum Event {
+    Span {
+        category: &'static str,
+        name: &'static str,
+        label: Option<String>,
+        args: Vec<(&'static str, String)>,
+        thread: u64,
+        start_us: f64,
+        duration_us: f64,
+    },
+    ThreadName {
+        thread: u64,
+        name: String,
+    },
+}
+
+/// Start recording spans. Calling this more than once has no further effect.
+pub fn enable() {
+    PROFILE.get_or_init(|| Profile {
+        start: Instant::now(),
+        events: Mutex::new(Vec::new()),
+        labels: Mutex::new(HashMap::new()),
+    });
+}
+
+pub fn is_enabled() -> bool {
+    PROFILE.get().is_some()
+}
+
+/// Measure the time until the returned [Span] is dropped.
+///
+/// The category groups related spans (e.g. `"load"` or `"codegen"`), and the name says what
+/// happened within it (e.g. `"solve"`).
+pub fn span(category: &'static str, name: &'static str) -> Span {
+    Span {
+        active: PROFILE.get().map(|profile| ActiveSpan {
+            profile,
+            category,
+            name,
+            label: None,
+            args: Vec::new(),
+            start: Instant::now(),
+        }),
+    }
+}
+
+/// Replace span labels when the trace is written.
+///
+/// Useful for labels that are cheap to compute while a span is running, but not very readable.
+pub fn set_labels(labels: impl IntoIterator<Item = (String, String)>) {
+    if let Some(profile) = PROFILE.get() {
+        profile.labels.lock().unwrap().extend(labels);
+    }
+}
+
+/// A running span, which is recorded when dropped.
+#[must_use = "a span measures the time until it is dropped"]
+pub struct Span {
+    active: Option<ActiveSpan>,
+}
+
+struct ActiveSpan {
+    profile: &'static Profile,
+    category: &'static str,
+    name: &'static str,
+    label: Option<String>,
+    args: Vec<(&'static str, String)>,
+    start: Instant,
+}
+
+impl Span {
+    /// What the span worked on, e.g. a module. This is appended to the span's name in the trace.
+    pub fn label(mut self, label: impl Into<String>) -> Self {
+        if let Some(active) = &mut self.active {
+            active.label = Some(label.into());
+        }
+
+        self
+    }
+
+    pub fn arg(mut self, key: &'static str, value: impl ToString) -> Self {
+        if let Some(active) = &mut self.active {
+            active.args.push((key, value.to_string()));
+        }
+
+        self
+    }
+}
+
+impl Drop for Span {
+    fn drop(&mut self) {
+        let Some(active) = self.active.take() else {
+            return;
+        };
+
+        let end = Instant::now();
+        let profile = active.profile;
+        let start_us = micros(active.start.duration_since(profile.start));
+        let duration_us = micros(end.duration_since(active.start));
+
+        let (thread, new_thread_name) = current_thread();
+        let mut events = profile.events.lock().unwrap();
+
+        if let Some(name) = new_thread_name {
+            events.push(Event::ThreadName { thread, name });
+        }
+
+        events.push(Event::Span {
+            category: active.category,
+            name: active.name,
+            label: active.label,
+            args: active.args,
+            thread,
+            start_us,
+            duration_us,
+        });
+    }
+}
+
+fn micros(duration: std::time::Duration) -> f64 {
+    duration.as_secs_f64() * 1_000_000.0
+}
+
+/// The id of the current thread in the trace, and its name if this is its first event.
+fn current_thread() -> (u64, Option<String>) {
+    THREAD_ID.with(|id| match id.get() {
+        Some(thread) => (thread, None),
+        None => {
+            let thread = NEXT_THREAD_ID.fetch_add(1, Ordering::Relaxed);
+            let name = match std::thread::current().name() {
+                Some(name) => name.to_string(),
+                None => format!("thread {thread}"),
+            };
+
+            id.set(Some(thread));
+
+            (thread, Some(name))
+        }
+    })
+}
+
+/// Write everything recorded so far as a JSON trace file.
+pub fn write_trace(path: &Path) -> io::Result<()> {
+    let Some(profile) = PROFILE.get() else {
+        return Ok(());
+    };
+
+    let json = to_json(
+        &profile.events.lock().unwrap(),
+        &profile.labels.lock().unwrap(),
+        std::process::id(),
+    );
+
+    let file = io::BufWriter::new(std::fs::File::create(path)?);
+
+    serde_json::to_writer(file, &json)?;
+
+    Ok(())
+}
+
+fn to_json(events: &[Event], labels: &HashMap<String, String>, pid: u32) -> Value {
+    let trace_events: Vec<Value> = events
+        .iter()
+        .map(|event| match event {
+            Event::Span {
+                category,
+                name,
+                label,
+                args,
+                thread,
+                start_us,
+                duration_us,
+            } => {
+                let label = label
+                    .as_ref()
+                    .map(|label| labels.get(label).unwrap_or(label));
+
+                let full_name = match label {
+                    Some(label) => format!("{name} {label}"),
+                    None => name.to_string(),
+                };
+
+                let mut span_args = Map::new();
+
+                if let Some(label) = label {
+                    span_args.insert("label".into(), label.as_str().into());
+                }
+
+                for (key, value) in args {
+                    span_args.insert(key.to_string(), value.as_str().into());
+                }
+
+                json!({
+                    "ph": "X",
+                    "pid": pid,
+                    "tid": thread,
+                    "ts": start_us,
+                    "dur": duration_us,
+                    "cat": category,
+                    "name": full_name,
+                    "args": span_args,
+                })
+            }
+            Event::ThreadName { thread, name } => json!({
+                "ph": "M",
+                "pid": pid,
+                "tid": thread,
+                "name": "thread_name",
+                "args": { "name": name },
+            }),
+        })
+        .collect();
+
+    json!({
+        "displayTimeUnit": "ms",
+        "traceEvents": trace_events,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn trace_json() {
+        let events = [
+            Event::ThreadName {
+                thread: 0,
+                name: "main".to_string(),
+            },
+            Event::Span {
+                category: "load",
+                name: "solve",
+                label: Some("ModuleId(42)".to_string()),
+                args: vec![("note", "a \"quoted\"\nvalue".to_string())],
+                thread: 0,
+                start_us: 1.5,
+                duration_us: 20.25,
+            },
+            Event::Span {
+                category: "codegen",
+                name: "llvm",
+                label: None,
+                args: Vec::new(),
+                thread: 1,
+                start_us: 30.0,
+                duration_us: 4.0,
+            },
+        ];
+        let labels = HashMap::from([("ModuleId(42)".to_string(), "Main".to_string())]);
+
+        let written = serde_json::to_string(&to_json(&events, &labels, 7)).unwrap();
+        let parsed: Value = serde_json::from_str(&written).unwrap();
+
+        assert_eq!(
+            parsed,
+            json!({
+                "displayTimeUnit": "ms",
+                "traceEvents": [
+                    {
+                        "ph": "M",
+                        "pid": 7,
+                        "tid": 0,
+                        "name": "thread_name",
+                        "args": { "name": "main" },
+                    },
+                    {
+                        "ph": "X",
+                        "pid": 7,
+                        "tid": 0,
+                        "ts": 1.5,
+                        "dur": 20.25,
+                        "cat": "load",
+                        "name": "solve Main",
+                        "args": { "label": "Main", "note": "a \"quoted\"\nvalue" },
+                    },
+                    {
+                        "ph": "X",
+                        "pid": 7,
+                        "tid": 1,
+                        "ts": 30.0,
+                        "dur": 4.0,
+                        "cat": "codegen",
+                        "name": "llvm",
+                        "args": {},
+                    },
+                ],
+            })
+        );
+    }
+
+    #[test]
+    fn spans_are_recorded_once_enabled() {
+        drop(span("test", "before"));
+        enable();
+
+        drop(span("test", "recorded").label("a label").arg("count", 3));
+
+        let events = PROFILE.get().unwrap().events.lock().unwrap();
+        let names: Vec<_> = events
+            .iter()
+            .filter_map(|event| match event {
+                Event::Span {
+                    category: "test",
+                    name,
+                    label,
+                    args,
+                    ..
+                } => Some((*name, label.clone(), args.clone())),
+                _ => None,
+            })
+            .collect();
+
+        assert_eq!(
+            names,
+            [(
+                "recorded",
+                Some("a label".to_string()),
+                vec![("count", "3".to_string())]
+            )]
+        );
+    }
+}
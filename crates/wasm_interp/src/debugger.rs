@@ -0,0 +1,295 @@
+use roc_wasm_module::opcodes::OpCode;
+use roc_wasm_module::parse::{Parse, SkipBytes};
+use roc_wasm_module::{ExportType, Value, ValueType, WasmModule};
+use std::fmt::Write;
+
+use crate::{CallError, ImportDispatcher, Instance};
+
+/// Size of a RocList or RocStr in wasm32 memory: elements pointer, length and capacity
+pub const ROC_LIST_SIZE: u32 = 12;
+
+/// Place to stop execution
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Breakpoint {
+    /// Entry to a function, by function index
+    Function(u32),
+    /// An instruction at a file offset
+    Address(u32),
+}
+
+/// How far to run when resuming execution
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Resume {
+    /// Run until a breakpoint or the end of the program
+    Continue,
+    /// Execute one instruction, stepping into calls
+    Step,
+    /// Execute one instruction, stepping over calls
+    Next,
+    /// Run until the current function returns
+    Finish,
+}
+
+/// Why execution stopped
+#[derive(Debug, Clone, PartialEq)]
+pub enum StopReason {
+    /// Reached the breakpoint with this number
+    Breakpoint(usize),
+    /// Completed a `Step`, `Next` or `Finish`
+    Step,
+    /// The entry function returned, with this value
+    Exit(Option<Value>),
+    /// The program trapped or exceeded a limit
+    Error(CallError),
+}
+
+/// Step debugger for an [`Instance`]
+///
+/// Start a call with [`Instance::start_export`], then drive it with [`Debugger::resume`].
+/// Breakpoints are numbered in the order they were created, and keep their numbers when others are deleted.
+#[derive(Debug, Default)]
+pub struct Debugger {
+    /// Each breakpoint, with the code section address it resolves to
+    breakpoints: Vec<Option<(Breakpoint, usize)>>,
+}
+
+impl Debugger {
+    pub fn new() -> Self {
+        Debugger {
+            breakpoints: Vec::new(),
+        }
+    }
+
+    /// Add a breakpoint at a function name or a file offset (`0x`-prefixed hex, or decimal).
+    /// Function names are resolved through the name section, falling back to exports.
+    /// Returns the breakpoint's number.
+    pub fn add_breakpoint(
+        &mut self,
+        module: &WasmModule<'_>,
+        location: &str,
+    ) -> Result<usize, String> {
+        let breakpoint = match parse_number(location) {
+            Some(file_offset) => Breakpoint::Address(file_offset),
+            None => Breakpoint::Function(find_function(module, location)?),
+        };
+        let code_addr = resolve_breakpoint(module, breakpoint)?;
+        self.breakpoints.push(Some((breakpoint, code_addr)));
+        Ok(self.breakpoints.len() - 1)
+    }
+
+    /// Delete a breakpoint by number. Returns false if there was no such breakpoint.
+    pub fn delete_breakpoint(&mut self, number: usize) -> bool {
+        match self.breakpoints.get_mut(number) {
+            Some(slot @ Some(_)) => {
+                *slot = None;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// All active breakpoints, with their numbers
+    pub fn breakpoints(&self) -> impl Iterator<Item = (usize, Breakpoint)> + '_ {
+        self.breakpoints
+            .iter()
+            .enumerate()
+            .filter_map(|(number, bp)| bp.map(|(breakpoint, _)| (number, breakpoint)))
+    }
+
+    fn breakpoint_at(&self, code_addr: usize) -> Option<usize> {
+        self.breakpoints
+            .iter()
+            .position(|bp| matches!(bp, Some((_, addr)) if *addr == code_addr))
+    }
+
+    /// Resume execution of a call started with [`Instance::start_export`]
+    pub fn resume<I: ImportDispatcher>(
+        &self,
+        inst: &mut Instance<'_, I>,
+        resume: Resume,
+    ) -> Result<StopReason, String> {
+        if !inst.is_running() {
+            return Err("The program is not running.".to_string());
+        }
+
+        let start_depth = inst.call_depth();
+        let mut is_first = true;
+        loop {
+            // Don't stop at the breakpoint we're resuming from
+            if !is_first {
+                if let Some(number) = self.breakpoint_at(inst.program_counter) {
+                    return Ok(StopReason::Breakpoint(number));
+                }
+            }
+            is_first = false;
+
+            if let Some(stop) = inst.debugger_step() {
+                return Ok(stop);
+            }
+
+            let depth = inst.call_depth();
+            let is_done = match resume {
+                Resume::Continue => false,
+                Resume::Step => true,
+                Resume::Next => depth <= start_depth,
+                Resume::Finish => depth < start_depth,
+            };
+            if is_done {
+                return Ok(StopReason::Step);
+            }
+        }
+    }
+}
+
+/// Parse a `0x`-prefixed hex number, or a decimal number
+pub fn parse_number(s: &str) -> Option<u32> {
+    match s.strip_prefix("0x") {
+        Some(hex) => u32::from_str_radix(hex, 16).ok(),
+        None => s.parse().ok(),
+    }
+}
+
+fn find_function(module: &WasmModule<'_>, name: &str) -> Result<u32, String> {
+    let from_names = module.names.function_names.iter().find(|(_, n)| *n == name);
+    let from_exports = || {
+        module
+            .export
+            .exports
+            .iter()
+            .find(|ex| ex.ty == ExportType::Func && ex.name == name)
+            .map(|ex| ex.index)
+    };
+    from_names
+        .map(|(index, _)| *index)
+        .or_else(from_exports)
+        .ok_or_else(|| format!("I couldn't find a function called '{name}'"))
+}
+
+/// Find the address in the code section where a breakpoint should stop
+fn resolve_breakpoint(module: &WasmModule<'_>, breakpoint: Breakpoint) -> Result<usize, String> {
+    let import_count = module.import.imports.len();
+    let code = &module.code;
+    match breakpoint {
+        Breakpoint::Function(fn_index) => {
+            let fn_index = fn_index as usize;
+            if fn_index < import_count {
+                let import = &module.import.imports[fn_index];
+                return Err(format!(
+                    "func[{fn_index}] is the imported function {}.{}, so it has no code to stop in",
+                    import.module, import.name
+                ));
+            }
+            Ok(function_body_start(module, fn_index - import_count))
+        }
+        Breakpoint::Address(file_offset) => {
+            let code_addr = (file_offset as usize)
+                .checked_sub(code.section_offset as usize)
+                .filter(|addr| *addr < code.bytes.len())
+                .ok_or_else(|| format!("{file_offset:#x} is not in the code section"))?;
+
+            // Check it's the start of an instruction, or the breakpoint would never be hit
+            let internal_fn_index = code
+                .function_offsets
+                .iter()
+                .rposition(|offset| *offset as usize <= code_addr)
+                .ok_or_else(|| format!("{file_offset:#x} is not in a function body"))?;
+            let mut cursor = function_body_start(module, internal_fn_index);
+            while cursor < code_addr {
+                OpCode::skip_bytes(&code.bytes, &mut cursor).map_err(|e| e.message)?;
+            }
+            if cursor == code_addr {
+                Ok(code_addr)
+            } else {
+                Err(format!(
+                    "{file_offset:#x} is not the start of an instruction"
+                ))
+            }
+        }
+    }
+}
+
+/// Address of the first instruction of a function, after its local variable declarations
+fn function_body_start(module: &WasmModule<'_>, internal_fn_index: usize) -> usize {
+    let bytes = &module.code.bytes;
+    let mut cursor = module.code.function_offsets[internal_fn_index] as usize;
+    let _body_size = u32::parse((), bytes, &mut cursor).unwrap();
+    let local_group_count = u32::parse((), bytes, &mut cursor).unwrap();
+    for _ in 0..local_group_count {
+        <(u32, ValueType)>::parse((), bytes, &mut cursor).unwrap();
+    }
+    cursor
+}
+
+/// The three fields of a RocList or RocStr, as stored in wasm32 memory
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RocListFields {
+    pub elements: u32,
+    pub length: u32,
+    pub capacity_or_alloc_ptr: u32,
+}
+
+impl RocListFields {
+    pub fn read(memory: &[u8], addr: u32) -> Result<Self, String> {
+        let bytes = read_memory(memory, addr, ROC_LIST_SIZE)?;
+        let field = |i: usize| u32::from_le_bytes(bytes[4 * i..][..4].try_into().unwrap());
+        Ok(RocListFields {
+            elements: field(0),
+            length: field(1),
+            capacity_or_alloc_ptr: field(2),
+        })
+    }
+}
+
+/// Read the bytes of a RocStr, whether it's a small string, a heap string or a seamless slice
+pub fn read_roc_str(memory: &[u8], addr: u32) -> Result<&[u8], String> {
+    let fields = RocListFields::read(memory, addr)?;
+    if (fields.capacity_or_alloc_ptr as i32) < 0 {
+        // Small string: the bytes are stored inline, with the length in the last byte
+        let bytes = read_memory(memory, addr, ROC_LIST_SIZE)?;
+        let length = bytes[ROC_LIST_SIZE as usize - 1] & 0x7f;
+        Ok(&bytes[..length as usize])
+    } else {
+        // The top bit of the length marks a seamless slice
+        let length = fields.length & 0x7fff_ffff;
+        read_memory(memory, fields.elements, length)
+    }
+}
+
+/// Read the elements of a RocList, as one byte slice per element
+pub fn read_roc_list(memory: &[u8], addr: u32, element_size: u32) -> Result<Vec<&[u8]>, String> {
+    if element_size == 0 {
+        return Err("List elements must have a non-zero size".to_string());
+    }
+    let fields = RocListFields::read(memory, addr)?;
+    let total_size = fields
+        .length
+        .checked_mul(element_size)
+        .ok_or_else(|| format!("List length {} is too large", fields.length))?;
+    let bytes = read_memory(memory, fields.elements, total_size)?;
+    Ok(bytes.chunks(element_size as usize).collect())
+}
+
+/// Format a region of memory as lines of 16 hex bytes, each prefixed with its address
+pub fn hex_dump(memory: &[u8], addr: u32, length: u32) -> Result<String, String> {
+    let bytes = read_memory(memory, addr, length)?;
+    let mut output = String::new();
+    for (i, line) in bytes.chunks(16).enumerate() {
+        write!(output, "{:08x}:", addr as usize + 16 * i).unwrap();
+        for byte in line {
+            write!(output, " {byte:02x}").unwrap();
+        }
+        writeln!(output).unwrap();
+    }
+    Ok(output)
+}
+
+fn read_memory(memory: &[u8], addr: u32, length: u32) -> Result<&[u8], String> {
+    let start = addr as usize;
+    memory.get(start..start + length as usize).ok_or_else(|| {
+        format!(
+            "Memory access out of bounds: {addr:#x}..{:#x}, but memory size is {:#x}",
+            start + length as usize,
+            memory.len()
+        )
+    })
+}
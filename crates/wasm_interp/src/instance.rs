@@ -4,13 +4,17 @@ use std::iter::{self, once, Iterator};
 
 use roc_wasm_module::opcodes::{MemoryInstruction, OpCode};
 use roc_wasm_module::parse::{Parse, SkipBytes};
-use roc_wasm_module::sections::{ImportDesc, MemorySection, SignatureParamsIter};
+use roc_wasm_module::sections::{
+    DataMode, ImportDesc, Limits as SectionLimits, MemorySection, SignatureParamsIter,
+};
 use roc_wasm_module::{ExportType, WasmModule};
 use roc_wasm_module::{Value, ValueType};
 
+use crate::debugger::StopReason;
 use crate::frame::Frame;
+use crate::profiler::Profile;
 use crate::value_store::ValueStore;
-use crate::{Error, ImportDispatcher};
+use crate::{CallError, Error, ImportDispatcher, Limits};
 
 #[derive(Debug)]
 pub enum Action {
@@ -52,6 +56,10 @@ pub struct Instance<'a, I: ImportDispatcher> {
     pub(crate) value_store: ValueStore<'a>,
     /// Values of any global variables
     pub(crate) globals: Vec<'a, Value>,
+    /// Contents of each data segment, for `memory.init`. Active segments are dropped after loading.
+    data_segments: Vec<'a, &'a [u8]>,
+    /// The function table, mapping element indices to function indices (function pointers)
+    table: Vec<'a, Option<u32>>,
     /// Index in the code section of the current instruction
     pub(crate) program_counter: usize,
     /// One entry per nested block. For loops, stores the address of the first instruction.
@@ -66,6 +74,12 @@ pub struct Instance<'a, I: ImportDispatcher> {
     import_arguments: Vec<'a, Value>,
     /// temporary storage for output using the --debug option
     debug_string: Option<String>,
+    /// Instruction counts, if profiling is enabled
+    profile: Option<Profile>,
+    /// Resource limits
+    limits: Limits,
+    /// Number of instructions left to execute, if limited
+    fuel: Option<u64>,
 }
 
 impl<'a, I: ImportDispatcher> Instance<'a, I> {
@@ -88,6 +102,8 @@ impl<'a, I: ImportDispatcher> Instance<'a, I> {
             previous_frames: Vec::new_in(arena),
             value_store: ValueStore::new(arena),
             globals: Vec::from_iter_in(globals, arena),
+            data_segments: Vec::new_in(arena),
+            table: Vec::new_in(arena),
             program_counter,
             blocks: Vec::new_in(arena),
             branch_cache: bumpalo::vec![in arena; bumpalo::vec![in arena]],
@@ -95,6 +111,9 @@ impl<'a, I: ImportDispatcher> Instance<'a, I> {
             import_dispatcher,
             import_arguments: Vec::new_in(arena),
             debug_string: Some(String::new()),
+            profile: None,
+            limits: Limits::default(),
+            fuel: None,
         }
     }
 
@@ -114,6 +133,22 @@ impl<'a, I: ImportDispatcher> Instance<'a, I> {
         module: &'a WasmModule<'a>,
         import_dispatcher: I,
         is_debug_mode: bool,
+    ) -> Result<Self, std::string::String> {
+        Self::for_module_with_limits(
+            arena,
+            module,
+            import_dispatcher,
+            is_debug_mode,
+            Limits::default(),
+        )
+    }
+
+    pub fn for_module_with_limits(
+        arena: &'a Bump,
+        module: &'a WasmModule<'a>,
+        import_dispatcher: I,
+        is_debug_mode: bool,
+        limits: Limits,
     ) -> Result<Self, std::string::String> {
         let mem_bytes = module.memory.min_bytes().map_err(|e| {
             format!(
@@ -121,8 +156,42 @@ impl<'a, I: ImportDispatcher> Instance<'a, I> {
                 e.offset, e.message
             )
         })?;
+        if let Some(max_pages) = limits.max_memory_pages {
+            let pages = mem_bytes / MemorySection::PAGE_SIZE;
+            if pages > max_pages {
+                return Err(format!(
+                    "This module needs {pages} pages of memory, but the limit is {max_pages} pages"
+                ));
+            }
+        }
         let mut memory = Vec::from_iter_in(iter::repeat(0).take(mem_bytes as usize), arena);
         module.data.load_into(&mut memory)?;
+        let data_segments = Vec::from_iter_in(
+            module
+                .data
+                .segments()?
+                .into_iter()
+                .map(|(mode, init)| match mode {
+                    DataMode::Passive => init,
+                    DataMode::Active { .. } => &[],
+                }),
+            arena,
+        );
+
+        let table = {
+            let table_size = match module.table.function_table.limits {
+                SectionLimits::Min(min) | SectionLimits::MinMax(min, _) => min,
+            }
+            .max(module.element.max_table_index());
+            let mut table = Vec::from_iter_in(iter::repeat(None).take(table_size as usize), arena);
+            for segment in module.element.segments.iter() {
+                let offset = segment.offset.unwrap_i32() as usize;
+                for (i, fn_index) in segment.fn_indices.iter().enumerate() {
+                    table[offset + i] = Some(*fn_index);
+                }
+            }
+            table
+        };
 
         let globals = module.global.initial_values(arena);
 
@@ -156,6 +225,8 @@ impl<'a, I: ImportDispatcher> Instance<'a, I> {
             previous_frames: Vec::new_in(arena),
             value_store,
             globals,
+            data_segments,
+            table,
             program_counter: usize::MAX,
             blocks: Vec::new_in(arena),
             branch_cache,
@@ -163,10 +234,49 @@ impl<'a, I: ImportDispatcher> Instance<'a, I> {
             import_dispatcher,
             import_arguments: Vec::new_in(arena),
             debug_string,
+            profile: None,
+            limits,
+            fuel: limits.fuel,
         })
     }
 
-    pub fn call_export<A>(&mut self, fn_name: &str, arg_values: A) -> Result<Option<Value>, String>
+    /// Count instructions and calls for every function executed from now on
+    pub fn enable_profiling(&mut self) {
+        let function_count = self.import_count + self.module.code.function_count as usize;
+        self.profile = Some(Profile::new(function_count));
+    }
+
+    /// The profile collected since [`enable_profiling`](Self::enable_profiling) was called
+    pub fn profile(&self) -> Option<&Profile> {
+        self.profile.as_ref()
+    }
+
+    /// Number of instructions left before the instance runs out of fuel, if limited
+    pub fn remaining_fuel(&self) -> Option<u64> {
+        self.fuel
+    }
+
+    /// Refuel the instance, or pass `None` to remove the limit
+    pub fn set_fuel(&mut self, fuel: Option<u64>) {
+        self.fuel = fuel;
+    }
+
+    pub fn call_export<A>(
+        &mut self,
+        fn_name: &str,
+        arg_values: A,
+    ) -> Result<Option<Value>, CallError>
+    where
+        A: IntoIterator<Item = Value>,
+    {
+        self.start_export(fn_name, arg_values)
+            .map_err(CallError::Failed)?;
+        self.run_to_end(self.module)
+    }
+
+    /// Set up a call to an exported function, but stop before executing its first instruction.
+    /// Use this with a [`Debugger`](crate::Debugger) to step through the function.
+    pub fn start_export<A>(&mut self, fn_name: &str, arg_values: A) -> Result<(), String>
     where
         A: IntoIterator<Item = Value>,
     {
@@ -184,7 +294,8 @@ impl<'a, I: ImportDispatcher> Instance<'a, I> {
             self.value_store.push(value);
         }
 
-        self.call_export_help_after_arg_load(self.module, fn_index, n_args, ret_type)
+        self.enter_export(self.module, fn_index, n_args, ret_type);
+        Ok(())
     }
 
     pub fn call_export_from_cli(
@@ -192,7 +303,19 @@ impl<'a, I: ImportDispatcher> Instance<'a, I> {
         module: &WasmModule<'a>,
         fn_name: &str,
         arg_strings: &'a [&'a [u8]],
-    ) -> Result<Option<Value>, String> {
+    ) -> Result<Option<Value>, CallError> {
+        self.start_export_from_cli(module, fn_name, arg_strings)
+            .map_err(CallError::Failed)?;
+        self.run_to_end(module)
+    }
+
+    /// Like [`call_export_from_cli`](Self::call_export_from_cli), but stop before executing the first instruction.
+    pub fn start_export_from_cli(
+        &mut self,
+        module: &WasmModule<'a>,
+        fn_name: &str,
+        arg_strings: &'a [&'a [u8]],
+    ) -> Result<(), String> {
         // We have two different mechanisms for handling CLI arguments!
         // 1. Basic numbers:
         //      e.g. `roc_wasm_interp fibonacci 12`
@@ -221,11 +344,18 @@ impl<'a, I: ImportDispatcher> Instance<'a, I> {
                 I64 => Value::I64(value_str.parse::<i64>().map_err(|e| e.to_string())?),
                 F32 => Value::F32(value_str.parse::<f32>().map_err(|e| e.to_string())?),
                 F64 => Value::F64(value_str.parse::<f64>().map_err(|e| e.to_string())?),
+                V128 => Value::V128(
+                    value_str
+                        .parse::<i128>()
+                        .map_err(|e| e.to_string())?
+                        .to_le_bytes(),
+                ),
             };
             self.value_store.push(value);
         }
 
-        self.call_export_help_after_arg_load(module, fn_index, n_args, ret_type)
+        self.enter_export(module, fn_index, n_args, ret_type);
+        Ok(())
     }
 
     fn call_export_help_before_arg_load<'m>(
@@ -266,6 +396,9 @@ impl<'a, I: ImportDispatcher> Instance<'a, I> {
 
         let internal_fn_index = fn_index - self.import_count;
 
+        // Discard any values left over from a previous call that was stopped by an error
+        self.value_store.truncate(0);
+
         self.program_counter = {
             let mut cursor = module.code.function_offsets[internal_fn_index] as usize;
             let _start_fn_byte_length = u32::parse((), &module.code.bytes, &mut cursor);
@@ -289,13 +422,13 @@ impl<'a, I: ImportDispatcher> Instance<'a, I> {
         Ok((fn_index, param_type_iter, return_type))
     }
 
-    fn call_export_help_after_arg_load(
+    fn enter_export(
         &mut self,
         module: &WasmModule<'a>,
         fn_index: usize,
         n_args: usize,
         return_type: Option<ValueType>,
-    ) -> Result<Option<Value>, String> {
+    ) {
         self.previous_frames.clear();
         self.blocks.clear();
         self.blocks.push(Block {
@@ -316,7 +449,12 @@ impl<'a, I: ImportDispatcher> Instance<'a, I> {
             ty: BlockType::FunctionBody(fn_index),
             vstack: self.value_store.depth(),
         });
+        if let Some(profile) = self.profile.as_mut() {
+            profile.enter_export(fn_index);
+        }
+    }
 
+    fn run_to_end(&mut self, module: &WasmModule<'a>) -> Result<Option<Value>, CallError> {
         loop {
             match self.execute_next_instruction(module) {
                 Ok(Action::Continue) => {}
@@ -324,24 +462,117 @@ impl<'a, I: ImportDispatcher> Instance<'a, I> {
                     break;
                 }
                 Err(e) => {
-                    let file_offset = self.program_counter + module.code.section_offset as usize;
-                    let mut message = e.to_string_at(file_offset);
-                    self.debug_stack_trace(&mut message).unwrap();
-                    return Err(message);
+                    return Err(self.call_error(module, e));
                 }
             };
         }
 
-        let return_value = if !self.value_store.is_empty() {
+        Ok(self.take_return_value())
+    }
+
+    fn take_return_value(&mut self) -> Option<Value> {
+        if !self.value_store.is_empty() {
             Some(self.value_store.pop())
         } else {
             None
-        };
+        }
+    }
+
+    fn call_error(&self, module: &WasmModule<'a>, e: Error) -> CallError {
+        let file_offset = self.program_counter + module.code.section_offset as usize;
+        let mut message = e.to_string_at(file_offset);
+        self.debug_stack_trace(&mut message).unwrap();
+        match e.limit() {
+            Some(limit) => CallError::LimitExceeded(limit, message),
+            None => CallError::Failed(message),
+        }
+    }
+
+    /// Execute a single instruction on behalf of the debugger.
+    /// Returns a reason to stop if the program has finished.
+    pub(crate) fn debugger_step(&mut self) -> Option<StopReason> {
+        let module = self.module;
+        match self.execute_next_instruction(module) {
+            Ok(Action::Continue) => None,
+            Ok(Action::Break) => Some(StopReason::Exit(self.take_return_value())),
+            Err(e) => {
+                let error = self.call_error(module, e);
+                // The program can't continue after a trap, but keep the frames for inspection
+                self.blocks.clear();
+                Some(StopReason::Error(error))
+            }
+        }
+    }
+
+    /// Whether there is a function call in progress that can be resumed
+    pub fn is_running(&self) -> bool {
+        !self.blocks.is_empty()
+    }
 
-        Ok(return_value)
+    /// Number of call frames below the current one
+    pub fn call_depth(&self) -> usize {
+        self.previous_frames.len()
     }
 
-    fn fetch_immediate_u32(&mut self, module: &WasmModule<'a>) -> u32 {
+    /// Index of the function currently executing
+    pub fn current_function(&self) -> usize {
+        self.current_frame.fn_index
+    }
+
+    /// File offset of the next instruction to execute. Matches the addresses shown by `wasm-objdump`.
+    pub fn current_file_offset(&self) -> usize {
+        self.program_counter + self.module.code.section_offset as usize
+    }
+
+    /// Look up a function's name in the module's debug info
+    pub fn function_name(&self, fn_index: usize) -> Option<&'a str> {
+        self.module
+            .names
+            .function_names
+            .iter()
+            .find(|(idx, _)| *idx == fn_index as u32)
+            .map(|(_, name)| *name)
+    }
+
+    /// Arguments and local variables of the current function, in index order
+    pub fn locals(&self) -> &[Value] {
+        let Frame {
+            locals_start,
+            locals_count,
+            ..
+        } = self.current_frame;
+        &self.value_store.get_slice(locals_start)[..locals_count]
+    }
+
+    /// The current function's part of the stack machine's value stack, bottom first
+    pub fn value_stack(&self) -> &[Value] {
+        let Frame {
+            locals_start,
+            locals_count,
+            ..
+        } = self.current_frame;
+        self.value_store.get_slice(locals_start + locals_count)
+    }
+
+    /// Describe the next instruction to execute, e.g. `func[3] main at 0x00012a: I32ADD`
+    pub fn debug_location(&self) -> String {
+        let fn_index = self.current_frame.fn_index;
+        let name = self.function_name(fn_index).unwrap_or("");
+        let op_code = OpCode::from(self.module.code.bytes[self.program_counter]);
+        format!(
+            "func[{fn_index}] {name} at {:#08x}: {op_code:?}",
+            self.current_file_offset()
+        )
+    }
+
+    /// Stack trace of all the active call frames, with their locals and values
+    pub fn backtrace(&self) -> String {
+        let mut buffer = String::new();
+        self.debug_stack_trace(&mut buffer).unwrap();
+        buffer
+    }
+
+    pub(crate) fn fetch_immediate_u32(&mut self, module: &WasmModule<'a>) -> u32 {
         let x = u32::parse((), &module.code.bytes, &mut self.program_counter).unwrap();
         if let Some(debug_string) = self.debug_string.as_mut() {
             write!(debug_string, "{x} ").unwrap();
@@ -376,6 +607,10 @@ impl<'a, I: ImportDispatcher> Instance<'a, I> {
         self.blocks.truncate(new_block_len);
         self.program_counter = return_addr;
 
+        if let Some(profile) = self.profile.as_mut() {
+            profile.leave();
+        }
+
         // self.debug_values_and_blocks("end do_return");
 
         if let Some(caller_frame) = self.previous_frames.pop() {
@@ -420,7 +655,7 @@ impl<'a, I: ImportDispatcher> Instance<'a, I> {
         }
     }
 
-    fn write_debug<T: fmt::Debug>(&mut self, value: T) {
+    pub(crate) fn write_debug<T: fmt::Debug>(&mut self, value: T) {
         if let Some(debug_string) = self.debug_string.as_mut() {
             std::write!(debug_string, "{value:?} ").unwrap();
         }
@@ -518,6 +753,14 @@ impl<'a, I: ImportDispatcher> Instance<'a, I> {
             self.debug_call(n_args, ret_type);
         }
 
+        if let Some(profile) = self.profile.as_mut() {
+            if opt_import.is_some() {
+                profile.call_import(fn_index);
+            } else {
+                profile.enter(fn_index);
+            }
+        }
+
         if let Some(import) = opt_import {
             self.import_arguments.clear();
             self.import_arguments
@@ -544,6 +787,13 @@ impl<'a, I: ImportDispatcher> Instance<'a, I> {
                 write!(debug_string, " {}.{}", import.module, import.name).unwrap();
             }
         } else {
+            if let Some(max_depth) = self.limits.max_call_depth {
+                // The current frame plus the previous ones, plus the new one
+                if self.previous_frames.len() + 2 > max_depth {
+                    return Err(Error::CallStackExhausted(max_depth));
+                }
+            }
+
             let return_addr = self.program_counter;
             // set PC to start of function bytes
             let internal_fn_index = fn_index - self.import_count;
@@ -606,6 +856,13 @@ impl<'a, I: ImportDispatcher> Instance<'a, I> {
     ) -> Result<Action, Error> {
         use OpCode::*;
 
+        if let Some(fuel) = self.fuel.as_mut() {
+            if *fuel == 0 {
+                return Err(Error::OutOfFuel);
+            }
+            *fuel -= 1;
+        }
+
         let file_offset = self.program_counter as u32 + module.code.section_offset;
         let op_code = OpCode::from(module.code.bytes[self.program_counter]);
         self.program_counter += 1;
@@ -614,6 +871,9 @@ impl<'a, I: ImportDispatcher> Instance<'a, I> {
             debug_string.clear();
             self.write_debug(op_code);
         }
+        if let Some(profile) = self.profile.as_mut() {
+            profile.count_instruction();
+        }
 
         let mut action = Action::Continue;
         let mut implicit_return = false;
@@ -651,6 +911,11 @@ impl<'a, I: ImportDispatcher> Instance<'a, I> {
                         .find(|entry| entry.addr == addr);
                     if let Some(entry) = cache_result {
                         self.program_counter = entry.target as usize;
+                        let skipped_op = module.code.bytes[self.program_counter - 1];
+                        if skipped_op == END as u8 {
+                            // `if` without `else`
+                            self.blocks.pop();
+                        }
                     } else {
                         let target_depth = self.blocks.len();
                         let mut depth = target_depth;
@@ -746,7 +1011,7 @@ impl<'a, I: ImportDispatcher> Instance<'a, I> {
                 );
 
                 // Dereference the function pointer (look up the element index in the function table)
-                let fn_index = module.element.lookup(element_index).unwrap_or_else(|| {
+                let fn_index = self.table.get(element_index as usize).copied().flatten().unwrap_or_else(|| {
                     panic!(
                         "Indirect function call failed. There is no function with element index {element_index}"
                     )
@@ -954,14 +1219,22 @@ impl<'a, I: ImportDispatcher> Instance<'a, I> {
                 let old_bytes = self.memory.len() as u32;
                 let old_pages = old_bytes / MemorySection::PAGE_SIZE;
                 let grow_pages = self.value_store.pop_u32()?;
-                let grow_bytes = grow_pages * MemorySection::PAGE_SIZE;
-                let new_bytes = old_bytes + grow_bytes;
+                let new_pages = old_pages.saturating_add(grow_pages);
 
-                let success = match module.memory.max_bytes().unwrap() {
-                    Some(max_bytes) => new_bytes <= max_bytes,
+                // Like the module's own maximum, the limit makes `memory.grow` fail, not trap,
+                // so the program can handle running out of memory.
+                let within_limit = match self.limits.max_memory_pages {
+                    Some(max_pages) => new_pages <= max_pages,
                     None => true,
                 };
-                if success {
+                let within_max = match module.memory.max_bytes().unwrap() {
+                    Some(max_bytes) => {
+                        (new_pages as u64) * (MemorySection::PAGE_SIZE as u64) <= max_bytes as u64
+                    }
+                    None => new_pages <= u32::MAX / MemorySection::PAGE_SIZE,
+                };
+                if within_limit && within_max {
+                    let grow_bytes = grow_pages * MemorySection::PAGE_SIZE;
                     self.memory
                         .extend(iter::repeat(0).take(grow_bytes as usize));
                     self.value_store.push(Value::I32(old_pages as i32));
@@ -973,8 +1246,37 @@ impl<'a, I: ImportDispatcher> Instance<'a, I> {
                 // the first argument determines exactly which memory operation we have
                 match MemoryInstruction::try_from(module.code.bytes[self.program_counter]) {
                     Ok(op) => match op {
-                        MemoryInstruction::MemoryInit => todo!("WASM instruction: memory.init"),
-                        MemoryInstruction::DataDrop => todo!("WASM instruction: data.drop"),
+                        MemoryInstruction::MemoryInit => {
+                            self.program_counter += 1;
+                            let segment_index = self.fetch_immediate_u32(module) as usize;
+                            // skip the memory index, which is always zero
+                            self.program_counter += 1;
+
+                            let size = self.value_store.pop_u32()? as usize;
+                            let source = self.value_store.pop_u32()? as usize;
+                            let destination = self.value_store.pop_u32()? as usize;
+
+                            let segment = self.data_segments[segment_index];
+                            if source + size > segment.len() {
+                                return Err(Error::DataSegmentOutOfBounds(
+                                    (source + size) as u32,
+                                    segment.len() as u32,
+                                ));
+                            }
+                            if destination + size > self.memory.len() {
+                                return Err(Error::MemoryAccessOutOfBounds(
+                                    (destination + size) as u32,
+                                    self.memory.len() as u32,
+                                ));
+                            }
+                            self.memory[destination..][..size]
+                                .copy_from_slice(&segment[source..][..size]);
+                        }
+                        MemoryInstruction::DataDrop => {
+                            self.program_counter += 1;
+                            let segment_index = self.fetch_immediate_u32(module) as usize;
+                            self.data_segments[segment_index] = &[];
+                        }
                         MemoryInstruction::MemoryCopy => {
                             let size = self.value_store.pop_u32()? as usize;
                             let source = self.value_store.pop_u32()? as usize;
@@ -997,10 +1299,52 @@ impl<'a, I: ImportDispatcher> Instance<'a, I> {
 
                             self.memory[destination..][..size].fill(byte_value);
                         }
+                        MemoryInstruction::TableInit => {
+                            self.program_counter += 1;
+                            let _segment_index = self.fetch_immediate_u32(module);
+                            let _table_index = self.fetch_immediate_u32(module);
+
+                            let size = self.value_store.pop_u32()?;
+                            let _source = self.value_store.pop_u32()?;
+                            let _destination = self.value_store.pop_u32()?;
+
+                            // We only support active element segments (the Wasm MVP format).
+                            // They're dropped after instantiation, so any non-empty copy is out of bounds.
+                            if size > 0 {
+                                return Err(Error::TableAccessOutOfBounds(size, 0));
+                            }
+                        }
+                        MemoryInstruction::ElemDrop => {
+                            // Element segments are always active, so they're already dropped
+                            self.program_counter += 1;
+                            self.fetch_immediate_u32(module);
+                        }
+                        MemoryInstruction::TableCopy => {
+                            self.program_counter += 1;
+                            let _destination_table = self.fetch_immediate_u32(module);
+                            let _source_table = self.fetch_immediate_u32(module);
+
+                            let size = self.value_store.pop_u32()? as usize;
+                            let source = self.value_store.pop_u32()? as usize;
+                            let destination = self.value_store.pop_u32()? as usize;
+
+                            let table_size = self.table.len();
+                            let end = source.max(destination) + size;
+                            if end > table_size {
+                                return Err(Error::TableAccessOutOfBounds(
+                                    end as u32,
+                                    table_size as u32,
+                                ));
+                            }
+                            self.table.copy_within(source..source + size, destination);
+                        }
                     },
                     Err(other) => unreachable!("invalid memory instruction {other:?}"),
                 };
             }
+            SIMD => {
+                self.execute_simd(module)?;
+            }
             I32CONST => {
                 let value = i32::parse((), &module.code.bytes, &mut self.program_counter).unwrap();
                 self.write_debug(value);
@@ -1853,18 +2197,13 @@ impl<'a, I: ImportDispatcher> Instance<'a, I> {
                 self.module.types.look_up(signature_index).0.len()
             };
 
-            let fn_name = self
-                .module
-                .names
-                .function_names
-                .iter()
-                .find(|(idx, _)| *idx == *fn_index as u32)
-                .map(|(_, name)| *name)
-                .unwrap_or("");
+            let fn_name = self.function_name(*fn_index).unwrap_or("");
 
             // Function and address match wasm-objdump formatting, for easy copy & find
             writeln!(buffer, "func[{fn_index}]  {fn_name}")?;
-            writeln!(buffer, "  address  {:06x}", execution_addrs.next().unwrap())?;
+            let code_addr = execution_addrs.next().unwrap();
+            let file_offset = code_addr + self.module.code.section_offset as usize;
+            writeln!(buffer, "  address  {file_offset:06x}")?;
 
             write!(buffer, "  args     ")?;
             for local_index in 0..*locals_count {
@@ -1,5 +1,8 @@
+pub mod debugger;
 mod frame;
 mod instance;
+pub mod profiler;
+mod simd;
 #[cfg(test)]
 mod tests;
 
@@ -7,11 +10,14 @@ mod value_store;
 pub mod wasi;
 
 // Main external interface
+pub use debugger::Debugger;
 pub use instance::Instance;
+pub use profiler::Profile;
 pub use wasi::{WasiDispatcher, WasiFile};
 
 pub use roc_wasm_module::Value;
 use roc_wasm_module::ValueType;
+use std::fmt;
 
 pub trait ImportDispatcher {
     /// Dispatch a call from WebAssembly to your own code, based on module and function name.
@@ -60,6 +66,50 @@ impl<'a> ImportDispatcher for DefaultImportDispatcher<'a> {
     }
 }
 
+/// Resource limits for an [`Instance`], to stop runaway programs.
+/// `None` means unlimited.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Limits {
+    /// Maximum number of instructions to execute, across all calls into the instance
+    pub fuel: Option<u64>,
+    /// Maximum number of nested Wasm function calls, including the entry function
+    pub max_call_depth: Option<usize>,
+    /// Maximum size of linear memory, in 64kB pages. Beyond this, `memory.grow` returns -1.
+    pub max_memory_pages: Option<u32>,
+}
+
+/// Which of the [`Limits`] was exceeded
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Limit {
+    Fuel,
+    CallDepth,
+}
+
+/// Error returned when a call into the Wasm module doesn't complete
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CallError {
+    /// The program trapped, or couldn't be started. Traps include a stack trace.
+    Failed(String),
+    /// The program was stopped because it exceeded one of the instance's [`Limits`]
+    LimitExceeded(Limit, String),
+}
+
+impl fmt::Display for CallError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CallError::Failed(message) | CallError::LimitExceeded(_, message) => {
+                write!(f, "{message}")
+            }
+        }
+    }
+}
+
+impl From<CallError> for String {
+    fn from(error: CallError) -> Self {
+        error.to_string()
+    }
+}
+
 /// Errors that can happen while interpreting the program
 /// All of these cause a WebAssembly stack trace to be dumped
 #[derive(Debug, PartialEq)]
@@ -68,6 +118,10 @@ pub(crate) enum Error {
     StackEmpty,
     MemoryAccessOutOfBounds(u32, u32),
     UnreachableOp,
+    OutOfFuel,
+    CallStackExhausted(usize),
+    DataSegmentOutOfBounds(u32, u32),
+    TableAccessOutOfBounds(u32, u32),
 }
 
 impl Error {
@@ -92,6 +146,33 @@ impl Error {
             Error::UnreachableOp => {
                 format!("WebAssembly `unreachable` instruction at file offset {file_offset:#x}.\n")
             }
+            Error::OutOfFuel => {
+                format!("ERROR: The program ran out of fuel at file offset {file_offset:#x}.\n")
+            }
+            Error::CallStackExhausted(max_depth) => {
+                format!(
+                    "ERROR: A call at file offset {file_offset:#x} exceeded the maximum call depth of {max_depth}.\n"
+                )
+            }
+            Error::DataSegmentOutOfBounds(end, segment_size) => {
+                format!(
+                    "ERROR: A Wasm instruction at file offset {file_offset:#x} tried to read up to byte {end:#x} of a data segment, but its size is {segment_size:#x}.\n"
+                )
+            }
+            Error::TableAccessOutOfBounds(end, table_size) => {
+                format!(
+                    "ERROR: A Wasm instruction at file offset {file_offset:#x} tried to access the function table up to index {end}, but its size is {table_size}.\n"
+                )
+            }
+        }
+    }
+
+    /// The resource limit that caused this error, if any
+    pub fn limit(&self) -> Option<Limit> {
+        match self {
+            Error::OutOfFuel => Some(Limit::Fuel),
+            Error::CallStackExhausted(_) => Some(Limit::CallDepth),
+            _ => None,
         }
     }
 }
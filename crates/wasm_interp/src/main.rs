@@ -1,17 +1,28 @@
 use bumpalo::{collections::Vec, Bump};
 use clap::ArgAction;
-use clap::{Arg, Command};
+use clap::{value_parser, Arg, Command};
 use std::fs;
-use std::io;
+use std::io::{self, BufRead, Write};
 use std::iter::once;
+use std::path::Path;
 use std::process;
 
-use roc_wasm_interp::{DefaultImportDispatcher, Instance};
+use roc_wasm_interp::debugger::{self, Breakpoint, Resume, StopReason};
+use roc_wasm_interp::{
+    CallError, Debugger, DefaultImportDispatcher, ImportDispatcher, Instance, Limits, Value,
+};
 use roc_wasm_module::WasmModule;
 
 pub const FLAG_FUNCTION: &str = "function";
 pub const FLAG_DEBUG: &str = "debug";
+pub const FLAG_DEBUGGER: &str = "debugger";
 pub const FLAG_HEX: &str = "hex";
+pub const FLAG_DIR: &str = "dir";
+pub const FLAG_PROFILE: &str = "profile";
+pub const FLAG_PROFILE_STACKS: &str = "profile-stacks";
+pub const FLAG_FUEL: &str = "fuel";
+pub const FLAG_MAX_CALL_DEPTH: &str = "max-call-depth";
+pub const FLAG_MAX_MEMORY_PAGES: &str = "max-memory-pages";
 pub const WASM_FILE: &str = "WASM_FILE";
 pub const ARGS_FOR_APP: &str = "ARGS_FOR_APP";
 
@@ -32,12 +43,55 @@ fn main() -> io::Result<()> {
         .action(ArgAction::SetTrue)
         .required(false);
 
+    let flag_debugger = Arg::new(FLAG_DEBUGGER)
+        .long(FLAG_DEBUGGER)
+        .help("Run the program in an interactive step debugger. Type `help` at the prompt for a list of commands.")
+        .action(ArgAction::SetTrue)
+        .required(false);
+
     let flag_hex = Arg::new(FLAG_HEX)
         .long(FLAG_HEX)
         .help("If the called function returns a value, print it in hexadecimal format.")
         .action(ArgAction::SetTrue)
         .required(false);
 
+    let flag_dir = Arg::new(FLAG_DIR)
+        .long(FLAG_DIR)
+        .help("Give the app access to a host directory, as HOST_DIR or HOST_DIR::GUEST_DIR\nThe app can't access any files outside of these directories.")
+        .value_name("DIR")
+        .action(ArgAction::Append)
+        .required(false);
+
+    let flag_profile = Arg::new(FLAG_PROFILE)
+        .long(FLAG_PROFILE)
+        .help("Count the instructions executed in each function, and print a report to stderr.")
+        .action(ArgAction::SetTrue)
+        .required(false);
+
+    let flag_profile_stacks = Arg::new(FLAG_PROFILE_STACKS)
+        .long(FLAG_PROFILE_STACKS)
+        .help("Count the instructions executed in each call stack, and write them to a file.\nThe file is in the collapsed stack format used by flamegraph tools.")
+        .value_name("FILE")
+        .required(false);
+
+    let flag_fuel = Arg::new(FLAG_FUEL)
+        .long(FLAG_FUEL)
+        .help("Stop the program after executing this many instructions")
+        .value_parser(value_parser!(u64))
+        .required(false);
+
+    let flag_max_call_depth = Arg::new(FLAG_MAX_CALL_DEPTH)
+        .long(FLAG_MAX_CALL_DEPTH)
+        .help("Stop the program if it makes more than this many nested function calls")
+        .value_parser(value_parser!(usize))
+        .required(false);
+
+    let flag_max_memory_pages = Arg::new(FLAG_MAX_MEMORY_PAGES)
+        .long(FLAG_MAX_MEMORY_PAGES)
+        .help("Make memory.grow fail beyond this many 64kB pages")
+        .value_parser(value_parser!(u32))
+        .required(false);
+
     let wasm_file_to_run = Arg::new(WASM_FILE)
         .help("The .wasm file to run")
         .required(true);
@@ -50,7 +104,14 @@ fn main() -> io::Result<()> {
         .about("Run the given .wasm file")
         .arg(flag_function)
         .arg(flag_debug)
+        .arg(flag_debugger)
         .arg(flag_hex)
+        .arg(flag_dir)
+        .arg(flag_profile)
+        .arg(flag_profile_stacks)
+        .arg(flag_fuel)
+        .arg(flag_max_call_depth)
+        .arg(flag_max_memory_pages)
         .arg(wasm_file_to_run)
         .arg(args_for_app);
 
@@ -59,7 +120,16 @@ fn main() -> io::Result<()> {
     let matches = app.get_matches();
     let start_fn_name = matches.get_one::<String>(FLAG_FUNCTION).unwrap();
     let is_debug_mode = matches.get_flag(FLAG_DEBUG);
+    let is_debugger = matches.get_flag(FLAG_DEBUGGER);
     let is_hex_format = matches.get_flag(FLAG_HEX);
+    let is_profile = matches.get_flag(FLAG_PROFILE);
+    let profile_stacks_path = matches.get_one::<String>(FLAG_PROFILE_STACKS);
+    let limits = Limits {
+        fuel: matches.get_one::<u64>(FLAG_FUEL).copied(),
+        max_call_depth: matches.get_one::<usize>(FLAG_MAX_CALL_DEPTH).copied(),
+        max_memory_pages: matches.get_one::<u32>(FLAG_MAX_MEMORY_PAGES).copied(),
+    };
+    let preopen_dirs = matches.get_many::<String>(FLAG_DIR).unwrap_or_default();
     let start_arg_strings = matches.get_many::<String>(ARGS_FOR_APP).unwrap_or_default();
     let wasm_path = matches.get_one::<String>(WASM_FILE).unwrap();
     // WASI expects the .wasm file to be argv[0]
@@ -87,33 +157,250 @@ fn main() -> io::Result<()> {
 
     // Create an execution instance
 
-    let dispatcher = DefaultImportDispatcher::new(&wasi_argv);
+    let mut dispatcher = DefaultImportDispatcher::new(&wasi_argv);
+    for dir in preopen_dirs {
+        // The app sees the directory by the same name as the host, unless there's a GUEST_DIR
+        let (host_dir, guest_dir) = dir.split_once("::").unwrap_or((dir, dir));
+        if let Err(e) = dispatcher.wasi.preopen_dir(Path::new(host_dir), guest_dir) {
+            eprintln!("I couldn't open the directory {host_dir}: {e}");
+            process::exit(1);
+        }
+    }
     let mut inst =
-        Instance::for_module(&arena, &module, dispatcher, is_debug_mode).unwrap_or_else(|e| {
-            eprintln!("{e}");
-            process::exit(2);
-        });
+        Instance::for_module_with_limits(&arena, &module, dispatcher, is_debug_mode, limits)
+            .unwrap_or_else(|e| {
+                eprintln!("{e}");
+                process::exit(2);
+            });
+
+    if is_profile || profile_stacks_path.is_some() {
+        inst.enable_profiling();
+    }
 
     // Run
 
+    if is_debugger {
+        if let Err(e) = inst.start_export_from_cli(&module, start_fn_name, &wasi_argv) {
+            eprintln!("{e}");
+            process::exit(3);
+        }
+        run_debugger(&mut inst, &module, is_hex_format)?;
+        return write_profile(&inst, &module, is_profile, profile_stacks_path);
+    }
+
     let result = inst.call_export_from_cli(&module, start_fn_name, &wasi_argv);
 
+    write_profile(&inst, &module, is_profile, profile_stacks_path)?;
+
     // Print out return value, if any
 
     match result {
-        Ok(Some(val)) => {
-            if is_hex_format {
-                println!("{val:#x?}")
-            } else {
-                println!("{val:?}")
-            }
-        }
+        Ok(Some(val)) => println!("{}", format_value(val, is_hex_format)),
         Ok(None) => {}
-        Err(e) => {
+        Err(CallError::Failed(e)) => {
             eprintln!("{e}");
             process::exit(3);
         }
+        Err(CallError::LimitExceeded(_, e)) => {
+            eprintln!("{e}");
+            process::exit(4);
+        }
     }
 
     Ok(())
 }
+
+fn write_profile<I: ImportDispatcher>(
+    inst: &Instance<'_, I>,
+    module: &WasmModule<'_>,
+    is_profile: bool,
+    stacks_path: Option<&String>,
+) -> io::Result<()> {
+    if let Some(profile) = inst.profile() {
+        if is_profile {
+            eprint!("{}", profile.flat_report(module));
+        }
+        if let Some(path) = stacks_path {
+            fs::write(path, profile.collapsed_stacks(module))?;
+        }
+    }
+    Ok(())
+}
+
+fn format_value(val: Value, is_hex_format: bool) -> String {
+    if is_hex_format {
+        format!("{val:#x?}")
+    } else {
+        format!("{val:?}")
+    }
+}
+
+const DEBUGGER_HELP: &str = "\
+Commands:
+  break FUNCTION|ADDRESS   (b)  Stop on entry to a function, or at a file offset
+  delete N                      Delete breakpoint number N
+  breakpoints                   List breakpoints
+  continue                 (c)  Run until a breakpoint or the end of the program
+  step                     (s)  Execute one instruction, stepping into calls
+  next                     (n)  Execute one instruction, stepping over calls
+  finish                   (f)  Run until the current function returns
+  where                    (bt) Show all call frames
+  locals                   (l)  Show the current function's arguments and locals
+  stack                         Show the current function's value stack
+  memory ADDRESS [LENGTH]  (x)  Dump linear memory
+  str ADDRESS                   Decode a RocStr
+  list ADDRESS SIZE             Decode a RocList with elements of SIZE bytes
+  help                     (h)  Show this message
+  quit                     (q)  Exit the debugger
+Numbers can be decimal or 0x-prefixed hex. An ADDRESS can also be $N, the value of local N.
+An empty line repeats the previous command.";
+
+fn run_debugger<I: ImportDispatcher>(
+    inst: &mut Instance<'_, I>,
+    module: &WasmModule<'_>,
+    is_hex_format: bool,
+) -> io::Result<()> {
+    let mut debugger = Debugger::new();
+    let mut previous_line = String::new();
+    let stdin = io::stdin();
+    let mut lines = stdin.lock().lines();
+
+    println!("{}", inst.debug_location());
+    loop {
+        print!("(debug) ");
+        io::stdout().flush()?;
+        let line = match lines.next() {
+            Some(line) => line?,
+            None => return Ok(()),
+        };
+        let line = if line.trim().is_empty() {
+            previous_line.clone()
+        } else {
+            previous_line = line.clone();
+            line
+        };
+        let words: std::vec::Vec<&str> = line.split_whitespace().collect();
+        let Some((command, args)) = words.split_first() else {
+            continue;
+        };
+
+        let resume = match *command {
+            "c" | "continue" => Some(Resume::Continue),
+            "s" | "step" => Some(Resume::Step),
+            "n" | "next" => Some(Resume::Next),
+            "f" | "finish" => Some(Resume::Finish),
+            _ => None,
+        };
+        if let Some(resume) = resume {
+            match debugger.resume(inst, resume) {
+                Ok(StopReason::Breakpoint(number)) => {
+                    println!("Breakpoint {number}, {}", inst.debug_location());
+                }
+                Ok(StopReason::Step) => println!("{}", inst.debug_location()),
+                Ok(StopReason::Exit(Some(val))) => {
+                    println!(
+                        "Program exited, returning {}",
+                        format_value(val, is_hex_format)
+                    );
+                }
+                Ok(StopReason::Exit(None)) => println!("Program exited"),
+                Ok(StopReason::Error(e)) => eprintln!("{e}"),
+                Err(e) => eprintln!("{e}"),
+            }
+            continue;
+        }
+
+        let result = match (*command, args) {
+            ("b" | "break", [location]) => debugger
+                .add_breakpoint(module, location)
+                .map(|number| println!("Breakpoint {number} at {location}")),
+            ("delete", [number]) => match debugger::parse_number(number) {
+                Some(n) if debugger.delete_breakpoint(n as usize) => Ok(()),
+                _ => Err(format!("There is no breakpoint {number}")),
+            },
+            ("breakpoints", []) => {
+                for (number, breakpoint) in debugger.breakpoints() {
+                    match breakpoint {
+                        Breakpoint::Function(fn_index) => {
+                            let name = inst.function_name(fn_index as usize).unwrap_or("");
+                            println!("{number}: func[{fn_index}] {name}");
+                        }
+                        Breakpoint::Address(file_offset) => {
+                            println!("{number}: {file_offset:#08x}");
+                        }
+                    }
+                }
+                Ok(())
+            }
+            ("bt" | "where", []) => {
+                print!("{}", inst.backtrace());
+                Ok(())
+            }
+            ("l" | "locals", []) => {
+                for (i, value) in inst.locals().iter().enumerate() {
+                    println!("{i}: {}", format_value(*value, is_hex_format));
+                }
+                Ok(())
+            }
+            ("stack", []) => {
+                for value in inst.value_stack().iter().rev() {
+                    println!("{}", format_value(*value, is_hex_format));
+                }
+                Ok(())
+            }
+            ("x" | "memory", [addr, rest @ ..]) if rest.len() <= 1 => {
+                let length = rest
+                    .first()
+                    .map_or(Some(64), |len| debugger::parse_number(len));
+                parse_address(inst, addr)
+                    .and_then(|addr| {
+                        let length = length.ok_or_else(|| format!("Invalid length {rest:?}"))?;
+                        debugger::hex_dump(&inst.memory, addr, length)
+                    })
+                    .map(|dump| print!("{dump}"))
+            }
+            ("str", [addr]) => parse_address(inst, addr)
+                .and_then(|addr| debugger::read_roc_str(&inst.memory, addr))
+                .map(|bytes| println!("{:?}", String::from_utf8_lossy(bytes))),
+            ("list", [addr, size]) => {
+                let size =
+                    debugger::parse_number(size).ok_or_else(|| format!("Invalid size {size}"));
+                parse_address(inst, addr).and_then(|addr| {
+                    let elements = debugger::read_roc_list(&inst.memory, addr, size?)?;
+                    println!("{} elements", elements.len());
+                    for (i, element) in elements.iter().enumerate() {
+                        println!("{i}: {element:02x?}");
+                    }
+                    Ok(())
+                })
+            }
+            ("h" | "help", []) => {
+                println!("{DEBUGGER_HELP}");
+                Ok(())
+            }
+            ("q" | "quit", []) => return Ok(()),
+            _ => Err(format!(
+                "I don't understand `{line}`. Type `help` for a list of commands."
+            )),
+        };
+        if let Err(e) = result {
+            eprintln!("{e}");
+        }
+    }
+}
+
+/// Parse a memory address, which can be a number or `$N` for the value of local variable N
+fn parse_address<I: ImportDispatcher>(inst: &Instance<'_, I>, s: &str) -> Result<u32, String> {
+    let invalid = || format!("Invalid address {s}");
+    match s.strip_prefix('$') {
+        Some(local) => {
+            let index = debugger::parse_number(local).ok_or_else(invalid)?;
+            match inst.locals().get(index as usize) {
+                Some(Value::I32(addr)) => Ok(*addr as u32),
+                Some(other) => Err(format!("Local {index} is {other:?}, not an I32 address")),
+                None => Err(format!("There is no local {index}")),
+            }
+        }
+        None => debugger::parse_number(s).ok_or_else(invalid),
+    }
+}
@@ -0,0 +1,307 @@
+use roc_wasm_module::WasmModule;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+/// Instruction counts for one function
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct FunctionStats {
+    /// Number of times the function was called
+    pub calls: u64,
+    /// Instructions executed in the function itself
+    pub exclusive: u64,
+    /// Instructions executed in the function and everything it called
+    pub inclusive: u64,
+}
+
+/// Calls from one function to another
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CallEdge {
+    pub caller: u32,
+    pub callee: u32,
+    pub calls: u64,
+    /// Instructions executed in the callee and everything it called, when called from this caller
+    pub inclusive: u64,
+}
+
+/// A node in the tree of call paths. The root node represents the host calling into Wasm.
+#[derive(Debug)]
+struct CallPath {
+    fn_index: u32,
+    parent: usize,
+    children: Vec<usize>,
+    /// Instructions executed in this function when called through this path
+    instructions: u64,
+}
+
+const ROOT: usize = 0;
+
+/// Instruction-level profile of the code run by an [`Instance`](crate::Instance)
+///
+/// Counts are attributed to call paths as the program runs, and aggregated per function when reported.
+/// Children always come after their parent in `paths`, so totals can be summed in one backward pass.
+#[derive(Debug)]
+pub struct Profile {
+    paths: Vec<CallPath>,
+    current: usize,
+    calls: Vec<u64>,
+    edge_calls: BTreeMap<(u32, u32), u64>,
+}
+
+impl Profile {
+    pub(crate) fn new(function_count: usize) -> Self {
+        Profile {
+            paths: vec![CallPath {
+                fn_index: u32::MAX,
+                parent: ROOT,
+                children: Vec::new(),
+                instructions: 0,
+            }],
+            current: ROOT,
+            calls: vec![0; function_count],
+            edge_calls: BTreeMap::new(),
+        }
+    }
+
+    pub(crate) fn count_instruction(&mut self) {
+        self.paths[self.current].instructions += 1;
+    }
+
+    /// Called from the host into an exported function
+    pub(crate) fn enter_export(&mut self, fn_index: usize) {
+        self.current = ROOT;
+        self.enter(fn_index);
+    }
+
+    /// Call a Wasm function from the current one
+    pub(crate) fn enter(&mut self, fn_index: usize) {
+        self.count_call(fn_index);
+        let fn_index = fn_index as u32;
+        let existing = self.paths[self.current]
+            .children
+            .iter()
+            .copied()
+            .find(|child| self.paths[*child].fn_index == fn_index);
+        self.current = existing.unwrap_or_else(|| {
+            let child = self.paths.len();
+            self.paths.push(CallPath {
+                fn_index,
+                parent: self.current,
+                children: Vec::new(),
+                instructions: 0,
+            });
+            self.paths[self.current].children.push(child);
+            child
+        });
+    }
+
+    /// Call an imported function, which executes no Wasm instructions
+    pub(crate) fn call_import(&mut self, fn_index: usize) {
+        self.count_call(fn_index);
+    }
+
+    pub(crate) fn leave(&mut self) {
+        self.current = self.paths[self.current].parent;
+    }
+
+    fn count_call(&mut self, fn_index: usize) {
+        self.calls[fn_index] += 1;
+        if self.current != ROOT {
+            let caller = self.paths[self.current].fn_index;
+            *self
+                .edge_calls
+                .entry((caller, fn_index as u32))
+                .or_insert(0) += 1;
+        }
+    }
+
+    /// Total number of instructions executed
+    pub fn total_instructions(&self) -> u64 {
+        self.paths.iter().map(|path| path.instructions).sum()
+    }
+
+    /// Instructions executed in each call path, including the paths it calls
+    fn path_totals(&self) -> Vec<u64> {
+        let mut totals: Vec<u64> = self.paths.iter().map(|path| path.instructions).collect();
+        for i in (1..self.paths.len()).rev() {
+            totals[self.paths[i].parent] += totals[i];
+        }
+        totals
+    }
+
+    /// Whether any ancestor of a call path satisfies a condition.
+    /// Used to avoid counting recursive calls twice in inclusive totals.
+    fn any_ancestor(&self, path: usize, condition: impl Fn(usize) -> bool) -> bool {
+        let mut ancestor = self.paths[path].parent;
+        while ancestor != ROOT {
+            if condition(ancestor) {
+                return true;
+            }
+            ancestor = self.paths[ancestor].parent;
+        }
+        false
+    }
+
+    /// Statistics for every function, indexed by function index
+    pub fn function_stats(&self) -> Vec<FunctionStats> {
+        let mut stats: Vec<FunctionStats> = self
+            .calls
+            .iter()
+            .map(|calls| FunctionStats {
+                calls: *calls,
+                ..Default::default()
+            })
+            .collect();
+
+        let totals = self.path_totals();
+        for (i, path) in self.paths.iter().enumerate().skip(1) {
+            let fn_stats = &mut stats[path.fn_index as usize];
+            fn_stats.exclusive += path.instructions;
+            let is_recursive = self.any_ancestor(i, |a| self.paths[a].fn_index == path.fn_index);
+            if !is_recursive {
+                fn_stats.inclusive += totals[i];
+            }
+        }
+        stats
+    }
+
+    /// Every caller/callee pair that was called at least once
+    pub fn call_edges(&self) -> Vec<CallEdge> {
+        let mut inclusive: BTreeMap<(u32, u32), u64> = BTreeMap::new();
+        let totals = self.path_totals();
+        for (i, path) in self.paths.iter().enumerate().skip(1) {
+            if path.parent == ROOT {
+                continue;
+            }
+            let edge = (self.paths[path.parent].fn_index, path.fn_index);
+            let is_recursive = self.any_ancestor(i, |a| {
+                let parent = self.paths[a].parent;
+                parent != ROOT && (self.paths[parent].fn_index, self.paths[a].fn_index) == edge
+            });
+            if !is_recursive {
+                *inclusive.entry(edge).or_insert(0) += totals[i];
+            }
+        }
+
+        self.edge_calls
+            .iter()
+            .map(|(&(caller, callee), &calls)| CallEdge {
+                caller,
+                callee,
+                calls,
+                inclusive: inclusive.get(&(caller, callee)).copied().unwrap_or(0),
+            })
+            .collect()
+    }
+
+    /// Human-readable report, with functions sorted by exclusive instruction count
+    pub fn flat_report(&self, module: &WasmModule<'_>) -> String {
+        let total = self.total_instructions();
+        let percent = |n: u64| {
+            if total == 0 {
+                0.0
+            } else {
+                100.0 * n as f64 / total as f64
+            }
+        };
+        let mut report = String::new();
+        writeln!(report, "Executed {total} instructions\n").unwrap();
+
+        let mut functions: Vec<(usize, FunctionStats)> = self
+            .function_stats()
+            .into_iter()
+            .enumerate()
+            .filter(|(_, stats)| stats.calls > 0)
+            .collect();
+        functions.sort_by(|(a_index, a), (b_index, b)| {
+            (b.exclusive, b.inclusive, a_index).cmp(&(a.exclusive, a.inclusive, b_index))
+        });
+
+        writeln!(
+            report,
+            "{:>12} {:>6}  {:>12} {:>6}  {:>10}  function",
+            "exclusive", "%", "inclusive", "%", "calls"
+        )
+        .unwrap();
+        for (fn_index, stats) in functions {
+            writeln!(
+                report,
+                "{:>12} {:>5.1}%  {:>12} {:>5.1}%  {:>10}  {}",
+                stats.exclusive,
+                percent(stats.exclusive),
+                stats.inclusive,
+                percent(stats.inclusive),
+                stats.calls,
+                function_label(module, fn_index as u32),
+            )
+            .unwrap();
+        }
+
+        writeln!(report, "\nCall graph\n").unwrap();
+        writeln!(
+            report,
+            "{:>10}  {:>12} {:>6}  caller -> callee",
+            "calls", "inclusive", "%"
+        )
+        .unwrap();
+        for edge in self.call_edges() {
+            writeln!(
+                report,
+                "{:>10}  {:>12} {:>5.1}%  {} -> {}",
+                edge.calls,
+                edge.inclusive,
+                percent(edge.inclusive),
+                function_label(module, edge.caller),
+                function_label(module, edge.callee),
+            )
+            .unwrap();
+        }
+
+        report
+    }
+
+    /// One line per call path, in the "collapsed stack" format read by flamegraph tools
+    /// e.g. `main;List.map;add 1234`
+    pub fn collapsed_stacks(&self, module: &WasmModule<'_>) -> String {
+        let mut output = String::new();
+        let mut frames = Vec::new();
+        for (i, path) in self.paths.iter().enumerate().skip(1) {
+            if path.instructions == 0 {
+                continue;
+            }
+            frames.clear();
+            let mut ancestor = i;
+            while ancestor != ROOT {
+                frames.push(self.paths[ancestor].fn_index);
+                ancestor = self.paths[ancestor].parent;
+            }
+            for (depth, fn_index) in frames.iter().rev().enumerate() {
+                if depth > 0 {
+                    output.push(';');
+                }
+                let name = function_name(module, *fn_index)
+                    .map(|name| name.replace(';', ":"))
+                    .unwrap_or_else(|| format!("func[{fn_index}]"));
+                output.push_str(&name);
+            }
+            writeln!(output, " {}", path.instructions).unwrap();
+        }
+        output
+    }
+}
+
+fn function_name<'a>(module: &WasmModule<'a>, fn_index: u32) -> Option<&'a str> {
+    module
+        .names
+        .function_names
+        .iter()
+        .find(|(index, _)| *index == fn_index)
+        .map(|(_, name)| *name)
+}
+
+/// Function index and name, in the same format as stack traces
+fn function_label(module: &WasmModule<'_>, fn_index: u32) -> String {
+    match function_name(module, fn_index) {
+        Some(name) => format!("func[{fn_index}] {name}"),
+        None => format!("func[{fn_index}]"),
+    }
+}
@@ -0,0 +1,808 @@
+//! Execution of 128-bit SIMD instructions (the 0xFD prefix)
+//! https://webassembly.github.io/spec/core/exec/instructions.html#vector-instructions
+//!
+//! Vectors are stored as little-endian bytes, the same as in memory, and interpreted
+//! as lanes of different types by each instruction.
+
+use roc_wasm_module::opcodes::SimdInstruction;
+use roc_wasm_module::parse::Parse;
+use roc_wasm_module::{Value, WasmModule};
+
+use crate::{Error, ImportDispatcher, Instance};
+
+type V128 = [u8; 16];
+
+/// A scalar type that can be stored in a vector lane
+trait Lane: Copy {
+    const SIZE: usize;
+    fn read(bytes: &[u8]) -> Self;
+    fn write(self, bytes: &mut [u8]);
+}
+
+macro_rules! impl_lane {
+    ($($t: ty),*) => {
+        $(
+            impl Lane for $t {
+                const SIZE: usize = std::mem::size_of::<$t>();
+
+                fn read(bytes: &[u8]) -> Self {
+                    let mut buf = [0; std::mem::size_of::<$t>()];
+                    buf.copy_from_slice(bytes);
+                    <$t>::from_le_bytes(buf)
+                }
+
+                fn write(self, bytes: &mut [u8]) {
+                    bytes.copy_from_slice(&self.to_le_bytes());
+                }
+            }
+        )*
+    };
+}
+
+impl_lane!(i8, u8, i16, u16, i32, u32, i64, u64, f32, f64);
+
+fn lane<T: Lane>(v: &V128, index: usize) -> T {
+    T::read(&v[index * T::SIZE..][..T::SIZE])
+}
+
+fn set_lane<T: Lane>(v: &mut V128, index: usize, x: T) {
+    x.write(&mut v[index * T::SIZE..][..T::SIZE]);
+}
+
+fn splat<T: Lane>(x: T) -> V128 {
+    let mut result = [0; 16];
+    for i in 0..16 / T::SIZE {
+        set_lane(&mut result, i, x);
+    }
+    result
+}
+
+fn unop<T: Lane>(a: V128, f: impl Fn(T) -> T) -> V128 {
+    let mut result = [0; 16];
+    for i in 0..16 / T::SIZE {
+        set_lane(&mut result, i, f(lane(&a, i)));
+    }
+    result
+}
+
+fn binop<T: Lane>(a: V128, b: V128, f: impl Fn(T, T) -> T) -> V128 {
+    let mut result = [0; 16];
+    for i in 0..16 / T::SIZE {
+        set_lane(&mut result, i, f(lane(&a, i), lane(&b, i)));
+    }
+    result
+}
+
+/// Each lane of the result is all ones if the comparison is true, or all zeros if not
+fn compare<T: Lane>(a: V128, b: V128, f: impl Fn(T, T) -> bool) -> V128 {
+    let mut result = [0; 16];
+    for i in 0..16 / T::SIZE {
+        if f(lane(&a, i), lane(&b, i)) {
+            result[i * T::SIZE..][..T::SIZE].fill(0xff);
+        }
+    }
+    result
+}
+
+/// Shift amounts are taken modulo the lane width
+fn shift<T: Lane>(a: V128, amount: u32, f: impl Fn(T, u32) -> T) -> V128 {
+    let bits = 8 * T::SIZE as u32;
+    unop(a, |x| f(x, amount % bits))
+}
+
+/// Convert lanes to another type, starting from lane `first` of the input.
+/// Converts as many lanes as fit in both types. Any lanes left over in the result are zero.
+fn convert<S: Lane, D: Lane>(a: V128, first: usize, f: impl Fn(S) -> D) -> V128 {
+    let mut result = [0; 16];
+    let count = 16 / S::SIZE.max(D::SIZE);
+    for i in 0..count {
+        set_lane(&mut result, i, f(lane(&a, first + i)));
+    }
+    result
+}
+
+/// Narrow the lanes of two vectors into one, with the lanes of `a` first
+fn narrow<W: Lane, N: Lane>(a: V128, b: V128, f: impl Fn(W) -> N) -> V128 {
+    let mut result = [0; 16];
+    let count = 16 / W::SIZE;
+    for i in 0..count {
+        set_lane(&mut result, i, f(lane(&a, i)));
+        set_lane(&mut result, count + i, f(lane(&b, i)));
+    }
+    result
+}
+
+/// Combine lanes from the low or high half of two vectors into wider lanes
+fn extend_binop<N: Lane, W: Lane>(a: V128, b: V128, is_high: bool, f: impl Fn(N, N) -> W) -> V128 {
+    let mut result = [0; 16];
+    let count = 16 / W::SIZE;
+    let first = if is_high { count } else { 0 };
+    for i in 0..count {
+        set_lane(&mut result, i, f(lane(&a, first + i), lane(&b, first + i)));
+    }
+    result
+}
+
+/// Combine adjacent pairs of lanes into wider lanes
+fn pairwise<N: Lane, W: Lane>(a: V128, b: V128, f: impl Fn(N, N, N, N) -> W) -> V128 {
+    let mut result = [0; 16];
+    for i in 0..16 / W::SIZE {
+        let (a0, a1) = (lane(&a, 2 * i), lane(&a, 2 * i + 1));
+        let (b0, b1) = (lane(&b, 2 * i), lane(&b, 2 * i + 1));
+        set_lane(&mut result, i, f(a0, a1, b0, b1));
+    }
+    result
+}
+
+fn all_true<T: Lane + PartialEq + Default>(a: V128) -> bool {
+    (0..16 / T::SIZE).all(|i| lane::<T>(&a, i) != T::default())
+}
+
+/// One bit for the sign bit of each lane
+fn bitmask(a: V128, lane_size: usize) -> i32 {
+    let mut result = 0;
+    for i in 0..16 / lane_size {
+        let top_byte = a[i * lane_size + lane_size - 1];
+        result |= ((top_byte >> 7) as i32) << i;
+    }
+    result
+}
+
+fn bitwise(a: V128, b: V128, f: impl Fn(u8, u8) -> u8) -> V128 {
+    let mut result = [0; 16];
+    for ((r, x), y) in result.iter_mut().zip(a).zip(b) {
+        *r = f(x, y);
+    }
+    result
+}
+
+macro_rules! float_helpers {
+    ($t: ty, $min: ident, $max: ident, $nearest: ident) => {
+        /// Wasm `min` propagates NaN and treats -0 as less than +0
+        fn $min(a: $t, b: $t) -> $t {
+            if a.is_nan() || b.is_nan() {
+                <$t>::NAN
+            } else if a == b {
+                if a.is_sign_negative() {
+                    a
+                } else {
+                    b
+                }
+            } else if a < b {
+                a
+            } else {
+                b
+            }
+        }
+
+        /// Wasm `max` propagates NaN and treats +0 as greater than -0
+        fn $max(a: $t, b: $t) -> $t {
+            if a.is_nan() || b.is_nan() {
+                <$t>::NAN
+            } else if a == b {
+                if a.is_sign_positive() {
+                    a
+                } else {
+                    b
+                }
+            } else if a > b {
+                a
+            } else {
+                b
+            }
+        }
+
+        /// Round to the nearest integer, with ties to even
+        /// https://webassembly.github.io/spec/core/exec/numerics.html#op-fnearest
+        fn $nearest(x: $t) -> $t {
+            let rounded = x.round(); // "Rounds half-way cases away from 0.0"
+            let frac = x - rounded;
+            if frac == 0.5 || frac == -0.5 {
+                let rounded_half = rounded / 2.0;
+                let is_rounded_even = rounded_half.trunc() == rounded_half;
+                if is_rounded_even {
+                    rounded
+                } else if rounded < x {
+                    rounded + 1.0
+                } else {
+                    rounded - 1.0
+                }
+            } else {
+                rounded
+            }
+        }
+    };
+}
+
+float_helpers!(f32, f32_min, f32_max, f32_nearest);
+float_helpers!(f64, f64_min, f64_max, f64_nearest);
+
+fn avgr_u8(a: u8, b: u8) -> u8 {
+    ((a as u16 + b as u16 + 1) / 2) as u8
+}
+
+fn avgr_u16(a: u16, b: u16) -> u16 {
+    ((a as u32 + b as u32 + 1) / 2) as u16
+}
+
+impl<'a, I: ImportDispatcher> Instance<'a, I> {
+    /// Execute an instruction with the SIMD prefix. The program counter is just after the prefix byte.
+    pub(crate) fn execute_simd(&mut self, module: &WasmModule<'a>) -> Result<(), Error> {
+        use SimdInstruction::*;
+
+        let simd_op_u32 = u32::parse((), &module.code.bytes, &mut self.program_counter).unwrap();
+        let simd_op = SimdInstruction::try_from(simd_op_u32)
+            .unwrap_or_else(|x| unreachable!("unsupported SIMD instruction 0xfd {x:#x?}"));
+        self.write_debug(simd_op);
+
+        match simd_op {
+            V128Load => {
+                let addr = self.simd_address(module, 16)?;
+                let mut v = [0; 16];
+                v.copy_from_slice(&self.memory[addr..][..16]);
+                self.push_v128(v);
+            }
+            V128Load8x8S => self.load_extend::<i8, i16>(module, |x| x as i16)?,
+            V128Load8x8U => self.load_extend::<u8, u16>(module, |x| x as u16)?,
+            V128Load16x4S => self.load_extend::<i16, i32>(module, |x| x as i32)?,
+            V128Load16x4U => self.load_extend::<u16, u32>(module, |x| x as u32)?,
+            V128Load32x2S => self.load_extend::<i32, i64>(module, |x| x as i64)?,
+            V128Load32x2U => self.load_extend::<u32, u64>(module, |x| x as u64)?,
+            V128Load8Splat => self.load_splat::<u8>(module)?,
+            V128Load16Splat => self.load_splat::<u16>(module)?,
+            V128Load32Splat => self.load_splat::<u32>(module)?,
+            V128Load64Splat => self.load_splat::<u64>(module)?,
+            V128Load32Zero => self.load_zero::<u32>(module)?,
+            V128Load64Zero => self.load_zero::<u64>(module)?,
+            V128Store => {
+                let v = self.pop_v128()?;
+                let addr = self.simd_address(module, 16)?;
+                self.memory[addr..][..16].copy_from_slice(&v);
+            }
+            V128Load8Lane => self.load_lane::<u8>(module)?,
+            V128Load16Lane => self.load_lane::<u16>(module)?,
+            V128Load32Lane => self.load_lane::<u32>(module)?,
+            V128Load64Lane => self.load_lane::<u64>(module)?,
+            V128Store8Lane => self.store_lane::<u8>(module)?,
+            V128Store16Lane => self.store_lane::<u16>(module)?,
+            V128Store32Lane => self.store_lane::<u32>(module)?,
+            V128Store64Lane => self.store_lane::<u64>(module)?,
+
+            V128Const => {
+                let v = self.fetch_v128_immediate(module);
+                self.push_v128(v);
+            }
+            I8x16Shuffle => {
+                let lanes = self.fetch_v128_immediate(module);
+                let b = self.pop_v128()?;
+                let a = self.pop_v128()?;
+                let mut result = [0; 16];
+                for (r, index) in result.iter_mut().zip(lanes) {
+                    let index = index as usize;
+                    *r = if index < 16 { a[index] } else { b[index - 16] };
+                }
+                self.push_v128(result);
+            }
+            I8x16Swizzle => {
+                let indices = self.pop_v128()?;
+                let a = self.pop_v128()?;
+                let mut result = [0; 16];
+                for (r, index) in result.iter_mut().zip(indices) {
+                    *r = a.get(index as usize).copied().unwrap_or(0);
+                }
+                self.push_v128(result);
+            }
+
+            I8x16Splat => {
+                let x = self.value_store.pop_i32()?;
+                self.push_v128(splat(x as u8));
+            }
+            I16x8Splat => {
+                let x = self.value_store.pop_i32()?;
+                self.push_v128(splat(x as u16));
+            }
+            I32x4Splat => {
+                let x = self.value_store.pop_i32()?;
+                self.push_v128(splat(x));
+            }
+            I64x2Splat => {
+                let x = self.value_store.pop_i64()?;
+                self.push_v128(splat(x));
+            }
+            F32x4Splat => {
+                let x = self.value_store.pop_f32()?;
+                self.push_v128(splat(x));
+            }
+            F64x2Splat => {
+                let x = self.value_store.pop_f64()?;
+                self.push_v128(splat(x));
+            }
+
+            I8x16ExtractLaneS => {
+                let index = self.fetch_lane_index(module);
+                let v = self.pop_v128()?;
+                let x: i8 = lane(&v, index);
+                self.value_store.push(Value::I32(x as i32));
+            }
+            I8x16ExtractLaneU => {
+                let index = self.fetch_lane_index(module);
+                let v = self.pop_v128()?;
+                let x: u8 = lane(&v, index);
+                self.value_store.push(Value::I32(x as i32));
+            }
+            I16x8ExtractLaneS => {
+                let index = self.fetch_lane_index(module);
+                let v = self.pop_v128()?;
+                let x: i16 = lane(&v, index);
+                self.value_store.push(Value::I32(x as i32));
+            }
+            I16x8ExtractLaneU => {
+                let index = self.fetch_lane_index(module);
+                let v = self.pop_v128()?;
+                let x: u16 = lane(&v, index);
+                self.value_store.push(Value::I32(x as i32));
+            }
+            I32x4ExtractLane => {
+                let index = self.fetch_lane_index(module);
+                let v = self.pop_v128()?;
+                self.value_store.push(Value::I32(lane(&v, index)));
+            }
+            I64x2ExtractLane => {
+                let index = self.fetch_lane_index(module);
+                let v = self.pop_v128()?;
+                self.value_store.push(Value::I64(lane(&v, index)));
+            }
+            F32x4ExtractLane => {
+                let index = self.fetch_lane_index(module);
+                let v = self.pop_v128()?;
+                self.value_store.push(Value::F32(lane(&v, index)));
+            }
+            F64x2ExtractLane => {
+                let index = self.fetch_lane_index(module);
+                let v = self.pop_v128()?;
+                self.value_store.push(Value::F64(lane(&v, index)));
+            }
+
+            I8x16ReplaceLane => {
+                let index = self.fetch_lane_index(module);
+                let x = self.value_store.pop_i32()?;
+                self.replace_lane(index, x as u8)?;
+            }
+            I16x8ReplaceLane => {
+                let index = self.fetch_lane_index(module);
+                let x = self.value_store.pop_i32()?;
+                self.replace_lane(index, x as u16)?;
+            }
+            I32x4ReplaceLane => {
+                let index = self.fetch_lane_index(module);
+                let x = self.value_store.pop_i32()?;
+                self.replace_lane(index, x)?;
+            }
+            I64x2ReplaceLane => {
+                let index = self.fetch_lane_index(module);
+                let x = self.value_store.pop_i64()?;
+                self.replace_lane(index, x)?;
+            }
+            F32x4ReplaceLane => {
+                let index = self.fetch_lane_index(module);
+                let x = self.value_store.pop_f32()?;
+                self.replace_lane(index, x)?;
+            }
+            F64x2ReplaceLane => {
+                let index = self.fetch_lane_index(module);
+                let x = self.value_store.pop_f64()?;
+                self.replace_lane(index, x)?;
+            }
+
+            I8x16Eq => self.compare::<u8>(|a, b| a == b)?,
+            I8x16Ne => self.compare::<u8>(|a, b| a != b)?,
+            I8x16LtS => self.compare::<i8>(|a, b| a < b)?,
+            I8x16LtU => self.compare::<u8>(|a, b| a < b)?,
+            I8x16GtS => self.compare::<i8>(|a, b| a > b)?,
+            I8x16GtU => self.compare::<u8>(|a, b| a > b)?,
+            I8x16LeS => self.compare::<i8>(|a, b| a <= b)?,
+            I8x16LeU => self.compare::<u8>(|a, b| a <= b)?,
+            I8x16GeS => self.compare::<i8>(|a, b| a >= b)?,
+            I8x16GeU => self.compare::<u8>(|a, b| a >= b)?,
+            I16x8Eq => self.compare::<u16>(|a, b| a == b)?,
+            I16x8Ne => self.compare::<u16>(|a, b| a != b)?,
+            I16x8LtS => self.compare::<i16>(|a, b| a < b)?,
+            I16x8LtU => self.compare::<u16>(|a, b| a < b)?,
+            I16x8GtS => self.compare::<i16>(|a, b| a > b)?,
+            I16x8GtU => self.compare::<u16>(|a, b| a > b)?,
+            I16x8LeS => self.compare::<i16>(|a, b| a <= b)?,
+            I16x8LeU => self.compare::<u16>(|a, b| a <= b)?,
+            I16x8GeS => self.compare::<i16>(|a, b| a >= b)?,
+            I16x8GeU => self.compare::<u16>(|a, b| a >= b)?,
+            I32x4Eq => self.compare::<u32>(|a, b| a == b)?,
+            I32x4Ne => self.compare::<u32>(|a, b| a != b)?,
+            I32x4LtS => self.compare::<i32>(|a, b| a < b)?,
+            I32x4LtU => self.compare::<u32>(|a, b| a < b)?,
+            I32x4GtS => self.compare::<i32>(|a, b| a > b)?,
+            I32x4GtU => self.compare::<u32>(|a, b| a > b)?,
+            I32x4LeS => self.compare::<i32>(|a, b| a <= b)?,
+            I32x4LeU => self.compare::<u32>(|a, b| a <= b)?,
+            I32x4GeS => self.compare::<i32>(|a, b| a >= b)?,
+            I32x4GeU => self.compare::<u32>(|a, b| a >= b)?,
+            I64x2Eq => self.compare::<u64>(|a, b| a == b)?,
+            I64x2Ne => self.compare::<u64>(|a, b| a != b)?,
+            I64x2LtS => self.compare::<i64>(|a, b| a < b)?,
+            I64x2GtS => self.compare::<i64>(|a, b| a > b)?,
+            I64x2LeS => self.compare::<i64>(|a, b| a <= b)?,
+            I64x2GeS => self.compare::<i64>(|a, b| a >= b)?,
+            F32x4Eq => self.compare::<f32>(|a, b| a == b)?,
+            F32x4Ne => self.compare::<f32>(|a, b| a != b)?,
+            F32x4Lt => self.compare::<f32>(|a, b| a < b)?,
+            F32x4Gt => self.compare::<f32>(|a, b| a > b)?,
+            F32x4Le => self.compare::<f32>(|a, b| a <= b)?,
+            F32x4Ge => self.compare::<f32>(|a, b| a >= b)?,
+            F64x2Eq => self.compare::<f64>(|a, b| a == b)?,
+            F64x2Ne => self.compare::<f64>(|a, b| a != b)?,
+            F64x2Lt => self.compare::<f64>(|a, b| a < b)?,
+            F64x2Gt => self.compare::<f64>(|a, b| a > b)?,
+            F64x2Le => self.compare::<f64>(|a, b| a <= b)?,
+            F64x2Ge => self.compare::<f64>(|a, b| a >= b)?,
+
+            V128Not => self.unop::<u8>(|x| !x)?,
+            V128And => self.bitwise(|a, b| a & b)?,
+            V128Andnot => self.bitwise(|a, b| a & !b)?,
+            V128Or => self.bitwise(|a, b| a | b)?,
+            V128Xor => self.bitwise(|a, b| a ^ b)?,
+            V128Bitselect => {
+                let c = self.pop_v128()?;
+                let b = self.pop_v128()?;
+                let a = self.pop_v128()?;
+                let mask = bitwise(a, c, |x, m| x & m);
+                let unmasked = bitwise(b, c, |x, m| x & !m);
+                self.push_v128(bitwise(mask, unmasked, |x, y| x | y));
+            }
+            V128AnyTrue => {
+                let v = self.pop_v128()?;
+                let result = v.iter().any(|byte| *byte != 0);
+                self.value_store.push(Value::I32(result as i32));
+            }
+
+            I8x16AllTrue => self.all_true::<u8>()?,
+            I16x8AllTrue => self.all_true::<u16>()?,
+            I32x4AllTrue => self.all_true::<u32>()?,
+            I64x2AllTrue => self.all_true::<u64>()?,
+            I8x16Bitmask => self.bitmask(1)?,
+            I16x8Bitmask => self.bitmask(2)?,
+            I32x4Bitmask => self.bitmask(4)?,
+            I64x2Bitmask => self.bitmask(8)?,
+
+            I8x16Abs => self.unop::<i8>(|x| x.wrapping_abs())?,
+            I8x16Neg => self.unop::<i8>(|x| x.wrapping_neg())?,
+            I8x16Popcnt => self.unop::<u8>(|x| x.count_ones() as u8)?,
+            I16x8Abs => self.unop::<i16>(|x| x.wrapping_abs())?,
+            I16x8Neg => self.unop::<i16>(|x| x.wrapping_neg())?,
+            I32x4Abs => self.unop::<i32>(|x| x.wrapping_abs())?,
+            I32x4Neg => self.unop::<i32>(|x| x.wrapping_neg())?,
+            I64x2Abs => self.unop::<i64>(|x| x.wrapping_abs())?,
+            I64x2Neg => self.unop::<i64>(|x| x.wrapping_neg())?,
+
+            I8x16Shl => self.shift::<u8>(|x, n| x << n)?,
+            I8x16ShrS => self.shift::<i8>(|x, n| x >> n)?,
+            I8x16ShrU => self.shift::<u8>(|x, n| x >> n)?,
+            I16x8Shl => self.shift::<u16>(|x, n| x << n)?,
+            I16x8ShrS => self.shift::<i16>(|x, n| x >> n)?,
+            I16x8ShrU => self.shift::<u16>(|x, n| x >> n)?,
+            I32x4Shl => self.shift::<u32>(|x, n| x << n)?,
+            I32x4ShrS => self.shift::<i32>(|x, n| x >> n)?,
+            I32x4ShrU => self.shift::<u32>(|x, n| x >> n)?,
+            I64x2Shl => self.shift::<u64>(|x, n| x << n)?,
+            I64x2ShrS => self.shift::<i64>(|x, n| x >> n)?,
+            I64x2ShrU => self.shift::<u64>(|x, n| x >> n)?,
+
+            I8x16Add => self.binop::<u8>(|a, b| a.wrapping_add(b))?,
+            I8x16AddSatS => self.binop::<i8>(|a, b| a.saturating_add(b))?,
+            I8x16AddSatU => self.binop::<u8>(|a, b| a.saturating_add(b))?,
+            I8x16Sub => self.binop::<u8>(|a, b| a.wrapping_sub(b))?,
+            I8x16SubSatS => self.binop::<i8>(|a, b| a.saturating_sub(b))?,
+            I8x16SubSatU => self.binop::<u8>(|a, b| a.saturating_sub(b))?,
+            I8x16MinS => self.binop::<i8>(|a, b| a.min(b))?,
+            I8x16MinU => self.binop::<u8>(|a, b| a.min(b))?,
+            I8x16MaxS => self.binop::<i8>(|a, b| a.max(b))?,
+            I8x16MaxU => self.binop::<u8>(|a, b| a.max(b))?,
+            I8x16AvgrU => self.binop::<u8>(avgr_u8)?,
+            I16x8Add => self.binop::<u16>(|a, b| a.wrapping_add(b))?,
+            I16x8AddSatS => self.binop::<i16>(|a, b| a.saturating_add(b))?,
+            I16x8AddSatU => self.binop::<u16>(|a, b| a.saturating_add(b))?,
+            I16x8Sub => self.binop::<u16>(|a, b| a.wrapping_sub(b))?,
+            I16x8SubSatS => self.binop::<i16>(|a, b| a.saturating_sub(b))?,
+            I16x8SubSatU => self.binop::<u16>(|a, b| a.saturating_sub(b))?,
+            I16x8Mul => self.binop::<u16>(|a, b| a.wrapping_mul(b))?,
+            I16x8MinS => self.binop::<i16>(|a, b| a.min(b))?,
+            I16x8MinU => self.binop::<u16>(|a, b| a.min(b))?,
+            I16x8MaxS => self.binop::<i16>(|a, b| a.max(b))?,
+            I16x8MaxU => self.binop::<u16>(|a, b| a.max(b))?,
+            I16x8AvgrU => self.binop::<u16>(avgr_u16)?,
+            I16x8Q15mulrSatS => self.binop::<i16>(|a, b| {
+                let product = (a as i32 * b as i32 + 0x4000) >> 15;
+                product.clamp(i16::MIN as i32, i16::MAX as i32) as i16
+            })?,
+            I32x4Add => self.binop::<u32>(|a, b| a.wrapping_add(b))?,
+            I32x4Sub => self.binop::<u32>(|a, b| a.wrapping_sub(b))?,
+            I32x4Mul => self.binop::<u32>(|a, b| a.wrapping_mul(b))?,
+            I32x4MinS => self.binop::<i32>(|a, b| a.min(b))?,
+            I32x4MinU => self.binop::<u32>(|a, b| a.min(b))?,
+            I32x4MaxS => self.binop::<i32>(|a, b| a.max(b))?,
+            I32x4MaxU => self.binop::<u32>(|a, b| a.max(b))?,
+            I64x2Add => self.binop::<u64>(|a, b| a.wrapping_add(b))?,
+            I64x2Sub => self.binop::<u64>(|a, b| a.wrapping_sub(b))?,
+            I64x2Mul => self.binop::<u64>(|a, b| a.wrapping_mul(b))?,
+
+            I32x4DotI16x8S => {
+                let b = self.pop_v128()?;
+                let a = self.pop_v128()?;
+                self.push_v128(pairwise(a, b, |a0: i16, a1, b0, b1| {
+                    (a0 as i32 * b0 as i32).wrapping_add(a1 as i32 * b1 as i32)
+                }));
+            }
+            I16x8ExtaddPairwiseI8x16S => {
+                let a = self.pop_v128()?;
+                self.push_v128(pairwise(a, a, |a0: i8, a1, _, _| a0 as i16 + a1 as i16));
+            }
+            I16x8ExtaddPairwiseI8x16U => {
+                let a = self.pop_v128()?;
+                self.push_v128(pairwise(a, a, |a0: u8, a1, _, _| a0 as u16 + a1 as u16));
+            }
+            I32x4ExtaddPairwiseI16x8S => {
+                let a = self.pop_v128()?;
+                self.push_v128(pairwise(a, a, |a0: i16, a1, _, _| a0 as i32 + a1 as i32));
+            }
+            I32x4ExtaddPairwiseI16x8U => {
+                let a = self.pop_v128()?;
+                self.push_v128(pairwise(a, a, |a0: u16, a1, _, _| a0 as u32 + a1 as u32));
+            }
+
+            I16x8ExtmulLowI8x16S => self.extmul::<i8, i16>(false, |a, b| a as i16 * b as i16)?,
+            I16x8ExtmulHighI8x16S => self.extmul::<i8, i16>(true, |a, b| a as i16 * b as i16)?,
+            I16x8ExtmulLowI8x16U => self.extmul::<u8, u16>(false, |a, b| a as u16 * b as u16)?,
+            I16x8ExtmulHighI8x16U => self.extmul::<u8, u16>(true, |a, b| a as u16 * b as u16)?,
+            I32x4ExtmulLowI16x8S => self.extmul::<i16, i32>(false, |a, b| a as i32 * b as i32)?,
+            I32x4ExtmulHighI16x8S => self.extmul::<i16, i32>(true, |a, b| a as i32 * b as i32)?,
+            I32x4ExtmulLowI16x8U => self.extmul::<u16, u32>(false, |a, b| a as u32 * b as u32)?,
+            I32x4ExtmulHighI16x8U => self.extmul::<u16, u32>(true, |a, b| a as u32 * b as u32)?,
+            I64x2ExtmulLowI32x4S => self.extmul::<i32, i64>(false, |a, b| a as i64 * b as i64)?,
+            I64x2ExtmulHighI32x4S => self.extmul::<i32, i64>(true, |a, b| a as i64 * b as i64)?,
+            I64x2ExtmulLowI32x4U => self.extmul::<u32, u64>(false, |a, b| a as u64 * b as u64)?,
+            I64x2ExtmulHighI32x4U => self.extmul::<u32, u64>(true, |a, b| a as u64 * b as u64)?,
+
+            I16x8ExtendLowI8x16S => self.convert::<i8, i16>(8, 0, |x| x as i16)?,
+            I16x8ExtendHighI8x16S => self.convert::<i8, i16>(8, 8, |x| x as i16)?,
+            I16x8ExtendLowI8x16U => self.convert::<u8, u16>(8, 0, |x| x as u16)?,
+            I16x8ExtendHighI8x16U => self.convert::<u8, u16>(8, 8, |x| x as u16)?,
+            I32x4ExtendLowI16x8S => self.convert::<i16, i32>(4, 0, |x| x as i32)?,
+            I32x4ExtendHighI16x8S => self.convert::<i16, i32>(4, 4, |x| x as i32)?,
+            I32x4ExtendLowI16x8U => self.convert::<u16, u32>(4, 0, |x| x as u32)?,
+            I32x4ExtendHighI16x8U => self.convert::<u16, u32>(4, 4, |x| x as u32)?,
+            I64x2ExtendLowI32x4S => self.convert::<i32, i64>(2, 0, |x| x as i64)?,
+            I64x2ExtendHighI32x4S => self.convert::<i32, i64>(2, 2, |x| x as i64)?,
+            I64x2ExtendLowI32x4U => self.convert::<u32, u64>(2, 0, |x| x as u64)?,
+            I64x2ExtendHighI32x4U => self.convert::<u32, u64>(2, 2, |x| x as u64)?,
+
+            I8x16NarrowI16x8S => {
+                self.narrow::<i16, i8>(|x| x.clamp(i8::MIN as i16, i8::MAX as i16) as i8)?
+            }
+            I8x16NarrowI16x8U => self.narrow::<i16, u8>(|x| x.clamp(0, u8::MAX as i16) as u8)?,
+            I16x8NarrowI32x4S => {
+                self.narrow::<i32, i16>(|x| x.clamp(i16::MIN as i32, i16::MAX as i32) as i16)?
+            }
+            I16x8NarrowI32x4U => self.narrow::<i32, u16>(|x| x.clamp(0, u16::MAX as i32) as u16)?,
+
+            F32x4Abs => self.unop::<f32>(|x| x.abs())?,
+            F32x4Neg => self.unop::<f32>(|x| -x)?,
+            F32x4Sqrt => self.unop::<f32>(|x| x.sqrt())?,
+            F32x4Ceil => self.unop::<f32>(|x| x.ceil())?,
+            F32x4Floor => self.unop::<f32>(|x| x.floor())?,
+            F32x4Trunc => self.unop::<f32>(|x| x.trunc())?,
+            F32x4Nearest => self.unop::<f32>(f32_nearest)?,
+            F32x4Add => self.binop::<f32>(|a, b| a + b)?,
+            F32x4Sub => self.binop::<f32>(|a, b| a - b)?,
+            F32x4Mul => self.binop::<f32>(|a, b| a * b)?,
+            F32x4Div => self.binop::<f32>(|a, b| a / b)?,
+            F32x4Min => self.binop::<f32>(f32_min)?,
+            F32x4Max => self.binop::<f32>(f32_max)?,
+            F32x4Pmin => self.binop::<f32>(|a, b| if b < a { b } else { a })?,
+            F32x4Pmax => self.binop::<f32>(|a, b| if a < b { b } else { a })?,
+            F64x2Abs => self.unop::<f64>(|x| x.abs())?,
+            F64x2Neg => self.unop::<f64>(|x| -x)?,
+            F64x2Sqrt => self.unop::<f64>(|x| x.sqrt())?,
+            F64x2Ceil => self.unop::<f64>(|x| x.ceil())?,
+            F64x2Floor => self.unop::<f64>(|x| x.floor())?,
+            F64x2Trunc => self.unop::<f64>(|x| x.trunc())?,
+            F64x2Nearest => self.unop::<f64>(f64_nearest)?,
+            F64x2Add => self.binop::<f64>(|a, b| a + b)?,
+            F64x2Sub => self.binop::<f64>(|a, b| a - b)?,
+            F64x2Mul => self.binop::<f64>(|a, b| a * b)?,
+            F64x2Div => self.binop::<f64>(|a, b| a / b)?,
+            F64x2Min => self.binop::<f64>(f64_min)?,
+            F64x2Max => self.binop::<f64>(f64_max)?,
+            F64x2Pmin => self.binop::<f64>(|a, b| if b < a { b } else { a })?,
+            F64x2Pmax => self.binop::<f64>(|a, b| if a < b { b } else { a })?,
+
+            // Rust's float-to-int casts saturate, and convert NaN to zero, just like Wasm's `trunc_sat`
+            I32x4TruncSatF32x4S => self.convert::<f32, i32>(4, 0, |x| x as i32)?,
+            I32x4TruncSatF32x4U => self.convert::<f32, u32>(4, 0, |x| x as u32)?,
+            I32x4TruncSatF64x2SZero => self.convert::<f64, i32>(2, 0, |x| x as i32)?,
+            I32x4TruncSatF64x2UZero => self.convert::<f64, u32>(2, 0, |x| x as u32)?,
+            F32x4ConvertI32x4S => self.convert::<i32, f32>(4, 0, |x| x as f32)?,
+            F32x4ConvertI32x4U => self.convert::<u32, f32>(4, 0, |x| x as f32)?,
+            F64x2ConvertLowI32x4S => self.convert::<i32, f64>(2, 0, |x| x as f64)?,
+            F64x2ConvertLowI32x4U => self.convert::<u32, f64>(2, 0, |x| x as f64)?,
+            F32x4DemoteF64x2Zero => self.convert::<f64, f32>(2, 0, |x| x as f32)?,
+            F64x2PromoteLowF32x4 => self.convert::<f32, f64>(2, 0, |x| x as f64)?,
+        }
+
+        Ok(())
+    }
+
+    fn pop_v128(&mut self) -> Result<V128, Error> {
+        self.value_store.pop_v128()
+    }
+
+    fn push_v128(&mut self, v: V128) {
+        self.value_store.push(Value::V128(v));
+    }
+
+    fn fetch_v128_immediate(&mut self, module: &WasmModule<'a>) -> V128 {
+        let mut v = [0; 16];
+        v.copy_from_slice(&module.code.bytes[self.program_counter..][..16]);
+        self.program_counter += 16;
+        self.write_debug(v);
+        v
+    }
+
+    fn fetch_lane_index(&mut self, module: &WasmModule<'a>) -> usize {
+        let index = module.code.bytes[self.program_counter];
+        self.program_counter += 1;
+        self.write_debug(index);
+        index as usize
+    }
+
+    /// Read a memarg immediate and pop the base address, checking that `size` bytes are in bounds
+    fn simd_address(&mut self, module: &WasmModule<'a>, size: usize) -> Result<usize, Error> {
+        let _alignment = self.fetch_immediate_u32(module);
+        let offset = self.fetch_immediate_u32(module);
+        let base_addr = self.value_store.pop_u32()?;
+        let addr = base_addr as u64 + offset as u64;
+        let memory_size = self.memory.len() as u32;
+        if addr + size as u64 > memory_size as u64 {
+            Err(Error::MemoryAccessOutOfBounds(addr as u32, memory_size))
+        } else {
+            Ok(addr as usize)
+        }
+    }
+
+    fn load_extend<N: Lane, W: Lane>(
+        &mut self,
+        module: &WasmModule<'a>,
+        f: impl Fn(N) -> W,
+    ) -> Result<(), Error> {
+        let addr = self.simd_address(module, 8)?;
+        let mut loaded = [0; 16];
+        loaded[..8].copy_from_slice(&self.memory[addr..][..8]);
+        self.push_v128(convert(loaded, 0, f));
+        Ok(())
+    }
+
+    fn load_splat<T: Lane>(&mut self, module: &WasmModule<'a>) -> Result<(), Error> {
+        let addr = self.simd_address(module, T::SIZE)?;
+        let x = T::read(&self.memory[addr..][..T::SIZE]);
+        self.push_v128(splat(x));
+        Ok(())
+    }
+
+    fn load_zero<T: Lane>(&mut self, module: &WasmModule<'a>) -> Result<(), Error> {
+        let addr = self.simd_address(module, T::SIZE)?;
+        let mut v = [0; 16];
+        v[..T::SIZE].copy_from_slice(&self.memory[addr..][..T::SIZE]);
+        self.push_v128(v);
+        Ok(())
+    }
+
+    fn load_lane<T: Lane>(&mut self, module: &WasmModule<'a>) -> Result<(), Error> {
+        let mut v = self.pop_v128()?;
+        let addr = self.simd_address(module, T::SIZE)?;
+        let index = self.fetch_lane_index(module);
+        let x = T::read(&self.memory[addr..][..T::SIZE]);
+        set_lane(&mut v, index, x);
+        self.push_v128(v);
+        Ok(())
+    }
+
+    fn store_lane<T: Lane>(&mut self, module: &WasmModule<'a>) -> Result<(), Error> {
+        let v = self.pop_v128()?;
+        let addr = self.simd_address(module, T::SIZE)?;
+        let index = self.fetch_lane_index(module);
+        let x: T = lane(&v, index);
+        x.write(&mut self.memory[addr..][..T::SIZE]);
+        Ok(())
+    }
+
+    fn replace_lane<T: Lane>(&mut self, index: usize, x: T) -> Result<(), Error> {
+        let mut v = self.pop_v128()?;
+        set_lane(&mut v, index, x);
+        self.push_v128(v);
+        Ok(())
+    }
+
+    fn unop<T: Lane>(&mut self, f: impl Fn(T) -> T) -> Result<(), Error> {
+        let a = self.pop_v128()?;
+        self.push_v128(unop(a, f));
+        Ok(())
+    }
+
+    fn binop<T: Lane>(&mut self, f: impl Fn(T, T) -> T) -> Result<(), Error> {
+        let b = self.pop_v128()?;
+        let a = self.pop_v128()?;
+        self.push_v128(binop(a, b, f));
+        Ok(())
+    }
+
+    fn bitwise(&mut self, f: impl Fn(u8, u8) -> u8) -> Result<(), Error> {
+        let b = self.pop_v128()?;
+        let a = self.pop_v128()?;
+        self.push_v128(bitwise(a, b, f));
+        Ok(())
+    }
+
+    fn compare<T: Lane>(&mut self, f: impl Fn(T, T) -> bool) -> Result<(), Error> {
+        let b = self.pop_v128()?;
+        let a = self.pop_v128()?;
+        self.push_v128(compare(a, b, f));
+        Ok(())
+    }
+
+    fn shift<T: Lane>(&mut self, f: impl Fn(T, u32) -> T) -> Result<(), Error> {
+        let amount = self.value_store.pop_u32()?;
+        let a = self.pop_v128()?;
+        self.push_v128(shift(a, amount, f));
+        Ok(())
+    }
+
+    fn all_true<T: Lane + PartialEq + Default>(&mut self) -> Result<(), Error> {
+        let a = self.pop_v128()?;
+        self.value_store.push(Value::I32(all_true::<T>(a) as i32));
+        Ok(())
+    }
+
+    fn bitmask(&mut self, lane_size: usize) -> Result<(), Error> {
+        let a = self.pop_v128()?;
+        self.value_store.push(Value::I32(bitmask(a, lane_size)));
+        Ok(())
+    }
+
+    fn extmul<N: Lane, W: Lane>(
+        &mut self,
+        is_high: bool,
+        f: impl Fn(N, N) -> W,
+    ) -> Result<(), Error> {
+        let b = self.pop_v128()?;
+        let a = self.pop_v128()?;
+        self.push_v128(extend_binop(a, b, is_high, f));
+        Ok(())
+    }
+
+    /// Convert lanes, starting from lane `first` of the input.
+    /// `count` is just there to make the call sites easier to check against the spec.
+    fn convert<S: Lane, D: Lane>(
+        &mut self,
+        count: usize,
+        first: usize,
+        f: impl Fn(S) -> D,
+    ) -> Result<(), Error> {
+        debug_assert_eq!(count, 16 / S::SIZE.max(D::SIZE));
+        let a = self.pop_v128()?;
+        self.push_v128(convert(a, first, f));
+        Ok(())
+    }
+
+    fn narrow<W: Lane, N: Lane>(&mut self, f: impl Fn(W) -> N) -> Result<(), Error> {
+        let b = self.pop_v128()?;
+        let a = self.pop_v128()?;
+        self.push_v128(narrow(a, b, f));
+        Ok(())
+    }
+}
@@ -180,6 +180,60 @@ fn test_if_else_help(condition: i32, expected: i32) {
     assert_eq!(result, Value::I32(expected));
 }
 
+#[test]
+fn test_if_without_else_skipped_twice() {
+    let arena = Bump::new();
+    let mut module = WasmModule::new(&arena);
+
+    let signature = Signature {
+        param_types: bumpalo::vec![in &arena],
+        ret_type: Some(ValueType::I32),
+    };
+    let local_types = [(1, ValueType::I32)];
+    create_exported_function_with_locals(&mut module, "test", signature, &local_types, |buf| {
+        // i32.const 0
+        buf.push(OpCode::I32CONST as u8);
+        buf.encode_i32(0);
+
+        // if <blocktype>
+        buf.push(OpCode::IF as u8);
+        buf.push(ValueType::VOID);
+
+        // i32.const 111
+        buf.push(OpCode::I32CONST as u8);
+        buf.encode_i32(111);
+
+        // local.set 0
+        buf.push(OpCode::SETLOCAL as u8);
+        buf.encode_u32(0);
+
+        // end
+        buf.push(OpCode::END as u8);
+
+        // local.get 0
+        buf.push(OpCode::GETLOCAL as u8);
+        buf.encode_u32(0);
+
+        // end function
+        buf.push(OpCode::END as u8);
+    });
+
+    let is_debug_mode = false;
+    let mut inst = Instance::for_module(
+        &arena,
+        &module,
+        DefaultImportDispatcher::default(),
+        is_debug_mode,
+    )
+    .unwrap();
+
+    // The second call skips the `if` block using the branch cache
+    for _ in 0..2 {
+        let result = inst.call_export("test", []).unwrap().unwrap();
+        assert_eq!(result, Value::I32(0));
+    }
+}
+
 #[test]
 fn test_br() {
     let start_fn_name = "test";
@@ -786,6 +840,54 @@ fn test_call_indirect_help(table_index: u32, elem_index: u32) -> Value {
     inst.call_export(start_fn_name, []).unwrap().unwrap()
 }
 
+#[test]
+fn test_table_copy() {
+    let arena = Bump::new();
+    let mut module = WasmModule::new(&arena);
+
+    let signature = || Signature {
+        param_types: bumpalo::vec![in &arena],
+        ret_type: Some(ValueType::I32),
+    };
+
+    // function 0: copy table entry 0 over entry 1, then call entry 1
+    create_exported_function_no_locals(&mut module, "test", signature(), |buf| {
+        buf.append_u8(OpCode::I32CONST as u8);
+        buf.encode_i32(1); // destination
+        buf.append_u8(OpCode::I32CONST as u8);
+        buf.encode_i32(0); // source
+        buf.append_u8(OpCode::I32CONST as u8);
+        buf.encode_i32(1); // size
+        buf.append_u8(OpCode::MEMORY as u8);
+        buf.append_u8(14); // table.copy
+        buf.encode_u32(0);
+        buf.encode_u32(0);
+        buf.append_u8(OpCode::I32CONST as u8);
+        buf.encode_i32(1);
+        buf.append_u8(OpCode::CALLINDIRECT as u8);
+        buf.encode_u32(0); // signature index
+        buf.encode_u32(0); // table index
+        buf.append_u8(OpCode::END as u8);
+    });
+
+    for (name, value) in [("callee1", 111), ("callee2", 222)] {
+        create_exported_function_no_locals(&mut module, name, signature(), |buf| {
+            buf.append_u8(OpCode::I32CONST as u8);
+            buf.encode_i32(value);
+            buf.append_u8(OpCode::END as u8);
+        });
+    }
+
+    module.element.segments.push(ElementSegment::new(&arena));
+    assert_eq!(module.element.get_or_insert_fn(1), 0);
+    assert_eq!(module.element.get_or_insert_fn(2), 1);
+
+    let mut inst =
+        Instance::for_module(&arena, &module, DefaultImportDispatcher::default(), false).unwrap();
+    let result = inst.call_export("test", []).unwrap().unwrap();
+    assert_eq!(result, Value::I32(111));
+}
+
 // #[test]
 // fn test_drop() {}
 
@@ -0,0 +1,198 @@
+use super::create_exported_function_no_locals;
+use crate::debugger::{self, Breakpoint, Resume, StopReason};
+use crate::{Debugger, DefaultImportDispatcher, Instance};
+use bumpalo::Bump;
+use roc_wasm_module::{opcodes::OpCode, Signature, Value, ValueType, WasmModule};
+
+/// Function 0 `main` computes `add(2, 3) * 10` and function 1 is `add`.
+/// Only `add` has a name in the name section.
+fn create_module(arena: &Bump) -> WasmModule<'_> {
+    let mut module = WasmModule::new(arena);
+
+    let signature = Signature {
+        param_types: bumpalo::vec![in arena],
+        ret_type: Some(ValueType::I32),
+    };
+    create_exported_function_no_locals(&mut module, "main", signature, |buf| {
+        buf.push(OpCode::I32CONST as u8);
+        buf.push(2);
+        buf.push(OpCode::I32CONST as u8);
+        buf.push(3);
+        buf.push(OpCode::CALL as u8);
+        buf.push(1);
+        buf.push(OpCode::I32CONST as u8);
+        buf.push(10);
+        buf.push(OpCode::I32MUL as u8);
+        buf.push(OpCode::END as u8);
+    });
+
+    let signature = Signature {
+        param_types: bumpalo::vec![in arena; ValueType::I32, ValueType::I32],
+        ret_type: Some(ValueType::I32),
+    };
+    create_exported_function_no_locals(&mut module, "add_export", signature, |buf| {
+        buf.push(OpCode::GETLOCAL as u8);
+        buf.push(0);
+        buf.push(OpCode::GETLOCAL as u8);
+        buf.push(1);
+        buf.push(OpCode::I32ADD as u8);
+        buf.push(OpCode::END as u8);
+    });
+    module.names.function_names.push((1, "add"));
+
+    module
+}
+
+/// File offset of the first instruction in a function created by `create_exported_function_no_locals`
+fn body_start(module: &WasmModule<'_>, fn_index: usize) -> u32 {
+    let padded_u32_size = 5;
+    let no_locals_size = 1;
+    module.code.section_offset
+        + module.code.function_offsets[fn_index]
+        + padded_u32_size
+        + no_locals_size
+}
+
+#[test]
+fn test_break_on_function_name() {
+    let arena = Bump::new();
+    let module = create_module(&arena);
+    let mut inst =
+        Instance::for_module(&arena, &module, DefaultImportDispatcher::default(), false).unwrap();
+    let mut debugger = Debugger::new();
+
+    assert_eq!(debugger.add_breakpoint(&module, "add"), Ok(0));
+    assert_eq!(
+        debugger.breakpoints().collect::<std::vec::Vec<_>>(),
+        [(0, Breakpoint::Function(1))]
+    );
+
+    inst.start_export("main", []).unwrap();
+    assert_eq!(inst.current_function(), 0);
+
+    let stop = debugger.resume(&mut inst, Resume::Continue).unwrap();
+    assert_eq!(stop, StopReason::Breakpoint(0));
+    assert_eq!(inst.current_function(), 1);
+    assert_eq!(inst.function_name(1), Some("add"));
+    assert_eq!(inst.call_depth(), 1);
+    assert_eq!(inst.locals(), [Value::I32(2), Value::I32(3)]);
+    assert_eq!(inst.value_stack(), []);
+
+    let stop = debugger.resume(&mut inst, Resume::Finish).unwrap();
+    assert_eq!(stop, StopReason::Step);
+    assert_eq!(inst.current_function(), 0);
+    assert_eq!(inst.value_stack(), [Value::I32(5)]);
+
+    let stop = debugger.resume(&mut inst, Resume::Continue).unwrap();
+    assert_eq!(stop, StopReason::Exit(Some(Value::I32(50))));
+    assert!(!inst.is_running());
+    assert!(debugger.resume(&mut inst, Resume::Step).is_err());
+}
+
+#[test]
+fn test_step_and_next() {
+    let arena = Bump::new();
+    let module = create_module(&arena);
+    let mut inst =
+        Instance::for_module(&arena, &module, DefaultImportDispatcher::default(), false).unwrap();
+    let debugger = Debugger::new();
+
+    // `next` steps over the call
+    inst.start_export("main", []).unwrap();
+    for _ in 0..3 {
+        let stop = debugger.resume(&mut inst, Resume::Next).unwrap();
+        assert_eq!(stop, StopReason::Step);
+    }
+    assert_eq!(inst.current_function(), 0);
+    assert_eq!(inst.value_stack(), [Value::I32(5)]);
+
+    // `step` steps into it
+    inst.start_export("main", []).unwrap();
+    for _ in 0..3 {
+        debugger.resume(&mut inst, Resume::Step).unwrap();
+    }
+    assert_eq!(inst.current_function(), 1);
+    assert_eq!(inst.current_file_offset(), body_start(&module, 1) as usize);
+    assert!(inst.debug_location().ends_with("GETLOCAL"));
+}
+
+#[test]
+fn test_break_on_address() {
+    let arena = Bump::new();
+    let module = create_module(&arena);
+    let mut inst =
+        Instance::for_module(&arena, &module, DefaultImportDispatcher::default(), false).unwrap();
+    let mut debugger = Debugger::new();
+
+    // The I32MUL instruction, after the call returns
+    let mul_offset = body_start(&module, 0) + 8;
+    let location = format!("{mul_offset:#x}");
+    assert_eq!(debugger.add_breakpoint(&module, &location), Ok(0));
+
+    // In the middle of the I32CONST instruction before it
+    let location = format!("{}", mul_offset - 1);
+    assert!(debugger.add_breakpoint(&module, &location).is_err());
+    assert!(debugger.add_breakpoint(&module, "0xffffff").is_err());
+    assert!(debugger.add_breakpoint(&module, "nonexistent").is_err());
+
+    inst.start_export("main", []).unwrap();
+    let stop = debugger.resume(&mut inst, Resume::Continue).unwrap();
+    assert_eq!(stop, StopReason::Breakpoint(0));
+    assert_eq!(inst.current_file_offset(), mul_offset as usize);
+    assert_eq!(inst.value_stack(), [Value::I32(5), Value::I32(10)]);
+
+    assert!(debugger.delete_breakpoint(0));
+    assert!(!debugger.delete_breakpoint(0));
+    assert_eq!(debugger.breakpoints().count(), 0);
+}
+
+#[test]
+fn test_read_roc_str() {
+    let mut memory = [0; 128];
+
+    // Small string, stored inline with the length in the last byte
+    memory[..5].copy_from_slice(b"hello");
+    memory[11] = 0x80 | 5;
+    assert_eq!(debugger::read_roc_str(&memory, 0).unwrap(), b"hello");
+
+    // Heap string
+    let text = b"a string that's too big to be small";
+    memory[64..][..text.len()].copy_from_slice(text);
+    memory[16..20].copy_from_slice(&64u32.to_le_bytes());
+    memory[20..24].copy_from_slice(&(text.len() as u32).to_le_bytes());
+    memory[24..28].copy_from_slice(&(text.len() as u32).to_le_bytes());
+    assert_eq!(debugger::read_roc_str(&memory, 16).unwrap(), text);
+
+    // Seamless slice of the first 8 bytes
+    memory[20..24].copy_from_slice(&(8u32 | 0x8000_0000).to_le_bytes());
+    assert_eq!(debugger::read_roc_str(&memory, 16).unwrap(), b"a string");
+
+    // Out of bounds
+    memory[20..24].copy_from_slice(&1000u32.to_le_bytes());
+    assert!(debugger::read_roc_str(&memory, 16).is_err());
+}
+
+#[test]
+fn test_read_roc_list() {
+    let mut memory = [0; 64];
+    memory[0..4].copy_from_slice(&32u32.to_le_bytes());
+    memory[4..8].copy_from_slice(&3u32.to_le_bytes());
+    memory[8..12].copy_from_slice(&3u32.to_le_bytes());
+    memory[32..38].copy_from_slice(&[1, 0, 2, 0, 3, 0]);
+
+    let elements = debugger::read_roc_list(&memory, 0, 2).unwrap();
+    assert_eq!(elements, [[1, 0], [2, 0], [3, 0]]);
+    assert!(debugger::read_roc_list(&memory, 0, 0).is_err());
+    assert!(debugger::read_roc_list(&memory, 60, 1).is_err());
+}
+
+#[test]
+fn test_hex_dump() {
+    let memory: std::vec::Vec<u8> = (0..=255).collect();
+    let dump = debugger::hex_dump(&memory, 0x10, 20).unwrap();
+    assert_eq!(
+        dump,
+        "00000010: 10 11 12 13 14 15 16 17 18 19 1a 1b 1c 1d 1e 1f\n00000020: 20 21 22 23\n"
+    );
+    assert!(debugger::hex_dump(&memory, 250, 10).is_err());
+}
@@ -0,0 +1,171 @@
+use super::create_exported_function_no_locals;
+use crate::{CallError, DefaultImportDispatcher, Instance, Limit, Limits};
+use bumpalo::Bump;
+use roc_wasm_module::{
+    opcodes::OpCode, sections::MemorySection, SerialBuffer, Signature, Value, ValueType, WasmModule,
+};
+
+/// Function 0 `forever` loops forever, function 1 `recurse` calls itself until its argument is 0,
+/// and function 2 `grow` grows memory by its argument, in pages.
+fn create_module(arena: &Bump) -> WasmModule<'_> {
+    let mut module = WasmModule::new(arena);
+    module.memory = MemorySection::new(arena, MemorySection::PAGE_SIZE);
+
+    let signature = Signature {
+        param_types: bumpalo::vec![in arena],
+        ret_type: None,
+    };
+    create_exported_function_no_locals(&mut module, "forever", signature, |buf| {
+        buf.push(OpCode::LOOP as u8);
+        buf.push(ValueType::VOID);
+        buf.push(OpCode::BR as u8);
+        buf.push(0);
+        buf.push(OpCode::END as u8);
+        buf.push(OpCode::END as u8);
+    });
+
+    let signature = Signature {
+        param_types: bumpalo::vec![in arena; ValueType::I32],
+        ret_type: None,
+    };
+    create_exported_function_no_locals(&mut module, "recurse", signature, |buf| {
+        buf.push(OpCode::GETLOCAL as u8);
+        buf.push(0);
+        buf.push(OpCode::IF as u8);
+        buf.push(ValueType::VOID);
+        buf.push(OpCode::GETLOCAL as u8);
+        buf.push(0);
+        buf.push(OpCode::I32CONST as u8);
+        buf.push(1);
+        buf.push(OpCode::I32SUB as u8);
+        buf.push(OpCode::CALL as u8);
+        buf.push(1);
+        buf.push(OpCode::END as u8);
+        buf.push(OpCode::END as u8);
+    });
+
+    let signature = Signature {
+        param_types: bumpalo::vec![in arena; ValueType::I32],
+        ret_type: Some(ValueType::I32),
+    };
+    create_exported_function_no_locals(&mut module, "grow", signature, |buf| {
+        buf.push(OpCode::GETLOCAL as u8);
+        buf.push(0);
+        buf.push(OpCode::GROWMEMORY as u8);
+        buf.encode_u32(0);
+        buf.push(OpCode::END as u8);
+    });
+
+    module
+}
+
+fn expect_limit_exceeded(result: Result<Option<Value>, CallError>, expected: Limit) {
+    match result {
+        Err(CallError::LimitExceeded(limit, _)) => assert_eq!(limit, expected),
+        other => panic!("Expected {expected:?} limit to be exceeded, but got {other:?}"),
+    }
+}
+
+#[test]
+fn test_fuel() {
+    let arena = Bump::new();
+    let module = create_module(&arena);
+    let limits = Limits {
+        fuel: Some(1000),
+        ..Default::default()
+    };
+    let mut inst = Instance::for_module_with_limits(
+        &arena,
+        &module,
+        DefaultImportDispatcher::default(),
+        false,
+        limits,
+    )
+    .unwrap();
+
+    expect_limit_exceeded(inst.call_export("forever", []), Limit::Fuel);
+    assert_eq!(inst.remaining_fuel(), Some(0));
+
+    // `grow` executes 3 instructions
+    inst.set_fuel(Some(2));
+    expect_limit_exceeded(inst.call_export("grow", [Value::I32(0)]), Limit::Fuel);
+    inst.set_fuel(Some(3));
+    let result = inst.call_export("grow", [Value::I32(0)]).unwrap();
+    assert_eq!(result, Some(Value::I32(1)));
+    assert_eq!(inst.remaining_fuel(), Some(0));
+}
+
+#[test]
+fn test_max_call_depth() {
+    let arena = Bump::new();
+    let module = create_module(&arena);
+    let limits = Limits {
+        max_call_depth: Some(10),
+        ..Default::default()
+    };
+    let mut inst = Instance::for_module_with_limits(
+        &arena,
+        &module,
+        DefaultImportDispatcher::default(),
+        false,
+        limits,
+    )
+    .unwrap();
+
+    // 10 nested calls: recurse(9) down to recurse(0)
+    inst.call_export("recurse", [Value::I32(9)]).unwrap();
+    expect_limit_exceeded(
+        inst.call_export("recurse", [Value::I32(10)]),
+        Limit::CallDepth,
+    );
+}
+
+#[test]
+fn test_max_memory_pages() {
+    let arena = Bump::new();
+    let module = create_module(&arena);
+    let limits = Limits {
+        max_memory_pages: Some(3),
+        ..Default::default()
+    };
+    let mut inst = Instance::for_module_with_limits(
+        &arena,
+        &module,
+        DefaultImportDispatcher::default(),
+        false,
+        limits,
+    )
+    .unwrap();
+
+    let result = inst.call_export("grow", [Value::I32(2)]).unwrap();
+    assert_eq!(result, Some(Value::I32(1)));
+
+    // Past the limit, memory.grow fails and the program carries on
+    let result = inst.call_export("grow", [Value::I32(1)]).unwrap();
+    assert_eq!(result, Some(Value::I32(-1)));
+    let result = inst.call_export("grow", [Value::I32(-1)]).unwrap();
+    assert_eq!(result, Some(Value::I32(-1)));
+    assert_eq!(inst.memory.len(), 3 * MemorySection::PAGE_SIZE as usize);
+
+    let result = inst.call_export("grow", [Value::I32(0)]).unwrap();
+    assert_eq!(result, Some(Value::I32(3)));
+}
+
+#[test]
+fn test_initial_memory_over_limit() {
+    let arena = Bump::new();
+    let mut module = create_module(&arena);
+    module.memory = MemorySection::new(&arena, 4 * MemorySection::PAGE_SIZE);
+    let limits = Limits {
+        max_memory_pages: Some(3),
+        ..Default::default()
+    };
+    let result = Instance::for_module_with_limits(
+        &arena,
+        &module,
+        DefaultImportDispatcher::default(),
+        false,
+        limits,
+    );
+    assert!(result.is_err());
+}
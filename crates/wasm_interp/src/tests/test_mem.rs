@@ -1,4 +1,4 @@
-use super::create_exported_function_no_locals;
+use super::{const_value, create_exported_function_no_locals};
 use crate::{DefaultImportDispatcher, Instance};
 use bumpalo::{collections::Vec, Bump};
 use roc_wasm_module::{
@@ -119,6 +119,105 @@ fn test_memory_copy() {
     assert_eq!(dest_slice, &[0xAA; SIZE as usize])
 }
 
+#[test]
+fn test_memory_init_and_data_drop() {
+    let arena = Bump::new();
+    let mut module = WasmModule::new(&arena);
+    module.memory = MemorySection::new(&arena, MemorySection::PAGE_SIZE);
+
+    module.data.append_segment(DataSegment {
+        mode: DataMode::active_at(0),
+        init: Vec::from_iter_in([0xff; 4], &arena),
+    });
+    let passive_index = module.data.append_segment(DataSegment {
+        mode: DataMode::Passive,
+        init: Vec::from_iter_in([1, 2, 3, 4, 5, 6, 7, 8], &arena),
+    });
+
+    // memory.init(dst, src, size) followed by data.drop
+    let signature = Signature {
+        param_types: bumpalo::vec![in &arena; ValueType::I32, ValueType::I32, ValueType::I32],
+        ret_type: None,
+    };
+    create_exported_function_no_locals(&mut module, "init_and_drop", signature, |buf| {
+        for local in 0..3 {
+            buf.push(OpCode::GETLOCAL as u8);
+            buf.encode_u32(local);
+        }
+        buf.push(OpCode::MEMORY as u8);
+        buf.push(8);
+        buf.encode_u32(passive_index);
+        buf.push(0);
+        buf.push(OpCode::MEMORY as u8);
+        buf.push(9);
+        buf.encode_u32(passive_index);
+        buf.push(OpCode::END as u8);
+    });
+
+    let mut inst =
+        Instance::for_module(&arena, &module, DefaultImportDispatcher::default(), false).unwrap();
+
+    // The active segment is loaded on instantiation, the passive one is not
+    assert_eq!(&inst.memory[0..8], &[0xff, 0xff, 0xff, 0xff, 0, 0, 0, 0]);
+
+    let args = [Value::I32(16), Value::I32(2), Value::I32(5)];
+    inst.call_export("init_and_drop", args).unwrap();
+    assert_eq!(&inst.memory[16..24], &[3, 4, 5, 6, 7, 0, 0, 0]);
+
+    // After data.drop, the segment behaves as if it were empty
+    let args = [Value::I32(32), Value::I32(0), Value::I32(0)];
+    inst.call_export("init_and_drop", args).unwrap();
+
+    let args = [Value::I32(32), Value::I32(0), Value::I32(1)];
+    assert!(inst.call_export("init_and_drop", args).is_err());
+}
+
+#[test]
+fn test_memory_init_out_of_bounds() {
+    let arena = Bump::new();
+    let mut module = WasmModule::new(&arena);
+    module.memory = MemorySection::new(&arena, MemorySection::PAGE_SIZE);
+
+    module.data.append_segment(DataSegment {
+        mode: DataMode::Passive,
+        init: Vec::from_iter_in([1, 2, 3, 4], &arena),
+    });
+
+    let signature = Signature {
+        param_types: bumpalo::vec![in &arena; ValueType::I32, ValueType::I32, ValueType::I32],
+        ret_type: None,
+    };
+    create_exported_function_no_locals(&mut module, "init", signature, |buf| {
+        for local in 0..3 {
+            buf.push(OpCode::GETLOCAL as u8);
+            buf.encode_u32(local);
+        }
+        buf.push(OpCode::MEMORY as u8);
+        buf.push(8);
+        buf.encode_u32(0);
+        buf.push(0);
+        buf.push(OpCode::END as u8);
+    });
+
+    let mut inst =
+        Instance::for_module(&arena, &module, DefaultImportDispatcher::default(), false).unwrap();
+
+    // Reading past the end of the segment
+    let args = [Value::I32(0), Value::I32(2), Value::I32(3)];
+    assert!(inst.call_export("init", args).is_err());
+
+    // Writing past the end of memory
+    let args = [
+        Value::I32(MemorySection::PAGE_SIZE as i32 - 2),
+        Value::I32(0),
+        Value::I32(4),
+    ];
+    assert!(inst.call_export("init", args).is_err());
+
+    // Failed instructions have no side effects
+    assert!(inst.memory.iter().all(|b| *b == 0));
+}
+
 fn test_load(load_op: OpCode, ty: ValueType, data: &[u8], addr: u32, offset: u32) -> Value {
     let arena = Bump::new();
     let mut module = WasmModule::new(&arena);
@@ -348,6 +447,7 @@ fn test_store<'a>(
                 buf.append_u8(OpCode::F64CONST as u8);
                 buf.encode_f64(x);
             }
+            Value::V128(_) => const_value(buf, value),
         }
         buf.append_u8(store_op as u8);
         buf.encode_u32(0); // align
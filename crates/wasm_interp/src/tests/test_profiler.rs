@@ -0,0 +1,143 @@
+use super::create_exported_function_no_locals;
+use crate::profiler::{CallEdge, FunctionStats};
+use crate::{DefaultImportDispatcher, Instance};
+use bumpalo::Bump;
+use roc_wasm_module::{opcodes::OpCode, Signature, Value, ValueType, WasmModule};
+
+/// Function 0 `main` calls function 1, a recursive `fact`, with argument 3
+fn create_module(arena: &Bump) -> WasmModule<'_> {
+    let mut module = WasmModule::new(arena);
+
+    let signature = Signature {
+        param_types: bumpalo::vec![in arena],
+        ret_type: Some(ValueType::I32),
+    };
+    create_exported_function_no_locals(&mut module, "main", signature, |buf| {
+        buf.push(OpCode::I32CONST as u8);
+        buf.push(3);
+        buf.push(OpCode::CALL as u8);
+        buf.push(1);
+        buf.push(OpCode::END as u8);
+    });
+
+    let signature = Signature {
+        param_types: bumpalo::vec![in arena; ValueType::I32],
+        ret_type: Some(ValueType::I32),
+    };
+    create_exported_function_no_locals(&mut module, "fact", signature, |buf| {
+        buf.push(OpCode::GETLOCAL as u8);
+        buf.push(0);
+        buf.push(OpCode::I32EQZ as u8);
+        buf.push(OpCode::IF as u8);
+        buf.push(ValueType::VOID);
+        buf.push(OpCode::I32CONST as u8);
+        buf.push(1);
+        buf.push(OpCode::RETURN as u8);
+        buf.push(OpCode::END as u8);
+        buf.push(OpCode::GETLOCAL as u8);
+        buf.push(0);
+        buf.push(OpCode::GETLOCAL as u8);
+        buf.push(0);
+        buf.push(OpCode::I32CONST as u8);
+        buf.push(1);
+        buf.push(OpCode::I32SUB as u8);
+        buf.push(OpCode::CALL as u8);
+        buf.push(1);
+        buf.push(OpCode::I32MUL as u8);
+        buf.push(OpCode::END as u8);
+    });
+
+    module.names.function_names.push((0, "main"));
+    module.names.function_names.push((1, "fact"));
+    module
+}
+
+// Instruction counts for each call
+const MAIN: u64 = 3;
+const FACT_N: u64 = 10;
+const FACT_0: u64 = 5;
+
+#[test]
+fn test_profile_recursion() {
+    let arena = Bump::new();
+    let module = create_module(&arena);
+    let mut inst =
+        Instance::for_module(&arena, &module, DefaultImportDispatcher::default(), false).unwrap();
+    inst.enable_profiling();
+
+    let result = inst.call_export("main", []).unwrap();
+    assert_eq!(result, Some(Value::I32(6)));
+
+    let profile = inst.profile().unwrap();
+    let fact_total = 3 * FACT_N + FACT_0;
+    assert_eq!(profile.total_instructions(), MAIN + fact_total);
+
+    assert_eq!(
+        profile.function_stats(),
+        [
+            FunctionStats {
+                calls: 1,
+                exclusive: MAIN,
+                inclusive: MAIN + fact_total,
+            },
+            FunctionStats {
+                calls: 4,
+                exclusive: fact_total,
+                inclusive: fact_total,
+            },
+        ]
+    );
+
+    assert_eq!(
+        profile.call_edges(),
+        [
+            CallEdge {
+                caller: 0,
+                callee: 1,
+                calls: 1,
+                inclusive: fact_total,
+            },
+            CallEdge {
+                caller: 1,
+                callee: 1,
+                calls: 3,
+                inclusive: fact_total - FACT_N,
+            },
+        ]
+    );
+
+    assert_eq!(
+        profile.collapsed_stacks(&module),
+        format!(
+            "main {MAIN}\n\
+            main;fact {FACT_N}\n\
+            main;fact;fact {FACT_N}\n\
+            main;fact;fact;fact {FACT_N}\n\
+            main;fact;fact;fact;fact {FACT_0}\n"
+        )
+    );
+
+    let report = profile.flat_report(&module);
+    assert!(report.starts_with(&format!("Executed {} instructions", MAIN + fact_total)));
+    assert!(report.contains("func[0] main -> func[1] fact"));
+}
+
+#[test]
+fn test_profile_accumulates() {
+    let arena = Bump::new();
+    let module = create_module(&arena);
+    let mut inst =
+        Instance::for_module(&arena, &module, DefaultImportDispatcher::default(), false).unwrap();
+
+    inst.call_export("main", []).unwrap();
+    assert!(inst.profile().is_none());
+
+    inst.enable_profiling();
+    inst.call_export("main", []).unwrap();
+    inst.call_export("fact", [Value::I32(0)]).unwrap();
+
+    let stats = inst.profile().unwrap().function_stats();
+    assert_eq!(stats[0].calls, 1);
+    assert_eq!(stats[1].calls, 5);
+    assert_eq!(stats[1].inclusive, 3 * FACT_N + 2 * FACT_0);
+}
@@ -0,0 +1,453 @@
+use super::{const_value, create_exported_function_no_locals};
+use crate::{DefaultImportDispatcher, Instance};
+use bumpalo::{collections::Vec, Bump};
+use roc_wasm_module::{
+    opcodes::{OpCode, SimdInstruction},
+    sections::MemorySection,
+    SerialBuffer, Signature, Value, ValueType, WasmModule,
+};
+
+fn v128_from<const N: usize, T>(lanes: [T; N], to_bytes: impl Fn(T) -> std::vec::Vec<u8>) -> Value {
+    let bytes: std::vec::Vec<u8> = lanes.into_iter().flat_map(to_bytes).collect();
+    let mut v = [0; 16];
+    v.copy_from_slice(&bytes);
+    Value::V128(v)
+}
+
+fn i8x16(lanes: [i8; 16]) -> Value {
+    v128_from(lanes, |x| x.to_le_bytes().to_vec())
+}
+
+fn i16x8(lanes: [i16; 8]) -> Value {
+    v128_from(lanes, |x| x.to_le_bytes().to_vec())
+}
+
+fn i32x4(lanes: [i32; 4]) -> Value {
+    v128_from(lanes, |x| x.to_le_bytes().to_vec())
+}
+
+fn i64x2(lanes: [i64; 2]) -> Value {
+    v128_from(lanes, |x| x.to_le_bytes().to_vec())
+}
+
+fn f32x4(lanes: [f32; 4]) -> Value {
+    v128_from(lanes, |x| x.to_le_bytes().to_vec())
+}
+
+fn push_simd_op(buf: &mut Vec<'_, u8>, op: SimdInstruction) {
+    buf.push(OpCode::SIMD as u8);
+    buf.encode_u32(op as u32);
+}
+
+/// Run a function that pushes the arguments, executes one SIMD instruction, and returns the result
+fn test_simd_example<A>(op: SimdInstruction, immediates: &[u8], args: A, expected: Value)
+where
+    A: IntoIterator<Item = Value>,
+{
+    let arena = Bump::new();
+    let mut module = WasmModule::new(&arena);
+    module.memory = MemorySection::new(&arena, MemorySection::PAGE_SIZE);
+
+    let signature = Signature {
+        param_types: bumpalo::vec![in &arena],
+        ret_type: Some(ValueType::from(expected)),
+    };
+    create_exported_function_no_locals(&mut module, "test", signature, |buf| {
+        for arg in args {
+            const_value(buf, arg);
+        }
+        push_simd_op(buf, op);
+        buf.extend_from_slice(immediates);
+        buf.push(OpCode::END as u8);
+    });
+
+    let mut inst =
+        Instance::for_module(&arena, &module, DefaultImportDispatcher::default(), false).unwrap();
+    let result = inst.call_export("test", []).unwrap();
+    assert_eq!(result, Some(expected), "{op:?}");
+}
+
+#[test]
+fn test_integer_arithmetic() {
+    use SimdInstruction::*;
+
+    let mut a = [0; 16];
+    let mut b = [0; 16];
+    a[0] = 127;
+    b[0] = 1;
+    a[15] = -100;
+    b[15] = -100;
+    let mut wrapped = [0; 16];
+    wrapped[0] = -128;
+    wrapped[15] = 56;
+    let mut saturated = [0; 16];
+    saturated[0] = 127;
+    saturated[15] = -128;
+    test_simd_example(I8x16Add, &[], [i8x16(a), i8x16(b)], i8x16(wrapped));
+    test_simd_example(I8x16AddSatS, &[], [i8x16(a), i8x16(b)], i8x16(saturated));
+
+    test_simd_example(
+        I16x8Mul,
+        &[],
+        [
+            i16x8([1, 2, 3, 4, 5, 6, 7, 0x4000]),
+            i16x8([2, 2, 2, 2, -2, -2, -2, 4]),
+        ],
+        i16x8([2, 4, 6, 8, -10, -12, -14, 0]),
+    );
+    test_simd_example(
+        I32x4MinU,
+        &[],
+        [i32x4([1, -1, 5, 0]), i32x4([2, 3, -5, 0])],
+        i32x4([1, 3, 5, 0]),
+    );
+    test_simd_example(
+        I32x4MinS,
+        &[],
+        [i32x4([1, -1, 5, 0]), i32x4([2, 3, -5, 0])],
+        i32x4([1, -1, -5, 0]),
+    );
+    test_simd_example(
+        I64x2Sub,
+        &[],
+        [i64x2([i64::MIN, 10]), i64x2([1, 20])],
+        i64x2([i64::MAX, -10]),
+    );
+    test_simd_example(
+        I32x4DotI16x8S,
+        &[],
+        [
+            i16x8([1, 2, 3, 4, -5, 6, i16::MIN, i16::MIN]),
+            i16x8([10, 100, 1, 1, 2, 2, i16::MIN, i16::MIN]),
+        ],
+        i32x4([210, 7, 2, i32::MIN]),
+    );
+}
+
+#[test]
+fn test_shifts() {
+    use SimdInstruction::*;
+
+    let v = i32x4([1, -8, i32::MIN, 3]);
+    test_simd_example(I32x4Shl, &[], [v, Value::I32(33)], i32x4([2, -16, 0, 6]));
+    test_simd_example(
+        I32x4ShrS,
+        &[],
+        [v, Value::I32(1)],
+        i32x4([0, -4, i32::MIN / 2, 1]),
+    );
+    test_simd_example(
+        I32x4ShrU,
+        &[],
+        [v, Value::I32(1)],
+        i32x4([0, 0x7fff_fffc, 0x4000_0000, 1]),
+    );
+}
+
+#[test]
+fn test_comparisons() {
+    use SimdInstruction::*;
+
+    let a = i32x4([1, -1, 5, 7]);
+    let b = i32x4([2, 3, 5, -7]);
+    test_simd_example(I32x4LtS, &[], [a, b], i32x4([-1, -1, 0, 0]));
+    test_simd_example(I32x4LtU, &[], [a, b], i32x4([-1, 0, 0, -1]));
+    test_simd_example(I32x4Eq, &[], [a, b], i32x4([0, 0, -1, 0]));
+
+    let nan = f32x4([f32::NAN, 1.0, 2.0, 0.0]);
+    let other = f32x4([f32::NAN, 1.0, 3.0, -0.0]);
+    test_simd_example(F32x4Eq, &[], [nan, other], i32x4([0, -1, 0, -1]));
+    test_simd_example(F32x4Ne, &[], [nan, other], i32x4([-1, 0, -1, 0]));
+}
+
+#[test]
+fn test_boolean_reductions() {
+    use SimdInstruction::*;
+
+    test_simd_example(V128AnyTrue, &[], [i32x4([0, 0, 0, 0])], Value::I32(0));
+    test_simd_example(V128AnyTrue, &[], [i32x4([0, 0, 256, 0])], Value::I32(1));
+    test_simd_example(I32x4AllTrue, &[], [i32x4([1, 2, 3, 0])], Value::I32(0));
+    test_simd_example(I32x4AllTrue, &[], [i32x4([1, 2, 3, -1])], Value::I32(1));
+    test_simd_example(I16x8AllTrue, &[], [i32x4([1, 1, 1, 1])], Value::I32(0));
+    test_simd_example(
+        I16x8Bitmask,
+        &[],
+        [i16x8([-1, 1, -1, 1, 0, 0, 0, i16::MIN])],
+        Value::I32(0b1000_0101),
+    );
+    test_simd_example(
+        V128Bitselect,
+        &[],
+        [
+            i32x4([0x1111_1111, 0, 0, 0]),
+            i32x4([0x2222_2222, 0, 0, 0]),
+            i32x4([0x0000_ffff, 0, 0, 0]),
+        ],
+        i32x4([0x2222_1111, 0, 0, 0]),
+    );
+}
+
+#[test]
+fn test_lanes() {
+    use SimdInstruction::*;
+
+    let v = i16x8([0, -1, 2, 3, 4, 5, 6, 7]);
+    test_simd_example(I16x8ExtractLaneS, &[1], [v], Value::I32(-1));
+    test_simd_example(I16x8ExtractLaneU, &[1], [v], Value::I32(0xffff));
+    test_simd_example(
+        I16x8ReplaceLane,
+        &[7],
+        [v, Value::I32(0x1_0042)],
+        i16x8([0, -1, 2, 3, 4, 5, 6, 0x42]),
+    );
+    test_simd_example(
+        I64x2ReplaceLane,
+        &[0],
+        [i64x2([1, 2]), Value::I64(-3)],
+        i64x2([-3, 2]),
+    );
+    test_simd_example(I32x4Splat, &[], [Value::I32(9)], i32x4([9; 4]));
+    test_simd_example(
+        F32x4ExtractLane,
+        &[3],
+        [f32x4([0.0, 1.0, 2.0, 3.5])],
+        Value::F32(3.5),
+    );
+
+    let a: [i8; 16] = core::array::from_fn(|i| i as i8);
+    let b: [i8; 16] = core::array::from_fn(|i| 16 + i as i8);
+    let shuffle: [u8; 16] = [31, 0, 30, 1, 29, 2, 28, 3, 0, 0, 0, 0, 16, 16, 16, 16];
+    let expected: [i8; 16] = [31, 0, 30, 1, 29, 2, 28, 3, 0, 0, 0, 0, 16, 16, 16, 16];
+    test_simd_example(
+        I8x16Shuffle,
+        &shuffle,
+        [i8x16(a), i8x16(b)],
+        i8x16(expected),
+    );
+
+    let indices: [i8; 16] = [15, 14, 16, -1, 0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11];
+    let expected: [i8; 16] = [15, 14, 0, 0, 0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11];
+    test_simd_example(
+        I8x16Swizzle,
+        &[],
+        [i8x16(a), i8x16(indices)],
+        i8x16(expected),
+    );
+}
+
+#[test]
+fn test_widening_and_narrowing() {
+    use SimdInstruction::*;
+
+    let v = i16x8([-1, 2, -3, 4, i16::MAX, i16::MIN, 300, -300]);
+    test_simd_example(
+        I32x4ExtendHighI16x8S,
+        &[],
+        [v],
+        i32x4([i16::MAX as i32, i16::MIN as i32, 300, -300]),
+    );
+    test_simd_example(
+        I32x4ExtendLowI16x8U,
+        &[],
+        [v],
+        i32x4([0xffff, 2, 0xfffd, 4]),
+    );
+    test_simd_example(
+        I8x16NarrowI16x8S,
+        &[],
+        [v, v],
+        i8x16([
+            -1, 2, -3, 4, 127, -128, 127, -128, -1, 2, -3, 4, 127, -128, 127, -128,
+        ]),
+    );
+    test_simd_example(
+        I8x16NarrowI16x8U,
+        &[],
+        [v, i16x8([0; 8])],
+        v128_from(
+            [0u8, 2, 0, 4, 255, 0, 255, 0, 0, 0, 0, 0, 0, 0, 0, 0],
+            |x| vec![x],
+        ),
+    );
+    test_simd_example(
+        I64x2ExtmulHighI32x4U,
+        &[],
+        [i32x4([0, 0, -1, 2]), i32x4([0, 0, -1, 3])],
+        i64x2([0xffff_fffe_0000_0001_u64 as i64, 6]),
+    );
+    test_simd_example(
+        I16x8ExtaddPairwiseI8x16S,
+        &[],
+        [i8x16([
+            127, 127, -128, -128, 1, 2, 3, 4, 0, 0, 0, 0, 0, 0, 0, 0,
+        ])],
+        i16x8([254, -256, 3, 7, 0, 0, 0, 0]),
+    );
+}
+
+#[test]
+fn test_float_operations() {
+    use SimdInstruction::*;
+
+    let a = f32x4([f32::NAN, -0.0, 1.0, 2.5]);
+    let b = f32x4([1.0, 0.0, -1.0, 2.5]);
+    test_simd_example(F32x4Max, &[], [b, b], b);
+    test_simd_example(F32x4Pmin, &[], [a, b], f32x4([f32::NAN, -0.0, -1.0, 2.5]));
+
+    // NaN is not equal to itself, so check the other lanes separately
+    let min_no_nan = f32x4([0.0, -0.0, -1.0, 2.5]);
+    test_simd_example(F32x4Min, &[], [f32x4([0.0, -0.0, 1.0, 2.5]), b], min_no_nan);
+    test_simd_example(
+        F32x4Min,
+        &[],
+        [f32x4([0.0, 0.0, 1.0, 2.5]), f32x4([0.0, -0.0, -1.0, 2.5])],
+        min_no_nan,
+    );
+
+    test_simd_example(
+        F32x4Nearest,
+        &[],
+        [f32x4([0.5, 1.5, -2.5, 2.4])],
+        f32x4([0.0, 2.0, -2.0, 2.0]),
+    );
+    test_simd_example(
+        I32x4TruncSatF32x4S,
+        &[],
+        [f32x4([f32::NAN, 1e10, -1e10, -3.9])],
+        i32x4([0, i32::MAX, i32::MIN, -3]),
+    );
+    test_simd_example(
+        F32x4DemoteF64x2Zero,
+        &[],
+        [v128_from([1.5f64, -2.0], |x| x.to_le_bytes().to_vec())],
+        f32x4([1.5, -2.0, 0.0, 0.0]),
+    );
+}
+
+#[test]
+fn test_load_and_store() {
+    use SimdInstruction::*;
+
+    let arena = Bump::new();
+    let mut module = WasmModule::new(&arena);
+    module.memory = MemorySection::new(&arena, MemorySection::PAGE_SIZE);
+
+    // Store a vector at address 32, then load it back in various ways
+    let signature = Signature {
+        param_types: bumpalo::vec![in &arena],
+        ret_type: None,
+    };
+    create_exported_function_no_locals(&mut module, "store", signature, |buf| {
+        buf.push(OpCode::I32CONST as u8);
+        buf.encode_i32(30);
+        const_value(buf, i16x8([1, -2, 3, -4, 5, -6, 7, -8]));
+        push_simd_op(buf, V128Store);
+        buf.encode_u32(0);
+        buf.encode_u32(2);
+        buf.push(OpCode::END as u8);
+    });
+
+    let signature = Signature {
+        param_types: bumpalo::vec![in &arena],
+        ret_type: Some(ValueType::V128),
+    };
+    create_exported_function_no_locals(&mut module, "load_extend", signature, |buf| {
+        buf.push(OpCode::I32CONST as u8);
+        buf.encode_i32(40);
+        push_simd_op(buf, V128Load16x4S);
+        buf.encode_u32(0);
+        buf.encode_u32(0);
+        buf.push(OpCode::END as u8);
+    });
+
+    let signature = Signature {
+        param_types: bumpalo::vec![in &arena],
+        ret_type: Some(ValueType::V128),
+    };
+    create_exported_function_no_locals(&mut module, "load_lane", signature, |buf| {
+        buf.push(OpCode::I32CONST as u8);
+        buf.encode_i32(34);
+        const_value(buf, i32x4([0; 4]));
+        push_simd_op(buf, V128Load32Lane);
+        buf.encode_u32(0);
+        buf.encode_u32(0);
+        buf.push(2);
+        buf.push(OpCode::END as u8);
+    });
+
+    let signature = Signature {
+        param_types: bumpalo::vec![in &arena],
+        ret_type: None,
+    };
+    create_exported_function_no_locals(&mut module, "out_of_bounds", signature, |buf| {
+        buf.push(OpCode::I32CONST as u8);
+        buf.encode_i32((MemorySection::PAGE_SIZE - 8) as i32);
+        push_simd_op(buf, V128Load);
+        buf.encode_u32(0);
+        buf.encode_u32(0);
+        buf.push(OpCode::DROP as u8);
+        buf.push(OpCode::END as u8);
+    });
+
+    let mut inst =
+        Instance::for_module(&arena, &module, DefaultImportDispatcher::default(), false).unwrap();
+
+    inst.call_export("store", []).unwrap();
+    assert_eq!(
+        &inst.memory[32..48],
+        &[1, 0, 0xfe, 0xff, 3, 0, 0xfc, 0xff, 5, 0, 0xfa, 0xff, 7, 0, 0xf8, 0xff]
+    );
+
+    let result = inst.call_export("load_extend", []).unwrap();
+    assert_eq!(result, Some(i32x4([5, -6, 7, -8])));
+
+    let result = inst.call_export("load_lane", []).unwrap();
+    assert_eq!(result, Some(i32x4([0, 0, 0x0003_fffe, 0])));
+
+    assert!(inst.call_export("out_of_bounds", []).is_err());
+}
+
+#[test]
+fn test_skip_simd_instructions() {
+    use SimdInstruction::*;
+
+    // Branching over SIMD instructions requires parsing their immediates
+    let arena = Bump::new();
+    let mut module = WasmModule::new(&arena);
+    module.memory = MemorySection::new(&arena, MemorySection::PAGE_SIZE);
+
+    let signature = Signature {
+        param_types: bumpalo::vec![in &arena],
+        ret_type: Some(ValueType::I32),
+    };
+    create_exported_function_no_locals(&mut module, "test", signature, |buf| {
+        buf.push(OpCode::I32CONST as u8);
+        buf.encode_i32(0);
+        buf.push(OpCode::IF as u8);
+        buf.push(ValueType::VOID);
+        {
+            buf.push(OpCode::I32CONST as u8);
+            buf.encode_i32(0);
+            const_value(buf, i32x4([0; 4]));
+            const_value(buf, i32x4([0; 4]));
+            push_simd_op(buf, I8x16Shuffle);
+            buf.extend_from_slice(&[0; 16]);
+            push_simd_op(buf, I32x4ExtractLane);
+            buf.push(0);
+            push_simd_op(buf, I8x16Splat);
+            push_simd_op(buf, V128Store64Lane);
+            buf.encode_u32(3);
+            buf.encode_u32(0);
+            buf.push(1);
+        }
+        buf.push(OpCode::END as u8);
+        buf.push(OpCode::I32CONST as u8);
+        buf.encode_i32(1);
+        buf.push(OpCode::END as u8);
+    });
+
+    let mut inst =
+        Instance::for_module(&arena, &module, DefaultImportDispatcher::default(), false).unwrap();
+    let result = inst.call_export("test", []).unwrap();
+    assert_eq!(result, Some(Value::I32(1)));
+}
@@ -0,0 +1,336 @@
+use crate::wasi::{Errno, WasiDispatcher};
+use roc_wasm_module::Value;
+
+const PATH: usize = 0x100;
+const IOVS: usize = 0x200;
+const BUF: usize = 0x300;
+const OUT: usize = 0x400;
+
+const RIGHTS_READ_WRITE: i64 = (1 << 1) | (1 << 6);
+
+fn call(wasi: &mut WasiDispatcher, memory: &mut [u8], name: &str, args: &[Value]) {
+    let code = errno(wasi, memory, name, args);
+    assert!(
+        code == Errno::Success as i32,
+        "WASI {name} returned error code {code}"
+    );
+}
+
+fn errno(wasi: &mut WasiDispatcher, memory: &mut [u8], name: &str, args: &[Value]) -> i32 {
+    wasi.dispatch(name, args, memory)
+        .unwrap()
+        .expect_i32()
+        .unwrap()
+}
+
+fn read_u32(memory: &[u8], addr: usize) -> u32 {
+    u32::from_le_bytes(memory[addr..][..4].try_into().unwrap())
+}
+
+fn read_u64(memory: &[u8], addr: usize) -> u64 {
+    u64::from_le_bytes(memory[addr..][..8].try_into().unwrap())
+}
+
+fn set_path(memory: &mut [u8], path: &str) -> [Value; 2] {
+    memory[PATH..][..path.len()].copy_from_slice(path.as_bytes());
+    [Value::I32(PATH as i32), Value::I32(path.len() as i32)]
+}
+
+fn set_iov(memory: &mut [u8], data: &[u8]) {
+    memory[BUF..][..data.len()].copy_from_slice(data);
+    memory[IOVS..][..4].copy_from_slice(&(BUF as u32).to_le_bytes());
+    memory[IOVS + 4..][..4].copy_from_slice(&(data.len() as u32).to_le_bytes());
+}
+
+fn path_open(
+    wasi: &mut WasiDispatcher,
+    memory: &mut [u8],
+    dir_fd: i32,
+    path: &str,
+    oflags: i32,
+) -> i32 {
+    let [ptr, len] = set_path(memory, path);
+    let code = errno(
+        wasi,
+        memory,
+        "path_open",
+        &[
+            Value::I32(dir_fd),
+            Value::I32(1),
+            ptr,
+            len,
+            Value::I32(oflags),
+            Value::I64(RIGHTS_READ_WRITE),
+            Value::I64(RIGHTS_READ_WRITE),
+            Value::I32(0),
+            Value::I32(OUT as i32),
+        ],
+    );
+    if code == Errno::Success as i32 {
+        read_u32(memory, OUT) as i32
+    } else {
+        -code
+    }
+}
+
+#[test]
+fn test_preopened_dir() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut wasi = WasiDispatcher::default();
+    wasi.preopen_dir(dir.path(), "/sandbox").unwrap();
+    let mut memory = vec![0; 0x1000];
+
+    call(
+        &mut wasi,
+        &mut memory,
+        "fd_prestat_get",
+        &[Value::I32(3), Value::I32(OUT as i32)],
+    );
+    assert_eq!(read_u32(&memory, OUT), 0);
+    assert_eq!(read_u32(&memory, OUT + 4), 8);
+
+    call(
+        &mut wasi,
+        &mut memory,
+        "fd_prestat_dir_name",
+        &[Value::I32(3), Value::I32(BUF as i32), Value::I32(8)],
+    );
+    assert_eq!(&memory[BUF..][..8], b"/sandbox");
+
+    // wasi-libc stops looking for preopens at the first bad file descriptor
+    assert_eq!(
+        errno(
+            &mut wasi,
+            &mut memory,
+            "fd_prestat_get",
+            &[Value::I32(4), Value::I32(OUT as i32)]
+        ),
+        Errno::Badf as i32
+    );
+}
+
+#[test]
+fn test_write_seek_read() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut wasi = WasiDispatcher::default();
+    wasi.preopen_dir(dir.path(), ".").unwrap();
+    let mut memory = vec![0; 0x1000];
+
+    const OFLAGS_CREAT: i32 = 1;
+    let fd = path_open(&mut wasi, &mut memory, 3, "hello.txt", OFLAGS_CREAT);
+    assert_eq!(fd, 4);
+
+    set_iov(&mut memory, b"Hello, World!");
+    call(
+        &mut wasi,
+        &mut memory,
+        "fd_write",
+        &[
+            Value::I32(fd),
+            Value::I32(IOVS as i32),
+            Value::I32(1),
+            Value::I32(OUT as i32),
+        ],
+    );
+    assert_eq!(read_u32(&memory, OUT), 13);
+
+    // Seek to "World"
+    call(
+        &mut wasi,
+        &mut memory,
+        "fd_seek",
+        &[
+            Value::I32(fd),
+            Value::I64(7),
+            Value::I32(0),
+            Value::I32(OUT as i32),
+        ],
+    );
+    assert_eq!(read_u64(&memory, OUT), 7);
+
+    set_iov(&mut memory, &[0; 5]);
+    call(
+        &mut wasi,
+        &mut memory,
+        "fd_read",
+        &[
+            Value::I32(fd),
+            Value::I32(IOVS as i32),
+            Value::I32(1),
+            Value::I32(OUT as i32),
+        ],
+    );
+    assert_eq!(read_u32(&memory, OUT), 5);
+    assert_eq!(&memory[BUF..][..5], b"World");
+
+    call(
+        &mut wasi,
+        &mut memory,
+        "fd_filestat_get",
+        &[Value::I32(fd), Value::I32(OUT as i32)],
+    );
+    assert_eq!(memory[OUT + 16], 4); // regular file
+    assert_eq!(read_u64(&memory, OUT + 32), 13);
+
+    call(&mut wasi, &mut memory, "fd_close", &[Value::I32(fd)]);
+    assert_eq!(
+        errno(&mut wasi, &mut memory, "fd_close", &[Value::I32(fd)]),
+        Errno::Badf as i32
+    );
+
+    assert_eq!(
+        std::fs::read_to_string(dir.path().join("hello.txt")).unwrap(),
+        "Hello, World!"
+    );
+}
+
+#[test]
+fn test_sandbox() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::create_dir(dir.path().join("inner")).unwrap();
+    std::fs::write(dir.path().join("inner/file.txt"), "inside").unwrap();
+    std::fs::write(dir.path().join("outside.txt"), "outside").unwrap();
+
+    let mut wasi = WasiDispatcher::default();
+    wasi.preopen_dir(&dir.path().join("inner"), ".").unwrap();
+    let mut memory = vec![0; 0x1000];
+
+    assert_eq!(path_open(&mut wasi, &mut memory, 3, "file.txt", 0), 4);
+    assert_eq!(
+        path_open(&mut wasi, &mut memory, 3, "./x/../file.txt", 0),
+        5
+    );
+
+    let notcapable = -(Errno::Notcapable as i32);
+    assert_eq!(
+        path_open(&mut wasi, &mut memory, 3, "../inner/file.txt", 0),
+        notcapable
+    );
+    assert_eq!(
+        path_open(&mut wasi, &mut memory, 3, "/etc/passwd", 0),
+        notcapable
+    );
+
+    #[cfg(unix)]
+    {
+        std::os::unix::fs::symlink(dir.path(), dir.path().join("inner/escape")).unwrap();
+        assert_eq!(
+            path_open(&mut wasi, &mut memory, 3, "escape/outside.txt", 0),
+            notcapable
+        );
+    }
+}
+
+#[cfg(unix)]
+#[test]
+fn test_dangling_symlink_escape() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::create_dir(dir.path().join("inner")).unwrap();
+
+    let mut wasi = WasiDispatcher::default();
+    wasi.preopen_dir(&dir.path().join("inner"), ".").unwrap();
+    let mut memory = vec![0; 0x1000];
+
+    const OFLAGS_CREAT: i32 = 1;
+    let notcapable = Errno::Notcapable as i32;
+
+    // The app can't make a link that leads out of the sandbox
+    memory[BUF..][..14].copy_from_slice(b"../outside.txt");
+    let [ptr, len] = set_path(&mut memory, "link");
+    let code = errno(
+        &mut wasi,
+        &mut memory,
+        "path_symlink",
+        &[
+            Value::I32(BUF as i32),
+            Value::I32(14),
+            Value::I32(3),
+            ptr,
+            len,
+        ],
+    );
+    assert_eq!(code, notcapable);
+
+    // ...and links that were already there can't be used to create files out of the sandbox
+    std::os::unix::fs::symlink("../outside.txt", dir.path().join("inner/link")).unwrap();
+    assert_eq!(
+        path_open(&mut wasi, &mut memory, 3, "link", OFLAGS_CREAT),
+        -notcapable
+    );
+    assert!(!dir.path().join("outside.txt").exists());
+
+    // Dangling links inside the sandbox are fine
+    memory[BUF..][..10].copy_from_slice(b"./made.txt");
+    let [ptr, len] = set_path(&mut memory, "inside");
+    call(
+        &mut wasi,
+        &mut memory,
+        "path_symlink",
+        &[
+            Value::I32(BUF as i32),
+            Value::I32(10),
+            Value::I32(3),
+            ptr,
+            len,
+        ],
+    );
+    assert_eq!(
+        path_open(&mut wasi, &mut memory, 3, "inside", OFLAGS_CREAT),
+        4
+    );
+    assert!(dir.path().join("inner/made.txt").exists());
+}
+
+#[test]
+fn test_readdir() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("b"), "").unwrap();
+    std::fs::create_dir(dir.path().join("a")).unwrap();
+
+    let mut wasi = WasiDispatcher::default();
+    wasi.preopen_dir(dir.path(), ".").unwrap();
+    let mut memory = vec![0; 0x1000];
+
+    call(
+        &mut wasi,
+        &mut memory,
+        "fd_readdir",
+        &[
+            Value::I32(3),
+            Value::I32(BUF as i32),
+            Value::I32(0x100),
+            Value::I64(0),
+            Value::I32(OUT as i32),
+        ],
+    );
+
+    let bufused = read_u32(&memory, OUT) as usize;
+    let mut entries = vec![];
+    let mut offset = BUF;
+    while offset < BUF + bufused {
+        let namlen = read_u32(&memory, offset + 16) as usize;
+        let filetype = memory[offset + 20];
+        let name = std::str::from_utf8(&memory[offset + 24..][..namlen]).unwrap();
+        entries.push((name.to_string(), filetype));
+        offset += 24 + namlen;
+    }
+
+    let expected = [(".", 3), ("..", 3), ("a", 3), ("b", 4)];
+    let expected: Vec<_> = expected.iter().map(|(n, t)| (n.to_string(), *t)).collect();
+    assert_eq!(entries, expected);
+}
+
+#[test]
+fn test_clock_time_get() {
+    let mut wasi = WasiDispatcher::default();
+    let mut memory = vec![0; 0x1000];
+
+    call(
+        &mut wasi,
+        &mut memory,
+        "clock_time_get",
+        &[Value::I32(0), Value::I64(1), Value::I32(OUT as i32)],
+    );
+    // Some time after 2020
+    assert!(read_u64(&memory, OUT) > 1_577_836_800_000_000_000);
+}
@@ -104,6 +104,14 @@ impl<'a> ValueStore<'a> {
         }
     }
 
+    pub(crate) fn pop_v128(&mut self) -> Result<[u8; 16], Error> {
+        match self.values.pop() {
+            Some(Value::V128(x)) => Ok(x),
+            Some(bad) => Err(Error::Type(ValueType::V128, ValueType::from(bad))),
+            None => Err(Error::StackEmpty),
+        }
+    }
+
     pub(crate) fn iter(&self) -> std::slice::Iter<Value> {
         self.values.iter()
     }
@@ -112,7 +120,7 @@ impl<'a> ValueStore<'a> {
         self.values.truncate(depth)
     }
 
-    pub(crate) fn get_slice(&mut self, from: usize) -> &[Value] {
+    pub(crate) fn get_slice(&self, from: usize) -> &[Value] {
         &self.values[from..]
     }
 }
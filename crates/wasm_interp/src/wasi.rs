@@ -1,14 +1,46 @@
 use rand::prelude::*;
 use roc_wasm_module::Value;
-use std::io::{self, Read, StderrLock, StdoutLock, Write};
+use std::fs::{self, File, Metadata, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, StderrLock, StdoutLock, Write};
+use std::path::{Component, Path, PathBuf};
 use std::process::exit;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
 
 pub const MODULE_NAME: &str = "wasi_snapshot_preview1";
 
+const CLOCK_REALTIME: i32 = 0;
+const CLOCK_MONOTONIC: i32 = 1;
+const CLOCK_PROCESS_CPUTIME: i32 = 2;
+const CLOCK_THREAD_CPUTIME: i32 = 3;
+
+const FILETYPE_UNKNOWN: u8 = 0;
+const FILETYPE_CHARACTER_DEVICE: u8 = 2;
+const FILETYPE_DIRECTORY: u8 = 3;
+const FILETYPE_REGULAR_FILE: u8 = 4;
+const FILETYPE_SYMBOLIC_LINK: u8 = 7;
+
+const FDFLAGS_APPEND: u16 = 1;
+const LOOKUPFLAGS_SYMLINK_FOLLOW: i32 = 1;
+
+const OFLAGS_CREAT: i32 = 1;
+const OFLAGS_DIRECTORY: i32 = 2;
+const OFLAGS_EXCL: i32 = 4;
+const OFLAGS_TRUNC: i32 = 8;
+
+const RIGHTS_FD_READ: u64 = 1 << 1;
+const RIGHTS_FD_WRITE: u64 = 1 << 6;
+/// We don't restrict what the app can do with its files, other than keeping them in the sandbox
+const RIGHTS_ALL: u64 = (1 << 30) - 1;
+
+/// Size of a `dirent` in `fd_readdir`, not counting the name that follows it
+const DIRENT_SIZE: usize = 24;
+
 pub struct WasiDispatcher<'a> {
     pub args: &'a [&'a [u8]],
     pub rng: ThreadRng,
     pub files: Vec<WasiFile>,
+    /// Origin for the monotonic clock
+    pub start_time: Instant,
 }
 
 impl Default for WasiDispatcher<'_> {
@@ -22,12 +54,30 @@ pub enum WasiFile {
     WriteOnly(Vec<u8>),
     ReadWrite(Vec<u8>),
     HostSystemFile,
+    /// A host directory that the app sees as `guest_path`. The app can't open anything outside it.
+    PreopenedDir {
+        guest_path: String,
+        host_path: PathBuf,
+    },
+    /// A directory the app opened inside the preopened directory `sandbox`
+    HostDir {
+        host_path: PathBuf,
+        sandbox: PathBuf,
+    },
+    /// A file the app opened inside a preopened directory
+    HostFile {
+        file: File,
+        fdflags: u16,
+    },
+    /// A file descriptor that has been closed, and can be reused
+    Closed,
 }
 
 enum WriteLock<'a> {
     StdOut(StdoutLock<'a>),
     Stderr(StderrLock<'a>),
     RegularFile(&'a mut Vec<u8>),
+    HostFile(&'a mut File),
 }
 
 /// Implementation of WASI syscalls
@@ -44,9 +94,28 @@ impl<'a> WasiDispatcher<'a> {
                 WasiFile::HostSystemFile,
                 WasiFile::HostSystemFile,
             ],
+            start_time: Instant::now(),
         }
     }
 
+    /// Give the app access to a host directory, under the name `guest_path`.
+    /// Preopened directories get the file descriptors after stdio, in the order they're added.
+    pub fn preopen_dir(&mut self, host_path: &Path, guest_path: &str) -> io::Result<()> {
+        let host_path = host_path.canonicalize()?;
+        if !host_path.is_dir() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{} is not a directory", host_path.display()),
+            ));
+        }
+
+        self.files.push(WasiFile::PreopenedDir {
+            guest_path: guest_path.to_string(),
+            host_path,
+        });
+        Ok(())
+    }
+
     pub fn dispatch(
         &mut self,
         function_name: &str,
@@ -107,12 +176,74 @@ impl<'a> WasiDispatcher<'a> {
 
                 success_code
             }
-            "clock_res_get" => success_code, // this dummy implementation seems to be good enough for some functions
-            "clock_time_get" => success_code,
-            "fd_advise" => todo!("WASI {}({:?})", function_name, arguments),
-            "fd_allocate" => todo!("WASI {}({:?})", function_name, arguments),
-            "fd_close" => todo!("WASI {}({:?})", function_name, arguments),
-            "fd_datasync" => todo!("WASI {}({:?})", function_name, arguments),
+            "clock_res_get" => {
+                let clock_id = arguments[0].expect_i32().unwrap();
+                // Out param: the resolution in nanoseconds
+                let ptr_resolution = arguments[1].expect_i32().unwrap() as usize;
+
+                match clock_id {
+                    CLOCK_REALTIME
+                    | CLOCK_MONOTONIC
+                    | CLOCK_PROCESS_CPUTIME
+                    | CLOCK_THREAD_CPUTIME => {
+                        write_u64(memory, ptr_resolution, 1);
+                        success_code
+                    }
+                    _ => Some(Value::I32(Errno::Inval as i32)),
+                }
+            }
+            "clock_time_get" => {
+                let clock_id = arguments[0].expect_i32().unwrap();
+                // The maximum lag that the caller will accept. We're always as precise as we can be.
+                let _precision = arguments[1].expect_i64().unwrap();
+                // Out param: the time in nanoseconds
+                let ptr_time = arguments[2].expect_i32().unwrap() as usize;
+
+                let nanos = match clock_id {
+                    CLOCK_REALTIME => nanos_since_epoch(Ok(SystemTime::now())),
+                    // We don't have CPU time, but it's only used for measuring intervals
+                    CLOCK_MONOTONIC | CLOCK_PROCESS_CPUTIME | CLOCK_THREAD_CPUTIME => {
+                        self.start_time.elapsed().as_nanos() as u64
+                    }
+                    _ => return Some(Value::I32(Errno::Inval as i32)),
+                };
+                write_u64(memory, ptr_time, nanos);
+
+                success_code
+            }
+            "fd_advise" => {
+                // It's only advice, so we can ignore it
+                let fd = arguments[0].expect_i32().unwrap() as usize;
+                errno_value(self.host_file(fd).map(|_| ()))
+            }
+            "fd_allocate" => {
+                let fd = arguments[0].expect_i32().unwrap() as usize;
+                let offset = arguments[1].expect_i64().unwrap() as u64;
+                let len = arguments[2].expect_i64().unwrap() as u64;
+
+                errno_value(self.host_file(fd).and_then(|file| {
+                    let size = file.metadata()?.len();
+                    if offset + len > size {
+                        file.set_len(offset + len)?;
+                    }
+                    Ok(())
+                }))
+            }
+            "fd_close" => {
+                let fd = arguments[0].expect_i32().unwrap() as usize;
+
+                match self.files.get_mut(fd) {
+                    None | Some(WasiFile::Closed) => Some(Value::I32(Errno::Badf as i32)),
+                    Some(file) => {
+                        *file = WasiFile::Closed;
+                        success_code
+                    }
+                }
+            }
+            "fd_datasync" => {
+                let fd = arguments[0].expect_i32().unwrap() as usize;
+                errno_value(self.host_file(fd).and_then(|file| Ok(file.sync_data()?)))
+            }
             "fd_fdstat_get" => {
                 // (i32, i32) -> i32
 
@@ -121,9 +252,16 @@ impl<'a> WasiDispatcher<'a> {
                 // ptr to a wasi_fdstat_t
                 let stat_mut_ptr = arguments[1].expect_i32().unwrap() as usize;
 
-                match fd {
-                    1 => {
-                        // Tell WASI that stdout is a tty (no seek or tell)
+                let (filetype, fdflags, rights) = match self.files.get(fd) {
+                    None | Some(WasiFile::Closed) => return Some(Value::I32(Errno::Badf as i32)),
+                    Some(WasiFile::PreopenedDir { .. } | WasiFile::HostDir { .. }) => {
+                        (FILETYPE_DIRECTORY, 0, RIGHTS_ALL)
+                    }
+                    Some(WasiFile::HostFile { fdflags, .. }) => {
+                        (FILETYPE_REGULAR_FILE, *fdflags, RIGHTS_ALL)
+                    }
+                    Some(_) => {
+                        // Tell WASI that stdio is a tty (no seek or tell)
                         // https://github.com/WebAssembly/wasi-libc/blob/659ff414560721b1660a19685110e484a081c3d4/libc-bottom-half/sources/isatty.c
                         // *Not* a tty if:
                         //     (statbuf.fs_filetype != __WASI_FILETYPE_CHARACTER_DEVICE ||
@@ -131,25 +269,99 @@ impl<'a> WasiDispatcher<'a> {
                         // So it's sufficient to set:
                         //     .fs_filetype = __WASI_FILETYPE_CHARACTER_DEVICE
                         //     .fs_rights_base = 0
+                        (FILETYPE_CHARACTER_DEVICE, 0, 0)
+                    }
+                };
 
-                        const WASI_FILETYPE_CHARACTER_DEVICE: u8 = 2;
-                        memory[stat_mut_ptr] = WASI_FILETYPE_CHARACTER_DEVICE;
+                memory[stat_mut_ptr..][..24].fill(0);
+                memory[stat_mut_ptr] = filetype;
+                memory[stat_mut_ptr + 2..][..2].copy_from_slice(&fdflags.to_le_bytes());
+                write_u64(memory, stat_mut_ptr + 8, rights);
+                write_u64(memory, stat_mut_ptr + 16, rights);
 
-                        for b in memory[stat_mut_ptr + 1..stat_mut_ptr + 24].iter_mut() {
-                            *b = 0;
-                        }
-                    }
-                    _ => todo!("WASI {}({:?})", function_name, arguments),
-                }
+                success_code
+            }
+            "fd_fdstat_set_flags" => {
+                let fd = arguments[0].expect_i32().unwrap() as usize;
+                let flags = arguments[1].expect_i32().unwrap() as u16;
 
+                match self.files.get(fd) {
+                    None | Some(WasiFile::Closed) => Some(Value::I32(Errno::Badf as i32)),
+                    // The host file was opened with these flags, and we can't change them now
+                    Some(WasiFile::HostFile { fdflags, .. }) if *fdflags == flags => success_code,
+                    Some(_) => Some(Value::I32(Errno::Notsup as i32)),
+                }
+            }
+            "fd_fdstat_set_rights" => {
+                // Rights can only be dropped, and we don't enforce them anyway
                 success_code
             }
-            "fd_fdstat_set_flags" => todo!("WASI {}({:?})", function_name, arguments),
-            "fd_fdstat_set_rights" => todo!("WASI {}({:?})", function_name, arguments),
-            "fd_filestat_get" => todo!("WASI {}({:?})", function_name, arguments),
-            "fd_filestat_set_size" => todo!("WASI {}({:?})", function_name, arguments),
-            "fd_filestat_set_times" => todo!("WASI {}({:?})", function_name, arguments),
-            "fd_pread" => todo!("WASI {}({:?})", function_name, arguments),
+            "fd_filestat_get" => {
+                let fd = arguments[0].expect_i32().unwrap() as usize;
+                // ptr to a wasi_filestat_t
+                let ptr_buf = arguments[1].expect_i32().unwrap() as usize;
+
+                let metadata = match self.files.get(fd) {
+                    None | Some(WasiFile::Closed) => return Some(Value::I32(Errno::Badf as i32)),
+                    Some(WasiFile::HostFile { file, .. }) => file.metadata(),
+                    Some(
+                        WasiFile::PreopenedDir { host_path, .. }
+                        | WasiFile::HostDir { host_path, .. },
+                    ) => fs::metadata(host_path),
+                    Some(
+                        WasiFile::ReadOnly(content)
+                        | WasiFile::WriteOnly(content)
+                        | WasiFile::ReadWrite(content),
+                    ) => {
+                        memory[ptr_buf..][..64].fill(0);
+                        memory[ptr_buf + 16] = FILETYPE_REGULAR_FILE;
+                        write_u64(memory, ptr_buf + 24, 1);
+                        write_u64(memory, ptr_buf + 32, content.len() as u64);
+                        return success_code;
+                    }
+                    Some(WasiFile::HostSystemFile) => {
+                        memory[ptr_buf..][..64].fill(0);
+                        memory[ptr_buf + 16] = FILETYPE_CHARACTER_DEVICE;
+                        write_u64(memory, ptr_buf + 24, 1);
+                        return success_code;
+                    }
+                };
+
+                errno_value(
+                    metadata
+                        .map(|metadata| write_filestat(memory, ptr_buf, &metadata))
+                        .map_err(Errno::from),
+                )
+            }
+            "fd_filestat_set_size" => {
+                let fd = arguments[0].expect_i32().unwrap() as usize;
+                let size = arguments[1].expect_i64().unwrap() as u64;
+                errno_value(self.host_file(fd).and_then(|file| Ok(file.set_len(size)?)))
+            }
+            "fd_filestat_set_times" => {
+                // Setting file times needs a newer Rust than we're using
+                Some(Value::I32(Errno::Nosys as i32))
+            }
+            "fd_pread" => {
+                let fd = arguments[0].expect_i32().unwrap() as usize;
+                let ptr_iovs = arguments[1].expect_i32().unwrap() as usize;
+                let iovs_len = arguments[2].expect_i32().unwrap();
+                let offset = arguments[3].expect_i64().unwrap() as u64;
+                // Out param: number of bytes read
+                let ptr_nread = arguments[4].expect_i32().unwrap() as usize;
+
+                let iovs = read_iovs(memory, ptr_iovs, iovs_len);
+                let result = self.host_file(fd).and_then(|file| {
+                    // pread doesn't move the file position
+                    let position = file.stream_position()?;
+                    file.seek(SeekFrom::Start(offset))?;
+                    let n_read = read_into_iovs(file, memory, &iovs);
+                    file.seek(SeekFrom::Start(position))?;
+                    Ok(n_read?)
+                });
+
+                errno_value(result.map(|n_read| write_u32(memory, ptr_nread, n_read as u32)))
+            }
             "fd_prestat_get" => {
                 // The preopened file descriptor to query
                 let fd = arguments[0].expect_i32().unwrap() as usize;
@@ -157,19 +369,60 @@ impl<'a> WasiDispatcher<'a> {
                 //  preopen type: 4 bytes, where 0=dir is the only one supported, it seems
                 //  preopen name length: 4 bytes
                 let ptr_buf = arguments[1].expect_i32().unwrap() as usize;
-                memory[ptr_buf..][..8].copy_from_slice(&0u64.to_le_bytes());
-                if fd < self.files.len() {
-                    success_code
-                } else {
-                    println!("WASI warning: file descriptor {fd} does not exist");
-                    Some(Value::I32(Errno::Badf as i32))
+
+                // wasi-libc asks about each file descriptor after stdio, until it gets Badf
+                match self.files.get(fd) {
+                    Some(WasiFile::PreopenedDir { guest_path, .. }) => {
+                        write_u32(memory, ptr_buf, 0);
+                        write_u32(memory, ptr_buf + 4, guest_path.len() as u32);
+                        success_code
+                    }
+                    _ => Some(Value::I32(Errno::Badf as i32)),
                 }
             }
             "fd_prestat_dir_name" => {
-                // We're not giving names to any of our files so just return success
-                success_code
+                let fd = arguments[0].expect_i32().unwrap() as usize;
+                let ptr_path = arguments[1].expect_i32().unwrap() as usize;
+                let path_len = arguments[2].expect_i32().unwrap() as usize;
+
+                match self.files.get(fd) {
+                    Some(WasiFile::PreopenedDir { guest_path, .. }) => {
+                        if path_len < guest_path.len() {
+                            return Some(Value::I32(Errno::Nametoolong as i32));
+                        }
+                        memory[ptr_path..][..guest_path.len()]
+                            .copy_from_slice(guest_path.as_bytes());
+                        success_code
+                    }
+                    _ => Some(Value::I32(Errno::Badf as i32)),
+                }
+            }
+            "fd_pwrite" => {
+                let fd = arguments[0].expect_i32().unwrap() as usize;
+                let ptr_iovs = arguments[1].expect_i32().unwrap() as usize;
+                let iovs_len = arguments[2].expect_i32().unwrap();
+                let offset = arguments[3].expect_i64().unwrap() as u64;
+                // Out param: number of bytes written
+                let ptr_nwritten = arguments[4].expect_i32().unwrap() as usize;
+
+                let iovs = read_iovs(memory, ptr_iovs, iovs_len);
+                let result = self.host_file(fd).and_then(|file| {
+                    // pwrite doesn't move the file position
+                    let position = file.stream_position()?;
+                    file.seek(SeekFrom::Start(offset))?;
+                    let mut n_written = 0;
+                    for (iov_base, iov_len) in iovs {
+                        file.write_all(&memory[iov_base..][..iov_len])?;
+                        n_written += iov_len;
+                    }
+                    file.seek(SeekFrom::Start(position))?;
+                    Ok(n_written)
+                });
+
+                errno_value(
+                    result.map(|n_written| write_u32(memory, ptr_nwritten, n_written as u32)),
+                )
             }
-            "fd_pwrite" => todo!("WASI {}({:?})", function_name, arguments),
             "fd_read" => {
                 use WasiFile::*;
 
@@ -188,12 +441,11 @@ impl<'a> WasiDispatcher<'a> {
                 //     size_t iov_len;     /* Number of bytes to transfer */
                 // };
 
+                let iovs = read_iovs(memory, ptr_iovs, iovs_len);
                 let mut n_read: usize = 0;
-                match self.files.get(fd) {
+                match self.files.get_mut(fd) {
                     Some(ReadOnly(content) | ReadWrite(content)) => {
-                        for _ in 0..iovs_len {
-                            let iov_base = read_u32(memory, ptr_iovs) as usize;
-                            let iov_len = read_i32(memory, ptr_iovs + 4) as usize;
+                        for (iov_base, iov_len) in iovs {
                             let remaining = content.len() - n_read;
                             let len = remaining.min(iov_len);
                             if len == 0 {
@@ -204,19 +456,15 @@ impl<'a> WasiDispatcher<'a> {
                         }
                     }
                     Some(HostSystemFile) if fd == 0 => {
-                        let mut stdin = io::stdin();
-                        for _ in 0..iovs_len {
-                            let iov_base = read_u32(memory, ptr_iovs) as usize;
-                            let iov_len = read_i32(memory, ptr_iovs + 4) as usize;
-                            match stdin.read(&mut memory[iov_base..][..iov_len]) {
-                                Ok(n) => {
-                                    n_read += n;
-                                }
-                                Err(_) => {
-                                    break;
-                                }
-                            }
-                        }
+                        // Errors are treated like the end of the input
+                        n_read = read_into_iovs(&mut io::stdin(), memory, &iovs).unwrap_or(0);
+                    }
+                    Some(HostFile { file, .. }) => match read_into_iovs(file, memory, &iovs) {
+                        Ok(n) => n_read = n,
+                        Err(e) => return Some(Value::I32(Errno::from(e) as i32)),
+                    },
+                    Some(PreopenedDir { .. } | HostDir { .. }) => {
+                        return Some(Value::I32(Errno::Isdir as i32))
                     }
                     _ => return Some(Value::I32(Errno::Badf as i32)),
                 };
@@ -224,11 +472,99 @@ impl<'a> WasiDispatcher<'a> {
                 memory[ptr_nread..][..4].copy_from_slice(&(n_read as u32).to_le_bytes());
                 success_code
             }
-            "fd_readdir" => todo!("WASI {}({:?})", function_name, arguments),
-            "fd_renumber" => todo!("WASI {}({:?})", function_name, arguments),
-            "fd_seek" => todo!("WASI {}({:?})", function_name, arguments),
-            "fd_sync" => todo!("WASI {}({:?})", function_name, arguments),
-            "fd_tell" => todo!("WASI {}({:?})", function_name, arguments),
+            "fd_readdir" => {
+                let fd = arguments[0].expect_i32().unwrap() as usize;
+                let ptr_buf = arguments[1].expect_i32().unwrap() as usize;
+                let buf_len = arguments[2].expect_i32().unwrap() as usize;
+                // Index of the first entry to read. Entries are sorted, so that this is stable.
+                let cookie = arguments[3].expect_i64().unwrap() as usize;
+                // Out param: number of bytes written. If the buffer is full, there may be more.
+                let ptr_bufused = arguments[4].expect_i32().unwrap() as usize;
+
+                let entries = match self.files.get(fd) {
+                    Some(
+                        WasiFile::PreopenedDir { host_path, .. }
+                        | WasiFile::HostDir { host_path, .. },
+                    ) => match read_dir_entries(host_path) {
+                        Ok(entries) => entries,
+                        Err(e) => return Some(Value::I32(Errno::from(e) as i32)),
+                    },
+                    None | Some(WasiFile::Closed) => return Some(Value::I32(Errno::Badf as i32)),
+                    Some(_) => return Some(Value::I32(Errno::Notdir as i32)),
+                };
+
+                let buf = &mut memory[ptr_buf..][..buf_len];
+                let mut bufused = 0;
+                for (index, (name, ino, filetype)) in entries.iter().enumerate().skip(cookie) {
+                    let mut dirent = [0; DIRENT_SIZE];
+                    dirent[0..8].copy_from_slice(&(index as u64 + 1).to_le_bytes());
+                    dirent[8..16].copy_from_slice(&ino.to_le_bytes());
+                    dirent[16..20].copy_from_slice(&(name.len() as u32).to_le_bytes());
+                    dirent[20] = *filetype;
+
+                    // The last entry is cut off if it doesn't fit
+                    for bytes in [&dirent[..], name.as_slice()] {
+                        let len = bytes.len().min(buf_len - bufused);
+                        buf[bufused..][..len].copy_from_slice(&bytes[..len]);
+                        bufused += len;
+                    }
+                    if bufused == buf_len {
+                        break;
+                    }
+                }
+
+                write_u32(memory, ptr_bufused, bufused as u32);
+                success_code
+            }
+            "fd_renumber" => {
+                let from = arguments[0].expect_i32().unwrap() as usize;
+                let to = arguments[1].expect_i32().unwrap() as usize;
+
+                match (self.files.get(from), self.files.get(to)) {
+                    (Some(from_file), Some(to_file))
+                        if !matches!(from_file, WasiFile::Closed)
+                            && !matches!(to_file, WasiFile::Closed) =>
+                    {
+                        self.files.swap(from, to);
+                        self.files[from] = WasiFile::Closed;
+                        success_code
+                    }
+                    _ => Some(Value::I32(Errno::Badf as i32)),
+                }
+            }
+            "fd_seek" => {
+                let fd = arguments[0].expect_i32().unwrap() as usize;
+                let offset = arguments[1].expect_i64().unwrap();
+                let whence = arguments[2].expect_i32().unwrap();
+                // Out param: the new offset from the start of the file
+                let ptr_newoffset = arguments[3].expect_i32().unwrap() as usize;
+
+                let seek_from = match whence {
+                    0 if offset >= 0 => SeekFrom::Start(offset as u64),
+                    1 => SeekFrom::Current(offset),
+                    2 => SeekFrom::End(offset),
+                    _ => return Some(Value::I32(Errno::Inval as i32)),
+                };
+
+                let result = self
+                    .host_file(fd)
+                    .and_then(|file| Ok(file.seek(seek_from)?));
+                errno_value(result.map(|position| write_u64(memory, ptr_newoffset, position)))
+            }
+            "fd_sync" => {
+                let fd = arguments[0].expect_i32().unwrap() as usize;
+                errno_value(self.host_file(fd).and_then(|file| Ok(file.sync_all()?)))
+            }
+            "fd_tell" => {
+                let fd = arguments[0].expect_i32().unwrap() as usize;
+                // Out param: the offset from the start of the file
+                let ptr_offset = arguments[1].expect_i32().unwrap() as usize;
+
+                let result = self
+                    .host_file(fd)
+                    .and_then(|file| Ok(file.stream_position()?));
+                errno_value(result.map(|position| write_u64(memory, ptr_offset, position)))
+            }
             "fd_write" => {
                 use WasiFile::*;
 
@@ -252,6 +588,10 @@ impl<'a> WasiDispatcher<'a> {
                     Some(WriteOnly(content) | ReadWrite(content)) => {
                         WriteLock::RegularFile(content)
                     }
+                    Some(HostFile { file, .. }) => WriteLock::HostFile(file),
+                    Some(PreopenedDir { .. } | HostDir { .. }) => {
+                        return Some(Value::I32(Errno::Isdir as i32))
+                    }
                     _ => return Some(Value::I32(Errno::Badf as i32)),
                 };
 
@@ -280,6 +620,7 @@ impl<'a> WasiDispatcher<'a> {
                         WriteLock::StdOut(stdout) => stdout.write_all(bytes),
                         WriteLock::Stderr(stderr) => stderr.write_all(bytes),
                         WriteLock::RegularFile(content) => content.write_all(bytes),
+                        WriteLock::HostFile(file) => file.write_all(bytes),
                     };
                     if write_result.is_err() {
                         break;
@@ -300,23 +641,187 @@ impl<'a> WasiDispatcher<'a> {
                     Err(_) => Some(Value::I32(Errno::Io as i32)),
                 }
             }
-            "path_create_directory" => todo!("WASI {}({:?})", function_name, arguments),
-            "path_filestat_get" => todo!("WASI {}({:?})", function_name, arguments),
-            "path_filestat_set_times" => todo!("WASI {}({:?})", function_name, arguments),
-            "path_link" => todo!("WASI {}({:?})", function_name, arguments),
-            "path_open" => todo!("WASI {}({:?})", function_name, arguments),
-            "path_readlink" => todo!("WASI {}({:?})", function_name, arguments),
-            "path_remove_directory" => todo!("WASI {}({:?})", function_name, arguments),
-            "path_rename" => todo!("WASI {}({:?})", function_name, arguments),
-            "path_symlink" => todo!("WASI {}({:?})", function_name, arguments),
-            "path_unlink_file" => todo!("WASI {}({:?})", function_name, arguments),
-            "poll_oneoff" => todo!("WASI {}({:?})", function_name, arguments),
+            "path_create_directory" => {
+                let fd = arguments[0].expect_i32().unwrap() as usize;
+                let ptr_path = arguments[1].expect_i32().unwrap() as usize;
+                let path_len = arguments[2].expect_i32().unwrap() as usize;
+
+                let result = read_path(memory, ptr_path, path_len)
+                    .and_then(|path| self.resolve(fd, path, false))
+                    .and_then(|(host_path, _)| Ok(fs::create_dir(host_path)?));
+                errno_value(result)
+            }
+            "path_filestat_get" => {
+                let fd = arguments[0].expect_i32().unwrap() as usize;
+                let lookupflags = arguments[1].expect_i32().unwrap();
+                let ptr_path = arguments[2].expect_i32().unwrap() as usize;
+                let path_len = arguments[3].expect_i32().unwrap() as usize;
+                // ptr to a wasi_filestat_t
+                let ptr_buf = arguments[4].expect_i32().unwrap() as usize;
+
+                let follow = lookupflags & LOOKUPFLAGS_SYMLINK_FOLLOW != 0;
+                let result = read_path(memory, ptr_path, path_len)
+                    .and_then(|path| self.resolve(fd, path, follow))
+                    .and_then(|(host_path, _)| {
+                        if follow {
+                            Ok(fs::metadata(host_path)?)
+                        } else {
+                            Ok(fs::symlink_metadata(host_path)?)
+                        }
+                    });
+                errno_value(result.map(|metadata| write_filestat(memory, ptr_buf, &metadata)))
+            }
+            "path_filestat_set_times" => {
+                // Setting file times needs a newer Rust than we're using
+                Some(Value::I32(Errno::Nosys as i32))
+            }
+            "path_link" => {
+                let old_fd = arguments[0].expect_i32().unwrap() as usize;
+                let old_flags = arguments[1].expect_i32().unwrap();
+                let ptr_old_path = arguments[2].expect_i32().unwrap() as usize;
+                let old_path_len = arguments[3].expect_i32().unwrap() as usize;
+                let new_fd = arguments[4].expect_i32().unwrap() as usize;
+                let ptr_new_path = arguments[5].expect_i32().unwrap() as usize;
+                let new_path_len = arguments[6].expect_i32().unwrap() as usize;
+
+                let follow = old_flags & LOOKUPFLAGS_SYMLINK_FOLLOW != 0;
+                let result = read_path(memory, ptr_old_path, old_path_len)
+                    .and_then(|path| self.resolve(old_fd, path, follow))
+                    .and_then(|(old_path, _)| {
+                        let new_path = read_path(memory, ptr_new_path, new_path_len)?;
+                        let (new_path, _) = self.resolve(new_fd, new_path, false)?;
+                        Ok(fs::hard_link(old_path, new_path)?)
+                    });
+                errno_value(result)
+            }
+            "path_open" => {
+                let fd = arguments[0].expect_i32().unwrap() as usize;
+                // Symlinks are always followed, as long as they stay in the sandbox
+                let _lookupflags = arguments[1].expect_i32().unwrap();
+                let ptr_path = arguments[2].expect_i32().unwrap() as usize;
+                let path_len = arguments[3].expect_i32().unwrap() as usize;
+                let oflags = arguments[4].expect_i32().unwrap();
+                let rights_base = arguments[5].expect_i64().unwrap() as u64;
+                let _rights_inheriting = arguments[6].expect_i64().unwrap();
+                let fdflags = arguments[7].expect_i32().unwrap() as u16;
+                // Out param: the new file descriptor
+                let ptr_fd = arguments[8].expect_i32().unwrap() as usize;
+
+                let result = read_path(memory, ptr_path, path_len)
+                    .and_then(|path| self.resolve(fd, path, true))
+                    .and_then(|(host_path, sandbox)| {
+                        open_host_path(host_path, sandbox, oflags, rights_base, fdflags)
+                    });
+
+                errno_value(result.map(|file| {
+                    let new_fd = self.insert_file(file);
+                    write_u32(memory, ptr_fd, new_fd as u32);
+                }))
+            }
+            "path_readlink" => {
+                let fd = arguments[0].expect_i32().unwrap() as usize;
+                let ptr_path = arguments[1].expect_i32().unwrap() as usize;
+                let path_len = arguments[2].expect_i32().unwrap() as usize;
+                let ptr_buf = arguments[3].expect_i32().unwrap() as usize;
+                let buf_len = arguments[4].expect_i32().unwrap() as usize;
+                // Out param: number of bytes written. The contents are truncated to fit.
+                let ptr_bufused = arguments[5].expect_i32().unwrap() as usize;
+
+                let result = read_path(memory, ptr_path, path_len)
+                    .and_then(|path| self.resolve(fd, path, false))
+                    .and_then(|(host_path, _)| Ok(fs::read_link(host_path)?));
+
+                errno_value(result.map(|target| {
+                    let target = target.to_string_lossy();
+                    let len = target.len().min(buf_len);
+                    memory[ptr_buf..][..len].copy_from_slice(&target.as_bytes()[..len]);
+                    write_u32(memory, ptr_bufused, len as u32);
+                }))
+            }
+            "path_remove_directory" => {
+                let fd = arguments[0].expect_i32().unwrap() as usize;
+                let ptr_path = arguments[1].expect_i32().unwrap() as usize;
+                let path_len = arguments[2].expect_i32().unwrap() as usize;
+
+                let result = read_path(memory, ptr_path, path_len)
+                    .and_then(|path| self.resolve(fd, path, false))
+                    .and_then(|(host_path, _)| {
+                        if !fs::symlink_metadata(&host_path)?.is_dir() {
+                            Err(Errno::Notdir)
+                        } else if fs::read_dir(&host_path)?.next().is_some() {
+                            Err(Errno::Notempty)
+                        } else {
+                            Ok(fs::remove_dir(host_path)?)
+                        }
+                    });
+                errno_value(result)
+            }
+            "path_rename" => {
+                let old_fd = arguments[0].expect_i32().unwrap() as usize;
+                let ptr_old_path = arguments[1].expect_i32().unwrap() as usize;
+                let old_path_len = arguments[2].expect_i32().unwrap() as usize;
+                let new_fd = arguments[3].expect_i32().unwrap() as usize;
+                let ptr_new_path = arguments[4].expect_i32().unwrap() as usize;
+                let new_path_len = arguments[5].expect_i32().unwrap() as usize;
+
+                let result = read_path(memory, ptr_old_path, old_path_len)
+                    .and_then(|path| self.resolve(old_fd, path, false))
+                    .and_then(|(old_path, _)| {
+                        let new_path = read_path(memory, ptr_new_path, new_path_len)?;
+                        let (new_path, _) = self.resolve(new_fd, new_path, false)?;
+                        Ok(fs::rename(old_path, new_path)?)
+                    });
+                errno_value(result)
+            }
+            "path_symlink" => {
+                let ptr_old_path = arguments[0].expect_i32().unwrap() as usize;
+                let old_path_len = arguments[1].expect_i32().unwrap() as usize;
+                let fd = arguments[2].expect_i32().unwrap() as usize;
+                let ptr_new_path = arguments[3].expect_i32().unwrap() as usize;
+                let new_path_len = arguments[4].expect_i32().unwrap() as usize;
+
+                let result = read_path(memory, ptr_old_path, old_path_len).and_then(|target| {
+                    let link = read_path(memory, ptr_new_path, new_path_len)?;
+                    let (link, sandbox) = self.resolve(fd, link, false)?;
+
+                    // The link may not point outside the sandbox, even if its target doesn't
+                    // exist yet. Absolute links always would.
+                    let link_dir = link.parent().ok_or(Errno::Notcapable)?.canonicalize()?;
+                    match join_lexically(&link_dir, Path::new(target)) {
+                        Some(target_path) if target_path.starts_with(&sandbox) => {
+                            symlink(target, &link)
+                        }
+                        _ => Err(Errno::Notcapable),
+                    }
+                });
+                errno_value(result)
+            }
+            "path_unlink_file" => {
+                let fd = arguments[0].expect_i32().unwrap() as usize;
+                let ptr_path = arguments[1].expect_i32().unwrap() as usize;
+                let path_len = arguments[2].expect_i32().unwrap() as usize;
+
+                let result = read_path(memory, ptr_path, path_len)
+                    .and_then(|path| self.resolve(fd, path, false))
+                    .and_then(|(host_path, _)| {
+                        if fs::symlink_metadata(&host_path)?.is_dir() {
+                            Err(Errno::Isdir)
+                        } else {
+                            Ok(fs::remove_file(host_path)?)
+                        }
+                    });
+                errno_value(result)
+            }
+            "poll_oneoff" => Some(Value::I32(Errno::Nosys as i32)),
             "proc_exit" => {
                 let exit_code = arguments[0].expect_i32().unwrap();
                 exit(exit_code);
             }
-            "proc_raise" => todo!("WASI {}({:?})", function_name, arguments),
-            "sched_yield" => todo!("WASI {}({:?})", function_name, arguments),
+            "proc_raise" => Some(Value::I32(Errno::Nosys as i32)),
+            "sched_yield" => {
+                std::thread::yield_now();
+                success_code
+            }
             "random_get" => {
                 // A pointer to a buffer where the random bytes will be written
                 let ptr_buf = arguments[0].expect_i32().unwrap() as usize;
@@ -333,6 +838,284 @@ impl<'a> WasiDispatcher<'a> {
             _ => panic!("Unknown WASI function {function_name}({arguments:?})"),
         }
     }
+
+    fn host_file(&mut self, fd: usize) -> Result<&mut File, Errno> {
+        match self.files.get_mut(fd) {
+            Some(WasiFile::HostFile { file, .. }) => Ok(file),
+            Some(WasiFile::PreopenedDir { .. } | WasiFile::HostDir { .. }) => Err(Errno::Isdir),
+            None | Some(WasiFile::Closed) => Err(Errno::Badf),
+            // stdio and in-memory files can't seek
+            Some(_) => Err(Errno::Spipe),
+        }
+    }
+
+    /// Put a newly opened file in the first free file descriptor
+    fn insert_file(&mut self, file: WasiFile) -> usize {
+        match self
+            .files
+            .iter()
+            .position(|f| matches!(f, WasiFile::Closed))
+        {
+            Some(fd) => {
+                self.files[fd] = file;
+                fd
+            }
+            None => {
+                self.files.push(file);
+                self.files.len() - 1
+            }
+        }
+    }
+
+    /// Find the host path for a path relative to the directory `fd`, along with the preopened
+    /// directory that contains it. The path may not lead outside of that directory, not even
+    /// through a symlink.
+    fn resolve(
+        &self,
+        fd: usize,
+        path: &str,
+        follow_symlinks: bool,
+    ) -> Result<(PathBuf, PathBuf), Errno> {
+        let (dir, sandbox) = match self.files.get(fd) {
+            Some(WasiFile::PreopenedDir { host_path, .. }) => (host_path, host_path),
+            Some(WasiFile::HostDir { host_path, sandbox }) => (host_path, sandbox),
+            None | Some(WasiFile::Closed) => return Err(Errno::Badf),
+            Some(_) => return Err(Errno::Notdir),
+        };
+
+        let mut resolved = dir.clone();
+        for component in Path::new(path).components() {
+            match component {
+                Component::Normal(name) => resolved.push(name),
+                Component::CurDir => {}
+                Component::ParentDir if resolved != *sandbox => {
+                    resolved.pop();
+                }
+                // Absolute paths, or going up from the top of the sandbox
+                _ => return Err(Errno::Notcapable),
+            }
+        }
+
+        match real_path(&resolved, follow_symlinks)? {
+            Some(real_path) if !real_path.starts_with(sandbox) => Err(Errno::Notcapable),
+            _ => Ok((resolved, sandbox.clone())),
+        }
+    }
+}
+
+/// Where `path` really leads, following symlinks in its directories and, if `follow_symlinks`
+/// is set, in its last component. A dangling symlink leads to where its target would be created.
+/// `None` if the directory containing `path` doesn't exist, so nothing can be done with it.
+fn real_path(path: &Path, follow_symlinks: bool) -> Result<Option<PathBuf>, Errno> {
+    // Same limit as Linux
+    const MAX_SYMLINKS: usize = 40;
+
+    let mut path = path.to_path_buf();
+    for _ in 0..MAX_SYMLINKS {
+        if follow_symlinks {
+            if let Ok(real_path) = path.canonicalize() {
+                return Ok(Some(real_path));
+            }
+        }
+
+        let parent = match path.parent().map(Path::canonicalize) {
+            Some(Ok(parent)) => parent,
+            _ => return Ok(None),
+        };
+        let name = match path.file_name() {
+            Some(name) => parent.join(name),
+            None => return Ok(Some(parent)),
+        };
+        if !follow_symlinks {
+            return Ok(Some(name));
+        }
+
+        // `canonicalize` fails on a dangling symlink, so follow it ourselves
+        match fs::read_link(&name) {
+            Ok(target) => match join_lexically(&parent, &target) {
+                Some(target) => path = target,
+                None => return Err(Errno::Notcapable),
+            },
+            Err(_) => return Ok(Some(name)),
+        }
+    }
+
+    Err(Errno::Loop)
+}
+
+/// Join a relative path onto `base` without touching the file system. `None` for absolute paths.
+fn join_lexically(base: &Path, relative: &Path) -> Option<PathBuf> {
+    let mut joined = base.to_path_buf();
+    for component in relative.components() {
+        match component {
+            Component::Normal(name) => joined.push(name),
+            Component::CurDir => {}
+            Component::ParentDir => {
+                joined.pop();
+            }
+            Component::RootDir | Component::Prefix(_) => return None,
+        }
+    }
+    Some(joined)
+}
+
+fn errno_value(result: Result<(), Errno>) -> Option<Value> {
+    match result {
+        Ok(()) => Some(Value::I32(Errno::Success as i32)),
+        Err(errno) => Some(Value::I32(errno as i32)),
+    }
+}
+
+fn read_path(memory: &[u8], ptr_path: usize, path_len: usize) -> Result<&str, Errno> {
+    std::str::from_utf8(&memory[ptr_path..][..path_len]).map_err(|_| Errno::Ilseq)
+}
+
+fn open_host_path(
+    host_path: PathBuf,
+    sandbox: PathBuf,
+    oflags: i32,
+    rights_base: u64,
+    fdflags: u16,
+) -> Result<WasiFile, Errno> {
+    if oflags & OFLAGS_DIRECTORY != 0 || host_path.is_dir() {
+        if oflags & (OFLAGS_CREAT | OFLAGS_EXCL) == OFLAGS_CREAT | OFLAGS_EXCL {
+            return Err(Errno::Exist);
+        }
+        return match fs::metadata(&host_path)?.is_dir() {
+            true => Ok(WasiFile::HostDir { host_path, sandbox }),
+            false => Err(Errno::Notdir),
+        };
+    }
+
+    let write = rights_base & RIGHTS_FD_WRITE != 0;
+    let append = fdflags & FDFLAGS_APPEND != 0;
+    let create = oflags & OFLAGS_CREAT != 0;
+    let exclusive = oflags & OFLAGS_EXCL != 0;
+
+    let file = OpenOptions::new()
+        .read(rights_base & RIGHTS_FD_READ != 0 || !write)
+        .write(write && !append)
+        .append(append)
+        .create(create && !exclusive)
+        .create_new(create && exclusive)
+        .truncate(oflags & OFLAGS_TRUNC != 0)
+        .open(host_path)?;
+
+    Ok(WasiFile::HostFile { file, fdflags })
+}
+
+#[cfg(unix)]
+fn symlink(target: &str, link: &Path) -> Result<(), Errno> {
+    Ok(std::os::unix::fs::symlink(target, link)?)
+}
+
+#[cfg(not(unix))]
+fn symlink(_target: &str, _link: &Path) -> Result<(), Errno> {
+    Err(Errno::Nosys)
+}
+
+/// Read the (base, length) pairs from an array of `iovec`s
+fn read_iovs(memory: &[u8], ptr_iovs: usize, iovs_len: i32) -> std::vec::Vec<(usize, usize)> {
+    // https://man7.org/linux/man-pages/man2/readv.2.html
+    // struct iovec {
+    //     void  *iov_base;    /* Starting address */
+    //     size_t iov_len;     /* Number of bytes to transfer */
+    // };
+    (0..iovs_len.max(0) as usize)
+        .map(|i| {
+            let ptr_iov = ptr_iovs + 8 * i;
+            (
+                read_u32(memory, ptr_iov) as usize,
+                read_u32(memory, ptr_iov + 4) as usize,
+            )
+        })
+        .collect()
+}
+
+fn read_into_iovs(
+    reader: &mut impl Read,
+    memory: &mut [u8],
+    iovs: &[(usize, usize)],
+) -> io::Result<usize> {
+    let mut n_read = 0;
+    for (iov_base, iov_len) in iovs.iter().copied() {
+        let n = reader.read(&mut memory[iov_base..][..iov_len])?;
+        n_read += n;
+        if n < iov_len {
+            break;
+        }
+    }
+    Ok(n_read)
+}
+
+/// The name, inode and file type of each entry in a directory, including `.` and `..`
+fn read_dir_entries(host_path: &Path) -> io::Result<std::vec::Vec<(std::vec::Vec<u8>, u64, u8)>> {
+    let mut entries = std::vec::Vec::new();
+    for entry in fs::read_dir(host_path)? {
+        let entry = entry?;
+        let metadata = entry.metadata()?;
+        let name = entry.file_name().to_string_lossy().as_bytes().to_vec();
+        entries.push((name, inode(&metadata), filetype(&metadata)));
+    }
+    entries.sort();
+
+    let dot = fs::metadata(host_path)?;
+    let dot_dot = fs::metadata(host_path.join(".."))?;
+    entries.insert(0, (b".".to_vec(), inode(&dot), FILETYPE_DIRECTORY));
+    entries.insert(1, (b"..".to_vec(), inode(&dot_dot), FILETYPE_DIRECTORY));
+
+    Ok(entries)
+}
+
+fn filetype(metadata: &Metadata) -> u8 {
+    let file_type = metadata.file_type();
+    if file_type.is_dir() {
+        FILETYPE_DIRECTORY
+    } else if file_type.is_file() {
+        FILETYPE_REGULAR_FILE
+    } else if file_type.is_symlink() {
+        FILETYPE_SYMBOLIC_LINK
+    } else {
+        FILETYPE_UNKNOWN
+    }
+}
+
+#[cfg(unix)]
+fn inode(metadata: &Metadata) -> u64 {
+    std::os::unix::fs::MetadataExt::ino(metadata)
+}
+
+#[cfg(not(unix))]
+fn inode(_metadata: &Metadata) -> u64 {
+    0
+}
+
+fn nanos_since_epoch(time: io::Result<SystemTime>) -> u64 {
+    time.ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map_or(0, |d| d.as_nanos() as u64)
+}
+
+/// Write a `wasi_filestat_t`
+fn write_filestat(memory: &mut [u8], ptr_buf: usize, metadata: &Metadata) {
+    #[cfg(unix)]
+    let (dev, nlink) = {
+        use std::os::unix::fs::MetadataExt;
+        (metadata.dev(), metadata.nlink())
+    };
+    #[cfg(not(unix))]
+    let (dev, nlink) = (0, 1);
+
+    memory[ptr_buf..][..64].fill(0);
+    write_u64(memory, ptr_buf, dev);
+    write_u64(memory, ptr_buf + 8, inode(metadata));
+    memory[ptr_buf + 16] = filetype(metadata);
+    write_u64(memory, ptr_buf + 24, nlink);
+    write_u64(memory, ptr_buf + 32, metadata.len());
+    write_u64(memory, ptr_buf + 40, nanos_since_epoch(metadata.accessed()));
+    write_u64(memory, ptr_buf + 48, nanos_since_epoch(metadata.modified()));
+    // We don't have the status change time, and creation time is the closest thing
+    write_u64(memory, ptr_buf + 56, nanos_since_epoch(metadata.created()));
 }
 
 fn read_u32(memory: &[u8], addr: usize) -> u32 {
@@ -355,6 +1138,10 @@ fn write_i32(memory: &mut [u8], addr: usize, value: i32) {
     memory[addr..][..4].copy_from_slice(&value.to_le_bytes());
 }
 
+fn write_u64(memory: &mut [u8], addr: usize, value: u64) {
+    memory[addr..][..8].copy_from_slice(&value.to_le_bytes());
+}
+
 /// Error codes returned by functions.
 /// Not all of these error codes are returned by the functions provided by this
 /// API; some are used in higher-level library layers, and others are provided
@@ -517,3 +1304,21 @@ pub enum Errno {
     /// Extension: Capabilities insufficient.
     Notcapable,
 }
+
+impl From<io::Error> for Errno {
+    fn from(e: io::Error) -> Self {
+        use io::ErrorKind::*;
+        match e.kind() {
+            NotFound => Errno::Noent,
+            PermissionDenied => Errno::Access,
+            AlreadyExists => Errno::Exist,
+            WouldBlock => Errno::Again,
+            InvalidInput => Errno::Inval,
+            InvalidData => Errno::Ilseq,
+            Interrupted => Errno::Intr,
+            Unsupported => Errno::Notsup,
+            OutOfMemory => Errno::Nomem,
+            _ => Errno::Io,
+        }
+    }
+}
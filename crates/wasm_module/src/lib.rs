@@ -3,6 +3,8 @@ pub mod opcodes;
 pub mod parse;
 pub mod sections;
 pub mod serialize;
+pub mod source_map;
+pub mod wat;
 
 use std::iter::repeat;
 
@@ -17,11 +19,12 @@ use bumpalo::{collections::Vec, Bump};
 use self::linking::{IndexRelocType, LinkingSection, RelocationSection, WasmObjectSymbol};
 use self::parse::{Parse, ParseError};
 use self::sections::{
-    CodeSection, DataSection, ElementSection, ExportSection, FunctionSection, GlobalSection,
-    ImportDesc, ImportSection, MemorySection, NameSection, OpaqueSection, Section, SectionId,
-    TableSection, TypeSection,
+    CodeSection, DataCountSection, DataSection, ElementSection, ExportSection, FunctionSection,
+    GlobalSection, ImportDesc, ImportSection, MemorySection, NameSection, OpaqueSection, Section,
+    SectionId, TableSection, TypeSection,
 };
 pub use self::serialize::{SerialBuffer, Serialize};
+use self::source_map::SourceMap;
 
 pub const STACK_POINTER_GLOBAL_ID: u32 = 0;
 pub const FRAME_ALIGNMENT_BYTES: i32 = 16;
@@ -39,12 +42,14 @@ pub struct WasmModule<'a> {
     pub export: ExportSection<'a>,
     pub start: OpaqueSection<'a>,
     pub element: ElementSection<'a>,
+    pub data_count: DataCountSection,
     pub code: CodeSection<'a>,
     pub data: DataSection<'a>,
     pub linking: LinkingSection<'a>,
     pub reloc_code: RelocationSection<'a>,
     pub reloc_data: RelocationSection<'a>,
     pub names: NameSection<'a>,
+    pub source_map: SourceMap<'a>,
 }
 
 impl<'a> WasmModule<'a> {
@@ -61,12 +66,14 @@ impl<'a> WasmModule<'a> {
             export: ExportSection::new(arena),
             start: OpaqueSection::new(),
             element: ElementSection::new(arena),
+            data_count: DataCountSection::new(),
             code: CodeSection::new(arena),
             data: DataSection::new(arena),
             linking: LinkingSection::new(arena),
             reloc_code: RelocationSection::new(arena, "reloc.CODE"),
             reloc_data: RelocationSection::new(arena, "reloc.DATA"),
             names: NameSection::new(arena),
+            source_map: SourceMap::new(arena),
         }
     }
 
@@ -93,9 +100,30 @@ impl<'a> WasmModule<'a> {
         self.export.serialize(buffer);
         self.start.serialize(buffer);
         self.element.serialize(buffer);
+        if self.data_count.is_present {
+            self.data.serialize_count(buffer);
+        }
         self.code.serialize(buffer);
+        let code_section_end = buffer.size();
         self.data.serialize(buffer);
         self.names.serialize(buffer);
+        self.source_map.serialize(buffer, |fn_index| {
+            self.serialized_function_offset(code_section_end, fn_index)
+        });
+    }
+
+    /// Where a function ended up in the serialized module.
+    /// Dummy functions and imports have no code of their own, so they return None.
+    fn serialized_function_offset(&self, code_section_end: usize, fn_index: u32) -> Option<u32> {
+        let first_fn_index =
+            self.import.function_count() as u32 + self.code.dead_import_dummy_count;
+        let internal_fn_index = fn_index.checked_sub(first_fn_index)? as usize;
+        let offset = *self.code.function_offsets.get(internal_fn_index)? as usize;
+
+        // The Code section ends with our `bytes`, starting from the first function
+        let first_fn_offset = self.code.function_offsets[0] as usize;
+        let first_fn_start = code_section_end - (self.code.bytes.len() - first_fn_offset);
+        Some((first_fn_start + offset - first_fn_offset) as u32)
     }
 
     /// Module size in bytes (assuming no linker data)
@@ -110,9 +138,11 @@ impl<'a> WasmModule<'a> {
             + self.export.size()
             + self.start.size()
             + self.element.size()
+            + self.data_count.size()
             + self.code.size()
             + self.data.size()
             + self.names.size()
+            + self.source_map.size()
     }
 
     pub fn preload(
@@ -141,7 +171,7 @@ impl<'a> WasmModule<'a> {
         let export = ExportSection::parse(arena, bytes, &mut cursor)?;
         let start = OpaqueSection::parse((arena, SectionId::Start), bytes, &mut cursor)?;
         let element = ElementSection::parse(arena, bytes, &mut cursor)?;
-        let _data_count = OpaqueSection::parse((arena, SectionId::DataCount), bytes, &mut cursor)?;
+        let data_count = DataCountSection::parse((), bytes, &mut cursor)?;
         let code = CodeSection::parse(arena, bytes, &mut cursor)?;
         let data = DataSection::parse(arena, bytes, &mut cursor)?;
 
@@ -224,12 +254,14 @@ impl<'a> WasmModule<'a> {
             export,
             start,
             element,
+            data_count,
             code,
             data,
             linking,
             reloc_code,
             reloc_data,
             names,
+            source_map: SourceMap::new(arena),
         })
     }
 
@@ -374,8 +406,10 @@ impl<'a> WasmModule<'a> {
         // Live function indices are unchanged, so no relocations are needed.
         //
         let mut buffer = Vec::with_capacity_in(self.code.bytes.len(), arena);
+        let mut function_offsets = Vec::with_capacity_in(self.code.function_offsets.len(), arena);
         self.code.function_count.serialize(&mut buffer);
         for (i, fn_index) in (fn_index_min..fn_index_max).enumerate() {
+            function_offsets.push(buffer.len() as u32);
             if live_flags[fn_index as usize] {
                 let code_start = self.code.function_offsets[i] as usize;
                 let code_end = if i < self.code.function_offsets.len() - 1 {
@@ -390,6 +424,12 @@ impl<'a> WasmModule<'a> {
         }
 
         self.code.bytes = buffer;
+        self.code.function_offsets = function_offsets;
+
+        // Source locations of dead functions would point at dummies
+        self.source_map
+            .locations
+            .retain(|(fn_index, _, _)| live_flags[*fn_index as usize]);
     }
 
     fn trace_live_functions<I: Iterator<Item = u32>>(
@@ -692,6 +732,7 @@ pub enum ValueType {
     I64 = 0x7e,
     F32 = 0x7d,
     F64 = 0x7c,
+    V128 = 0x7b,
 }
 
 impl ValueType {
@@ -711,6 +752,7 @@ impl From<u8> for ValueType {
             0x7e => Self::I64,
             0x7d => Self::F32,
             0x7c => Self::F64,
+            0x7b => Self::V128,
             _ => internal_error!("Invalid ValueType 0x{:02x}", x),
         }
     }
@@ -723,6 +765,7 @@ impl From<Value> for ValueType {
             Value::I64(_) => Self::I64,
             Value::F32(_) => Self::F32,
             Value::F64(_) => Self::F64,
+            Value::V128(_) => Self::V128,
         }
     }
 }
@@ -749,6 +792,8 @@ pub enum Value {
     I64(i64),
     F32(f32),
     F64(f64),
+    /// 128-bit SIMD vector, as little-endian bytes
+    V128([u8; 16]),
 }
 
 impl Value {
@@ -776,6 +821,12 @@ impl Value {
             _ => Err((ValueType::F64, ValueType::from(*self))),
         }
     }
+    pub fn expect_v128(&self) -> Result<[u8; 16], (ValueType, ValueType)> {
+        match self {
+            Value::V128(x) => Ok(*x),
+            _ => Err((ValueType::V128, ValueType::from(*self))),
+        }
+    }
 }
 
 impl From<u32> for Value {
@@ -52,6 +52,7 @@ pub enum OpCode {
     CURRENTMEMORY = 0x3f,
     GROWMEMORY = 0x40,
     MEMORY = 0xFC,
+    SIMD = 0xFD,
     I32CONST = 0x41,
     I64CONST = 0x42,
     F32CONST = 0x43,
@@ -244,6 +245,7 @@ pub const LOOKUP_TABLE: [Option<OpCode>; 256] = {
     result[0x3f] = Some(CURRENTMEMORY);
     result[0x40] = Some(GROWMEMORY);
     result[0xfc] = Some(MEMORY);
+    result[0xfd] = Some(SIMD);
     result[0x41] = Some(I32CONST);
     result[0x42] = Some(I64CONST);
     result[0x43] = Some(F32CONST);
@@ -398,6 +400,196 @@ impl From<u8> for OpCode {
     }
 }
 
+impl OpCode {
+    /// The name of the instruction in the WebAssembly text format.
+    /// `MEMORY` and `SIMD` are prefixes, so the instruction is named by whatever follows them.
+    pub fn text_name(self) -> &'static str {
+        use OpCode::*;
+
+        match self {
+            UNREACHABLE => "unreachable",
+            NOP => "nop",
+            BLOCK => "block",
+            LOOP => "loop",
+            IF => "if",
+            ELSE => "else",
+            END => "end",
+            BR => "br",
+            BRIF => "br_if",
+            BRTABLE => "br_table",
+            RETURN => "return",
+            CALL => "call",
+            CALLINDIRECT => "call_indirect",
+            DROP => "drop",
+            SELECT => "select",
+            GETLOCAL => "local.get",
+            SETLOCAL => "local.set",
+            TEELOCAL => "local.tee",
+            GETGLOBAL => "global.get",
+            SETGLOBAL => "global.set",
+            I32LOAD => "i32.load",
+            I64LOAD => "i64.load",
+            F32LOAD => "f32.load",
+            F64LOAD => "f64.load",
+            I32LOAD8S => "i32.load8_s",
+            I32LOAD8U => "i32.load8_u",
+            I32LOAD16S => "i32.load16_s",
+            I32LOAD16U => "i32.load16_u",
+            I64LOAD8S => "i64.load8_s",
+            I64LOAD8U => "i64.load8_u",
+            I64LOAD16S => "i64.load16_s",
+            I64LOAD16U => "i64.load16_u",
+            I64LOAD32S => "i64.load32_s",
+            I64LOAD32U => "i64.load32_u",
+            I32STORE => "i32.store",
+            I64STORE => "i64.store",
+            F32STORE => "f32.store",
+            F64STORE => "f64.store",
+            I32STORE8 => "i32.store8",
+            I32STORE16 => "i32.store16",
+            I64STORE8 => "i64.store8",
+            I64STORE16 => "i64.store16",
+            I64STORE32 => "i64.store32",
+            CURRENTMEMORY => "memory.size",
+            GROWMEMORY => "memory.grow",
+            I32CONST => "i32.const",
+            I64CONST => "i64.const",
+            F32CONST => "f32.const",
+            F64CONST => "f64.const",
+            I32EQZ => "i32.eqz",
+            I32EQ => "i32.eq",
+            I32NE => "i32.ne",
+            I32LTS => "i32.lt_s",
+            I32LTU => "i32.lt_u",
+            I32GTS => "i32.gt_s",
+            I32GTU => "i32.gt_u",
+            I32LES => "i32.le_s",
+            I32LEU => "i32.le_u",
+            I32GES => "i32.ge_s",
+            I32GEU => "i32.ge_u",
+            I64EQZ => "i64.eqz",
+            I64EQ => "i64.eq",
+            I64NE => "i64.ne",
+            I64LTS => "i64.lt_s",
+            I64LTU => "i64.lt_u",
+            I64GTS => "i64.gt_s",
+            I64GTU => "i64.gt_u",
+            I64LES => "i64.le_s",
+            I64LEU => "i64.le_u",
+            I64GES => "i64.ge_s",
+            I64GEU => "i64.ge_u",
+            F32EQ => "f32.eq",
+            F32NE => "f32.ne",
+            F32LT => "f32.lt",
+            F32GT => "f32.gt",
+            F32LE => "f32.le",
+            F32GE => "f32.ge",
+            F64EQ => "f64.eq",
+            F64NE => "f64.ne",
+            F64LT => "f64.lt",
+            F64GT => "f64.gt",
+            F64LE => "f64.le",
+            F64GE => "f64.ge",
+            I32CLZ => "i32.clz",
+            I32CTZ => "i32.ctz",
+            I32POPCNT => "i32.popcnt",
+            I32ADD => "i32.add",
+            I32SUB => "i32.sub",
+            I32MUL => "i32.mul",
+            I32DIVS => "i32.div_s",
+            I32DIVU => "i32.div_u",
+            I32REMS => "i32.rem_s",
+            I32REMU => "i32.rem_u",
+            I32AND => "i32.and",
+            I32OR => "i32.or",
+            I32XOR => "i32.xor",
+            I32SHL => "i32.shl",
+            I32SHRS => "i32.shr_s",
+            I32SHRU => "i32.shr_u",
+            I32ROTL => "i32.rotl",
+            I32ROTR => "i32.rotr",
+            I64CLZ => "i64.clz",
+            I64CTZ => "i64.ctz",
+            I64POPCNT => "i64.popcnt",
+            I64ADD => "i64.add",
+            I64SUB => "i64.sub",
+            I64MUL => "i64.mul",
+            I64DIVS => "i64.div_s",
+            I64DIVU => "i64.div_u",
+            I64REMS => "i64.rem_s",
+            I64REMU => "i64.rem_u",
+            I64AND => "i64.and",
+            I64OR => "i64.or",
+            I64XOR => "i64.xor",
+            I64SHL => "i64.shl",
+            I64SHRS => "i64.shr_s",
+            I64SHRU => "i64.shr_u",
+            I64ROTL => "i64.rotl",
+            I64ROTR => "i64.rotr",
+            F32ABS => "f32.abs",
+            F32NEG => "f32.neg",
+            F32CEIL => "f32.ceil",
+            F32FLOOR => "f32.floor",
+            F32TRUNC => "f32.trunc",
+            F32NEAREST => "f32.nearest",
+            F32SQRT => "f32.sqrt",
+            F32ADD => "f32.add",
+            F32SUB => "f32.sub",
+            F32MUL => "f32.mul",
+            F32DIV => "f32.div",
+            F32MIN => "f32.min",
+            F32MAX => "f32.max",
+            F32COPYSIGN => "f32.copysign",
+            F64ABS => "f64.abs",
+            F64NEG => "f64.neg",
+            F64CEIL => "f64.ceil",
+            F64FLOOR => "f64.floor",
+            F64TRUNC => "f64.trunc",
+            F64NEAREST => "f64.nearest",
+            F64SQRT => "f64.sqrt",
+            F64ADD => "f64.add",
+            F64SUB => "f64.sub",
+            F64MUL => "f64.mul",
+            F64DIV => "f64.div",
+            F64MIN => "f64.min",
+            F64MAX => "f64.max",
+            F64COPYSIGN => "f64.copysign",
+            I32WRAPI64 => "i32.wrap_i64",
+            I32TRUNCSF32 => "i32.trunc_f32_s",
+            I32TRUNCUF32 => "i32.trunc_f32_u",
+            I32TRUNCSF64 => "i32.trunc_f64_s",
+            I32TRUNCUF64 => "i32.trunc_f64_u",
+            I64EXTENDSI32 => "i64.extend_i32_s",
+            I64EXTENDUI32 => "i64.extend_i32_u",
+            I64TRUNCSF32 => "i64.trunc_f32_s",
+            I64TRUNCUF32 => "i64.trunc_f32_u",
+            I64TRUNCSF64 => "i64.trunc_f64_s",
+            I64TRUNCUF64 => "i64.trunc_f64_u",
+            F32CONVERTSI32 => "f32.convert_i32_s",
+            F32CONVERTUI32 => "f32.convert_i32_u",
+            F32CONVERTSI64 => "f32.convert_i64_s",
+            F32CONVERTUI64 => "f32.convert_i64_u",
+            F32DEMOTEF64 => "f32.demote_f64",
+            F64CONVERTSI32 => "f64.convert_i32_s",
+            F64CONVERTUI32 => "f64.convert_i32_u",
+            F64CONVERTSI64 => "f64.convert_i64_s",
+            F64CONVERTUI64 => "f64.convert_i64_u",
+            F64PROMOTEF32 => "f64.promote_f32",
+            I32REINTERPRETF32 => "i32.reinterpret_f32",
+            I64REINTERPRETF64 => "i64.reinterpret_f64",
+            F32REINTERPRETI32 => "f32.reinterpret_i32",
+            F64REINTERPRETI64 => "f64.reinterpret_i64",
+            I32EXTEND8S => "i32.extend8_s",
+            I32EXTEND16S => "i32.extend16_s",
+            I64EXTEND8S => "i64.extend8_s",
+            I64EXTEND16S => "i64.extend16_s",
+            I64EXTEND32S => "i64.extend32_s",
+            MEMORY => "<memory prefix>",
+            SIMD => "<simd prefix>",
+        }
+    }
+}
+
 #[repr(u8)]
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum MemoryInstruction {
@@ -405,6 +597,9 @@ pub enum MemoryInstruction {
     DataDrop = 9,
     MemoryCopy = 10,
     MemoryFill = 11,
+    TableInit = 12,
+    ElemDrop = 13,
+    TableCopy = 14,
 }
 
 impl TryFrom<u8> for MemoryInstruction {
@@ -416,11 +611,575 @@ impl TryFrom<u8> for MemoryInstruction {
             9 => Ok(Self::DataDrop),
             10 => Ok(Self::MemoryCopy),
             11 => Ok(Self::MemoryFill),
+            12 => Ok(Self::TableInit),
+            13 => Ok(Self::ElemDrop),
+            14 => Ok(Self::TableCopy),
+            _ => Err(value),
+        }
+    }
+}
+
+impl MemoryInstruction {
+    /// The name of the instruction in the WebAssembly text format
+    pub fn text_name(self) -> &'static str {
+        match self {
+            Self::MemoryInit => "memory.init",
+            Self::DataDrop => "data.drop",
+            Self::MemoryCopy => "memory.copy",
+            Self::MemoryFill => "memory.fill",
+            Self::TableInit => "table.init",
+            Self::ElemDrop => "elem.drop",
+            Self::TableCopy => "table.copy",
+        }
+    }
+}
+
+/// Instructions with the SIMD prefix (0xFD), operating on 128-bit vectors.
+/// After the prefix, the instruction is identified by a LEB-encoded u32.
+/// https://webassembly.github.io/spec/core/binary/instructions.html#vector-instructions
+// NOTE: when adding a new variant, be sure to add it to SIMD_LOOKUP_TABLE below as well
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SimdInstruction {
+    V128Load = 0x00,
+    V128Load8x8S = 0x01,
+    V128Load8x8U = 0x02,
+    V128Load16x4S = 0x03,
+    V128Load16x4U = 0x04,
+    V128Load32x2S = 0x05,
+    V128Load32x2U = 0x06,
+    V128Load8Splat = 0x07,
+    V128Load16Splat = 0x08,
+    V128Load32Splat = 0x09,
+    V128Load64Splat = 0x0a,
+    V128Store = 0x0b,
+    V128Const = 0x0c,
+    I8x16Shuffle = 0x0d,
+    I8x16Swizzle = 0x0e,
+    I8x16Splat = 0x0f,
+    I16x8Splat = 0x10,
+    I32x4Splat = 0x11,
+    I64x2Splat = 0x12,
+    F32x4Splat = 0x13,
+    F64x2Splat = 0x14,
+    I8x16ExtractLaneS = 0x15,
+    I8x16ExtractLaneU = 0x16,
+    I8x16ReplaceLane = 0x17,
+    I16x8ExtractLaneS = 0x18,
+    I16x8ExtractLaneU = 0x19,
+    I16x8ReplaceLane = 0x1a,
+    I32x4ExtractLane = 0x1b,
+    I32x4ReplaceLane = 0x1c,
+    I64x2ExtractLane = 0x1d,
+    I64x2ReplaceLane = 0x1e,
+    F32x4ExtractLane = 0x1f,
+    F32x4ReplaceLane = 0x20,
+    F64x2ExtractLane = 0x21,
+    F64x2ReplaceLane = 0x22,
+    I8x16Eq = 0x23,
+    I8x16Ne = 0x24,
+    I8x16LtS = 0x25,
+    I8x16LtU = 0x26,
+    I8x16GtS = 0x27,
+    I8x16GtU = 0x28,
+    I8x16LeS = 0x29,
+    I8x16LeU = 0x2a,
+    I8x16GeS = 0x2b,
+    I8x16GeU = 0x2c,
+    I16x8Eq = 0x2d,
+    I16x8Ne = 0x2e,
+    I16x8LtS = 0x2f,
+    I16x8LtU = 0x30,
+    I16x8GtS = 0x31,
+    I16x8GtU = 0x32,
+    I16x8LeS = 0x33,
+    I16x8LeU = 0x34,
+    I16x8GeS = 0x35,
+    I16x8GeU = 0x36,
+    I32x4Eq = 0x37,
+    I32x4Ne = 0x38,
+    I32x4LtS = 0x39,
+    I32x4LtU = 0x3a,
+    I32x4GtS = 0x3b,
+    I32x4GtU = 0x3c,
+    I32x4LeS = 0x3d,
+    I32x4LeU = 0x3e,
+    I32x4GeS = 0x3f,
+    I32x4GeU = 0x40,
+    F32x4Eq = 0x41,
+    F32x4Ne = 0x42,
+    F32x4Lt = 0x43,
+    F32x4Gt = 0x44,
+    F32x4Le = 0x45,
+    F32x4Ge = 0x46,
+    F64x2Eq = 0x47,
+    F64x2Ne = 0x48,
+    F64x2Lt = 0x49,
+    F64x2Gt = 0x4a,
+    F64x2Le = 0x4b,
+    F64x2Ge = 0x4c,
+    V128Not = 0x4d,
+    V128And = 0x4e,
+    V128Andnot = 0x4f,
+    V128Or = 0x50,
+    V128Xor = 0x51,
+    V128Bitselect = 0x52,
+    V128AnyTrue = 0x53,
+    V128Load8Lane = 0x54,
+    V128Load16Lane = 0x55,
+    V128Load32Lane = 0x56,
+    V128Load64Lane = 0x57,
+    V128Store8Lane = 0x58,
+    V128Store16Lane = 0x59,
+    V128Store32Lane = 0x5a,
+    V128Store64Lane = 0x5b,
+    V128Load32Zero = 0x5c,
+    V128Load64Zero = 0x5d,
+    F32x4DemoteF64x2Zero = 0x5e,
+    F64x2PromoteLowF32x4 = 0x5f,
+    I8x16Abs = 0x60,
+    I8x16Neg = 0x61,
+    I8x16Popcnt = 0x62,
+    I8x16AllTrue = 0x63,
+    I8x16Bitmask = 0x64,
+    I8x16NarrowI16x8S = 0x65,
+    I8x16NarrowI16x8U = 0x66,
+    F32x4Ceil = 0x67,
+    F32x4Floor = 0x68,
+    F32x4Trunc = 0x69,
+    F32x4Nearest = 0x6a,
+    I8x16Shl = 0x6b,
+    I8x16ShrS = 0x6c,
+    I8x16ShrU = 0x6d,
+    I8x16Add = 0x6e,
+    I8x16AddSatS = 0x6f,
+    I8x16AddSatU = 0x70,
+    I8x16Sub = 0x71,
+    I8x16SubSatS = 0x72,
+    I8x16SubSatU = 0x73,
+    F64x2Ceil = 0x74,
+    F64x2Floor = 0x75,
+    I8x16MinS = 0x76,
+    I8x16MinU = 0x77,
+    I8x16MaxS = 0x78,
+    I8x16MaxU = 0x79,
+    F64x2Trunc = 0x7a,
+    I8x16AvgrU = 0x7b,
+    I16x8ExtaddPairwiseI8x16S = 0x7c,
+    I16x8ExtaddPairwiseI8x16U = 0x7d,
+    I32x4ExtaddPairwiseI16x8S = 0x7e,
+    I32x4ExtaddPairwiseI16x8U = 0x7f,
+    I16x8Abs = 0x80,
+    I16x8Neg = 0x81,
+    I16x8Q15mulrSatS = 0x82,
+    I16x8AllTrue = 0x83,
+    I16x8Bitmask = 0x84,
+    I16x8NarrowI32x4S = 0x85,
+    I16x8NarrowI32x4U = 0x86,
+    I16x8ExtendLowI8x16S = 0x87,
+    I16x8ExtendHighI8x16S = 0x88,
+    I16x8ExtendLowI8x16U = 0x89,
+    I16x8ExtendHighI8x16U = 0x8a,
+    I16x8Shl = 0x8b,
+    I16x8ShrS = 0x8c,
+    I16x8ShrU = 0x8d,
+    I16x8Add = 0x8e,
+    I16x8AddSatS = 0x8f,
+    I16x8AddSatU = 0x90,
+    I16x8Sub = 0x91,
+    I16x8SubSatS = 0x92,
+    I16x8SubSatU = 0x93,
+    F64x2Nearest = 0x94,
+    I16x8Mul = 0x95,
+    I16x8MinS = 0x96,
+    I16x8MinU = 0x97,
+    I16x8MaxS = 0x98,
+    I16x8MaxU = 0x99,
+    I16x8AvgrU = 0x9b,
+    I16x8ExtmulLowI8x16S = 0x9c,
+    I16x8ExtmulHighI8x16S = 0x9d,
+    I16x8ExtmulLowI8x16U = 0x9e,
+    I16x8ExtmulHighI8x16U = 0x9f,
+    I32x4Abs = 0xa0,
+    I32x4Neg = 0xa1,
+    I32x4AllTrue = 0xa3,
+    I32x4Bitmask = 0xa4,
+    I32x4ExtendLowI16x8S = 0xa7,
+    I32x4ExtendHighI16x8S = 0xa8,
+    I32x4ExtendLowI16x8U = 0xa9,
+    I32x4ExtendHighI16x8U = 0xaa,
+    I32x4Shl = 0xab,
+    I32x4ShrS = 0xac,
+    I32x4ShrU = 0xad,
+    I32x4Add = 0xae,
+    I32x4Sub = 0xb1,
+    I32x4Mul = 0xb5,
+    I32x4MinS = 0xb6,
+    I32x4MinU = 0xb7,
+    I32x4MaxS = 0xb8,
+    I32x4MaxU = 0xb9,
+    I32x4DotI16x8S = 0xba,
+    I32x4ExtmulLowI16x8S = 0xbc,
+    I32x4ExtmulHighI16x8S = 0xbd,
+    I32x4ExtmulLowI16x8U = 0xbe,
+    I32x4ExtmulHighI16x8U = 0xbf,
+    I64x2Abs = 0xc0,
+    I64x2Neg = 0xc1,
+    I64x2AllTrue = 0xc3,
+    I64x2Bitmask = 0xc4,
+    I64x2ExtendLowI32x4S = 0xc7,
+    I64x2ExtendHighI32x4S = 0xc8,
+    I64x2ExtendLowI32x4U = 0xc9,
+    I64x2ExtendHighI32x4U = 0xca,
+    I64x2Shl = 0xcb,
+    I64x2ShrS = 0xcc,
+    I64x2ShrU = 0xcd,
+    I64x2Add = 0xce,
+    I64x2Sub = 0xd1,
+    I64x2Mul = 0xd5,
+    I64x2Eq = 0xd6,
+    I64x2Ne = 0xd7,
+    I64x2LtS = 0xd8,
+    I64x2GtS = 0xd9,
+    I64x2LeS = 0xda,
+    I64x2GeS = 0xdb,
+    I64x2ExtmulLowI32x4S = 0xdc,
+    I64x2ExtmulHighI32x4S = 0xdd,
+    I64x2ExtmulLowI32x4U = 0xde,
+    I64x2ExtmulHighI32x4U = 0xdf,
+    F32x4Abs = 0xe0,
+    F32x4Neg = 0xe1,
+    F32x4Sqrt = 0xe3,
+    F32x4Add = 0xe4,
+    F32x4Sub = 0xe5,
+    F32x4Mul = 0xe6,
+    F32x4Div = 0xe7,
+    F32x4Min = 0xe8,
+    F32x4Max = 0xe9,
+    F32x4Pmin = 0xea,
+    F32x4Pmax = 0xeb,
+    F64x2Abs = 0xec,
+    F64x2Neg = 0xed,
+    F64x2Sqrt = 0xef,
+    F64x2Add = 0xf0,
+    F64x2Sub = 0xf1,
+    F64x2Mul = 0xf2,
+    F64x2Div = 0xf3,
+    F64x2Min = 0xf4,
+    F64x2Max = 0xf5,
+    F64x2Pmin = 0xf6,
+    F64x2Pmax = 0xf7,
+    I32x4TruncSatF32x4S = 0xf8,
+    I32x4TruncSatF32x4U = 0xf9,
+    F32x4ConvertI32x4S = 0xfa,
+    F32x4ConvertI32x4U = 0xfb,
+    I32x4TruncSatF64x2SZero = 0xfc,
+    I32x4TruncSatF64x2UZero = 0xfd,
+    F64x2ConvertLowI32x4S = 0xfe,
+    F64x2ConvertLowI32x4U = 0xff,
+}
+
+pub const SIMD_LOOKUP_TABLE: [Option<SimdInstruction>; 256] = {
+    use SimdInstruction::*;
+
+    let mut result = [None; 256];
+
+    result[0x00] = Some(V128Load);
+    result[0x01] = Some(V128Load8x8S);
+    result[0x02] = Some(V128Load8x8U);
+    result[0x03] = Some(V128Load16x4S);
+    result[0x04] = Some(V128Load16x4U);
+    result[0x05] = Some(V128Load32x2S);
+    result[0x06] = Some(V128Load32x2U);
+    result[0x07] = Some(V128Load8Splat);
+    result[0x08] = Some(V128Load16Splat);
+    result[0x09] = Some(V128Load32Splat);
+    result[0x0a] = Some(V128Load64Splat);
+    result[0x0b] = Some(V128Store);
+    result[0x0c] = Some(V128Const);
+    result[0x0d] = Some(I8x16Shuffle);
+    result[0x0e] = Some(I8x16Swizzle);
+    result[0x0f] = Some(I8x16Splat);
+    result[0x10] = Some(I16x8Splat);
+    result[0x11] = Some(I32x4Splat);
+    result[0x12] = Some(I64x2Splat);
+    result[0x13] = Some(F32x4Splat);
+    result[0x14] = Some(F64x2Splat);
+    result[0x15] = Some(I8x16ExtractLaneS);
+    result[0x16] = Some(I8x16ExtractLaneU);
+    result[0x17] = Some(I8x16ReplaceLane);
+    result[0x18] = Some(I16x8ExtractLaneS);
+    result[0x19] = Some(I16x8ExtractLaneU);
+    result[0x1a] = Some(I16x8ReplaceLane);
+    result[0x1b] = Some(I32x4ExtractLane);
+    result[0x1c] = Some(I32x4ReplaceLane);
+    result[0x1d] = Some(I64x2ExtractLane);
+    result[0x1e] = Some(I64x2ReplaceLane);
+    result[0x1f] = Some(F32x4ExtractLane);
+    result[0x20] = Some(F32x4ReplaceLane);
+    result[0x21] = Some(F64x2ExtractLane);
+    result[0x22] = Some(F64x2ReplaceLane);
+    result[0x23] = Some(I8x16Eq);
+    result[0x24] = Some(I8x16Ne);
+    result[0x25] = Some(I8x16LtS);
+    result[0x26] = Some(I8x16LtU);
+    result[0x27] = Some(I8x16GtS);
+    result[0x28] = Some(I8x16GtU);
+    result[0x29] = Some(I8x16LeS);
+    result[0x2a] = Some(I8x16LeU);
+    result[0x2b] = Some(I8x16GeS);
+    result[0x2c] = Some(I8x16GeU);
+    result[0x2d] = Some(I16x8Eq);
+    result[0x2e] = Some(I16x8Ne);
+    result[0x2f] = Some(I16x8LtS);
+    result[0x30] = Some(I16x8LtU);
+    result[0x31] = Some(I16x8GtS);
+    result[0x32] = Some(I16x8GtU);
+    result[0x33] = Some(I16x8LeS);
+    result[0x34] = Some(I16x8LeU);
+    result[0x35] = Some(I16x8GeS);
+    result[0x36] = Some(I16x8GeU);
+    result[0x37] = Some(I32x4Eq);
+    result[0x38] = Some(I32x4Ne);
+    result[0x39] = Some(I32x4LtS);
+    result[0x3a] = Some(I32x4LtU);
+    result[0x3b] = Some(I32x4GtS);
+    result[0x3c] = Some(I32x4GtU);
+    result[0x3d] = Some(I32x4LeS);
+    result[0x3e] = Some(I32x4LeU);
+    result[0x3f] = Some(I32x4GeS);
+    result[0x40] = Some(I32x4GeU);
+    result[0x41] = Some(F32x4Eq);
+    result[0x42] = Some(F32x4Ne);
+    result[0x43] = Some(F32x4Lt);
+    result[0x44] = Some(F32x4Gt);
+    result[0x45] = Some(F32x4Le);
+    result[0x46] = Some(F32x4Ge);
+    result[0x47] = Some(F64x2Eq);
+    result[0x48] = Some(F64x2Ne);
+    result[0x49] = Some(F64x2Lt);
+    result[0x4a] = Some(F64x2Gt);
+    result[0x4b] = Some(F64x2Le);
+    result[0x4c] = Some(F64x2Ge);
+    result[0x4d] = Some(V128Not);
+    result[0x4e] = Some(V128And);
+    result[0x4f] = Some(V128Andnot);
+    result[0x50] = Some(V128Or);
+    result[0x51] = Some(V128Xor);
+    result[0x52] = Some(V128Bitselect);
+    result[0x53] = Some(V128AnyTrue);
+    result[0x54] = Some(V128Load8Lane);
+    result[0x55] = Some(V128Load16Lane);
+    result[0x56] = Some(V128Load32Lane);
+    result[0x57] = Some(V128Load64Lane);
+    result[0x58] = Some(V128Store8Lane);
+    result[0x59] = Some(V128Store16Lane);
+    result[0x5a] = Some(V128Store32Lane);
+    result[0x5b] = Some(V128Store64Lane);
+    result[0x5c] = Some(V128Load32Zero);
+    result[0x5d] = Some(V128Load64Zero);
+    result[0x5e] = Some(F32x4DemoteF64x2Zero);
+    result[0x5f] = Some(F64x2PromoteLowF32x4);
+    result[0x60] = Some(I8x16Abs);
+    result[0x61] = Some(I8x16Neg);
+    result[0x62] = Some(I8x16Popcnt);
+    result[0x63] = Some(I8x16AllTrue);
+    result[0x64] = Some(I8x16Bitmask);
+    result[0x65] = Some(I8x16NarrowI16x8S);
+    result[0x66] = Some(I8x16NarrowI16x8U);
+    result[0x67] = Some(F32x4Ceil);
+    result[0x68] = Some(F32x4Floor);
+    result[0x69] = Some(F32x4Trunc);
+    result[0x6a] = Some(F32x4Nearest);
+    result[0x6b] = Some(I8x16Shl);
+    result[0x6c] = Some(I8x16ShrS);
+    result[0x6d] = Some(I8x16ShrU);
+    result[0x6e] = Some(I8x16Add);
+    result[0x6f] = Some(I8x16AddSatS);
+    result[0x70] = Some(I8x16AddSatU);
+    result[0x71] = Some(I8x16Sub);
+    result[0x72] = Some(I8x16SubSatS);
+    result[0x73] = Some(I8x16SubSatU);
+    result[0x74] = Some(F64x2Ceil);
+    result[0x75] = Some(F64x2Floor);
+    result[0x76] = Some(I8x16MinS);
+    result[0x77] = Some(I8x16MinU);
+    result[0x78] = Some(I8x16MaxS);
+    result[0x79] = Some(I8x16MaxU);
+    result[0x7a] = Some(F64x2Trunc);
+    result[0x7b] = Some(I8x16AvgrU);
+    result[0x7c] = Some(I16x8ExtaddPairwiseI8x16S);
+    result[0x7d] = Some(I16x8ExtaddPairwiseI8x16U);
+    result[0x7e] = Some(I32x4ExtaddPairwiseI16x8S);
+    result[0x7f] = Some(I32x4ExtaddPairwiseI16x8U);
+    result[0x80] = Some(I16x8Abs);
+    result[0x81] = Some(I16x8Neg);
+    result[0x82] = Some(I16x8Q15mulrSatS);
+    result[0x83] = Some(I16x8AllTrue);
+    result[0x84] = Some(I16x8Bitmask);
+    result[0x85] = Some(I16x8NarrowI32x4S);
+    result[0x86] = Some(I16x8NarrowI32x4U);
+    result[0x87] = Some(I16x8ExtendLowI8x16S);
+    result[0x88] = Some(I16x8ExtendHighI8x16S);
+    result[0x89] = Some(I16x8ExtendLowI8x16U);
+    result[0x8a] = Some(I16x8ExtendHighI8x16U);
+    result[0x8b] = Some(I16x8Shl);
+    result[0x8c] = Some(I16x8ShrS);
+    result[0x8d] = Some(I16x8ShrU);
+    result[0x8e] = Some(I16x8Add);
+    result[0x8f] = Some(I16x8AddSatS);
+    result[0x90] = Some(I16x8AddSatU);
+    result[0x91] = Some(I16x8Sub);
+    result[0x92] = Some(I16x8SubSatS);
+    result[0x93] = Some(I16x8SubSatU);
+    result[0x94] = Some(F64x2Nearest);
+    result[0x95] = Some(I16x8Mul);
+    result[0x96] = Some(I16x8MinS);
+    result[0x97] = Some(I16x8MinU);
+    result[0x98] = Some(I16x8MaxS);
+    result[0x99] = Some(I16x8MaxU);
+    result[0x9b] = Some(I16x8AvgrU);
+    result[0x9c] = Some(I16x8ExtmulLowI8x16S);
+    result[0x9d] = Some(I16x8ExtmulHighI8x16S);
+    result[0x9e] = Some(I16x8ExtmulLowI8x16U);
+    result[0x9f] = Some(I16x8ExtmulHighI8x16U);
+    result[0xa0] = Some(I32x4Abs);
+    result[0xa1] = Some(I32x4Neg);
+    result[0xa3] = Some(I32x4AllTrue);
+    result[0xa4] = Some(I32x4Bitmask);
+    result[0xa7] = Some(I32x4ExtendLowI16x8S);
+    result[0xa8] = Some(I32x4ExtendHighI16x8S);
+    result[0xa9] = Some(I32x4ExtendLowI16x8U);
+    result[0xaa] = Some(I32x4ExtendHighI16x8U);
+    result[0xab] = Some(I32x4Shl);
+    result[0xac] = Some(I32x4ShrS);
+    result[0xad] = Some(I32x4ShrU);
+    result[0xae] = Some(I32x4Add);
+    result[0xb1] = Some(I32x4Sub);
+    result[0xb5] = Some(I32x4Mul);
+    result[0xb6] = Some(I32x4MinS);
+    result[0xb7] = Some(I32x4MinU);
+    result[0xb8] = Some(I32x4MaxS);
+    result[0xb9] = Some(I32x4MaxU);
+    result[0xba] = Some(I32x4DotI16x8S);
+    result[0xbc] = Some(I32x4ExtmulLowI16x8S);
+    result[0xbd] = Some(I32x4ExtmulHighI16x8S);
+    result[0xbe] = Some(I32x4ExtmulLowI16x8U);
+    result[0xbf] = Some(I32x4ExtmulHighI16x8U);
+    result[0xc0] = Some(I64x2Abs);
+    result[0xc1] = Some(I64x2Neg);
+    result[0xc3] = Some(I64x2AllTrue);
+    result[0xc4] = Some(I64x2Bitmask);
+    result[0xc7] = Some(I64x2ExtendLowI32x4S);
+    result[0xc8] = Some(I64x2ExtendHighI32x4S);
+    result[0xc9] = Some(I64x2ExtendLowI32x4U);
+    result[0xca] = Some(I64x2ExtendHighI32x4U);
+    result[0xcb] = Some(I64x2Shl);
+    result[0xcc] = Some(I64x2ShrS);
+    result[0xcd] = Some(I64x2ShrU);
+    result[0xce] = Some(I64x2Add);
+    result[0xd1] = Some(I64x2Sub);
+    result[0xd5] = Some(I64x2Mul);
+    result[0xd6] = Some(I64x2Eq);
+    result[0xd7] = Some(I64x2Ne);
+    result[0xd8] = Some(I64x2LtS);
+    result[0xd9] = Some(I64x2GtS);
+    result[0xda] = Some(I64x2LeS);
+    result[0xdb] = Some(I64x2GeS);
+    result[0xdc] = Some(I64x2ExtmulLowI32x4S);
+    result[0xdd] = Some(I64x2ExtmulHighI32x4S);
+    result[0xde] = Some(I64x2ExtmulLowI32x4U);
+    result[0xdf] = Some(I64x2ExtmulHighI32x4U);
+    result[0xe0] = Some(F32x4Abs);
+    result[0xe1] = Some(F32x4Neg);
+    result[0xe3] = Some(F32x4Sqrt);
+    result[0xe4] = Some(F32x4Add);
+    result[0xe5] = Some(F32x4Sub);
+    result[0xe6] = Some(F32x4Mul);
+    result[0xe7] = Some(F32x4Div);
+    result[0xe8] = Some(F32x4Min);
+    result[0xe9] = Some(F32x4Max);
+    result[0xea] = Some(F32x4Pmin);
+    result[0xeb] = Some(F32x4Pmax);
+    result[0xec] = Some(F64x2Abs);
+    result[0xed] = Some(F64x2Neg);
+    result[0xef] = Some(F64x2Sqrt);
+    result[0xf0] = Some(F64x2Add);
+    result[0xf1] = Some(F64x2Sub);
+    result[0xf2] = Some(F64x2Mul);
+    result[0xf3] = Some(F64x2Div);
+    result[0xf4] = Some(F64x2Min);
+    result[0xf5] = Some(F64x2Max);
+    result[0xf6] = Some(F64x2Pmin);
+    result[0xf7] = Some(F64x2Pmax);
+    result[0xf8] = Some(I32x4TruncSatF32x4S);
+    result[0xf9] = Some(I32x4TruncSatF32x4U);
+    result[0xfa] = Some(F32x4ConvertI32x4S);
+    result[0xfb] = Some(F32x4ConvertI32x4U);
+    result[0xfc] = Some(I32x4TruncSatF64x2SZero);
+    result[0xfd] = Some(I32x4TruncSatF64x2UZero);
+    result[0xfe] = Some(F64x2ConvertLowI32x4S);
+    result[0xff] = Some(F64x2ConvertLowI32x4U);
+
+    result
+};
+
+impl TryFrom<u32> for SimdInstruction {
+    type Error = u32;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        match SIMD_LOOKUP_TABLE.get(value as usize) {
+            Some(Some(op)) => Ok(*op),
             _ => Err(value),
         }
     }
 }
 
+/// The format of the immediate operands of a SIMD instruction
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SimdImmediates {
+    NoImmediate,
+    /// Alignment and offset, like other load and store instructions
+    MemArg,
+    /// Alignment and offset, followed by a lane index byte
+    MemArgLane,
+    /// A lane index byte
+    Lane,
+    /// 16 bytes, either a constant or the lane indices for a shuffle
+    Bytes16,
+}
+
+impl SimdInstruction {
+    /// The name of the instruction in the WebAssembly text format.
+    /// The variant names are chosen so that this is the shape, a dot, and the rest in snake case,
+    /// e.g. `I16x8ExtaddPairwiseI8x16S` is `i16x8.extadd_pairwise_i8x16_s`.
+    pub fn text_name(self) -> String {
+        let variant = format!("{self:?}");
+        let mut name = String::with_capacity(variant.len() + 4);
+
+        for (i, c) in variant.char_indices() {
+            if c.is_ascii_uppercase() && i > 0 {
+                name.push(if name.contains('.') { '_' } else { '.' });
+            }
+            name.push(c.to_ascii_lowercase());
+        }
+
+        name
+    }
+
+    pub fn immediates(self) -> SimdImmediates {
+        use SimdImmediates::*;
+
+        match self as u8 {
+            0x00..=0x0b | 0x5c | 0x5d => MemArg,
+            0x0c | 0x0d => Bytes16,
+            0x15..=0x22 => Lane,
+            0x54..=0x5b => MemArgLane,
+            _ => NoImmediate,
+        }
+    }
+}
+
 /// The format of the *immediate* operands of an operator
 /// Immediates appear directly in the byte stream after the opcode,
 /// rather than being popped off the value stack. These are the possible forms.
@@ -435,6 +1194,7 @@ enum OpImmediates {
     Leb32x2,
     BrTable,
     Memory,
+    Simd,
 }
 
 fn immediates_for(op: OpCode) -> Result<OpImmediates, String> {
@@ -464,6 +1224,7 @@ fn immediates_for(op: OpCode) -> Result<OpImmediates, String> {
 
         CURRENTMEMORY | GROWMEMORY => Byte1,
         MEMORY => Memory,
+        SIMD => Simd,
 
         I32CONST => Leb32x1,
         I64CONST => Leb64x1,
@@ -547,12 +1308,15 @@ impl SkipBytes for OpCode {
                 match MemoryInstruction::try_from(bytes[*cursor + 1]) {
                     Ok(op) => match op {
                         MemoryInstruction::MemoryInit => {
-                            // memory.init
-                            todo!("WASM instruction: memory.init")
+                            // memory.init dataidx 0x00
+                            *cursor += 1 + 1;
+                            u32::skip_bytes(bytes, cursor)?;
+                            *cursor += 1;
                         }
                         MemoryInstruction::DataDrop => {
-                            // data.drop x
-                            todo!("WASM instruction: data.drop")
+                            // data.drop dataidx
+                            *cursor += 1 + 1;
+                            u32::skip_bytes(bytes, cursor)?;
                         }
                         MemoryInstruction::MemoryCopy => {
                             // memory.copy
@@ -562,10 +1326,49 @@ impl SkipBytes for OpCode {
                             // memory.fill
                             *cursor += 1 + 1 + 1;
                         }
+                        MemoryInstruction::TableInit | MemoryInstruction::TableCopy => {
+                            // table.init elemidx tableidx
+                            // table.copy tableidx tableidx
+                            *cursor += 1 + 1;
+                            u32::skip_bytes(bytes, cursor)?;
+                            u32::skip_bytes(bytes, cursor)?;
+                        }
+                        MemoryInstruction::ElemDrop => {
+                            // elem.drop elemidx
+                            *cursor += 1 + 1;
+                            u32::skip_bytes(bytes, cursor)?;
+                        }
                     },
                     Err(other) => unreachable!("invalid memory instruction {other:?}"),
                 }
             }
+            Simd => {
+                let start = *cursor;
+                *cursor += 1;
+                let simd_op = u32::parse((), bytes, cursor)?;
+                let op = SimdInstruction::try_from(simd_op).map_err(|other| ParseError {
+                    message: format!("Unknown SIMD instruction 0xfd 0x{other:02x}"),
+                    offset: start,
+                })?;
+                match op.immediates() {
+                    SimdImmediates::NoImmediate => {}
+                    SimdImmediates::MemArg => {
+                        u32::skip_bytes(bytes, cursor)?;
+                        u32::skip_bytes(bytes, cursor)?;
+                    }
+                    SimdImmediates::MemArgLane => {
+                        u32::skip_bytes(bytes, cursor)?;
+                        u32::skip_bytes(bytes, cursor)?;
+                        *cursor += 1;
+                    }
+                    SimdImmediates::Lane => {
+                        *cursor += 1;
+                    }
+                    SimdImmediates::Bytes16 => {
+                        *cursor += 16;
+                    }
+                }
+            }
         }
         Ok(())
     }
@@ -34,8 +34,7 @@ pub enum SectionId {
     Element = 9,
     Code = 10,
     Data = 11,
-    /// DataCount section is unused. Only needed for single-pass validation of
-    /// memory.init and data.drop, which we don't use
+    /// Only needed for single-pass validation of memory.init and data.drop
     DataCount = 12,
 }
 
@@ -299,6 +298,11 @@ impl<'a> TypeSection<'a> {
         self.bytes.is_empty()
     }
 
+    /// Number of signatures
+    pub fn len(&self) -> usize {
+        self.offsets.len()
+    }
+
     pub fn look_up(&'a self, sig_index: u32) -> (SignatureParamsIter<'a>, Option<ValueType>) {
         let mut offset = self.offsets[sig_index as usize];
         offset += 1; // separator
@@ -716,6 +720,7 @@ impl Parse<()> for TableSection {
                         message: "Only funcref tables are supported".into(),
                     })
                 } else {
+                    *cursor += 1; // ref type
                     let limits = Limits::parse((), module_bytes, cursor)?;
                     *cursor = range.end;
                     Ok(TableSection {
@@ -929,7 +934,7 @@ impl ConstExpr {
         Ok(value)
     }
 
-    fn unwrap_i32(&self) -> i32 {
+    pub fn unwrap_i32(&self) -> i32 {
         match self {
             Self::I32(x) => *x,
             _ => internal_error!("Expected ConstExpr to be I32"),
@@ -1572,10 +1577,7 @@ impl<'a> DataSection<'a> {
     }
 
     pub fn load_into(&self, memory: &mut [u8]) -> Result<(), String> {
-        let mut cursor = 0;
-        for _ in 0..self.count {
-            let mode =
-                DataMode::parse((), &self.bytes, &mut cursor).map_err(|e| format!("{e:?}"))?;
+        for (mode, init) in self.segments()? {
             let start = match mode {
                 DataMode::Active {
                     offset: ConstExpr::I32(addr),
@@ -1584,15 +1586,34 @@ impl<'a> DataSection<'a> {
                     continue;
                 }
             };
+            let mut target_slice = &mut memory[start..][..init.len()];
+            target_slice.write(init).map_err(|e| format!("{e:?}"))?;
+        }
+        Ok(())
+    }
+
+    /// The mode and initial bytes of every segment, in index order
+    /// (Passive segments are referred to by index in `memory.init` and `data.drop`)
+    pub fn segments(&self) -> Result<std::vec::Vec<(DataMode, &[u8])>, String> {
+        let mut segments = std::vec::Vec::with_capacity(self.count as usize);
+        let mut cursor = 0;
+        for _ in 0..self.count {
+            let mode =
+                DataMode::parse((), &self.bytes, &mut cursor).map_err(|e| format!("{e:?}"))?;
             let len32 = u32::parse((), &self.bytes, &mut cursor).map_err(|e| format!("{e:?}"))?;
             let len = len32 as usize;
-            let mut target_slice = &mut memory[start..][..len];
-            target_slice
-                .write(&self.bytes[cursor..][..len])
-                .map_err(|e| format!("{e:?}"))?;
+            segments.push((mode, &self.bytes[cursor..][..len]));
             cursor += len;
         }
-        Ok(())
+        Ok(segments)
+    }
+
+    /// Serialize a DataCount section for this Data section.
+    /// It goes before the Code section, so it can't be written at the same time as the Data section.
+    pub fn serialize_count<B: SerialBuffer>(&self, buffer: &mut B) {
+        let header_indices = write_section_header(buffer, SectionId::DataCount);
+        buffer.encode_u32(self.count);
+        update_section_size(buffer, header_indices);
     }
 }
 
@@ -1642,6 +1663,49 @@ impl<'a> Serialize for DataSection<'a> {
     }
 }
 
+/*******************************************************************
+ *
+ * DataCount section
+ *
+ * The number of segments in the Data section. Wasm runtimes need it to validate
+ * `memory.init` and `data.drop` instructions in a single pass, since the Code section
+ * comes before the Data section. We only keep track of whether it's present, because
+ * the count itself is always taken from the Data section when serializing.
+ *
+ *******************************************************************/
+
+#[derive(Debug, Default)]
+pub struct DataCountSection {
+    pub is_present: bool,
+}
+
+impl DataCountSection {
+    const ID: SectionId = SectionId::DataCount;
+
+    pub fn new() -> Self {
+        DataCountSection { is_present: false }
+    }
+
+    pub fn size(&self) -> usize {
+        if self.is_present {
+            MAX_SIZE_SECTION_HEADER
+        } else {
+            0
+        }
+    }
+}
+
+impl Parse<()> for DataCountSection {
+    fn parse(_: (), module_bytes: &[u8], cursor: &mut usize) -> Result<Self, ParseError> {
+        if *cursor >= module_bytes.len() || module_bytes[*cursor] != Self::ID as u8 {
+            return Ok(DataCountSection { is_present: false });
+        }
+        let (_count, range) = parse_section(Self::ID, module_bytes, cursor)?;
+        *cursor = range.end;
+        Ok(DataCountSection { is_present: true })
+    }
+}
+
 /*******************************************************************
  *
  * Opaque section
@@ -1901,7 +1965,7 @@ mod tests {
                 ret_type: None,
             },
             Signature {
-                param_types: bumpalo::vec![in arena; I32, I64, F32, F64],
+                param_types: bumpalo::vec![in arena; I32, I64, F32, F64, V128],
                 ret_type: None,
             },
             Signature {
@@ -1921,4 +1985,52 @@ mod tests {
         }
         test_assert_types_preload(arena, &section);
     }
+
+    #[test]
+    fn test_data_segments() {
+        let arena = &Bump::new();
+        let mut section = DataSection::new(arena);
+        section.append_segment(DataSegment {
+            mode: DataMode::active_at(4),
+            init: bumpalo::vec![in arena; 1, 2, 3],
+        });
+        section.append_segment(DataSegment {
+            mode: DataMode::Passive,
+            init: bumpalo::vec![in arena; 4, 5],
+        });
+
+        let mut serialized = Vec::with_capacity_in(section.size(), arena);
+        section.serialize_count(&mut serialized);
+        section.serialize(&mut serialized);
+
+        let mut cursor = 0;
+        let data_count = DataCountSection::parse((), &serialized, &mut cursor).unwrap();
+        assert!(data_count.is_present);
+        let preloaded = DataSection::parse(arena, &serialized, &mut cursor).unwrap();
+        assert_eq!(cursor, serialized.len());
+
+        let segments = preloaded.segments().unwrap();
+        assert_eq!(segments.len(), 2);
+        assert!(matches!(segments[0], (DataMode::Active { .. }, [1, 2, 3])));
+        assert!(matches!(segments[1], (DataMode::Passive, [4, 5])));
+
+        let mut memory = [0; 8];
+        preloaded.load_into(&mut memory).unwrap();
+        assert_eq!(memory, [0, 0, 0, 0, 1, 2, 3, 0]);
+    }
+
+    #[test]
+    fn test_table_section() {
+        let arena = &Bump::new();
+        let mut section = TableSection::new();
+        section.function_table.limits = Limits::MinMax(3, 3);
+
+        let mut serialized = Vec::with_capacity_in(section.size(), arena);
+        section.serialize(&mut serialized);
+
+        let mut cursor = 0;
+        let preloaded = TableSection::parse((), &serialized, &mut cursor).unwrap();
+        assert_eq!(cursor, serialized.len());
+        assert_eq!(preloaded.function_table, section.function_table);
+    }
 }
@@ -0,0 +1,323 @@
+//! Source maps for Wasm modules, so that browser devtools can show source locations for our code.
+//! https://sourcemaps.info/spec.html
+//!
+//! For Wasm, the generated "line" is always 0 and the generated "column" is a byte offset
+//! in the module file. We embed the whole map in a `sourceMappingURL` custom section,
+//! as a data URL, so that it doesn't need to be served separately.
+
+use bumpalo::collections::vec::Vec;
+use bumpalo::Bump;
+
+use crate::sections::{update_section_size, write_custom_section_header};
+use crate::serialize::{SerialBuffer, Serialize};
+
+#[derive(Debug)]
+pub struct SourceFile<'a> {
+    pub path: &'a str,
+    pub content: &'a str,
+}
+
+/// A zero-based line and column in one of the source files
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SourceLocation {
+    pub file_index: u32,
+    pub line: u32,
+    pub column: u32,
+}
+
+#[derive(Debug)]
+pub struct SourceMap<'a> {
+    pub files: Vec<'a, SourceFile<'a>>,
+    /// Source locations by function index and byte offset within the function
+    pub locations: Vec<'a, (u32, u32, SourceLocation)>,
+}
+
+impl<'a> SourceMap<'a> {
+    const SECTION_NAME: &'static str = "sourceMappingURL";
+    const URL_PREFIX: &'static str = "data:application/json;base64,";
+
+    pub fn new(arena: &'a Bump) -> Self {
+        SourceMap {
+            files: Vec::new_in(arena),
+            locations: Vec::new_in(arena),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.locations.is_empty()
+    }
+
+    /// Overestimate, since the JSON is base64 encoded
+    pub fn size(&self) -> usize {
+        if self.is_empty() {
+            return 0;
+        }
+        let files_size: usize = self
+            .files
+            .iter()
+            .map(|file| 2 * (file.path.len() + file.content.len()) + 8)
+            .sum();
+        let json_size = 128 + files_size + 32 * self.locations.len();
+        64 + 4 * json_size / 3
+    }
+
+    /// Get the index of a source file, adding it if we haven't seen it before
+    pub fn file_index(&mut self, path: &'a str, content: &'a str) -> u32 {
+        match self.files.iter().position(|file| file.path == path) {
+            Some(index) => index as u32,
+            None => {
+                self.files.push(SourceFile { path, content });
+                self.files.len() as u32 - 1
+            }
+        }
+    }
+
+    /// Map the code at `offset` bytes into a function (counting from its size prefix) to a source location
+    pub fn add_location(&mut self, fn_index: u32, offset: u32, location: SourceLocation) {
+        self.locations.push((fn_index, offset, location));
+    }
+
+    /// Generate the JSON source map.
+    /// `function_offset` returns the byte offset of a function in the module file, if it's present.
+    pub fn to_json<F>(&self, function_offset: F) -> String
+    where
+        F: Fn(u32) -> Option<u32>,
+    {
+        let mut segments: std::vec::Vec<(u32, SourceLocation)> = self
+            .locations
+            .iter()
+            .filter_map(|(fn_index, offset, loc)| {
+                Some((function_offset(*fn_index)? + offset, *loc))
+            })
+            .collect();
+        segments.sort_by_key(|(offset, _)| *offset);
+
+        // Every field is encoded relative to the previous segment
+        let mut mappings = String::with_capacity(8 * segments.len());
+        let mut prev_offset = 0;
+        let mut prev = SourceLocation {
+            file_index: 0,
+            line: 0,
+            column: 0,
+        };
+        for (i, (offset, loc)) in segments.into_iter().enumerate() {
+            if i > 0 {
+                mappings.push(',');
+            }
+            encode_vlq(&mut mappings, offset as i64 - prev_offset as i64);
+            encode_vlq(
+                &mut mappings,
+                loc.file_index as i64 - prev.file_index as i64,
+            );
+            encode_vlq(&mut mappings, loc.line as i64 - prev.line as i64);
+            encode_vlq(&mut mappings, loc.column as i64 - prev.column as i64);
+            prev_offset = offset;
+            prev = loc;
+        }
+
+        let mut json = String::with_capacity(self.size());
+        json.push_str("{\"version\":3,\"sources\":[");
+        for (i, file) in self.files.iter().enumerate() {
+            if i > 0 {
+                json.push(',');
+            }
+            push_json_string(&mut json, file.path);
+        }
+        json.push_str("],\"sourcesContent\":[");
+        for (i, file) in self.files.iter().enumerate() {
+            if i > 0 {
+                json.push(',');
+            }
+            push_json_string(&mut json, file.content);
+        }
+        json.push_str("],\"names\":[],\"mappings\":\"");
+        json.push_str(&mappings);
+        json.push_str("\"}");
+        json
+    }
+
+    /// Write a `sourceMappingURL` custom section containing the source map as a data URL
+    pub fn serialize<T: SerialBuffer, F>(&self, buffer: &mut T, function_offset: F)
+    where
+        F: Fn(u32) -> Option<u32>,
+    {
+        if self.is_empty() {
+            return;
+        }
+        let json = self.to_json(function_offset);
+        let mut url = String::with_capacity(Self::URL_PREFIX.len() + 4 * json.len() / 3 + 4);
+        url.push_str(Self::URL_PREFIX);
+        encode_base64(&mut url, json.as_bytes());
+
+        let header_indices = write_custom_section_header(buffer, Self::SECTION_NAME);
+        url.as_str().serialize(buffer);
+        update_section_size(buffer, header_indices);
+    }
+}
+
+const BASE64_CHARS: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Base64 variable-length quantity, as used in source map "mappings"
+fn encode_vlq(out: &mut String, value: i64) {
+    // The sign goes in the lowest bit
+    let mut vlq = if value < 0 {
+        ((-value as u64) << 1) | 1
+    } else {
+        (value as u64) << 1
+    };
+    loop {
+        let mut digit = (vlq & 0x1f) as usize;
+        vlq >>= 5;
+        if vlq > 0 {
+            digit |= 0x20; // continuation bit
+        }
+        out.push(BASE64_CHARS[digit] as char);
+        if vlq == 0 {
+            break;
+        }
+    }
+}
+
+fn encode_base64(out: &mut String, bytes: &[u8]) {
+    for chunk in bytes.chunks(3) {
+        let b = [
+            chunk[0],
+            chunk.get(1).copied().unwrap_or(0),
+            chunk.get(2).copied().unwrap_or(0),
+        ];
+        let n = (b[0] as usize) << 16 | (b[1] as usize) << 8 | b[2] as usize;
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(BASE64_CHARS[(n >> (18 - 6 * i)) & 0x3f] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+}
+
+fn push_json_string(out: &mut String, s: &str) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_vlq() {
+        let mut s = String::new();
+        for value in [0, 1, -1, 15, 16, -16, 123, 1234567] {
+            encode_vlq(&mut s, value);
+            s.push(' ');
+        }
+        assert_eq!(s, "A C D e gB hB 2H uorrC ");
+    }
+
+    #[test]
+    fn test_base64() {
+        for (input, expected) in [
+            ("", ""),
+            ("M", "TQ=="),
+            ("Ma", "TWE="),
+            ("Man", "TWFu"),
+            ("Many", "TWFueQ=="),
+        ] {
+            let mut s = String::new();
+            encode_base64(&mut s, input.as_bytes());
+            assert_eq!(s, expected);
+        }
+    }
+
+    #[test]
+    fn test_function_offsets() {
+        use crate::{SerialBuffer, Signature, WasmModule};
+
+        let arena = Bump::new();
+        let mut module = WasmModule::new(&arena);
+        let file_index = module.source_map.file_index("A.roc", "");
+        for (i, body_len) in [3, 200, 5].into_iter().enumerate() {
+            module.add_function_signature(Signature {
+                param_types: Vec::new_in(&arena),
+                ret_type: None,
+            });
+            let buf = &mut module.code.bytes;
+            module.code.function_offsets.push(buf.len() as u32);
+            buf.encode_u32(body_len + 2);
+            buf.push(0); // no locals
+            buf.resize(buf.len() + body_len as usize, 0x01); // nop
+            buf.push(0x0b); // end
+            module.code.function_count += 1;
+
+            let location = SourceLocation {
+                file_index,
+                line: i as u32,
+                column: 0,
+            };
+            module.source_map.add_location(i as u32, 0, location);
+        }
+
+        let mut bytes = std::vec::Vec::with_capacity(module.size());
+        module.serialize(&mut bytes);
+
+        // Compare to where the parser finds the functions
+        let parsed = WasmModule::preload(&arena, &bytes, false).unwrap();
+        let code_section_end = (parsed.code.section_offset as usize) + parsed.code.bytes.len();
+        for fn_index in 0..3 {
+            let expected = parsed.code.section_offset + parsed.code.function_offsets[fn_index];
+            let actual = module.serialized_function_offset(code_section_end, fn_index as u32);
+            assert_eq!(actual, Some(expected));
+        }
+
+        let url_section = bytes
+            .windows(SourceMap::SECTION_NAME.len())
+            .position(|w| w == SourceMap::SECTION_NAME.as_bytes());
+        assert!(url_section.is_some());
+    }
+
+    #[test]
+    fn test_to_json() {
+        let arena = Bump::new();
+        let mut source_map = SourceMap::new(&arena);
+        let a = source_map.file_index("A.roc", "x = 1\n");
+        let b = source_map.file_index("dir/B \"quoted\".roc", "y = 2\n\tz = 3");
+        assert_eq!(source_map.file_index("A.roc", "x = 1\n"), a);
+
+        let loc = |file_index, line, column| SourceLocation {
+            file_index,
+            line,
+            column,
+        };
+        source_map.add_location(5, 0, loc(b, 1, 4));
+        source_map.add_location(3, 0, loc(a, 0, 0));
+        source_map.add_location(3, 20, loc(a, 2, 4));
+        source_map.add_location(7, 0, loc(a, 10, 0)); // dead, so no offset
+
+        let json = source_map.to_json(|fn_index| match fn_index {
+            3 => Some(100),
+            5 => Some(150),
+            _ => None,
+        });
+
+        assert_eq!(
+            json,
+            concat!(
+                r#"{"version":3,"sources":["A.roc","dir/B \"quoted\".roc"],"#,
+                r#""sourcesContent":["x = 1\n","y = 2\n\tz = 3"],"#,
+                r#""names":[],"mappings":"oGAAA,oBAEI,8BCDA"}"#
+            )
+        );
+    }
+}
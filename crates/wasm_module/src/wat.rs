@@ -0,0 +1,609 @@
+//! Print a module in the WebAssembly text format, for `roc build --emit-wat`.
+//! https://webassembly.github.io/spec/core/text/index.html
+//!
+//! Functions get `$names` from the Name section, so that calls can be followed without external
+//! tools. Locals, globals and branch labels are referred to by index.
+
+use std::collections::HashSet;
+use std::fmt::Write;
+
+use crate::opcodes::{MemoryInstruction, OpCode, SimdImmediates, SimdInstruction, LOOKUP_TABLE};
+use crate::parse::{Parse, ParseError};
+use crate::sections::{ConstExpr, DataMode, ExportType, GlobalType, ImportDesc, Limits};
+use crate::{ValueType, WasmModule};
+
+impl<'a> WasmModule<'a> {
+    /// Print the module in the WebAssembly text format
+    pub fn to_wat(&self) -> Result<String, ParseError> {
+        let names = FunctionNames::new(self);
+        let mut wat = String::from("(module\n");
+
+        for sig_index in 0..self.types.len() as u32 {
+            let signature = self.signature_text(sig_index);
+            writeln!(wat, "  (type (;{sig_index};) (func{signature}))").unwrap();
+        }
+
+        let mut fn_index = 0;
+        let mut global_index = 0;
+        for import in self.import.imports.iter() {
+            let description = match &import.description {
+                ImportDesc::Func { signature_index } => {
+                    let text = format!(
+                        "func {} (type {signature_index})",
+                        names.definition(fn_index)
+                    );
+                    fn_index += 1;
+                    text
+                }
+                ImportDesc::Table { ty } => format!("table {} funcref", limits_text(&ty.limits)),
+                ImportDesc::Mem { limits } => format!("memory {}", limits_text(limits)),
+                ImportDesc::Global { ty } => {
+                    let text = format!("global (;{global_index};) {}", global_type_text(ty));
+                    global_index += 1;
+                    text
+                }
+            };
+            writeln!(
+                wat,
+                "  (import {} {} ({description}))",
+                string_text(import.module.as_bytes()),
+                string_text(import.name.as_bytes())
+            )
+            .unwrap();
+        }
+
+        let code = &self.code;
+        let dummy_count = code.dead_import_dummy_count as usize;
+        for (i, sig_index) in self.function.signatures.iter().enumerate() {
+            let fn_index = fn_index + i as u32;
+            let signature = self.signature_text(*sig_index);
+            writeln!(
+                wat,
+                "  (func {} (type {sig_index}){signature}",
+                names.definition(fn_index)
+            )
+            .unwrap();
+
+            // Dummy functions replace eliminated imports, and have no bytes in the Code section
+            if i < dummy_count {
+                wat.push_str("    unreachable\n");
+            } else {
+                let mut cursor = code.function_offsets[i - dummy_count] as usize;
+                let size = u32::parse((), &code.bytes, &mut cursor)? as usize;
+                let end = cursor + size;
+                self.write_function_body(&mut wat, &names, &mut cursor, end)?;
+            }
+
+            wat.push_str("  )\n");
+        }
+
+        if !self.element.is_empty() {
+            let limits = limits_text(&self.table.function_table.limits);
+            writeln!(wat, "  (table (;0;) {limits} funcref)").unwrap();
+        }
+
+        if self.memory.count > 0 {
+            let limits = Limits::parse((), &self.memory.bytes, &mut 0)?;
+            writeln!(wat, "  (memory (;0;) {})", limits_text(&limits)).unwrap();
+        }
+
+        let mut cursor = 0;
+        for _ in 0..self.global.count {
+            let ty = GlobalType::parse((), &self.global.bytes, &mut cursor)?;
+            let init = ConstExpr::parse((), &self.global.bytes, &mut cursor)?;
+            writeln!(
+                wat,
+                "  (global (;{global_index};) {} ({}))",
+                global_type_text(&ty),
+                const_expr_text(&init)
+            )
+            .unwrap();
+            global_index += 1;
+        }
+
+        for export in self.export.exports.iter() {
+            let item = match export.ty {
+                ExportType::Func => format!("func {}", names.reference(export.index)),
+                ExportType::Table => format!("table {}", export.index),
+                ExportType::Mem => format!("memory {}", export.index),
+                ExportType::Global => format!("global {}", export.index),
+            };
+            writeln!(
+                wat,
+                "  (export {} ({item}))",
+                string_text(export.name.as_bytes())
+            )
+            .unwrap();
+        }
+
+        for segment in self.element.segments.iter() {
+            if segment.fn_indices.is_empty() {
+                continue;
+            }
+            write!(wat, "  (elem ({})", const_expr_text(&segment.offset)).unwrap();
+            for fn_index in segment.fn_indices.iter() {
+                write!(wat, " {}", names.reference(*fn_index)).unwrap();
+            }
+            wat.push_str(")\n");
+        }
+
+        let segments = self
+            .data
+            .segments()
+            .map_err(|message| ParseError { offset: 0, message })?;
+        for (mode, init) in segments {
+            wat.push_str("  (data ");
+            if let DataMode::Active { offset } = mode {
+                write!(wat, "({}) ", const_expr_text(&offset)).unwrap();
+            }
+            wat.push_str(&string_text(init));
+            wat.push_str(")\n");
+        }
+
+        wat.push_str(")\n");
+
+        Ok(wat)
+    }
+
+    fn signature_text(&self, sig_index: u32) -> String {
+        let (params, result) = self.types.look_up(sig_index);
+        let mut text = String::new();
+
+        for (i, param) in params.enumerate() {
+            let prefix = if i == 0 { " (param" } else { "" };
+            write!(text, "{prefix} {}", value_type_text(param)).unwrap();
+        }
+        if !text.is_empty() {
+            text.push(')');
+        }
+
+        if let Some(result) = result {
+            write!(text, " (result {})", value_type_text(result)).unwrap();
+        }
+
+        text
+    }
+
+    fn write_function_body(
+        &self,
+        wat: &mut String,
+        names: &FunctionNames,
+        cursor: &mut usize,
+        end: usize,
+    ) -> Result<(), ParseError> {
+        let bytes = &self.code.bytes;
+
+        let local_group_count = u32::parse((), bytes, cursor)?;
+        if local_group_count > 0 {
+            wat.push_str("    (local");
+            for _ in 0..local_group_count {
+                let (count, ty) = <(u32, ValueType)>::parse((), bytes, cursor)?;
+                for _ in 0..count {
+                    write!(wat, " {}", value_type_text(ty)).unwrap();
+                }
+            }
+            wat.push_str(")\n");
+        }
+
+        // Blocks we're inside of, not counting the function body itself
+        let mut depth: usize = 0;
+
+        while *cursor < end {
+            let offset = *cursor;
+            let op = LOOKUP_TABLE[bytes[offset] as usize].ok_or_else(|| ParseError {
+                offset,
+                message: format!("Unknown Wasm instruction 0x{:02x}", bytes[offset]),
+            })?;
+            *cursor += 1;
+
+            let indent = match op {
+                OpCode::END if depth == 0 => {
+                    // The end of the function body
+                    break;
+                }
+                OpCode::END => {
+                    depth -= 1;
+                    depth
+                }
+                OpCode::ELSE => depth.saturating_sub(1),
+                _ => depth,
+            };
+
+            let instruction = self.instruction_text(names, op, cursor)?;
+            writeln!(wat, "    {:indent$}{instruction}", "", indent = 2 * indent).unwrap();
+
+            if matches!(op, OpCode::BLOCK | OpCode::LOOP | OpCode::IF) {
+                depth += 1;
+            }
+        }
+
+        if *cursor != end {
+            return Err(ParseError {
+                offset: *cursor,
+                message: format!("Function body should end at offset {end}"),
+            });
+        }
+
+        Ok(())
+    }
+
+    /// The instruction and its immediates. The cursor starts just after the opcode.
+    fn instruction_text(
+        &self,
+        names: &FunctionNames,
+        op: OpCode,
+        cursor: &mut usize,
+    ) -> Result<String, ParseError> {
+        use OpCode::*;
+
+        let bytes = &self.code.bytes;
+        let name = op.text_name();
+
+        let text = match op {
+            BLOCK | LOOP | IF => {
+                let block_type = bytes[*cursor];
+                *cursor += 1;
+                if block_type == ValueType::VOID {
+                    name.to_string()
+                } else {
+                    let ty = value_type_text(ValueType::from(block_type));
+                    format!("{name} (result {ty})")
+                }
+            }
+            BR | BRIF | GETLOCAL | SETLOCAL | TEELOCAL | GETGLOBAL | SETGLOBAL => {
+                format!("{name} {}", u32::parse((), bytes, cursor)?)
+            }
+            BRTABLE => {
+                let mut text = name.to_string();
+                // the targets, and then the default
+                let label_count = 1 + u32::parse((), bytes, cursor)?;
+                for _ in 0..label_count {
+                    write!(text, " {}", u32::parse((), bytes, cursor)?).unwrap();
+                }
+                text
+            }
+            CALL => {
+                let fn_index = u32::parse((), bytes, cursor)?;
+                format!("{name} {}", names.reference(fn_index))
+            }
+            CALLINDIRECT => {
+                let sig_index = u32::parse((), bytes, cursor)?;
+                let table_index = u32::parse((), bytes, cursor)?;
+                if table_index == 0 {
+                    format!("{name} (type {sig_index})")
+                } else {
+                    format!("{name} {table_index} (type {sig_index})")
+                }
+            }
+            I32LOAD | I64LOAD | F32LOAD | F64LOAD | I32LOAD8S | I32LOAD8U | I32LOAD16S
+            | I32LOAD16U | I64LOAD8S | I64LOAD8U | I64LOAD16S | I64LOAD16U | I64LOAD32S
+            | I64LOAD32U | I32STORE | I64STORE | F32STORE | F64STORE | I32STORE8 | I32STORE16
+            | I64STORE8 | I64STORE16 | I64STORE32 => {
+                format!("{name}{}", memarg_text(bytes, cursor)?)
+            }
+            CURRENTMEMORY | GROWMEMORY => {
+                // memory index, always 0
+                *cursor += 1;
+                name.to_string()
+            }
+            I32CONST => format!("{name} {}", i32::parse((), bytes, cursor)?),
+            I64CONST => format!("{name} {}", i64::parse((), bytes, cursor)?),
+            F32CONST => {
+                let mut b = [0; 4];
+                b.copy_from_slice(&bytes[*cursor..][..4]);
+                *cursor += 4;
+                format!("{name} {}", float_text(f32::from_le_bytes(b)))
+            }
+            F64CONST => {
+                let mut b = [0; 8];
+                b.copy_from_slice(&bytes[*cursor..][..8]);
+                *cursor += 8;
+                format!("{name} {}", float_text(f64::from_le_bytes(b)))
+            }
+            MEMORY => memory_instruction_text(bytes, cursor)?,
+            SIMD => simd_instruction_text(bytes, cursor)?,
+            _ => name.to_string(),
+        };
+
+        Ok(text)
+    }
+}
+
+/// `$names` for functions, from the Name section.
+/// Names that aren't valid identifiers in the text format are cleaned up and made unique.
+struct FunctionNames {
+    names: std::vec::Vec<Option<String>>,
+}
+
+impl FunctionNames {
+    fn new(module: &WasmModule) -> Self {
+        let fn_count = module.import.function_count() + module.function.signatures.len();
+        let mut names = vec![None; fn_count];
+        let mut used = HashSet::new();
+
+        for (fn_index, name) in module.names.function_names.iter() {
+            let Some(slot) = names.get_mut(*fn_index as usize) else {
+                continue;
+            };
+
+            let mut identifier: String = name
+                .chars()
+                .map(|c| if is_id_char(c) { c } else { '_' })
+                .collect();
+            if identifier.is_empty() || used.contains(&identifier) {
+                write!(identifier, ".{fn_index}").unwrap();
+            }
+
+            used.insert(identifier.clone());
+            *slot = Some(identifier);
+        }
+
+        FunctionNames { names }
+    }
+
+    fn reference(&self, fn_index: u32) -> String {
+        match self.names.get(fn_index as usize) {
+            Some(Some(name)) => format!("${name}"),
+            _ => fn_index.to_string(),
+        }
+    }
+
+    fn definition(&self, fn_index: u32) -> String {
+        match self.names.get(fn_index as usize) {
+            Some(Some(name)) => format!("${name} (;{fn_index};)"),
+            _ => format!("(;{fn_index};)"),
+        }
+    }
+}
+
+/// Characters allowed in identifiers, e.g. `$names`
+fn is_id_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || "!#$%&'*+-./:<=>?@\\^_`|~".contains(c)
+}
+
+fn value_type_text(ty: ValueType) -> &'static str {
+    match ty {
+        ValueType::I32 => "i32",
+        ValueType::I64 => "i64",
+        ValueType::F32 => "f32",
+        ValueType::F64 => "f64",
+        ValueType::V128 => "v128",
+    }
+}
+
+fn global_type_text(ty: &GlobalType) -> String {
+    let value_type = value_type_text(ty.value_type);
+    if ty.is_mutable {
+        format!("(mut {value_type})")
+    } else {
+        value_type.to_string()
+    }
+}
+
+fn limits_text(limits: &Limits) -> String {
+    match limits {
+        Limits::Min(min) => min.to_string(),
+        Limits::MinMax(min, max) => format!("{min} {max}"),
+    }
+}
+
+fn const_expr_text(expr: &ConstExpr) -> String {
+    match expr {
+        ConstExpr::I32(x) => format!("i32.const {x}"),
+        ConstExpr::I64(x) => format!("i64.const {x}"),
+        ConstExpr::F32(x) => format!("f32.const {}", float_text(*x)),
+        ConstExpr::F64(x) => format!("f64.const {}", float_text(*x)),
+    }
+}
+
+fn float_text<F: Into<f64> + std::fmt::Debug + Copy>(x: F) -> String {
+    let wide: f64 = x.into();
+    if wide.is_nan() {
+        "nan".to_string()
+    } else {
+        // Debug formatting prints the shortest digits that parse back to the same value,
+        // and `inf` for infinities, which is what the text format expects.
+        format!("{x:?}")
+    }
+}
+
+/// Alignment and offset of a load or store
+fn memarg_text(bytes: &[u8], cursor: &mut usize) -> Result<String, ParseError> {
+    let align_log2 = u32::parse((), bytes, cursor)?;
+    let offset = u32::parse((), bytes, cursor)?;
+
+    let mut text = String::new();
+    if offset != 0 {
+        write!(text, " offset={offset}").unwrap();
+    }
+    write!(text, " align={}", 1u64 << align_log2).unwrap();
+
+    Ok(text)
+}
+
+/// A string literal, with anything other than printable ASCII escaped
+fn string_text(bytes: &[u8]) -> String {
+    let mut text = String::with_capacity(bytes.len() + 2);
+    text.push('"');
+    for byte in bytes {
+        match byte {
+            b'"' | b'\\' => write!(text, "\\{}", *byte as char).unwrap(),
+            0x20..=0x7e => text.push(*byte as char),
+            _ => write!(text, "\\{byte:02x}").unwrap(),
+        }
+    }
+    text.push('"');
+    text
+}
+
+fn memory_instruction_text(bytes: &[u8], cursor: &mut usize) -> Result<String, ParseError> {
+    let offset = *cursor;
+    let op = MemoryInstruction::try_from(bytes[offset]).map_err(|other| ParseError {
+        offset,
+        message: format!("Unknown memory instruction 0xfc 0x{other:02x}"),
+    })?;
+    *cursor += 1;
+
+    let name = op.text_name();
+    let text = match op {
+        MemoryInstruction::MemoryInit => {
+            let data_index = u32::parse((), bytes, cursor)?;
+            // memory index, always 0
+            *cursor += 1;
+            format!("{name} {data_index}")
+        }
+        MemoryInstruction::DataDrop | MemoryInstruction::ElemDrop => {
+            format!("{name} {}", u32::parse((), bytes, cursor)?)
+        }
+        MemoryInstruction::MemoryCopy => {
+            // destination and source memory indices, always 0
+            *cursor += 2;
+            name.to_string()
+        }
+        MemoryInstruction::MemoryFill => {
+            // memory index, always 0
+            *cursor += 1;
+            name.to_string()
+        }
+        MemoryInstruction::TableInit => {
+            let elem_index = u32::parse((), bytes, cursor)?;
+            let table_index = u32::parse((), bytes, cursor)?;
+            format!("{name} {table_index} {elem_index}")
+        }
+        MemoryInstruction::TableCopy => {
+            let destination = u32::parse((), bytes, cursor)?;
+            let source = u32::parse((), bytes, cursor)?;
+            format!("{name} {destination} {source}")
+        }
+    };
+
+    Ok(text)
+}
+
+fn simd_instruction_text(bytes: &[u8], cursor: &mut usize) -> Result<String, ParseError> {
+    let offset = *cursor;
+    let simd_op = u32::parse((), bytes, cursor)?;
+    let op = SimdInstruction::try_from(simd_op).map_err(|other| ParseError {
+        offset,
+        message: format!("Unknown SIMD instruction 0xfd 0x{other:02x}"),
+    })?;
+
+    let mut text = op.text_name();
+    match op.immediates() {
+        SimdImmediates::NoImmediate => {}
+        SimdImmediates::MemArg => {
+            text.push_str(&memarg_text(bytes, cursor)?);
+        }
+        SimdImmediates::MemArgLane => {
+            text.push_str(&memarg_text(bytes, cursor)?);
+            write!(text, " {}", bytes[*cursor]).unwrap();
+            *cursor += 1;
+        }
+        SimdImmediates::Lane => {
+            write!(text, " {}", bytes[*cursor]).unwrap();
+            *cursor += 1;
+        }
+        SimdImmediates::Bytes16 => {
+            if op == SimdInstruction::V128Const {
+                text.push_str(" i8x16");
+            }
+            for byte in &bytes[*cursor..][..16] {
+                write!(text, " {byte}").unwrap();
+            }
+            *cursor += 16;
+        }
+    }
+
+    Ok(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bumpalo::Bump;
+
+    #[test]
+    fn function_with_names_and_locals() {
+        let arena = Bump::new();
+
+        #[rustfmt::skip]
+        let bytes = [
+            0x00, b'a', b's', b'm', 0x01, 0x00, 0x00, 0x00,
+            // Type section: (func (param i32 i32) (result i32))
+            0x01, 0x07, 0x01, 0x60, 0x02, 0x7f, 0x7f, 0x01, 0x7f,
+            // Function section
+            0x03, 0x02, 0x01, 0x00,
+            // Code section
+            0x0a, 0x15, 0x01, 0x13, 0x01, 0x01, 0x7f,
+            0x20, 0x00, 0x20, 0x01, 0x6a, 0x22, 0x02, // local.get 0, local.get 1, i32.add, local.tee 2
+            0x04, 0x7f, 0x41, 0x7f, 0x05, 0x20, 0x02, 0x0b, // if (result i32) i32.const -1 else local.get 2 end
+            0x0b,
+            // Name section
+            0x00, 0x0f, 0x04, b'n', b'a', b'm', b'e', 0x01, 0x08, 0x01, 0x00, 0x05, b'N', b'u', b'm', b' ', b'a',
+        ];
+
+        let module = WasmModule::preload(&arena, &bytes, false).unwrap();
+
+        assert_eq!(
+            module.to_wat().unwrap(),
+            [
+                "(module",
+                "  (type (;0;) (func (param i32 i32) (result i32)))",
+                "  (func $Num_a (;0;) (type 0) (param i32 i32) (result i32)",
+                "    (local i32)",
+                "    local.get 0",
+                "    local.get 1",
+                "    i32.add",
+                "    local.tee 2",
+                "    if (result i32)",
+                "      i32.const -1",
+                "    else",
+                "      local.get 2",
+                "    end",
+                "  )",
+                ")",
+                "",
+            ]
+            .join("\n")
+        );
+    }
+
+    #[test]
+    fn import_and_export_names_are_escaped() {
+        let arena = Bump::new();
+
+        #[rustfmt::skip]
+        let bytes = [
+            0x00, b'a', b's', b'm', 0x01, 0x00, 0x00, 0x00,
+            // Type section: (func)
+            0x01, 0x04, 0x01, 0x60, 0x00, 0x00,
+            // Import section: "env" "é" (func (type 0))
+            0x02, 0x0a, 0x01, 0x03, b'e', b'n', b'v', 0x02, 0xc3, 0xa9, 0x00, 0x00,
+            // Function section
+            0x03, 0x02, 0x01, 0x00,
+            // Export section: "a\"b" (func 1)
+            0x07, 0x07, 0x01, 0x03, b'a', b'"', b'b', 0x00, 0x01,
+            // Code section
+            0x0a, 0x04, 0x01, 0x02, 0x00, 0x0b,
+        ];
+
+        let module = WasmModule::preload(&arena, &bytes, false).unwrap();
+        let wat = module.to_wat().unwrap();
+
+        assert!(
+            wat.contains("  (import \"env\" \"\\c3\\a9\" (func "),
+            "{wat}"
+        );
+        assert!(wat.contains("  (export \"a\\\"b\" (func "), "{wat}");
+    }
+
+    #[test]
+    fn simd_instruction_names() {
+        assert_eq!(
+            SimdInstruction::I16x8ExtaddPairwiseI8x16S.text_name(),
+            "i16x8.extadd_pairwise_i8x16_s"
+        );
+        assert_eq!(SimdInstruction::V128Load8x8S.text_name(), "v128.load8x8_s");
+        assert_eq!(SimdInstruction::V128AnyTrue.text_name(), "v128.any_true");
+    }
+}